@@ -0,0 +1,125 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use subtitles_core::audio::{Segmenter, SegmenterConfig};
+use subtitles_core::streaming::{Stabilizer, StreamingConfig, StreamingSegmenter};
+use subtitles_core::testing::{chunked, SignalBuilder};
+
+const SR: u32 = 16_000;
+const CHUNK: usize = 480; // ~30 ms, roughly what the capture thread delivers
+
+/// 30 s of alternating speech and pauses.
+fn conversation() -> Vec<f32> {
+    let mut builder = SignalBuilder::new(SR, 42).silence(0.5);
+    for i in 0..10 {
+        builder = builder
+            .speech(1.5 + (i % 3) as f32, 0.1)
+            .noise(0.4 + 0.1 * (i % 4) as f32, 0.004);
+    }
+    builder.build()
+}
+
+fn bench_segmenters(c: &mut Criterion) {
+    let audio = conversation();
+    let mut group = c.benchmark_group("push_audio");
+    group.throughput(Throughput::Elements(audio.len() as u64));
+
+    group.bench_function("segmenter", |b| {
+        b.iter(|| {
+            let mut segmenter = Segmenter::new(SegmenterConfig {
+                sample_rate_hz: SR,
+                vad_threshold: 0.012,
+                vad_end_silence_s: 0.6,
+                max_segment_s: 20.0,
+                pre_roll_s: 0.25,
+            });
+            for chunk in chunked(&audio, CHUNK) {
+                criterion::black_box(segmenter.push_audio(chunk));
+            }
+        })
+    });
+
+    group.bench_function("streaming_segmenter", |b| {
+        b.iter(|| {
+            let mut segmenter = StreamingSegmenter::new(StreamingConfig {
+                sample_rate_hz: SR,
+                vad_threshold: 0.012,
+                vad_end_silence_s: 0.6,
+                max_segment_s: 20.0,
+                pre_roll_s: 0.25,
+                min_speech_ms: 300,
+                partial_onset_ms: 700,
+                asr_step_ms: 350,
+                max_window_s: 12.0,
+                partial_overlap_s: 3.0,
+                final_interval_s: 0.0,
+            });
+            for chunk in chunked(&audio, CHUNK) {
+                criterion::black_box(segmenter.push_audio(chunk));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn hypothesis(words: usize) -> String {
+    (0..words)
+        .map(|i| format!("word{}", i % 97))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A stabilizer that has committed `words` tokens (keeping at most `max_committed`).
+fn committed_stabilizer(words: usize, max_committed: usize) -> (Stabilizer, String) {
+    let mut stabilizer = Stabilizer::new(2).with_max_committed(max_committed);
+    let text = hypothesis(words);
+    stabilizer.update(&text);
+    stabilizer.update(&text);
+    (stabilizer, text)
+}
+
+fn bench_stabilizer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stabilizer_update");
+    for words in [25, 100, 400, 1600] {
+        // Re-decoding the whole utterance plus one new word: the common steady-state case,
+        // where `strip_committed_overlap` has to scan the full committed history.
+        group.bench_with_input(BenchmarkId::new("extend", words), &words, |b, &words| {
+            b.iter_batched(
+                || {
+                    let (stabilizer, text) = committed_stabilizer(words, 0);
+                    (stabilizer, format!("{text} next"))
+                },
+                |(mut stabilizer, next)| criterion::black_box(stabilizer.update(&next)),
+                BatchSize::SmallInput,
+            )
+        });
+
+        // Same, with the committed history capped as the app does by default.
+        group.bench_with_input(BenchmarkId::new("extend_bounded", words), &words, |b, &words| {
+            b.iter_batched(
+                || {
+                    let (stabilizer, text) = committed_stabilizer(words, 32);
+                    (stabilizer, format!("{text} next"))
+                },
+                |(mut stabilizer, next)| criterion::black_box(stabilizer.update(&next)),
+                BatchSize::SmallInput,
+            )
+        });
+
+        // A hypothesis that shares nothing with the history (worst case for the overlap scan).
+        group.bench_with_input(BenchmarkId::new("disjoint", words), &words, |b, &words| {
+            b.iter_batched(
+                || {
+                    let (stabilizer, _) = committed_stabilizer(words, 0);
+                    (stabilizer, hypothesis(words).replace("word", "other"))
+                },
+                |(mut stabilizer, next)| criterion::black_box(stabilizer.update(&next)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_segmenters, bench_stabilizer);
+criterion_main!(benches);
@@ -0,0 +1,130 @@
+//! Acoustic echo cancellation for a microphone that also hears the system audio through the
+//! speakers. The system audio is the reference; an NLMS adaptive filter learns the speaker to
+//! mic echo path and subtracts its estimate, so the same speech isn't transcribed twice.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy)]
+pub struct EchoCancellerConfig {
+    /// Echo tail the filter can model, in samples (2048 = 128 ms at 16 kHz).
+    pub filter_len: usize,
+    /// Fixed delay of the reference before the filter, in samples, for playback and capture
+    /// buffering that the filter would otherwise waste taps on.
+    pub bulk_delay: usize,
+    /// NLMS step size in `(0, 2)`; larger adapts faster but leaves more residual.
+    pub step_size: f32,
+}
+
+impl Default for EchoCancellerConfig {
+    fn default() -> Self {
+        Self {
+            filter_len: 2048,
+            bulk_delay: 0,
+            step_size: 0.5,
+        }
+    }
+}
+
+/// Smoothing of the powers used for double-talk detection (~16 ms). The mic's rises faster
+/// (~2 ms) so adaptation stops as soon as a near-end talker starts.
+const POWER_ALPHA: f32 = 1.0 / 256.0;
+const MIC_ATTACK_ALPHA: f32 = 1.0 / 32.0;
+/// Adaptation stays frozen this long after double talk was last seen (30 ms at 16 kHz).
+const DOUBLE_TALK_HOLD: usize = 480;
+/// Speaker to mic coupling loses at least 3 dB, so a mic above half the reference power means
+/// someone is talking near the mic, and adapting now would teach the filter to cancel them too.
+const DOUBLE_TALK_RATIO: f32 = 0.5;
+/// Below this reference power there is nothing to learn from.
+const MIN_REFERENCE_POWER: f32 = 1e-8;
+
+pub struct EchoCanceller {
+    config: EchoCancellerConfig,
+    weights: Vec<f32>,
+    /// Reference history, stored twice so the newest `filter_len` samples are always one
+    /// contiguous slice starting at `pos` (newest first).
+    history: Vec<f32>,
+    pos: usize,
+    /// Sum of squares over the current history window.
+    energy: f32,
+    delay_line: VecDeque<f32>,
+    mic_power: f32,
+    reference_power: f32,
+    /// Samples left before adaptation resumes after double talk.
+    hold: usize,
+}
+
+impl EchoCanceller {
+    pub fn new(config: EchoCancellerConfig) -> Self {
+        let filter_len = config.filter_len.max(1);
+        Self {
+            config: EchoCancellerConfig {
+                filter_len,
+                ..config
+            },
+            weights: vec![0.0; filter_len],
+            history: vec![0.0; filter_len * 2],
+            pos: 0,
+            energy: 0.0,
+            delay_line: VecDeque::with_capacity(config.bulk_delay + 1),
+            mic_power: 0.0,
+            reference_power: 0.0,
+            hold: 0,
+        }
+    }
+
+    /// Removes the echo of `reference` from `mic`. Both must be the same length and cover the
+    /// same span of time; the output has the mic's length.
+    pub fn process(&mut self, mic: &[f32], reference: &[f32]) -> Vec<f32> {
+        debug_assert_eq!(mic.len(), reference.len());
+        mic.iter()
+            .zip(reference.iter().chain(std::iter::repeat(&0.0)))
+            .map(|(&mic, &reference)| self.process_sample(mic, reference))
+            .collect()
+    }
+
+    /// Forgets the learned echo path, e.g. after the output device changes.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    fn process_sample(&mut self, mic: f32, reference: f32) -> f32 {
+        self.delay_line.push_back(reference);
+        let reference = if self.delay_line.len() > self.config.bulk_delay {
+            self.delay_line.pop_front().unwrap_or(0.0)
+        } else {
+            0.0
+        };
+
+        let n = self.config.filter_len;
+        self.pos = (self.pos + n - 1) % n;
+        // The slot being overwritten holds the sample leaving the window.
+        let leaving = self.history[self.pos];
+        self.history[self.pos] = reference;
+        self.history[self.pos + n] = reference;
+        self.energy = (self.energy + reference * reference - leaving * leaving).max(0.0);
+
+        let window = &self.history[self.pos..self.pos + n];
+        let estimate: f32 = self.weights.iter().zip(window).map(|(w, x)| w * x).sum();
+        let error = mic - estimate;
+
+        let mic_alpha = if mic * mic > self.mic_power {
+            MIC_ATTACK_ALPHA
+        } else {
+            POWER_ALPHA
+        };
+        self.mic_power += mic_alpha * (mic * mic - self.mic_power);
+        self.reference_power += POWER_ALPHA * (reference * reference - self.reference_power);
+        if self.mic_power > DOUBLE_TALK_RATIO * self.reference_power {
+            self.hold = DOUBLE_TALK_HOLD;
+        } else {
+            self.hold = self.hold.saturating_sub(1);
+        }
+        if self.hold == 0 && self.reference_power > MIN_REFERENCE_POWER {
+            let step = self.config.step_size * error / (self.energy + 1e-6);
+            for (w, x) in self.weights.iter_mut().zip(window) {
+                *w += step * x;
+            }
+        }
+        error
+    }
+}
@@ -0,0 +1,148 @@
+//! Captured audio with its format attached, as it travels from a capture source to the
+//! processing thread. Sources tag what they send instead of everyone assuming 16 kHz mono, so
+//! a source that can't resample (or a host pushing audio through the FFI) still works, and a
+//! mismatch is caught where the audio enters the pipeline.
+
+use std::time::Duration;
+
+use super::Resampler;
+
+/// The rate the segmenters and transcribers run at.
+pub const ASR_SAMPLE_RATE: u32 = 16_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioChunk {
+    /// Interleaved when there is more than one channel.
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Stream time of the first sample, counted from the start of the capture. Chunks dropped
+    /// on a full queue leave a gap here.
+    pub pts: Duration,
+}
+
+impl AudioChunk {
+    /// 16 kHz mono samples, the pipeline's own format.
+    pub fn asr(samples: Vec<f32>, pts: Duration) -> Self {
+        Self {
+            samples,
+            sample_rate: ASR_SAMPLE_RATE,
+            channels: 1,
+            pts,
+        }
+    }
+
+    pub fn is_asr_format(&self) -> bool {
+        self.sample_rate == ASR_SAMPLE_RATE && self.channels == 1
+    }
+
+    /// Samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / usize::from(self.channels.max(1))
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    /// Averages the channels into one.
+    pub fn into_mono(self) -> Self {
+        if self.channels <= 1 {
+            return self;
+        }
+        let channels = usize::from(self.channels);
+        let samples = self
+            .samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        Self {
+            samples,
+            channels: 1,
+            ..self
+        }
+    }
+}
+
+/// Tags each chunk a source sends with its format and stream time.
+#[derive(Debug, Clone)]
+pub struct ChunkClock {
+    sample_rate: u32,
+    channels: u16,
+    /// Stream time at the last format change, and frames stamped since.
+    base: Duration,
+    frames: u64,
+}
+
+impl ChunkClock {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels: channels.max(1),
+            base: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    /// For sources that resample to 16 kHz mono themselves.
+    pub fn asr() -> Self {
+        Self::new(ASR_SAMPLE_RATE, 1)
+    }
+
+    /// For a source whose format changes mid-stream; stream time carries on.
+    pub fn set_format(&mut self, sample_rate: u32, channels: u16) {
+        if (sample_rate, channels.max(1)) != (self.sample_rate, self.channels) {
+            self.base = self.now();
+            self.frames = 0;
+            self.sample_rate = sample_rate;
+            self.channels = channels.max(1);
+        }
+    }
+
+    pub fn stamp(&mut self, samples: Vec<f32>) -> AudioChunk {
+        let pts = self.now();
+        let chunk = AudioChunk {
+            samples,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            pts,
+        };
+        self.frames += chunk.frames() as u64;
+        chunk
+    }
+
+    fn now(&self) -> Duration {
+        self.base + Duration::from_secs_f64(self.frames as f64 / self.sample_rate.max(1) as f64)
+    }
+}
+
+/// Brings chunks in any format to 16 kHz mono, following rate changes mid-stream. Chunks
+/// already in that format pass through untouched.
+#[derive(Debug, Default)]
+pub struct AsrConverter {
+    resampler: Option<Resampler>,
+}
+
+impl AsrConverter {
+    pub fn convert(&mut self, chunk: AudioChunk) -> Vec<f32> {
+        if chunk.is_asr_format() {
+            return chunk.samples;
+        }
+        if chunk.sample_rate == 0 {
+            tracing::warn!("dropping audio with a sample rate of 0");
+            return Vec::new();
+        }
+        let mono = chunk.into_mono();
+        let resampler = match &mut self.resampler {
+            Some(resampler) if resampler.input_rate() == mono.sample_rate => resampler,
+            slot => slot.insert(Resampler::new(mono.sample_rate, ASR_SAMPLE_RATE)),
+        };
+        match resampler.process_chunk(&mono) {
+            Ok(resampled) => resampled.samples,
+            Err(err) => {
+                tracing::warn!("dropping audio: {err:#}");
+                Vec::new()
+            }
+        }
+    }
+}
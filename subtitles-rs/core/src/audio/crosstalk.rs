@@ -0,0 +1,64 @@
+//! Crosstalk detection (`--mark-crosstalk`): finds the parts of an utterance where two people
+//! talk at once, so the caption can say so instead of presenting whisper's merge of both as
+//! confident text. Two voices at once are louder than either, and their harmonics beat
+//! against each other, so the spectrum (the mel cepstrum voice prints use) jumps from frame to
+//! frame far more than one voice's does. Both are judged against the rest of the same
+//! utterance, so a stretch is only found when someone joins in or drops out; an utterance
+//! that is crosstalk from start to finish looks like one unusual voice.
+
+use super::voice::{cepstral_frames, CEPSTRA};
+
+/// Half-second blocks, each judged as a whole.
+const BLOCK: usize = 8_000;
+/// Blocks needed for the rest of the utterance to be a baseline.
+const MIN_BLOCKS: usize = 4;
+/// Frames a block needs to be judged (about half of it not silent).
+const MIN_BLOCK_FRAMES: usize = 25;
+/// How much more a block's spectrum must change frame to frame than the utterance's median.
+const CHANGE_RATIO: f32 = 2.0;
+/// How far below the utterance's median level a block may be and still count. Quieter blocks
+/// are word tails and breaths, whose spectra are unsteady anyway.
+const LEVEL_MARGIN_DB: f32 = 3.0;
+
+/// The share (`0.0..=1.0`) of an utterance's judged half-seconds that sound like two voices
+/// at once. Utterances under two seconds are never judged.
+pub fn crosstalk_fraction(samples: &[f32]) -> f32 {
+    let frames = cepstral_frames(samples);
+    let mut blocks: Vec<(f32, f32)> = Vec::new();
+    for block in 0..samples.len() / BLOCK {
+        let frames: Vec<_> = frames
+            .iter()
+            .filter(|frame| frame.start / BLOCK == block)
+            .collect();
+        if frames.len() < MIN_BLOCK_FRAMES {
+            continue;
+        }
+        let level = frames.iter().map(|frame| frame.db).sum::<f32>() / frames.len() as f32;
+        let change = frames
+            .windows(2)
+            .map(|pair| {
+                (0..CEPSTRA)
+                    .map(|k| (pair[1].cepstra[k] - pair[0].cepstra[k]).powi(2))
+                    .sum::<f32>()
+                    .sqrt()
+            })
+            .sum::<f32>()
+            / (frames.len() - 1) as f32;
+        blocks.push((level, change));
+    }
+    if blocks.len() < MIN_BLOCKS {
+        return 0.0;
+    }
+
+    let median = |mut values: Vec<f32>| {
+        values.sort_by(f32::total_cmp);
+        values[values.len() / 2]
+    };
+    let level = median(blocks.iter().map(|block| block.0).collect());
+    let change = median(blocks.iter().map(|block| block.1).collect());
+    let overlapped = blocks
+        .iter()
+        .filter(|block| block.0 >= level - LEVEL_MARGIN_DB && block.1 >= change * CHANGE_RATIO)
+        .count();
+    overlapped as f32 / blocks.len() as f32
+}
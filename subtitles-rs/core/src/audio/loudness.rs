@@ -0,0 +1,138 @@
+//! Loudness-gated voice detection (`--vad-loudness-lufs`). Frames are judged by K-weighted
+//! loudness (ITU-R BS.1770, the LUFS scale) instead of raw RMS, against an absolute floor and
+//! relative to the loudness of the last 3 s of speech. K-weighting discounts rumble and counts
+//! the presence band speech lives in, and the relative gate follows how loud the source is
+//! mastered: a film's music bed between lines is a pause, though it would pass any RMS
+//! threshold low enough for a quiet call.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use super::preprocess::{AudioStage, Biquad};
+
+/// Speech level is the loudness of this much of the latest speech (EBU R128's short-term).
+const SPEECH_LEVEL_S: f32 = 3.0;
+/// Speech older than this no longer counts, so a source that turns down isn't gated forever.
+const SPEECH_MEMORY_S: f32 = 10.0;
+/// A frame this far below the speech level is a pause, however loud the source.
+const RELATIVE_GATE_LU: f32 = 15.0;
+
+pub struct LoudnessGate {
+    threshold_lufs: f32,
+    k_weighting: [Biquad; 2],
+    scratch: Vec<f32>,
+    /// K-weighted samples of the latest frame, so overlapping frames filter each sample once.
+    frame: VecDeque<f32>,
+    /// Stream position, K-weighted energy and length of recent speech hops, summed below.
+    speech: VecDeque<(u64, f64, usize)>,
+    energy: f64,
+    samples: usize,
+    position: u64,
+    level_len: usize,
+    memory_len: u64,
+}
+
+impl LoudnessGate {
+    /// Frames quieter than `threshold_lufs` are never voice.
+    pub fn new(threshold_lufs: f32, sample_rate_hz: u32) -> Self {
+        Self {
+            threshold_lufs,
+            k_weighting: k_weighting(sample_rate_hz),
+            scratch: Vec::new(),
+            frame: VecDeque::new(),
+            speech: VecDeque::new(),
+            energy: 0.0,
+            samples: 0,
+            position: 0,
+            level_len: (SPEECH_LEVEL_S * sample_rate_hz as f32) as usize,
+            memory_len: (SPEECH_MEMORY_S * sample_rate_hz as f32) as u64,
+        }
+    }
+
+    /// Whether the frame ending with `hop`, the next samples of the stream, is voice. The frame
+    /// is the last `frame_len` samples, which overlap earlier hops when longer than `hop`; each
+    /// sample counts towards the speech level once.
+    pub fn is_voice(&mut self, hop: &[f32], frame_len: usize) -> bool {
+        if hop.is_empty() {
+            return false;
+        }
+        self.scratch.clear();
+        self.scratch.extend_from_slice(hop);
+        for filter in &mut self.k_weighting {
+            filter.process(&mut self.scratch);
+        }
+        let energy: f64 = self.scratch.iter().map(|&s| s as f64 * s as f64).sum();
+        self.position += hop.len() as u64;
+        self.frame.extend(&self.scratch);
+        let excess = self.frame.len().saturating_sub(frame_len.max(hop.len()));
+        self.frame.drain(..excess);
+        let frame_energy: f64 = self.frame.iter().map(|&s| s as f64 * s as f64).sum();
+
+        // Forget speech beyond the level window or too long ago.
+        while let Some(&(at, oldest, len)) = self.speech.front() {
+            if self.samples - len < self.level_len && at + self.memory_len >= self.position {
+                break;
+            }
+            self.speech.pop_front();
+            self.energy -= oldest;
+            self.samples -= len;
+        }
+
+        let frame_lufs = lufs(frame_energy / self.frame.len() as f64);
+        let speech_lufs = (self.samples > 0)
+            .then(|| lufs(self.energy.max(0.0) / self.samples as f64));
+        let is_voice = frame_lufs >= self.threshold_lufs
+            && speech_lufs.is_none_or(|speech| frame_lufs >= speech - RELATIVE_GATE_LU);
+        if is_voice {
+            self.speech.push_back((self.position, energy, hop.len()));
+            self.energy += energy;
+            self.samples += hop.len();
+        }
+        is_voice
+    }
+}
+
+/// Integrated loudness of `samples` in LUFS, ungated: a full-scale 1 kHz sine is about -3.
+pub fn loudness_lufs(samples: &[f32], sample_rate_hz: u32) -> f32 {
+    let mut weighted = samples.to_vec();
+    for mut filter in k_weighting(sample_rate_hz) {
+        filter.process(&mut weighted);
+    }
+    let energy: f64 = weighted.iter().map(|&s| s as f64 * s as f64).sum();
+    lufs(energy / samples.len().max(1) as f64)
+}
+
+fn lufs(mean_square: f64) -> f32 {
+    (-0.691 + 10.0 * mean_square.max(1e-12).log10()) as f32
+}
+
+/// The BS.1770 pre-filter (a +4 dB shelf above ~1.7 kHz, for the head) and RLB high-pass,
+/// designed for any sample rate from their analog prototypes.
+fn k_weighting(sample_rate_hz: u32) -> [Biquad; 2] {
+    let fs = sample_rate_hz as f64;
+
+    let (f0, gain_db) = (1_681.974_450_955_533, 3.999_843_853_973_347);
+    let q = 0.707_175_236_955_419_6;
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, highpass]
+}
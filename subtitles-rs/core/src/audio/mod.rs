@@ -0,0 +1,411 @@
+pub mod aec;
+pub mod chunk;
+pub mod crosstalk;
+pub mod loudness;
+pub mod preprocess;
+pub mod resample;
+pub mod voice;
+
+use std::time::{Duration, Instant};
+
+pub use aec::{EchoCanceller, EchoCancellerConfig};
+pub use chunk::{AsrConverter, AudioChunk, ChunkClock, ASR_SAMPLE_RATE};
+pub use crosstalk::crosstalk_fraction;
+pub use loudness::{loudness_lufs, LoudnessGate};
+pub use preprocess::{AudioStage, PreprocessStage};
+pub use resample::Resampler;
+pub use voice::{VoicePrint, VoiceProfile, VoiceProfiles};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SegmenterConfig {
+    pub sample_rate_hz: u32,
+    pub vad_threshold: f32,
+    pub vad_end_silence_s: f32,
+    pub max_segment_s: f32,
+    pub pre_roll_s: f32,
+}
+
+pub struct Segmenter {
+    cfg: SegmenterConfig,
+    /// Audio the VAD judges at once, and how far it moves between judgements.
+    frame_size: usize,
+    hop_size: usize,
+    end_silence_frames: usize,
+    max_segment_samples: usize,
+    pre_roll_samples: usize,
+
+    stash: Vec<f32>,
+    stash_pos: usize,
+
+    in_speech: bool,
+    voice: VoiceSmoother,
+    silent_frames: usize,
+    pre_roll: std::collections::VecDeque<f32>,
+    current: Vec<f32>,
+    /// Samples consumed into frames so far; the current segment always ends at this position.
+    framed_samples: u64,
+    segment_starts: std::collections::VecDeque<u64>,
+    loudness_gate: Option<LoudnessGate>,
+}
+
+impl Segmenter {
+    pub fn new(cfg: SegmenterConfig) -> Self {
+        let frame_dur = Duration::from_millis(20);
+        let frame_size = ((cfg.sample_rate_hz as f32) * frame_dur.as_secs_f32()).round() as usize;
+
+        let end_silence_frames = end_silence_hops(cfg.vad_end_silence_s, frame_dur);
+
+        let max_segment_samples = ((cfg.max_segment_s * cfg.sample_rate_hz as f32).max(1.0))
+            .round() as usize;
+        let pre_roll_samples =
+            ((cfg.pre_roll_s * cfg.sample_rate_hz as f32).max(0.0)).round() as usize;
+
+        Self {
+            cfg,
+            frame_size: frame_size.max(1),
+            hop_size: frame_size.max(1),
+            end_silence_frames,
+            max_segment_samples,
+            pre_roll_samples,
+            stash: Vec::new(),
+            stash_pos: 0,
+            in_speech: false,
+            voice: VoiceSmoother::new(frame_dur),
+            silent_frames: 0,
+            pre_roll: std::collections::VecDeque::new(),
+            current: Vec::new(),
+            framed_samples: 0,
+            segment_starts: std::collections::VecDeque::new(),
+            loudness_gate: None,
+        }
+    }
+
+    /// Judges frames by loudness instead of `vad_threshold`.
+    pub fn with_loudness_gate(mut self, gate: LoudnessGate) -> Self {
+        self.loudness_gate = Some(gate);
+        self
+    }
+
+    /// Judges `frame_ms` of audio every `hop_ms` instead of back-to-back 20 ms frames.
+    pub fn with_vad_frames(mut self, frame_ms: u32, hop_ms: u32) -> Self {
+        let frames = VadFrames::new(frame_ms, hop_ms, self.cfg.sample_rate_hz);
+        self.frame_size = frames.frame_size;
+        self.hop_size = frames.hop_size;
+        self.end_silence_frames = end_silence_hops(self.cfg.vad_end_silence_s, frames.hop);
+        self.voice = VoiceSmoother::new(frames.hop);
+        self
+    }
+
+    pub fn push_audio(&mut self, audio: &[f32]) -> Vec<Vec<f32>> {
+        self.stash.extend_from_slice(audio);
+        self.segment_starts.clear();
+
+        let mut out = Vec::new();
+        while self.stash.len().saturating_sub(self.stash_pos) >= self.hop_size {
+            let start = self.stash_pos;
+            let end = self.stash_pos + self.hop_size;
+            let window = &self.stash[end.saturating_sub(self.frame_size)..end];
+            let frame = &self.stash[start..end];
+            self.stash_pos = end;
+            self.framed_samples += self.hop_size as u64;
+
+            let probability = match self.loudness_gate.as_mut() {
+                Some(gate) => f32::from(u8::from(gate.is_voice(frame, self.frame_size))),
+                None => voice_probability(rms(window), self.cfg.vad_threshold),
+            };
+            let is_voice = self.voice.update(probability);
+
+            if self.in_speech {
+                self.current.extend_from_slice(frame);
+                if is_voice {
+                    self.silent_frames = 0;
+                } else {
+                    self.silent_frames += 1;
+                }
+
+                if self.silent_frames >= self.end_silence_frames
+                    || self.current.len() >= self.max_segment_samples
+                {
+                    out.push(self.flush_segment());
+                }
+            } else {
+                push_pre_roll(&mut self.pre_roll, self.pre_roll_samples, frame);
+                if is_voice {
+                    self.in_speech = true;
+                    self.silent_frames = 0;
+                    self.current.extend(self.pre_roll.drain(..));
+                }
+            }
+        }
+
+        // keep stash from growing without bound, less what the next window looks back on
+        if self.stash_pos > self.frame_size * 128 {
+            let keep = self.frame_size - self.hop_size;
+            self.stash.drain(..self.stash_pos - keep);
+            self.stash_pos = keep;
+        }
+
+        out
+    }
+
+    /// Where the next segment returned by the last `push_audio` starts, counted in samples
+    /// pushed since the segmenter was created. Call once per segment, in order.
+    pub fn take_segment_start(&mut self) -> Option<u64> {
+        self.segment_starts.pop_front()
+    }
+
+    fn flush_segment(&mut self) -> Vec<f32> {
+        let start = self.framed_samples - self.current.len() as u64;
+        self.segment_starts.push_back(start);
+        self.in_speech = false;
+        self.silent_frames = 0;
+        self.pre_roll.clear();
+        std::mem::take(&mut self.current)
+    }
+
+}
+
+/// VAD frame and hop lengths, in samples, for `--vad-frame-ms` and `--vad-hop-ms`.
+pub(crate) struct VadFrames {
+    pub(crate) frame_size: usize,
+    /// At most `frame_size`.
+    pub(crate) hop_size: usize,
+    pub(crate) hop: Duration,
+}
+
+impl VadFrames {
+    pub(crate) fn new(frame_ms: u32, hop_ms: u32, sample_rate_hz: u32) -> Self {
+        let samples = |ms: u32| (sample_rate_hz as f32 * ms as f32 / 1000.0).round() as usize;
+        let frame_size = samples(frame_ms).max(1);
+        let hop_size = samples(hop_ms).clamp(1, frame_size);
+        Self {
+            frame_size,
+            hop_size,
+            hop: Duration::from_secs_f32(hop_size as f32 / sample_rate_hz.max(1) as f32),
+        }
+    }
+}
+
+/// How many hops of silence make up `end_silence_s`.
+pub(crate) fn end_silence_hops(end_silence_s: f32, hop: Duration) -> usize {
+    ((end_silence_s / hop.as_secs_f32()).max(1.0)).round() as usize
+}
+
+/// How likely a frame at `rms` is voice: one half at `threshold`, nearly certain 6 dB over.
+pub(crate) fn voice_probability(rms: f32, threshold: f32) -> f32 {
+    let over_db = 20.0 * (rms.max(1e-9) / threshold.max(1e-9)).log10();
+    1.0 / (1.0 + (-over_db / VOICE_SLOPE_DB).exp())
+}
+
+const VOICE_SLOPE_DB: f32 = 1.5;
+/// A frame must be about 1.5 dB over the threshold to start speech on its own.
+const VOICE_ATTACK: f32 = 0.7;
+/// Certain voice holds through 4 frames (80 ms) of silence before dropping below one half,
+/// scaled to the hop so the hangover lasts as long with any `--vad-hop-ms`.
+const VOICE_RELEASE: f32 = 0.13;
+const VOICE_RELEASE_HOP: Duration = Duration::from_millis(20);
+
+/// Smooths per-frame voice probabilities with a fast attack and a slower release, so the
+/// closure of a plosive or the fading end of a word isn't counted as end silence, and a lone
+/// frame that barely crosses the threshold doesn't start speech.
+#[derive(Debug)]
+pub(crate) struct VoiceSmoother {
+    probability: f32,
+    release: f32,
+}
+
+impl VoiceSmoother {
+    pub(crate) fn new(hop: Duration) -> Self {
+        let hops = hop.as_secs_f32() / VOICE_RELEASE_HOP.as_secs_f32();
+        Self {
+            probability: 0.0,
+            release: 1.0 - (1.0 - VOICE_RELEASE).powf(hops),
+        }
+    }
+
+    /// Feeds the next frame's probability; returns whether the stream is in voice.
+    pub(crate) fn update(&mut self, probability: f32) -> bool {
+        let rate = if probability > self.probability {
+            VOICE_ATTACK
+        } else {
+            self.release
+        };
+        self.probability += (probability - self.probability) * rate;
+        self.probability >= 0.5
+    }
+}
+
+/// The `--preprocess` stages, run in order on every captured chunk before segmentation.
+pub struct PreprocessChain {
+    stages: Vec<Box<dyn AudioStage>>,
+}
+
+impl PreprocessChain {
+    pub fn new(stages: &[PreprocessStage], sample_rate_hz: u32) -> Self {
+        Self {
+            stages: stages
+                .iter()
+                .map(|stage| stage.build(sample_rate_hz))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for stage in &mut self.stages {
+            stage.process(samples);
+        }
+    }
+}
+
+/// Signal level of a block of audio, for level meters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct AudioLevel {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+impl AudioLevel {
+    pub fn measure(samples: &[f32]) -> Self {
+        Self {
+            rms: rms(samples),
+            peak: samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs())),
+        }
+    }
+
+    /// Louder of two readings, channel by channel.
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            rms: self.rms.max(other.rms),
+            peak: self.peak.max(other.peak),
+        }
+    }
+
+    pub fn rms_dbfs(&self) -> f32 {
+        20.0 * self.rms.max(1e-6).log10()
+    }
+}
+
+/// Turns a stream of samples into one `AudioLevel` per `block_len` samples.
+pub struct LevelMeter {
+    block_len: usize,
+    sum_sq: f32,
+    peak: f32,
+    count: usize,
+}
+
+impl LevelMeter {
+    pub fn new(block_len: usize) -> Self {
+        Self {
+            block_len: block_len.max(1),
+            sum_sq: 0.0,
+            peak: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Changes how many samples each level covers, from the block being measured on.
+    pub fn set_block_len(&mut self, block_len: usize) {
+        self.block_len = block_len.max(1);
+    }
+
+    pub fn push(&mut self, samples: &[f32]) -> Vec<AudioLevel> {
+        let mut out = Vec::new();
+        for &s in samples {
+            self.sum_sq += s * s;
+            self.peak = self.peak.max(s.abs());
+            self.count += 1;
+            if self.count >= self.block_len {
+                out.push(AudioLevel {
+                    rms: (self.sum_sq / self.count as f32).sqrt(),
+                    peak: self.peak,
+                });
+                self.sum_sq = 0.0;
+                self.peak = 0.0;
+                self.count = 0;
+            }
+        }
+        out
+    }
+}
+
+/// Decides when the input has gone quiet for long enough to pause the engine.
+pub struct IdleDetector {
+    threshold: f32,
+    timeout: Duration,
+    last_audible: Instant,
+    idle: bool,
+}
+
+impl IdleDetector {
+    /// `threshold` is an RMS level (same scale as `vad_threshold`).
+    pub fn new(threshold: f32, timeout: Duration, now: Instant) -> Self {
+        Self {
+            threshold,
+            timeout,
+            last_audible: now,
+            idle: false,
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Feeds the latest chunk (`None` when nothing arrived) and whether the capture source has
+    /// gone away. Returns the new state when it changes.
+    pub fn update(
+        &mut self,
+        chunk: Option<&[f32]>,
+        source_gone: bool,
+        now: Instant,
+    ) -> Option<bool> {
+        let audible = chunk.is_some_and(|c| rms(c) >= self.threshold);
+        if audible {
+            self.last_audible = now;
+        }
+        // Once idle, only audible input resumes (not a closed app reappearing).
+        let idle = source_gone
+            || if self.idle {
+                !audible
+            } else {
+                now.duration_since(self.last_audible) >= self.timeout
+            };
+        (idle != self.idle).then(|| {
+            self.idle = idle;
+            idle
+        })
+    }
+}
+
+fn push_pre_roll(
+    pre_roll: &mut std::collections::VecDeque<f32>,
+    pre_roll_samples: usize,
+    frame: &[f32],
+) {
+    if pre_roll_samples == 0 {
+        return;
+    }
+
+    for &s in frame {
+        pre_roll.push_back(s);
+    }
+    while pre_roll.len() > pre_roll_samples {
+        pre_roll.pop_front();
+    }
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+
+    let mut sum = 0.0f32;
+    for &s in frame {
+        sum += s * s;
+    }
+    (sum / (frame.len() as f32)).sqrt()
+}
@@ -0,0 +1,323 @@
+//! The stages `--preprocess` can chain in front of the segmenter, e.g.
+//! `--preprocess highpass,agc,denoise`. Each runs on the 16 kHz mono capture in place, chunk by
+//! chunk, keeping whatever state it needs across chunks.
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use super::voice::fft;
+
+/// One named stage and its parameter, as written in the config (`highpass:80`, `denoise`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreprocessStage {
+    /// Removes a constant offset (a cheap interface or a bad driver), nothing audible.
+    Dc,
+    /// Second-order high-pass at a cutoff in Hz (default 80): HVAC and traffic rumble, desk
+    /// thumps and DC, which otherwise hold the VAD's RMS above threshold with nobody talking.
+    Highpass { cutoff_hz: f32 },
+    /// Automatic gain control towards a target level in dBFS (default -20).
+    Agc { target_dbfs: f32 },
+    /// Spectral subtraction of a stationary noise floor, by a factor (default 1.5) of it.
+    Denoise { strength: f32 },
+}
+
+impl PreprocessStage {
+    pub const NAMES: [&'static str; 4] = ["dc", "highpass", "agc", "denoise"];
+
+    /// A fresh instance of the stage for audio at `sample_rate_hz`.
+    pub fn build(&self, sample_rate_hz: u32) -> Box<dyn AudioStage> {
+        match *self {
+            Self::Dc => Box::new(DcBlocker::new(sample_rate_hz)),
+            Self::Highpass { cutoff_hz } => Box::new(Biquad::highpass(cutoff_hz, sample_rate_hz)),
+            Self::Agc { target_dbfs } => Box::new(Agc::new(target_dbfs, sample_rate_hz)),
+            Self::Denoise { strength } => Box::new(Denoise::new(strength)),
+        }
+    }
+}
+
+impl FromStr for PreprocessStage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, param) = match s.trim().split_once(':') {
+            Some((name, param)) => (name.trim(), Some(param.trim())),
+            None => (s.trim(), None),
+        };
+        let number = |default: f32| -> Result<f32, String> {
+            match param {
+                None => Ok(default),
+                Some(param) => param
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| format!("`{name}` takes a number, got `{param}`")),
+            }
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "dc" if param.is_none() => Ok(Self::Dc),
+            "dc" => Err("`dc` takes no parameter".to_string()),
+            "highpass" => {
+                let cutoff_hz = number(80.0)?;
+                if !(10.0..=1000.0).contains(&cutoff_hz) {
+                    return Err(format!("highpass cutoff must be 10..1000 Hz, got {cutoff_hz}"));
+                }
+                Ok(Self::Highpass { cutoff_hz })
+            }
+            "agc" => {
+                let target_dbfs = number(-20.0)?;
+                if !(-60.0..=0.0).contains(&target_dbfs) {
+                    return Err(format!("agc target must be -60..0 dBFS, got {target_dbfs}"));
+                }
+                Ok(Self::Agc { target_dbfs })
+            }
+            "denoise" => {
+                let strength = number(1.5)?;
+                if !(0.0..=4.0).contains(&strength) {
+                    return Err(format!("denoise strength must be 0..4, got {strength}"));
+                }
+                Ok(Self::Denoise { strength })
+            }
+            _ => Err(format!(
+                "unknown preprocessing stage `{name}` (expected one of: {})",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PreprocessStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dc => write!(f, "dc"),
+            Self::Highpass { cutoff_hz } => write!(f, "highpass:{cutoff_hz}"),
+            Self::Agc { target_dbfs } => write!(f, "agc:{target_dbfs}"),
+            Self::Denoise { strength } => write!(f, "denoise:{strength}"),
+        }
+    }
+}
+
+/// A preprocessing step: rewrites `samples` in place, the same number of samples out as in.
+pub trait AudioStage: Send {
+    fn process(&mut self, samples: &mut [f32]);
+}
+
+/// Corner of the DC blocker: well below any voice or the room's rumble.
+const DC_CORNER_HZ: f32 = 5.0;
+
+/// One-pole DC blocker, `y[n] = x[n] - x[n-1] + r * y[n-1]`.
+struct DcBlocker {
+    r: f32,
+    last_in: f32,
+    last_out: f32,
+}
+
+impl DcBlocker {
+    fn new(sample_rate_hz: u32) -> Self {
+        Self {
+            r: 1.0 - 2.0 * PI * DC_CORNER_HZ / sample_rate_hz as f32,
+            last_in: 0.0,
+            last_out: 0.0,
+        }
+    }
+}
+
+impl AudioStage for DcBlocker {
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let out = *sample - self.last_in + self.r * self.last_out;
+            self.last_in = *sample;
+            self.last_out = out;
+            *sample = out;
+        }
+    }
+}
+
+/// A second-order section in transposed direct form II, with the state in f64 so that poles
+/// this close to DC don't drift.
+pub(super) struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    /// From coefficients normalized so that `a0` is 1.
+    pub(super) fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            state: [0.0; 2],
+        }
+    }
+
+    /// Butterworth high-pass (Q = 1/√2), from the RBJ audio EQ cookbook.
+    fn highpass(cutoff_hz: f32, sample_rate_hz: u32) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * cutoff_hz as f64 / sample_rate_hz as f64;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / std::f64::consts::SQRT_2;
+        let a0 = 1.0 + alpha;
+        let b0 = (1.0 + cos) / 2.0 / a0;
+        Self::new([b0, -2.0 * b0, b0], [-2.0 * cos / a0, (1.0 - alpha) / a0])
+    }
+}
+
+impl AudioStage for Biquad {
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let x = *sample as f64;
+            let y = self.b[0] * x + self.state[0];
+            self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+            self.state[1] = self.b[2] * x - self.a[1] * y;
+            *sample = y as f32;
+        }
+    }
+}
+
+/// Level is measured over 10 ms blocks.
+const AGC_BLOCK_S: f32 = 0.01;
+/// Gain limits: quiet input is lifted at most 20 dB, loud input cut at most 20 dB.
+const AGC_MAX_GAIN: f32 = 10.0;
+const AGC_MIN_GAIN: f32 = 0.1;
+/// Below this RMS the input is silence, and the gain holds rather than chase it up.
+const AGC_SILENCE_RMS: f32 = 1e-3;
+
+/// Moves the level towards a target: fast when the input gets louder (~10 ms, so a sudden
+/// shout isn't clipped), slowly when it gets quieter (~1 s, so pauses aren't pumped up).
+struct Agc {
+    target_rms: f32,
+    block_len: usize,
+    gain: f32,
+}
+
+impl Agc {
+    fn new(target_dbfs: f32, sample_rate_hz: u32) -> Self {
+        Self {
+            target_rms: 10f32.powf(target_dbfs / 20.0),
+            block_len: ((sample_rate_hz as f32 * AGC_BLOCK_S) as usize).max(1),
+            gain: 1.0,
+        }
+    }
+}
+
+impl AudioStage for Agc {
+    fn process(&mut self, samples: &mut [f32]) {
+        for chunk in samples.chunks_mut(self.block_len) {
+            let rms = (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt();
+            let previous = self.gain;
+            if rms >= AGC_SILENCE_RMS {
+                let wanted = (self.target_rms / rms).clamp(AGC_MIN_GAIN, AGC_MAX_GAIN);
+                let rate = if wanted < self.gain { 0.5 } else { 0.01 };
+                self.gain += (wanted - self.gain) * rate;
+            }
+            // Ramp across the block so gain steps don't click.
+            let step = (self.gain - previous) / chunk.len() as f32;
+            for (i, sample) in chunk.iter_mut().enumerate() {
+                *sample = (*sample * (previous + step * (i + 1) as f32)).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+/// 32 ms frames at 16 kHz, half overlapping.
+const DENOISE_FRAME: usize = 512;
+const DENOISE_HOP: usize = DENOISE_FRAME / 2;
+/// How fast the noise estimate may rise per frame (falls are immediate), about 3 dB a second,
+/// so speech doesn't get learned as noise but a fan switching on is within a few seconds.
+const NOISE_RISE: f32 = 1.011;
+/// The minimum of a bin's smoothed power sits well below the noise's mean power; this scales
+/// it back up.
+const NOISE_BIAS: f32 = 3.0;
+/// Smoothing of each bin's power across frames before it's compared with the noise.
+const POWER_SMOOTHING: f32 = 0.7;
+/// Power gain floor per bin (-10 dB): removing more leaves "musical" artefacts that hurt
+/// recognition.
+const MIN_BIN_GAIN: f32 = 0.1;
+
+/// Spectral subtraction with a minimum-tracking noise estimate: each bin keeps what's left
+/// after taking out `strength` times the noise floor. It removes steady noise (fans, hum, hiss)
+/// and does nothing for babble or music. Frames assume 16 kHz; the audio comes out 32 ms late.
+struct Denoise {
+    strength: f32,
+    window: Vec<f32>,
+    noise: Vec<f32>,
+    smoothed: Vec<f32>,
+    input: Vec<f32>,
+    overlap: Vec<f32>,
+    output: VecDeque<f32>,
+}
+
+impl Denoise {
+    fn new(strength: f32) -> Self {
+        let bins = DENOISE_FRAME / 2 + 1;
+        // Square-root Hann on analysis and synthesis sums to one at half overlap.
+        let window = (0..DENOISE_FRAME)
+            .map(|i| (PI * i as f32 / DENOISE_FRAME as f32).sin())
+            .collect();
+        Self {
+            strength,
+            window,
+            noise: vec![f32::MAX; bins],
+            smoothed: vec![0.0; bins],
+            input: vec![0.0; DENOISE_HOP],
+            overlap: vec![0.0; DENOISE_HOP],
+            output: std::iter::repeat_n(0.0, DENOISE_HOP).collect(),
+        }
+    }
+
+    /// Filters the frame made of the last `DENOISE_FRAME` input samples and emits a hop.
+    fn frame(&mut self) {
+        let mut re = [0.0f32; DENOISE_FRAME];
+        let mut im = [0.0f32; DENOISE_FRAME];
+        for (i, (out, &sample)) in re.iter_mut().zip(&self.input).enumerate() {
+            *out = sample * self.window[i];
+        }
+        fft(&mut re, &mut im);
+        for bin in 0..=DENOISE_FRAME / 2 {
+            let power = re[bin] * re[bin] + im[bin] * im[bin];
+            let smoothed =
+                POWER_SMOOTHING * self.smoothed[bin] + (1.0 - POWER_SMOOTHING) * power;
+            self.smoothed[bin] = smoothed;
+            self.noise[bin] = (self.noise[bin] * NOISE_RISE).min(smoothed.max(1e-12));
+            let gain = if power > 0.0 {
+                (1.0 - self.strength * NOISE_BIAS * self.noise[bin] / power.max(smoothed))
+                    .max(MIN_BIN_GAIN)
+                    .sqrt()
+            } else {
+                MIN_BIN_GAIN
+            };
+            re[bin] *= gain;
+            im[bin] *= gain;
+            // Keep the spectrum conjugate-symmetric so the inverse stays real.
+            if bin > 0 && bin < DENOISE_FRAME / 2 {
+                re[DENOISE_FRAME - bin] = re[bin];
+                im[DENOISE_FRAME - bin] = -im[bin];
+            }
+        }
+        // Inverse FFT via the forward one on the conjugate.
+        for value in im.iter_mut() {
+            *value = -*value;
+        }
+        fft(&mut re, &mut im);
+        let scale = 1.0 / DENOISE_FRAME as f32;
+        for i in 0..DENOISE_HOP {
+            let sample = self.overlap[i] + re[i] * scale * self.window[i];
+            self.output.push_back(sample);
+            self.overlap[i] = re[i + DENOISE_HOP] * scale * self.window[i + DENOISE_HOP];
+        }
+        self.input.drain(..DENOISE_HOP);
+    }
+}
+
+impl AudioStage for Denoise {
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            self.input.push(*sample);
+            if self.input.len() == DENOISE_FRAME {
+                self.frame();
+            }
+            *sample = self.output.pop_front().unwrap_or(0.0);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Sample-rate conversion of captured audio to the 16 kHz the pipeline runs at. Capture
+//! devices can switch rate mid-session (AirPods dropping to 24 kHz when their mic opens), so
+//! the rate is a parameter rather than a fixed 48 kHz.
+
+use anyhow::{ensure, Result};
+
+use super::chunk::AudioChunk;
+
+/// Streaming resampler for mono audio. Each output sample is the average of the input it
+/// spans, weighted by overlap, which doubles as the anti-aliasing filter when downsampling.
+/// At 48 kHz in that is a plain average of every three samples.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    input_rate: u32,
+    output_rate: u32,
+    /// Input samples per output sample.
+    step: f64,
+    acc: f64,
+    filled: f64,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        Self {
+            input_rate,
+            output_rate,
+            step: input_rate.max(1) as f64 / output_rate.max(1) as f64,
+            acc: 0.0,
+            filled: 0.0,
+        }
+    }
+
+    pub fn input_rate(&self) -> u32 {
+        self.input_rate
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    pub fn push(&mut self, sample: f32, out: &mut Vec<f32>) {
+        let mut remaining = 1.0;
+        while remaining > 0.0 {
+            let take = (self.step - self.filled).min(remaining);
+            self.acc += sample as f64 * take;
+            self.filled += take;
+            remaining -= take;
+            // Within rounding of a full bin.
+            if self.filled >= self.step - 1e-9 {
+                out.push((self.acc / self.step) as f32);
+                self.acc = 0.0;
+                self.filled = 0.0;
+            }
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let mut out = Vec::with_capacity((samples.len() as f64 / self.step) as usize + 1);
+        for &sample in samples {
+            self.push(sample, &mut out);
+        }
+        out
+    }
+
+    /// Like [`Resampler::process`], checking the chunk is mono at this resampler's input rate
+    /// rather than trusting the caller. The output keeps the chunk's pts.
+    pub fn process_chunk(&mut self, chunk: &AudioChunk) -> Result<AudioChunk> {
+        ensure!(
+            chunk.sample_rate == self.input_rate,
+            "resampler expects {} Hz audio, got {} Hz",
+            self.input_rate,
+            chunk.sample_rate
+        );
+        ensure!(
+            chunk.channels == 1,
+            "resampler expects mono audio, got {} channels",
+            chunk.channels
+        );
+        Ok(AudioChunk {
+            samples: self.process(&chunk.samples),
+            sample_rate: self.output_rate,
+            channels: 1,
+            pts: chunk.pts,
+        })
+    }
+}
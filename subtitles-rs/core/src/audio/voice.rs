@@ -0,0 +1,276 @@
+//! Voice prints for telling enrolled people apart (`--voice-profiles`). A print is the mean and
+//! spread of the voiced frames' mel cepstrum, which mostly reflects the speaker's vocal tract
+//! rather than what they said. It is no match for a trained speaker-embedding model: it
+//! separates a handful of distinct voices in one room, not strangers at scale.
+
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+const SAMPLE_RATE: f32 = 16_000.0;
+/// 25 ms frames every 10 ms, zero-padded to the FFT size.
+const FRAME_LEN: usize = 400;
+const HOP: usize = 160;
+const FFT_LEN: usize = 512;
+const MEL_BANDS: usize = 26;
+/// Cepstral coefficients kept, after dropping c0 (loudness).
+pub(super) const CEPSTRA: usize = 12;
+/// Voiced frames (1 s) a print needs to be worth comparing.
+pub const MIN_VOICED_FRAMES: usize = 100;
+/// Frames more than this far below the loudest one (in dB) count as silence.
+pub(super) const VOICED_RANGE_DB: f32 = 30.0;
+const SILENCE_RMS: f32 = 1e-3;
+/// Keeps a coefficient that barely varies from dividing by zero in `similarity`.
+const MIN_VARIANCE: f32 = 1e-6;
+
+/// What a stretch of 16 kHz mono speech sounds like, comparable with `similarity`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VoicePrint(Vec<f32>);
+
+impl VoicePrint {
+    /// The print of `samples` (16 kHz mono), or `None` with under a second of voiced audio.
+    pub fn from_samples(samples: &[f32]) -> Option<Self> {
+        let frames = cepstral_frames(samples);
+        let loudest = frames.iter().map(|frame| frame.db).fold(f32::MIN, f32::max);
+        let voiced: Vec<&[f32; CEPSTRA]> = frames
+            .iter()
+            .filter(|frame| frame.db >= loudest - VOICED_RANGE_DB)
+            .map(|frame| &frame.cepstra)
+            .collect();
+        if voiced.len() < MIN_VOICED_FRAMES {
+            return None;
+        }
+        let n = voiced.len() as f32;
+        let mean: Vec<f32> = (0..CEPSTRA)
+            .map(|k| voiced.iter().map(|c| c[k]).sum::<f32>() / n)
+            .collect();
+        let spread = (0..CEPSTRA).map(|k| {
+            let variance = voiced.iter().map(|c| (c[k] - mean[k]).powi(2)).sum::<f32>() / n;
+            variance.sqrt()
+        });
+        Some(Self(mean.iter().copied().chain(spread).collect()))
+    }
+
+    /// How alike two prints are, in `[0, 1]`; 1 is the same voice. The cepstral means are
+    /// compared in units of their spread, so a difference counts as much as it stands out from
+    /// how much one voice varies between sounds: `exp(-d²)`, with `d` the RMS over coefficients
+    /// of the mean difference over the pooled spread. Samples of one voice score about 0.9,
+    /// different vocal tracts under 0.7.
+    pub fn similarity(&self, other: &VoicePrint) -> f32 {
+        if self.0.len() != 2 * CEPSTRA || other.0.len() != 2 * CEPSTRA {
+            return 0.0;
+        }
+        let (mean_a, spread_a) = self.0.split_at(CEPSTRA);
+        let (mean_b, spread_b) = other.0.split_at(CEPSTRA);
+        let squares: f32 = (0..CEPSTRA)
+            .map(|k| {
+                let variance = (spread_a[k].powi(2) + spread_b[k].powi(2)) / 2.0;
+                (mean_a[k] - mean_b[k]).powi(2) / variance.max(MIN_VARIANCE)
+            })
+            .sum();
+        (-squares / CEPSTRA as f32).exp()
+    }
+}
+
+/// One non-silent 25 ms frame: where it starts, its level and its mel cepstrum.
+pub(super) struct CepstralFrame {
+    pub start: usize,
+    pub db: f32,
+    pub cepstra: [f32; CEPSTRA],
+}
+
+/// The non-silent frames of `samples` (16 kHz mono), every 10 ms.
+pub(super) fn cepstral_frames(samples: &[f32]) -> Vec<CepstralFrame> {
+    let filters = mel_filters();
+    let window: Vec<f32> = (0..FRAME_LEN)
+        .map(|i| 0.54 - 0.46 * (2.0 * PI * i as f32 / (FRAME_LEN - 1) as f32).cos())
+        .collect();
+
+    let mut frames = Vec::new();
+    let mut start = 0;
+    while start + FRAME_LEN <= samples.len() {
+        let frame = &samples[start..start + FRAME_LEN];
+        let frame_start = start;
+        start += HOP;
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / FRAME_LEN as f32).sqrt();
+        if rms < SILENCE_RMS {
+            continue;
+        }
+        let mut re = [0.0f32; FFT_LEN];
+        let mut im = [0.0f32; FFT_LEN];
+        // Pre-emphasis flattens the spectral tilt every voice has.
+        let mut previous = frame[0];
+        for (i, &sample) in frame.iter().enumerate() {
+            re[i] = (sample - 0.97 * previous) * window[i];
+            previous = sample;
+        }
+        fft(&mut re, &mut im);
+        let power: Vec<f32> = (0..=FFT_LEN / 2)
+            .map(|bin| re[bin] * re[bin] + im[bin] * im[bin])
+            .collect();
+        let log_mel: Vec<f32> = filters
+            .iter()
+            .map(|filter| {
+                let energy: f32 = filter.iter().map(|&(bin, w)| power[bin] * w).sum();
+                energy.max(1e-10).ln()
+            })
+            .collect();
+        frames.push(CepstralFrame {
+            start: frame_start,
+            db: 20.0 * rms.log10(),
+            cepstra: dct(&log_mel),
+        });
+    }
+    frames
+}
+
+/// One enrolled person and the prints of their samples.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceProfile {
+    pub name: String,
+    pub prints: Vec<VoicePrint>,
+}
+
+/// The `--voice-profiles` file: enrolled people, in enrollment order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VoiceProfiles {
+    pub voices: Vec<VoiceProfile>,
+}
+
+impl VoiceProfiles {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read voice profiles from {}", path.display()))?;
+        serde_json::from_str(&raw)
+            .with_context(|| format!("invalid voice profiles file {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Adds `print` to `name`'s profile (names ignore case), creating it if needed.
+    pub fn enroll(&mut self, name: &str, print: VoicePrint) {
+        let name = name.trim();
+        match self
+            .voices
+            .iter_mut()
+            .find(|voice| voice.name.eq_ignore_ascii_case(name))
+        {
+            Some(voice) => voice.prints.push(print),
+            None => self.voices.push(VoiceProfile {
+                name: name.to_string(),
+                prints: vec![print],
+            }),
+        }
+    }
+
+    /// The index of the enrolled voice closest to `print`, with its similarity, if any is at
+    /// least `threshold`.
+    pub fn identify(&self, print: &VoicePrint, threshold: f32) -> Option<(usize, f32)> {
+        self.voices
+            .iter()
+            .enumerate()
+            .filter_map(|(index, voice)| {
+                let best = voice
+                    .prints
+                    .iter()
+                    .map(|enrolled| enrolled.similarity(print))
+                    .fold(f32::MIN, f32::max);
+                (best >= threshold).then_some((index, best))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular filters over 60 Hz..8 kHz as `(bin, weight)` lists.
+fn mel_filters() -> Vec<Vec<(usize, f32)>> {
+    let (low, high) = (hz_to_mel(60.0), hz_to_mel(SAMPLE_RATE / 2.0));
+    let bin_of = |mel: f32| mel_to_hz(mel) * FFT_LEN as f32 / SAMPLE_RATE;
+    let edges: Vec<f32> = (0..MEL_BANDS + 2)
+        .map(|i| bin_of(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32))
+        .collect();
+    edges
+        .windows(3)
+        .map(|edge| {
+            let (left, center, right) = (edge[0], edge[1], edge[2]);
+            (left.floor() as usize..=(right.ceil() as usize).min(FFT_LEN / 2))
+                .filter_map(|bin| {
+                    let x = bin as f32;
+                    let weight = if x <= center {
+                        (x - left) / (center - left)
+                    } else {
+                        (right - x) / (right - center)
+                    };
+                    (weight > 0.0).then_some((bin, weight))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// DCT-II of the log mel energies, without c0.
+fn dct(log_mel: &[f32]) -> [f32; CEPSTRA] {
+    let mut out = [0.0; CEPSTRA];
+    for (k, coefficient) in out.iter_mut().enumerate() {
+        let k = k + 1;
+        *coefficient = log_mel
+            .iter()
+            .enumerate()
+            .map(|(n, &e)| e * (PI * k as f32 * (n as f32 + 0.5) / log_mel.len() as f32).cos())
+            .sum();
+    }
+    out
+}
+
+/// In-place radix-2 FFT; the length must be a power of two.
+pub(super) fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -0,0 +1,88 @@
+//! Options shared by the pipeline and its front ends. They derive `ValueEnum` so the desktop
+//! CLI can take them as flags directly.
+
+use clap::ValueEnum;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputLanguage {
+    /// Show subtitles in Chinese (assumes the input is Chinese).
+    #[value(
+        name = "chinese",
+        alias = "zh",
+        alias = "zh-cn",
+        alias = "zh-hans",
+        alias = "cn",
+        alias = "original"
+    )]
+    Chinese,
+    /// Show subtitles in English.
+    #[value(name = "english", alias = "en")]
+    English,
+    /// Show subtitles in both Chinese and English (original + English translation).
+    #[value(name = "bilingual", alias = "bi", alias = "zh-en", alias = "en-zh")]
+    Bilingual,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum WhisperModelPreset {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    #[value(name = "large-v3", alias = "largev3", alias = "large_v3")]
+    LargeV3,
+}
+
+/// When the stabilizer commits words of a partial, so they stop changing on screen.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CommitPolicy {
+    /// Once a word has stayed the same for `--partial-stable-iters` updates in a row.
+    #[default]
+    Agreement,
+    /// Never while speaking: the whole partial may change until the final.
+    FinalOnly,
+}
+
+/// Inverse text normalization for finals (spelled-out numbers to digits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ItnLocale {
+    /// Leave text as decoded.
+    Off,
+    #[value(name = "en", alias = "english")]
+    English,
+}
+
+/// What a sink does with whisper's sound tags (`[Music]`, `(laughs)`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SoundTagMode {
+    /// Show them as decoded.
+    Keep,
+    /// Remove them.
+    Strip,
+    /// Replace each run of tags with `--sound-tag-glyph`.
+    Glyph,
+}
+
+/// How captions are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CaptionLayout {
+    /// Left to right in rows.
+    #[default]
+    Horizontal,
+    /// Top to bottom in columns read right to left, for Japanese (and other CJK) captions.
+    Vertical,
+}
+
+/// What headless (`--no-ui`) mode prints to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PrintMode {
+    /// Finals, one per line.
+    #[default]
+    Finals,
+    /// Partials too, redrawn in place on one line (carriage returns) until the final.
+    Partials,
+    /// Nothing, e.g. when only `--record` or `--report` output is wanted.
+    None,
+}
@@ -0,0 +1,10 @@
+//! The platform-independent half of `subtitles`: everything between 16 kHz mono samples and
+//! caption text. No ScreenCaptureKit or Tauri, so it also builds for iOS.
+
+pub mod audio;
+pub mod config;
+pub mod network;
+pub mod streaming;
+pub mod testing;
+pub mod text;
+pub mod transcribe;
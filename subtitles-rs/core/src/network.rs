@@ -0,0 +1,89 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+
+static LOCAL_ONLY: AtomicBool = AtomicBool::new(false);
+static AUDIT_LOG: Mutex<Option<File>> = Mutex::new(None);
+
+/// Enables or disables privacy mode for the whole process.
+///
+/// Once enabled, every component that would send captured audio (or text derived from it)
+/// off the machine must refuse to start. Model downloads are still allowed because they
+/// never carry user data.
+pub fn set_local_only(enabled: bool) {
+    LOCAL_ONLY.store(enabled, Ordering::SeqCst);
+}
+
+pub fn local_only() -> bool {
+    LOCAL_ONLY.load(Ordering::SeqCst)
+}
+
+/// Must be called by every network-using transcriber/translator/sink before it is constructed.
+pub fn ensure_egress_allowed(purpose: &str) -> anyhow::Result<()> {
+    if local_only() {
+        anyhow::bail!("{purpose} would send audio off this machine, but --local-only is set");
+    }
+    Ok(())
+}
+
+/// Starts appending one JSON line per outbound request to `path`.
+pub fn enable_audit_log(path: &Path) -> anyhow::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open audit log {}", path.display()))?;
+    *AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    tracing::info!("auditing outbound network calls to {}", path.display());
+    Ok(())
+}
+
+/// One outbound call, as recorded in the audit log.
+#[derive(Debug, Clone)]
+pub struct AuditRecord<'a> {
+    /// Why the call was made (e.g. `transcription`, `model-download`).
+    pub purpose: &'a str,
+    pub method: &'a str,
+    pub destination: &'a str,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// HTTP status, or `None` if the request never got a response.
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// Every HTTP/WebSocket engine or sink reports its calls here. No-op unless
+/// `--audit-log` is set.
+pub fn audit(record: AuditRecord<'_>) {
+    let mut guard = AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = guard.as_mut() else {
+        return;
+    };
+
+    let ts_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let line = serde_json::json!({
+        "ts_ms": ts_ms,
+        "purpose": record.purpose,
+        "method": record.method,
+        "destination": strip_query(record.destination),
+        "bytes_sent": record.bytes_sent,
+        "bytes_received": record.bytes_received,
+        "status": record.status,
+        "error": record.error,
+    });
+    if let Err(err) = writeln!(file, "{line}").and_then(|_| file.flush()) {
+        tracing::warn!("failed to write audit log: {err}");
+    }
+}
+
+fn strip_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
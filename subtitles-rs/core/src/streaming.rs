@@ -0,0 +1,1179 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::audio::{
+    end_silence_hops, voice_probability, LoudnessGate, VadFrames, VoiceSmoother,
+};
+use crate::config::CommitPolicy;
+use crate::text::joiner::is_cjk;
+use crate::transcribe::TranscriptSegment;
+
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingConfig {
+    pub sample_rate_hz: u32,
+    pub vad_threshold: f32,
+    pub vad_end_silence_s: f32,
+    pub max_segment_s: f32,
+    pub pre_roll_s: f32,
+    pub min_speech_ms: u64,
+    /// Audio an utterance needs before its first partial (below `min_speech_ms` has no effect).
+    /// Whisper guesses wildly at a word or two, so waiting a little avoids a flash of nonsense.
+    pub partial_onset_ms: u64,
+    pub asr_step_ms: u64,
+    pub max_window_s: f32,
+    /// Audio carried over into the next partial window once `max_window_s` is exceeded.
+    /// Values >= `max_window_s` make the window slide with every step.
+    pub partial_overlap_s: f32,
+    /// Force a final every this many seconds of continuous speech (0 = only on silence or
+    /// `max_segment_s`). The cut lands on the quietest nearby frame and the rest carries over.
+    pub final_interval_s: f32,
+}
+
+#[derive(Debug)]
+pub enum StreamingEvent {
+    Partial(SharedAudio),
+    Final(SharedAudio),
+    Reset,
+}
+
+/// Audio of a partial window or final: a range of the segmenter's utterance buffer, shared
+/// rather than copied. Cloning one is cheap too.
+#[derive(Clone)]
+pub struct SharedAudio {
+    buffer: Arc<[f32]>,
+    range: Range<usize>,
+}
+
+impl Deref for SharedAudio {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        &self.buffer[self.range.clone()]
+    }
+}
+
+impl From<Vec<f32>> for SharedAudio {
+    fn from(samples: Vec<f32>) -> Self {
+        let range = 0..samples.len();
+        Self {
+            buffer: samples.into(),
+            range,
+        }
+    }
+}
+
+impl fmt::Debug for SharedAudio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SharedAudio({} samples)", self.range.len())
+    }
+}
+
+/// The utterance being segmented, in one allocation that events share. Audio is written into
+/// it in place while no event holds it (the partial gate makes that the usual case); while one
+/// does, new audio waits in `tail`, and if it is needed before then the utterance moves to a
+/// fresh buffer. Finals take the buffer with them and the next utterance starts a new one.
+struct UtteranceBuffer {
+    buffer: Arc<[f32]>,
+    /// Samples of `buffer` in use.
+    len: usize,
+    tail: Vec<f32>,
+    /// Longest an utterance normally gets; fresh buffers grow towards it.
+    capacity: usize,
+}
+
+impl UtteranceBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            buffer: zeroed(capacity),
+            len: 0,
+            tail: Vec::new(),
+            capacity,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len + self.tail.len()
+    }
+
+    fn extend_from_slice(&mut self, samples: &[f32]) {
+        self.tail.extend_from_slice(samples);
+        self.write_tail();
+    }
+
+    /// Moves `tail` into the buffer if nothing else holds it; false if it couldn't.
+    fn write_tail(&mut self) -> bool {
+        let end = self.len + self.tail.len();
+        if end > self.buffer.len() {
+            return false;
+        }
+        let Some(buffer) = Arc::get_mut(&mut self.buffer) else {
+            return false;
+        };
+        buffer[self.len..end].copy_from_slice(&self.tail);
+        self.len = end;
+        self.tail.clear();
+        true
+    }
+
+    /// The whole utterance as one slice.
+    fn samples(&mut self) -> &[f32] {
+        if !self.tail.is_empty() && !self.write_tail() {
+            let needed = self.len();
+            let mut buffer = zeroed((2 * needed).min(self.capacity).max(needed));
+            let fresh = Arc::get_mut(&mut buffer).expect("a new buffer is unshared");
+            fresh[..self.len].copy_from_slice(&self.buffer[..self.len]);
+            fresh[self.len..self.len + self.tail.len()].copy_from_slice(&self.tail);
+            self.buffer = buffer;
+            self.len += self.tail.len();
+            self.tail.clear();
+        }
+        &self.buffer[..self.len]
+    }
+
+    fn share(&mut self, range: Range<usize>) -> SharedAudio {
+        self.samples();
+        SharedAudio {
+            buffer: self.buffer.clone(),
+            range,
+        }
+    }
+
+    /// Hands out `..cut` and keeps the rest as the start of a new utterance.
+    fn split(&mut self, cut: usize) -> SharedAudio {
+        let head = self.share(0..cut);
+        self.tail = self.buffer[cut..self.len].to_vec();
+        self.len = 0;
+        head
+    }
+
+    fn take(&mut self) -> SharedAudio {
+        let len = self.len();
+        self.split(len)
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.tail.clear();
+    }
+}
+
+fn zeroed(len: usize) -> Arc<[f32]> {
+    std::iter::repeat_n(0.0, len).collect()
+}
+
+/// Tracks whether a partial is queued or being decoded. The segmenter holds back new partials
+/// while one is in flight, so a slow decoder gets one wider window instead of a backlog.
+/// The decoder can also use it to ask for the utterance to be ended inside that partial.
+#[derive(Debug, Clone, Default)]
+pub struct PartialGate {
+    in_flight: Arc<AtomicBool>,
+    endpoint: Arc<Mutex<Option<usize>>>,
+}
+
+impl PartialGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claims the gate for a new partial; false if the previous one hasn't been released yet.
+    pub fn try_acquire(&self) -> bool {
+        self.in_flight
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Called once the decoder is done with (or has discarded) the in-flight partial.
+    pub fn release(&self) {
+        self.in_flight.store(false, Ordering::Release);
+    }
+
+    pub fn is_in_flight(&self) -> bool {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Asks the segmenter to finalize the utterance `samples` into the in-flight partial's audio.
+    /// Must be called before `release`.
+    pub fn end_at(&self, samples: usize) {
+        *self.endpoint.lock() = Some(samples);
+    }
+
+    fn take_endpoint(&self) -> Option<usize> {
+        self.endpoint.lock().take()
+    }
+}
+
+/// Seconds of decoded audio that must follow a sentence end before we trust it; whisper
+/// rewrites punctuation freely near the end of the window.
+const ENDPOINT_GUARD_S: f32 = 0.8;
+/// Segments above this no-speech probability are likely music or noise, not a sentence.
+const ENDPOINT_MAX_NO_SPEECH: f32 = 0.6;
+
+/// Picks a natural place to end the utterance from a timestamped partial decode: the end of
+/// the last segment that closes a sentence, once enough audio has been decoded past it.
+/// Returns seconds from the start of the decoded window.
+pub fn sentence_endpoint(segments: &[TranscriptSegment], window_s: f32) -> Option<f32> {
+    segments
+        .iter()
+        .rev()
+        .filter(|seg| seg.no_speech_prob <= ENDPOINT_MAX_NO_SPEECH)
+        .find(|seg| ends_sentence(&seg.text))
+        .map(|seg| seg.end_s)
+        .filter(|&end_s| end_s > 0.0 && window_s - end_s >= ENDPOINT_GUARD_S)
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end()
+        .trim_end_matches(['"', '\'', '”', '’', ')', '」'])
+        .ends_with(['.', '?', '!', '…', '。', '？', '！'])
+}
+
+/// New tokens the adaptive step aims to add to the caption per partial.
+const TOKENS_PER_STEP: f32 = 1.0;
+/// Weight of the newest partial in the speech rate and decode time estimates.
+const STEP_SMOOTHING: f32 = 0.3;
+/// Shorter windows say little about the speech rate (mostly onset and pre-roll).
+const MIN_RATE_WINDOW_S: f32 = 1.0;
+/// Steps are kept this much longer than a decode takes, so the decoder keeps up.
+const DECODE_HEADROOM: f32 = 1.25;
+
+/// Adapts `asr_step_ms` to how fast captions change (`--adaptive-asr-step`): fast speech gets
+/// partials more often, slow speech (and music, with nothing to caption) less often. The step
+/// never drops below what decoding a window takes, since partials issued faster than that are
+/// skipped by the partial gate anyway.
+#[derive(Debug, Clone)]
+pub struct AsrStepController {
+    /// Used until the speech rate is known.
+    initial_ms: u64,
+    min_ms: u64,
+    max_ms: u64,
+    tokens_per_s: Option<f32>,
+    decode_ms: Option<f32>,
+}
+
+impl AsrStepController {
+    pub fn new(initial_ms: u64, min_ms: u64, max_ms: u64) -> Self {
+        let min_ms = min_ms.max(1);
+        Self {
+            initial_ms,
+            min_ms,
+            max_ms: max_ms.max(min_ms),
+            tokens_per_s: None,
+            decode_ms: None,
+        }
+    }
+
+    /// Records a partial: its hypothesis, the seconds of audio decoded and how long decoding
+    /// took. Returns the step to use from now on.
+    pub fn update(&mut self, hypothesis: &str, window_s: f32, decode: Duration) -> u64 {
+        let decode_ms = decode.as_secs_f32() * 1000.0;
+        self.decode_ms = Some(smooth(self.decode_ms, decode_ms));
+        if window_s >= MIN_RATE_WINDOW_S {
+            let rate = caption_tokens(hypothesis) as f32 / window_s;
+            self.tokens_per_s = Some(smooth(self.tokens_per_s, rate));
+        }
+        self.step_ms()
+    }
+
+    pub fn step_ms(&self) -> u64 {
+        let floor = (self.decode_ms.unwrap_or(0.0) * DECODE_HEADROOM) as u64;
+        let floor = floor.clamp(self.min_ms, self.max_ms);
+        match self.tokens_per_s {
+            Some(rate) if rate > 0.0 => {
+                let step = (1000.0 * TOKENS_PER_STEP / rate) as u64;
+                step.clamp(floor, self.max_ms)
+            }
+            Some(_) => self.max_ms,
+            None => self.initial_ms.clamp(floor, self.max_ms),
+        }
+    }
+}
+
+fn smooth(average: Option<f32>, value: f32) -> f32 {
+    average.map_or(value, |average| average + STEP_SMOOTHING * (value - average))
+}
+
+/// Words, counting each character of unspaced (CJK) text as one.
+fn caption_tokens(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|&c| is_cjk(c) && c.is_alphanumeric())
+                .count()
+                .max(1)
+        })
+        .sum()
+}
+
+pub struct StreamingSegmenter {
+    cfg: StreamingConfig,
+    /// Audio the VAD judges at once, and how far it moves between judgements.
+    frame_size: usize,
+    hop_size: usize,
+    end_silence_frames: usize,
+    min_speech_samples: usize,
+    /// At least `min_speech_samples`.
+    onset_samples: usize,
+    max_segment_samples: usize,
+    final_interval_samples: usize,
+    pre_roll_samples: usize,
+    asr_step_samples: usize,
+    max_window_samples: usize,
+    overlap_samples: usize,
+
+    stash: Vec<f32>,
+    stash_pos: usize,
+
+    in_speech: bool,
+    voice: VoiceSmoother,
+    silent_frames: usize,
+    pre_roll: VecDeque<f32>,
+    utterance: UtteranceBuffer,
+    last_asr_samples: usize,
+    window_start: usize,
+    /// Bumped whenever an utterance ends, so stale endpoint requests can be recognised.
+    utterance_seq: u64,
+    /// Utterance and window start of the partial currently held by the gate.
+    in_flight_window: Option<(u64, usize)>,
+    partial_gate: Option<PartialGate>,
+    /// Samples consumed into frames so far; the utterance always ends at this position.
+    framed_samples: u64,
+    /// Start positions (in input samples) of the finals from the last `push_audio`.
+    final_starts: VecDeque<u64>,
+    /// Same for the partials' windows.
+    partial_starts: VecDeque<u64>,
+    loudness_gate: Option<LoudnessGate>,
+    /// Audio before the pre-roll that warm partials are decoded with (0 = off).
+    warm_context_samples: usize,
+    /// The latest audio, long enough to hold the context and the pre-roll.
+    lookback: VecDeque<f32>,
+    /// Context for the current utterance's warm partials; empty when it started cold.
+    warm_context: Vec<f32>,
+    /// Samples since the last final ended, if there was one.
+    since_final: Option<usize>,
+}
+
+impl StreamingSegmenter {
+    pub fn new(cfg: StreamingConfig) -> Self {
+        let frame_dur = Duration::from_millis(20);
+        let frame_size = ((cfg.sample_rate_hz as f32) * frame_dur.as_secs_f32()).round() as usize;
+
+        let end_silence_frames = end_silence_hops(cfg.vad_end_silence_s, frame_dur);
+
+        let max_segment_samples = ((cfg.max_segment_s * cfg.sample_rate_hz as f32).max(1.0))
+            .round() as usize;
+        let final_interval_samples =
+            ((cfg.final_interval_s * cfg.sample_rate_hz as f32).max(0.0)).round() as usize;
+        let pre_roll_samples =
+            ((cfg.pre_roll_s * cfg.sample_rate_hz as f32).max(0.0)).round() as usize;
+
+        let min_speech_samples =
+            ((cfg.min_speech_ms as f32 / 1000.0) * cfg.sample_rate_hz as f32)
+                .round()
+                .max(1.0) as usize;
+
+        let onset_samples = ((cfg.partial_onset_ms as f32 / 1000.0) * cfg.sample_rate_hz as f32)
+            .round() as usize;
+        let onset_samples = onset_samples.max(min_speech_samples);
+
+        let asr_step_samples = asr_step_samples(cfg.asr_step_ms, cfg.sample_rate_hz);
+
+        let mut max_window_samples =
+            ((cfg.max_window_s * cfg.sample_rate_hz as f32).max(0.0)).round() as usize;
+        if max_window_samples == 0 {
+            max_window_samples = max_segment_samples;
+        }
+        max_window_samples = max_window_samples.min(max_segment_samples);
+
+        let overlap_samples = ((cfg.partial_overlap_s * cfg.sample_rate_hz as f32).max(0.0))
+            .round() as usize;
+        let overlap_samples = overlap_samples.min(max_window_samples);
+
+        Self {
+            cfg,
+            frame_size: frame_size.max(1),
+            hop_size: frame_size.max(1),
+            end_silence_frames,
+            min_speech_samples,
+            onset_samples,
+            max_segment_samples,
+            final_interval_samples,
+            pre_roll_samples,
+            asr_step_samples,
+            max_window_samples,
+            overlap_samples,
+            stash: Vec::new(),
+            stash_pos: 0,
+            in_speech: false,
+            voice: VoiceSmoother::new(frame_dur),
+            silent_frames: 0,
+            pre_roll: VecDeque::new(),
+            // Room for the longest utterance plus what a split carries over.
+            utterance: UtteranceBuffer::new(max_segment_samples + pre_roll_samples),
+            last_asr_samples: 0,
+            window_start: 0,
+            utterance_seq: 0,
+            in_flight_window: None,
+            partial_gate: None,
+            framed_samples: 0,
+            final_starts: VecDeque::new(),
+            partial_starts: VecDeque::new(),
+            loudness_gate: None,
+            warm_context_samples: 0,
+            lookback: VecDeque::new(),
+            warm_context: Vec::new(),
+            since_final: None,
+        }
+    }
+
+    /// Skip partials while `gate` is held by the decoder. Skipped steps aren't lost: the next
+    /// partial is scheduled as soon as the gate is free and covers all audio since the last one.
+    pub fn with_partial_gate(mut self, gate: PartialGate) -> Self {
+        self.partial_gate = Some(gate);
+        self
+    }
+
+    /// Judges frames by loudness instead of `vad_threshold`.
+    pub fn with_loudness_gate(mut self, gate: LoudnessGate) -> Self {
+        self.loudness_gate = Some(gate);
+        self
+    }
+
+    /// Judges `frame_ms` of audio every `hop_ms` instead of back-to-back 20 ms frames.
+    pub fn with_vad_frames(mut self, frame_ms: u32, hop_ms: u32) -> Self {
+        let frames = VadFrames::new(frame_ms, hop_ms, self.cfg.sample_rate_hz);
+        self.frame_size = frames.frame_size;
+        self.hop_size = frames.hop_size;
+        self.end_silence_frames = end_silence_hops(self.cfg.vad_end_silence_s, frames.hop);
+        self.voice = VoiceSmoother::new(frames.hop);
+        self
+    }
+
+    /// When speech resumes within `context_s` of the last final, decode its first partials with
+    /// the `context_s` of audio before the pre-roll, from the first step on rather than after
+    /// the partial onset. The context carries the end of the previous sentence, which keeps
+    /// whisper from guessing wildly at a word or two; its words are for the decoder's benefit
+    /// and come back in the hypothesis (see `Stabilizer::with_carried_context`).
+    pub fn with_warm_partials(mut self, context_s: f32) -> Self {
+        self.warm_context_samples =
+            (context_s * self.cfg.sample_rate_hz as f32).max(0.0).round() as usize;
+        self
+    }
+
+    /// Changes how often partials are decoded; the next one is due a new step after the last.
+    pub fn set_asr_step_ms(&mut self, asr_step_ms: u64) {
+        self.cfg.asr_step_ms = asr_step_ms;
+        self.asr_step_samples = asr_step_samples(asr_step_ms, self.cfg.sample_rate_hz);
+    }
+
+    pub fn push_audio(&mut self, audio: &[f32]) -> Vec<StreamingEvent> {
+        self.stash.extend_from_slice(audio);
+        self.final_starts.clear();
+        self.partial_starts.clear();
+
+        let mut out = Vec::new();
+        if let Some(final_audio) = self.apply_requested_endpoint() {
+            out.push(StreamingEvent::Final(final_audio));
+        }
+        while self.stash.len().saturating_sub(self.stash_pos) >= self.hop_size {
+            let start = self.stash_pos;
+            let end = self.stash_pos + self.hop_size;
+            let window = &self.stash[end.saturating_sub(self.frame_size)..end];
+            let frame = &self.stash[start..end];
+            self.stash_pos = end;
+            self.framed_samples += self.hop_size as u64;
+
+            let probability = match self.loudness_gate.as_mut() {
+                Some(gate) => f32::from(u8::from(gate.is_voice(frame, self.frame_size))),
+                None => voice_probability(rms(window), self.cfg.vad_threshold),
+            };
+            let is_voice = self.voice.update(probability);
+            if self.warm_context_samples > 0 {
+                push_pre_roll(
+                    &mut self.lookback,
+                    self.warm_context_samples + self.pre_roll_samples,
+                    frame,
+                );
+            }
+
+            if self.in_speech {
+                self.utterance.extend_from_slice(frame);
+                if is_voice {
+                    self.silent_frames = 0;
+                } else {
+                    self.silent_frames += 1;
+                }
+
+                let reached_silence = self.silent_frames >= self.end_silence_frames;
+                let reached_max = self.utterance.len() >= self.max_segment_samples;
+
+                if reached_silence || reached_max {
+                    if self.utterance.len() >= self.min_speech_samples {
+                        self.record_final_start();
+                        out.push(StreamingEvent::Final(self.flush_utterance()));
+                    } else {
+                        self.reset_state();
+                        out.push(StreamingEvent::Reset);
+                    }
+                    continue;
+                }
+
+                if self.final_interval_samples > 0
+                    && self.utterance.len() >= self.final_interval_samples
+                {
+                    let cut = self.quietest_cut();
+                    if let Some(final_audio) = self.split_utterance(cut) {
+                        out.push(StreamingEvent::Final(final_audio));
+                        continue;
+                    }
+                }
+
+                // No partial yet in this utterance: hold off until the onset has passed, unless
+                // there is context to decode it with.
+                let warm = self.is_warm();
+                let needed = if warm {
+                    0
+                } else if self.last_asr_samples == 0 {
+                    self.onset_samples
+                } else {
+                    self.min_speech_samples
+                };
+                if self.utterance.len() >= needed
+                    && self.utterance.len().saturating_sub(self.last_asr_samples)
+                        >= self.asr_step_samples
+                    && self.partial_gate.as_ref().is_none_or(PartialGate::try_acquire)
+                {
+                    self.last_asr_samples = self.utterance.len();
+                    if warm {
+                        out.push(StreamingEvent::Partial(self.warm_window_audio()));
+                        // Endpoints are offsets into the window, which here starts before
+                        // the utterance.
+                        self.in_flight_window = None;
+                        self.partial_starts.push_back(
+                            self.framed_samples
+                                - (self.utterance.len() + self.warm_context.len()) as u64,
+                        );
+                    } else {
+                        out.push(StreamingEvent::Partial(self.window_audio()));
+                        self.in_flight_window = Some((self.utterance_seq, self.window_start));
+                        self.partial_starts.push_back(
+                            self.framed_samples
+                                - (self.utterance.len() - self.window_start) as u64,
+                        );
+                    }
+                }
+            } else {
+                push_pre_roll(&mut self.pre_roll, self.pre_roll_samples, frame);
+                if let Some(since_final) = self.since_final.as_mut() {
+                    *since_final += self.hop_size;
+                }
+                if is_voice {
+                    self.take_warm_context();
+                    self.in_speech = true;
+                    self.silent_frames = 0;
+                    self.last_asr_samples = 0;
+                    let (front, back) = self.pre_roll.as_slices();
+                    self.utterance.extend_from_slice(front);
+                    self.utterance.extend_from_slice(back);
+                    self.pre_roll.clear();
+                }
+            }
+        }
+
+        // keep stash from growing without bound, less what the next window looks back on
+        if self.stash_pos > self.frame_size * 128 {
+            let keep = self.frame_size - self.hop_size;
+            self.stash.drain(..self.stash_pos - keep);
+            self.stash_pos = keep;
+        }
+
+        out
+    }
+
+    /// Ends the utterance where the decoder found a sentence boundary, keeping the audio after
+    /// it as the start of the next utterance. Requests for utterances that already ended (or
+    /// that would leave less than `min_speech` in the final) are dropped.
+    fn apply_requested_endpoint(&mut self) -> Option<SharedAudio> {
+        let samples = self.partial_gate.as_ref()?.take_endpoint()?;
+        let (seq, window_start) = self.in_flight_window.take()?;
+        if !self.in_speech || seq != self.utterance_seq {
+            return None;
+        }
+
+        self.split_utterance(window_start + samples)
+    }
+
+    /// Where the next `Final` returned by the last `push_audio` starts, counted in samples
+    /// pushed since the segmenter was created. Call once per `Final`, in order.
+    pub fn take_final_start(&mut self) -> Option<u64> {
+        self.final_starts.pop_front()
+    }
+
+    /// Where the window of the next `Partial` returned by the last `push_audio` starts, on the
+    /// same clock as `take_final_start`. Call once per `Partial`, in order.
+    pub fn take_partial_start(&mut self) -> Option<u64> {
+        self.partial_starts.pop_front()
+    }
+
+    /// Records where the current utterance (and so a final cut from its front) starts.
+    fn record_final_start(&mut self) {
+        let start = self.framed_samples - self.utterance.len() as u64;
+        self.final_starts.push_back(start);
+    }
+
+    /// Finalizes `utterance[..cut]` and keeps the rest as the start of a new utterance.
+    fn split_utterance(&mut self, cut: usize) -> Option<SharedAudio> {
+        if cut < self.min_speech_samples || cut >= self.utterance.len() {
+            return None;
+        }
+        self.record_final_start();
+
+        let final_audio = self.utterance.split(cut);
+        self.warm_context.clear();
+        self.utterance_seq += 1;
+        self.last_asr_samples = 0;
+        self.window_start = 0;
+        Some(final_audio)
+    }
+
+    /// Start of the quietest frame in the last `FINAL_CUT_SEARCH_S` of the utterance, so
+    /// interval finals are less likely to split a word.
+    fn quietest_cut(&mut self) -> usize {
+        let hop = self.hop_size;
+        let search =
+            (FINAL_CUT_SEARCH_S * self.cfg.sample_rate_hz as f32).round() as usize;
+        let frames = (search / hop).max(1);
+        let utterance = self.utterance.samples();
+        let len = utterance.len();
+        (1..=frames)
+            .filter_map(|i| len.checked_sub(i * hop))
+            .min_by(|&a, &b| {
+                let rms_a = rms(&utterance[a..a + hop]);
+                let rms_b = rms(&utterance[b..b + hop]);
+                rms_a.total_cmp(&rms_b)
+            })
+            .unwrap_or(len)
+    }
+
+    fn flush_utterance(&mut self) -> SharedAudio {
+        self.since_final = Some(0);
+        self.utterance_seq += 1;
+        self.in_speech = false;
+        self.silent_frames = 0;
+        self.pre_roll.clear();
+        self.last_asr_samples = 0;
+        self.window_start = 0;
+        self.warm_context.clear();
+        self.utterance.take()
+    }
+
+    fn reset_state(&mut self) {
+        self.utterance_seq += 1;
+        self.in_speech = false;
+        self.silent_frames = 0;
+        self.pre_roll.clear();
+        self.last_asr_samples = 0;
+        self.window_start = 0;
+        self.warm_context.clear();
+        self.utterance.clear();
+    }
+
+    /// Partials of an utterance that started warm are decoded with its context until the
+    /// utterance is long enough to have had a partial of its own.
+    fn is_warm(&self) -> bool {
+        !self.warm_context.is_empty() && self.utterance.len() < self.onset_samples
+    }
+
+    /// At the start of an utterance: the audio before its pre-roll, if the last final is
+    /// recent enough to be in it.
+    fn take_warm_context(&mut self) {
+        self.warm_context.clear();
+        if self
+            .since_final
+            .is_none_or(|since_final| since_final >= self.warm_context_samples)
+        {
+            return;
+        }
+        let context_end = self.lookback.len().saturating_sub(self.pre_roll.len());
+        let context_start = context_end.saturating_sub(self.warm_context_samples);
+        self.warm_context
+            .extend(self.lookback.range(context_start..context_end));
+    }
+
+    fn warm_window_audio(&mut self) -> SharedAudio {
+        let mut window = Vec::with_capacity(self.warm_context.len() + self.utterance.len());
+        window.extend_from_slice(&self.warm_context);
+        window.extend_from_slice(self.utterance.samples());
+        window.into()
+    }
+
+    /// Partial windows are anchored: they grow from `window_start` until they exceed the
+    /// maximum window, then restart with the last `overlap_samples` of context. Keeping the
+    /// start fixed between jumps lets the stabilizer see the same sentence start every step.
+    fn window_audio(&mut self) -> SharedAudio {
+        let len = self.utterance.len();
+        if len - self.window_start > self.max_window_samples {
+            self.window_start = len.saturating_sub(self.overlap_samples);
+        }
+        self.utterance.share(self.window_start..len)
+    }
+}
+
+/// How far back from the interval boundary to look for a quiet frame to cut at.
+const FINAL_CUT_SEARCH_S: f32 = 0.5;
+
+/// Shortest phrase, in tokens, that `finalize` collapses when the final repeats it.
+const MIN_REPEATED_PHRASE: usize = 3;
+
+/// How many trailing committed tokens are searched for anywhere in a new hypothesis when it
+/// doesn't start with the committed tail (sliding windows, trimmed history).
+const MAX_UNANCHORED_OVERLAP: usize = 8;
+
+pub struct Stabilizer {
+    stable_required: usize,
+    policy: CommitPolicy,
+    /// Maximum committed tokens kept (0 = unbounded). Older tokens scroll off the caption.
+    max_committed: usize,
+    committed: Vec<String>,
+    /// Where each committed token's audio is estimated to end (input samples), when the
+    /// hypothesis that committed it came with its range.
+    committed_at: Vec<Option<u64>>,
+    pending_prev: Vec<String>,
+    pending_counts: Vec<usize>,
+    /// Times a new hypothesis (or the final) no longer agreed with committed text, since the
+    /// last `take_retractions`.
+    retractions: u64,
+    carries_context: bool,
+    /// Whether finals keep the committed words they cover (`with_kept_committed_words`).
+    keeps_committed: bool,
+    /// The end of the last final, which warm partials of the next utterance start with.
+    context: Vec<String>,
+    /// Where the last final's audio ended, when known: windows starting there or later are
+    /// past the warm partials, and have no context to strip.
+    context_end: Option<u64>,
+}
+
+impl Stabilizer {
+    pub fn new(stable_required: usize) -> Self {
+        Self {
+            stable_required: stable_required.max(1),
+            policy: CommitPolicy::Agreement,
+            max_committed: 0,
+            committed: Vec::new(),
+            committed_at: Vec::new(),
+            pending_prev: Vec::new(),
+            pending_counts: Vec::new(),
+            retractions: 0,
+            carries_context: false,
+            keeps_committed: false,
+            context: Vec::new(),
+            context_end: None,
+        }
+    }
+
+    /// Changes how many updates a token must survive; takes effect on the next update.
+    pub fn set_stable_required(&mut self, stable_required: usize) {
+        self.stable_required = stable_required.max(1);
+    }
+
+    /// Changes when tokens are committed; what is already committed stays.
+    pub fn set_policy(&mut self, policy: CommitPolicy) {
+        self.policy = policy;
+    }
+
+    /// Retractions since the last call: updates whose hypothesis, or finals whose text, no
+    /// longer contained the committed tail. Each one is committed text the viewer saw that
+    /// turned out wrong, so this is the flicker a lower `stable_required` costs.
+    pub fn take_retractions(&mut self) -> u64 {
+        std::mem::take(&mut self.retractions)
+    }
+
+    /// Keeps only the newest `max_tokens` committed tokens (0 = unbounded), so long utterances
+    /// scroll instead of growing into a paragraph and the overlap scan stays cheap.
+    pub fn with_max_committed(mut self, max_tokens: usize) -> Self {
+        self.max_committed = max_tokens;
+        self
+    }
+
+    /// For partials decoded with the end of the previous utterance in front
+    /// (`StreamingSegmenter::with_warm_partials`): hypotheses that start with the last final's
+    /// words have them removed, until one (`update_at`) is decoded from audio after the final.
+    pub fn with_carried_context(mut self, carries_context: bool) -> Self {
+        self.carries_context = carries_context;
+        self
+    }
+
+    /// Finals keep the committed words they cover where they heard them differently, so the
+    /// caption doesn't change under the viewer; matching words still take the final's casing
+    /// and punctuation. Off by default: the final decode, which heard the whole utterance, is
+    /// usually the better text, and it is what gets recorded.
+    pub fn with_kept_committed_words(mut self, keeps_committed: bool) -> Self {
+        self.keeps_committed = keeps_committed;
+        self
+    }
+
+    pub fn max_committed(&self) -> usize {
+        self.max_committed
+    }
+
+    /// The committed (stable) tail currently shown, oldest first.
+    pub fn committed(&self) -> &[String] {
+        &self.committed
+    }
+
+    pub fn reset(&mut self) {
+        self.committed.clear();
+        self.committed_at.clear();
+        self.pending_prev.clear();
+        self.pending_counts.clear();
+        self.context.clear();
+        self.context_end = None;
+    }
+
+    pub fn update(&mut self, hypothesis: &str) -> (String, String) {
+        self.update_at(hypothesis, None)
+    }
+
+    /// Like `update`, for a hypothesis decoded from the `audio` range of input samples (the
+    /// segmenter's clock), so `finalize_at` can tell which committed words its final covers.
+    pub fn update_at(&mut self, hypothesis: &str, audio: Option<Range<u64>>) -> (String, String) {
+        if audio
+            .as_ref()
+            .zip(self.context_end)
+            .is_some_and(|(audio, end)| audio.start >= end)
+        {
+            self.context.clear();
+        }
+        let tokens = tokenize(hypothesis);
+        if tokens.is_empty() {
+            return (tokens_to_text(&self.committed), String::new());
+        }
+
+        let total = tokens.len();
+        // A warm window starts with the end of the previous sentence.
+        let tokens = strip_context(&self.context, tokens);
+        if tokens.is_empty() {
+            // The window hasn't decoded past the previous sentence yet.
+            return (tokens_to_text(&self.committed), String::new());
+        }
+        let mut pending = match strip_committed_overlap(&self.committed, &tokens) {
+            Some(pending) => pending,
+            None => {
+                self.retractions += 1;
+                tokens
+            }
+        };
+        // Index in the hypothesis of the first pending token.
+        let skipped = total - pending.len();
+        let lcp = lcp_len(&self.pending_prev, &pending);
+
+        let mut counts = Vec::with_capacity(pending.len());
+        for i in 0..pending.len() {
+            if i < lcp {
+                counts.push(self.pending_counts.get(i).copied().unwrap_or(0) + 1);
+            } else {
+                counts.push(1);
+            }
+        }
+
+        let mut commit_len = 0usize;
+        for &count in &counts {
+            if self.policy == CommitPolicy::FinalOnly {
+                break;
+            }
+            if count >= self.stable_required {
+                commit_len += 1;
+            } else {
+                break;
+            }
+        }
+
+        if commit_len > 0 {
+            self.committed
+                .extend(pending.iter().take(commit_len).cloned());
+            // Words are assumed evenly spread over the window; close enough to tell which side
+            // of a final's cut they fall on.
+            self.committed_at
+                .extend((skipped..skipped + commit_len).map(|index| {
+                    audio.as_ref().map(|audio| {
+                        audio.start + (audio.end - audio.start) * (index as u64 + 1) / total as u64
+                    })
+                }));
+            pending = pending[commit_len..].to_vec();
+            counts = counts[commit_len..].to_vec();
+
+            if self.max_committed > 0 && self.committed.len() > self.max_committed {
+                let excess = self.committed.len() - self.max_committed;
+                self.committed.drain(..excess);
+                self.committed_at.drain(..excess);
+            }
+        }
+
+        self.pending_prev = pending.clone();
+        self.pending_counts = counts;
+
+        (tokens_to_text(&self.committed), tokens_to_text(&pending))
+    }
+
+    pub fn finalize(&mut self, hypothesis: &str) -> String {
+        self.finalize_at(hypothesis, None)
+    }
+
+    /// The final text for the utterance decoded from `audio`. The final is compared with the
+    /// committed words it covers: one that disagrees with most of them counts as a retraction.
+    /// With `with_kept_committed_words`, where the final heard a committed word differently or
+    /// not at all, the committed word stays; matching words take the final's casing and
+    /// punctuation, and words the final adds are kept. Committed words from audio after the
+    /// final's end (the segmenter cut the utterance before them) stay committed for the next
+    /// utterance. Without `audio`, every committed word is covered.
+    pub fn finalize_at(&mut self, hypothesis: &str, audio: Option<Range<u64>>) -> String {
+        // What the last partial showed for this utterance: committed words plus the rest.
+        let heard: Vec<String> = self
+            .committed
+            .iter()
+            .chain(&self.pending_prev)
+            .cloned()
+            .collect();
+        let tokens = drop_repeats(&heard, tokenize(hypothesis));
+
+        let covered = match &audio {
+            Some(audio) => self
+                .committed_at
+                .iter()
+                .position(|at| at.is_some_and(|at| at > audio.end))
+                .unwrap_or(self.committed.len()),
+            None => self.committed.len(),
+        };
+        let later = self.committed.split_off(covered);
+        let later_at = self.committed_at.split_off(covered);
+        let tokens = match reconcile(&self.committed, &tokens) {
+            Some(reconciled) if self.keeps_committed => reconciled,
+            Some(_) => tokens,
+            None => {
+                self.retractions += 1;
+                tokens
+            }
+        };
+        self.reset();
+        self.committed = later;
+        self.committed_at = later_at;
+        if self.carries_context {
+            let start = tokens.len().saturating_sub(MAX_UNANCHORED_OVERLAP);
+            self.context = tokens[start..].to_vec();
+            self.context_end = audio.map(|audio| audio.end);
+        }
+        tokens_to_text(&tokens)
+    }
+}
+
+/// How the stabilizer splits a hypothesis into tokens (whitespace-separated words).
+pub fn tokenize(s: &str) -> Vec<String> {
+    s.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+fn tokens_to_text(tokens: &[String]) -> String {
+    if tokens.is_empty() {
+        String::new()
+    } else {
+        tokens.join(" ")
+    }
+}
+
+/// The tokens after the committed tail, or `None` when `tokens` don't contain it.
+fn strip_committed_overlap(committed: &[String], tokens: &[String]) -> Option<Vec<String>> {
+    if committed.is_empty() {
+        return Some(tokens.to_vec());
+    }
+    if tokens.is_empty() {
+        return None;
+    }
+
+    let max_overlap = committed.len().min(tokens.len());
+    for k in (1..=max_overlap).rev() {
+        if committed[committed.len() - k..] == tokens[..k] {
+            return Some(tokens[k..].to_vec());
+        }
+    }
+
+    // The hypothesis may still start with tokens that have already scrolled out of the
+    // committed window; find where the committed tail reappears instead.
+    let max_unanchored = max_overlap.min(MAX_UNANCHORED_OVERLAP);
+    for k in (2.min(max_unanchored)..=max_unanchored).rev() {
+        let tail = &committed[committed.len() - k..];
+        if let Some(pos) = (1..=tokens.len() - k).rev().find(|&p| tokens[p..p + k] == *tail) {
+            return Some(tokens[pos + k..].to_vec());
+        }
+    }
+
+    None
+}
+
+/// `tokens` without the words at its start that end the `context`, compared ignoring case and
+/// punctuation. Only the start counts: the new sentence may well repeat the previous one's
+/// words, and those are new speech.
+fn strip_context(context: &[String], tokens: Vec<String>) -> Vec<String> {
+    let context_keys: Vec<String> = context.iter().map(|token| token_key(token)).collect();
+    let keys: Vec<String> = tokens.iter().map(|token| token_key(token)).collect();
+    let max_overlap = context_keys.len().min(keys.len());
+    match (1..=max_overlap)
+        .rev()
+        .find(|&k| context_keys[context_keys.len() - k..] == keys[..k])
+    {
+        Some(k) => tokens[k..].to_vec(),
+        None => tokens,
+    }
+}
+
+/// `final_tokens` with the `shown` (committed) words kept where the two disagree, or `None` when
+/// they disagree on more than half the shown words. The shown words are aligned to any stretch
+/// of the final (it may also cover words that scrolled out of the committed history, or came
+/// after them), by edit distance ignoring case and punctuation.
+fn reconcile(shown: &[String], final_tokens: &[String]) -> Option<Vec<String>> {
+    if shown.is_empty() {
+        return Some(final_tokens.to_vec());
+    }
+    let shown_keys: Vec<String> = shown.iter().map(|token| token_key(token)).collect();
+    let final_keys: Vec<String> = final_tokens.iter().map(|token| token_key(token)).collect();
+    let (m, n) = (shown.len(), final_tokens.len());
+
+    // cost[i][j]: shown[..i] aligned to final[..j], with final words before the alignment
+    // free.
+    let mut cost = vec![vec![0usize; n + 1]; m + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for i in 1..=m {
+        for j in 1..=n {
+            let step = usize::from(shown_keys[i - 1] != final_keys[j - 1]);
+            cost[i][j] = (cost[i - 1][j - 1] + step)
+                .min(cost[i - 1][j] + 1)
+                .min(cost[i][j - 1] + 1);
+        }
+    }
+    // Final words after the alignment are free too.
+    let end = (0..=n).min_by_key(|&j| (cost[m][j], std::cmp::Reverse(j)))?;
+
+    let (mut i, mut j) = (m, end);
+    let mut contradictions = 0;
+    let mut merged = Vec::new();
+    while i > 0 {
+        // On ties a match beats a word the final added, which beats a substitution.
+        if j > 0 {
+            let same = shown_keys[i - 1] == final_keys[j - 1];
+            if same && cost[i][j] == cost[i - 1][j - 1] {
+                merged.push(final_tokens[j - 1].clone());
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+            if cost[i][j] == cost[i][j - 1] + 1 {
+                merged.push(final_tokens[j - 1].clone());
+                j -= 1;
+                continue;
+            }
+            if !same && cost[i][j] == cost[i - 1][j - 1] + 1 {
+                contradictions += 1;
+                merged.push(shown[i - 1].clone());
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        // Missing from the final.
+        contradictions += 1;
+        merged.push(shown[i - 1].clone());
+        i -= 1;
+    }
+    if contradictions * 2 > m {
+        return None;
+    }
+    merged.reverse();
+    Some([&final_tokens[..j], &merged, &final_tokens[end..]].concat())
+}
+
+/// A token as compared between decodes: lowercase letters and digits, or the token itself
+/// when it has none.
+fn token_key(token: &str) -> String {
+    let word: String = token
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if word.is_empty() {
+        token.to_string()
+    } else {
+        word
+    }
+}
+
+/// Drops the second copy of a phrase the final repeats back to back (`a b c a b c`) when the
+/// partials for the same audio had it only once: re-decoding a window that was force-flushed
+/// at `max_segment_s` sometimes doubles a sentence. A repeat the partials had too is kept,
+/// since people do say things twice. Case and punctuation are ignored when comparing.
+fn drop_repeats(heard: &[String], mut tokens: Vec<String>) -> Vec<String> {
+    let heard: Vec<String> = heard.iter().map(|token| token_key(token)).collect();
+    let mut keys: Vec<String> = tokens.iter().map(|token| token_key(token)).collect();
+    'collapse: loop {
+        for len in (MIN_REPEATED_PHRASE..=keys.len() / 2).rev() {
+            for start in 0..=keys.len() - 2 * len {
+                let (phrase, rest) = keys[start..].split_at(len);
+                if phrase != &rest[..len] || !heard.windows(len).any(|window| window == phrase) {
+                    continue;
+                }
+                let doubled = [phrase, phrase].concat();
+                if heard.windows(2 * len).any(|window| window == doubled.as_slice()) {
+                    continue;
+                }
+                tokens.drain(start + len..start + 2 * len);
+                keys.drain(start + len..start + 2 * len);
+                continue 'collapse;
+            }
+        }
+        return tokens;
+    }
+}
+
+fn asr_step_samples(asr_step_ms: u64, sample_rate_hz: u32) -> usize {
+    ((asr_step_ms as f32 / 1000.0) * sample_rate_hz as f32)
+        .round()
+        .max(1.0) as usize
+}
+
+fn lcp_len(a: &[String], b: &[String]) -> usize {
+    let mut n = 0usize;
+    let len = a.len().min(b.len());
+    while n < len && a[n] == b[n] {
+        n += 1;
+    }
+    n
+}
+
+fn push_pre_roll(pre_roll: &mut VecDeque<f32>, pre_roll_samples: usize, frame: &[f32]) {
+    if pre_roll_samples == 0 {
+        return;
+    }
+
+    for &s in frame {
+        pre_roll.push_back(s);
+    }
+    while pre_roll.len() > pre_roll_samples {
+        pre_roll.pop_front();
+    }
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+
+    let mut sum = 0.0f32;
+    for &s in frame {
+        sum += s * s;
+    }
+    (sum / (frame.len() as f32)).sqrt()
+}
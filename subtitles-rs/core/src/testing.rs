@@ -0,0 +1,159 @@
+//! Synthetic audio for exercising the VAD and segmenters without recordings.
+//!
+//! Everything here is deterministic for a given seed, so tests can assert exact
+//! segment boundaries.
+
+/// Small xorshift64* generator; good enough for noise and for picking test cases.
+#[derive(Debug, Clone)]
+pub struct TestRng(u64);
+
+impl TestRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in `[-1.0, 1.0)`.
+    pub fn next_f32(&mut self) -> f32 {
+        ((self.next_u64() >> 40) as f32 / (1u64 << 23) as f32) - 1.0
+    }
+
+    /// Uniform in `[lo, hi)`.
+    pub fn range_f32(&mut self, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * (self.next_f32() * 0.5 + 0.5)
+    }
+}
+
+/// Builds a test signal out of speech-shaped bursts, silence and background noise.
+#[derive(Debug, Clone)]
+pub struct SignalBuilder {
+    sample_rate_hz: u32,
+    samples: Vec<f32>,
+    rng: TestRng,
+}
+
+impl SignalBuilder {
+    pub fn new(sample_rate_hz: u32, seed: u64) -> Self {
+        Self {
+            sample_rate_hz,
+            samples: Vec::new(),
+            rng: TestRng::new(seed),
+        }
+    }
+
+    /// Low-passed noise with a ~4 Hz syllable envelope, scaled so its RMS is `rms`.
+    /// The envelope never drops below 60%, so every 20 ms frame stays well above
+    /// a VAD threshold of `rms / 2`.
+    pub fn speech(mut self, seconds: f32, rms: f32) -> Self {
+        let n = self.samples_for(seconds);
+        let mut burst = Vec::with_capacity(n);
+        let mut lp = 0.0f32;
+        for _ in 0..n {
+            lp = 0.7 * lp + 0.3 * self.rng.next_f32();
+            burst.push(lp);
+        }
+
+        let current_rms = rms_of(&burst);
+        let gain = if current_rms > 0.0 { rms / current_rms } else { 0.0 };
+        let sr = self.sample_rate_hz as f32;
+        for (i, s) in burst.iter_mut().enumerate() {
+            let t = i as f32 / sr;
+            let envelope = 0.8 + 0.2 * (2.0 * std::f32::consts::PI * 4.0 * t).sin();
+            *s *= gain * envelope;
+        }
+        self.samples.extend(burst);
+        self
+    }
+
+    /// A talker saying vowels ("ah", "ee", "oo", one every half second, starting at a random
+    /// one): harmonics of `pitch_hz` with a little vibrato, shaped by formants scaled by
+    /// `tract` (1.0 is an average adult; shorter vocal tracts have higher formants).
+    pub fn voice(mut self, seconds: f32, pitch_hz: f32, tract: f32) -> Self {
+        const VOWELS: [[f32; 3]; 3] = [
+            [730.0, 1090.0, 2440.0],
+            [270.0, 2290.0, 3010.0],
+            [300.0, 870.0, 2240.0],
+        ];
+        let n = self.samples_for(seconds);
+        let sr = self.sample_rate_hz as f32;
+        let first = (self.rng.next_u64() % VOWELS.len() as u64) as usize;
+        let vowel_len = self.samples_for(0.5).max(1);
+        let tau = 2.0 * std::f32::consts::PI;
+        let mut phase = 0.0f32;
+        for i in 0..n {
+            let formants = VOWELS[(first + i / vowel_len) % VOWELS.len()];
+            let f0 = pitch_hz * (1.0 + 0.02 * (tau * 5.0 * i as f32 / sr).sin());
+            phase = (phase + f0 / sr).fract();
+            let mut sample = 0.0;
+            let mut harmonic = 1.0;
+            while harmonic * f0 < sr * 0.47 {
+                let hz = harmonic * f0;
+                let gain = formants
+                    .iter()
+                    .map(|&f| (-((hz - f * tract) / (90.0 * tract)).powi(2)).exp())
+                    .sum::<f32>()
+                    + 0.02;
+                sample += gain / harmonic * (tau * phase * harmonic).sin();
+                harmonic += 1.0;
+            }
+            self.samples.push(0.1 * sample);
+        }
+        self
+    }
+
+    pub fn silence(mut self, seconds: f32) -> Self {
+        let n = self.samples_for(seconds);
+        self.samples.resize(self.samples.len() + n, 0.0);
+        self
+    }
+
+    /// Uniform background noise at the given peak amplitude.
+    pub fn noise(mut self, seconds: f32, amplitude: f32) -> Self {
+        let n = self.samples_for(seconds);
+        for _ in 0..n {
+            let s = self.rng.next_f32() * amplitude;
+            self.samples.push(s);
+        }
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn build(self) -> Vec<f32> {
+        self.samples
+    }
+
+    fn samples_for(&self, seconds: f32) -> usize {
+        samples_for(seconds, self.sample_rate_hz)
+    }
+}
+
+pub fn samples_for(seconds: f32, sample_rate_hz: u32) -> usize {
+    (seconds.max(0.0) * sample_rate_hz as f32).round() as usize
+}
+
+pub fn rms_of(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Splits `audio` into capture-sized chunks, the way the capture thread delivers it.
+pub fn chunked(audio: &[f32], chunk_len: usize) -> impl Iterator<Item = &[f32]> {
+    audio.chunks(chunk_len.max(1))
+}
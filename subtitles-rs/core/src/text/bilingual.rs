@@ -0,0 +1,22 @@
+/// Original line over its translation, or just one of them when the other is empty or the two
+/// read the same (e.g. English input with bilingual output).
+pub fn merge_bilingual(primary: &str, secondary: &str) -> String {
+    let primary = primary.trim();
+    let secondary = secondary.trim();
+    match (primary.is_empty(), secondary.is_empty()) {
+        (true, true) => String::new(),
+        (false, true) => primary.to_string(),
+        (true, false) => secondary.to_string(),
+        (false, false) => {
+            if normalize_for_compare(primary) == normalize_for_compare(secondary) {
+                primary.to_string()
+            } else {
+                format!("{primary}\n{secondary}")
+            }
+        }
+    }
+}
+
+fn normalize_for_compare(s: &str) -> String {
+    s.trim().to_lowercase()
+}
@@ -0,0 +1,105 @@
+//! Composable post-processing for finalized captions. Each stage is a `TextFilter`; stages are
+//! registered by name in a `FilterRegistry` and run in the order the user configures.
+
+use crate::config::{ItnLocale, OutputLanguage};
+use crate::text::normalize_numbers;
+
+/// A finalized caption passing through the filter chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSegment {
+    pub segment_id: u64,
+    pub text: String,
+    pub output_language: OutputLanguage,
+}
+
+/// One post-processing stage: takes a segment and returns it, usually with new text.
+pub trait TextFilter: Send {
+    fn apply(&mut self, segment: TextSegment) -> TextSegment;
+}
+
+impl TextFilter for ItnLocale {
+    fn apply(&mut self, mut segment: TextSegment) -> TextSegment {
+        segment.text = normalize_numbers(&segment.text, *self);
+        segment
+    }
+}
+
+/// An unconfigured stage passes segments through.
+impl<T: TextFilter> TextFilter for Option<T> {
+    fn apply(&mut self, segment: TextSegment) -> TextSegment {
+        match self {
+            Some(filter) => filter.apply(segment),
+            None => segment,
+        }
+    }
+}
+
+/// Creates a fresh instance of a stage; chains are built once per thread that filters text.
+pub type FilterFactory = Box<dyn Fn() -> anyhow::Result<Box<dyn TextFilter>> + Send + Sync>;
+
+/// Named stages that a configuration can put in a chain.
+#[derive(Default)]
+pub struct FilterRegistry {
+    factories: Vec<(String, FilterFactory)>,
+}
+
+impl FilterRegistry {
+    /// Adds (or replaces) the stage called `name`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn() -> anyhow::Result<Box<dyn TextFilter>> + Send + Sync + 'static,
+    ) {
+        self.factories.retain(|(existing, _)| existing != name);
+        self.factories.push((name.to_string(), Box::new(factory)));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// A chain running the named stages in order.
+    pub fn build(&self, names: &[String]) -> anyhow::Result<FilterChain> {
+        let mut filters = Vec::with_capacity(names.len());
+        for name in names {
+            let Some((_, factory)) = self.factories.iter().find(|(known, _)| known == name) else {
+                let known: Vec<&str> = self.names().collect();
+                anyhow::bail!(
+                    "unknown text filter `{name}` (available: {})",
+                    known.join(", ")
+                );
+            };
+            filters.push(factory()?);
+        }
+        Ok(FilterChain { filters })
+    }
+}
+
+/// Stages built from a registry, applied in order.
+#[derive(Default)]
+pub struct FilterChain {
+    filters: Vec<Box<dyn TextFilter>>,
+}
+
+impl FilterChain {
+    pub fn apply(&mut self, segment: TextSegment) -> TextSegment {
+        self.filters
+            .iter_mut()
+            .fold(segment, |segment, filter| filter.apply(segment))
+    }
+
+    /// Shorthand for filtering just the text of a segment.
+    pub fn apply_text(
+        &mut self,
+        segment_id: u64,
+        text: String,
+        output_language: OutputLanguage,
+    ) -> String {
+        self.apply(TextSegment {
+            segment_id,
+            text,
+            output_language,
+        })
+        .text
+    }
+}
@@ -0,0 +1,130 @@
+//! User terminology for translation: source term -> preferred English rendering.
+//!
+//! The file is a JSON object. A value is either the translation, or an object that also lists
+//! renderings to correct after the fact:
+//!
+//! ```json
+//! {
+//!   "微信": "WeChat",
+//!   "飞书": { "translation": "Lark", "avoid": ["Flying Book", "Feishu"] }
+//! }
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlossaryEntry {
+    pub source: String,
+    pub translation: String,
+    /// Literal or unwanted renderings that post-editing replaces with `translation`.
+    pub avoid: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Glossary {
+    entries: Vec<GlossaryEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawEntry {
+    Translation(String),
+    Detailed {
+        translation: String,
+        #[serde(default)]
+        avoid: Vec<String>,
+    },
+}
+
+impl Glossary {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read glossary {}", path.display()))?;
+        Self::from_json(&raw).with_context(|| format!("invalid glossary {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let raw: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+        let mut entries = Vec::with_capacity(raw.len());
+        for (source, value) in raw {
+            let entry = serde_json::from_value(value)
+                .with_context(|| format!("glossary entry `{source}`"))?;
+            let (translation, avoid) = match entry {
+                RawEntry::Translation(translation) => (translation, Vec::new()),
+                RawEntry::Detailed { translation, avoid } => (translation, avoid),
+            };
+            if source.trim().is_empty() || translation.trim().is_empty() {
+                anyhow::bail!("glossary entry `{source}` has an empty term");
+            }
+            entries.push(GlossaryEntry {
+                source,
+                translation,
+                avoid,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[GlossaryEntry] {
+        &self.entries
+    }
+
+    /// Prompt text that primes the translator with the preferred terms.
+    pub fn prompt_hint(&self) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let terms: Vec<String> = self
+            .entries
+            .iter()
+            .map(|e| format!("{} = {}", e.source, e.translation))
+            .collect();
+        Some(format!("Glossary: {}.", terms.join(", ")))
+    }
+
+    /// Replaces untranslated source terms and listed unwanted renderings with the preferred
+    /// translation. Latin-script matches are case-insensitive and respect word boundaries.
+    pub fn post_edit(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for entry in &self.entries {
+            for term in std::iter::once(&entry.source).chain(&entry.avoid) {
+                out = replace_term(&out, term, &entry.translation);
+            }
+        }
+        out
+    }
+}
+
+pub(crate) fn replace_term(text: &str, term: &str, replacement: &str) -> String {
+    if term.is_empty() {
+        return text.to_string();
+    }
+    // ASCII-only folding keeps byte offsets identical between `text` and `haystack`.
+    let haystack = text.to_ascii_lowercase();
+    let needle = term.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    while let Some(found) = haystack[pos..].find(&needle) {
+        let start = pos + found;
+        let end = start + needle.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        let bounded = !needs_boundary(term.chars().next(), before)
+            && !needs_boundary(term.chars().next_back(), after);
+        out.push_str(&text[pos..start]);
+        out.push_str(if bounded { replacement } else { &text[start..end] });
+        pos = end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// A term edge that is a Latin letter or digit can't touch another one ("Lark" in "Larkspur").
+fn needs_boundary(edge: Option<char>, neighbour: Option<char>) -> bool {
+    let word_char = |c: char| c.is_ascii_alphanumeric();
+    edge.is_some_and(word_char) && neighbour.is_some_and(word_char)
+}
@@ -0,0 +1,538 @@
+//! Inverse text normalization: spelled-out numbers, years, times, currencies and percentages
+//! in decoded text become digits ("twenty twenty five" -> "2025", "five dollars and fifty
+//! cents" -> "$5.50"). Only English is supported so far.
+//!
+//! Plain numbers below ten stay spelled out ("one of them", "wait a second"); they are only
+//! converted as part of a time, amount or percentage.
+
+use crate::config::ItnLocale;
+
+/// Rewrites spelled-out numbers in `text` for `locale`. Text without number words is returned
+/// unchanged.
+pub fn normalize_numbers(text: &str, locale: ItnLocale) -> String {
+    match locale {
+        ItnLocale::Off => text.to_string(),
+        ItnLocale::English => normalize_english(text),
+    }
+}
+
+/// A whitespace-separated word with its surrounding punctuation split off.
+struct Token<'a> {
+    lead: &'a str,
+    core: &'a str,
+    trail: &'a str,
+    /// Lowercased `core`, for matching.
+    word: String,
+    /// This token came from a hyphenated word ("twenty-five") and the next one continues it.
+    hyphen_next: bool,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for piece in text.split_whitespace() {
+        let start = piece
+            .find(|c: char| c.is_alphanumeric())
+            .unwrap_or(piece.len());
+        let end = piece
+            .rfind(|c: char| c.is_alphanumeric())
+            .map_or(start, |i| i + piece[i..].chars().next().map_or(1, char::len_utf8));
+        let (lead, core, trail) = (&piece[..start], &piece[start..end], &piece[end..]);
+
+        let parts: Vec<&str> = core.split('-').collect();
+        let hyphenated_number =
+            parts.len() > 1 && parts.iter().all(|part| number_word(&part.to_lowercase()));
+        if !hyphenated_number {
+            tokens.push(Token {
+                lead,
+                core,
+                trail,
+                word: core.to_lowercase(),
+                hyphen_next: false,
+            });
+            continue;
+        }
+        let last = parts.len() - 1;
+        for (i, part) in parts.into_iter().enumerate() {
+            tokens.push(Token {
+                lead: if i == 0 { lead } else { "" },
+                core: part,
+                trail: if i == last { trail } else { "" },
+                word: part.to_lowercase(),
+                hyphen_next: i != last,
+            });
+        }
+    }
+    tokens
+}
+
+fn normalize_english(text: &str) -> String {
+    let tokens = tokenize(text);
+    if !tokens.iter().any(|t| number_word(&t.word)) {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        // A phrase may end on a token with trailing punctuation but can't run past it.
+        let mut end = i;
+        while end + 1 < tokens.len()
+            && tokens[end].trail.is_empty()
+            && tokens[end + 1].lead.is_empty()
+        {
+            end += 1;
+        }
+        let words: Vec<&str> = tokens[i..=end].iter().map(|t| t.word.as_str()).collect();
+
+        if let Some((replacement, len)) = match_phrase(&words) {
+            let last = &tokens[i + len - 1];
+            let trail = if replacement.ends_with('.') {
+                last.trail.strip_prefix('.').unwrap_or(last.trail)
+            } else {
+                last.trail
+            };
+            push_separated(&mut out, i, &tokens);
+            out.push_str(tokens[i].lead);
+            out.push_str(&replacement);
+            out.push_str(trail);
+            i += len;
+            continue;
+        }
+
+        let token = &tokens[i];
+        push_separated(&mut out, i, &tokens);
+        out.push_str(token.lead);
+        out.push_str(token.core);
+        out.push_str(token.trail);
+        i += 1;
+    }
+    out
+}
+
+fn push_separated(out: &mut String, i: usize, tokens: &[Token<'_>]) {
+    if i == 0 {
+        return;
+    }
+    // Keep "one-two" hyphenated when it isn't rewritten.
+    if tokens[i - 1].hyphen_next {
+        out.push('-');
+    } else {
+        out.push(' ');
+    }
+}
+
+/// Tries every number pattern at the start of `words`, returning the replacement text and how
+/// many words it covers.
+fn match_phrase(words: &[&str]) -> Option<(String, usize)> {
+    if let Some(year) = match_year(words) {
+        return Some(year);
+    }
+
+    let number = parse_number(words)?;
+    let rest = &words[number.len..];
+
+    if !number.ordinal {
+        if let Some((time, len)) = match_time(&number, rest) {
+            return Some((time, number.len + len));
+        }
+        if let Some((amount, len)) = match_currency(&number, rest) {
+            return Some((amount, number.len + len));
+        }
+        match rest {
+            ["percent", ..] => return Some((format!("{}%", number.text), number.len + 1)),
+            ["per", "cent", ..] => return Some((format!("{}%", number.text), number.len + 2)),
+            _ => {}
+        }
+    }
+
+    if number.decimal || number.value >= 10 {
+        let text = if number.ordinal {
+            format!("{}{}", number.text, ordinal_suffix(number.value))
+        } else {
+            number.text
+        };
+        return Some((text, number.len));
+    }
+    None
+}
+
+/// "nineteen eighty four" -> 1984, "twenty twenty five" -> 2025, "nineteen oh five" -> 1905.
+fn match_year(words: &[&str]) -> Option<(String, usize)> {
+    let century = match *words.first()? {
+        "eighteen" => 18,
+        "nineteen" => 19,
+        "twenty" => 20,
+        _ => return None,
+    };
+    let (rest, len) = match words.get(1..)? {
+        ["oh", unit, ..] => (units(unit).filter(|&u| u > 0)?, 2),
+        [teen, ..] if teens(teen).is_some() => (teens(teen)?, 1),
+        [ten, unit, ..] if tens(ten).is_some() && units(unit).is_some_and(|u| u > 0) => {
+            (tens(ten)? + units(unit)?, 2)
+        }
+        [ten, ..] if tens(ten).is_some() => (tens(ten)?, 1),
+        _ => return None,
+    };
+    // "twenty twenty five thousand" is a count, not a year.
+    if words.get(1 + len).is_some_and(|w| scale(w).is_some() || *w == "hundred") {
+        return None;
+    }
+    Some(((century * 100 + rest).to_string(), 1 + len))
+}
+
+/// "three thirty p m" -> "3:30 p.m.", "seven o'clock" -> "7 o'clock".
+fn match_time(number: &Number, rest: &[&str]) -> Option<(String, usize)> {
+    if number.len != 1 || number.decimal || !(1..=12).contains(&number.value) {
+        return None;
+    }
+    let hour = number.value;
+    if rest.first() == Some(&"o'clock") {
+        return Some((format!("{hour} o'clock"), 1));
+    }
+
+    let (minutes, used) = match rest {
+        ["oh", unit, ..] if units(unit).is_some_and(|u| u > 0) => (Some(units(unit)?), 2),
+        [teen, ..] if teens(teen).is_some_and(|t| t >= 10) => (Some(teens(teen)?), 1),
+        [ten, unit, ..]
+            if tens(ten).is_some_and(|t| t < 60) && units(unit).is_some_and(|u| u > 0) =>
+        {
+            (Some(tens(ten)? + units(unit)?), 2)
+        }
+        [ten, ..] if tens(ten).is_some_and(|t| t < 60) => (Some(tens(ten)?), 1),
+        _ => (None, 0),
+    };
+    let (meridiem, marker_len) = match &rest[used..] {
+        ["am", ..] | ["a.m", ..] => ("a.m.", 1),
+        ["pm", ..] | ["p.m", ..] => ("p.m.", 1),
+        ["a", "m", ..] => ("a.m.", 2),
+        ["p", "m", ..] => ("p.m.", 2),
+        _ => return None,
+    };
+    let time = match minutes {
+        Some(minutes) => format!("{hour}:{minutes:02} {meridiem}"),
+        None => format!("{hour} {meridiem}"),
+    };
+    Some((time, used + marker_len))
+}
+
+/// "five dollars and fifty cents" -> "$5.50", "two million euros" -> "€2 million".
+fn match_currency(number: &Number, rest: &[&str]) -> Option<(String, usize)> {
+    let symbol = match *rest.first()? {
+        "dollar" | "dollars" => "$",
+        "euro" | "euros" => "€",
+        _ => return None,
+    };
+    let cents = match rest.get(1..) {
+        Some(["and", more @ ..]) => parse_cents(more).map(|(c, len)| (c, len + 1)),
+        Some(more) => parse_cents(more),
+        None => None,
+    };
+    match cents {
+        Some((cents, len)) if !number.decimal => {
+            Some((format!("{symbol}{}.{cents:02}", number.text), 1 + len))
+        }
+        _ => Some((format!("{symbol}{}", number.text), 1)),
+    }
+}
+
+fn parse_cents(words: &[&str]) -> Option<(u64, usize)> {
+    let cents = parse_number(words)?;
+    if cents.decimal || cents.ordinal || cents.value >= 100 {
+        return None;
+    }
+    matches!(words.get(cents.len), Some(&"cent") | Some(&"cents"))
+        .then_some((cents.value, cents.len + 1))
+}
+
+struct Number {
+    value: u64,
+    /// Digits as they should be written, e.g. "2,500", "3.14" or "2 million".
+    text: String,
+    len: usize,
+    ordinal: bool,
+    decimal: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Last {
+    Start,
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+    And,
+}
+
+/// Parses a cardinal ("two thousand and five"), ordinal ("twenty first") or decimal
+/// ("three point one four") from the start of `words`.
+fn parse_number(words: &[&str]) -> Option<Number> {
+    if words.first() == Some(&"zero") {
+        return parse_decimal(0, 1, words);
+    }
+
+    let mut total = 0u64;
+    let mut current = 0u64;
+    let mut last = Last::Start;
+    let mut last_scale = u64::MAX;
+    let mut len = 0;
+    let mut ordinal = false;
+
+    while let Some(&raw) = words.get(len) {
+        let (word, is_ordinal) = match ordinal_to_cardinal(raw) {
+            Some(word) => (word, true),
+            None => (raw, false),
+        };
+        let after_group = matches!(last, Last::Start | Last::Hundred | Last::Scale | Last::And);
+        if let Some(v) = units(word).filter(|&v| v > 0) {
+            if !(after_group || last == Last::Tens) {
+                break;
+            }
+            current += v;
+            last = Last::Unit;
+        } else if let Some(v) = teens(word) {
+            if !after_group {
+                break;
+            }
+            current += v;
+            last = Last::Teen;
+        } else if let Some(v) = tens(word) {
+            if !after_group {
+                break;
+            }
+            current += v;
+            last = Last::Tens;
+        } else if word == "hundred" {
+            if !matches!(last, Last::Unit | Last::Teen) || current >= 100 {
+                break;
+            }
+            current *= 100;
+            last = Last::Hundred;
+        } else if let Some(s) = scale(word) {
+            if matches!(last, Last::Start | Last::Scale | Last::And) || s >= last_scale {
+                break;
+            }
+            total += current * s;
+            current = 0;
+            last_scale = s;
+            last = Last::Scale;
+        } else if word == "and" && !is_ordinal {
+            let continues = words.get(len + 1).is_some_and(|next| {
+                units(next).is_some_and(|v| v > 0) || teens(next).is_some() || tens(next).is_some()
+            });
+            if !matches!(last, Last::Hundred | Last::Scale) || !continues {
+                break;
+            }
+            last = Last::And;
+        } else {
+            break;
+        }
+        len += 1;
+        if is_ordinal {
+            ordinal = true;
+            break;
+        }
+    }
+
+    if len == 0 {
+        return None;
+    }
+    let value = total + current;
+    if ordinal {
+        return Some(Number {
+            value,
+            text: group_thousands(value),
+            len,
+            ordinal,
+            decimal: false,
+        });
+    }
+    parse_decimal(value, len, words)
+}
+
+/// Extends a whole number with "point <digits>" and a trailing "million"/"billion".
+fn parse_decimal(value: u64, len: usize, words: &[&str]) -> Option<Number> {
+    let mut digits = String::new();
+    let mut used = len;
+    if words.get(used) == Some(&"point") {
+        while let Some(d) = words.get(used + 1 + digits.len()).and_then(|w| digit(w)) {
+            digits.push(char::from(b'0' + d as u8));
+        }
+        if !digits.is_empty() {
+            used += 1 + digits.len();
+        }
+    }
+
+    if digits.is_empty() {
+        if value == 0 && len == 1 {
+            // A lone "zero" reads fine as a word.
+            return None;
+        }
+        return Some(Number {
+            value,
+            text: format_whole(value),
+            len,
+            ordinal: false,
+            decimal: false,
+        });
+    }
+
+    let mut text = format!("{}.{digits}", group_thousands(value));
+    if let Some(&word) = words.get(used) {
+        if matches!(word, "million" | "billion") && value < 1000 {
+            text = format!("{text} {word}");
+            used += 1;
+        }
+    }
+    Some(Number {
+        value,
+        text,
+        len: used,
+        ordinal: false,
+        decimal: true,
+    })
+}
+
+/// Round millions and billions keep their scale word ("2 million"); other large numbers are
+/// grouped ("12,500").
+fn format_whole(value: u64) -> String {
+    for (scale, name) in [(1_000_000_000, "billion"), (1_000_000, "million")] {
+        if value >= scale && value.is_multiple_of(scale) {
+            return format!("{} {name}", group_thousands(value / scale));
+        }
+    }
+    if value >= 10_000 {
+        group_thousands(value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn ordinal_suffix(value: u64) -> &'static str {
+    match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+fn number_word(word: &str) -> bool {
+    units(word).is_some()
+        || teens(word).is_some()
+        || tens(word).is_some()
+        || word == "hundred"
+        || scale(word).is_some()
+        || ordinal_to_cardinal(word).is_some()
+}
+
+fn digit(word: &str) -> Option<u64> {
+    if word == "oh" {
+        return Some(0);
+    }
+    units(word)
+}
+
+fn units(word: &str) -> Option<u64> {
+    Some(match word {
+        "zero" => 0,
+        "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        _ => return None,
+    })
+}
+
+fn teens(word: &str) -> Option<u64> {
+    Some(match word {
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        "thirteen" => 13,
+        "fourteen" => 14,
+        "fifteen" => 15,
+        "sixteen" => 16,
+        "seventeen" => 17,
+        "eighteen" => 18,
+        "nineteen" => 19,
+        _ => return None,
+    })
+}
+
+fn tens(word: &str) -> Option<u64> {
+    Some(match word {
+        "twenty" => 20,
+        "thirty" => 30,
+        "forty" => 40,
+        "fifty" => 50,
+        "sixty" => 60,
+        "seventy" => 70,
+        "eighty" => 80,
+        "ninety" => 90,
+        _ => return None,
+    })
+}
+
+fn scale(word: &str) -> Option<u64> {
+    Some(match word {
+        "thousand" => 1_000,
+        "million" => 1_000_000,
+        "billion" => 1_000_000_000,
+        _ => return None,
+    })
+}
+
+fn ordinal_to_cardinal(word: &str) -> Option<&'static str> {
+    Some(match word {
+        "first" => "one",
+        "second" => "two",
+        "third" => "three",
+        "fourth" => "four",
+        "fifth" => "five",
+        "sixth" => "six",
+        "seventh" => "seven",
+        "eighth" => "eight",
+        "ninth" => "nine",
+        "tenth" => "ten",
+        "eleventh" => "eleven",
+        "twelfth" => "twelve",
+        "thirteenth" => "thirteen",
+        "fourteenth" => "fourteen",
+        "fifteenth" => "fifteen",
+        "sixteenth" => "sixteen",
+        "seventeenth" => "seventeen",
+        "eighteenth" => "eighteen",
+        "nineteenth" => "nineteen",
+        "twentieth" => "twenty",
+        "thirtieth" => "thirty",
+        "fortieth" => "forty",
+        "fiftieth" => "fifty",
+        "sixtieth" => "sixty",
+        "seventieth" => "seventy",
+        "eightieth" => "eighty",
+        "ninetieth" => "ninety",
+        "hundredth" => "hundred",
+        "thousandth" => "thousand",
+        "millionth" => "million",
+        _ => return None,
+    })
+}
@@ -0,0 +1,125 @@
+//! Language-aware joining and spacing for caption text: no spaces between CJK characters,
+//! and French spacing before high punctuation.
+
+use crate::config::OutputLanguage;
+
+/// Narrow no-break space, used before `; : ! ?` in French.
+const NNBSP: char = '\u{202F}';
+/// No-break space, used inside « guillemets ».
+const NBSP: char = '\u{A0}';
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextJoiner {
+    french: bool,
+}
+
+impl TextJoiner {
+    /// Rules for a language code such as `en`, `zh` or `fr`; `None` (auto-detect) only applies
+    /// the script-based CJK rule.
+    pub fn for_language(language: Option<&str>) -> Self {
+        let primary = language
+            .and_then(|lang| lang.split(['-', '_']).next())
+            .map(str::to_ascii_lowercase);
+        Self {
+            french: primary.as_deref() == Some("fr"),
+        }
+    }
+
+    /// Rules for one caption line: English output is always English; otherwise the line is in
+    /// the input language.
+    pub fn for_output(output: OutputLanguage, input_language: Option<&str>) -> Self {
+        match output {
+            OutputLanguage::English => Self::for_language(Some("en")),
+            OutputLanguage::Chinese | OutputLanguage::Bilingual => {
+                Self::for_language(input_language)
+            }
+        }
+    }
+
+    /// Appends `right` to `left` with whatever separator the boundary needs.
+    pub fn join(&self, left: &str, right: &str) -> String {
+        let (left, right) = (left.trim(), right.trim());
+        let (Some(a), Some(b)) = (left.chars().next_back(), right.chars().next()) else {
+            return format!("{left}{right}");
+        };
+        match self.separator(a, b, right.chars().nth(1)) {
+            Some(sep) => format!("{left}{sep}{right}"),
+            None => format!("{left}{right}"),
+        }
+    }
+
+    /// Fixes spacing inside already-joined text (e.g. stabilizer tokens joined with spaces).
+    pub fn format(&self, text: &str) -> String {
+        let chars: Vec<char> = text.trim().chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut gap = false;
+        for (i, &c) in chars.iter().enumerate() {
+            if c.is_whitespace() {
+                gap = true;
+                continue;
+            }
+            if let Some(a) = out.chars().next_back() {
+                let next = chars.get(i + 1).copied();
+                let sep = if gap {
+                    self.separator(a, c, next)
+                } else if self.french {
+                    self.french_space(a, c, next)
+                } else {
+                    None
+                };
+                out.extend(sep);
+            }
+            gap = false;
+            out.push(c);
+        }
+        out
+    }
+
+    /// What goes between `a` and `b` when they are separate words; `next` follows `b`.
+    fn separator(&self, a: char, b: char, next: Option<char>) -> Option<char> {
+        if is_cjk(a) && is_cjk(b) || is_cjk_punctuation(a) || is_cjk_punctuation(b) {
+            return None;
+        }
+        if self.french {
+            if let Some(sep) = self.french_space(a, b, next) {
+                return Some(sep);
+            }
+        }
+        if matches!(b, ',' | '.' | ';' | ':' | '!' | '?' | ')' | ']' | '}' | '%') {
+            return None;
+        }
+        Some(' ')
+    }
+
+    /// French puts a narrow no-break space before `; : ! ?` and no-break spaces inside
+    /// guillemets. A colon followed by a non-space (`http:`, `10:30`) is left alone.
+    fn french_space(&self, a: char, b: char, next: Option<char>) -> Option<char> {
+        if matches!(a, ';' | ':' | '!' | '?') {
+            // "?!" stays together.
+            return None;
+        }
+        match b {
+            ';' | '!' | '?' => Some(NNBSP),
+            ':' if next.is_none_or(char::is_whitespace) => Some(NNBSP),
+            '»' => Some(NBSP),
+            _ if a == '«' => Some(NBSP),
+            _ => None,
+        }
+    }
+}
+
+/// Han ideographs and kana, which are written without spaces between words.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2A6DF}'
+    ) || is_cjk_punctuation(c)
+}
+
+/// Ideographic and full-width punctuation (。，、！？「」 etc.), which never takes a space.
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FF65}')
+}
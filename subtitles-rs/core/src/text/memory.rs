@@ -0,0 +1,210 @@
+//! Translation memory: source sentences and the translations they got, reused when the same
+//! (or nearly the same) sentence comes up again so recurring phrases are translated the same
+//! way every time and don't need another decode.
+//!
+//! With a file, entries are appended to it as JSON lines (`{"source": ..., "translation":
+//! ...}`) and loaded again next session.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// Entries kept; the oldest are forgotten first.
+pub const MAX_ENTRIES: usize = 5_000;
+/// How alike two sources must be (1 - edit distance / length) to share a translation.
+pub const MIN_SIMILARITY: f64 = 0.9;
+/// Shorter sources only match exactly: one changed character is a different sentence.
+const MIN_FUZZY_CHARS: usize = 8;
+/// Chinese numerals and negations, which a near match must not change.
+const CJK_NUMERALS: &str = "〇零一二两三四五六七八九十百千万亿";
+const CJK_NEGATIONS: &str = "不没别未无非勿否";
+const NEGATIONS: [&str; 7] = ["no", "not", "never", "none", "nothing", "nobody", "cannot"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    source: String,
+    translation: String,
+}
+
+#[derive(Debug, Default)]
+pub struct TranslationMemory {
+    /// Normalized source, then the entry; newest last.
+    entries: VecDeque<(Vec<char>, Entry)>,
+    file: Option<(PathBuf, File)>,
+}
+
+impl TranslationMemory {
+    /// A memory for this session only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A memory kept in `path`, starting from what earlier sessions wrote there. A file past
+    /// `MAX_ENTRIES` is compacted.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut memory = Self::new();
+        let mut lines = 0;
+        match fs::read_to_string(path) {
+            Ok(raw) => {
+                for (n, line) in raw.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    lines += 1;
+                    let entry: Entry = serde_json::from_str(line).with_context(|| {
+                        format!(
+                            "invalid translation memory {} line {}",
+                            path.display(),
+                            n + 1
+                        )
+                    })?;
+                    memory.remember(entry);
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("failed to read translation memory {}", path.display())
+                })
+            }
+        }
+        if lines > memory.entries.len() {
+            let mut compacted = String::new();
+            for (_, entry) in &memory.entries {
+                compacted.push_str(&serde_json::to_string(entry)?);
+                compacted.push('\n');
+            }
+            fs::write(path, compacted)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        memory.file = Some((path.to_path_buf(), file));
+        Ok(memory)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The translation of `source`, or of the most similar remembered sentence if it is at
+    /// least `MIN_SIMILARITY` alike and has the same numbers and negations.
+    pub fn lookup(&self, source: &str) -> Option<&str> {
+        let key = normalize(source);
+        if key.is_empty() {
+            return None;
+        }
+        if let Some((_, entry)) = self.entries.iter().rev().find(|(k, _)| *k == key) {
+            return Some(&entry.translation);
+        }
+        if key.len() < MIN_FUZZY_CHARS {
+            return None;
+        }
+        let fixed = fixed_words(source);
+        self.entries
+            .iter()
+            .rev()
+            .filter(|(k, _)| k.len() >= MIN_FUZZY_CHARS)
+            .map(|(k, entry)| (similarity(&key, k), entry))
+            .filter(|(score, entry)| {
+                *score >= MIN_SIMILARITY && fixed_words(&entry.source) == fixed
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, entry)| entry.translation.as_str())
+    }
+
+    /// Remembers a translation, replacing any for the same source, and appends it to the file.
+    pub fn insert(&mut self, source: &str, translation: &str) {
+        let entry = Entry {
+            source: source.trim().to_string(),
+            translation: translation.trim().to_string(),
+        };
+        if entry.translation.is_empty() || normalize(&entry.source).is_empty() {
+            return;
+        }
+        if let (Some((path, file)), Ok(json)) = (self.file.as_mut(), serde_json::to_string(&entry))
+        {
+            if let Err(err) = writeln!(file, "{json}") {
+                tracing::warn!("failed to save to {}: {err}", path.display());
+            }
+        }
+        self.remember(entry);
+    }
+
+    fn remember(&mut self, entry: Entry) {
+        let key = normalize(&entry.source);
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_back((key, entry));
+        while self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Letters and digits only, lowercased, so punctuation and spacing don't matter.
+pub(crate) fn normalize(text: &str) -> Vec<char> {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The numbers and negations in `text`, in order. A sentence that differs in one of them
+/// means something else ("at 9" is no translation for "at 10"), however alike the rest is.
+fn fixed_words(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+    {
+        let word = word.to_lowercase();
+        if NEGATIONS.contains(&word.as_str()) || word.ends_with("n't") {
+            out.push(word);
+            continue;
+        }
+        out.extend(
+            word.chars()
+                .filter(|&c| {
+                    c.is_numeric() || CJK_NUMERALS.contains(c) || CJK_NEGATIONS.contains(c)
+                })
+                .map(String::from),
+        );
+    }
+    out
+}
+
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    // Too different in length to clear the bar, whatever the characters.
+    if (a.len().abs_diff(b.len()) as f64) > (1.0 - MIN_SIMILARITY) * longest as f64 {
+        return 0.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+pub(crate) fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diagonal + usize::from(ca != cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,23 @@
+//! Text post-processing applied to decoded captions before they are shown or exported.
+
+pub mod bilingual;
+pub mod filter;
+pub mod glossary;
+pub mod itn;
+pub mod joiner;
+pub mod memory;
+pub mod reading;
+pub mod replace;
+pub mod sound_tags;
+pub mod vertical;
+
+pub use bilingual::merge_bilingual;
+pub use filter::{FilterChain, FilterRegistry, TextFilter, TextSegment};
+pub use glossary::Glossary;
+pub use itn::normalize_numbers;
+pub use joiner::TextJoiner;
+pub use memory::TranslationMemory;
+pub use reading::{ReadingSpeed, TimedChunk};
+pub use replace::{correction_pairs, ReplaceRules};
+pub use sound_tags::{SoundTagFilter, CROSSTALK_TAG};
+pub use vertical::{render_vertical, vertical_columns, vertical_forms};
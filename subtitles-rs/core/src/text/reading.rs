@@ -0,0 +1,170 @@
+//! Reading-speed pacing: long finals are split into cues that can each be read in the time they
+//! are shown, the way subtitling guidelines size cues.
+
+use std::ops::Range;
+
+/// Longest a single cue should take to read; longer text is split.
+pub const MAX_CUE_S: f64 = 5.0;
+/// Shortest time a cue is shown, however little text it has.
+pub const MIN_CUE_S: f64 = 1.0;
+
+const SENTENCE_ENDS: [char; 7] = ['.', '?', '!', '…', '。', '？', '！'];
+const CLAUSE_ENDS: [char; 8] = [',', ';', ':', '—', '、', '，', '；', '：'];
+
+/// Characters per second a viewer can comfortably read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadingSpeed {
+    chars_per_s: f64,
+}
+
+/// One cue of a split caption, on the same clock as the caption it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedChunk {
+    pub text: String,
+    pub start_s: f64,
+    pub end_s: f64,
+}
+
+impl ReadingSpeed {
+    /// `None` when `chars_per_s` is 0 (pacing off) or not a positive number.
+    pub fn new(chars_per_s: f32) -> Option<Self> {
+        (chars_per_s.is_finite() && chars_per_s > 0.0).then_some(Self {
+            chars_per_s: chars_per_s as f64,
+        })
+    }
+
+    /// How long `text` needs on screen, at least `MIN_CUE_S`.
+    pub fn read_time_s(&self, text: &str) -> f64 {
+        (text.trim().chars().count() as f64 / self.chars_per_s).max(MIN_CUE_S)
+    }
+
+    /// Most characters one cue may hold.
+    pub fn max_chars(&self) -> usize {
+        ((self.chars_per_s * MAX_CUE_S).floor() as usize).max(1)
+    }
+
+    /// Splits `text` into cues of at most `max_chars`, breaking after a sentence if one ends in
+    /// range, else after a clause, else at a space (text without spaces, like Chinese, is cut
+    /// at the limit).
+    pub fn chunks(&self, text: &str) -> Vec<String> {
+        let chars: Vec<char> = text.trim().chars().collect();
+        self.chunk_ranges(&chars)
+            .into_iter()
+            .map(|range| chars[range].iter().collect::<String>().trim().to_string())
+            .collect()
+    }
+
+    /// Where `chunks` cuts `chars` (trimmed text), as ranges of it.
+    fn chunk_ranges(&self, chars: &[char]) -> Vec<Range<usize>> {
+        let max = self.max_chars();
+        let mut start = 0;
+        let mut out = Vec::new();
+        while chars.len() - start > max {
+            let cut = start + break_point(&chars[start..], max);
+            out.push(start..cut);
+            start = cut + chars[cut..].iter().take_while(|c| c.is_whitespace()).count();
+        }
+        if start < chars.len() || out.is_empty() {
+            out.push(start..chars.len());
+        }
+        out
+    }
+
+    /// `chunks`, laid out one after another from `start_s`, each shown for its reading time.
+    /// When reading is quicker than the speech was, they are stretched to fill up to `end_s`.
+    pub fn timed_chunks(&self, text: &str, start_s: f64, end_s: f64) -> Vec<TimedChunk> {
+        let chunks = self.chunks(text);
+        let read: Vec<f64> = chunks.iter().map(|chunk| self.read_time_s(chunk)).collect();
+        let total: f64 = read.iter().sum();
+        let stretch = ((end_s - start_s) / total).max(1.0);
+        let mut at = start_s;
+        chunks
+            .into_iter()
+            .zip(read)
+            .map(|(text, read_s)| {
+                let start_s = at;
+                at += read_s * stretch;
+                TimedChunk {
+                    text,
+                    start_s,
+                    end_s: at,
+                }
+            })
+            .collect()
+    }
+
+    /// `chunks` timed by when their words were spoken: `words` holds a `(start_s, end_s)` per
+    /// whitespace-separated word of `text`. Each cue shows from its first word until the next
+    /// cue, and the last for at least its reading time. A cue that starts mid-word (Chinese
+    /// cut at the limit) starts partway through it, by characters. `None` when the word
+    /// counts differ, e.g. after the text was edited.
+    pub fn word_timed_chunks(&self, text: &str, words: &[(f64, f64)]) -> Option<Vec<TimedChunk>> {
+        let chars: Vec<char> = text.trim().chars().collect();
+        // The word each character belongs to, and where each word starts.
+        let mut word_of = Vec::with_capacity(chars.len());
+        let mut word_starts = Vec::new();
+        for (i, c) in chars.iter().enumerate() {
+            if !c.is_whitespace() && (i == 0 || chars[i - 1].is_whitespace()) {
+                word_starts.push(i);
+            }
+            word_of.push(word_starts.len().saturating_sub(1));
+        }
+        if words.is_empty() || word_starts.len() != words.len() {
+            return None;
+        }
+        let starts_at = |i: usize| {
+            let word = word_of[i];
+            let (start_s, end_s) = words[word];
+            let first = word_starts[word];
+            let len = chars[first..]
+                .iter()
+                .take_while(|c| !c.is_whitespace())
+                .count();
+            start_s + (end_s - start_s) * (i - first) as f64 / len as f64
+        };
+        let ranges = self.chunk_ranges(&chars);
+        let starts: Vec<f64> = ranges.iter().map(|range| starts_at(range.start)).collect();
+        let last_end_s = words[words.len() - 1].1;
+        Some(
+            ranges
+                .into_iter()
+                .enumerate()
+                .map(|(i, range)| {
+                    let text: String = chars[range].iter().collect();
+                    let end_s = match starts.get(i + 1) {
+                        Some(&next_s) => next_s,
+                        None => last_end_s.max(starts[i] + self.read_time_s(&text)),
+                    };
+                    TimedChunk {
+                        text: text.trim().to_string(),
+                        start_s: starts[i],
+                        end_s,
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Where to end the next cue: the length of its prefix of `chars`, at most `max`.
+fn break_point(chars: &[char], max: usize) -> usize {
+    // Don't leave a sliver at the start just to end on punctuation.
+    let min = max / 3;
+    let after_punctuation = |set: &[char]| {
+        (min.max(1)..=max).rev().find(|&cut| {
+            set.contains(&chars[cut - 1])
+                && chars
+                    .get(cut)
+                    .is_none_or(|&next| next.is_whitespace() || is_cjk(chars[cut - 1]))
+        })
+    };
+    after_punctuation(&SENTENCE_ENDS)
+        .or_else(|| after_punctuation(&CLAUSE_ENDS))
+        .or_else(|| (1..=max).rev().find(|&cut| chars[cut].is_whitespace()))
+        .unwrap_or(max)
+}
+
+/// Full-width punctuation, which is followed directly by the next word.
+fn is_cjk(c: char) -> bool {
+    matches!(c, '。' | '？' | '！' | '、' | '，' | '；' | '：')
+}
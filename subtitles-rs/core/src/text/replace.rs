@@ -0,0 +1,178 @@
+//! User replacement rules (`--replace-rules`): words or phrases to rewrite in finals, e.g. to
+//! censor (`"damn": "d***"`) or fix recurring misrecognitions (`"git hub": "GitHub"`).
+//!
+//! The file is a JSON object of phrase to replacement. Matching works like the glossary's:
+//! case-insensitive for Latin script and respecting word boundaries. Rules can also be added
+//! while running, e.g. from a caption the user corrected (`correction_pairs`).
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use parking_lot::RwLock;
+
+use crate::text::filter::{TextFilter, TextSegment};
+use crate::text::glossary::replace_term;
+use crate::text::joiner::is_cjk;
+
+/// Clones share their rules, so one added later applies in every filter chain.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceRules {
+    /// Longest phrase first, so "git hub" wins over a rule for "git".
+    rules: Arc<RwLock<Vec<(String, String)>>>,
+}
+
+impl ReplaceRules {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read replacement rules {}", path.display()))?;
+        Self::from_json(&raw)
+            .with_context(|| format!("invalid replacement rules {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let raw: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+        let mut rules = Vec::with_capacity(raw.len());
+        for (from, to) in raw {
+            let serde_json::Value::String(to) = to else {
+                anyhow::bail!("replacement for `{from}` must be a string");
+            };
+            if from.trim().is_empty() {
+                anyhow::bail!("replacement rule with an empty phrase");
+            }
+            rules.push((from, to));
+        }
+        rules.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        Ok(Self {
+            rules: Arc::new(RwLock::new(rules)),
+        })
+    }
+
+    /// Adds a rule, replacing any for the same phrase (ignoring case).
+    pub fn add(&self, from: &str, to: &str) -> anyhow::Result<()> {
+        let from = from.trim();
+        if from.is_empty() {
+            anyhow::bail!("replacement rule with an empty phrase");
+        }
+        let mut rules = self.rules.write();
+        rules.retain(|(existing, _)| !existing.eq_ignore_ascii_case(from));
+        rules.push((from.to_string(), to.trim().to_string()));
+        rules.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        Ok(())
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.rules
+            .read()
+            .iter()
+            .fold(text.to_string(), |text, (from, to)| {
+                replace_term(&text, from, to)
+            })
+    }
+}
+
+impl TextFilter for ReplaceRules {
+    fn apply(&mut self, mut segment: TextSegment) -> TextSegment {
+        segment.text = ReplaceRules::apply(self, &segment.text);
+        segment
+    }
+}
+
+/// Longest run of words (or CJK characters) a suggested rule may replace.
+const MAX_PAIR_TOKENS: usize = 4;
+
+/// The phrases a correction of `original` into `corrected` replaced, as `(from, to)` rules
+/// that would make the same fix next time. Words (single characters in CJK text) are
+/// aligned, and each run of changed ones that was rewritten rather than only added or removed
+/// becomes a pair; punctuation both sides share at its edges is left out.
+pub fn correction_pairs(original: &str, corrected: &str) -> Vec<(String, String)> {
+    let a = tokens(original);
+    let b = tokens(corrected);
+    // Longest common subsequence of tokens, filled from the end.
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i].1 == b[j].1 {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i].1 == b[j].1 {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (start_a, start_b) = (i, j);
+        while (i < a.len() || j < b.len()) && !(i < a.len() && j < b.len() && a[i].1 == b[j].1) {
+            if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        let (changed_a, changed_b) = (i - start_a, j - start_b);
+        if changed_a == 0 || changed_b == 0 {
+            continue;
+        }
+        if changed_a > MAX_PAIR_TOKENS || changed_b > MAX_PAIR_TOKENS {
+            continue;
+        }
+        let from = &original[a[start_a].0..a[i - 1].0 + a[i - 1].1.len()];
+        let to = &corrected[b[start_b].0..b[j - 1].0 + b[j - 1].1.len()];
+        if let Some(pair) = trim_shared_punctuation(from, to) {
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+    }
+    pairs
+}
+
+/// Words and CJK characters of `text`, with their byte offsets.
+fn tokens(text: &str) -> Vec<(usize, &str)> {
+    let mut out = Vec::new();
+    let mut word: Option<usize> = None;
+    for (pos, c) in text.char_indices() {
+        if c.is_whitespace() || is_cjk(c) {
+            if let Some(start) = word.take() {
+                out.push((start, &text[start..pos]));
+            }
+            if !c.is_whitespace() {
+                out.push((pos, &text[pos..pos + c.len_utf8()]));
+            }
+        } else if word.is_none() {
+            word = Some(pos);
+        }
+    }
+    if let Some(start) = word {
+        out.push((start, &text[start..]));
+    }
+    out
+}
+
+fn trim_shared_punctuation(from: &str, to: &str) -> Option<(String, String)> {
+    let (mut from, mut to) = (from, to);
+    while let (Some(a), Some(b)) = (from.chars().next_back(), to.chars().next_back()) {
+        if a != b || a.is_alphanumeric() {
+            break;
+        }
+        from = &from[..from.len() - a.len_utf8()];
+        to = &to[..to.len() - b.len_utf8()];
+    }
+    while let (Some(a), Some(b)) = (from.chars().next(), to.chars().next()) {
+        if a != b || a.is_alphanumeric() {
+            break;
+        }
+        from = &from[a.len_utf8()..];
+        to = &to[b.len_utf8()..];
+    }
+    let (from, to) = (from.trim(), to.trim());
+    (!from.is_empty() && !to.is_empty() && from != to).then(|| (from.to_string(), to.to_string()))
+}
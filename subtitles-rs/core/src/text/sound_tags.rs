@@ -0,0 +1,60 @@
+//! Whisper's non-speech annotations: `[Music]`, `(laughs)`, `[BLANK_AUDIO]` and the like.
+
+use crate::config::SoundTagMode;
+
+/// Longest bracketed text (in words) still treated as a sound tag rather than a spoken aside.
+const MAX_TAG_WORDS: usize = 3;
+
+/// Marks a final where two people talked at once (`--mark-crosstalk`). It's the pipeline's own
+/// note rather than whisper's, so sinks keep it whatever their `SoundTagMode`.
+pub const CROSSTALK_TAG: &str = "[overlapping]";
+
+/// Applies one sink's `SoundTagMode` to caption text.
+#[derive(Debug, Clone)]
+pub struct SoundTagFilter {
+    mode: SoundTagMode,
+    glyph: String,
+}
+
+impl SoundTagFilter {
+    pub fn new(mode: SoundTagMode, glyph: impl Into<String>) -> Self {
+        Self {
+            mode,
+            glyph: glyph.into(),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        if self.mode == SoundTagMode::Keep || !text.contains(['[', '(']) {
+            return text.to_string();
+        }
+
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(open) = rest.find(['[', '(']) {
+            let close_char = if rest[open..].starts_with('[') { ']' } else { ')' };
+            let Some(len) = rest[open + 1..].find(close_char) else {
+                break;
+            };
+            let close = open + 1 + len;
+            out.push_str(&rest[..open]);
+            if is_sound_tag(&rest[open + 1..close]) && rest[open..=close] != *CROSSTALK_TAG {
+                if self.mode == SoundTagMode::Glyph && !out.trim_end().ends_with(&self.glyph) {
+                    out.push(' ');
+                    out.push_str(&self.glyph);
+                    out.push(' ');
+                }
+            } else {
+                out.push_str(&rest[open..=close]);
+            }
+            rest = &rest[close + 1..];
+        }
+        out.push_str(rest);
+        out.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn is_sound_tag(inner: &str) -> bool {
+    let words = inner.split_whitespace().count();
+    (1..=MAX_TAG_WORDS).contains(&words) && !inner.contains(|c: char| c.is_ascii_digit())
+}
@@ -0,0 +1,137 @@
+//! Vertical layout for CJK captions (`--caption-layout vertical`): characters run top to
+//! bottom in columns that are read right to left, the way Japanese subtitles are often set.
+
+/// Punctuation and brackets with a vertical presentation form.
+const VERTICAL_FORMS: [(char, char); 23] = [
+    ('，', '︐'),
+    ('、', '︑'),
+    ('。', '︒'),
+    ('：', '︓'),
+    ('；', '︔'),
+    ('！', '︕'),
+    ('？', '︖'),
+    ('…', '︙'),
+    ('—', '︱'),
+    ('（', '︵'),
+    ('）', '︶'),
+    ('｛', '︷'),
+    ('｝', '︸'),
+    ('【', '︻'),
+    ('】', '︼'),
+    ('《', '︽'),
+    ('》', '︾'),
+    ('〈', '︿'),
+    ('〉', '﹀'),
+    ('「', '﹁'),
+    ('」', '﹂'),
+    ('『', '﹃'),
+    ('』', '﹄'),
+];
+
+/// Characters that may not start a column (kinsoku); they hang off the end of the one before.
+const NO_COLUMN_START: [char; 14] = [
+    '、', '。', '，', '．', '）', '」', '』', '】', '》', '〉', '！', '？', 'ー', '々',
+];
+
+/// `text` with punctuation and brackets swapped for their vertical forms, for renderers that
+/// don't rotate them themselves.
+pub fn vertical_forms(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            VERTICAL_FORMS
+                .iter()
+                .find(|(from, _)| *from == c)
+                .map_or(c, |(_, to)| *to)
+        })
+        .collect()
+}
+
+/// Splits `text` into columns of at most `rows` cells, first column (the rightmost) first.
+///
+/// Each cell is one character, except that one- or two-digit numbers share a cell
+/// (tate-chu-yoko). Line breaks start a new column and spaces are dropped. Closing punctuation
+/// that would start a column hangs at the end of the previous one instead, so a column can hold
+/// `rows + 1` cells.
+pub fn vertical_columns(text: &str, rows: usize) -> Vec<Vec<String>> {
+    let rows = rows.max(1);
+    let mut columns: Vec<Vec<String>> = Vec::new();
+    for line in text.lines() {
+        let mut column: Vec<String> = Vec::new();
+        for cell in cells(line) {
+            if column.len() >= rows {
+                let hangs = cell.chars().all(|c| NO_COLUMN_START.contains(&c));
+                if column.len() == rows && hangs {
+                    column.push(cell);
+                    continue;
+                }
+                columns.push(std::mem::take(&mut column));
+            }
+            column.push(cell);
+        }
+        if !column.is_empty() {
+            columns.push(column);
+        }
+    }
+    columns
+}
+
+fn cells(line: &str) -> Vec<String> {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let digits = chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if (1..=2).contains(&digits) {
+            out.push(chars[i..i + digits].iter().collect());
+            i += digits;
+        } else {
+            // Longer numbers go one digit per cell.
+            let take = digits.max(1);
+            out.extend(chars[i..i + take].iter().map(char::to_string));
+            i += take;
+        }
+    }
+    out
+}
+
+/// Draws `vertical_columns` as plain text for a terminal: one line per row, columns right to
+/// left, each cell two columns wide (full-width characters, or narrow ones padded).
+pub fn render_vertical(text: &str, rows: usize) -> String {
+    let columns = vertical_columns(text, rows);
+    let height = columns.iter().map(Vec::len).max().unwrap_or(0);
+    (0..height)
+        .map(|row| {
+            let line: String = columns
+                .iter()
+                .rev()
+                .map(|column| match column.get(row) {
+                    Some(cell) => {
+                        let cell = vertical_forms(cell);
+                        if cell.chars().any(is_wide) {
+                            cell
+                        } else {
+                            format!("{cell:<2}")
+                        }
+                    }
+                    None => "  ".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Characters a terminal draws two columns wide.
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE10}'..='\u{FE19}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}')
+}
@@ -0,0 +1,80 @@
+//! Word timings for finals (`--word-timings`). whisper.cpp aligns every decoded token to the
+//! audio with DTW over its cross-attention, the way whisper-timestamped does, so each word gets
+//! its own time instead of sharing its segment's coarse one. Here the timed tokens are grouped
+//! back into words.
+
+use crate::text::joiner::is_cjk;
+
+/// A decoded token and where DTW put it, in seconds from the start of the decoded audio.
+/// Special tokens (end of text, timestamps) have no text and close the word before them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignedToken {
+    /// Raw bytes: a multi-byte character (Chinese, emoji) can be split across tokens.
+    pub bytes: Vec<u8>,
+    pub time_s: f32,
+}
+
+/// A word and when it was spoken, in seconds from the start of the decoded audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_s: f32,
+    pub end_s: f32,
+}
+
+/// Groups tokens into words at leading spaces, each running from its first token to the next
+/// word or special token; the last runs to `end_s`, the end of the audio, unless a special
+/// token closes it first. Chinese and Japanese, written without spaces, get a word per token
+/// instead, once its characters are whole. Times never go backwards, even where DTW wobbles.
+pub fn words_from_tokens(tokens: &[AlignedToken], end_s: f32) -> Vec<WordTiming> {
+    let mut words = Vec::new();
+    let mut word: Option<(Vec<u8>, f32)> = None;
+    let mut last_s = 0.0f32;
+    let mut close = |word: &mut Option<(Vec<u8>, f32)>, until_s: f32| {
+        if let Some((bytes, start_s)) = word.take() {
+            let text = String::from_utf8_lossy(&bytes).trim().to_string();
+            if !text.is_empty() {
+                words.push(WordTiming {
+                    text,
+                    start_s,
+                    end_s: until_s.max(start_s),
+                });
+            }
+        }
+    };
+    for token in tokens {
+        let time_s = token.time_s.max(last_s);
+        last_s = time_s;
+        let starts_word = token.bytes.first().is_some_and(u8::is_ascii_whitespace)
+            || word
+                .as_ref()
+                .is_some_and(|(bytes, _)| starts_unspaced_word(bytes, &token.bytes));
+        if token.bytes.is_empty() || starts_word {
+            close(&mut word, time_s);
+        }
+        if !token.bytes.is_empty() {
+            word.get_or_insert_with(|| (Vec::new(), time_s))
+                .0
+                .extend_from_slice(&token.bytes);
+        }
+    }
+    close(&mut word, end_s.max(last_s));
+    words
+}
+
+/// Whether `token` starts a new word after `word` in unspaced text: `word` ends in a whole
+/// Han or kana character (or full-width punctuation), or `token` starts with one.
+/// Punctuation stays with the word before it.
+fn starts_unspaced_word(word: &[u8], token: &[u8]) -> bool {
+    let Ok(word) = std::str::from_utf8(word) else {
+        // The token finishes a character.
+        return false;
+    };
+    let is_letter = |c: char| is_cjk(c) && c.is_alphanumeric();
+    match String::from_utf8_lossy(token).chars().next() {
+        Some(next) if next.is_alphanumeric() || next == char::REPLACEMENT_CHARACTER => {
+            word.chars().next_back().is_some_and(is_cjk) || is_letter(next)
+        }
+        _ => false,
+    }
+}
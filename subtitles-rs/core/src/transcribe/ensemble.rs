@@ -0,0 +1,142 @@
+//! Ensemble decoding (`--ensemble`): finals go to a local and a cloud engine at once and the
+//! better result is kept, judged by how much the two agree, each engine's confidence and
+//! signs of a decoding loop. Partials only use the local engine, so latency is unchanged.
+
+use anyhow::anyhow;
+
+use crate::text::memory::{edit_distance, normalize};
+use crate::transcribe::{Transcriber, TranscriberConfig, Transcript};
+
+/// Confidence assumed for cloud results, which don't report one: a little below a clean local
+/// decode, so a sure local result wins ties and an unsure one loses.
+pub const CLOUD_CONFIDENCE: f32 = 0.8;
+/// Confidence assumed for a local result without one.
+const LOCAL_CONFIDENCE: f32 = 0.5;
+/// Results at least this alike (1 - edit distance / length) are taken to agree.
+pub const AGREEMENT: f32 = 0.8;
+/// Taken off a result that repeats itself, the usual way whisper fails on noise or music.
+const LOOP_PENALTY: f32 = 0.3;
+/// Identical words in a row that count as a loop (twice as many characters for text without
+/// spaces, where "哈哈哈哈" is just laughter).
+const LOOP_RUN: usize = 4;
+
+pub struct EnsembleTranscriber {
+    local: Box<dyn Transcriber>,
+    cloud: Box<dyn Transcriber>,
+}
+
+impl EnsembleTranscriber {
+    pub fn new(local: Box<dyn Transcriber>, cloud: Box<dyn Transcriber>) -> Self {
+        Self { local, cloud }
+    }
+}
+
+impl Transcriber for EnsembleTranscriber {
+    fn transcribe(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<String> {
+        Ok(self.transcribe_detailed(audio_16k_mono, cfg)?.text)
+    }
+
+    fn transcribe_detailed(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<Transcript> {
+        if cfg.is_partial {
+            return self.local.transcribe_detailed(audio_16k_mono, cfg);
+        }
+        let cloud = &mut self.cloud;
+        let local = &mut self.local;
+        let (local, cloud) = std::thread::scope(|scope| {
+            let cloud = scope.spawn(|| cloud.transcribe_detailed(audio_16k_mono, cfg));
+            let local = local.transcribe_detailed(audio_16k_mono, cfg);
+            let cloud = cloud
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("cloud transcriber panicked")));
+            (local, cloud)
+        });
+        match (local, cloud) {
+            // Text in another language can't be weighed against a translation.
+            (Ok(local), Ok(cloud)) if cloud.untranslated && !local.untranslated => {
+                tracing::warn!("ensemble: cloud couldn't translate, using local");
+                Ok(local)
+            }
+            (Ok(local), Ok(cloud)) => Ok(choose(local, cloud)),
+            (Ok(local), Err(err)) => {
+                tracing::warn!("ensemble: cloud decode failed, using local: {err:#}");
+                Ok(local)
+            }
+            (Err(err), Ok(cloud)) => {
+                tracing::warn!("ensemble: local decode failed, using cloud: {err:#}");
+                Ok(cloud)
+            }
+            (Err(err), Err(_)) => Err(err),
+        }
+    }
+}
+
+/// The better of two decodes of the same audio. Its confidence is raised when the engines
+/// agree and lowered when they don't, so a disputed final is still flagged (e.g. for the
+/// second pass). Speaker turns always come from the local decode.
+pub fn choose(local: Transcript, cloud: Transcript) -> Transcript {
+    let agreement = agreement(&local.text, &cloud.text);
+    let local_score = score(&local.text, local.confidence.unwrap_or(LOCAL_CONFIDENCE));
+    let cloud_score = score(&cloud.text, cloud.confidence.unwrap_or(CLOUD_CONFIDENCE));
+    let speaker_turns = local.speaker_turns;
+    let (mut best, best_score, other_score) = if cloud_score > local_score {
+        (cloud, cloud_score, local_score)
+    } else {
+        (local, local_score, cloud_score)
+    };
+    tracing::debug!(
+        "ensemble: agreement {agreement:.2}, local {local_score:.2}, cloud {cloud_score:.2}"
+    );
+    best.speaker_turns = speaker_turns;
+    best.confidence = Some(if agreement >= AGREEMENT {
+        // Two engines independently landing on the same words.
+        1.0 - (1.0 - best_score) * (1.0 - other_score.max(0.0))
+    } else {
+        best_score * agreement.max(0.5)
+    });
+    best
+}
+
+/// How alike two results are, ignoring case, spacing and punctuation (1.0 when both are empty).
+pub fn agreement(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f32 / longest as f32
+}
+
+fn score(text: &str, confidence: f32) -> f32 {
+    if normalize(text).is_empty() {
+        return 0.0;
+    }
+    if is_looping(text) {
+        confidence - LOOP_PENALTY
+    } else {
+        confidence
+    }
+}
+
+fn is_looping(text: &str) -> bool {
+    let (tokens, run): (Vec<String>, usize) = if text.split_whitespace().nth(1).is_some() {
+        let words = text
+            .split_whitespace()
+            .map(|word| String::from_iter(normalize(word)))
+            .filter(|word| !word.is_empty());
+        (words.collect(), LOOP_RUN)
+    } else {
+        let chars = normalize(text).into_iter().map(String::from);
+        (chars.collect(), LOOP_RUN * 2)
+    };
+    tokens
+        .windows(run)
+        .any(|run| run.iter().all(|token| *token == run[0]))
+}
@@ -0,0 +1,294 @@
+//! Checks that a model file is a whisper.cpp ggml model whisper.cpp can actually run before it
+//! is loaded: the header, the architecture, and every tensor's name, shape and size. Fine-tuned
+//! models converted from other checkpoints fail here with the reason, instead of a bare "failed
+//! to load" (or a crash) from whisper.cpp.
+//!
+//! LoRA adapters can't be applied at load time; merge them into the base model and convert the
+//! result to ggml first.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use anyhow::Context;
+
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+const GGUF_MAGIC: u32 = 0x4655_4747;
+/// llama.cpp's standalone LoRA adapter files.
+const GGLA_MAGIC: u32 = 0x6767_6c61;
+/// Tokens in the English-only vocabulary; multilingual models have more.
+const ENGLISH_VOCAB: i32 = 51_864;
+
+/// What a model file declares, once it has been checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhisperModelInfo {
+    pub n_vocab: i32,
+    pub n_mels: i32,
+    pub n_state: i32,
+    pub n_audio_layer: i32,
+    pub n_text_layer: i32,
+    pub tensors: usize,
+}
+
+impl WhisperModelInfo {
+    /// English-only models can't transcribe or translate other languages.
+    pub fn multilingual(&self) -> bool {
+        self.n_vocab > ENGLISH_VOCAB
+    }
+
+    /// The stock model this was fine-tuned from, judging by its size.
+    pub fn architecture(&self) -> &'static str {
+        match (self.n_audio_layer, self.n_text_layer) {
+            (4, _) => "tiny",
+            (6, _) => "base",
+            (12, _) => "small",
+            (24, _) => "medium",
+            (32, 4) => "large-v3-turbo",
+            (32, 2) => "distil-large",
+            (32, _) => "large",
+            _ => "custom",
+        }
+    }
+}
+
+/// Reads `path`'s header and tensor table (tensor data is skipped, not read).
+pub fn inspect_model(path: &Path) -> anyhow::Result<WhisperModelInfo> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let len = file.metadata()?.len();
+    inspect(BufReader::new(file), len)
+        .with_context(|| format!("{} is not a usable whisper model", path.display()))
+}
+
+fn inspect(mut r: BufReader<File>, len: u64) -> anyhow::Result<WhisperModelInfo> {
+    match read_u32(&mut r).context("file is empty")? {
+        GGML_MAGIC => {}
+        GGUF_MAGIC => {
+            anyhow::bail!("GGUF files aren't supported; convert with whisper.cpp's scripts")
+        }
+        GGLA_MAGIC => anyhow::bail!(
+            "this is a LoRA adapter; merge it into its base model and convert that to ggml"
+        ),
+        _ => anyhow::bail!("not a ggml file (bad magic)"),
+    }
+
+    let mut hp = [0i32; 11];
+    for value in &mut hp {
+        *value = read_i32(&mut r).context("truncated header")?;
+    }
+    let [n_vocab, n_audio_ctx, n_audio_state, n_audio_head, n_audio_layer, ..] = hp;
+    let [.., n_text_ctx, n_text_state, n_text_head, n_text_layer, n_mels, _ftype] = hp;
+    anyhow::ensure!(
+        hp[..10].iter().all(|&v| v > 0),
+        "header has non-positive sizes: {hp:?}"
+    );
+    anyhow::ensure!(
+        n_mels == 80 || n_mels == 128,
+        "{n_mels} mel bins; whisper uses 80 or 128"
+    );
+    anyhow::ensure!(
+        n_vocab >= ENGLISH_VOCAB,
+        "vocabulary of {n_vocab} tokens; whisper's tokenizer has at least {ENGLISH_VOCAB} (was it \
+         fine-tuned with a different tokenizer?)"
+    );
+    anyhow::ensure!(
+        n_audio_state == n_text_state,
+        "encoder width {n_audio_state} differs from decoder width {n_text_state}"
+    );
+    anyhow::ensure!(
+        n_audio_state % n_audio_head == 0 && n_text_state % n_text_head == 0,
+        "width {n_audio_state} doesn't divide into {n_audio_head}/{n_text_head} heads"
+    );
+
+    // Mel filterbank, then the vocabulary.
+    let filters_mels = read_i32(&mut r)?;
+    let filters_fft = read_i32(&mut r)?;
+    skip(
+        &mut r,
+        filters_mels.max(0) as i64 * filters_fft.max(0) as i64 * 4,
+    )?;
+    let words = read_i32(&mut r).context("truncated vocabulary")?;
+    for _ in 0..words.max(0) {
+        let word_len = read_u32(&mut r).context("truncated vocabulary")?;
+        skip(&mut r, word_len as i64)?;
+    }
+
+    let n_state = n_audio_state as i64;
+    let mut expected = expected_tensors(n_audio_layer, n_text_layer);
+    let mut seen = HashSet::new();
+    loop {
+        let position = r.stream_position()?;
+        if position == len {
+            break;
+        }
+        let truncated = || format!("truncated tensor header at byte {position}");
+        let n_dims = read_i32(&mut r).with_context(truncated)?;
+        let name_len = read_i32(&mut r).with_context(truncated)?;
+        let ttype = read_i32(&mut r).with_context(truncated)?;
+        anyhow::ensure!(
+            (1..=4).contains(&n_dims) && (1..=256).contains(&name_len),
+            "corrupt tensor header at byte {position}"
+        );
+        let mut dims = Vec::with_capacity(n_dims as usize);
+        for _ in 0..n_dims {
+            dims.push(read_i32(&mut r).with_context(truncated)? as i64);
+        }
+        let mut name = vec![0; name_len as usize];
+        r.read_exact(&mut name).with_context(truncated)?;
+        let name = String::from_utf8_lossy(&name).into_owned();
+
+        if !expected.remove(&name) {
+            if seen.contains(&name) {
+                anyhow::bail!("tensor `{name}` appears twice");
+            }
+            anyhow::bail!(
+                "unexpected tensor `{name}` for a {n_audio_layer}+{n_text_layer} layer model \
+                 (an unmerged adapter, or a different architecture?)"
+            );
+        }
+        let want: Option<Vec<i64>> = match name.as_str() {
+            "encoder.positional_embedding" => Some(vec![n_state, n_audio_ctx as i64]),
+            "encoder.conv1.weight" => Some(vec![3, n_mels as i64, n_state]),
+            "decoder.positional_embedding" => Some(vec![n_state, n_text_ctx as i64]),
+            "decoder.token_embedding.weight" => Some(vec![n_state, n_vocab as i64]),
+            name if name.ends_with("attn.query.weight")
+                || name.ends_with("attn.key.weight")
+                || name.ends_with("attn.value.weight")
+                || name.ends_with("attn.out.weight") =>
+            {
+                Some(vec![n_state, n_state])
+            }
+            name if name.ends_with("mlp.0.weight") => Some(vec![n_state, 4 * n_state]),
+            name if name.ends_with("mlp.2.weight") => Some(vec![4 * n_state, n_state]),
+            _ => None,
+        };
+        if let Some(want) = want {
+            anyhow::ensure!(
+                dims == want,
+                "tensor `{name}` has shape {dims:?}, expected {want:?}"
+            );
+        }
+
+        let elements: i64 = dims.iter().product();
+        let (block_bytes, block) = type_size(ttype)
+            .with_context(|| format!("tensor `{name}` has unsupported type {ttype}"))?;
+        anyhow::ensure!(
+            elements % block == 0,
+            "tensor `{name}` has {elements} elements, not a multiple of its block size {block}"
+        );
+        let bytes = elements / block * block_bytes;
+        anyhow::ensure!(
+            r.stream_position()? + bytes as u64 <= len,
+            "tensor `{name}` runs past the end of the file (truncated download?)"
+        );
+        skip(&mut r, bytes)?;
+        seen.insert(name);
+    }
+
+    if !expected.is_empty() {
+        let mut missing: Vec<_> = expected.into_iter().collect();
+        missing.sort();
+        anyhow::bail!("{} tensor(s) missing, e.g. `{}`", missing.len(), missing[0]);
+    }
+    Ok(WhisperModelInfo {
+        n_vocab,
+        n_mels,
+        n_state: n_audio_state,
+        n_audio_layer,
+        n_text_layer,
+        tensors: seen.len(),
+    })
+}
+
+/// Every tensor whisper.cpp loads for a model with these layer counts.
+fn expected_tensors(audio_layers: i32, text_layers: i32) -> HashSet<String> {
+    const BLOCK: [&str; 15] = [
+        "mlp_ln.weight",
+        "mlp_ln.bias",
+        "mlp.0.weight",
+        "mlp.0.bias",
+        "mlp.2.weight",
+        "mlp.2.bias",
+        "attn_ln.weight",
+        "attn_ln.bias",
+        "attn.query.weight",
+        "attn.query.bias",
+        "attn.key.weight",
+        "attn.value.weight",
+        "attn.value.bias",
+        "attn.out.weight",
+        "attn.out.bias",
+    ];
+    const CROSS: [&str; 9] = [
+        "cross_attn_ln.weight",
+        "cross_attn_ln.bias",
+        "cross_attn.query.weight",
+        "cross_attn.query.bias",
+        "cross_attn.key.weight",
+        "cross_attn.value.weight",
+        "cross_attn.value.bias",
+        "cross_attn.out.weight",
+        "cross_attn.out.bias",
+    ];
+    let mut names: HashSet<String> = [
+        "encoder.positional_embedding",
+        "encoder.conv1.weight",
+        "encoder.conv1.bias",
+        "encoder.conv2.weight",
+        "encoder.conv2.bias",
+        "encoder.ln_post.weight",
+        "encoder.ln_post.bias",
+        "decoder.positional_embedding",
+        "decoder.token_embedding.weight",
+        "decoder.ln.weight",
+        "decoder.ln.bias",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    for i in 0..audio_layers {
+        names.extend(BLOCK.iter().map(|t| format!("encoder.blocks.{i}.{t}")));
+    }
+    for i in 0..text_layers {
+        names.extend(
+            BLOCK
+                .iter()
+                .chain(&CROSS)
+                .map(|t| format!("decoder.blocks.{i}.{t}")),
+        );
+    }
+    names
+}
+
+/// Bytes per block and elements per block of a ggml tensor type.
+fn type_size(ttype: i32) -> Option<(i64, i64)> {
+    Some(match ttype {
+        0 => (4, 1),      // f32
+        1 => (2, 1),      // f16
+        2 => (18, 32),    // q4_0
+        3 => (20, 32),    // q4_1
+        6 => (22, 32),    // q5_0
+        7 => (24, 32),    // q5_1
+        8 => (34, 32),    // q8_0
+        10 => (84, 256),  // q2_k
+        11 => (110, 256), // q3_k
+        12 => (144, 256), // q4_k
+        13 => (176, 256), // q5_k
+        14 => (210, 256), // q6_k
+        _ => return None,
+    })
+}
+
+fn read_u32(r: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_i32(r: &mut impl Read) -> std::io::Result<i32> {
+    read_u32(r).map(|v| v as i32)
+}
+
+fn skip(r: &mut BufReader<File>, bytes: i64) -> std::io::Result<()> {
+    r.seek_relative(bytes)
+}
@@ -0,0 +1,279 @@
+//! Bilingual decoding (`--language-pair`): two transcribers, each fixed to one of two input
+//! languages, decode every final side by side and the one in the language detected in the
+//! audio is kept. Auto-detect alone flip-flops mid-stream in a bilingual meeting and decodes
+//! English as garbled Japanese (or the reverse); a fixed language can't, and picking between
+//! two finished decodes only ever chooses between the two languages actually spoken.
+//! Partials use whichever language the last final was in, so latency is unchanged.
+//!
+//! With code-switching on, a final is first split where the detected language changes (one
+//! detection per second of audio) and each part is decoded in its own language, so a sentence
+//! that switches halfway isn't half translated and half transliterated.
+
+use anyhow::anyhow;
+
+use crate::transcribe::{Transcriber, TranscriberConfig, Transcript, TranscriptSegment, WordTiming};
+
+const SAMPLE_RATE: usize = 16_000;
+/// Audio per language detection when looking for switches.
+const SWITCH_WINDOW: usize = SAMPLE_RATE;
+/// A switch lasting fewer windows than this is taken for a detection error.
+const MIN_SWITCH_WINDOWS: usize = 2;
+/// How far a cut may move from a window boundary to land in the quietest 20 ms.
+const CUT_SEARCH: usize = SAMPLE_RATE / 4;
+const CUT_FRAME: usize = SAMPLE_RATE / 50;
+
+pub struct LanguagePairTranscriber {
+    languages: [String; 2],
+    engines: [Box<dyn Transcriber>; 2],
+    /// The engine the last final picked, which decodes partials.
+    current: usize,
+    code_switching: bool,
+}
+
+impl LanguagePairTranscriber {
+    /// `first` decodes as `languages[0]` and starts out decoding partials.
+    pub fn new(
+        languages: [String; 2],
+        first: Box<dyn Transcriber>,
+        second: Box<dyn Transcriber>,
+    ) -> Self {
+        Self {
+            languages,
+            engines: [first, second],
+            current: 0,
+            code_switching: false,
+        }
+    }
+
+    /// Splits finals where the language changes and decodes each part in its own language.
+    pub fn with_code_switching(mut self, enabled: bool) -> Self {
+        self.code_switching = enabled;
+        self
+    }
+
+    fn config_for(&self, engine: usize, cfg: &TranscriberConfig) -> TranscriberConfig {
+        TranscriberConfig {
+            input_language: Some(self.languages[engine].clone()),
+            ..cfg.clone()
+        }
+    }
+}
+
+impl Transcriber for LanguagePairTranscriber {
+    fn transcribe(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<String> {
+        Ok(self.transcribe_detailed(audio_16k_mono, cfg)?.text)
+    }
+
+    fn transcribe_detailed(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<Transcript> {
+        if cfg.is_partial {
+            let cfg = self.config_for(self.current, cfg);
+            return self.engines[self.current].transcribe_detailed(audio_16k_mono, &cfg);
+        }
+        if self.code_switching {
+            if let Some(transcript) = self.transcribe_switches(audio_16k_mono, cfg)? {
+                return Ok(transcript);
+            }
+        }
+        let cfgs = [self.config_for(0, cfg), self.config_for(1, cfg)];
+        let languages = &self.languages;
+        let [first, second] = &mut self.engines;
+        let (first, detected, second) = std::thread::scope(|scope| {
+            let second = scope.spawn(|| second.transcribe_detailed(audio_16k_mono, &cfgs[1]));
+            let first_result = first.transcribe_detailed(audio_16k_mono, &cfgs[0]);
+            let detected = first.detect_language(audio_16k_mono, languages);
+            let second = second
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("{} transcriber panicked", languages[1])));
+            (first_result, detected, second)
+        });
+        let pick = match (&first, &second) {
+            (Ok(_), Err(err)) => {
+                tracing::warn!("language pair: {} decode failed: {err:#}", languages[1]);
+                0
+            }
+            (Err(err), Ok(_)) => {
+                tracing::warn!("language pair: {} decode failed: {err:#}", languages[0]);
+                1
+            }
+            (Err(_), Err(_)) => return first,
+            (Ok(first), Ok(second)) => pick_language(languages, detected.as_deref(), first, second),
+        };
+        tracing::debug!(
+            "language pair: detected {}, keeping the {} decode",
+            detected.as_deref().unwrap_or("nothing"),
+            languages[pick]
+        );
+        self.current = pick;
+        if pick == 0 {
+            first
+        } else {
+            second
+        }
+    }
+}
+
+impl LanguagePairTranscriber {
+    /// Decodes a final that switches language part by part, or `None` when it doesn't switch
+    /// (or detection isn't available) and the whole-utterance path should decide.
+    fn transcribe_switches(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<Option<Transcript>> {
+        if audio_16k_mono.len() < 2 * MIN_SWITCH_WINDOWS * SWITCH_WINDOW {
+            return Ok(None);
+        }
+        let languages = self.languages.clone();
+        let detected: Vec<Option<usize>> = audio_16k_mono
+            .chunks(SWITCH_WINDOW)
+            .map(|window| {
+                let language = self.engines[0].detect_language(window, &languages)?;
+                languages
+                    .iter()
+                    .position(|candidate| candidate.eq_ignore_ascii_case(&language))
+            })
+            .collect();
+        let runs = language_runs(&detected, MIN_SWITCH_WINDOWS);
+        if runs.len() < 2 {
+            return Ok(None);
+        }
+        tracing::debug!(
+            "language pair: {} switches in a {:.1} s final",
+            runs.len() - 1,
+            audio_16k_mono.len() as f32 / SAMPLE_RATE as f32
+        );
+
+        let mut cuts = vec![0];
+        cuts.extend(
+            runs.iter()
+                .skip(1)
+                .map(|run| quietest_cut(audio_16k_mono, run.0 * SWITCH_WINDOW)),
+        );
+        cuts.push(audio_16k_mono.len());
+
+        let mut merged = Transcript::default();
+        let mut confidences = Vec::new();
+        for (run, span) in runs.iter().zip(cuts.windows(2)) {
+            let (start, end) = (span[0], span[1]);
+            let engine = run.1;
+            let cfg = self.config_for(engine, cfg);
+            let part =
+                self.engines[engine].transcribe_detailed(&audio_16k_mono[start..end], &cfg)?;
+            let offset_s = start as f32 / SAMPLE_RATE as f32;
+            merged
+                .segments
+                .extend(part.segments.into_iter().map(|segment| TranscriptSegment {
+                    start_s: segment.start_s + offset_s,
+                    end_s: segment.end_s + offset_s,
+                    ..segment
+                }));
+            merged
+                .words
+                .extend(part.words.into_iter().map(|word| WordTiming {
+                    start_s: word.start_s + offset_s,
+                    end_s: word.end_s + offset_s,
+                    ..word
+                }));
+            let text = part.text.trim();
+            if !text.is_empty() {
+                if !merged.text.is_empty() {
+                    merged.text.push(' ');
+                }
+                merged.text.push_str(text);
+            }
+            confidences.extend(part.confidence);
+            merged.speaker_turns += part.speaker_turns;
+            merged.untranslated |= part.untranslated;
+            self.current = engine;
+        }
+        merged.confidence = (!confidences.is_empty())
+            .then(|| confidences.iter().sum::<f32>() / confidences.len() as f32);
+        Ok(Some(merged))
+    }
+}
+
+/// Groups per-window detections into `(first window, language)` runs. A window nothing was
+/// detected in, and a run shorter than `min_windows` (a misdetection more often than a
+/// switch), belong to the run before it, or the one after at the start.
+pub fn language_runs(detected: &[Option<usize>], min_windows: usize) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+    for (window, language) in detected.iter().enumerate() {
+        match (runs.last_mut(), *language) {
+            (Some(run), None) => run.2 += 1,
+            (Some(run), Some(language)) if run.1 == language => run.2 += 1,
+            (_, Some(language)) => runs.push((window, language, 1)),
+            (None, None) => {}
+        }
+    }
+    // Fold short runs into a neighbor, then join neighbors left in the same language.
+    let mut kept: Vec<(usize, usize, usize)> = Vec::new();
+    for run in runs {
+        match kept.last_mut() {
+            Some(last) if run.2 < min_windows || last.1 == run.1 => last.2 += run.2,
+            _ => kept.push(run),
+        }
+    }
+    if kept.len() > 1 && kept[0].2 < min_windows {
+        let first = kept.remove(0);
+        kept[0].0 = first.0;
+        kept[0].2 += first.2;
+    }
+    kept.into_iter()
+        .map(|(start, language, _)| (start, language))
+        .collect()
+}
+
+/// The start of the quietest 20 ms frame within a quarter second of `around`, so a cut falls
+/// between words rather than through one.
+fn quietest_cut(audio: &[f32], around: usize) -> usize {
+    let from = around.saturating_sub(CUT_SEARCH);
+    let to = (around + CUT_SEARCH).min(audio.len().saturating_sub(CUT_FRAME));
+    (from..=to.max(from))
+        .step_by(CUT_FRAME / 2)
+        .min_by(|a, b| {
+            let energy = |start: usize| -> f32 {
+                audio[start..(start + CUT_FRAME).min(audio.len())]
+                    .iter()
+                    .map(|sample| sample * sample)
+                    .sum()
+            };
+            energy(*a).total_cmp(&energy(*b))
+        })
+        .unwrap_or(around)
+}
+
+/// Which of two decodes to keep: the one in the `detected` language, else (detection
+/// unavailable) the more confident one. An empty decode never beats one with words.
+pub fn pick_language(
+    languages: &[String; 2],
+    detected: Option<&str>,
+    first: &Transcript,
+    second: &Transcript,
+) -> usize {
+    match (first.text.trim().is_empty(), second.text.trim().is_empty()) {
+        (true, false) => return 1,
+        (false, true) => return 0,
+        _ => {}
+    }
+    if let Some(index) = detected.and_then(|detected| {
+        languages
+            .iter()
+            .position(|language| language.eq_ignore_ascii_case(detected))
+    }) {
+        return index;
+    }
+    let confidence = |transcript: &Transcript| transcript.confidence.unwrap_or(0.0);
+    if confidence(second) > confidence(first) {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,343 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use anyhow::Context;
+use parking_lot::Mutex;
+use whisper_rs::{
+    DtwMode, DtwModelPreset, DtwParameters, FullParams, SamplingStrategy, WhisperContext,
+    WhisperContextParameters,
+};
+
+use crate::config::OutputLanguage;
+use crate::config::WhisperModelPreset;
+use crate::transcribe::model_download::{estimated_gpu_memory_mb, resolve_whisper_model_path};
+use crate::transcribe::{
+    inspect_model, words_from_tokens, AlignedToken, DownloadProgress, Transcriber,
+    TranscriberConfig, Transcript, TranscriptSegment,
+};
+
+/// Loaded models, keyed by canonical path. Every transcriber for the same model file shares
+/// one `WhisperContext` (weights + Metal buffers) and only owns its decoding state, so running
+/// a partial and a final decoder side by side doesn't double GPU memory.
+static CONTEXT_POOL: Mutex<Vec<PooledContext>> = Mutex::new(Vec::new());
+
+struct PooledContext {
+    path: PathBuf,
+    ctx: Weak<WhisperContext>,
+    /// Estimated GPU memory the model takes; 0 on the CPU.
+    gpu_mb: u64,
+}
+
+/// `--gpu-memory-limit-mb`, 0 for none.
+static GPU_MEMORY_LIMIT_MB: AtomicU64 = AtomicU64::new(0);
+
+/// Models expected to need more GPU memory than is left of `limit_mb`, after the models
+/// already loaded (live, second pass, shadow), load without Metal offload (on the CPU),
+/// leaving the GPU to other apps. Applies to models loaded from now on.
+pub fn set_gpu_memory_limit_mb(limit_mb: Option<u64>) {
+    GPU_MEMORY_LIMIT_MB.store(limit_mb.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// `--word-timings`.
+static WORD_ALIGNMENT: AtomicBool = AtomicBool::new(false);
+
+/// Text layers whose attention heads DTW aligns with, for models that aren't a known preset.
+const ALIGNMENT_LAYERS: i32 = 2;
+
+/// Models load with DTW token alignment, and their finals carry word timings. Applies to
+/// models loaded from now on. whisper.cpp aligns every decode of such a model, partials
+/// included, which adds a little to each.
+pub fn set_word_alignment(enabled: bool) {
+    WORD_ALIGNMENT.store(enabled, Ordering::Relaxed);
+}
+
+fn shared_context(model_path: &Path) -> anyhow::Result<Arc<WhisperContext>> {
+    let key = model_path
+        .canonicalize()
+        .unwrap_or_else(|_| model_path.to_path_buf());
+
+    let mut pool = CONTEXT_POOL.lock();
+    pool.retain(|pooled| pooled.ctx.strong_count() > 0);
+    if let Some(ctx) = pool
+        .iter()
+        .find(|pooled| pooled.path == key)
+        .and_then(|pooled| pooled.ctx.upgrade())
+    {
+        tracing::info!("reusing loaded whisper model: {}", model_path.display());
+        return Ok(ctx);
+    }
+
+    let info = inspect_model(model_path)?;
+    tracing::info!(
+        "loading whisper model: {} ({}, {} tensors)",
+        model_path.display(),
+        info.architecture(),
+        info.tensors
+    );
+    let mut params = WhisperContextParameters::default();
+    let limit_mb = GPU_MEMORY_LIMIT_MB.load(Ordering::Relaxed);
+    let mut needed_mb = std::fs::metadata(model_path)
+        .map(|meta| estimated_gpu_memory_mb(meta.len()))
+        .unwrap_or(0);
+    let in_use_mb: u64 = pool.iter().map(|pooled| pooled.gpu_mb).sum();
+    if limit_mb > 0 && in_use_mb + needed_mb > limit_mb {
+        tracing::warn!(
+            "the model needs about {needed_mb} MB of GPU memory, with {in_use_mb} MB taken by \
+             other models over --gpu-memory-limit-mb {limit_mb}: running it on the CPU"
+        );
+        params.use_gpu(false);
+        needed_mb = 0;
+    }
+    if WORD_ALIGNMENT.load(Ordering::Relaxed) {
+        params.dtw_parameters(DtwParameters {
+            mode: dtw_mode(model_path),
+            ..DtwParameters::default()
+        });
+    }
+    let ctx = WhisperContext::new_with_params(
+        model_path
+            .to_str()
+            .context("model path is not valid UTF-8")?,
+        params,
+    )
+    .context("failed to load whisper model")?;
+    let ctx = Arc::new(ctx);
+    pool.push(PooledContext {
+        path: key,
+        ctx: Arc::downgrade(&ctx),
+        gpu_mb: needed_mb,
+    });
+    Ok(ctx)
+}
+
+/// The alignment heads of the OpenAI model `model_path` was converted from, known from its
+/// file name (`ggml-base.en.bin`, quantized `ggml-small-q5_1.bin`); other models align with
+/// the top `ALIGNMENT_LAYERS` text layers.
+fn dtw_mode(model_path: &Path) -> DtwMode {
+    let name = model_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    let name = name.strip_prefix("ggml-").unwrap_or(&name);
+    // Quantization doesn't change the heads.
+    let name = match name.find("-q") {
+        Some(at) => &name[..at],
+        None => name,
+    };
+    let model_preset = match name {
+        "tiny.en" => DtwModelPreset::TinyEn,
+        "tiny" => DtwModelPreset::Tiny,
+        "base.en" => DtwModelPreset::BaseEn,
+        "base" => DtwModelPreset::Base,
+        "small.en" => DtwModelPreset::SmallEn,
+        "small" => DtwModelPreset::Small,
+        "medium.en" => DtwModelPreset::MediumEn,
+        "medium" => DtwModelPreset::Medium,
+        "large-v1" => DtwModelPreset::LargeV1,
+        "large-v2" => DtwModelPreset::LargeV2,
+        "large-v3" => DtwModelPreset::LargeV3,
+        "large-v3-turbo" => DtwModelPreset::LargeV3Turbo,
+        _ => {
+            return DtwMode::TopMost {
+                n_top: ALIGNMENT_LAYERS,
+            }
+        }
+    };
+    DtwMode::ModelPreset { model_preset }
+}
+
+pub struct WhisperLocalTranscriber {
+    ctx: Arc<WhisperContext>,
+    state: whisper_rs::WhisperState,
+    n_threads: i32,
+}
+
+impl WhisperLocalTranscriber {
+    pub fn new(
+        model_path: Option<PathBuf>,
+        preset: WhisperModelPreset,
+        whisper_threads: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        Self::new_with_progress(model_path, preset, whisper_threads, &mut |_| {})
+    }
+
+    /// Like `new`, reporting progress if the model has to be downloaded first.
+    pub fn new_with_progress(
+        model_path: Option<PathBuf>,
+        preset: WhisperModelPreset,
+        whisper_threads: Option<usize>,
+        on_progress: &mut dyn FnMut(DownloadProgress),
+    ) -> anyhow::Result<Self> {
+        let model_path = resolve_whisper_model_path(model_path, preset, on_progress)?;
+        let ctx = shared_context(&model_path)?;
+        let state = ctx.create_state().context("failed to create state")?;
+
+        let max_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        let n_threads = whisper_threads
+            .unwrap_or(max_threads)
+            .clamp(1, max_threads) as i32;
+
+        Ok(Self {
+            ctx,
+            state,
+            n_threads,
+        })
+    }
+
+    /// Creates a second transcriber on the same loaded model, with its own decoding state.
+    pub fn fork(&self) -> anyhow::Result<Self> {
+        let state = self.ctx.create_state().context("failed to create state")?;
+        Ok(Self {
+            ctx: self.ctx.clone(),
+            state,
+            n_threads: self.n_threads,
+        })
+    }
+}
+
+impl Transcriber for WhisperLocalTranscriber {
+    fn transcribe(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<String> {
+        Ok(self.transcribe_detailed(audio_16k_mono, cfg)?.text)
+    }
+
+    fn transcribe_detailed(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<Transcript> {
+        if audio_16k_mono.is_empty() {
+            return Ok(Transcript::default());
+        }
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 0 });
+
+        params.set_n_threads(self.n_threads);
+        let translate = cfg.output_language == OutputLanguage::English;
+        params.set_translate(translate);
+        // In whisper.cpp, setting `detect_language=true` performs language detection *only*
+        // and returns early (no transcription). Auto-detection for transcription/translation
+        // is done by passing `language=None` or `language="auto"`.
+        params.set_language(cfg.input_language.as_deref());
+        params.set_no_timestamps(!cfg.segment_timestamps);
+        params.set_single_segment(cfg.is_partial && !cfg.segment_timestamps);
+        if cfg.is_partial {
+            params.set_no_context(true);
+        }
+        if let Some(prompt) = cfg.effective_prompt() {
+            // whisper.cpp takes a C string; an embedded NUL would panic.
+            params.set_initial_prompt(&prompt.replace('\0', ""));
+        }
+        // Speaker turns only mean something over a whole utterance.
+        params.set_tdrz_enable(cfg.diarize && !cfg.is_partial);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        self.state
+            .full(params, audio_16k_mono)
+            .context("whisper inference failed")?;
+
+        // Special tokens (timestamps, language, task markers) all sit at or above EOT.
+        let token_eot = self.ctx.token_eot();
+        let mut prob_sum = 0.0f32;
+        let mut prob_count = 0usize;
+
+        let mut out = String::new();
+        let mut segments = Vec::new();
+        let mut speaker_turns = 0;
+        // DTW times are in centiseconds, -1 when the model was loaded without alignment.
+        let mut aligned = Vec::new();
+        let align = WORD_ALIGNMENT.load(Ordering::Relaxed) && !cfg.is_partial;
+        for seg in self.state.as_iter() {
+            if cfg.diarize && seg.next_segment_speaker_turn() {
+                speaker_turns += 1;
+            }
+            for i in 0..seg.n_tokens() {
+                if let Some(token) = seg.get_token(i) {
+                    let is_text = token.token_id() < token_eot;
+                    if is_text {
+                        prob_sum += token.token_probability();
+                        prob_count += 1;
+                    }
+                    let t_dtw = token.token_data().t_dtw;
+                    if align && t_dtw >= 0 {
+                        let bytes = if is_text {
+                            token.to_bytes().map(<[u8]>::to_vec).unwrap_or_default()
+                        } else {
+                            Vec::new()
+                        };
+                        aligned.push(AlignedToken {
+                            bytes,
+                            time_s: t_dtw as f32 / 100.0,
+                        });
+                    }
+                }
+            }
+
+            let s = seg.to_string();
+            let s = s.trim();
+            if s.is_empty() {
+                continue;
+            }
+            if cfg.segment_timestamps {
+                // whisper.cpp timestamps are in centiseconds.
+                segments.push(TranscriptSegment {
+                    text: s.to_string(),
+                    start_s: seg.start_timestamp() as f32 / 100.0,
+                    end_s: seg.end_timestamp() as f32 / 100.0,
+                    no_speech_prob: seg.no_speech_probability(),
+                });
+            }
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(s);
+        }
+
+        let confidence = (prob_count > 0).then(|| prob_sum / prob_count as f32);
+        Ok(Transcript {
+            text: out,
+            confidence,
+            segments,
+            speaker_turns,
+            untranslated: false,
+            words: words_from_tokens(&aligned, audio_16k_mono.len() as f32 / 16_000.0),
+        })
+    }
+
+    fn detect_language(
+        &mut self,
+        audio_16k_mono: &[f32],
+        candidates: &[String],
+    ) -> Option<String> {
+        let threads = self.n_threads as usize;
+        let detected = self
+            .state
+            .pcm_to_mel(audio_16k_mono, threads)
+            .and_then(|()| self.state.lang_detect(0, threads));
+        let probabilities = match detected {
+            Ok((_, probabilities)) => probabilities,
+            Err(err) => {
+                tracing::debug!("language detection failed: {err}");
+                return None;
+            }
+        };
+        candidates
+            .iter()
+            .filter_map(|candidate| {
+                let id = whisper_rs::get_lang_id(&candidate.replace('\0', ""))?;
+                Some((candidate, *probabilities.get(usize::try_from(id).ok()?)?))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(candidate, _)| candidate.clone())
+    }
+}
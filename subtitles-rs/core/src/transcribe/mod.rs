@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use crate::config::OutputLanguage;
+use crate::text::{merge_bilingual, Glossary, TextJoiner};
+
+mod align;
+mod ensemble;
+mod ggml;
+mod language_pair;
+
+pub use align::{words_from_tokens, AlignedToken, WordTiming};
+pub use ensemble::{agreement, choose, EnsembleTranscriber, AGREEMENT, CLOUD_CONFIDENCE};
+pub use ggml::{inspect_model, WhisperModelInfo};
+pub use language_pair::{language_runs, pick_language, LanguagePairTranscriber};
+
+// The engines themselves need whisper.cpp and an HTTP client; without the `engines` feature
+// (e.g. for wasm) only the shared types are built.
+#[cfg(feature = "engines")]
+mod local_whisper;
+#[cfg(feature = "engines")]
+mod model_download;
+#[cfg(feature = "engines")]
+mod openai;
+
+#[cfg(feature = "engines")]
+pub use local_whisper::{set_gpu_memory_limit_mb, set_word_alignment, WhisperLocalTranscriber};
+#[cfg(feature = "engines")]
+pub use model_download::{
+    cached_smaller_preset, download_file, estimated_gpu_memory_mb, largest_preset_within,
+    model_dir, preset_gpu_memory_mb, preset_model_path, resolve_custom_model_path,
+    resolve_whisper_model_path, set_model_mirrors, CustomModel,
+};
+#[cfg(feature = "engines")]
+pub use openai::{encode_wav_16k_mono_i16, OpenAiTranscriber};
+
+/// Progress of a model download, reported about once per megabyte and once at the end.
+#[derive(Debug, Clone)]
+pub struct DownloadProgress {
+    pub file_name: String,
+    pub downloaded_bytes: u64,
+    /// From `Content-Length`, when the server sends one.
+    pub total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriberConfig {
+    pub input_language: Option<String>,
+    pub output_language: OutputLanguage,
+    pub is_partial: bool,
+    /// Decode with timestamps and let the engine split segments (used for endpointing).
+    pub segment_timestamps: bool,
+    /// Style hint passed as whisper's initial prompt / the API's `prompt` field (`--prompt`).
+    pub prompt: Option<String>,
+    /// Preferred translations, applied when translating to English (`--glossary`).
+    pub glossary: Option<Arc<Glossary>>,
+    /// Ask for speaker-turn markers on finals (whisper.cpp's tinydiarize, `--diarize`).
+    pub diarize: bool,
+}
+
+impl TranscriberConfig {
+    fn translating_glossary(&self) -> Option<&Glossary> {
+        self.glossary
+            .as_deref()
+            .filter(|_| self.output_language == OutputLanguage::English)
+    }
+
+    /// `prompt`, plus the glossary's terms when translating.
+    pub fn effective_prompt(&self) -> Option<String> {
+        let hint = self.translating_glossary().and_then(Glossary::prompt_hint);
+        match (self.prompt.as_deref(), hint) {
+            (Some(prompt), Some(hint)) => Some(format!("{prompt} {hint}")),
+            (Some(prompt), None) => Some(prompt.to_string()),
+            (None, hint) => hint,
+        }
+    }
+
+    /// Enforces the glossary on translated text; other output is returned unchanged.
+    pub fn post_edit(&self, text: String) -> String {
+        match self.translating_glossary() {
+            Some(glossary) => glossary.post_edit(&text),
+            None => text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    /// Mean token probability in `0.0..=1.0`, for engines that report one.
+    pub confidence: Option<f32>,
+    /// Timed segments, when `segment_timestamps` was requested and the engine supports it.
+    pub segments: Vec<TranscriptSegment>,
+    /// Places where the speaker changes, with `diarize`: after the text, the voice is a
+    /// different one this many times over (the last may be a turn right at the end).
+    pub speaker_turns: usize,
+    /// Translation was asked for but failed, so `text` is in the original language instead.
+    pub untranslated: bool,
+    /// Aligned word timings of a final, from engines loaded with word alignment.
+    pub words: Vec<WordTiming>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptSegment {
+    pub text: String,
+    /// Offsets in seconds from the start of the decoded audio.
+    pub start_s: f32,
+    pub end_s: f32,
+    pub no_speech_prob: f32,
+}
+
+/// Builds a transcriber off the engine-start path (model download + load can take minutes on
+/// first run), reporting download progress through the callback.
+pub type TranscriberLoader = Box<
+    dyn FnOnce(&mut dyn FnMut(DownloadProgress)) -> anyhow::Result<Box<dyn Transcriber>> + Send,
+>;
+
+pub trait Transcriber: Send {
+    fn transcribe(&mut self, audio_16k_mono: &[f32], cfg: &TranscriberConfig)
+        -> anyhow::Result<String>;
+
+    /// Like `transcribe`, plus whatever metadata the engine can provide.
+    fn transcribe_detailed(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<Transcript> {
+        Ok(Transcript {
+            text: self.transcribe(audio_16k_mono, cfg)?,
+            ..Transcript::default()
+        })
+    }
+
+    /// Which of `candidates` (language codes) is most likely spoken in the audio, for engines
+    /// that can tell.
+    fn detect_language(
+        &mut self,
+        _audio_16k_mono: &[f32],
+        _candidates: &[String],
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// A whole utterance decoded again as a final in `output_language`, joined and post-edited
+/// like the live engine's; `Bilingual` decodes it twice and merges the lines. For the workers
+/// that re-run finals (second pass, shadow engine) and batch transcription.
+pub fn decode_final(
+    transcriber: &mut dyn Transcriber,
+    audio_16k_mono: &[f32],
+    cfg: &TranscriberConfig,
+    output_language: OutputLanguage,
+) -> anyhow::Result<String> {
+    let mut run = |output_language| -> anyhow::Result<String> {
+        let cfg = TranscriberConfig {
+            output_language,
+            is_partial: false,
+            segment_timestamps: false,
+            ..cfg.clone()
+        };
+        let joiner = TextJoiner::for_output(output_language, cfg.input_language.as_deref());
+        let text = transcriber.transcribe(audio_16k_mono, &cfg)?;
+        Ok(joiner.format(&cfg.post_edit(text)))
+    };
+    if output_language == OutputLanguage::Bilingual {
+        Ok(merge_bilingual(
+            &run(OutputLanguage::Chinese)?,
+            &run(OutputLanguage::English)?,
+        ))
+    } else {
+        run(output_language)
+    }
+}
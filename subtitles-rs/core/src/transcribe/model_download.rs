@@ -0,0 +1,612 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::config::WhisperModelPreset;
+use crate::network::{self, AuditRecord};
+use crate::transcribe::DownloadProgress;
+
+/// Bytes between progress callbacks while downloading.
+const PROGRESS_INTERVAL_BYTES: u64 = 1 << 20;
+
+/// Parallel connections for a download, when the server accepts byte ranges.
+const DOWNLOAD_CONNECTIONS: u64 = 4;
+
+/// Files smaller than this are fetched over one connection.
+const MIN_PARALLEL_BYTES: u64 = 16 << 20;
+
+/// How often combined progress is checked during a parallel download.
+const PROGRESS_POLL: Duration = Duration::from_millis(100);
+
+const MODEL_DIR: &str = "models";
+
+/// Where presets are downloaded from when no mirror is configured.
+const HUGGING_FACE: &str = "https://huggingface.co";
+
+static MIRRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// A preset's file name, its path on a Hugging Face host and its sha256, so a mirror can't
+/// hand out a different file.
+fn preset_file(preset: &WhisperModelPreset) -> (&'static str, &'static str, &'static str) {
+    match preset {
+        WhisperModelPreset::Tiny => (
+            "ggml-tiny.bin",
+            "ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin",
+            "be07e048e1e599ad46341c8d2a135645097a538221678b7acdd1b1919c6e1b21",
+        ),
+        WhisperModelPreset::Base => (
+            "ggml-base.bin",
+            "ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+            "60ed5bc3dd14eea856493d334349b405782ddcaf0028d4b5df4088345fba2efe",
+        ),
+        WhisperModelPreset::Small => (
+            "ggml-small.bin",
+            "ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
+            "1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b",
+        ),
+        WhisperModelPreset::Medium => (
+            "ggml-medium.bin",
+            "ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
+            "6c14d5adee5f86394037b4e4e8b59f1673b6cee10e3cf0b11bbdbee79c156208",
+        ),
+        WhisperModelPreset::LargeV3 => (
+            "ggml-large-v3.bin",
+            "ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin",
+            "64d182b440b98d5203c4f9bd541544d84c605196c4f7b845dfa11fb23594d1e2",
+        ),
+    }
+}
+
+/// Hosts to download presets from instead of huggingface.co, tried in order. Each must serve
+/// Hugging Face's `/<repo>/resolve/main/<file>` paths, as `https://hf-mirror.com` and a
+/// caching proxy do. With none set, `$HF_ENDPOINT` is used if set, like Hugging Face's tools.
+pub fn set_model_mirrors(mirrors: Vec<String>) {
+    *MIRRORS.lock().unwrap_or_else(|e| e.into_inner()) = mirrors;
+}
+
+fn model_hosts() -> Vec<String> {
+    let mut hosts = MIRRORS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if hosts.is_empty() {
+        let endpoint = std::env::var("HF_ENDPOINT").unwrap_or_default();
+        hosts.push(match endpoint.trim() {
+            "" => HUGGING_FACE.to_string(),
+            _ => endpoint,
+        });
+    }
+    hosts
+        .into_iter()
+        .map(|host| host.trim().trim_end_matches('/').to_string())
+        .collect()
+}
+
+/// A model from outside the presets, declared as `NAME=URL` with an optional `#sha256=HEX` the
+/// download must match (the fragment is never sent to the server).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomModel {
+    pub name: String,
+    pub url: String,
+    /// Lowercase hex.
+    pub sha256: Option<String>,
+}
+
+impl FromStr for CustomModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("expected NAME=URL[#sha256=HEX], got `{s}`");
+        let (name, url) = s.split_once('=').ok_or_else(usage)?;
+        let name = name.trim().to_ascii_lowercase();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(format!(
+                "model name `{name}` may only use letters, digits, `-`, `_` and `.`"
+            ));
+        }
+        let (url, sha256) = match url.trim().split_once("#sha256=") {
+            Some((url, hash)) => {
+                let hash = hash.trim().to_ascii_lowercase();
+                if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("sha256 of `{name}` must be 64 hex digits"));
+                }
+                (url, Some(hash))
+            }
+            None => (url.trim(), None),
+        };
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(usage());
+        }
+        Ok(Self {
+            name,
+            url: url.to_string(),
+            sha256,
+        })
+    }
+}
+
+impl CustomModel {
+    /// Where the model is kept once downloaded, named after it rather than the URL so two
+    /// hosts' `ggml-small.bin` (or a preset's) can't overwrite each other.
+    pub fn path(&self) -> PathBuf {
+        model_dir().join(format!("custom-{}.bin", self.name))
+    }
+}
+
+/// Presets from smallest to largest.
+const PRESETS_BY_SIZE: [WhisperModelPreset; 5] = [
+    WhisperModelPreset::Tiny,
+    WhisperModelPreset::Base,
+    WhisperModelPreset::Small,
+    WhisperModelPreset::Medium,
+    WhisperModelPreset::LargeV3,
+];
+
+/// Where a preset's model file lives once downloaded.
+pub fn preset_model_path(preset: &WhisperModelPreset) -> PathBuf {
+    model_dir().join(preset_file(preset).0)
+}
+
+/// The directory presets are downloaded to.
+pub fn model_dir() -> &'static Path {
+    Path::new(MODEL_DIR)
+}
+
+/// The largest preset smaller than `preset` that is already downloaded to `dir`, to stand in
+/// when `preset` can't be downloaded (offline on first run).
+pub fn cached_smaller_preset(
+    dir: &Path,
+    preset: &WhisperModelPreset,
+) -> Option<WhisperModelPreset> {
+    let wanted = preset_file(preset).0;
+    PRESETS_BY_SIZE
+        .iter()
+        .take_while(|smaller| preset_file(smaller).0 != wanted)
+        .filter(|smaller| dir.join(preset_file(smaller).0).is_file())
+        .last()
+        .cloned()
+}
+
+/// whisper.cpp's compute buffers and KV cache on top of the weights, in MB.
+const GPU_OVERHEAD_MB: u64 = 200;
+
+/// GPU memory a model of `model_bytes` needs with Metal offload, in MB: the weights plus
+/// whisper.cpp's buffers. An estimate, erring on the high side.
+pub fn estimated_gpu_memory_mb(model_bytes: u64) -> u64 {
+    model_bytes.div_ceil(1 << 20) + GPU_OVERHEAD_MB
+}
+
+/// `estimated_gpu_memory_mb` for a preset, from its (approximate) download size.
+pub fn preset_gpu_memory_mb(preset: &WhisperModelPreset) -> u64 {
+    let file_mb = match preset {
+        WhisperModelPreset::Tiny => 75,
+        WhisperModelPreset::Base => 142,
+        WhisperModelPreset::Small => 466,
+        WhisperModelPreset::Medium => 1_533,
+        WhisperModelPreset::LargeV3 => 3_095,
+    };
+    estimated_gpu_memory_mb(file_mb << 20)
+}
+
+/// The largest preset no bigger than `preset` expected to fit in `limit_mb` of GPU memory
+/// (`--gpu-memory-limit-mb`), or `None` if not even the smallest does.
+pub fn largest_preset_within(
+    preset: &WhisperModelPreset,
+    limit_mb: u64,
+) -> Option<WhisperModelPreset> {
+    let wanted = preset_file(preset).0;
+    let mut fitting = None;
+    for candidate in &PRESETS_BY_SIZE {
+        if preset_gpu_memory_mb(candidate) <= limit_mb {
+            fitting = Some(candidate.clone());
+        }
+        if preset_file(candidate).0 == wanted {
+            break;
+        }
+    }
+    fitting
+}
+
+pub fn resolve_whisper_model_path(
+    explicit_path: Option<PathBuf>,
+    preset: WhisperModelPreset,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+) -> anyhow::Result<PathBuf> {
+    if let Some(path) = explicit_path {
+        return Ok(path);
+    }
+
+    let (filename, path, sha256) = preset_file(&preset);
+    fs::create_dir_all(model_dir()).context("failed to create models/ directory")?;
+    let model_path = preset_model_path(&preset);
+
+    if model_path.exists() {
+        return Ok(model_path);
+    }
+
+    tracing::info!(
+        "downloading whisper model ({}) to {}",
+        filename,
+        model_path.display()
+    );
+    let mut last_err = None;
+    for host in model_hosts() {
+        let url = format!("{host}/{path}");
+        match download_file(&url, &model_path, filename, Some(sha256), on_progress) {
+            Ok(()) => return Ok(model_path),
+            Err(err) => {
+                let err = err.context(format!("failed to download model from {url}"));
+                tracing::warn!("{err:#}");
+                last_err = Some(err);
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("no host to download {filename} from")))
+}
+
+/// The downloaded file of a custom model, fetching (and checking) it first if needed.
+pub fn resolve_custom_model_path(
+    model: &CustomModel,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(model_dir()).context("failed to create models/ directory")?;
+    let model_path = model.path();
+    if model_path.exists() {
+        return Ok(model_path);
+    }
+
+    let file_name = format!("custom-{}.bin", model.name);
+    tracing::info!(
+        "downloading custom model `{}` to {}",
+        model.name,
+        model_path.display()
+    );
+    download_file(
+        &model.url,
+        &model_path,
+        &file_name,
+        model.sha256.as_deref(),
+        on_progress,
+    )
+    .with_context(|| format!("failed to download model `{}` from {}", model.name, model.url))?;
+    Ok(model_path)
+}
+
+/// Downloads `url` to `dest` through a temp file, over `DOWNLOAD_CONNECTIONS` ranged requests
+/// when the server supports them and the file is big enough to be worth it, otherwise as one
+/// stream. With `sha256`, a file that doesn't match is discarded with an error.
+pub fn download_file(
+    url: &str,
+    dest: &Path,
+    file_name: &str,
+    sha256: Option<&str>,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+) -> anyhow::Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(60 * 30))
+        .user_agent("subtitles/0.1")
+        .build()
+        .context("failed to build HTTP client")?;
+
+    let tmp_path = dest.with_extension("download");
+    match probe_ranges(&client, url) {
+        Some(total) if total >= MIN_PARALLEL_BYTES => {
+            tracing::info!("downloading {file_name} over {DOWNLOAD_CONNECTIONS} connections");
+            download_ranges(&client, url, &tmp_path, total, file_name, on_progress)?
+        }
+        _ => download_stream(&client, url, &tmp_path, file_name, on_progress)?,
+    }
+    if let Some(expected) = sha256 {
+        let actual = sha256_file(&tmp_path)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            let _ = fs::remove_file(&tmp_path);
+            anyhow::bail!("{file_name} has sha256 {actual}, expected {expected}");
+        }
+    }
+
+    fs::rename(&tmp_path, dest).with_context(|| {
+        format!(
+            "failed to move {} to {}",
+            tmp_path.display(),
+            dest.display()
+        )
+    })?;
+    Ok(())
+}
+
+/// The file's size if the server accepts byte ranges for it. Any failure here just means a
+/// single-stream download, which reports the error properly if there is one.
+fn probe_ranges(client: &reqwest::blocking::Client, url: &str) -> Option<u64> {
+    let resp = client.head(url).send();
+    let status = resp.as_ref().ok().map(|r| r.status().as_u16());
+    audit_download(
+        "HEAD",
+        url,
+        0,
+        status,
+        resp.as_ref().err().map(|e| e.to_string()),
+    );
+    let resp = resp.ok().filter(|r| r.status().is_success())?;
+    let headers = resp.headers();
+    let ranges = headers
+        .get(reqwest::header::ACCEPT_RANGES)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("bytes"));
+    // Not `content_length()`: a HEAD response has no body, so that would be 0.
+    let total = headers
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())?;
+    (ranges && total > 0).then_some(total)
+}
+
+fn download_stream(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    tmp_path: &Path,
+    file_name: &str,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+) -> anyhow::Result<()> {
+    let resp = client.get(url).send();
+    let status = resp.as_ref().ok().map(|r| r.status().as_u16());
+    let mut resp = match resp.and_then(|r| r.error_for_status()) {
+        Ok(resp) => resp,
+        Err(err) => {
+            audit_download("GET", url, 0, status, Some(err.to_string()));
+            return Err(err).with_context(|| format!("GET {url}"));
+        }
+    };
+
+    let mut tmp = fs::File::create(tmp_path)
+        .with_context(|| format!("failed to create temp file {}", tmp_path.display()))?;
+
+    let total_bytes = resp.content_length();
+    let mut report = |downloaded_bytes| {
+        on_progress(DownloadProgress {
+            file_name: file_name.to_string(),
+            downloaded_bytes,
+            total_bytes,
+        })
+    };
+    report(0);
+
+    let copied = copy_with_progress(&mut resp, &mut tmp, &mut report);
+    audit_download(
+        "GET",
+        url,
+        *copied.as_ref().unwrap_or(&0),
+        status,
+        copied.as_ref().err().map(|e| e.to_string()),
+    );
+    copied.context("failed downloading model file")?;
+
+    tmp.flush().ok();
+    Ok(())
+}
+
+/// Fetches `total` bytes as `DOWNLOAD_CONNECTIONS` ranges in parallel, each written at its
+/// offset in a preallocated temp file. Progress is the sum over all connections.
+fn download_ranges(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    tmp_path: &Path,
+    total: u64,
+    file_name: &str,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+) -> anyhow::Result<()> {
+    fs::File::create(tmp_path)
+        .and_then(|tmp| tmp.set_len(total))
+        .with_context(|| format!("failed to create temp file {}", tmp_path.display()))?;
+
+    let mut report = |downloaded_bytes| {
+        on_progress(DownloadProgress {
+            file_name: file_name.to_string(),
+            downloaded_bytes,
+            total_bytes: Some(total),
+        })
+    };
+    report(0);
+
+    let received = AtomicU64::new(0);
+    let failed = AtomicBool::new(false);
+    let chunk = total.div_ceil(DOWNLOAD_CONNECTIONS);
+    let results = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..DOWNLOAD_CONNECTIONS)
+            .map(|i| (i * chunk, ((i + 1) * chunk).min(total)))
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| {
+                let (received, failed) = (&received, &failed);
+                scope.spawn(move || {
+                    let fetched = fetch_range(client, url, tmp_path, start..end, received, failed);
+                    if fetched.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    fetched
+                })
+            })
+            .collect();
+
+        // `on_progress` stays on this thread; the workers only bump the counter.
+        let mut last_report = 0;
+        while !workers.iter().all(|worker| worker.is_finished()) {
+            std::thread::sleep(PROGRESS_POLL);
+            let now = received.load(Ordering::Relaxed);
+            if now - last_report >= PROGRESS_INTERVAL_BYTES {
+                last_report = now;
+                report(now);
+            }
+        }
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("download worker panicked")))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // The first real error, not the "cancelled" of connections stopped because of it.
+    let mut errors = results.into_iter().filter_map(Result::err);
+    if let Some(err) = errors.next() {
+        return Err(err).context("failed downloading model file");
+    }
+    report(received.load(Ordering::Relaxed));
+    Ok(())
+}
+
+/// One connection's share of a parallel download: `range` of the file, written at its offset.
+fn fetch_range(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    tmp_path: &Path,
+    range: std::ops::Range<u64>,
+    received: &AtomicU64,
+    failed: &AtomicBool,
+) -> anyhow::Result<()> {
+    let resp = client
+        .get(url)
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes={}-{}", range.start, range.end - 1),
+        )
+        .send();
+    let status = resp.as_ref().ok().map(|r| r.status().as_u16());
+    let mut resp = match resp.and_then(|r| r.error_for_status()) {
+        Ok(resp) if resp.status() == reqwest::StatusCode::PARTIAL_CONTENT => resp,
+        Ok(resp) => {
+            let err = format!("expected 206 for a range request, got {}", resp.status());
+            audit_download("GET", url, 0, status, Some(err.clone()));
+            anyhow::bail!("GET {url}: {err}");
+        }
+        Err(err) => {
+            audit_download("GET", url, 0, status, Some(err.to_string()));
+            return Err(err).with_context(|| format!("GET {url}"));
+        }
+    };
+
+    let copied = copy_range(&mut resp, tmp_path, &range, received, failed);
+    audit_download(
+        "GET",
+        url,
+        *copied.as_ref().unwrap_or(&0),
+        status,
+        copied.as_ref().err().map(|e| e.to_string()),
+    );
+    let copied = copied?;
+    let expected = range.end - range.start;
+    anyhow::ensure!(
+        copied == expected,
+        "bytes {}-{} ended after {copied} of {expected} bytes",
+        range.start,
+        range.end - 1
+    );
+    Ok(())
+}
+
+fn copy_range(
+    reader: &mut impl Read,
+    tmp_path: &Path,
+    range: &std::ops::Range<u64>,
+    received: &AtomicU64,
+    failed: &AtomicBool,
+) -> std::io::Result<u64> {
+    // A handle of its own, so its position is independent of the other connections'.
+    let mut file = fs::OpenOptions::new().write(true).open(tmp_path)?;
+    file.seek(SeekFrom::Start(range.start))?;
+    let limit = range.end - range.start;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut copied = 0u64;
+    while copied < limit {
+        if failed.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other(
+                "cancelled: another connection failed",
+            ));
+        }
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n.min((limit - copied) as usize),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        file.write_all(&buf[..n])?;
+        copied += n as u64;
+        received.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    Ok(copied)
+}
+
+fn sha256_file(path: &Path) -> anyhow::Result<String> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut digest = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        digest.update(&buf[..n]);
+    }
+    Ok(digest
+        .finish()
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn copy_with_progress(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    report: &mut impl FnMut(u64),
+) -> std::io::Result<u64> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut copied = 0u64;
+    let mut last_report = 0u64;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+        if copied - last_report >= PROGRESS_INTERVAL_BYTES {
+            last_report = copied;
+            report(copied);
+        }
+    }
+    report(copied);
+    Ok(copied)
+}
+
+fn audit_download(
+    method: &'static str,
+    url: &str,
+    bytes_received: u64,
+    status: Option<u16>,
+    error: Option<String>,
+) {
+    network::audit(AuditRecord {
+        purpose: "model-download",
+        method,
+        destination: url,
+        bytes_sent: 0,
+        bytes_received,
+        status,
+        error,
+    });
+}
@@ -6,8 +6,10 @@ use reqwest::blocking::multipart;
 use serde::Deserialize;
 
 use crate::config::OutputLanguage;
-use crate::transcribe::{Transcriber, TranscriberConfig};
+use crate::network::{self, AuditRecord};
+use crate::transcribe::{Transcriber, TranscriberConfig, Transcript};
 
+#[derive(Clone)]
 pub struct OpenAiTranscriber {
     api_key: String,
     model: String,
@@ -23,6 +25,7 @@ impl OpenAiTranscriber {
         transcription_endpoint: String,
         translation_endpoint: String,
     ) -> anyhow::Result<Self> {
+        network::ensure_egress_allowed("OpenAI transcription")?;
         let api_key = api_key.context("missing OpenAI API key (set --openai-api-key or OPENAI_API_KEY)")?;
         let client = reqwest::blocking::Client::builder()
             .timeout(Duration::from_secs(60))
@@ -40,19 +43,13 @@ impl OpenAiTranscriber {
     }
 }
 
-impl Transcriber for OpenAiTranscriber {
-    fn transcribe(
-        &mut self,
-        audio_16k_mono: &[f32],
-        cfg: &TranscriberConfig,
-    ) -> anyhow::Result<String> {
-        if audio_16k_mono.is_empty() {
-            return Ok(String::new());
-        }
-
-        let wav = encode_wav_16k_mono_i16(audio_16k_mono)?;
+impl OpenAiTranscriber {
+    /// One upload of `wav` to the translation endpoint when `cfg` asks for English, else to the
+    /// transcription endpoint.
+    fn request(&self, wav: &[u8], cfg: &TranscriberConfig) -> anyhow::Result<String> {
+        let bytes_sent = wav.len() as u64;
 
-        let file_part = multipart::Part::bytes(wav)
+        let file_part = multipart::Part::bytes(wav.to_vec())
             .file_name("audio.wav")
             .mime_str("audio/wav")
             .context("invalid mime")?;
@@ -71,19 +68,46 @@ impl Transcriber for OpenAiTranscriber {
         if let Some(lang) = cfg.input_language.as_ref() {
             form = form.text("language", lang.clone());
         }
+        if let Some(prompt) = cfg.effective_prompt() {
+            form = form.text("prompt", prompt);
+        }
 
-        let resp = self
+        let purpose = if translate { "translation" } else { "transcription" };
+        let resp = match self
             .client
             .post(endpoint)
             .bearer_auth(&self.api_key)
             .multipart(form)
             .send()
-            .with_context(|| format!("POST {}", endpoint))?;
+        {
+            Ok(resp) => resp,
+            Err(err) => {
+                network::audit(AuditRecord {
+                    purpose,
+                    method: "POST",
+                    destination: endpoint,
+                    bytes_sent,
+                    bytes_received: 0,
+                    status: None,
+                    error: Some(err.to_string()),
+                });
+                return Err(err).with_context(|| format!("POST {}", endpoint));
+            }
+        };
 
         let status = resp.status();
         let body = resp.text().context("failed to read response body")?;
+        network::audit(AuditRecord {
+            purpose,
+            method: "POST",
+            destination: endpoint,
+            bytes_sent,
+            bytes_received: body.len() as u64,
+            status: Some(status.as_u16()),
+            error: None,
+        });
         if !status.is_success() {
-            anyhow::bail!("transcription API error ({status}): {body}");
+            anyhow::bail!("{purpose} API error ({status}): {body}");
         }
 
         let parsed: OpenAiTranscriptionResponse =
@@ -92,12 +116,61 @@ impl Transcriber for OpenAiTranscriber {
     }
 }
 
+impl Transcriber for OpenAiTranscriber {
+    fn transcribe(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<String> {
+        self.transcribe_detailed(audio_16k_mono, cfg)
+            .map(|transcript| transcript.text)
+    }
+
+    /// When translating fails, the same audio is resent for a transcription in the original
+    /// language and returned as `untranslated`, so the segment isn't lost.
+    fn transcribe_detailed(
+        &mut self,
+        audio_16k_mono: &[f32],
+        cfg: &TranscriberConfig,
+    ) -> anyhow::Result<Transcript> {
+        if audio_16k_mono.is_empty() {
+            return Ok(Transcript::default());
+        }
+
+        let wav = encode_wav_16k_mono_i16(audio_16k_mono)?;
+        let err = match self.request(&wav, cfg) {
+            Ok(text) => {
+                return Ok(Transcript {
+                    text,
+                    ..Transcript::default()
+                })
+            }
+            Err(err) if cfg.output_language == OutputLanguage::English => err,
+            Err(err) => return Err(err),
+        };
+        tracing::warn!("translation failed, resending as a transcription: {err:#}");
+        let original = TranscriberConfig {
+            output_language: OutputLanguage::Chinese,
+            ..cfg.clone()
+        };
+        match self.request(&wav, &original) {
+            Ok(text) => Ok(Transcript {
+                text,
+                untranslated: true,
+                ..Transcript::default()
+            }),
+            Err(_) => Err(err),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenAiTranscriptionResponse {
     text: String,
 }
 
-fn encode_wav_16k_mono_i16(audio_16k_mono: &[f32]) -> anyhow::Result<Vec<u8>> {
+/// 16-bit PCM WAV of 16 kHz mono audio.
+pub fn encode_wav_16k_mono_i16(audio_16k_mono: &[f32]) -> anyhow::Result<Vec<u8>> {
     let spec = hound::WavSpec {
         channels: 1,
         sample_rate: 16_000,
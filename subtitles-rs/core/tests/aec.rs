@@ -0,0 +1,71 @@
+use subtitles_core::audio::{EchoCanceller, EchoCancellerConfig};
+use subtitles_core::testing::{chunked, rms_of, SignalBuilder};
+
+/// What the mic hears of `reference`: a delayed, attenuated copy with a short reflection.
+fn echo_of(reference: &[f32], delay: usize) -> Vec<f32> {
+    (0..reference.len())
+        .map(|i| {
+            let direct = i.checked_sub(delay).map_or(0.0, |j| 0.4 * reference[j]);
+            let reflection = i
+                .checked_sub(delay + 37)
+                .map_or(0.0, |j| -0.15 * reference[j]);
+            direct + reflection
+        })
+        .collect()
+}
+
+fn config() -> EchoCancellerConfig {
+    EchoCancellerConfig {
+        filter_len: 256,
+        bulk_delay: 100,
+        step_size: 0.5,
+    }
+}
+
+#[test]
+fn echo_of_the_reference_is_removed() {
+    let reference = SignalBuilder::new(16_000, 5).speech(4.0, 0.1).build();
+    let mic = echo_of(&reference, 180);
+
+    let mut aec = EchoCanceller::new(config());
+    let out: Vec<f32> = chunked(&mic, 320)
+        .zip(chunked(&reference, 320))
+        .flat_map(|(mic, reference)| aec.process(mic, reference))
+        .collect();
+    assert_eq!(out.len(), mic.len());
+
+    // After a second to converge, what's left is well over 20 dB below the echo.
+    let tail = 16_000..mic.len();
+    let attenuation = rms_of(&out[tail.clone()]) / rms_of(&mic[tail]);
+    assert!(
+        attenuation < 0.1,
+        "residual at {attenuation:.3} of the echo"
+    );
+}
+
+#[test]
+fn near_end_speech_is_kept() {
+    let reference = SignalBuilder::new(16_000, 6).speech(4.0, 0.1).build();
+    let near = SignalBuilder::new(16_000, 7)
+        .silence(2.0)
+        .speech(2.0, 0.1)
+        .build();
+    let echo = echo_of(&reference, 180);
+    let mic: Vec<f32> = echo.iter().zip(&near).map(|(e, n)| e + n).collect();
+
+    let mut aec = EchoCanceller::new(config());
+    let out = aec.process(&mic, &reference);
+
+    // During double talk the filter holds still, so the output tracks the near-end talker.
+    let talk = 40_000..mic.len();
+    let residual: Vec<f32> = out[talk.clone()]
+        .iter()
+        .zip(&near[talk.clone()])
+        .map(|(o, n)| o - n)
+        .collect();
+    assert!(rms_of(&residual) < 0.2 * rms_of(&near[talk]));
+
+    // Without a reference the mic passes through untouched.
+    let mut aec = EchoCanceller::new(config());
+    assert_eq!(aec.process(&near, &vec![0.0; near.len()]), near);
+}
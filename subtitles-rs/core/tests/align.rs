@@ -0,0 +1,102 @@
+use subtitles_core::transcribe::{words_from_tokens, AlignedToken, WordTiming};
+
+fn token(text: &str, time_s: f32) -> AlignedToken {
+    AlignedToken {
+        bytes: text.as_bytes().to_vec(),
+        time_s,
+    }
+}
+
+fn word(text: &str, start_s: f32, end_s: f32) -> WordTiming {
+    WordTiming {
+        text: text.to_string(),
+        start_s,
+        end_s,
+    }
+}
+
+#[test]
+fn tokens_join_into_words_at_leading_spaces() {
+    let tokens = [
+        token(" We", 0.4),
+        token(" ship", 0.7),
+        token("ped", 0.9),
+        token(" today", 1.2),
+        token(".", 1.6),
+        // End of text.
+        token("", 1.9),
+    ];
+    assert_eq!(
+        words_from_tokens(&tokens, 3.0),
+        [
+            word("We", 0.4, 0.7),
+            word("shipped", 0.7, 1.2),
+            word("today.", 1.2, 1.9),
+        ]
+    );
+}
+
+#[test]
+fn the_last_word_runs_to_the_end_of_the_audio_without_a_closing_token() {
+    let tokens = [token(" Hello", 0.2), token(" there", 0.8)];
+    assert_eq!(
+        words_from_tokens(&tokens, 1.5),
+        [word("Hello", 0.2, 0.8), word("there", 0.8, 1.5)]
+    );
+    assert!(words_from_tokens(&[], 1.5).is_empty());
+}
+
+#[test]
+fn characters_split_across_tokens_are_put_back_together() {
+    let text = "你好";
+    let bytes = text.as_bytes();
+    // "你" is three bytes; the second token starts inside it.
+    let tokens = [
+        AlignedToken {
+            bytes: bytes[..2].to_vec(),
+            time_s: 0.3,
+        },
+        AlignedToken {
+            bytes: bytes[2..].to_vec(),
+            time_s: 0.5,
+        },
+        token("", 0.9),
+    ];
+    assert_eq!(words_from_tokens(&tokens, 2.0), [word("你好", 0.3, 0.9)]);
+}
+
+#[test]
+fn unspaced_text_gets_a_word_per_token() {
+    let tokens = [
+        token("我们", 0.2),
+        token("周五", 0.6),
+        token("发布", 1.0),
+        token("。", 1.3),
+        token("iPhone", 1.5),
+        token("很好", 1.9),
+        token("", 2.2),
+    ];
+    assert_eq!(
+        words_from_tokens(&tokens, 3.0),
+        [
+            word("我们", 0.2, 0.6),
+            word("周五", 0.6, 1.0),
+            word("发布。", 1.0, 1.5),
+            word("iPhone", 1.5, 1.9),
+            word("很好", 1.9, 2.2),
+        ]
+    );
+}
+
+#[test]
+fn times_never_go_backwards() {
+    let tokens = [token(" one", 1.0), token(" two", 0.8), token(" three", 1.4)];
+    assert_eq!(
+        words_from_tokens(&tokens, 2.0),
+        [
+            word("one", 1.0, 1.0),
+            word("two", 1.0, 1.4),
+            word("three", 1.4, 2.0),
+        ]
+    );
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use subtitles_core::streaming::AsrStepController;
+
+const FAST_DECODE: Duration = Duration::from_millis(40);
+
+/// Step after a few partials of `hypothesis` over a 3 s window.
+fn settled_step(controller: &mut AsrStepController, hypothesis: &str, decode: Duration) -> u64 {
+    (0..10)
+        .map(|_| controller.update(hypothesis, 3.0, decode))
+        .last()
+        .unwrap()
+}
+
+#[test]
+fn keeps_the_configured_step_until_the_speech_rate_is_known() {
+    let mut controller = AsrStepController::new(350, 150, 1000);
+    assert_eq!(controller.step_ms(), 350);
+    // Too little audio to judge the rate from.
+    assert_eq!(controller.update("so", 0.7, FAST_DECODE), 350);
+}
+
+#[test]
+fn fast_speech_shortens_the_step_and_slow_speech_lengthens_it() {
+    let fast = "and then we went over to the market to pick up a few more things before dinner";
+    let slow = "well ... um";
+    let mut controller = AsrStepController::new(350, 150, 1000);
+    let fast_step = settled_step(&mut controller, fast, FAST_DECODE);
+    let slow_step = settled_step(&mut controller, slow, FAST_DECODE);
+    assert!(fast_step < 350, "{fast_step}");
+    assert!(slow_step > 700, "{slow_step}");
+    // Nothing to caption (music, or a decoder that hears no words) backs right off.
+    assert_eq!(settled_step(&mut controller, "", FAST_DECODE), 1000);
+}
+
+#[test]
+fn unspaced_text_counts_by_character() {
+    let mut controller = AsrStepController::new(350, 150, 1000);
+    let step = settled_step(&mut controller, "我们明天早上九点在公司门口见面吧。", FAST_DECODE);
+    assert!(step < 250, "{step}");
+}
+
+#[test]
+fn step_never_outpaces_the_decoder() {
+    let fast = "and then we went over to the market to pick up a few more things before dinner";
+    let mut controller = AsrStepController::new(350, 150, 1000);
+    let step = settled_step(&mut controller, fast, Duration::from_millis(400));
+    assert!((490..=510).contains(&step), "{step}");
+    // Even a decoder slower than the longest step gets no more than that.
+    let step = settled_step(&mut controller, fast, Duration::from_secs(2));
+    assert_eq!(step, 1000);
+}
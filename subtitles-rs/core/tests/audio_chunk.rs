@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use subtitles_core::audio::{AsrConverter, AudioChunk, ChunkClock, Resampler};
+use subtitles_core::testing::{chunked, SignalBuilder};
+
+#[test]
+fn clock_stamps_format_and_stream_time() {
+    let mut clock = ChunkClock::new(48_000, 2);
+    let first = clock.stamp(vec![0.0; 960]);
+    let second = clock.stamp(vec![0.0; 960]);
+    assert_eq!((first.sample_rate, first.channels), (48_000, 2));
+    assert_eq!(first.frames(), 480);
+    assert_eq!(first.duration(), Duration::from_millis(10));
+    assert_eq!(first.pts, Duration::ZERO);
+    assert_eq!(second.pts, Duration::from_millis(10));
+    assert!(!first.is_asr_format());
+    assert!(ChunkClock::asr().stamp(vec![0.0; 320]).is_asr_format());
+}
+
+#[test]
+fn clock_carries_stream_time_across_a_format_change() {
+    let mut clock = ChunkClock::asr();
+    clock.stamp(vec![0.0; 1_600]);
+    clock.set_format(24_000, 1);
+    let changed = clock.stamp(vec![0.0; 2_400]);
+    assert_eq!((changed.sample_rate, changed.pts), (24_000, Duration::from_millis(100)));
+    assert_eq!(clock.stamp(Vec::new()).pts, Duration::from_millis(200));
+}
+
+#[test]
+fn converter_passes_asr_audio_through_untouched() {
+    let audio = SignalBuilder::new(16_000, 2).speech(0.5, 0.1).build();
+    let mut converter = AsrConverter::default();
+    assert_eq!(converter.convert(AudioChunk::asr(audio.clone(), Duration::ZERO)), audio);
+}
+
+#[test]
+fn converter_downmixes_and_resamples_stereo_48k() {
+    let mono = SignalBuilder::new(48_000, 4).speech(1.0, 0.1).build();
+    // Left and right out of phase except for the mono signal, so a bad downmix shows.
+    let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s + 0.05, s - 0.05]).collect();
+    let expected = Resampler::new(48_000, 16_000).process(&mono);
+
+    let mut clock = ChunkClock::new(48_000, 2);
+    let mut converter = AsrConverter::default();
+    let out: Vec<f32> = chunked(&stereo, 2 * 441)
+        .flat_map(|chunk| converter.convert(clock.stamp(chunk.to_vec())))
+        .collect();
+    assert_eq!(out.len(), expected.len());
+    for (got, want) in out.iter().zip(&expected) {
+        assert!((got - want).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn converter_follows_a_rate_change() {
+    let mut converter = AsrConverter::default();
+    let wide = converter.convert(ChunkClock::new(48_000, 1).stamp(vec![0.1; 4_800]));
+    let narrow = converter.convert(ChunkClock::new(24_000, 1).stamp(vec![0.1; 2_400]));
+    assert_eq!((wide.len(), narrow.len()), (1_600, 1_600));
+}
+
+#[test]
+fn resampler_rejects_chunks_in_the_wrong_format() {
+    let mut resampler = Resampler::new(48_000, 16_000);
+    let wrong_rate = ChunkClock::new(44_100, 1).stamp(vec![0.0; 441]);
+    let err = resampler.process_chunk(&wrong_rate).unwrap_err().to_string();
+    assert!(err.contains("44100"), "{err}");
+    let stereo = ChunkClock::new(48_000, 2).stamp(vec![0.0; 960]);
+    assert!(resampler.process_chunk(&stereo).is_err());
+
+    let chunk = AudioChunk {
+        samples: vec![0.0; 480],
+        sample_rate: 48_000,
+        channels: 1,
+        pts: Duration::from_secs(3),
+    };
+    let out = resampler.process_chunk(&chunk).unwrap();
+    assert!(out.is_asr_format());
+    assert_eq!((out.samples.len(), out.pts), (160, Duration::from_secs(3)));
+}
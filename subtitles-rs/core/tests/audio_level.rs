@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+use subtitles_core::audio::{AudioLevel, IdleDetector, LevelMeter};
+use subtitles_core::testing::{chunked, SignalBuilder};
+
+#[test]
+fn level_meter_reports_one_reading_per_block_regardless_of_chunking() {
+    let audio = SignalBuilder::new(16_000, 3)
+        .silence(0.5)
+        .speech(1.0, 0.1)
+        .build();
+
+    let mut reference = LevelMeter::new(1_600);
+    let expected = reference.push(&audio);
+    assert_eq!(expected.len(), audio.len() / 1_600);
+
+    for chunk_len in [1, 333, 4_096] {
+        let mut meter = LevelMeter::new(1_600);
+        let levels: Vec<AudioLevel> = chunked(&audio, chunk_len)
+            .flat_map(|chunk| meter.push(chunk))
+            .collect();
+        assert_eq!(levels.len(), expected.len());
+        for (got, want) in levels.iter().zip(&expected) {
+            assert!((got.rms - want.rms).abs() < 1e-4 && got.peak == want.peak);
+        }
+    }
+}
+
+#[test]
+fn level_meter_matches_direct_measurement() {
+    let audio = SignalBuilder::new(16_000, 5).speech(0.1, 0.2).build();
+    let mut meter = LevelMeter::new(audio.len());
+    let levels = meter.push(&audio);
+    assert_eq!(levels.len(), 1);
+
+    let direct = AudioLevel::measure(&audio);
+    assert!((levels[0].rms - direct.rms).abs() < 1e-4);
+    assert_eq!(levels[0].peak, direct.peak);
+    assert_eq!(AudioLevel::measure(&[0.0; 64]).rms_dbfs(), -120.0);
+}
+
+#[test]
+fn idle_detector_goes_idle_after_silence_and_wakes_on_audio() {
+    let start = Instant::now();
+    let at = |s: u64| start + Duration::from_secs(s);
+    let speech = SignalBuilder::new(16_000, 7).speech(0.1, 0.2).build();
+    let silence = vec![0.0f32; 1_600];
+
+    let mut idle = IdleDetector::new(0.012, Duration::from_secs(60), start);
+    assert_eq!(idle.update(Some(&speech), false, at(1)), None);
+    assert_eq!(idle.update(Some(&silence), false, at(30)), None);
+    // No chunks at all (e.g. the app stopped producing audio) count as silence too.
+    assert_eq!(idle.update(None, false, at(61)), Some(true));
+    assert_eq!(idle.update(None, false, at(90)), None);
+    assert_eq!(idle.update(Some(&speech), false, at(91)), Some(false));
+    assert!(!idle.is_idle());
+}
+
+#[test]
+fn idle_detector_idles_immediately_when_the_source_is_gone() {
+    let start = Instant::now();
+    let silence = vec![0.0f32; 1_600];
+    let mut idle = IdleDetector::new(0.012, Duration::from_secs(300), start);
+    assert_eq!(idle.update(Some(&silence), true, start), Some(true));
+    // Coming back alone doesn't resume; audio has to return.
+    assert_eq!(idle.update(Some(&silence), false, start), None);
+    assert!(idle.is_idle());
+}
+
+#[test]
+fn level_meter_block_length_can_change_mid_stream() {
+    let audio = SignalBuilder::new(16_000, 9).speech(1.0, 0.1).build();
+    let mut meter = LevelMeter::new(1_600);
+    assert_eq!(meter.push(&audio[..8_000]).len(), 5);
+    // A slower meter (`--battery-saver`) reports a quarter as often.
+    meter.set_block_len(6_400);
+    assert_eq!(meter.push(&audio[8_000..]).len(), 1);
+    meter.set_block_len(1_600);
+    assert_eq!(meter.push(&[0.0; 1_600]).len(), 1);
+}
@@ -0,0 +1,37 @@
+use subtitles_core::audio::crosstalk_fraction;
+use subtitles_core::testing::SignalBuilder;
+
+fn voice(seed: u64, seconds: f32, pitch_hz: f32, tract: f32) -> Vec<f32> {
+    SignalBuilder::new(16_000, seed)
+        .voice(seconds, pitch_hz, tract)
+        .build()
+}
+
+#[test]
+fn a_second_voice_joining_in_is_crosstalk() {
+    let mut audio = voice(1, 6.0, 120.0, 1.0);
+    assert_eq!(crosstalk_fraction(&audio), 0.0);
+
+    // Someone else talks over seconds 2-4.
+    let other = voice(2, 2.0, 210.0, 1.18);
+    for (sample, joined) in audio[32_000..64_000].iter_mut().zip(&other) {
+        *sample += joined;
+    }
+    let fraction = crosstalk_fraction(&audio);
+    assert!((0.2..=0.4).contains(&fraction), "{fraction}");
+}
+
+#[test]
+fn taking_turns_and_short_utterances_are_not_crosstalk() {
+    let turns = SignalBuilder::new(16_000, 4)
+        .voice(3.0, 120.0, 1.0)
+        .voice(3.0, 215.0, 1.18)
+        .build();
+    assert_eq!(crosstalk_fraction(&turns), 0.0);
+
+    let mut short = voice(1, 1.5, 120.0, 1.0);
+    for (sample, joined) in short[8_000..].iter_mut().zip(&voice(2, 1.0, 210.0, 1.18)) {
+        *sample += joined;
+    }
+    assert_eq!(crosstalk_fraction(&short), 0.0);
+}
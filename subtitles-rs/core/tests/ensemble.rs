@@ -0,0 +1,109 @@
+use subtitles_core::config::OutputLanguage;
+use subtitles_core::transcribe::{
+    agreement, choose, EnsembleTranscriber, Transcriber, TranscriberConfig, Transcript,
+};
+
+fn transcript(text: &str, confidence: Option<f32>) -> Transcript {
+    Transcript {
+        text: text.to_string(),
+        confidence,
+        ..Transcript::default()
+    }
+}
+
+/// Returns the same result for every decode, or fails.
+struct Fixed(Option<Transcript>);
+
+impl Transcriber for Fixed {
+    fn transcribe(&mut self, _: &[f32], _: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(self.transcribe_detailed(&[], &config(false))?.text)
+    }
+
+    fn transcribe_detailed(
+        &mut self,
+        _: &[f32],
+        _: &TranscriberConfig,
+    ) -> anyhow::Result<Transcript> {
+        self.0.clone().ok_or_else(|| anyhow::anyhow!("offline"))
+    }
+}
+
+fn config(is_partial: bool) -> TranscriberConfig {
+    TranscriberConfig {
+        input_language: None,
+        output_language: OutputLanguage::English,
+        is_partial,
+        segment_timestamps: false,
+        prompt: None,
+        glossary: None,
+        diarize: false,
+    }
+}
+
+#[test]
+fn choose_weighs_confidence_agreement_and_loops() {
+    assert_eq!(agreement("Hello, world.", "hello world"), 1.0);
+    assert!(agreement("the cat sat", "a dog ran off") < 0.5);
+
+    // A sure local decode beats the cloud's assumed confidence; agreement raises the result.
+    let mut local = transcript("We ship on Friday.", Some(0.9));
+    local.speaker_turns = 1;
+    let picked = choose(local, transcript("We ship on Friday", None));
+    assert_eq!(picked.text, "We ship on Friday.");
+    assert!(picked.confidence.unwrap() > 0.9);
+
+    // An unsure local decode loses, but keeps its speaker turns; disagreement lowers the result.
+    let mut local = transcript("We sip on fried eggs.", Some(0.4));
+    local.speaker_turns = 1;
+    let picked = choose(local, transcript("We ship on Friday.", None));
+    assert_eq!(picked.text, "We ship on Friday.");
+    assert_eq!(picked.speaker_turns, 1);
+    assert!(picked.confidence.unwrap() < 0.8);
+
+    // A looping decode loses even with a higher confidence.
+    let picked = choose(
+        transcript("thank you thank you you you you you", Some(0.95)),
+        transcript("Thank you, everyone.", None),
+    );
+    assert_eq!(picked.text, "Thank you, everyone.");
+    let picked = choose(transcript("", Some(0.99)), transcript("Next slide.", None));
+    assert_eq!(picked.text, "Next slide.");
+}
+
+#[test]
+fn partials_stay_local_and_a_failed_engine_falls_back() {
+    let mut ensemble = EnsembleTranscriber::new(
+        Box::new(Fixed(Some(transcript("local", Some(0.3))))),
+        Box::new(Fixed(Some(transcript("cloud", None)))),
+    );
+    let audio = [0.0f32; 160];
+    assert_eq!(ensemble.transcribe(&audio, &config(true)).unwrap(), "local");
+    assert_eq!(
+        ensemble.transcribe(&audio, &config(false)).unwrap(),
+        "cloud"
+    );
+
+    let mut offline = EnsembleTranscriber::new(
+        Box::new(Fixed(Some(transcript("local", Some(0.3))))),
+        Box::new(Fixed(None)),
+    );
+    assert_eq!(offline.transcribe(&audio, &config(false)).unwrap(), "local");
+
+    let mut broken = EnsembleTranscriber::new(Box::new(Fixed(None)), Box::new(Fixed(None)));
+    assert!(broken.transcribe(&audio, &config(false)).is_err());
+}
+
+#[test]
+fn an_untranslated_cloud_result_loses_to_a_local_translation() {
+    let mut untranslated = transcript("我们周五发布", None);
+    untranslated.untranslated = true;
+    let mut ensemble = EnsembleTranscriber::new(
+        Box::new(Fixed(Some(transcript("We ship on Friday", Some(0.3))))),
+        Box::new(Fixed(Some(untranslated))),
+    );
+    let picked = ensemble
+        .transcribe_detailed(&[0.0f32; 160], &config(false))
+        .unwrap();
+    assert_eq!(picked.text, "We ship on Friday");
+    assert!(!picked.untranslated);
+}
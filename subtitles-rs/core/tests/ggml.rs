@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::PathBuf;
+
+use subtitles_core::transcribe::inspect_model;
+
+const N_VOCAB: i32 = 51_865;
+const N_STATE: i32 = 4;
+const N_CTX: i32 = 8;
+
+/// A whisper-shaped ggml file with one encoder and one decoder layer and zeroed weights.
+/// `edit` can change the tensor list before it is written.
+fn write_model(name: &str, edit: impl FnOnce(&mut Vec<(String, Vec<i32>)>)) -> PathBuf {
+    let mut tensors: Vec<(String, Vec<i32>)> = vec![
+        ("encoder.positional_embedding".into(), vec![N_STATE, N_CTX]),
+        ("encoder.conv1.weight".into(), vec![3, 80, N_STATE]),
+        ("encoder.conv1.bias".into(), vec![1, N_STATE]),
+        ("encoder.conv2.weight".into(), vec![3, N_STATE, N_STATE]),
+        ("encoder.conv2.bias".into(), vec![1, N_STATE]),
+        ("encoder.ln_post.weight".into(), vec![N_STATE]),
+        ("encoder.ln_post.bias".into(), vec![N_STATE]),
+        ("decoder.positional_embedding".into(), vec![N_STATE, N_CTX]),
+        (
+            "decoder.token_embedding.weight".into(),
+            vec![N_STATE, N_VOCAB],
+        ),
+        ("decoder.ln.weight".into(), vec![N_STATE]),
+        ("decoder.ln.bias".into(), vec![N_STATE]),
+    ];
+    let block = |prefix: &str, cross: bool| {
+        let mut names = vec![];
+        let attn = |names: &mut Vec<(String, Vec<i32>)>, attn: &str| {
+            for (t, dims) in [
+                ("_ln.weight", vec![N_STATE]),
+                ("_ln.bias", vec![N_STATE]),
+                (".query.weight", vec![N_STATE, N_STATE]),
+                (".query.bias", vec![N_STATE]),
+                (".key.weight", vec![N_STATE, N_STATE]),
+                (".value.weight", vec![N_STATE, N_STATE]),
+                (".value.bias", vec![N_STATE]),
+                (".out.weight", vec![N_STATE, N_STATE]),
+                (".out.bias", vec![N_STATE]),
+            ] {
+                names.push((format!("{prefix}.{attn}{t}"), dims));
+            }
+        };
+        attn(&mut names, "attn");
+        if cross {
+            attn(&mut names, "cross_attn");
+        }
+        for (t, dims) in [
+            ("mlp_ln.weight", vec![N_STATE]),
+            ("mlp_ln.bias", vec![N_STATE]),
+            ("mlp.0.weight", vec![N_STATE, 4 * N_STATE]),
+            ("mlp.0.bias", vec![4 * N_STATE]),
+            ("mlp.2.weight", vec![4 * N_STATE, N_STATE]),
+            ("mlp.2.bias", vec![N_STATE]),
+        ] {
+            names.push((format!("{prefix}.{t}"), dims));
+        }
+        names
+    };
+    tensors.extend(block("encoder.blocks.0", false));
+    tensors.extend(block("decoder.blocks.0", true));
+    edit(&mut tensors);
+
+    let mut out = Vec::new();
+    let int = |out: &mut Vec<u8>, v: i32| out.extend_from_slice(&v.to_le_bytes());
+    out.extend_from_slice(&0x6767_6d6cu32.to_le_bytes());
+    // n_vocab, audio ctx/state/head/layer, text ctx/state/head/layer, n_mels, ftype (f16).
+    for v in [N_VOCAB, N_CTX, N_STATE, 1, 1, N_CTX, N_STATE, 1, 1, 80, 1] {
+        int(&mut out, v);
+    }
+    // A 2x2 mel filterbank and a one-word vocabulary.
+    int(&mut out, 2);
+    int(&mut out, 2);
+    out.extend_from_slice(&[0; 16]);
+    int(&mut out, 1);
+    int(&mut out, 1);
+    out.push(b'a');
+    for (name, dims) in &tensors {
+        int(&mut out, dims.len() as i32);
+        int(&mut out, name.len() as i32);
+        int(&mut out, 1);
+        for &dim in dims {
+            int(&mut out, dim);
+        }
+        out.extend_from_slice(name.as_bytes());
+        let elements: i32 = dims.iter().product();
+        out.resize(out.len() + elements as usize * 2, 0);
+    }
+
+    let dir = std::env::temp_dir().join(format!("subtitles-ggml-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, out).unwrap();
+    path
+}
+
+#[test]
+fn whisper_shaped_model_passes() {
+    let path = write_model("ok.bin", |_| {});
+    let info = inspect_model(&path).unwrap();
+    assert_eq!(info.n_vocab, N_VOCAB);
+    assert_eq!((info.n_audio_layer, info.n_text_layer), (1, 1));
+    assert_eq!(info.tensors, 11 + 15 + 24);
+    assert!(info.multilingual());
+    assert_eq!(info.architecture(), "custom");
+}
+
+#[test]
+fn incompatible_tensors_and_formats_are_rejected() {
+    let error = |path: PathBuf| format!("{:#}", inspect_model(&path).unwrap_err());
+
+    let adapter = write_model("adapter.bin", |tensors| {
+        tensors.push((
+            "decoder.blocks.0.attn.query.lora_a".into(),
+            vec![N_STATE, 2],
+        ));
+    });
+    assert!(error(adapter).contains("unexpected tensor"));
+
+    let reshaped = write_model("reshaped.bin", |tensors| {
+        tensors[8].1 = vec![N_STATE, 100];
+    });
+    assert!(error(reshaped).contains("decoder.token_embedding.weight"));
+
+    let missing = write_model("missing.bin", |tensors| {
+        tensors.pop();
+    });
+    assert!(error(missing).contains("missing"));
+
+    let truncated = write_model("truncated.bin", |_| {});
+    let bytes = fs::read(&truncated).unwrap();
+    fs::write(&truncated, &bytes[..bytes.len() - 4]).unwrap();
+    assert!(error(truncated).contains("past the end"));
+
+    let gguf = write_model("model.gguf", |_| {});
+    let mut bytes = fs::read(&gguf).unwrap();
+    bytes[..4].copy_from_slice(b"GGUF");
+    fs::write(&gguf, bytes).unwrap();
+    assert!(error(gguf).contains("GGUF"));
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use subtitles_core::config::OutputLanguage;
+use subtitles_core::text::Glossary;
+use subtitles_core::transcribe::TranscriberConfig;
+
+fn glossary() -> Glossary {
+    Glossary::from_json(
+        r#"{
+            "微信": "WeChat",
+            "飞书": { "translation": "Lark", "avoid": ["Flying Book", "feishu"] }
+        }"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn post_edit_replaces_source_terms_and_avoided_renderings() {
+    let glossary = glossary();
+    assert_eq!(glossary.entries().len(), 2);
+    assert_eq!(
+        glossary.post_edit("Send it on 微信 or the flying book app, Feishu works too."),
+        "Send it on WeChat or the Lark app, Lark works too."
+    );
+    // Whole words only for Latin terms.
+    assert_eq!(glossary.post_edit("feishuang"), "feishuang");
+}
+
+#[test]
+fn glossary_only_applies_when_translating() {
+    let mut cfg = TranscriberConfig {
+        input_language: Some("zh".into()),
+        output_language: OutputLanguage::English,
+        is_partial: false,
+        segment_timestamps: false,
+        prompt: Some("Use punctuation.".into()),
+        glossary: Some(Arc::new(glossary())),
+        diarize: false,
+    };
+    assert_eq!(
+        cfg.effective_prompt().as_deref(),
+        Some("Use punctuation. Glossary: 微信 = WeChat, 飞书 = Lark.")
+    );
+    assert_eq!(cfg.post_edit("open 微信".into()), "open WeChat");
+
+    cfg.output_language = OutputLanguage::Chinese;
+    assert_eq!(cfg.effective_prompt().as_deref(), Some("Use punctuation."));
+    assert_eq!(cfg.post_edit("打开微信".into()), "打开微信");
+}
+
+#[test]
+fn rejects_malformed_entries() {
+    assert!(Glossary::from_json(r#"{ "微信": 3 }"#).is_err());
+    assert!(Glossary::from_json(r#"{ "微信": "" }"#).is_err());
+}
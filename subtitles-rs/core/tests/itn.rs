@@ -0,0 +1,46 @@
+use subtitles_core::config::ItnLocale;
+use subtitles_core::text::normalize_numbers;
+
+fn en(text: &str) -> String {
+    normalize_numbers(text, ItnLocale::English)
+}
+
+#[test]
+fn cardinals_years_and_ordinals() {
+    assert_eq!(en("about twenty-five people came"), "about 25 people came");
+    assert_eq!(en("One hundred and five, maybe more."), "105, maybe more.");
+    assert_eq!(en("it cost two thousand five hundred"), "it cost 2500");
+    assert_eq!(en("forty two thousand fans"), "42,000 fans");
+    assert_eq!(en("two million people"), "2 million people");
+    assert_eq!(en("back in twenty twenty five"), "back in 2025");
+    assert_eq!(en("since nineteen eighty four and nineteen oh five"), "since 1984 and 1905");
+    assert_eq!(en("on the twenty first of May"), "on the 21st of May");
+    assert_eq!(en("pi is three point one four"), "pi is 3.14");
+}
+
+#[test]
+fn small_numbers_stay_spelled_out() {
+    assert_eq!(en("one of the two options"), "one of the two options");
+    assert_eq!(en("wait a second, the first one"), "wait a second, the first one");
+    assert_eq!(en("zero-sum"), "zero-sum");
+    assert_eq!(en("one-two punch"), "one-two punch");
+}
+
+#[test]
+fn times_amounts_and_percentages() {
+    assert_eq!(en("meet at three thirty p.m. today"), "meet at 3:30 p.m. today");
+    assert_eq!(en("lunch at twelve oh five pm."), "lunch at 12:05 p.m.");
+    assert_eq!(en("by seven o'clock"), "by 7 o'clock");
+    assert_eq!(en("five dollars and fifty cents"), "$5.50");
+    assert_eq!(en("two million euros"), "€2 million");
+    assert_eq!(en("up fifty percent"), "up 50%");
+    assert_eq!(en("three point five per cent"), "3.5%");
+}
+
+#[test]
+fn off_and_punctuation_boundaries() {
+    let text = "twenty twenty five was fine";
+    assert_eq!(normalize_numbers(text, ItnLocale::Off), text);
+    // A comma ends the number.
+    assert_eq!(en("twenty, twenty five"), "20, 25");
+}
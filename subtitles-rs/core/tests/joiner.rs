@@ -0,0 +1,40 @@
+use subtitles_core::config::OutputLanguage;
+use subtitles_core::text::TextJoiner;
+
+#[test]
+fn cjk_text_joins_without_spaces() {
+    let joiner = TextJoiner::for_language(Some("zh"));
+    assert_eq!(joiner.join("我们今天", "讨论一下"), "我们今天讨论一下");
+    assert_eq!(joiner.join("你好，", "世界"), "你好，世界");
+    // Stabilizer tokens come back space-separated.
+    assert_eq!(
+        joiner.format("我们 今天 讨论 iPhone 的 问题。"),
+        "我们今天讨论 iPhone 的问题。"
+    );
+    // Auto-detect still applies the script rule.
+    assert_eq!(TextJoiner::for_language(None).join("今日は", "いい天気"), "今日はいい天気");
+}
+
+#[test]
+fn latin_text_keeps_spaces_but_not_before_punctuation() {
+    let joiner = TextJoiner::for_output(OutputLanguage::English, Some("zh"));
+    assert_eq!(joiner.join("hello there", "general Kenobi"), "hello there general Kenobi");
+    assert_eq!(joiner.join("wait", ", what?"), "wait, what?");
+    assert_eq!(joiner.join("", "  only partial "), "only partial");
+    assert_eq!(joiner.format("  too   many spaces "), "too many spaces");
+}
+
+#[test]
+fn french_uses_no_break_spaces_before_high_punctuation() {
+    let joiner = TextJoiner::for_language(Some("fr-FR"));
+    assert_eq!(joiner.join("Vraiment", "?"), "Vraiment\u{202F}?");
+    assert_eq!(
+        joiner.format("Bonjour! Il dit: «Salut»"),
+        "Bonjour\u{202F}! Il dit\u{202F}: «\u{A0}Salut\u{A0}»"
+    );
+    assert_eq!(joiner.format("Quoi ?!"), "Quoi\u{202F}?!");
+    // Already formatted text is stable, and times keep their colon.
+    let formatted = joiner.format("Il est 10:30 ; c'est tard !");
+    assert_eq!(formatted, "Il est 10:30\u{202F}; c'est tard\u{202F}!");
+    assert_eq!(joiner.format(&formatted), formatted);
+}
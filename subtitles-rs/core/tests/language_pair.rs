@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use subtitles_core::config::OutputLanguage;
+use subtitles_core::transcribe::{
+    language_runs, pick_language, LanguagePairTranscriber, Transcriber, TranscriberConfig,
+    Transcript,
+};
+
+fn transcript(text: &str, confidence: Option<f32>) -> Transcript {
+    Transcript {
+        text: text.to_string(),
+        confidence,
+        ..Transcript::default()
+    }
+}
+
+fn config(is_partial: bool) -> TranscriberConfig {
+    TranscriberConfig {
+        input_language: None,
+        output_language: OutputLanguage::Chinese,
+        is_partial,
+        segment_timestamps: false,
+        prompt: None,
+        glossary: None,
+        diarize: false,
+    }
+}
+
+/// Tags its output with the language it was asked to decode in, and detects `detected`.
+struct Tagging {
+    detected: Arc<Mutex<Option<String>>>,
+}
+
+impl Transcriber for Tagging {
+    fn transcribe(&mut self, _: &[f32], cfg: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(format!(
+            "in {}",
+            cfg.input_language.as_deref().unwrap_or("auto")
+        ))
+    }
+
+    fn detect_language(&mut self, _: &[f32], _: &[String]) -> Option<String> {
+        self.detected.lock().clone()
+    }
+}
+
+#[test]
+fn the_decode_in_the_detected_language_is_kept() {
+    let detected = Arc::new(Mutex::new(Some("ja".to_string())));
+    let engine = |detected: &Arc<Mutex<Option<String>>>| {
+        Box::new(Tagging {
+            detected: detected.clone(),
+        })
+    };
+    let mut pair = LanguagePairTranscriber::new(
+        ["en".into(), "ja".into()],
+        engine(&detected),
+        engine(&detected),
+    );
+    let audio = [0.0; 160];
+
+    // Partials start out in the first language, then follow the last final.
+    assert_eq!(pair.transcribe(&audio, &config(true)).unwrap(), "in en");
+    assert_eq!(pair.transcribe(&audio, &config(false)).unwrap(), "in ja");
+    assert_eq!(pair.transcribe(&audio, &config(true)).unwrap(), "in ja");
+
+    *detected.lock() = Some("en".into());
+    assert_eq!(pair.transcribe(&audio, &config(false)).unwrap(), "in en");
+    assert_eq!(pair.transcribe(&audio, &config(true)).unwrap(), "in en");
+}
+
+#[test]
+fn without_detection_the_more_confident_decode_wins() {
+    let languages = ["en".to_string(), "ja".to_string()];
+    let en = transcript("we ship on friday", Some(0.9));
+    let ja = transcript("ウィーシップオンフライデー", Some(0.4));
+    assert_eq!(pick_language(&languages, Some("JA"), &en, &ja), 1);
+    assert_eq!(pick_language(&languages, None, &en, &ja), 0);
+    // A language outside the pair says nothing about either decode.
+    assert_eq!(pick_language(&languages, Some("ko"), &ja, &en), 1);
+    // Words beat silence, whatever the detection.
+    assert_eq!(
+        pick_language(&languages, Some("en"), &transcript("", None), &ja),
+        1
+    );
+}
+
+/// Detects Japanese in loud audio and English in quiet audio.
+struct ByLevel;
+
+impl Transcriber for ByLevel {
+    fn transcribe(&mut self, _: &[f32], cfg: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(format!(
+            "in {}",
+            cfg.input_language.as_deref().unwrap_or("auto")
+        ))
+    }
+
+    fn detect_language(&mut self, audio: &[f32], _: &[String]) -> Option<String> {
+        let level = audio.iter().sum::<f32>() / audio.len() as f32;
+        Some(if level > 0.5 { "ja" } else { "en" }.to_string())
+    }
+}
+
+#[test]
+fn code_switching_decodes_each_language_part_in_its_own_language() {
+    let seconds = |level: f32, s: usize| vec![level; 16_000 * s];
+    // 3 s of English, 3 s of Japanese and a 1 s flip back that is taken for noise.
+    let audio = [seconds(0.1, 3), seconds(0.9, 3), seconds(0.1, 1)].concat();
+
+    let mut whole = LanguagePairTranscriber::new(
+        ["en".into(), "ja".into()],
+        Box::new(ByLevel),
+        Box::new(ByLevel),
+    );
+    assert_eq!(whole.transcribe(&audio, &config(false)).unwrap(), "in en");
+
+    let mut pair = LanguagePairTranscriber::new(
+        ["en".into(), "ja".into()],
+        Box::new(ByLevel),
+        Box::new(ByLevel),
+    )
+    .with_code_switching(true);
+    assert_eq!(
+        pair.transcribe(&audio, &config(false)).unwrap(),
+        "in en in ja"
+    );
+    assert_eq!(pair.transcribe(&audio, &config(true)).unwrap(), "in ja");
+}
+
+#[test]
+fn short_language_flips_are_not_switches() {
+    let (en, ja) = (Some(0), Some(1));
+    assert_eq!(
+        language_runs(&[en, en, ja, ja, None, en], 2),
+        [(0, 0), (2, 1)]
+    );
+    assert_eq!(language_runs(&[en, en, ja, en, en], 2), [(0, 0)]);
+    // A short start belongs to what follows.
+    assert_eq!(language_runs(&[ja, en, en, en], 2), [(0, 0)]);
+    assert!(language_runs(&[None, None], 2).is_empty());
+}
@@ -0,0 +1,94 @@
+use std::f32::consts::PI;
+
+use subtitles_core::audio::{loudness_lufs, LoudnessGate, Segmenter, SegmenterConfig};
+use subtitles_core::testing::{chunked, SignalBuilder};
+
+const SR: u32 = 16_000;
+
+fn tone(hz: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+    (0..(seconds * SR as f32) as usize)
+        .map(|i| amplitude * (2.0 * PI * hz * i as f32 / SR as f32).sin())
+        .collect()
+}
+
+fn segment_count(audio: &[f32], gate: Option<LoudnessGate>) -> usize {
+    let mut segmenter = Segmenter::new(SegmenterConfig {
+        sample_rate_hz: SR,
+        vad_threshold: 0.012,
+        vad_end_silence_s: 0.6,
+        max_segment_s: 20.0,
+        pre_roll_s: 0.25,
+    });
+    if let Some(gate) = gate {
+        segmenter = segmenter.with_loudness_gate(gate);
+    }
+    chunked(audio, 320)
+        .map(|chunk| segmenter.push_audio(chunk).len())
+        .sum()
+}
+
+#[test]
+fn full_scale_tone_measures_as_bs1770_says() {
+    let lufs = loudness_lufs(&tone(997.0, 1.0, 2.0), SR);
+    assert!((lufs + 3.01).abs() < 0.1, "{lufs}");
+    let quieter = loudness_lufs(&tone(997.0, 0.1, 2.0), SR);
+    assert!((quieter - lufs + 20.0).abs() < 0.1, "{quieter}");
+}
+
+#[test]
+fn k_weighting_discounts_rumble() {
+    let rumble = loudness_lufs(&tone(30.0, 0.1, 2.0), SR);
+    let voice_band = loudness_lufs(&tone(997.0, 0.1, 2.0), SR);
+    assert!(rumble < voice_band - 8.0, "{rumble} vs {voice_band}");
+}
+
+#[test]
+fn loudness_gate_ends_lines_over_a_loud_music_bed() {
+    // Dialogue mastered hot, with a bed 20 dB down between lines: always above an RMS
+    // threshold, so the RMS VAD never sees a pause.
+    let film = SignalBuilder::new(SR, 5)
+        .speech(2.0, 0.2)
+        .noise(1.0, 0.026)
+        .speech(2.0, 0.2)
+        .noise(2.0, 0.026)
+        .build();
+    assert_eq!(segment_count(&film, None), 0);
+    assert_eq!(segment_count(&film, Some(LoudnessGate::new(-50.0, SR))), 2);
+}
+
+#[test]
+fn loudness_gate_hears_a_quiet_call_with_the_same_setting() {
+    let call = SignalBuilder::new(SR, 6)
+        .silence(1.0)
+        .speech(2.0, 0.008)
+        .silence(1.0)
+        .speech(2.0, 0.008)
+        .silence(2.0)
+        .build();
+    assert_eq!(segment_count(&call, None), 0);
+    assert_eq!(segment_count(&call, Some(LoudnessGate::new(-50.0, SR))), 2);
+}
+
+#[test]
+fn loudness_gate_ends_lines_with_overlapping_frames() {
+    // 40 ms frames every 10 ms, over the same film as above.
+    let film = SignalBuilder::new(SR, 5)
+        .speech(2.0, 0.2)
+        .noise(1.0, 0.026)
+        .speech(2.0, 0.2)
+        .noise(2.0, 0.026)
+        .build();
+    let mut segmenter = Segmenter::new(SegmenterConfig {
+        sample_rate_hz: SR,
+        vad_threshold: 0.012,
+        vad_end_silence_s: 0.6,
+        max_segment_s: 20.0,
+        pre_roll_s: 0.25,
+    })
+    .with_vad_frames(40, 10)
+    .with_loudness_gate(LoudnessGate::new(-50.0, SR));
+    let segments: usize = chunked(&film, 320)
+        .map(|chunk| segmenter.push_audio(chunk).len())
+        .sum();
+    assert_eq!(segments, 2);
+}
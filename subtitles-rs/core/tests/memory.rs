@@ -0,0 +1,72 @@
+use std::fs;
+
+use subtitles_core::text::TranslationMemory;
+
+#[test]
+fn lookup_matches_exact_and_near_identical_sentences() {
+    let mut memory = TranslationMemory::new();
+    memory.insert(
+        "我们明天早上九点在公司门口集合。",
+        "We meet at the office door at 9 tomorrow.",
+    );
+    memory.insert("好的", "OK");
+
+    // Punctuation and spacing don't matter.
+    assert_eq!(
+        memory.lookup("我们明天早上九点在公司门口集合"),
+        Some("We meet at the office door at 9 tomorrow.")
+    );
+    // One character off in a long sentence still matches.
+    assert_eq!(
+        memory.lookup("我们明天早上九点到公司门口集合。"),
+        Some("We meet at the office door at 9 tomorrow.")
+    );
+    // Unless it changes a number or adds a negation: that's a different sentence.
+    assert_eq!(memory.lookup("我们明天早上十点在公司门口集合。"), None);
+    assert_eq!(memory.lookup("我们明天早上九点不在公司门口集合。"), None);
+    // Short sentences only match exactly.
+    assert_eq!(memory.lookup("好的！"), Some("OK"));
+    assert_eq!(memory.lookup("好吧"), None);
+    assert_eq!(memory.lookup("今天天气很好，我们去公园散步吧。"), None);
+    assert_eq!(memory.len(), 2);
+}
+
+#[test]
+fn near_matches_keep_english_numbers_and_negations() {
+    let mut memory = TranslationMemory::new();
+    memory.insert("we will ship on the 12th", "我们12号发布");
+    memory.insert("the build is ready for review", "构建已可审查");
+
+    assert_eq!(memory.lookup("we will ship on the 12th."), Some("我们12号发布"));
+    assert_eq!(memory.lookup("we will ship on the 13th"), None);
+    assert_eq!(memory.lookup("the build isn't ready for review"), None);
+    assert_eq!(memory.lookup("the build is ready for a review"), Some("构建已可审查"));
+}
+
+#[test]
+fn file_memory_persists_and_compacts_repeated_sources() {
+    let dir = std::env::temp_dir().join(format!("subtitles-memory-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("memory.jsonl");
+    let _ = fs::remove_file(&path);
+
+    {
+        let mut memory = TranslationMemory::open(&path).unwrap();
+        assert!(memory.is_empty());
+        memory.insert("谢谢大家", "Thank you all");
+        memory.insert("谢谢大家", "Thanks, everyone");
+        memory.insert("下次见", "See you next time");
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+    let memory = TranslationMemory::open(&path).unwrap();
+    assert_eq!(memory.len(), 2);
+    assert_eq!(memory.lookup("谢谢大家。"), Some("Thanks, everyone"));
+    assert_eq!(memory.lookup("下次见"), Some("See you next time"));
+    // The superseded line was dropped when the file was loaded.
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+    fs::write(&path, "not json\n").unwrap();
+    assert!(TranslationMemory::open(&path).is_err());
+    let _ = fs::remove_dir_all(&dir);
+}
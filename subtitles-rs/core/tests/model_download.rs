@@ -0,0 +1,154 @@
+#![cfg(feature = "engines")]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use subtitles_core::transcribe::{download_file, CustomModel};
+
+/// Serves `body` on every request, honoring `Range` when `ranges` is set; counts the 206s.
+fn serve(body: Vec<u8>, ranges: bool) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/ggml-test.bin", listener.local_addr().unwrap());
+    let partials = Arc::new(AtomicUsize::new(0));
+    let body = Arc::new(body);
+    let counter = partials.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (body, counter) = (body.clone(), counter.clone());
+            std::thread::spawn(move || respond(stream, &body, ranges, &counter));
+        }
+    });
+    (url, partials)
+}
+
+fn respond(mut stream: TcpStream, body: &[u8], ranges: bool, partials: &AtomicUsize) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request = String::new();
+    reader.read_line(&mut request).unwrap();
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+            let (start, end) = value.trim().split_once('-').unwrap();
+            range = Some((
+                start.parse::<usize>().unwrap(),
+                end.parse::<usize>().unwrap(),
+            ));
+        }
+    }
+    let accept = if ranges {
+        "Accept-Ranges: bytes\r\n"
+    } else {
+        ""
+    };
+    let (status, part) = match range.filter(|_| ranges) {
+        Some((start, end)) => {
+            partials.fetch_add(1, Ordering::SeqCst);
+            ("206 Partial Content", &body[start..=end])
+        }
+        None => ("200 OK", body),
+    };
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\n{accept}Connection: close\r\n\r\n",
+        part.len()
+    );
+    stream.write_all(head.as_bytes()).unwrap();
+    if !request.starts_with("HEAD") {
+        let _ = stream.write_all(part);
+    }
+}
+
+fn dest(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("subtitles-download-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("ggml-test.bin")
+}
+
+#[test]
+fn a_large_file_is_fetched_over_parallel_ranges() {
+    let body: Vec<u8> = (0..20u32 << 20).map(|i| (i % 251) as u8).collect();
+    let (url, partials) = serve(body.clone(), true);
+    let path = dest("ranges");
+
+    let mut progress = Vec::new();
+    download_file(&url, &path, "ggml-test.bin", None, &mut |p| {
+        assert_eq!(p.total_bytes, Some(body.len() as u64));
+        progress.push(p.downloaded_bytes);
+    })
+    .unwrap();
+
+    assert!(fs::read(&path).unwrap() == body);
+    assert!(!path.with_extension("download").exists());
+    assert_eq!(partials.load(Ordering::SeqCst), 4);
+    assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(progress.last(), Some(&(body.len() as u64)));
+}
+
+#[test]
+fn a_server_without_ranges_gets_one_stream() {
+    let body: Vec<u8> = (0..20u32 << 20).map(|i| (i % 13) as u8).collect();
+    let (url, partials) = serve(body.clone(), false);
+    let path = dest("stream");
+
+    let mut last = 0;
+    download_file(&url, &path, "ggml-test.bin", None, &mut |p| {
+        last = p.downloaded_bytes
+    })
+    .unwrap();
+
+    assert!(fs::read(&path).unwrap() == body);
+    assert_eq!(partials.load(Ordering::SeqCst), 0);
+    assert_eq!(last, body.len() as u64);
+}
+
+#[test]
+fn a_download_must_match_its_sha256() {
+    let (url, _) = serve(b"not the model you wanted".to_vec(), false);
+    let path = dest("sha256");
+    // sha256 of the empty string.
+    let wrong = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    let err = download_file(&url, &path, "ggml-test.bin", Some(wrong), &mut |_| {}).unwrap_err();
+    assert!(err.to_string().contains("expected e3b0c442"), "{err:#}");
+    assert!(!path.exists() && !path.with_extension("download").exists());
+
+    let right = "BF049D9F25E12E8BD346482D720121D723F421EDE15163EBABD4A0D727E08763";
+    download_file(&url, &path, "ggml-test.bin", Some(right), &mut |_| {}).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"not the model you wanted");
+}
+
+#[test]
+fn custom_models_parse_with_an_optional_checksum() {
+    let model: CustomModel = "Acme-Small=https://models.acme.internal/ggml-small.bin#sha256=\
+                              E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+        .parse()
+        .unwrap();
+    assert_eq!(model.name, "acme-small");
+    assert_eq!(model.url, "https://models.acme.internal/ggml-small.bin");
+    assert_eq!(
+        model.sha256.as_deref(),
+        Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+    );
+    assert!(model.path().ends_with("custom-acme-small.bin"));
+
+    let plain: CustomModel = "tiny=http://mirror.lan/tiny.bin".parse().unwrap();
+    assert_eq!(plain.sha256, None);
+    assert!("tiny=ftp://mirror.lan/tiny.bin"
+        .parse::<CustomModel>()
+        .is_err());
+    assert!("tiny=https://mirror.lan/tiny.bin#sha256=abc"
+        .parse::<CustomModel>()
+        .is_err());
+    assert!("../x=https://mirror.lan/tiny.bin"
+        .parse::<CustomModel>()
+        .is_err());
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "engines")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use subtitles_core::config::WhisperModelPreset;
+use subtitles_core::transcribe::{
+    cached_smaller_preset, largest_preset_within, model_dir, preset_gpu_memory_mb,
+    preset_model_path,
+};
+
+fn models(name: &str, cached: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("subtitles-models-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for file in cached {
+        fs::write(dir.join(file), b"ggml").unwrap();
+    }
+    dir
+}
+
+#[test]
+fn the_largest_cached_smaller_preset_stands_in() {
+    let dir = models(
+        "mixed",
+        &["ggml-tiny.bin", "ggml-small.bin", "ggml-large-v3.bin"],
+    );
+    assert!(matches!(
+        cached_smaller_preset(&dir, &WhisperModelPreset::Medium),
+        Some(WhisperModelPreset::Small)
+    ));
+    assert!(matches!(
+        cached_smaller_preset(&dir, &WhisperModelPreset::Base),
+        Some(WhisperModelPreset::Tiny)
+    ));
+    // Never a larger model, even if it is there.
+    assert!(cached_smaller_preset(&dir, &WhisperModelPreset::Tiny).is_none());
+    assert_eq!(
+        preset_model_path(&WhisperModelPreset::Small),
+        model_dir().join("ggml-small.bin")
+    );
+}
+
+#[test]
+fn nothing_stands_in_without_a_cached_smaller_model() {
+    let dir = models("empty", &["ggml-medium.bin.download"]);
+    assert!(cached_smaller_preset(&dir, &WhisperModelPreset::LargeV3).is_none());
+    let missing = dir.join("missing");
+    assert!(cached_smaller_preset(&missing, &WhisperModelPreset::Medium).is_none());
+}
+
+#[test]
+fn presets_step_down_to_fit_a_gpu_memory_limit() {
+    let small = preset_gpu_memory_mb(&WhisperModelPreset::Small);
+    assert!(matches!(
+        largest_preset_within(&WhisperModelPreset::LargeV3, small),
+        Some(WhisperModelPreset::Small)
+    ));
+    // Never a larger model than asked for, however much room there is.
+    assert!(matches!(
+        largest_preset_within(&WhisperModelPreset::Base, u64::MAX),
+        Some(WhisperModelPreset::Base)
+    ));
+    let tiny = preset_gpu_memory_mb(&WhisperModelPreset::Tiny);
+    assert!(largest_preset_within(&WhisperModelPreset::Medium, tiny - 1).is_none());
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "engines")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+
+use subtitles_core::config::OutputLanguage;
+use subtitles_core::transcribe::{OpenAiTranscriber, Transcriber, TranscriberConfig};
+
+/// An API whose `/translations` answers `translation_status` and whose `/transcriptions`
+/// returns Chinese text.
+fn serve(translation_status: &'static str) -> (String, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}/v1/audio", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).unwrap();
+            let (status, body) = if request.contains("/translations") {
+                (translation_status, r#"{"error":"try again"}"#)
+            } else {
+                ("200 OK", r#"{"text":"我们周五发布"}"#)
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (
+        format!("{base}/transcriptions"),
+        format!("{base}/translations"),
+    )
+}
+
+fn config(output_language: OutputLanguage) -> TranscriberConfig {
+    TranscriberConfig {
+        input_language: Some("zh".to_string()),
+        output_language,
+        is_partial: false,
+        segment_timestamps: false,
+        prompt: None,
+        glossary: None,
+        diarize: false,
+    }
+}
+
+fn transcriber(endpoints: (String, String)) -> OpenAiTranscriber {
+    OpenAiTranscriber::new(
+        Some("test-key".to_string()),
+        "whisper-1".to_string(),
+        endpoints.0,
+        endpoints.1,
+    )
+    .unwrap()
+}
+
+#[test]
+fn a_failed_translation_is_resent_as_a_transcription() {
+    let mut openai = transcriber(serve("503 Service Unavailable"));
+    let transcript = openai
+        .transcribe_detailed(&[0.0; 1600], &config(OutputLanguage::English))
+        .unwrap();
+    assert_eq!(transcript.text, "我们周五发布");
+    assert!(transcript.untranslated);
+}
+
+#[test]
+fn a_transcription_is_never_marked_untranslated() {
+    let mut openai = transcriber(serve("503 Service Unavailable"));
+    let transcript = openai
+        .transcribe_detailed(&[0.0; 1600], &config(OutputLanguage::Chinese))
+        .unwrap();
+    assert_eq!(transcript.text, "我们周五发布");
+    assert!(!transcript.untranslated);
+}
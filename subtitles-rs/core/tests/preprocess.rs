@@ -0,0 +1,132 @@
+use std::f32::consts::PI;
+
+use subtitles_core::audio::{PreprocessChain, PreprocessStage, Segmenter, SegmenterConfig};
+use subtitles_core::testing::{chunked, rms_of, SignalBuilder};
+
+fn run(stages: &[PreprocessStage], audio: &[f32], chunk_len: usize) -> Vec<f32> {
+    let mut chain = PreprocessChain::new(stages, 16_000);
+    chunked(audio, chunk_len)
+        .flat_map(|chunk| {
+            let mut chunk = chunk.to_vec();
+            chain.process(&mut chunk);
+            chunk
+        })
+        .collect()
+}
+
+fn stages(config: &[&str]) -> Vec<PreprocessStage> {
+    config.iter().map(|stage| stage.parse().unwrap()).collect()
+}
+
+fn tone(hz: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+    (0..(seconds * 16_000.0) as usize)
+        .map(|i| amplitude * (2.0 * PI * hz * i as f32 / 16_000.0).sin())
+        .collect()
+}
+
+#[test]
+fn stages_parse_with_defaults_and_reject_bad_parameters() {
+    assert_eq!(
+        stages(&["dc", "highpass", "HighPass:120", "agc", "denoise", "AGC:-30", "denoise:2.5"]),
+        [
+            PreprocessStage::Dc,
+            PreprocessStage::Highpass { cutoff_hz: 80.0 },
+            PreprocessStage::Highpass { cutoff_hz: 120.0 },
+            PreprocessStage::Agc { target_dbfs: -20.0 },
+            PreprocessStage::Denoise { strength: 1.5 },
+            PreprocessStage::Agc { target_dbfs: -30.0 },
+            PreprocessStage::Denoise { strength: 2.5 },
+        ]
+    );
+    for bad in ["dc:3", "highpass:5", "agc:5", "denoise:-1", "denoise:loud", "reverb"] {
+        assert!(bad.parse::<PreprocessStage>().is_err(), "{bad} parsed");
+    }
+    let unknown = "reverb".parse::<PreprocessStage>().unwrap_err();
+    assert!(unknown.contains("dc, highpass, agc, denoise"), "{unknown}");
+
+    let stage = PreprocessStage::Agc { target_dbfs: -24.0 };
+    assert_eq!(stage.to_string().parse::<PreprocessStage>(), Ok(stage));
+}
+
+#[test]
+fn empty_chain_leaves_audio_alone() {
+    let audio = SignalBuilder::new(16_000, 1).speech(1.0, 0.1).build();
+    let chain = PreprocessChain::new(&[], 16_000);
+    assert!(chain.is_empty());
+    assert_eq!(run(&[], &audio, 320), audio);
+}
+
+#[test]
+fn agc_brings_quiet_and_loud_speech_towards_the_target() {
+    let agc = stages(&["agc"]);
+    for rms in [0.01, 0.5] {
+        let audio = SignalBuilder::new(16_000, 3).speech(4.0, rms).build();
+        let out = run(&agc, &audio, 480);
+        assert_eq!(out.len(), audio.len());
+        // -20 dBFS is an RMS of 0.1; allow a few dB for the syllable envelope.
+        let level = rms_of(&out[3 * 16_000..]);
+        assert!((0.06..0.13).contains(&level), "{rms} came out at {level}");
+    }
+}
+
+#[test]
+fn denoise_takes_steady_noise_down_and_keeps_the_voice() {
+    let noise = SignalBuilder::new(16_000, 7).noise(6.0, 0.05).build();
+    let voice = SignalBuilder::new(16_000, 9)
+        .silence(3.0)
+        .voice(3.0, 140.0, 1.0)
+        .build();
+    let mix: Vec<f32> = noise.iter().zip(&voice).map(|(n, v)| n + v).collect();
+    let out = run(&stages(&["denoise"]), &mix, 333);
+    assert_eq!(out.len(), mix.len());
+
+    // Noise alone, once the estimate has settled: at least 4 dB quieter.
+    let settled = 2 * 16_000..3 * 16_000;
+    assert!(rms_of(&out[settled.clone()]) < 0.63 * rms_of(&mix[settled]));
+    // The voice is mostly still there.
+    let speaking = 3 * 16_000 + 8_000..6 * 16_000;
+    assert!(rms_of(&out[speaking.clone()]) > 0.8 * rms_of(&voice[speaking]));
+}
+
+#[test]
+fn highpass_removes_rumble_and_offset_but_not_voices() {
+    let highpass = stages(&["highpass"]);
+    let rumble: Vec<f32> = tone(30.0, 0.05, 3.0).iter().map(|s| s + 0.1).collect();
+    let out = run(&highpass, &rumble, 320);
+    assert!(rms_of(&out[16_000..]) < 0.1 * rms_of(&rumble[16_000..]));
+
+    for hz in [300.0, 1_000.0] {
+        let voice_band = tone(hz, 0.1, 2.0);
+        let out = run(&highpass, &voice_band, 320);
+        assert!(rms_of(&out[16_000..]) > 0.95 * rms_of(&voice_band[16_000..]), "{hz} Hz");
+    }
+}
+
+#[test]
+fn dc_stage_removes_only_the_offset() {
+    let offset: Vec<f32> = tone(200.0, 0.1, 2.0).iter().map(|s| s + 0.2).collect();
+    let out = run(&stages(&["dc"]), &offset, 320);
+    let mean = out[16_000..].iter().sum::<f32>() / 16_000.0;
+    assert!(mean.abs() < 1e-3, "{mean}");
+    assert!((rms_of(&out[16_000..]) - rms_of(&tone(200.0, 0.1, 1.0))).abs() < 0.005);
+}
+
+#[test]
+fn highpass_keeps_hvac_rumble_from_holding_the_vad_open() {
+    let cfg = SegmenterConfig {
+        sample_rate_hz: 16_000,
+        vad_threshold: 0.012,
+        vad_end_silence_s: 0.6,
+        max_segment_s: 2.0,
+        pre_roll_s: 0.25,
+    };
+    let rumble: Vec<f32> = tone(40.0, 0.03, 6.0).iter().map(|s| s + 0.01).collect();
+    let segments = |audio: &[f32]| {
+        let mut segmenter = Segmenter::new(cfg);
+        chunked(audio, 320)
+            .map(|chunk| segmenter.push_audio(chunk).len())
+            .sum::<usize>()
+    };
+    assert!(segments(&rumble) >= 2);
+    assert_eq!(segments(&run(&stages(&["highpass"]), &rumble, 320)), 0);
+}
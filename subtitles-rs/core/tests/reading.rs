@@ -0,0 +1,90 @@
+use subtitles_core::text::ReadingSpeed;
+
+#[test]
+fn long_text_splits_at_sentences_then_clauses_then_spaces() {
+    // 10 chars/s and 5 s cues: at most 50 characters each.
+    let speed = ReadingSpeed::new(10.0).unwrap();
+    assert_eq!(speed.max_chars(), 50);
+    assert_eq!(speed.chunks("Short enough."), ["Short enough."]);
+
+    let text = "We shipped the new build today. It fixes the audio dropouts, the crash on \
+                wake and a few smaller things that people reported";
+    let chunks = speed.chunks(text);
+    assert_eq!(
+        chunks,
+        [
+            "We shipped the new build today.",
+            "It fixes the audio dropouts,",
+            "the crash on wake and a few smaller things that",
+            "people reported",
+        ]
+    );
+    assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 50));
+
+    let chinese = ReadingSpeed::new(2.0).unwrap();
+    assert_eq!(
+        chinese.chunks("我们今天讨论一下，这个问题很重要。"),
+        ["我们今天讨论一下，", "这个问题很重要。"]
+    );
+    assert!(ReadingSpeed::new(0.0).is_none());
+}
+
+#[test]
+fn timed_chunks_get_their_reading_time_and_fill_the_speech() {
+    let speed = ReadingSpeed::new(10.0).unwrap();
+    let text = "One two three four five six seven eight nine ten. Eleven twelve.";
+
+    // Speech faster than reading: each cue gets its reading time and the last runs over.
+    let fast = speed.timed_chunks(text, 10.0, 12.0);
+    assert_eq!(fast.len(), 2);
+    assert_eq!(fast[0].start_s, 10.0);
+    assert!((fast[0].end_s - 14.9).abs() < 1e-9, "{fast:?}");
+    assert_eq!(fast[1].start_s, fast[0].end_s);
+    assert!((fast[1].end_s - 16.3).abs() < 1e-9, "{fast:?}");
+
+    // Slow speech: the cues are stretched in proportion to cover it.
+    let slow = speed.timed_chunks(text, 0.0, 12.6);
+    assert!((slow[0].end_s - 9.8).abs() < 1e-9, "{slow:?}");
+    assert!((slow[1].end_s - 12.6).abs() < 1e-9, "{slow:?}");
+    // Short text is shown for at least a second.
+    assert_eq!(speed.read_time_s("ok"), 1.0);
+}
+
+#[test]
+fn word_timed_chunks_start_when_their_first_word_is_spoken() {
+    let speed = ReadingSpeed::new(10.0).unwrap();
+    let text = "One two three four five six seven eight nine ten. Eleven twelve.";
+    // A word every 0.5 s from 10 s, with a pause before "Eleven".
+    let mut words: Vec<(f64, f64)> = (0..10)
+        .map(|i| (10.0 + i as f64 * 0.5, 10.4 + i as f64 * 0.5))
+        .collect();
+    words.push((17.0, 17.4));
+    words.push((17.5, 18.0));
+
+    let chunks = speed.word_timed_chunks(text, &words).unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].text, "One two three four five six seven eight nine ten.");
+    assert_eq!((chunks[0].start_s, chunks[0].end_s), (10.0, 17.0));
+    assert_eq!(chunks[1].text, "Eleven twelve.");
+    // Shown for its reading time, past the end of the speech.
+    assert_eq!(chunks[1].start_s, 17.0);
+    assert!((chunks[1].end_s - 18.4).abs() < 1e-9);
+
+    // Text that no longer matches the words (a revision) isn't timed by them.
+    assert!(speed.word_timed_chunks("One two.", &words).is_none());
+    assert!(speed.word_timed_chunks(text, &[]).is_none());
+}
+
+#[test]
+fn a_chunk_cut_mid_word_starts_partway_through_it() {
+    let speed = ReadingSpeed::new(2.0).unwrap();
+    // No spaces: one word, cut after the comma.
+    let chunks = speed
+        .word_timed_chunks("我们今天讨论一下，这个问题很重要。", &[(4.0, 7.4)])
+        .unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].start_s, 4.0);
+    // 9 of the 17 characters in.
+    assert!((chunks[1].start_s - (4.0 + 3.4 * 9.0 / 17.0)).abs() < 1e-9);
+    assert_eq!(chunks[0].end_s, chunks[1].start_s);
+}
@@ -0,0 +1,50 @@
+use subtitles_core::audio::Resampler;
+use subtitles_core::testing::{chunked, SignalBuilder};
+
+fn sine(rate: u32, hz: f32, seconds: f32) -> Vec<f32> {
+    (0..(rate as f32 * seconds) as usize)
+        .map(|i| (std::f32::consts::TAU * hz * i as f32 / rate as f32).sin() * 0.5)
+        .collect()
+}
+
+fn zero_crossings(samples: &[f32]) -> usize {
+    samples
+        .windows(2)
+        .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+        .count()
+}
+
+#[test]
+fn averages_every_three_samples_at_48k() {
+    let audio = SignalBuilder::new(48_000, 9).speech(0.5, 0.1).build();
+    let expected: Vec<f32> = audio
+        .chunks_exact(3)
+        .map(|frame| frame.iter().sum::<f32>() / 3.0)
+        .collect();
+
+    let mut resampler = Resampler::new(48_000, 16_000);
+    let out: Vec<f32> = chunked(&audio, 1_001)
+        .flat_map(|chunk| resampler.process(chunk))
+        .collect();
+    assert_eq!(out.len(), expected.len());
+    for (got, want) in out.iter().zip(&expected) {
+        assert!((got - want).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn other_device_rates_keep_duration_and_pitch() {
+    for rate in [8_000, 24_000, 44_100, 96_000] {
+        let mut resampler = Resampler::new(rate, 16_000);
+        let out: Vec<f32> = chunked(&sine(rate, 440.0, 2.0), 480)
+            .flat_map(|chunk| resampler.process(chunk))
+            .collect();
+        assert!(
+            out.len().abs_diff(32_000) <= 1,
+            "{rate} Hz gave {} samples",
+            out.len()
+        );
+        // 440 Hz crosses zero 880 times a second.
+        assert!(zero_crossings(&out).abs_diff(1_760) <= 4, "{rate} Hz");
+    }
+}
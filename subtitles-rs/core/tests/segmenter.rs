@@ -0,0 +1,820 @@
+use subtitles_core::audio::{Segmenter, SegmenterConfig};
+use subtitles_core::streaming::{
+    sentence_endpoint, PartialGate, StreamingConfig, StreamingEvent, StreamingSegmenter,
+};
+use subtitles_core::testing::{chunked, SignalBuilder, TestRng};
+use subtitles_core::transcribe::TranscriptSegment;
+
+const SR: u32 = 16_000;
+const FRAME: usize = 320; // 20 ms at 16 kHz
+const SPEECH_RMS: f32 = 0.1;
+/// Frames of silence the VAD still counts as voice after speech stops.
+const HANGOVER_FRAMES: usize = 4;
+
+fn segmenter_cfg() -> SegmenterConfig {
+    SegmenterConfig {
+        sample_rate_hz: SR,
+        vad_threshold: 0.02,
+        vad_end_silence_s: 0.3,
+        max_segment_s: 20.0,
+        pre_roll_s: 0.1,
+    }
+}
+
+fn streaming_cfg() -> StreamingConfig {
+    StreamingConfig {
+        sample_rate_hz: SR,
+        vad_threshold: 0.02,
+        vad_end_silence_s: 0.3,
+        max_segment_s: 20.0,
+        pre_roll_s: 0.1,
+        min_speech_ms: 300,
+        partial_onset_ms: 0,
+        asr_step_ms: 200,
+        max_window_s: 2.0,
+        partial_overlap_s: 0.5,
+        final_interval_s: 0.0,
+    }
+}
+
+fn frames(n: usize) -> f32 {
+    n as f32 * 0.02
+}
+
+/// Segment length for a frame-aligned burst of `speech_frames` preceded by enough silence
+/// to fill the pre-roll: pre-roll (which already holds the triggering frame), the rest of
+/// the speech, then the hangover and the trailing silence that ends the segment.
+fn expected_len(pre_roll_frames: usize, speech_frames: usize, end_silence_frames: usize) -> usize {
+    (pre_roll_frames + speech_frames - 1 + HANGOVER_FRAMES + end_silence_frames) * FRAME
+}
+
+fn run_segmenter(cfg: SegmenterConfig, audio: &[f32], chunk_len: usize) -> Vec<Vec<f32>> {
+    let mut segmenter = Segmenter::new(cfg);
+    chunked(audio, chunk_len)
+        .flat_map(|chunk| segmenter.push_audio(chunk))
+        .collect()
+}
+
+fn run_streaming(cfg: StreamingConfig, audio: &[f32], chunk_len: usize) -> Vec<StreamingEvent> {
+    let mut segmenter = StreamingSegmenter::new(cfg);
+    chunked(audio, chunk_len)
+        .flat_map(|chunk| segmenter.push_audio(chunk))
+        .collect()
+}
+
+/// Compact, comparable view of an event stream.
+fn summarize(events: &[StreamingEvent]) -> Vec<(char, usize)> {
+    events
+        .iter()
+        .map(|event| match event {
+            StreamingEvent::Partial(audio) => ('p', audio.len()),
+            StreamingEvent::Final(audio) => ('f', audio.len()),
+            StreamingEvent::Reset => ('r', 0),
+        })
+        .collect()
+}
+
+fn finals(events: &[StreamingEvent]) -> Vec<&[f32]> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            StreamingEvent::Final(audio) => Some(&audio[..]),
+            _ => None,
+        })
+        .collect()
+}
+
+fn partials(events: &[StreamingEvent]) -> Vec<&[f32]> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            StreamingEvent::Partial(audio) => Some(&audio[..]),
+            _ => None,
+        })
+        .collect()
+}
+
+fn contains_window(haystack: &[f32], needle: &[f32]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[test]
+fn silence_and_quiet_noise_produce_nothing() {
+    let audio = SignalBuilder::new(SR, 1)
+        .silence(2.0)
+        .noise(2.0, 0.01)
+        .build();
+    assert!(run_segmenter(segmenter_cfg(), &audio, 480).is_empty());
+    assert!(run_streaming(streaming_cfg(), &audio, 480).is_empty());
+}
+
+#[test]
+fn segment_boundaries_match_burst_and_end_silence() {
+    let mut rng = TestRng::new(7);
+    for seed in 0..25 {
+        let speech_frames = 20 + (rng.next_u64() % 150) as usize;
+        let audio = SignalBuilder::new(SR, seed)
+            .silence(1.0)
+            .speech(frames(speech_frames), SPEECH_RMS)
+            .silence(1.0)
+            .build();
+
+        let segments = run_segmenter(segmenter_cfg(), &audio, 480);
+        assert_eq!(segments.len(), 1, "seed {seed}");
+        assert_eq!(
+            segments[0].len(),
+            expected_len(5, speech_frames, 15),
+            "seed {seed}, {speech_frames} speech frames"
+        );
+    }
+}
+
+#[test]
+fn pre_roll_is_included_before_speech_onset() {
+    let speech_start = SR as usize; // 1 s of leading silence
+    let audio = SignalBuilder::new(SR, 3)
+        .silence(1.0)
+        .speech(1.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    let segments = run_segmenter(segmenter_cfg(), &audio, 480);
+    assert_eq!(segments.len(), 1);
+    let segment = &segments[0];
+
+    // 0.1 s pre-roll = 5 frames, the last of which is the frame that triggered the VAD.
+    let pre_roll_silence = 4 * FRAME;
+    assert!(segment[..pre_roll_silence].iter().all(|&s| s == 0.0));
+    assert_eq!(
+        &segment[pre_roll_silence..pre_roll_silence + FRAME],
+        &audio[speech_start..speech_start + FRAME]
+    );
+}
+
+#[test]
+fn short_leading_silence_limits_pre_roll() {
+    // Speech starts 2 frames into the stream, so only those frames can be pre-rolled.
+    let audio = SignalBuilder::new(SR, 4)
+        .silence(frames(2))
+        .speech(1.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    let segments = run_segmenter(segmenter_cfg(), &audio, 480);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].len(), expected_len(3, 50, 15));
+}
+
+#[test]
+fn bursts_separated_by_long_silence_are_separate_segments() {
+    let mut rng = TestRng::new(11);
+    for seed in 0..20 {
+        let bursts = 1 + (rng.next_u64() % 4) as usize;
+        let mut builder = SignalBuilder::new(SR, seed).silence(0.5);
+        for _ in 0..bursts {
+            let speech_frames = 10 + (rng.next_u64() % 60) as usize;
+            let gap_frames = 20 + (rng.next_u64() % 40) as usize; // > 15 end-silence frames
+            builder = builder
+                .speech(frames(speech_frames), SPEECH_RMS)
+                .silence(frames(gap_frames));
+        }
+        let audio = builder.build();
+
+        assert_eq!(
+            run_segmenter(segmenter_cfg(), &audio, 480).len(),
+            bursts,
+            "seed {seed}"
+        );
+        assert_eq!(
+            finals(&run_streaming(streaming_cfg(), &audio, 480)).len(),
+            bursts,
+            "seed {seed}"
+        );
+    }
+}
+
+#[test]
+fn gaps_shorter_than_end_silence_do_not_split() {
+    let audio = SignalBuilder::new(SR, 5)
+        .silence(0.5)
+        .speech(0.6, SPEECH_RMS)
+        .silence(frames(10))
+        .speech(0.6, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    assert_eq!(run_segmenter(segmenter_cfg(), &audio, 480).len(), 1);
+}
+
+#[test]
+fn plosive_gaps_do_not_count_towards_end_silence() {
+    // With a 60 ms end silence, a 60 ms stop closure would end the utterance mid-word if it
+    // counted; the hangover covers it.
+    let cfg = StreamingConfig {
+        vad_end_silence_s: frames(3),
+        ..streaming_cfg()
+    };
+    let audio = SignalBuilder::new(SR, 9)
+        .silence(0.5)
+        .speech(0.6, SPEECH_RMS)
+        .silence(frames(3))
+        .speech(0.6, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let events = run_streaming(cfg, &audio, 480);
+    assert_eq!(finals(&events).len(), 1, "{:?}", summarize(&events));
+    let segmenter_cfg = SegmenterConfig {
+        vad_end_silence_s: frames(3),
+        ..segmenter_cfg()
+    };
+    assert_eq!(run_segmenter(segmenter_cfg, &audio, 480).len(), 1);
+}
+
+#[test]
+fn a_lone_frame_barely_over_the_threshold_does_not_start_speech() {
+    let click = |amplitude: f32| {
+        SignalBuilder::new(SR, 10)
+            .silence(0.5)
+            .noise(frames(1), amplitude)
+            .silence(1.0)
+            .build()
+    };
+    // Uniform noise at 0.038 peak is about 0.022 RMS, 1 dB over the 0.02 threshold.
+    assert!(run_segmenter(segmenter_cfg(), &click(0.038), 480).is_empty());
+    assert_eq!(run_segmenter(segmenter_cfg(), &click(0.2), 480).len(), 1);
+}
+
+#[test]
+fn forced_splits_at_max_segment_lose_no_audio() {
+    let cfg = SegmenterConfig {
+        max_segment_s: 2.0,
+        ..segmenter_cfg()
+    };
+    let speech_start = SR as usize;
+    let audio = SignalBuilder::new(SR, 9)
+        .silence(1.0)
+        .speech(7.3, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    let segments = run_segmenter(cfg, &audio, 480);
+    assert!(segments.len() >= 4, "got {} segments", segments.len());
+    let max_samples = 2 * SR as usize;
+    for segment in &segments {
+        assert!(segment.len() <= max_samples + FRAME);
+    }
+
+    let joined: Vec<f32> = segments.concat();
+    let start = speech_start - 4 * FRAME;
+    assert_eq!(&joined[..], &audio[start..start + joined.len()]);
+}
+
+#[test]
+fn streaming_short_burst_resets_instead_of_finalizing() {
+    let cfg = StreamingConfig {
+        min_speech_ms: 1000, // 50 frames
+        ..streaming_cfg()
+    };
+    let mut rng = TestRng::new(13);
+    for seed in 0..25 {
+        let speech_frames = 1 + (rng.next_u64() % 60) as usize;
+        let audio = SignalBuilder::new(SR, seed)
+            .silence(1.0)
+            .speech(frames(speech_frames), SPEECH_RMS)
+            .silence(1.0)
+            .build();
+
+        let events = run_streaming(cfg, &audio, 480);
+        let utterance_len = expected_len(5, speech_frames, 15);
+        if utterance_len >= 50 * FRAME {
+            assert!(
+                matches!(events.last(), Some(StreamingEvent::Final(a)) if a.len() == utterance_len),
+                "seed {seed}: {:?}",
+                summarize(&events)
+            );
+        } else {
+            assert_eq!(summarize(&events), vec![('r', 0)], "seed {seed}");
+        }
+    }
+}
+
+#[test]
+fn streaming_partials_respect_min_speech_step_and_window() {
+    let min_speech = 15 * FRAME; // 300 ms
+    let step = 10 * FRAME; // 200 ms
+    let max_window = 100 * FRAME; // 2 s
+    let overlap = 25 * FRAME; // 0.5 s
+
+    let audio = SignalBuilder::new(SR, 21)
+        .silence(1.0)
+        .speech(4.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let events = run_streaming(streaming_cfg(), &audio, 480);
+
+    let final_audio = finals(&events);
+    assert_eq!(final_audio.len(), 1);
+    let final_audio = final_audio[0];
+
+    let partials = partials(&events);
+    assert!(!partials.is_empty());
+    assert_eq!(partials[0].len(), min_speech);
+    for pair in partials.windows(2) {
+        let grown = pair[0].len() + step;
+        let expected = if grown <= max_window { grown } else { overlap };
+        assert_eq!(pair[1].len(), expected);
+    }
+    assert!(partials.iter().any(|p| p.len() == overlap), "window never restarted");
+    for partial in &partials {
+        assert!(contains_window(final_audio, partial));
+    }
+}
+
+#[test]
+fn partial_onset_delays_only_the_first_partial_of_each_utterance() {
+    let cfg = StreamingConfig {
+        partial_onset_ms: 700,
+        ..streaming_cfg()
+    };
+    let onset = 35 * FRAME; // 700 ms
+    let step = 10 * FRAME;
+    let audio = SignalBuilder::new(SR, 29)
+        .silence(1.0)
+        .speech(1.5, SPEECH_RMS)
+        .silence(1.0)
+        .speech(0.2, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let events = run_streaming(cfg, &audio, 480);
+
+    // The first utterance starts partials at the onset, then steps as usual; the second is
+    // too short to reach the onset and only gets its final.
+    let mut lengths = Vec::new();
+    let mut utterances = Vec::new();
+    for event in &events {
+        match event {
+            StreamingEvent::Partial(audio) => lengths.push(audio.len()),
+            StreamingEvent::Final(_) => utterances.push(std::mem::take(&mut lengths)),
+            StreamingEvent::Reset => {}
+        }
+    }
+    assert_eq!(utterances.len(), 2, "{:?}", summarize(&events));
+    assert_eq!(utterances[0][0], onset);
+    assert!(utterances[0].windows(2).all(|pair| pair[1] == pair[0] + step));
+    assert!(utterances[1].is_empty(), "{:?}", utterances[1]);
+}
+
+#[test]
+fn busy_partial_gate_skips_partials_and_widens_the_next_one() {
+    let step = 10 * FRAME;
+    let audio = SignalBuilder::new(SR, 23)
+        .silence(1.0)
+        .speech(1.5, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    let gate = PartialGate::new();
+    let mut segmenter = StreamingSegmenter::new(streaming_cfg()).with_partial_gate(gate.clone());
+    let mut partial_lens = Vec::new();
+    let mut saw_final = false;
+    for (i, chunk) in chunked(&audio, FRAME).enumerate() {
+        for event in segmenter.push_audio(chunk) {
+            match event {
+                StreamingEvent::Partial(audio) => {
+                    assert!(gate.is_in_flight());
+                    partial_lens.push(audio.len());
+                }
+                StreamingEvent::Final(_) => saw_final = true,
+                StreamingEvent::Reset => {}
+            }
+        }
+        // A decoder that needs 25 frames (500 ms) per partial.
+        if i % 25 == 0 {
+            gate.release();
+        }
+    }
+
+    assert!(saw_final);
+    assert!(partial_lens.len() >= 2, "{partial_lens:?}");
+    for pair in partial_lens.windows(2) {
+        assert!(pair[1] > pair[0] + step, "{partial_lens:?}");
+    }
+}
+
+#[test]
+fn output_does_not_depend_on_capture_chunk_size() {
+    let mut rng = TestRng::new(17);
+    for seed in 0..10 {
+        let mut builder = SignalBuilder::new(SR, seed).noise(0.7, 0.005);
+        for _ in 0..3 {
+            let speech = rng.range_f32(0.1, 2.5);
+            let gap = rng.range_f32(0.05, 1.0);
+            builder = builder.speech(speech, SPEECH_RMS).noise(gap, 0.005);
+        }
+        let audio = builder.build();
+
+        let reference = summarize(&run_streaming(streaming_cfg(), &audio, FRAME));
+        let reference_segments = run_segmenter(segmenter_cfg(), &audio, FRAME);
+        for chunk_len in [1, 160, 441, 1024, 4800] {
+            assert_eq!(
+                summarize(&run_streaming(streaming_cfg(), &audio, chunk_len)),
+                reference,
+                "seed {seed}, chunk {chunk_len}"
+            );
+            assert_eq!(
+                run_segmenter(segmenter_cfg(), &audio, chunk_len),
+                reference_segments,
+                "seed {seed}, chunk {chunk_len}"
+            );
+        }
+    }
+}
+
+#[test]
+fn restarted_partial_windows_overlap_the_previous_window() {
+    let audio = SignalBuilder::new(SR, 29)
+        .silence(1.0)
+        .speech(6.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let events = run_streaming(streaming_cfg(), &audio, 480);
+    let partials = partials(&events);
+
+    let overlap = 25 * FRAME;
+    let mut restarts = 0;
+    for pair in partials.windows(2) {
+        if pair[1].len() < pair[0].len() {
+            restarts += 1;
+            // The restarted window opens with the end of the previous one.
+            let carried = &pair[1][..overlap - 10 * FRAME];
+            assert!(contains_window(pair[0], carried));
+        } else {
+            // Between restarts the window start stays put.
+            assert_eq!(&pair[1][..pair[0].len()], pair[0]);
+        }
+    }
+    assert!(restarts >= 2, "{restarts} restarts");
+}
+
+fn seg(text: &str, start_s: f32, end_s: f32) -> TranscriptSegment {
+    TranscriptSegment {
+        text: text.to_string(),
+        start_s,
+        end_s,
+        no_speech_prob: 0.1,
+    }
+}
+
+#[test]
+fn sentence_endpoint_needs_punctuation_and_decoded_audio_after_it() {
+    let segments = [seg("So that's the plan.", 0.0, 2.0), seg("Next we", 2.2, 3.0)];
+    assert_eq!(sentence_endpoint(&segments, 3.0), Some(2.0));
+    // Too close to the end of the window to trust yet.
+    assert_eq!(sentence_endpoint(&segments, 2.5), None);
+    // No sentence end at all.
+    assert_eq!(sentence_endpoint(&[seg("and then we", 0.0, 1.0)], 4.0), None);
+    // Closing quotes and CJK punctuation count.
+    assert_eq!(sentence_endpoint(&[seg("他说“好。”", 0.0, 1.0)], 2.0), Some(1.0));
+
+    let mut music = seg("♪ la la.", 0.0, 1.0);
+    music.no_speech_prob = 0.9;
+    assert_eq!(sentence_endpoint(&[music], 3.0), None);
+}
+
+#[test]
+fn endpoint_request_finalizes_mid_utterance_and_keeps_the_rest() {
+    // Loud background that never drops below the VAD threshold.
+    let audio = SignalBuilder::new(SR, 31)
+        .silence(0.5)
+        .speech(8.0, SPEECH_RMS)
+        .build();
+
+    let gate = PartialGate::new();
+    let mut segmenter = StreamingSegmenter::new(streaming_cfg()).with_partial_gate(gate.clone());
+    let mut events = Vec::new();
+    let mut requested = false;
+    for chunk in chunked(&audio, 480) {
+        for event in segmenter.push_audio(chunk) {
+            if let StreamingEvent::Partial(window) = &event {
+                if !requested && window.len() >= 40 * FRAME {
+                    gate.end_at(30 * FRAME);
+                    requested = true;
+                }
+                gate.release();
+            }
+            events.push(event);
+        }
+    }
+
+    let finals = finals(&events);
+    assert_eq!(finals.len(), 1, "{:?}", summarize(&events));
+    assert_eq!(finals[0].len(), 30 * FRAME);
+    // Pre-roll starts 4 frames before the onset at frame 25.
+    assert_eq!(&finals[0][4 * FRAME..], &audio[25 * FRAME..51 * FRAME]);
+
+    // Partials after the cut start where the final ended.
+    let after: Vec<_> = events
+        .iter()
+        .skip_while(|e| !matches!(e, StreamingEvent::Final(_)))
+        .filter_map(|e| match e {
+            StreamingEvent::Partial(a) => Some(a),
+            _ => None,
+        })
+        .collect();
+    assert!(!after.is_empty());
+    assert_eq!(&after[0][..FRAME], &audio[51 * FRAME..52 * FRAME]);
+}
+
+#[test]
+fn stale_endpoint_requests_are_ignored() {
+    let audio = SignalBuilder::new(SR, 37)
+        .silence(0.5)
+        .speech(1.0, SPEECH_RMS)
+        .silence(1.0)
+        .speech(1.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    let gate = PartialGate::new();
+    let mut segmenter = StreamingSegmenter::new(streaming_cfg()).with_partial_gate(gate.clone());
+    let mut events = Vec::new();
+    let mut partial_seen = false;
+    for chunk in chunked(&audio, 480) {
+        for event in segmenter.push_audio(chunk) {
+            match &event {
+                // Hold the first partial until its utterance has ended on silence, then ask
+                // for a cut inside it: the second utterance must not be affected.
+                StreamingEvent::Partial(_) if !partial_seen => partial_seen = true,
+                StreamingEvent::Partial(_) => gate.release(),
+                StreamingEvent::Final(_) if partial_seen && gate.is_in_flight() => {
+                    gate.end_at(20 * FRAME);
+                    gate.release();
+                }
+                _ => {}
+            }
+            events.push(event);
+        }
+    }
+
+    let finals = finals(&events);
+    assert_eq!(finals.len(), 2, "{:?}", summarize(&events));
+    assert_eq!(finals[1].len(), expected_len(5, 50, 15));
+}
+
+#[test]
+fn partials_and_the_final_share_one_utterance_buffer() {
+    let audio = SignalBuilder::new(SR, 43)
+        .silence(0.5)
+        .speech(1.5, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    // A decoder that is done with each partial before the next: nothing gets copied, so every
+    // window and the final start at the same address.
+    let gate = PartialGate::new();
+    let mut segmenter = StreamingSegmenter::new(streaming_cfg()).with_partial_gate(gate.clone());
+    let mut starts = Vec::new();
+    let mut final_audio = None;
+    for chunk in chunked(&audio, 480) {
+        for event in segmenter.push_audio(chunk) {
+            match event {
+                StreamingEvent::Partial(window) => {
+                    starts.push(window.as_ptr());
+                    gate.release();
+                }
+                StreamingEvent::Final(segment) => final_audio = Some(segment),
+                StreamingEvent::Reset => {}
+            }
+        }
+    }
+
+    let final_audio = final_audio.expect("a final");
+    assert!(starts.len() >= 5, "{}", starts.len());
+    assert!(starts.iter().all(|&start| start == final_audio.as_ptr()));
+    assert_eq!(final_audio.len(), expected_len(5, 75, 15));
+    // Same audio as when every partial is kept, which moves the utterance to fresh buffers.
+    let again = run_streaming(streaming_cfg(), &audio, 480);
+    assert_eq!(finals(&again)[0], &final_audio[..]);
+}
+
+#[test]
+fn warm_partials_start_sooner_with_the_previous_sentence_in_front() {
+    let cfg = StreamingConfig {
+        partial_onset_ms: 700,
+        ..streaming_cfg()
+    };
+    let audio = SignalBuilder::new(SR, 47)
+        .silence(0.5)
+        .speech(1.5, SPEECH_RMS)
+        .silence(1.0)
+        .speech(1.5, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let second_onset = (3.0 * SR as f32) as usize;
+
+    // Where the first partial after `onset` comes out, where its window starts, and the window.
+    let first_partial = |mut segmenter: StreamingSegmenter, audio: &[f32], onset: usize| {
+        let mut pushed = 0;
+        for chunk in chunked(audio, FRAME) {
+            pushed += chunk.len();
+            for event in segmenter.push_audio(chunk) {
+                if let StreamingEvent::Partial(window) = event {
+                    let start = segmenter.take_partial_start().unwrap() as usize;
+                    if pushed > onset {
+                        return (pushed, start, window);
+                    }
+                }
+            }
+        }
+        panic!("no partial after {onset}");
+    };
+
+    let cold = StreamingSegmenter::new(cfg);
+    let (cold_at, cold_start, _) = first_partial(cold, &audio, second_onset);
+    let warm = StreamingSegmenter::new(cfg).with_warm_partials(1.0);
+    let (warm_at, warm_start, window) = first_partial(warm, &audio, second_onset);
+    assert!(cold_at >= second_onset + 30 * FRAME, "{cold_at}");
+    assert!(warm_at + 20 * FRAME <= cold_at, "{warm_at} vs {cold_at}");
+    // A second before the pre-roll, reaching back into the first utterance.
+    assert_eq!(cold_start - warm_start, SR as usize);
+    assert!(warm_start < (2.0 * SR as f32) as usize);
+    assert_eq!(&window[..], &audio[warm_start..warm_start + window.len()]);
+
+    // Speech after a long pause starts cold.
+    let late = SignalBuilder::new(SR, 47)
+        .silence(0.5)
+        .speech(1.5, SPEECH_RMS)
+        .silence(3.0)
+        .speech(1.5, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let late_onset = (5.0 * SR as f32) as usize;
+    let warm = StreamingSegmenter::new(cfg).with_warm_partials(1.0);
+    let (late_at, late_start, _) = first_partial(warm, &late, late_onset);
+    assert!(late_at >= late_onset + 30 * FRAME, "{late_at}");
+    assert!(late_start >= late_onset - 10 * FRAME, "{late_start}");
+}
+
+#[test]
+fn final_interval_splits_long_speech_without_losing_audio() {
+    let cfg = StreamingConfig {
+        final_interval_s: 3.0,
+        ..streaming_cfg()
+    };
+    let interval = 150 * FRAME;
+    let audio = SignalBuilder::new(SR, 41)
+        .silence(1.0)
+        .speech(14.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+
+    let events = run_streaming(cfg, &audio, 480);
+    let finals = finals(&events);
+    assert!(finals.len() >= 5, "{:?}", summarize(&events));
+    for forced in &finals[..finals.len() - 1] {
+        // Cut within the 0.5 s search window before the interval boundary.
+        assert!(forced.len() <= interval && forced.len() >= interval - 25 * FRAME);
+    }
+
+    let joined: Vec<f32> = finals.iter().flat_map(|f| f.iter().copied()).collect();
+    let start = SR as usize - 4 * FRAME;
+    assert_eq!(&joined[..], &audio[start..start + joined.len()]);
+}
+
+#[test]
+fn final_starts_locate_each_final_in_the_input() {
+    let audio = SignalBuilder::new(SR, 43)
+        .silence(0.7)
+        .speech(9.0, SPEECH_RMS)
+        .silence(1.3)
+        .speech(1.1, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let located = |start: Option<u64>, segment: &[f32]| {
+        let start = start.expect("start for every final") as usize;
+        assert_eq!(segment, &audio[start..start + segment.len()]);
+    };
+
+    let mut streaming = StreamingSegmenter::new(StreamingConfig {
+        final_interval_s: 3.0,
+        ..streaming_cfg()
+    });
+    let mut streaming_finals = 0;
+    for chunk in chunked(&audio, 333) {
+        for event in streaming.push_audio(chunk) {
+            if let StreamingEvent::Final(segment) = event {
+                located(streaming.take_final_start(), &segment);
+                streaming_finals += 1;
+            }
+        }
+        assert_eq!(streaming.take_final_start(), None);
+    }
+    assert!(streaming_finals >= 4);
+
+    let mut segmenter = Segmenter::new(SegmenterConfig {
+        max_segment_s: 4.0,
+        ..segmenter_cfg()
+    });
+    let mut segments = 0;
+    for chunk in chunked(&audio, 333) {
+        for segment in segmenter.push_audio(chunk) {
+            located(segmenter.take_segment_start(), &segment);
+            segments += 1;
+        }
+    }
+    assert!(segments >= 3);
+}
+
+#[test]
+fn partial_starts_locate_each_window_in_the_input() {
+    let audio = SignalBuilder::new(SR, 44)
+        .silence(0.5)
+        .speech(6.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let mut streaming = StreamingSegmenter::new(streaming_cfg());
+    let mut partials = 0;
+    for chunk in chunked(&audio, 333) {
+        for event in streaming.push_audio(chunk) {
+            if let StreamingEvent::Partial(window) = event {
+                let start = streaming.take_partial_start().expect("start for every partial");
+                let start = start as usize;
+                assert_eq!(&window[..], &audio[start..start + window.len()]);
+                partials += 1;
+            }
+        }
+        assert_eq!(streaming.take_partial_start(), None);
+    }
+    // Enough to have slid past the 2 s window.
+    assert!(partials >= 20);
+}
+
+#[test]
+fn longer_asr_step_decodes_fewer_partials() {
+    // Long enough to stay within one window, so every partial is an ASR step.
+    let audio = SignalBuilder::new(SR, 11)
+        .silence(0.2)
+        .speech(1.8, SPEECH_RMS)
+        .build();
+    let count = |asr_step_ms: Option<u64>| {
+        let mut segmenter = StreamingSegmenter::new(streaming_cfg());
+        if let Some(asr_step_ms) = asr_step_ms {
+            segmenter.set_asr_step_ms(asr_step_ms);
+        }
+        let events: Vec<StreamingEvent> = chunked(&audio, FRAME)
+            .flat_map(|chunk| segmenter.push_audio(chunk))
+            .collect();
+        partials(&events).len()
+    };
+    let (every_200_ms, every_second) = (count(None), count(Some(1_000)));
+    assert!(every_200_ms >= 6, "{every_200_ms}");
+    assert!((1..=2).contains(&every_second), "{every_second}");
+}
+
+#[test]
+fn overlapping_vad_frames_locate_speech_to_the_hop() {
+    const HOP: usize = 160; // 10 ms
+    let speech_starts = [3 * SR as usize, 5 * SR as usize];
+    let audio = SignalBuilder::new(SR, 45)
+        .silence(3.0)
+        .speech(1.0, SPEECH_RMS)
+        .silence(1.0)
+        .speech(1.0, SPEECH_RMS)
+        .silence(1.0)
+        .build();
+    let pre_roll = (0.1 * SR as f32) as usize;
+    let check = |start: Option<u64>, segment: &[f32], speech_start: usize| {
+        let start = start.expect("start for every segment") as usize;
+        assert_eq!(segment, &audio[start..start + segment.len()]);
+        assert_eq!(segment.len() % HOP, 0);
+        // The first hop with speech in it triggers, then the pre-roll is prepended.
+        assert_eq!(start + pre_roll, speech_start + HOP);
+        // Ends after the end silence, plus the frame's look-back and about 80 ms of hangover.
+        let after_speech = start + segment.len() - (speech_start + SR as usize);
+        assert!(
+            (15 * FRAME..=(15 + 5) * FRAME + 3 * HOP).contains(&after_speech),
+            "{after_speech}"
+        );
+    };
+
+    let mut segmenter = Segmenter::new(segmenter_cfg()).with_vad_frames(40, 10);
+    let mut segments = 0;
+    for chunk in chunked(&audio, 333) {
+        for segment in segmenter.push_audio(chunk) {
+            check(segmenter.take_segment_start(), &segment, speech_starts[segments]);
+            segments += 1;
+        }
+    }
+    assert_eq!(segments, 2);
+
+    let mut streaming = StreamingSegmenter::new(streaming_cfg()).with_vad_frames(40, 10);
+    let mut finals = 0;
+    for chunk in chunked(&audio, 333) {
+        for event in streaming.push_audio(chunk) {
+            if let StreamingEvent::Final(segment) = event {
+                check(streaming.take_final_start(), &segment, speech_starts[finals]);
+                finals += 1;
+            }
+        }
+    }
+    assert_eq!(finals, 2);
+}
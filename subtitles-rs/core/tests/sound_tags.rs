@@ -0,0 +1,31 @@
+use subtitles_core::config::SoundTagMode;
+use subtitles_core::text::{SoundTagFilter, CROSSTALK_TAG};
+
+#[test]
+fn strip_removes_tags_but_keeps_asides() {
+    let filter = SoundTagFilter::new(SoundTagMode::Strip, "♪");
+    assert_eq!(filter.apply("[Music] Welcome back (laughs) everyone"), "Welcome back everyone");
+    assert_eq!(filter.apply("[BLANK_AUDIO]"), "");
+    // Longer parentheticals are speech, and numbers are references.
+    let aside = "It works (at least on my machine, anyway) see [1]";
+    assert_eq!(filter.apply(aside), aside);
+}
+
+#[test]
+fn glyph_replaces_runs_of_tags_and_keep_is_a_no_op() {
+    let glyph = SoundTagFilter::new(SoundTagMode::Glyph, "♪");
+    assert_eq!(glyph.apply("[Music] [Music] la la (applause)"), "♪ la la ♪");
+    assert_eq!(glyph.apply("unclosed [bracket"), "unclosed [bracket");
+
+    let keep = SoundTagFilter::new(SoundTagMode::Keep, "♪");
+    assert_eq!(keep.apply("[Music]  Hi"), "[Music]  Hi");
+}
+
+#[test]
+fn the_crosstalk_mark_is_not_a_sound_tag() {
+    let text = format!("{CROSSTALK_TAG} [Music] so we both said");
+    let strip = SoundTagFilter::new(SoundTagMode::Strip, "♪");
+    assert_eq!(strip.apply(&text), "[overlapping] so we both said");
+    let glyph = SoundTagFilter::new(SoundTagMode::Glyph, "♪");
+    assert_eq!(glyph.apply(&text), "[overlapping] ♪ so we both said");
+}
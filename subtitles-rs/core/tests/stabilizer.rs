@@ -0,0 +1,199 @@
+use subtitles_core::config::CommitPolicy;
+use subtitles_core::streaming::Stabilizer;
+
+fn words(range: std::ops::Range<usize>) -> String {
+    range.map(|i| format!("w{i}")).collect::<Vec<_>>().join(" ")
+}
+
+/// Feeds each hypothesis twice (enough to commit with `stable_required = 2`).
+fn settle(stabilizer: &mut Stabilizer, hypothesis: &str) -> (String, String) {
+    stabilizer.update(hypothesis);
+    stabilizer.update(hypothesis)
+}
+
+#[test]
+fn unbounded_history_keeps_every_committed_token() {
+    let mut stabilizer = Stabilizer::new(2);
+    for end in (10..=100).step_by(10) {
+        settle(&mut stabilizer, &words(0..end));
+    }
+    assert_eq!(stabilizer.committed().len(), 100);
+}
+
+#[test]
+fn bounded_history_keeps_only_the_newest_tokens() {
+    let mut stabilizer = Stabilizer::new(2).with_max_committed(16);
+    let mut committed = String::new();
+    for end in (10..=100).step_by(10) {
+        (committed, _) = settle(&mut stabilizer, &words(0..end));
+    }
+    assert_eq!(stabilizer.committed().len(), 16);
+    assert_eq!(committed, words(84..100));
+}
+
+#[test]
+fn bounded_history_does_not_recommit_scrolled_out_tokens() {
+    let mut stabilizer = Stabilizer::new(2).with_max_committed(8);
+    settle(&mut stabilizer, &words(0..30));
+
+    // The decoder still returns the whole utterance, whose start is no longer in the window.
+    let (committed, pending) = stabilizer.update(&words(0..31));
+    assert_eq!(committed, words(22..30));
+    assert_eq!(pending, "w30");
+
+    // Same when a sliding decode window has dropped part of the start.
+    let (committed, pending) = stabilizer.update(&words(12..31));
+    assert_eq!(committed, words(23..31));
+    assert_eq!(pending, "");
+}
+
+#[test]
+fn changed_hypotheses_count_as_retractions() {
+    let mut stabilizer = Stabilizer::new(2);
+    settle(&mut stabilizer, "the cat sat");
+    assert_eq!(stabilizer.committed().len(), 3);
+    stabilizer.update("the cat sat on");
+    assert_eq!(stabilizer.take_retractions(), 0);
+
+    // The committed words came back different: the viewer saw them change.
+    stabilizer.update("a hat");
+    assert_eq!(stabilizer.finalize("a bat sat"), "a bat sat");
+    assert_eq!(stabilizer.take_retractions(), 2);
+    assert_eq!(stabilizer.take_retractions(), 0);
+}
+
+#[test]
+fn final_only_policy_and_live_iterations() {
+    let mut stabilizer = Stabilizer::new(2);
+    stabilizer.set_policy(CommitPolicy::FinalOnly);
+    for _ in 0..5 {
+        assert_eq!(
+            stabilizer.update("hello there"),
+            (String::new(), "hello there".into())
+        );
+    }
+    assert_eq!(
+        stabilizer.finalize("hello there friend"),
+        "hello there friend"
+    );
+    assert_eq!(stabilizer.take_retractions(), 0);
+
+    stabilizer.set_policy(CommitPolicy::Agreement);
+    stabilizer.set_stable_required(3);
+    settle(&mut stabilizer, "one two");
+    assert!(stabilizer.committed().is_empty());
+    stabilizer.update("one two");
+    assert_eq!(stabilizer.committed().len(), 2);
+}
+
+#[test]
+fn finals_drop_phrases_the_partials_had_only_once() {
+    let mut stabilizer = Stabilizer::new(2);
+    settle(&mut stabilizer, "we ship on friday and then");
+    assert_eq!(
+        stabilizer.finalize("We ship on Friday. We ship on Friday, and then"),
+        "We ship on Friday. and then"
+    );
+
+    // Said twice, and the partials heard it twice.
+    settle(&mut stabilizer, "one more time one more time");
+    assert_eq!(
+        stabilizer.finalize("one more time, one more time"),
+        "one more time, one more time"
+    );
+
+    // Repeats the partials never saw aren't touched.
+    stabilizer.update("something else");
+    assert_eq!(stabilizer.finalize("go team go go team go"), "go team go go team go");
+}
+
+#[test]
+fn finals_keep_their_own_words_unless_asked_not_to() {
+    let mut stabilizer = Stabilizer::new(2);
+    settle(&mut stabilizer, "we ship on friday");
+    // The final heard "shipped" over the whole utterance; that's the better text.
+    assert_eq!(
+        stabilizer.finalize("We shipped on Friday, and then"),
+        "We shipped on Friday, and then"
+    );
+    assert_eq!(stabilizer.take_retractions(), 0);
+
+    let mut stabilizer = Stabilizer::new(2).with_kept_committed_words(true);
+    settle(&mut stabilizer, "we ship on friday");
+    // The viewer already saw "ship"; the final's casing and added words still come through.
+    assert_eq!(
+        stabilizer.finalize("We shipped on Friday, and then"),
+        "We ship on Friday, and then"
+    );
+    assert_eq!(stabilizer.take_retractions(), 0);
+
+    // Mostly different is a correction, not a mishearing.
+    settle(&mut stabilizer, "the cat sat");
+    assert_eq!(stabilizer.finalize("a bat mat"), "a bat mat");
+    assert_eq!(stabilizer.take_retractions(), 1);
+}
+
+#[test]
+fn committed_words_after_the_final_carry_over() {
+    let mut stabilizer = Stabilizer::new(2);
+    // Four words over input samples 0..4000: each about a thousand samples long.
+    stabilizer.update_at("one two three four", Some(0..4000));
+    stabilizer.update_at("one two three four", Some(0..4000));
+
+    // The utterance was cut at sample 2000, after "two".
+    assert_eq!(stabilizer.finalize_at("one two", Some(0..2000)), "one two");
+    assert_eq!(stabilizer.committed(), ["three", "four"]);
+    let (committed, pending) = stabilizer.update_at("three four five", Some(2000..5000));
+    assert_eq!((committed.as_str(), pending.as_str()), ("three four", "five"));
+    assert_eq!(stabilizer.take_retractions(), 0);
+}
+
+#[test]
+fn carried_context_is_stripped_from_warm_partials() {
+    let mut stabilizer = Stabilizer::new(2).with_carried_context(true);
+    settle(&mut stabilizer, "we ship on");
+    assert_eq!(stabilizer.finalize("We ship on Friday."), "We ship on Friday.");
+
+    // The warm window starts in the previous sentence, wherever the decoder picks it up.
+    assert_eq!(stabilizer.update("on Friday. Any"), (String::new(), "Any".to_string()));
+    let (committed, pending) = stabilizer.update("Friday. Any questions");
+    assert_eq!((committed.as_str(), pending.as_str()), ("Any", "questions"));
+    // Past the warm partials the window is the new utterance alone.
+    assert_eq!(stabilizer.update("Any questions").0, "Any questions");
+    assert_eq!(stabilizer.take_retractions(), 0);
+}
+
+#[test]
+fn a_new_sentence_may_repeat_the_last_one() {
+    let mut stabilizer = Stabilizer::new(2).with_carried_context(true);
+    stabilizer.finalize_at("We ship on Friday.", Some(0..16_000));
+
+    // Warm: the window starts in the last sentence, then says its words again.
+    let (_, pending) = stabilizer.update_at("Friday, we ship on Friday", Some(12_000..30_000));
+    assert_eq!(pending, "we ship on Friday");
+    // Past the warm partials nothing is stripped, even where it matches the last final.
+    let (committed, pending) =
+        stabilizer.update_at("we ship on Friday again", Some(16_800..34_000));
+    assert_eq!((committed.as_str(), pending.as_str()), ("we ship on Friday", "again"));
+    assert_eq!(stabilizer.take_retractions(), 0);
+}
+
+#[test]
+fn a_warm_window_of_only_the_last_sentence_changes_nothing() {
+    let mut stabilizer = Stabilizer::new(2).with_carried_context(true);
+    stabilizer.update_at("one two three four", Some(0..4000));
+    stabilizer.update_at("one two three four", Some(0..4000));
+    stabilizer.finalize_at("one two", Some(0..2000));
+
+    // The window has decoded nothing past the final yet.
+    let (committed, pending) = stabilizer.update_at("one two", Some(1000..2500));
+    assert_eq!((committed.as_str(), pending.as_str()), ("three four", ""));
+    assert_eq!(stabilizer.take_retractions(), 0);
+}
+
+#[test]
+fn without_carried_context_repeated_words_stay() {
+    let mut stabilizer = Stabilizer::new(2);
+    stabilizer.finalize("Friday.");
+    assert_eq!(stabilizer.update("Friday. Friday"), (String::new(), "Friday. Friday".to_string()));
+}
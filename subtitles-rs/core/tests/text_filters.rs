@@ -0,0 +1,98 @@
+use subtitles_core::config::{ItnLocale, OutputLanguage};
+use subtitles_core::text::{correction_pairs, FilterRegistry, ReplaceRules, TextFilter};
+
+fn registry(rules: &str) -> FilterRegistry {
+    let rules = ReplaceRules::from_json(rules).unwrap();
+    let mut registry = FilterRegistry::default();
+    registry.register("itn", || {
+        Ok(Box::new(ItnLocale::English) as Box<dyn TextFilter>)
+    });
+    registry.register("replace", move || {
+        Ok(Box::new(rules.clone()) as Box<dyn TextFilter>)
+    });
+    registry
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn stages_run_in_configured_order() {
+    let registry = registry(r#"{ "twenty": "****" }"#);
+    let text = || "it costs twenty dollars".to_string();
+
+    let mut itn_first = registry.build(&names(&["itn", "replace"])).unwrap();
+    assert_eq!(
+        itn_first.apply_text(0, text(), OutputLanguage::English),
+        "it costs $20"
+    );
+    let mut replace_first = registry.build(&names(&["replace", "itn"])).unwrap();
+    assert_eq!(
+        replace_first.apply_text(0, text(), OutputLanguage::English),
+        "it costs **** dollars"
+    );
+    let mut none = registry.build(&[]).unwrap();
+    assert_eq!(none.apply_text(0, text(), OutputLanguage::English), text());
+
+    let err = registry.build(&names(&["itn", "romanize"])).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "unknown text filter `romanize` (available: itn, replace)"
+    );
+}
+
+#[test]
+fn replace_rules_match_whole_words_longest_first() {
+    let rules = ReplaceRules::from_json(r#"{ "git": "Git", "git hub": "GitHub", "damn": "d***" }"#)
+        .unwrap();
+    assert_eq!(
+        rules.apply("Damn, push it to git hub with git, not digits"),
+        "d***, push it to GitHub with Git, not digits"
+    );
+    assert!(ReplaceRules::from_json(r#"{ "git": 3 }"#).is_err());
+    assert!(ReplaceRules::from_json(r#"{ " ": "x" }"#).is_err());
+}
+
+#[test]
+fn corrections_suggest_the_phrases_they_replaced() {
+    assert_eq!(
+        correction_pairs(
+            "Push it to git hub, then ask Jon.",
+            "Push it to GitHub, then ask John."
+        ),
+        [
+            ("git hub".to_string(), "GitHub".to_string()),
+            ("Jon".to_string(), "John".to_string()),
+        ]
+    );
+    // Only added or removed words don't make a rule.
+    assert!(correction_pairs("so it works", "so um it works fine").is_empty());
+    assert!(correction_pairs("same text", "same text").is_empty());
+    assert_eq!(
+        correction_pairs("我们用飞属开会", "我们用飞书开会"),
+        [("属".to_string(), "书".to_string())]
+    );
+}
+
+#[test]
+fn rules_added_while_running_reach_every_chain() {
+    let rules = ReplaceRules::default();
+    let mut registry = FilterRegistry::default();
+    let shared = rules.clone();
+    registry.register("replace", move || {
+        Ok(Box::new(shared.clone()) as Box<dyn TextFilter>)
+    });
+    let mut chain = registry.build(&names(&["replace"])).unwrap();
+    let text = || "ask jon about git hub".to_string();
+    assert_eq!(chain.apply_text(0, text(), OutputLanguage::English), text());
+
+    rules.add("git hub", "GitHub").unwrap();
+    rules.add("Jon", "Jonathan").unwrap();
+    rules.add("jon", "John").unwrap();
+    assert_eq!(
+        chain.apply_text(1, text(), OutputLanguage::English),
+        "ask John about GitHub"
+    );
+    assert!(rules.add("  ", "x").is_err());
+}
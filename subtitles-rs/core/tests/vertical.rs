@@ -0,0 +1,27 @@
+use subtitles_core::text::{render_vertical, vertical_columns, vertical_forms};
+
+#[test]
+fn columns_keep_short_numbers_together_and_hang_closing_punctuation() {
+    let columns = vertical_columns("今日は12月です。ありがとう", 6);
+    assert_eq!(
+        columns,
+        vec![
+            vec!["今", "日", "は", "12", "月", "で"],
+            vec!["す", "。", "あ", "り", "が", "と"],
+            vec!["う"],
+        ]
+    );
+
+    // The full stop would start the second column, so it hangs off the first.
+    let hung = vertical_columns("東京に行きます。\n2024年", 7);
+    assert_eq!(hung[0].last().map(String::as_str), Some("。"));
+    assert_eq!(hung[0].len(), 8);
+    assert_eq!(hung[1], vec!["2", "0", "2", "4", "年"]);
+}
+
+#[test]
+fn rendering_reads_right_to_left_with_vertical_punctuation() {
+    assert_eq!(vertical_forms("「はい」、どうぞ。"), "﹁はい﹂︑どうぞ︒");
+    assert_eq!(render_vertical("あいうえ。", 3), "え あ\n︒ い\n   う");
+    assert_eq!(render_vertical("", 3), "");
+}
@@ -0,0 +1,62 @@
+use subtitles_core::audio::{VoicePrint, VoiceProfiles};
+use subtitles_core::testing::SignalBuilder;
+
+fn print(seed: u64, seconds: f32, pitch_hz: f32, tract: f32) -> Option<VoicePrint> {
+    VoicePrint::from_samples(
+        &SignalBuilder::new(16_000, seed)
+            .voice(seconds, pitch_hz, tract)
+            .build(),
+    )
+}
+
+#[test]
+fn enrolled_voices_are_told_apart() {
+    let mut profiles = VoiceProfiles::default();
+    profiles.enroll("Alice", print(1, 3.0, 210.0, 1.18).unwrap());
+    profiles.enroll("Bob", print(2, 3.0, 110.0, 1.0).unwrap());
+
+    let alice = print(3, 2.0, 215.0, 1.18).unwrap();
+    let bob = print(4, 2.0, 105.0, 1.0).unwrap();
+    assert_eq!(
+        profiles.identify(&alice, 0.8).map(|(index, _)| index),
+        Some(0)
+    );
+    assert_eq!(
+        profiles.identify(&bob, 0.8).map(|(index, _)| index),
+        Some(1)
+    );
+
+    // Someone who isn't enrolled stays unknown.
+    profiles.voices.remove(0);
+    assert_eq!(profiles.identify(&alice, 0.8), None);
+}
+
+#[test]
+fn different_voices_with_the_same_pitch_do_not_match() {
+    let mut profiles = VoiceProfiles::default();
+    profiles.enroll("Bob", print(1, 3.0, 110.0, 1.0).unwrap());
+
+    // Same pitch, a vocal tract 10% longer; then a little higher and longer.
+    assert_eq!(profiles.identify(&print(2, 2.0, 110.0, 1.1).unwrap(), 0.8), None);
+    assert_eq!(profiles.identify(&print(3, 2.0, 130.0, 1.08).unwrap(), 0.8), None);
+    assert!(profiles.identify(&print(4, 2.0, 120.0, 1.0).unwrap(), 0.8).is_some());
+}
+
+#[test]
+fn profiles_round_trip_and_need_a_second_of_speech() {
+    assert_eq!(print(1, 0.5, 120.0, 1.0), None);
+    assert_eq!(VoicePrint::from_samples(&vec![0.0; 48_000]), None);
+
+    let mut profiles = VoiceProfiles::default();
+    profiles.enroll("Alice", print(1, 2.0, 210.0, 1.18).unwrap());
+    profiles.enroll(" alice ", print(2, 2.0, 210.0, 1.18).unwrap());
+    assert_eq!(profiles.voices.len(), 1);
+    assert_eq!(profiles.voices[0].name, "Alice");
+    assert_eq!(profiles.voices[0].prints.len(), 2);
+
+    let path = std::env::temp_dir()
+        .join(format!("subtitles-voices-{}", std::process::id()))
+        .join("voices.json");
+    profiles.save(&path).unwrap();
+    assert_eq!(VoiceProfiles::load(&path).unwrap(), profiles);
+}
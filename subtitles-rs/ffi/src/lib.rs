@@ -0,0 +1,307 @@
+//! UniFFI bindings for `subtitles-core`, for native apps on Apple platforms and Android: the app
+//! captures audio itself, pushes samples in (16 kHz mono, or any format it names), and polls
+//! caption events out.
+//!
+//! Swift and Kotlin sources are generated from the compiled library (see the README).
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, Sender};
+use parking_lot::Mutex;
+use subtitles_core::audio::{self, AudioChunk, ChunkClock};
+use subtitles_core::{config, transcribe::DownloadProgress};
+
+mod pipeline;
+
+uniffi::setup_scaffolding!();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Engine {
+    /// On-device whisper.cpp.
+    Local,
+    /// OpenAI-compatible `/v1/audio/transcriptions` (or translations).
+    OpenAi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum OutputLanguage {
+    Chinese,
+    English,
+    /// Original line over its English translation.
+    Bilingual,
+}
+
+impl From<OutputLanguage> for config::OutputLanguage {
+    fn from(lang: OutputLanguage) -> Self {
+        match lang {
+            OutputLanguage::Chinese => config::OutputLanguage::Chinese,
+            OutputLanguage::English => config::OutputLanguage::English,
+            OutputLanguage::Bilingual => config::OutputLanguage::Bilingual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum ModelPreset {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    LargeV3,
+}
+
+impl From<ModelPreset> for config::WhisperModelPreset {
+    fn from(preset: ModelPreset) -> Self {
+        match preset {
+            ModelPreset::Tiny => config::WhisperModelPreset::Tiny,
+            ModelPreset::Base => config::WhisperModelPreset::Base,
+            ModelPreset::Small => config::WhisperModelPreset::Small,
+            ModelPreset::Medium => config::WhisperModelPreset::Medium,
+            ModelPreset::LargeV3 => config::WhisperModelPreset::LargeV3,
+        }
+    }
+}
+
+/// Engine settings; the fields mirror the desktop CLI flags of the same name. Start from
+/// `default_engine_config()`.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct EngineConfig {
+    pub engine: Engine,
+    /// Local model file. When unset, `whisper_model_preset` is downloaded into `models/` under
+    /// the working directory, which mobile apps usually can't write to, so set this there.
+    pub whisper_model: Option<String>,
+    pub whisper_model_preset: ModelPreset,
+    pub whisper_threads: Option<u32>,
+    pub openai_api_key: Option<String>,
+    pub openai_model: String,
+    pub openai_endpoint: String,
+    pub openai_translation_endpoint: String,
+    /// `None` to auto-detect.
+    pub input_language: Option<String>,
+    pub output_language: OutputLanguage,
+    pub prompt: Option<String>,
+    pub vad_threshold: f32,
+    pub vad_end_silence_s: f32,
+    pub max_segment_s: f32,
+    pub pre_roll_s: f32,
+    pub min_speech_ms: u64,
+    pub partial_onset_ms: u64,
+    pub asr_step_ms: u64,
+    pub max_window_s: f32,
+    pub partial_overlap_s: f32,
+    pub final_interval_s: f32,
+    pub partial_stable_iters: u32,
+    pub max_committed_tokens: u32,
+    /// How often to report `Level` events (0 = off).
+    pub level_interval_ms: u64,
+}
+
+/// The desktop CLI's defaults.
+#[uniffi::export]
+pub fn default_engine_config() -> EngineConfig {
+    EngineConfig {
+        engine: Engine::Local,
+        whisper_model: None,
+        whisper_model_preset: ModelPreset::Medium,
+        whisper_threads: None,
+        openai_api_key: None,
+        openai_model: "whisper-1".to_string(),
+        openai_endpoint: "https://api.openai.com/v1/audio/transcriptions".to_string(),
+        openai_translation_endpoint: "https://api.openai.com/v1/audio/translations".to_string(),
+        input_language: None,
+        output_language: OutputLanguage::English,
+        prompt: None,
+        vad_threshold: 0.012,
+        vad_end_silence_s: 0.6,
+        max_segment_s: 20.0,
+        pre_roll_s: 0.25,
+        min_speech_ms: 300,
+        partial_onset_ms: 700,
+        asr_step_ms: 350,
+        max_window_s: 12.0,
+        partial_overlap_s: 3.0,
+        final_interval_s: 0.0,
+        partial_stable_iters: 2,
+        max_committed_tokens: 32,
+        level_interval_ms: 100,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum CaptionEvent {
+    /// Live caption for segment `segment_id`; partials and the final of one utterance share an
+    /// id. Finals carry their span in seconds of pushed audio.
+    Update {
+        segment_id: u64,
+        text: String,
+        is_final: bool,
+        start_s: Option<f64>,
+        end_s: Option<f64>,
+    },
+    Clear,
+    Status {
+        status: EngineStatus,
+    },
+    /// Input level over the last `level_interval_ms`, for a VU meter.
+    Level {
+        rms: f32,
+        peak: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum EngineStatus {
+    Loading,
+    DownloadingModel {
+        file_name: String,
+        downloaded_bytes: u64,
+        total_bytes: Option<u64>,
+    },
+    Ready,
+    /// The model could not be loaded; the engine has stopped.
+    Failed {
+        message: String,
+    },
+}
+
+impl From<DownloadProgress> for EngineStatus {
+    fn from(progress: DownloadProgress) -> Self {
+        EngineStatus::DownloadingModel {
+            file_name: progress.file_name,
+            downloaded_bytes: progress.downloaded_bytes,
+            total_bytes: progress.total_bytes,
+        }
+    }
+}
+
+#[derive(Debug, uniffi::Error)]
+pub enum SubtitlesError {
+    InvalidConfig { message: String },
+}
+
+impl fmt::Display for SubtitlesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubtitlesError::InvalidConfig { message } => write!(f, "invalid config: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SubtitlesError {}
+
+/// A running pipeline. Dropping it stops the worker threads.
+#[derive(uniffi::Object)]
+pub struct CaptionEngine {
+    audio_tx: Sender<AudioChunk>,
+    clock: Mutex<ChunkClock>,
+    events: Receiver<CaptionEvent>,
+    stop: Arc<AtomicBool>,
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+#[uniffi::export]
+impl CaptionEngine {
+    /// Starts the pipeline. The model loads in the background; watch for `Status` events.
+    #[uniffi::constructor]
+    pub fn start(config: EngineConfig) -> Result<Arc<Self>, SubtitlesError> {
+        let (audio_tx, audio_rx) = crossbeam_channel::bounded::<AudioChunk>(256);
+        let (events_tx, events) = crossbeam_channel::bounded::<CaptionEvent>(256);
+        let stop = Arc::new(AtomicBool::new(false));
+        let threads =
+            pipeline::spawn(config, audio_rx, events_tx, stop.clone()).map_err(|err| {
+                SubtitlesError::InvalidConfig {
+                    message: format!("{err:#}"),
+                }
+            })?;
+        Ok(Arc::new(Self {
+            audio_tx,
+            clock: Mutex::new(ChunkClock::asr()),
+            events,
+            stop,
+            threads: Mutex::new(threads),
+        }))
+    }
+
+    /// 16 kHz mono samples in [-1, 1], in capture order. Dropped if the pipeline falls behind.
+    pub fn push_audio(&self, samples: Vec<f32>) {
+        self.push_audio_format(samples, 16_000, 1);
+    }
+
+    /// Samples in [-1, 1] at `sample_rate`, interleaved if `channels` is more than one. The
+    /// engine downmixes and resamples them, so apps can push what the platform captures.
+    pub fn push_audio_format(&self, samples: Vec<f32>, sample_rate: u32, channels: u16) {
+        let chunk = {
+            let mut clock = self.clock.lock();
+            clock.set_format(sample_rate, channels);
+            clock.stamp(samples)
+        };
+        if self.audio_tx.try_send(chunk).is_err() {
+            tracing::warn!("audio queue full; dropping samples");
+        }
+    }
+
+    /// Next event, waiting up to `timeout_ms`. `None` on timeout, or once stopped and drained.
+    pub fn poll_event(&self, timeout_ms: u64) -> Option<CaptionEvent> {
+        self.events
+            .recv_timeout(Duration::from_millis(timeout_ms))
+            .ok()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Stops and joins the worker threads. Events already queued can still be polled.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.threads.lock().drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for CaptionEngine {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Removes the echo of system audio played through the speakers from a microphone signal, for
+/// apps that capture both, so the same speech isn't transcribed twice. Feed it time-aligned
+/// 16 kHz mono chunks and push what it returns.
+#[derive(uniffi::Object)]
+pub struct EchoCanceller(Mutex<audio::EchoCanceller>);
+
+#[uniffi::export]
+impl EchoCanceller {
+    /// A canceller with a 128 ms echo tail.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self(Mutex::new(audio::EchoCanceller::new(
+            audio::EchoCancellerConfig::default(),
+        ))))
+    }
+
+    /// `mic` without the echo of `reference`, the system audio over the same span; a shorter
+    /// `reference` is padded with silence, a longer one cut.
+    pub fn process(&self, mic: Vec<f32>, mut reference: Vec<f32>) -> Vec<f32> {
+        reference.resize(mic.len(), 0.0);
+        self.0.lock().process(&mic, &reference)
+    }
+
+    /// Forgets the learned echo path, e.g. after the output device changes.
+    pub fn reset(&self) {
+        self.0.lock().reset();
+    }
+}
+
+pub(crate) fn send(events: &Sender<CaptionEvent>, event: CaptionEvent) {
+    let is_level = matches!(event, CaptionEvent::Level { .. });
+    if events.try_send(event).is_err() && !is_level {
+        tracing::warn!("event queue full; dropping event");
+    }
+}
@@ -0,0 +1,300 @@
+//! The worker threads behind `CaptionEngine`: a trimmed-down version of the desktop engine
+//! (no capture, second pass or idle handling) built from the same core pieces.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Context;
+use crossbeam_channel::{Receiver, Sender};
+use subtitles_core::audio::{AsrConverter, AudioChunk, LevelMeter, Segmenter, SegmenterConfig};
+use subtitles_core::config::OutputLanguage;
+use subtitles_core::streaming::{
+    PartialGate, Stabilizer, StreamingConfig, StreamingEvent, StreamingSegmenter,
+};
+use subtitles_core::text::{merge_bilingual, TextJoiner};
+use subtitles_core::transcribe::{
+    OpenAiTranscriber, Transcriber, TranscriberConfig, TranscriberLoader, WhisperLocalTranscriber,
+};
+
+use crate::{send, CaptionEvent, Engine, EngineConfig, EngineStatus};
+
+const SAMPLE_RATE_HZ: u32 = 16_000;
+
+pub(crate) fn spawn(
+    config: EngineConfig,
+    audio_rx: Receiver<AudioChunk>,
+    events: Sender<CaptionEvent>,
+    stop: Arc<AtomicBool>,
+) -> anyhow::Result<Vec<JoinHandle<()>>> {
+    let load = transcriber_loader(&config)?;
+    let streaming = config.engine == Engine::Local;
+    let gate = PartialGate::new();
+    // Finals carry where their audio starts, in samples pushed since the engine started.
+    let (segment_tx, segment_rx) = crossbeam_channel::bounded::<(StreamingEvent, Option<u64>)>(32);
+
+    let processing = {
+        let config = config.clone();
+        let events = events.clone();
+        let stop = stop.clone();
+        let gate = gate.clone();
+        std::thread::spawn(move || {
+            process_audio(&config, streaming, gate, audio_rx, segment_tx, events, stop)
+        })
+    };
+    let transcription = std::thread::spawn(move || {
+        transcribe_segments(&config, load, gate, segment_rx, events, stop)
+    });
+    Ok(vec![processing, transcription])
+}
+
+fn transcriber_loader(config: &EngineConfig) -> anyhow::Result<TranscriberLoader> {
+    Ok(match config.engine {
+        Engine::Local => {
+            let model = config.whisper_model.clone().map(PathBuf::from);
+            let preset = config.whisper_model_preset.into();
+            let threads = config.whisper_threads.map(|n| n as usize);
+            Box::new(move |on_progress| {
+                let transcriber =
+                    WhisperLocalTranscriber::new_with_progress(model, preset, threads, on_progress)
+                        .context("failed to initialize local whisper")?;
+                Ok(Box::new(transcriber) as Box<dyn Transcriber>)
+            })
+        }
+        Engine::OpenAi => {
+            // Nothing to download; fail fast on a missing key.
+            let transcriber = OpenAiTranscriber::new(
+                config.openai_api_key.clone(),
+                config.openai_model.clone(),
+                config.openai_endpoint.clone(),
+                config.openai_translation_endpoint.clone(),
+            )?;
+            Box::new(move |_| Ok(Box::new(transcriber) as Box<dyn Transcriber>))
+        }
+    })
+}
+
+fn process_audio(
+    config: &EngineConfig,
+    streaming: bool,
+    gate: PartialGate,
+    audio_rx: Receiver<AudioChunk>,
+    segment_tx: Sender<(StreamingEvent, Option<u64>)>,
+    events: Sender<CaptionEvent>,
+    stop: Arc<AtomicBool>,
+) {
+    let level_block_samples = (config.level_interval_ms * 16) as usize;
+    let mut level_meter = (level_block_samples > 0).then(|| LevelMeter::new(level_block_samples));
+    let mut streaming_segmenter = streaming.then(|| {
+        StreamingSegmenter::new(StreamingConfig {
+            sample_rate_hz: SAMPLE_RATE_HZ,
+            vad_threshold: config.vad_threshold,
+            vad_end_silence_s: config.vad_end_silence_s,
+            max_segment_s: config.max_segment_s,
+            pre_roll_s: config.pre_roll_s,
+            min_speech_ms: config.min_speech_ms,
+            partial_onset_ms: config.partial_onset_ms,
+            asr_step_ms: config.asr_step_ms,
+            max_window_s: config.max_window_s,
+            partial_overlap_s: config.partial_overlap_s,
+            final_interval_s: config.final_interval_s,
+        })
+        .with_partial_gate(gate.clone())
+    });
+    let mut segmenter = Segmenter::new(SegmenterConfig {
+        sample_rate_hz: SAMPLE_RATE_HZ,
+        vad_threshold: config.vad_threshold,
+        vad_end_silence_s: config.vad_end_silence_s,
+        max_segment_s: config.max_segment_s,
+        pre_roll_s: config.pre_roll_s,
+    });
+
+    let mut to_asr = AsrConverter::default();
+    while !stop.load(Ordering::Relaxed) {
+        let chunk = match audio_rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(chunk) => to_asr.convert(chunk),
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        };
+        if let Some(meter) = level_meter.as_mut() {
+            for level in meter.push(&chunk) {
+                send(
+                    &events,
+                    CaptionEvent::Level {
+                        rms: level.rms,
+                        peak: level.peak,
+                    },
+                );
+            }
+        }
+
+        let mut segments = Vec::new();
+        match streaming_segmenter.as_mut() {
+            Some(streaming) => {
+                for event in streaming.push_audio(&chunk) {
+                    let start = matches!(event, StreamingEvent::Final(_))
+                        .then(|| streaming.take_final_start())
+                        .flatten();
+                    segments.push((event, start));
+                }
+            }
+            None => {
+                for segment in segmenter.push_audio(&chunk) {
+                    let start = segmenter.take_segment_start();
+                    segments.push((StreamingEvent::Final(segment.into()), start));
+                }
+            }
+        }
+        for (event, start) in segments {
+            let is_partial = matches!(event, StreamingEvent::Partial(_));
+            if segment_tx.try_send((event, start)).is_err() {
+                tracing::warn!("segment queue full; dropping event");
+                if is_partial {
+                    gate.release();
+                }
+            }
+        }
+    }
+}
+
+fn transcribe_segments(
+    config: &EngineConfig,
+    load: TranscriberLoader,
+    gate: PartialGate,
+    segment_rx: Receiver<(StreamingEvent, Option<u64>)>,
+    events: Sender<CaptionEvent>,
+    stop: Arc<AtomicBool>,
+) {
+    send(&events, status(EngineStatus::Loading));
+    let mut transcriber = match load(&mut |progress| send(&events, status(progress.into()))) {
+        Ok(transcriber) => transcriber,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            let message = format!("{err:#}");
+            send(&events, status(EngineStatus::Failed { message }));
+            stop.store(true, Ordering::Relaxed);
+            return;
+        }
+    };
+    send(&events, status(EngineStatus::Ready));
+
+    let mode: OutputLanguage = config.output_language.into();
+    let decode_cfg = TranscriberConfig {
+        input_language: config
+            .input_language
+            .clone()
+            .filter(|l| !l.trim().is_empty()),
+        output_language: mode,
+        is_partial: false,
+        segment_timestamps: false,
+        prompt: config
+            .prompt
+            .clone()
+            .filter(|prompt| !prompt.trim().is_empty()),
+        glossary: None,
+        diarize: false,
+    };
+    let joiner = |mode| TextJoiner::for_output(mode, decode_cfg.input_language.as_deref());
+    let mut decode = |output_language, is_partial, audio: &[f32]| {
+        let cfg = TranscriberConfig {
+            output_language,
+            is_partial,
+            ..decode_cfg.clone()
+        };
+        match transcriber.transcribe(audio, &cfg) {
+            Ok(text) => Some(text),
+            Err(err) => {
+                tracing::warn!("transcription failed: {err:#}");
+                None
+            }
+        }
+    };
+    // One stabilizer per line; bilingual output has two.
+    let lines = match mode {
+        OutputLanguage::Bilingual => vec![OutputLanguage::Chinese, OutputLanguage::English],
+        other => vec![other],
+    };
+    let stable_iters = config.partial_stable_iters as usize;
+    let mut stabilizers: Vec<Stabilizer> = lines
+        .iter()
+        .map(|_| {
+            Stabilizer::new(stable_iters).with_max_committed(config.max_committed_tokens as usize)
+        })
+        .collect();
+    let merge = |texts: Vec<String>| {
+        texts
+            .into_iter()
+            .reduce(|primary, secondary| merge_bilingual(&primary, &secondary))
+            .unwrap_or_default()
+    };
+
+    let mut segment_id = 0;
+    let mut last_caption = String::new();
+    while !stop.load(Ordering::Relaxed) {
+        let (event, start) = match segment_rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(received) => received,
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        };
+        match event {
+            StreamingEvent::Partial(audio) => {
+                let mut texts = Vec::new();
+                for (line, stabilizer) in lines.iter().zip(&mut stabilizers) {
+                    let hypothesis = decode(*line, true, &audio).unwrap_or_default();
+                    let (committed, partial) = stabilizer.update(&hypothesis);
+                    let joiner = joiner(*line);
+                    texts.push(joiner.format(&joiner.join(&committed, &partial)));
+                }
+                gate.release();
+                let text = merge(texts);
+                if text != last_caption {
+                    last_caption = text.clone();
+                    send(&events, update(segment_id, text, false, None));
+                }
+            }
+            StreamingEvent::Final(audio) => {
+                let mut texts = Vec::new();
+                for (line, stabilizer) in lines.iter().zip(&mut stabilizers) {
+                    let hypothesis = decode(*line, false, &audio).unwrap_or_default();
+                    texts.push(joiner(*line).format(&stabilizer.finalize(&hypothesis)));
+                }
+                let text = merge(texts);
+                if !text.trim().is_empty() {
+                    let span = start.map(|start| {
+                        let start_s = start as f64 / SAMPLE_RATE_HZ as f64;
+                        (
+                            start_s,
+                            start_s + audio.len() as f64 / SAMPLE_RATE_HZ as f64,
+                        )
+                    });
+                    last_caption.clear();
+                    send(&events, update(segment_id, text, true, span));
+                    segment_id += 1;
+                }
+            }
+            StreamingEvent::Reset => {
+                stabilizers.iter_mut().for_each(Stabilizer::reset);
+                if !last_caption.is_empty() {
+                    last_caption.clear();
+                    send(&events, CaptionEvent::Clear);
+                }
+            }
+        }
+    }
+}
+
+fn status(status: EngineStatus) -> CaptionEvent {
+    CaptionEvent::Status { status }
+}
+
+fn update(segment_id: u64, text: String, is_final: bool, span: Option<(f64, f64)>) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text,
+        is_final,
+        start_s: span.map(|(start, _)| start),
+        end_s: span.map(|(_, end)| end),
+    }
+}
@@ -0,0 +1,67 @@
+use subtitles_ffi::{default_engine_config, CaptionEngine, Engine, EngineConfig, SubtitlesError};
+
+#[test]
+fn openai_engine_without_a_key_fails_to_start() {
+    let config = EngineConfig {
+        engine: Engine::OpenAi,
+        openai_api_key: None,
+        ..default_engine_config()
+    };
+    match CaptionEngine::start(config) {
+        Err(SubtitlesError::InvalidConfig { message }) => assert!(message.contains("API key")),
+        Ok(_) => panic!("started without an API key"),
+    }
+}
+
+#[test]
+fn engine_stops_cleanly_and_keeps_queued_events() {
+    let config = EngineConfig {
+        engine: Engine::OpenAi,
+        openai_api_key: Some("test".to_string()),
+        level_interval_ms: 100,
+        ..default_engine_config()
+    };
+    let engine = CaptionEngine::start(config).unwrap();
+    engine.push_audio(vec![0.25; 1_600]);
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    engine.stop();
+    assert!(!engine.is_running());
+
+    let mut saw_ready = false;
+    let mut saw_level = false;
+    while let Some(event) = engine.poll_event(0) {
+        match event {
+            subtitles_ffi::CaptionEvent::Status { status } => {
+                saw_ready |= status == subtitles_ffi::EngineStatus::Ready
+            }
+            subtitles_ffi::CaptionEvent::Level { peak, .. } => saw_level |= peak == 0.25,
+            _ => {}
+        }
+    }
+    assert!(saw_ready && saw_level);
+}
+
+#[test]
+fn audio_in_the_platform_format_is_converted() {
+    let config = EngineConfig {
+        engine: Engine::OpenAi,
+        openai_api_key: Some("test".to_string()),
+        level_interval_ms: 100,
+        ..default_engine_config()
+    };
+    let engine = CaptionEngine::start(config).unwrap();
+    // 100 ms of 48 kHz stereo, left and right averaging to 0.25.
+    let stereo: Vec<f32> = (0..4_800).flat_map(|_| [0.5, 0.0]).collect();
+    engine.push_audio_format(stereo, 48_000, 2);
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    engine.stop();
+
+    let mut peaks = Vec::new();
+    while let Some(event) = engine.poll_event(0) {
+        if let subtitles_ffi::CaptionEvent::Level { peak, .. } = event {
+            peaks.push(peak);
+        }
+    }
+    assert_eq!(peaks.len(), 1);
+    assert!((peaks[0] - 0.25).abs() < 1e-4, "{peaks:?}");
+}
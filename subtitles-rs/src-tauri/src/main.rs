@@ -1,15 +1,40 @@
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
+use clap::ValueEnum;
 use subtitles::{
-    app::{CaptionEvent, SharedOutputLanguage},
-    config::{Cli, OutputLanguage},
+    app::{
+        CaptionEvent, EngineStatus, RecentEvents, SegmentAudio, SegmentEdits, SharedCaptionDelay,
+        SharedOutputLanguage, SharedStabilizer, SpeakerNames,
+    },
+    config::{CaptionLayout, CommitPolicy, OutputLanguage, WhisperModelPreset},
+    domain::{SharedDomainModel, DEFAULT_DOMAIN},
+    instance,
+    macos_capture::{self, CaptureControl, CaptureSource, CaptureTarget},
+    playback,
+    protocol::Event,
+    settings::{self, Settings},
+    setup::{self, CaptureTest, ModelInfo, PermissionStatus},
     start_engine,
+    text::{correction_pairs, ReplaceRules, SoundTagFilter},
+    theme::{CaptionBox, CaptionTheme, SharedCaptionTheme, ThemeStyle},
 };
 use tauri::Emitter;
 
 #[derive(Clone)]
 struct AppState {
     output_language: SharedOutputLanguage,
+    capture: CaptureControl,
+    caption_delay: SharedCaptionDelay,
+    stabilizer: SharedStabilizer,
+    recent: RecentEvents,
+    sound_tags: SoundTagFilter,
+    caption_theme: SharedCaptionTheme,
+    domain: SharedDomainModel,
+    speaker_names: SpeakerNames,
+    segment_audio: SegmentAudio,
+    segment_edits: SegmentEdits,
+    replace_rules: ReplaceRules,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -17,13 +42,28 @@ struct ConfigPayload {
     font_size: f32,
     overlay_width_frac: f32,
     output_language: String,
+    caption_delay_ms: u64,
+    partial_stable_iters: usize,
+    commit_policy: String,
+    caption_theme: ThemeStyle,
+    caption_layout: &'static str,
 }
 
-#[derive(Clone, serde::Serialize)]
-struct CaptionPayload {
-    text: String,
-    is_final: bool,
-    clear: bool,
+/// What the overlay should be showing right now, for a webview that was just (re)loaded.
+/// Each event is named like the live event it stands for.
+#[tauri::command]
+fn get_recent_events(state: tauri::State<AppState>) -> Vec<Event> {
+    state
+        .recent
+        .snapshot()
+        .into_iter()
+        .filter(|event| match event {
+            CaptionEvent::Update { .. } | CaptionEvent::SpeakerName { .. } => true,
+            CaptionEvent::Status(status) => !matches!(status, EngineStatus::DownloadingModel(_)),
+            _ => false,
+        })
+        .map(|event| Event::from_caption_event(event, &state.sound_tags))
+        .collect()
 }
 
 #[tauri::command]
@@ -38,6 +78,220 @@ fn set_output_language(language: String, state: tauri::State<AppState>) -> Resul
     Ok(())
 }
 
+#[tauri::command]
+fn set_caption_delay(delay_ms: u64, state: tauri::State<AppState>) {
+    state.caption_delay.set(Duration::from_millis(delay_ms));
+}
+
+/// Either setting may be left out to keep it.
+#[tauri::command]
+fn set_partial_stability(
+    stable_iters: Option<usize>,
+    commit_policy: Option<String>,
+    state: tauri::State<AppState>,
+) -> Result<(), String> {
+    let policy = commit_policy
+        .map(|policy| CommitPolicy::from_str(&policy, true))
+        .transpose()
+        .map_err(|_| "unknown commit policy".to_string())?;
+    if let Some(iters) = stable_iters {
+        state.stabilizer.set_stable_iters(iters);
+    }
+    if let Some(policy) = policy {
+        state.stabilizer.set_policy(policy);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_speaker_name(id: u32, name: String, state: tauri::State<AppState>) -> Result<(), String> {
+    state
+        .speaker_names
+        .set(id, &name)
+        .map_err(|err| err.to_string())
+}
+
+/// Plays a caption's audio back, for checking what was really said.
+#[tauri::command]
+fn play_segment_audio(segment_id: u64, state: tauri::State<AppState>) -> Result<(), String> {
+    let audio = state
+        .segment_audio
+        .get(segment_id)
+        .ok_or_else(|| "that caption's audio is no longer kept".to_string())?;
+    playback::play(audio).map_err(|err| format!("{err:#}"))
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ReplacementPayload {
+    from: String,
+    to: String,
+}
+
+/// Corrects a final by hand; the `--record` subtitles and `--report` take the new text.
+/// Returns the phrases the correction replaced in `original_text`, which the overlay offers
+/// to add as replacement rules.
+#[tauri::command]
+fn edit_segment(
+    segment_id: u64,
+    corrected_text: String,
+    original_text: Option<String>,
+    state: tauri::State<AppState>,
+) -> Result<Vec<ReplacementPayload>, String> {
+    state
+        .segment_edits
+        .edit(segment_id, &corrected_text)
+        .map_err(|err| err.to_string())?;
+    let pairs = original_text.map_or_else(Vec::new, |original| {
+        correction_pairs(&original, &corrected_text)
+    });
+    Ok(pairs
+        .into_iter()
+        .map(|(from, to)| ReplacementPayload { from, to })
+        .collect())
+}
+
+/// Fixes `from` to `to` in every final for the rest of the session.
+#[tauri::command]
+fn add_replacement_rule(
+    from: String,
+    to: String,
+    state: tauri::State<AppState>,
+) -> Result<(), String> {
+    state
+        .replace_rules
+        .add(&from, &to)
+        .map_err(|err| err.to_string())
+}
+
+#[derive(Clone, serde::Serialize)]
+struct DomainModelsPayload {
+    /// `default` first, then each `--domain-model`.
+    names: Vec<String>,
+    selected: String,
+}
+
+#[tauri::command]
+fn list_domain_models(state: tauri::State<AppState>) -> DomainModelsPayload {
+    let declared = state.domain.models().iter().map(|model| model.name.clone());
+    DomainModelsPayload {
+        names: std::iter::once(DEFAULT_DOMAIN.to_string()).chain(declared).collect(),
+        selected: state.domain.selected_name(),
+    }
+}
+
+/// Switches the live model; the engine reports `loading` and then `ready` again.
+#[tauri::command]
+fn set_domain_model(name: String, state: tauri::State<AppState>) -> Result<(), String> {
+    state.domain.select(&name).map_err(|err| format!("{err:#}"))
+}
+
+/// Every theme, for the overlay's theme picker.
+#[tauri::command]
+fn list_caption_themes() -> Vec<ThemeStyle> {
+    CaptionTheme::ALL.iter().map(|theme| theme.style()).collect()
+}
+
+#[tauri::command]
+fn get_caption_theme(state: tauri::State<AppState>) -> ThemeStyle {
+    state.caption_theme.style()
+}
+
+/// Switches the theme and tells every window, so they all restyle together.
+#[tauri::command]
+fn set_caption_theme(
+    name: String,
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<ThemeStyle, String> {
+    let theme = CaptionTheme::from_name(&name).ok_or("unknown caption theme")?;
+    state.caption_theme.set(theme);
+    let style = state.caption_theme.style();
+    let _ = app.emit("caption-theme", style.clone());
+    Ok(style)
+}
+
+/// Changes the given settings of the box behind the captions and restyles every window.
+#[tauri::command]
+fn set_caption_box(
+    caption_box: CaptionBox,
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<ThemeStyle, String> {
+    caption_box.validate().map_err(|err| err.to_string())?;
+    state.caption_theme.update_box(caption_box);
+    let style = state.caption_theme.style();
+    let _ = app.emit("caption-theme", style.clone());
+    Ok(style)
+}
+
+#[tauri::command(async)]
+fn check_permissions() -> PermissionStatus {
+    setup::check_permissions()
+}
+
+#[tauri::command]
+fn list_models() -> Vec<ModelInfo> {
+    setup::model_presets()
+}
+
+#[tauri::command(async)]
+fn download_model(preset: String, app: tauri::AppHandle) -> Result<String, String> {
+    let preset = WhisperModelPreset::from_str(&preset, true)?;
+    let path = setup::download_model(preset, &mut |progress| {
+        let event = Event::ModelDownloadProgress(progress.into());
+        let _ = app.emit(event.name(), event.payload());
+    })
+    .map_err(|err| format!("{err:#}"))?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command(async)]
+fn list_capture_sources() -> Result<Vec<CaptureSource>, String> {
+    macos_capture::list_capture_sources().map_err(|err| format!("{err:#}"))
+}
+
+#[tauri::command]
+fn capture_target(state: tauri::State<AppState>) -> CaptureTarget {
+    state.capture.target()
+}
+
+#[tauri::command]
+fn set_capture_target(target: CaptureTarget, state: tauri::State<AppState>) {
+    state.capture.set_target(target);
+}
+
+#[tauri::command(async)]
+fn test_capture(
+    seconds: f32,
+    target: Option<CaptureTarget>,
+    app: tauri::AppHandle,
+) -> Result<CaptureTest, String> {
+    let duration = Duration::from_secs_f32(seconds.clamp(0.5, 30.0));
+    setup::test_capture(target.unwrap_or_default(), duration, &mut |level| {
+        let _ = app.emit("capture-level", level);
+    })
+    .map_err(|err| format!("{err:#}"))
+}
+
+#[tauri::command]
+fn load_settings() -> Settings {
+    settings::default_settings_path()
+        .filter(|path| path.exists())
+        .and_then(|path| settings::load_settings(&path).ok())
+        .unwrap_or_default()
+}
+
+/// Persists the wizard's choices and, if asked, restarts so the engine picks them up.
+#[tauri::command]
+fn save_settings(values: Settings, restart: bool, app: tauri::AppHandle) -> Result<(), String> {
+    let path = settings::default_settings_path().ok_or("cannot locate the settings directory")?;
+    settings::save_settings(&path, &values).map_err(|err| format!("{err:#}"))?;
+    if restart {
+        app.restart();
+    }
+    Ok(())
+}
+
 fn output_language_label(lang: OutputLanguage) -> String {
     match lang {
         OutputLanguage::Chinese => "chinese".to_string(),
@@ -46,6 +300,12 @@ fn output_language_label(lang: OutputLanguage) -> String {
     }
 }
 
+fn commit_policy_label(policy: CommitPolicy) -> String {
+    policy
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
 fn main() {
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -54,10 +314,31 @@ fn main() {
         )
         .init();
 
-    let cli = <Cli as clap::Parser>::parse();
+    let cli = match settings::parse_cli() {
+        Ok(cli) => cli,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            std::process::exit(2);
+        }
+    };
+    let caption_box = match CaptionBox::from_cli(&cli) {
+        Ok(caption_box) => caption_box,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            std::process::exit(2);
+        }
+    };
     if cli.no_ui {
         tracing::warn!("--no-ui is ignored in the Tauri app; use the CLI binary for headless output");
     }
+    match instance::forward_to_running(std::env::args_os()) {
+        Ok(false) => {}
+        Ok(true) => std::process::exit(0),
+        Err(err) => {
+            tracing::error!("{err:#}");
+            std::process::exit(1);
+        }
+    }
 
     let (caption_tx, caption_rx) = crossbeam_channel::bounded::<CaptionEvent>(64);
     let engine = match start_engine(cli.clone(), caption_tx) {
@@ -69,14 +350,34 @@ fn main() {
     };
 
     let stop = engine.stop.clone();
+    let sound_tags = SoundTagFilter::new(cli.overlay_sound_tags, cli.sound_tag_glyph.clone());
     let app_state = AppState {
         output_language: engine.output_language.clone(),
+        capture: engine.capture.clone(),
+        caption_delay: engine.caption_delay.clone(),
+        stabilizer: engine.stabilizer.clone(),
+        recent: engine.recent.clone(),
+        sound_tags: sound_tags.clone(),
+        caption_theme: SharedCaptionTheme::new(cli.caption_theme, caption_box),
+        domain: engine.domain.clone(),
+        speaker_names: engine.speaker_names.clone(),
+        segment_audio: engine.segment_audio.clone(),
+        segment_edits: engine.segment_edits.clone(),
+        replace_rules: engine.replace_rules.clone(),
     };
 
     let config_payload = ConfigPayload {
         font_size: cli.font_size,
         overlay_width_frac: cli.overlay_width_frac,
         output_language: output_language_label(cli.output_language),
+        caption_delay_ms: cli.caption_delay_ms,
+        partial_stable_iters: cli.partial_stable_iters,
+        commit_policy: commit_policy_label(cli.commit_policy),
+        caption_theme: app_state.caption_theme.style(),
+        caption_layout: match cli.caption_layout {
+            CaptionLayout::Horizontal => "horizontal",
+            CaptionLayout::Vertical => "vertical",
+        },
     };
 
     let app_result = tauri::Builder::default()
@@ -87,25 +388,38 @@ fn main() {
 
             std::thread::spawn(move || {
                 while let Ok(event) = caption_rx.recv() {
-                    let payload = match event {
-                        CaptionEvent::Update { text, is_final } => CaptionPayload {
-                            text,
-                            is_final,
-                            clear: false,
-                        },
-                        CaptionEvent::Clear => CaptionPayload {
-                            text: String::new(),
-                            is_final: true,
-                            clear: true,
-                        },
-                    };
-                    let _ = handle.emit("caption", payload);
+                    let event = Event::from_caption_event(event, &sound_tags);
+                    let _ = handle.emit(event.name(), event.payload());
                 }
             });
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![set_output_language])
+        .invoke_handler(tauri::generate_handler![
+            get_recent_events,
+            set_output_language,
+            set_caption_delay,
+            set_partial_stability,
+            set_speaker_name,
+            play_segment_audio,
+            edit_segment,
+            add_replacement_rule,
+            list_caption_themes,
+            get_caption_theme,
+            set_caption_theme,
+            set_caption_box,
+            list_domain_models,
+            set_domain_model,
+            check_permissions,
+            list_models,
+            download_model,
+            test_capture,
+            list_capture_sources,
+            capture_target,
+            set_capture_target,
+            load_settings,
+            save_settings
+        ])
         .on_window_event(move |_window, event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event {
                 stop.store(true, Ordering::Relaxed);
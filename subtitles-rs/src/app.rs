@@ -1,17 +1,66 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering},
     Arc,
 };
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
-use crossbeam_channel::Sender;
+use clap::ValueEnum;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
 
-use crate::audio::Segmenter;
-use crate::config::{Cli, Engine, OutputLanguage};
-use crate::macos_capture::start_macos_system_audio_capture;
-use crate::streaming::{Stabilizer, StreamingConfig, StreamingEvent, StreamingSegmenter};
-use crate::transcribe::{OpenAiTranscriber, Transcriber, TranscriberConfig, WhisperLocalTranscriber};
+use crate::ass::{AssStyle, AssWriter};
+use crate::audio::{
+    crosstalk_fraction, AsrConverter, AudioChunk, AudioLevel, IdleDetector, LevelMeter,
+    LoudnessGate, PreprocessChain, Segmenter, VoicePrint, VoiceProfiles,
+};
+use crate::braille::{BrailleSink, BrlApiHost};
+use crate::config::{
+    CaptionLayout, Cli, CommitPolicy, Engine, OutputLanguage, PrintMode, WhisperModelPreset,
+};
+use crate::crash::ReporterConfig;
+use crate::daemon::{PidFile, Signals};
+use crate::device_capture::start_device_capture;
+use crate::domain::{DomainModel, SharedDomainModel, DEFAULT_DOMAIN};
+use crate::engine_registry;
+use crate::error::{ErrorKind, SubtitlesError};
+use crate::filter_cmd::CommandFilter;
+use crate::instance::{runtime_dir, Handoff, InstanceLock, LiveControls};
+use crate::macos_capture::{start_macos_system_audio_capture, CaptureControl};
+use crate::platform::{Capabilities, CaptureBackend};
+use crate::power::PowerWatch;
+use crate::process_tap::start_process_tap_capture;
+use crate::protocol::{Event as ProtocolEvent, Message};
+use crate::report::{LiveStats, SessionReport, StatsTracker};
+use crate::script::CaptionScript;
+use crate::second_pass::{SecondPass, SecondPassJob};
+use crate::settings::parse_cli;
+use crate::shadow::{ShadowEngine, ShadowJob};
+use crate::speech::{say_command, SpeechSink};
+use crate::srt::SrtWriter;
+use crate::stdin_capture::start_stdin_capture;
+use crate::stream_capture::start_stream_capture;
+use crate::streaming::{
+    sentence_endpoint, AsrStepController, PartialGate, Stabilizer, StreamingConfig,
+    StreamingEvent, StreamingSegmenter,
+};
+use crate::text::{
+    merge_bilingual, render_vertical, FilterRegistry, Glossary, ReadingSpeed, ReplaceRules,
+    SoundTagFilter, TextFilter, TextJoiner, TranslationMemory, CROSSTALK_TAG,
+};
+use crate::theme::{CaptionBox, SPEAKER_COLORS};
+use crate::transcribe::{
+    cached_smaller_preset, estimated_gpu_memory_mb, largest_preset_within, model_dir,
+    preset_gpu_memory_mb, preset_model_path, resolve_custom_model_path, set_gpu_memory_limit_mb,
+    set_model_mirrors, set_word_alignment, DownloadProgress, EnsembleTranscriber,
+    LanguagePairTranscriber, OpenAiTranscriber, Transcriber, TranscriberConfig, TranscriberLoader,
+    Transcript, WhisperLocalTranscriber, WordTiming,
+};
+use crate::update::{spawn_update_check, UpdateInfo};
+use crate::voice_commands::{spoken_output_language, CommandSpotter, VoiceAction};
+use crate::vtt;
 
 #[derive(Debug, Clone)]
 pub struct SharedOutputLanguage {
@@ -38,18 +87,676 @@ impl SharedOutputLanguage {
     }
 }
 
+/// How long captions are held before they are shown, adjustable while the engine runs.
+#[derive(Debug, Clone, Default)]
+pub struct SharedCaptionDelay {
+    inner: Arc<AtomicU64>,
+}
+
+impl SharedCaptionDelay {
+    pub fn new(initial: Duration) -> Self {
+        let delay = Self::default();
+        delay.set(initial);
+        delay
+    }
+
+    pub fn get(&self) -> Duration {
+        Duration::from_millis(self.inner.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, value: Duration) {
+        self.inner.store(value.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+/// `--partial-stable-iters` and `--commit-policy`, adjustable while the engine runs, and the
+/// count of committed text later retracted, for the HUD's flicker rate.
+#[derive(Debug, Clone, Default)]
+pub struct SharedStabilizer {
+    stable_iters: Arc<AtomicUsize>,
+    policy: Arc<AtomicU8>,
+    retractions: Arc<AtomicU64>,
+}
+
+impl SharedStabilizer {
+    pub fn new(stable_iters: usize, policy: CommitPolicy) -> Self {
+        let shared = Self::default();
+        shared.set_stable_iters(stable_iters);
+        shared.set_policy(policy);
+        shared
+    }
+
+    pub fn stable_iters(&self) -> usize {
+        self.stable_iters.load(Ordering::Relaxed)
+    }
+
+    pub fn set_stable_iters(&self, value: usize) {
+        self.stable_iters.store(value.max(1), Ordering::Relaxed);
+    }
+
+    pub fn policy(&self) -> CommitPolicy {
+        match self.policy.load(Ordering::Relaxed) {
+            0 => CommitPolicy::Agreement,
+            _ => CommitPolicy::FinalOnly,
+        }
+    }
+
+    pub fn set_policy(&self, value: CommitPolicy) {
+        self.policy.store(value as u8, Ordering::Relaxed);
+    }
+
+    /// Retractions since the engine started.
+    pub fn retractions(&self) -> u64 {
+        self.retractions.load(Ordering::Relaxed)
+    }
+
+    fn add_retractions(&self, count: u64) {
+        self.retractions.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// The latest engine status and the captions currently worth showing, so a frontend that
+/// (re)connects can draw the current state instead of a blank overlay. Partials are replaced
+/// by later updates of the same segment, revisions are folded in, and `Clear` empties it.
+#[derive(Debug, Clone, Default)]
+pub struct RecentEvents {
+    inner: Arc<parking_lot::Mutex<RecentState>>,
+}
+
+#[derive(Debug, Default)]
+struct RecentState {
+    capacity: usize,
+    status: Option<EngineStatus>,
+    update: Option<UpdateInfo>,
+    warning: Option<String>,
+    speaker_names: BTreeMap<u32, String>,
+    captions: VecDeque<CaptionEvent>,
+}
+
+impl RecentEvents {
+    /// Keeps up to `capacity` caption events (0 keeps only the status).
+    pub fn new(capacity: usize) -> Self {
+        let recent = Self::default();
+        recent.inner.lock().capacity = capacity;
+        recent
+    }
+
+    pub fn record(&self, event: &CaptionEvent) {
+        let mut state = self.inner.lock();
+        match event {
+            // Progress is only interesting while it is happening.
+            CaptionEvent::Status(EngineStatus::DownloadingModel(_)) => {}
+            CaptionEvent::Status(EngineStatus::UpdateAvailable(update)) => {
+                state.update = Some(update.clone());
+            }
+            CaptionEvent::Status(EngineStatus::Warning(message)) => {
+                state.warning = Some(message.clone());
+            }
+            CaptionEvent::Status(status) => state.status = Some(status.clone()),
+            CaptionEvent::Update { segment_id, .. } => {
+                let id = *segment_id;
+                state.captions.retain(|old| {
+                    !matches!(old, CaptionEvent::Update { segment_id, .. } if *segment_id == id)
+                });
+                state.captions.push_back(event.clone());
+                while state.captions.len() > state.capacity {
+                    state.captions.pop_front();
+                }
+            }
+            CaptionEvent::Revise { segment_id, text } => {
+                for old in state.captions.iter_mut() {
+                    if let CaptionEvent::Update {
+                        segment_id: old_id,
+                        text: old_text,
+                        ..
+                    } = old
+                    {
+                        if old_id == segment_id {
+                            *old_text = text.clone();
+                        }
+                    }
+                }
+            }
+            CaptionEvent::SpeakerName { id, name } if name.is_empty() => {
+                state.speaker_names.remove(id);
+            }
+            CaptionEvent::SpeakerName { id, name } => {
+                state.speaker_names.insert(*id, name.clone());
+            }
+            CaptionEvent::Clear => state.captions.clear(),
+            CaptionEvent::Level(_) | CaptionEvent::Stats(_) => {}
+        }
+    }
+
+    /// The last status, any update notice and warning, the speaker names, then the kept
+    /// captions, oldest first.
+    pub fn snapshot(&self) -> Vec<CaptionEvent> {
+        let state = self.inner.lock();
+        let update = state.update.clone().map(EngineStatus::UpdateAvailable);
+        let warning = state.warning.clone().map(EngineStatus::Warning);
+        let names = state
+            .speaker_names
+            .iter()
+            .map(|(id, name)| CaptionEvent::SpeakerName {
+                id: *id,
+                name: name.clone(),
+            });
+        state
+            .status
+            .iter()
+            .cloned()
+            .chain(update)
+            .chain(warning)
+            .map(CaptionEvent::Status)
+            .chain(names)
+            .chain(state.captions.iter().cloned())
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum CaptionEvent {
-    Update { text: String, is_final: bool },
+    /// Live caption for segment `segment_id`; partials and the final of one utterance share an id.
+    Update {
+        segment_id: u64,
+        text: String,
+        is_final: bool,
+        /// Where a final's audio sits on the capture clock; `None` for partials.
+        timing: Option<CaptionTiming>,
+        /// Who said a final, with `--diarize`; `None` for partials.
+        speaker: Option<Speaker>,
+        /// A final in the original language because translating it failed.
+        untranslated: bool,
+        /// A partial's `text` cut where the stabilizer has committed it (stable) and where it
+        /// may still change; they join back into `text`. Empty for finals.
+        spans: Vec<CaptionSpan>,
+        /// When each word of a final was spoken, with `--word-timings`; empty otherwise.
+        words: Vec<CaptionWord>,
+    },
+    /// Corrected text for an already-finalized segment, from the second-pass worker.
+    Revise { segment_id: u64, text: String },
     Clear,
+    Status(EngineStatus),
+    /// Input level over the last `--level-interval-ms`, for a VU meter.
+    Level(AudioLevel),
+    /// Speaking rate and talk time, every `--stats-interval-ms`, for the overlay's HUD.
+    Stats(LiveStats),
+    /// A name for diarized speaker `id`, for captions already shown as well as later ones;
+    /// an empty name goes back to "Speaker N".
+    SpeakerName { id: u32, name: String },
+}
+
+/// Part of a partial caption, and whether the stabilizer has committed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptionSpan {
+    pub text: String,
+    pub stable: bool,
+}
+
+/// A word of a final and when it was spoken, on the capture clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionWord {
+    pub text: String,
+    pub timing: CaptionTiming,
+}
+
+/// Span of captured audio, in seconds since capture started. Shares its clock with
+/// `--record`'s video.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionTiming {
+    pub start_s: f64,
+    pub end_s: f64,
+}
+
+impl CaptionTiming {
+    fn from_samples(start: u64, len: usize) -> Self {
+        Self {
+            start_s: start as f64 / 16_000.0,
+            end_s: (start + len as u64) as f64 / 16_000.0,
+        }
+    }
+}
+
+impl CaptionWord {
+    /// An aligned word of a final whose audio starts at sample `start`.
+    fn aligned(start: u64, word: WordTiming) -> Self {
+        let offset_s = start as f64 / 16_000.0;
+        Self {
+            text: word.text,
+            timing: CaptionTiming {
+                start_s: offset_s + word.start_s as f64,
+                end_s: offset_s + word.end_s as f64,
+            },
+        }
+    }
+}
+
+/// A diarized speaker and the color their captions are drawn in, the same for the whole
+/// session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Speaker {
+    pub id: u32,
+    pub color: &'static str,
+}
+
+impl Speaker {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            color: SPEAKER_COLORS[id as usize % SPEAKER_COLORS.len()],
+        }
+    }
+}
+
+/// Names diarized speakers while the engine runs. The name goes through the caption pipeline,
+/// so the recent-events history, the `--record` subtitles and the frontend all pick it up.
+#[derive(Debug, Clone)]
+pub struct SpeakerNames {
+    tx: Sender<CaptionEvent>,
+}
+
+impl SpeakerNames {
+    /// Calls speaker `id` `name` from now on and in what was already captioned; an empty
+    /// name clears it. Fails rather than waits when the caption queue is full.
+    pub fn set(&self, id: u32, name: &str) -> anyhow::Result<()> {
+        let name = name.trim();
+        if name.chars().count() > 64 {
+            anyhow::bail!("speaker names are at most 64 characters");
+        }
+        let event = CaptionEvent::SpeakerName {
+            id,
+            name: name.to_string(),
+        };
+        match self.tx.try_send(event) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("caption queue full; dropping speaker name");
+                anyhow::bail!("the engine is busy; try again")
+            }
+            Err(TrySendError::Disconnected(_)) => anyhow::bail!("the engine has stopped"),
+        }
+    }
+}
+
+/// The audio of the latest finals (16 kHz mono), by segment id, so a caption can be listened
+/// back to (`--segment-audio-s`). The oldest segments are dropped past the budget.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentAudio {
+    inner: Arc<parking_lot::Mutex<SegmentAudioState>>,
+}
+
+#[derive(Debug, Default)]
+struct SegmentAudioState {
+    max_samples: usize,
+    samples: usize,
+    segments: VecDeque<(u64, Vec<f32>)>,
+}
+
+impl SegmentAudio {
+    /// Keeps up to `seconds` of audio (0 keeps none).
+    pub fn new(seconds: u64) -> Self {
+        let store = Self::default();
+        store.inner.lock().max_samples = seconds as usize * 16_000;
+        store
+    }
+
+    pub fn insert(&self, segment_id: u64, audio: &[f32]) {
+        let mut state = self.inner.lock();
+        if audio.len() > state.max_samples {
+            return;
+        }
+        state.segments.retain(|(id, _)| *id != segment_id);
+        state.segments.push_back((segment_id, audio.to_vec()));
+        state.samples = state.segments.iter().map(|(_, audio)| audio.len()).sum();
+        while state.samples > state.max_samples {
+            let Some((_, oldest)) = state.segments.pop_front() else {
+                break;
+            };
+            state.samples -= oldest.len();
+        }
+    }
+
+    /// The audio of final `segment_id`, if it is still kept.
+    pub fn get(&self, segment_id: u64) -> Option<Vec<f32>> {
+        let state = self.inner.lock();
+        state
+            .segments
+            .iter()
+            .find(|(id, _)| *id == segment_id)
+            .map(|(_, audio)| audio.clone())
+    }
+}
+
+/// Manual corrections of finals. An edit goes through the caption pipeline as a `Revise`, so
+/// the `--record` subtitles, the `--report` and the recent-events history take the corrected
+/// text; the second pass revising that segment later doesn't undo it.
+#[derive(Debug, Clone)]
+pub struct SegmentEdits {
+    tx: Sender<CaptionEvent>,
+    edited: Arc<parking_lot::Mutex<BTreeMap<u64, String>>>,
+}
+
+impl SegmentEdits {
+    pub fn new(tx: Sender<CaptionEvent>) -> Self {
+        Self {
+            tx,
+            edited: Arc::default(),
+        }
+    }
+
+    /// Replaces final `segment_id`'s text with `text`. Fails rather than waits when the
+    /// caption queue is full, leaving the segment as it was.
+    pub fn edit(&self, segment_id: u64, text: &str) -> anyhow::Result<()> {
+        let text = text.trim();
+        if text.is_empty() {
+            anyhow::bail!("a corrected caption can't be empty");
+        }
+        let previous = self.edited.lock().insert(segment_id, text.to_string());
+        let event = CaptionEvent::Revise {
+            segment_id,
+            text: text.to_string(),
+        };
+        let Err(err) = self.tx.try_send(event) else {
+            return Ok(());
+        };
+        let mut edited = self.edited.lock();
+        match previous {
+            Some(previous) => edited.insert(segment_id, previous),
+            None => edited.remove(&segment_id),
+        };
+        match err {
+            TrySendError::Full(_) => {
+                tracing::warn!("caption queue full; dropping correction");
+                anyhow::bail!("the engine is busy; try again")
+            }
+            TrySendError::Disconnected(_) => anyhow::bail!("the engine has stopped"),
+        }
+    }
+
+    /// Whether `event` is one of these corrections, which `--script` leaves alone.
+    pub fn is_edit(&self, event: &CaptionEvent) -> bool {
+        match event {
+            CaptionEvent::Revise { segment_id, text } => {
+                self.edited.lock().get(segment_id) == Some(text)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `event` is an automatic revision of a segment that was corrected by hand.
+    pub fn overrides(&self, event: &CaptionEvent) -> bool {
+        match event {
+            CaptionEvent::Revise { segment_id, text } => self
+                .edited
+                .lock()
+                .get(segment_id)
+                .is_some_and(|edited| edited != text),
+            _ => false,
+        }
+    }
+}
+
+/// Labels finals from tinydiarize's speaker turns. A turn only says the voice changed, not to
+/// whom, so this assumes a conversation between two people and alternates between them.
+#[derive(Debug, Default)]
+pub struct SpeakerTurns {
+    current: u32,
+}
+
+impl SpeakerTurns {
+    /// The speaker of a final that contains `turns` speaker changes (see
+    /// `Transcript::speaker_turns`); it starts with whoever spoke last.
+    pub fn label(&mut self, turns: usize) -> Speaker {
+        let speaker = Speaker::new(self.current);
+        if turns % 2 == 1 {
+            self.current = 1 - self.current;
+        }
+        speaker
+    }
+}
+
+/// Labels finals with the `--voice-profiles` voice they sound like: enrolled voice `i` is
+/// speaker `i`, named after the person. Finals that match no one keep their `--diarize` label,
+/// numbered after the enrolled voices.
+#[derive(Debug)]
+pub struct VoiceSpeakers {
+    profiles: VoiceProfiles,
+    threshold: f32,
+}
+
+impl VoiceSpeakers {
+    pub fn new(profiles: VoiceProfiles, threshold: f32) -> Self {
+        Self {
+            profiles,
+            threshold,
+        }
+    }
+
+    /// Names for the enrolled voices' speaker ids, to send before any caption.
+    pub fn names(&self) -> Vec<CaptionEvent> {
+        (0u32..)
+            .zip(&self.profiles.voices)
+            .map(|(id, voice)| CaptionEvent::SpeakerName {
+                id,
+                name: voice.name.clone(),
+            })
+            .collect()
+    }
+
+    /// Who said the final in `audio` (16 kHz), given what `--diarize` made of it.
+    pub fn label(&self, audio: &[f32], diarized: Option<Speaker>) -> Option<Speaker> {
+        let enrolled = VoicePrint::from_samples(audio)
+            .and_then(|print| self.profiles.identify(&print, self.threshold));
+        match enrolled {
+            Some((index, _)) => Some(Speaker::new(index as u32)),
+            None => {
+                let enrolled = self.profiles.voices.len() as u32;
+                diarized.map(|speaker| Speaker::new(speaker.id + enrolled))
+            }
+        }
+    }
+}
+
+/// Engine lifecycle, so the UI can show first-run downloads instead of looking frozen.
+#[derive(Debug, Clone)]
+pub enum EngineStatus {
+    /// Resolving and loading the live transcription model.
+    Loading,
+    DownloadingModel(DownloadProgress),
+    /// The live model is loaded; captions will follow.
+    Ready,
+    /// No audible input for `--idle-after-min` (or the captured window/app closed). Captions
+    /// resume with `Ready` once audio returns.
+    Idle,
+    /// Captions were paused by a voice command; `Ready` follows "resume".
+    Paused,
+    /// The live model could not be loaded; the engine is shutting down.
+    Failed { kind: ErrorKind, message: String },
+    /// A newer release is out (`--check-updates`); doesn't change the engine's state.
+    UpdateAvailable(UpdateInfo),
+    /// Running, but worse than asked: e.g. a smaller cached model stood in for one that
+    /// couldn't be downloaded. Doesn't change the engine's state.
+    Warning(String),
+}
+
+impl CaptionEvent {
+    /// Caption text (as opposed to status and level events), which `--caption-delay-ms` holds.
+    pub fn is_caption(&self) -> bool {
+        matches!(
+            self,
+            CaptionEvent::Update { .. } | CaptionEvent::Revise { .. } | CaptionEvent::Clear
+        )
+    }
+}
+
+/// Caption events waiting out the display delay, oldest first. The delay is applied when they
+/// are popped, so changing it also moves captions that are already queued.
+///
+/// With a minimum display time, a caption that would replace a final shown less than that long
+/// ago waits for it, but never more than the minimum past its own due time, so a fast speaker
+/// can't build up an ever-growing lag.
+///
+/// With a reading speed, long finals are queued as a series of cues, each due once the one
+/// before it has had its reading time, and later captions wait for the last cue (by at most
+/// `MAX_READING_LAG`).
+#[derive(Debug, Default)]
+pub struct CaptionDelayQueue {
+    queue: VecDeque<(Instant, CaptionEvent)>,
+    min_display: Duration,
+    /// The final on screen and when it was shown.
+    shown_final: Option<(u64, Instant)>,
+    reading: Option<ReadingSpeed>,
+    /// When the last queued final has been read.
+    read_by: Option<Instant>,
+}
+
+/// How far reading-speed pacing may hold captions behind the speech before they catch up.
+const MAX_READING_LAG: Duration = Duration::from_secs(10);
+
+impl CaptionDelayQueue {
+    /// Keeps each final up for at least `min_display` before the next caption replaces it.
+    pub fn with_min_display(min_display: Duration) -> Self {
+        Self {
+            min_display,
+            ..Self::default()
+        }
+    }
+
+    /// Splits finals into cues of `speed` and paces captions so each can be read.
+    pub fn with_reading_speed(mut self, speed: Option<ReadingSpeed>) -> Self {
+        self.reading = speed;
+        self
+    }
+
+    pub fn push(&mut self, event: CaptionEvent, received: Instant) {
+        let Some(speed) = self.reading else {
+            self.queue.push_back((received, event));
+            return;
+        };
+        let start = self
+            .read_by
+            .map_or(received, |read_by| read_by.min(received + MAX_READING_LAG))
+            .max(received);
+        let CaptionEvent::Update {
+            segment_id,
+            text,
+            is_final: true,
+            timing,
+            speaker,
+            untranslated,
+            words,
+            ..
+        } = event
+        else {
+            self.queue.push_back((start, event));
+            return;
+        };
+
+        let (from_s, to_s) = timing.map_or((0.0, 0.0), |t| (t.start_s, t.end_s));
+        let chunks = speed.timed_chunks(&text, from_s, to_s);
+        let offset = |at_s: f64| Duration::from_secs_f64((at_s - from_s).max(0.0));
+        self.read_by = chunks.last().map(|last| start + offset(last.end_s));
+        if chunks.len() == 1 {
+            let event = CaptionEvent::Update {
+                segment_id,
+                text,
+                is_final: true,
+                timing,
+                speaker,
+                untranslated,
+                spans: Vec::new(),
+                words,
+            };
+            self.queue.push_back((start, event));
+            return;
+        }
+        for chunk in chunks {
+            let event = CaptionEvent::Update {
+                segment_id,
+                text: chunk.text,
+                is_final: true,
+                timing: timing.map(|_| CaptionTiming {
+                    start_s: chunk.start_s,
+                    end_s: chunk.end_s,
+                }),
+                speaker,
+                untranslated,
+                spans: Vec::new(),
+                words: Vec::new(),
+            };
+            self.queue.push_back((start + offset(chunk.start_s), event));
+        }
+    }
+
+    pub fn pop_due(&mut self, delay: Duration, now: Instant) -> Option<CaptionEvent> {
+        if self.next_due(delay)? > now {
+            return None;
+        }
+        let (_, event) = self.queue.pop_front()?;
+        match &event {
+            CaptionEvent::Update {
+                segment_id,
+                is_final: true,
+                ..
+            } if !self.min_display.is_zero() => self.shown_final = Some((*segment_id, now)),
+            CaptionEvent::Clear => self.shown_final = None,
+            _ => {}
+        }
+        Some(event)
+    }
+
+    /// When the oldest queued event is due.
+    pub fn next_due(&self, delay: Duration) -> Option<Instant> {
+        let (received, event) = self.queue.front()?;
+        let due = *received + delay;
+        let replaces_final = |(shown_id, _): &(u64, Instant)| match event {
+            CaptionEvent::Update { segment_id, .. } => segment_id != shown_id,
+            CaptionEvent::Clear => true,
+            _ => false,
+        };
+        match self.shown_final.as_ref().filter(|shown| replaces_final(shown)) {
+            Some((_, shown_at)) => {
+                let held = (*shown_at + self.min_display).min(due + self.min_display);
+                Some(due.max(held))
+            }
+            None => Some(due),
+        }
+    }
+}
+
+pub(crate) fn send_status(caption_tx: &Sender<CaptionEvent>, status: EngineStatus) {
+    if caption_tx.try_send(CaptionEvent::Status(status)).is_err() {
+        tracing::debug!("caption queue full; dropping status update");
+    }
 }
 
 pub struct EngineHandle {
     pub stop: Arc<AtomicBool>,
     pub output_language: SharedOutputLanguage,
+    /// Switches the capture target without restarting the engine.
+    pub capture: CaptureControl,
+    /// `--caption-delay-ms`, adjustable live.
+    pub caption_delay: SharedCaptionDelay,
+    /// `--partial-stable-iters` and `--commit-policy`, adjustable live.
+    pub stabilizer: SharedStabilizer,
+    /// The selected `--domain-model`; the local engine reloads when it changes.
+    pub domain: SharedDomainModel,
+    /// What has been shown lately, for frontends that reconnect (`--recent-events`).
+    pub recent: RecentEvents,
+    /// Names for `--diarize`'s speakers.
+    pub speaker_names: SpeakerNames,
+    /// Audio of the latest finals, for listening back.
+    pub segment_audio: SegmentAudio,
+    /// Manual corrections of finals.
+    pub segment_edits: SegmentEdits,
+    /// `--replace-rules`, which corrections can add to.
+    pub replace_rules: ReplaceRules,
     capture_handle: std::thread::JoinHandle<()>,
     processing_handle: std::thread::JoinHandle<()>,
     transcription_handle: std::thread::JoinHandle<()>,
+    delay_handle: std::thread::JoinHandle<()>,
+    /// Keeps other launches from capturing too; they hand their settings over instead.
+    instance: Option<InstanceLock>,
 }
 
 impl EngineHandle {
@@ -58,53 +765,104 @@ impl EngineHandle {
         let _ = self.capture_handle.join();
         let _ = self.processing_handle.join();
         let _ = self.transcription_handle.join();
+        let _ = self.delay_handle.join();
+        drop(self.instance);
     }
 }
 
-fn combine_committed_partial(committed: &str, partial: &str) -> String {
-    match (committed.trim().is_empty(), partial.trim().is_empty()) {
-        (true, true) => String::new(),
-        (false, true) => committed.trim().to_string(),
-        (true, false) => partial.trim().to_string(),
-        (false, false) => format!("{} {}", committed.trim(), partial.trim()),
-    }
+fn combine_committed_partial(joiner: TextJoiner, committed: &str, partial: &str) -> String {
+    joiner.format(&joiner.join(committed, partial))
 }
 
-fn normalize_for_compare(s: &str) -> String {
-    s.trim().to_lowercase()
+/// Cuts a partial's `display` into stable and pending spans: on each line, the longest prefix
+/// it shares with that line's formatted committed text is stable. Line breaks stay with the
+/// span before them.
+pub fn stability_spans(display: &str, committed: &[String]) -> Vec<CaptionSpan> {
+    let mut spans = Vec::new();
+    for (index, line) in display.split('\n').enumerate() {
+        if index > 0 {
+            let stable = spans.last().is_none_or(|last: &CaptionSpan| last.stable);
+            push_span(&mut spans, "\n", stable);
+        }
+        let committed = committed.get(index).map_or("", String::as_str);
+        let stable_len = line
+            .char_indices()
+            .zip(committed.chars())
+            .take_while(|((_, shown), kept)| shown == kept)
+            .last()
+            .map_or(0, |((at, c), _)| at + c.len_utf8());
+        push_span(&mut spans, &line[..stable_len], true);
+        push_span(&mut spans, &line[stable_len..], false);
+    }
+    spans
 }
 
-fn merge_bilingual(primary: &str, secondary: &str) -> String {
-    let primary = primary.trim();
-    let secondary = secondary.trim();
-    match (primary.is_empty(), secondary.is_empty()) {
-        (true, true) => String::new(),
-        (false, true) => primary.to_string(),
-        (true, false) => secondary.to_string(),
-        (false, false) => {
-            if normalize_for_compare(primary) == normalize_for_compare(secondary) {
-                primary.to_string()
-            } else {
-                format!("{primary}\n{secondary}")
-            }
-        }
+fn push_span(spans: &mut Vec<CaptionSpan>, text: &str, stable: bool) {
+    match spans.last_mut() {
+        _ if text.is_empty() => {}
+        Some(last) if last.stable == stable => last.text.push_str(text),
+        _ => spans.push(CaptionSpan {
+            text: text.to_string(),
+            stable,
+        }),
     }
 }
 
 fn transcribe_text(
     transcriber: &mut dyn Transcriber,
-    input_language: &Option<String>,
+    decode_cfg: &TranscriberConfig,
     output_language: OutputLanguage,
     is_partial: bool,
     audio: &[f32],
 ) -> Option<String> {
+    transcribe_detailed(transcriber, decode_cfg, output_language, is_partial, audio)
+        .map(|transcript| transcript.text)
+}
+
+fn transcribe_detailed(
+    transcriber: &mut dyn Transcriber,
+    decode_cfg: &TranscriberConfig,
+    output_language: OutputLanguage,
+    is_partial: bool,
+    audio: &[f32],
+) -> Option<Transcript> {
     let cfg = TranscriberConfig {
-        input_language: input_language.clone(),
         output_language,
         is_partial,
+        segment_timestamps: false,
+        ..decode_cfg.clone()
     };
-    match transcriber.transcribe(audio, &cfg) {
-        Ok(text) => Some(text),
+    run_transcriber(transcriber, &cfg, audio)
+}
+
+/// Partial decode that also returns timed segments, for endpointing.
+fn transcribe_partial_segments(
+    transcriber: &mut dyn Transcriber,
+    decode_cfg: &TranscriberConfig,
+    output_language: OutputLanguage,
+    audio: &[f32],
+) -> Option<Transcript> {
+    let cfg = TranscriberConfig {
+        output_language,
+        is_partial: true,
+        segment_timestamps: true,
+        ..decode_cfg.clone()
+    };
+    run_transcriber(transcriber, &cfg, audio)
+}
+
+fn run_transcriber(
+    transcriber: &mut dyn Transcriber,
+    cfg: &TranscriberConfig,
+    audio: &[f32],
+) -> Option<Transcript> {
+    match transcriber.transcribe_detailed(audio, cfg) {
+        // The glossary's translations don't apply to text that wasn't translated.
+        Ok(transcript) if transcript.untranslated => Some(transcript),
+        Ok(transcript) => Some(Transcript {
+            text: cfg.post_edit(transcript.text),
+            ..transcript
+        }),
         Err(err) => {
             tracing::warn!("transcription failed: {err:#}");
             None
@@ -112,26 +870,103 @@ fn transcribe_text(
     }
 }
 
-fn maybe_send_update(
-    caption_tx: &Sender<CaptionEvent>,
-    last_caption: &mut String,
-    last_final: &mut bool,
+/// The last caption sent, so a decode that changed nothing isn't sent again.
+struct LastCaption {
     text: String,
     is_final: bool,
+}
+
+impl LastCaption {
+    fn new() -> Self {
+        Self {
+            text: String::new(),
+            is_final: true,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+        self.is_final = true;
+    }
+}
+
+fn maybe_send_partial(
+    caption_tx: &Sender<CaptionEvent>,
+    last: &mut LastCaption,
+    segment_id: u64,
+    text: String,
+    spans: Vec<CaptionSpan>,
 ) {
-    if text != *last_caption || is_final != *last_final {
-        *last_caption = text.clone();
-        *last_final = is_final;
-        if caption_tx
-            .try_send(CaptionEvent::Update { text, is_final })
-            .is_err()
-        {
+    let update = CaptionEvent::Update {
+        segment_id,
+        text,
+        is_final: false,
+        timing: None,
+        speaker: None,
+        untranslated: false,
+        spans,
+        words: Vec::new(),
+    };
+    maybe_send_update(caption_tx, last, update);
+}
+
+/// Share of a final's audio that must sound like two voices at once to mark it.
+const CROSSTALK_MARK: f32 = 0.2;
+
+fn maybe_send_final(
+    caption_tx: &Sender<CaptionEvent>,
+    last: &mut LastCaption,
+    segment_id: u64,
+    text: String,
+    timed: Option<(CaptionTiming, Vec<CaptionWord>)>,
+    speaker: Option<Speaker>,
+    untranslated: bool,
+) {
+    let (timing, words) = timed.unzip();
+    let update = CaptionEvent::Update {
+        segment_id,
+        text,
+        is_final: true,
+        timing,
+        speaker,
+        untranslated,
+        spans: Vec::new(),
+        words: words.unwrap_or_default(),
+    };
+    maybe_send_update(caption_tx, last, update);
+}
+
+/// Sends `update` unless it repeats the last one.
+fn maybe_send_update(
+    caption_tx: &Sender<CaptionEvent>,
+    last: &mut LastCaption,
+    update: CaptionEvent,
+) {
+    let CaptionEvent::Update { text, is_final, .. } = &update else {
+        return;
+    };
+    if *text != last.text || *is_final != last.is_final {
+        last.text = text.clone();
+        last.is_final = *is_final;
+        if caption_tx.try_send(update).is_err() {
             tracing::warn!("caption queue full; dropping update");
         }
     }
 }
 
-pub fn start_engine(cli: Cli, caption_tx: Sender<CaptionEvent>) -> anyhow::Result<EngineHandle> {
+/// Starts capture and transcription. Failures that happen later (the model failing to load)
+/// arrive as `EngineStatus::Failed` with the same classification.
+pub fn start_engine(
+    cli: Cli,
+    caption_tx: Sender<CaptionEvent>,
+) -> Result<EngineHandle, SubtitlesError> {
+    try_start_engine(cli, caption_tx).map_err(SubtitlesError::from)
+}
+
+fn try_start_engine(
+    mut cli: Cli,
+    caption_tx: Sender<CaptionEvent>,
+) -> anyhow::Result<EngineHandle> {
     #[cfg(not(target_os = "macos"))]
     {
         anyhow::bail!("This MVP only supports macOS for now.");
@@ -139,16 +974,301 @@ pub fn start_engine(cli: Cli, caption_tx: Sender<CaptionEvent>) -> anyhow::Resul
 
     #[cfg(target_os = "macos")]
     {
+        crate::network::set_local_only(cli.local_only);
+        if let Some(path) = cli.audit_log.as_deref() {
+            crate::network::enable_audit_log(path)?;
+        }
+        set_model_mirrors(cli.model_mirror.clone());
+        set_gpu_memory_limit_mb(cli.gpu_memory_limit_mb);
+        set_word_alignment(cli.word_timings);
+        if let Some(limit_mb) = cli.gpu_memory_limit_mb {
+            // Models loaded side by side share the limit: the live model (or a local shadow;
+            // battery and domain models replace it) first, then the second pass gets the rest.
+            let mut left_mb = limit_mb;
+            if cli.engine == Engine::Local || cli.shadow_engine == Some(Engine::Local) {
+                if cli.whisper_model.is_none() && cli.whisper_custom_model.is_none() {
+                    fit_gpu_memory_limit(&mut cli.whisper_model_preset, left_mb, &caption_tx);
+                }
+                if cli.battery_saver {
+                    fit_gpu_memory_limit(&mut cli.battery_model_preset, left_mb, &caption_tx);
+                }
+                left_mb = left_mb.saturating_sub(live_gpu_memory_mb(&cli));
+            }
+            if let (None, Some(preset)) = (&cli.second_pass_model, &mut cli.second_pass_preset) {
+                fit_gpu_memory_limit(preset, left_mb, &caption_tx);
+            }
+        }
+        let custom_model = match cli.whisper_custom_model.as_deref() {
+            Some(name) => Some(
+                cli.custom_model
+                    .iter()
+                    .find(|model| model.name.eq_ignore_ascii_case(name))
+                    .cloned()
+                    .with_context(|| format!("no --custom-model is named `{name}`"))?,
+            ),
+            None => None,
+        };
+        if cli.local_only && matches!(cli.engine, Engine::OpenAI) {
+            anyhow::bail!(
+                "--engine openai uploads audio to the cloud and cannot be used with --local-only"
+            );
+        }
+        if cli.ensemble && (cli.local_only || cli.engine != Engine::Local) {
+            anyhow::bail!(
+                "--ensemble runs OpenAI alongside --engine local; it can't be used with \
+                 another engine or --local-only"
+            );
+        }
+        if cli.code_switching && cli.language_pair.is_empty() {
+            anyhow::bail!("--code-switching needs --language-pair");
+        }
+        if cli.adaptive_asr_step && cli.asr_step_min_ms > cli.asr_step_max_ms {
+            anyhow::bail!("--asr-step-min-ms must not exceed --asr-step-max-ms");
+        }
+        let vad_hop_ms = cli.vad_hop_ms.unwrap_or(cli.vad_frame_ms);
+        if cli.vad_frame_ms == 0 || vad_hop_ms == 0 || vad_hop_ms > cli.vad_frame_ms {
+            anyhow::bail!("--vad-hop-ms must be between 1 and --vad-frame-ms");
+        }
+        if !cli.language_pair.is_empty() {
+            if cli.engine != Engine::Local {
+                anyhow::bail!("--language-pair needs --engine local");
+            }
+            if !cli.input_language.trim().eq_ignore_ascii_case("auto") {
+                tracing::warn!("--input-language is ignored with --language-pair");
+            }
+        }
+        for engine in [Some(&cli.engine), cli.shadow_engine.as_ref()].into_iter().flatten() {
+            if let Engine::Custom(name) = engine {
+                engine_registry::custom_factory(name)?;
+            }
+        }
+        if let Some(shadow) = &cli.shadow_engine {
+            if shadow == &cli.engine {
+                anyhow::bail!("--shadow-engine must differ from --engine");
+            }
+            if cli.local_only && matches!(shadow, Engine::OpenAI) {
+                anyhow::bail!("--shadow-engine openai cannot be used with --local-only");
+            }
+            if cli.ensemble {
+                anyhow::bail!("--shadow-engine can't be combined with --ensemble");
+            }
+        } else if cli.shadow_log.is_some() {
+            tracing::warn!("--shadow-log has no effect without --shadow-engine");
+        }
+        let engine_caps = cli.engine.capabilities();
+        if cli.output_language != OutputLanguage::Chinese && !engine_caps.translation {
+            anyhow::bail!(
+                "the {} engine can't translate; use --output-language chinese",
+                cli.engine
+            );
+        }
+        if cli.diarize && !engine_caps.speaker_labels {
+            tracing::warn!(
+                "--diarize is ignored: the {} engine doesn't mark speakers",
+                cli.engine
+            );
+        }
+        if cli.crash_reports || cli.crash_report_endpoint.is_some() {
+            if cli.local_only && cli.crash_report_endpoint.is_some() {
+                anyhow::bail!("--crash-report-endpoint cannot be used with --local-only");
+            }
+            match crate::crash::default_report_dir() {
+                Some(dir) => crate::crash::enable(ReporterConfig {
+                    dir,
+                    endpoint: cli.crash_report_endpoint.clone(),
+                })?,
+                None => tracing::warn!("HOME is not set; crash reports are off"),
+            }
+        }
+
+        // Release checks carry no user data, but privacy mode means no unexpected calls.
+        if cli.check_updates && !cli.local_only {
+            spawn_update_check(cli.update_feed.clone(), caption_tx.clone());
+        }
+
+        let capabilities = Capabilities::detect();
+        // Sources that don't capture from this machine bypass the macOS checks.
+        let external = match (&cli.stream_url, &cli.stdin_pcm) {
+            (Some(_), Some(_)) => anyhow::bail!("pick one of --stream-url and --stdin-pcm"),
+            (Some(_), None) => Some(("--stream-url", "stream", CaptureBackend::Stream)),
+            (None, Some(_)) => Some(("--stdin-pcm", "stdin", CaptureBackend::Stdin)),
+            (None, None) => None,
+        };
+        let backend = match external {
+            Some((flag, name, backend)) => {
+                if cli.input_device.is_some() {
+                    anyhow::bail!("{flag} can't be combined with --input-device");
+                }
+                if !matches!(cli.capture_backend, CaptureBackend::Auto)
+                    && cli.capture_backend != backend
+                {
+                    anyhow::bail!("{flag} needs --capture-backend auto or {name}");
+                }
+                if cli.record.is_some() {
+                    anyhow::bail!("--record needs ScreenCaptureKit capture; drop {flag}");
+                }
+                backend
+            }
+            None => capabilities
+                .choose(
+                    cli.capture_backend,
+                    cli.input_device.as_deref(),
+                    cli.record.is_some(),
+                )
+                .map_err(SubtitlesError::Capture)?,
+        };
+        if let Some(version) = capabilities.version {
+            tracing::info!("macOS {version}; capturing with {backend:?}");
+        }
+
+        let glossary = match cli.glossary.as_deref() {
+            Some(path) => Some(Arc::new(Glossary::load(path)?)),
+            None => None,
+        };
+        let mut translation_memory = match cli.translation_memory_file.as_deref() {
+            Some(path) => Some(TranslationMemory::open(path)?),
+            None => cli.translation_memory.then(TranslationMemory::new),
+        };
+        let domain = SharedDomainModel::new(cli.domain_model.clone(), cli.domain.as_deref())?;
+        for model in domain.models() {
+            let info = model.validate().map_err(SubtitlesError::ModelLoad)?;
+            tracing::info!(
+                "domain model {}: {} ({})",
+                model.name,
+                model.path.display(),
+                info.architecture()
+            );
+            if !info.multilingual() && !cli.input_language.trim().eq_ignore_ascii_case("en") {
+                tracing::warn!(
+                    "domain model `{}` is English-only; it can't transcribe or translate other \
+                     languages",
+                    model.name
+                );
+            }
+        }
+        let voice_commands = if cli.voice_commands || cli.voice_command_file.is_some() {
+            let spotter = CommandSpotter::new(&cli.wake_word)?;
+            Some(match cli.voice_command_file.as_deref() {
+                Some(path) => spotter.with_phrases_file(path)?,
+                None => spotter,
+            })
+        } else {
+            None
+        };
+        let replace_rules = match cli.replace_rules.as_deref() {
+            Some(path) => ReplaceRules::load(path)?,
+            None => ReplaceRules::default(),
+        };
+        let filter_registry = text_filter_registry(&cli, replace_rules.clone());
+        // Built up front so a misspelled stage name fails at startup.
+        let mut final_filters = filter_registry.build(&cli.text_filters)?;
+
+        let instance = match runtime_dir() {
+            Some(dir) => Some(InstanceLock::acquire(&dir)?.with_context(|| {
+                format!(
+                    "another subtitles engine is already capturing; stop it first (a second \
+                     launch with {} changes it)",
+                    Handoff::flag_list()
+                )
+            })?),
+            None => None,
+        };
+
         let stop = Arc::new(AtomicBool::new(false));
         let output_language = SharedOutputLanguage::new(cli.output_language);
 
-        let (audio_tx, audio_rx) = crossbeam_channel::bounded::<Vec<f32>>(256);
-        let (event_tx, event_rx) = crossbeam_channel::bounded::<StreamingEvent>(32);
+        // Everything below sends to the delay stage, which forwards to the caller's channel.
+        let caption_delay = SharedCaptionDelay::new(Duration::from_millis(cli.caption_delay_ms));
+        let stabilizer = SharedStabilizer::new(cli.partial_stable_iters, cli.commit_policy);
+        let (delayed_tx, delayed_rx) = crossbeam_channel::bounded::<CaptionEvent>(64);
+        let reading = ReadingSpeed::new(cli.reading_cps);
+        let srt = match cli.record.as_deref() {
+            Some(video) => {
+                let path = video.with_extension("srt");
+                let sound_tags =
+                    SoundTagFilter::new(cli.transcript_sound_tags, cli.sound_tag_glyph.clone());
+                Some(SrtWriter::create(&path, sound_tags)?.with_reading_speed(reading))
+            }
+            None => None,
+        };
+        let ass = match cli.record.as_deref().filter(|_| cli.record_ass) {
+            Some(video) => {
+                let style = AssStyle {
+                    theme: cli.caption_theme.style().with_box(&CaptionBox::from_cli(&cli)?),
+                    font_size: cli.font_size,
+                    width_frac: cli.overlay_width_frac,
+                };
+                let sound_tags =
+                    SoundTagFilter::new(cli.transcript_sound_tags, cli.sound_tag_glyph.clone());
+                let path = video.with_extension("ass");
+                Some(AssWriter::create(&path, sound_tags, &style)?.with_reading_speed(reading))
+            }
+            None => None,
+        };
+        let recent = RecentEvents::new(cli.recent_events);
+        let segment_edits = SegmentEdits::new(delayed_tx.clone());
+        let taps = CaptionTaps {
+            script: cli.script.as_deref().map(CaptionScript::load).transpose()?,
+            srt,
+            ass,
+            report: cli.report.as_deref().map(SessionReport::create).transpose()?,
+            stats: (cli.stats_interval_ms > 0).then(|| {
+                let interval = Duration::from_millis(cli.stats_interval_ms);
+                (StatsTracker::new(Instant::now()), interval)
+            }),
+            recent: recent.clone(),
+            stabilizer: stabilizer.clone(),
+            edits: segment_edits.clone(),
+            speech: cli.speak.then(|| {
+                let command = say_command(cli.speak_voice.as_deref(), cli.speak_device.as_deref());
+                SpeechSink::start(command, output_language.clone())
+            }),
+            braille: if cli.braille {
+                Some(BrailleSink::start(
+                    BrlApiHost::parse(&cli.braille_host)?,
+                    cli.braille_key.clone(),
+                    SoundTagFilter::new(cli.overlay_sound_tags, cli.sound_tag_glyph.clone()),
+                ))
+            } else {
+                None
+            },
+        };
+        let delay_handle = spawn_caption_delay(
+            delayed_rx,
+            caption_tx,
+            caption_delay.clone(),
+            Duration::from_millis(cli.min_display_ms),
+            reading,
+            taps,
+            stop.clone(),
+        );
+        let speaker_names = SpeakerNames {
+            tx: delayed_tx.clone(),
+        };
+        let segment_audio = SegmentAudio::new(cli.segment_audio_s);
+        let segment_audio_for_worker = segment_audio.clone();
+        let voices = match cli.voice_profiles.as_deref() {
+            Some(path) => {
+                let voices = VoiceSpeakers::new(VoiceProfiles::load(path)?, cli.voice_match);
+                for event in voices.names() {
+                    let _ = delayed_tx.try_send(event);
+                }
+                Some(voices)
+            }
+            None => None,
+        };
+        let caption_tx = delayed_tx;
+
+        let (audio_tx, audio_rx) = crossbeam_channel::bounded::<AudioChunk>(256);
+        // Events carry where their audio starts, in samples since capture started.
+        let (event_tx, event_rx) = crossbeam_channel::bounded::<(StreamingEvent, Option<u64>)>(32);
 
-        let streaming_enabled = cli.streaming && matches!(cli.engine, Engine::Local);
-        if cli.streaming && matches!(cli.engine, Engine::OpenAI) {
+        let streaming_enabled = cli.streaming && engine_caps.streaming_partials;
+        if cli.streaming && !engine_caps.streaming_partials {
             tracing::warn!(
-                "streaming partials are disabled for OpenAI engine; use --streaming=false to silence"
+                "streaming partials are disabled for the {} engine; use --streaming=false to \
+                 silence",
+                cli.engine
             );
         }
 
@@ -167,42 +1287,163 @@ pub fn start_engine(cli: Cli, caption_tx: Sender<CaptionEvent>) -> anyhow::Resul
             max_segment_s: cli.max_segment_s,
             pre_roll_s: cli.pre_roll_s,
             min_speech_ms: cli.min_speech_ms,
+            partial_onset_ms: cli.partial_onset_ms,
             asr_step_ms: cli.asr_step_ms,
             max_window_s: cli.max_window_s,
+            partial_overlap_s: cli.partial_overlap_s,
+            final_interval_s: cli.final_interval_s,
+        };
+
+        let partial_gate = PartialGate::new();
+        let partial_gate_for_processing = partial_gate.clone();
+
+        let power = cli.battery_saver.then(|| PowerWatch::spawn(stop.clone()));
+        let starts_on_battery = power.as_ref().is_some_and(PowerWatch::on_battery);
+        let power_for_processing = power.clone();
+        let power_for_worker = power;
+        let battery_asr_step_ms = cli.battery_asr_step_ms;
+        // Set by the transcription thread with `--adaptive-asr-step`, applied on AC power.
+        let asr_step_ms = Arc::new(AtomicU64::new(cli.asr_step_ms));
+        let asr_step_for_worker = asr_step_ms.clone();
+        let mut asr_step_controller = cli.adaptive_asr_step.then(|| {
+            AsrStepController::new(cli.asr_step_ms, cli.asr_step_min_ms, cli.asr_step_max_ms)
+        });
+
+        let level_block_samples = (cli.level_interval_ms * 16) as usize;
+        let mut level_meter =
+            (level_block_samples > 0).then(|| LevelMeter::new(level_block_samples));
+        let level_tx = caption_tx.clone();
+        let mut report_levels = move |chunk: &[f32], on_battery: bool| {
+            if let Some(meter) = level_meter.as_mut() {
+                meter.set_block_len(if on_battery {
+                    level_block_samples * BATTERY_LEVEL_SLOWDOWN
+                } else {
+                    level_block_samples
+                });
+                for level in meter.push(chunk) {
+                    // Meter updates are disposable; never block or warn on a full queue.
+                    let _ = level_tx.try_send(CaptionEvent::Level(level));
+                }
+            }
+        };
+
+        let capture = CaptureControl::new(cli.capture_target.clone())
+            .with_excluded_apps(cli.exclude_app.clone())
+            .with_recording(cli.record.clone())
+            .with_sample_rate(cli.capture_sample_rate);
+
+        if let Some(instance) = &instance {
+            let controls = LiveControls {
+                output_language: output_language.clone(),
+                capture: capture.clone(),
+                caption_delay: caption_delay.clone(),
+                stabilizer: stabilizer.clone(),
+                domain: domain.clone(),
+            };
+            instance.serve(controls, stop.clone())?;
+        }
+
+        let idle = Arc::new(AtomicBool::new(false));
+        let idle_for_processing = idle.clone();
+        let capture_for_processing = capture.clone();
+        let idle_timeout = Duration::from_secs_f32(cli.idle_after_min.max(0.0) * 60.0);
+        let mut idle_detector = (cli.idle_after_min > 0.0)
+            .then(|| IdleDetector::new(cli.vad_threshold, idle_timeout, Instant::now()));
+        let mut watch_idle = move |chunk: Option<&[f32]>| {
+            let Some(detector) = idle_detector.as_mut() else {
+                return;
+            };
+            let source_gone = capture_for_processing.source_gone();
+            if let Some(now_idle) = detector.update(chunk, source_gone, Instant::now()) {
+                tracing::info!("{}", if now_idle { "going idle" } else { "audio is back" });
+                idle_for_processing.store(now_idle, Ordering::Relaxed);
+            }
         };
 
+        // Sources tag their chunks, so any that arrive at another rate or channel count are
+        // converted here rather than misread as 16 kHz mono.
+        let mut to_asr = AsrConverter::default();
+        let mut preprocess = PreprocessChain::new(&cli.preprocess, 16_000);
+        if !preprocess.is_empty() {
+            let stages: Vec<String> = cli.preprocess.iter().map(ToString::to_string).collect();
+            tracing::info!("preprocessing: {}", stages.join(" → "));
+        }
+
+        let loudness_gate = cli.vad_loudness_lufs.map(|lufs| LoudnessGate::new(lufs, 16_000));
+        let vad_frame_ms = cli.vad_frame_ms;
+        let warm_context_s = cli.warm_context_s;
+
         let stop_processing = stop.clone();
         let processing_handle = std::thread::spawn(move || {
             if streaming_enabled {
-                let mut segmenter = StreamingSegmenter::new(streaming_cfg);
+                let mut segmenter = StreamingSegmenter::new(streaming_cfg)
+                    .with_partial_gate(partial_gate_for_processing.clone())
+                    .with_vad_frames(vad_frame_ms, vad_hop_ms)
+                    .with_warm_partials(warm_context_s);
+                if let Some(gate) = loudness_gate {
+                    segmenter = segmenter.with_loudness_gate(gate);
+                }
+                let mut step_ms = streaming_cfg.asr_step_ms;
                 while !stop_processing.load(Ordering::Relaxed) {
                     match audio_rx.recv_timeout(Duration::from_millis(50)) {
                         Ok(chunk) => {
+                            let mut chunk = to_asr.convert(chunk);
+                            preprocess.process(&mut chunk);
+                            let on_battery = running_on_battery(&power_for_processing);
+                            let wanted_step_ms = if on_battery {
+                                battery_asr_step_ms
+                            } else {
+                                asr_step_ms.load(Ordering::Relaxed)
+                            };
+                            if wanted_step_ms != step_ms {
+                                step_ms = wanted_step_ms;
+                                segmenter.set_asr_step_ms(step_ms);
+                            }
+                            report_levels(&chunk, on_battery);
+                            watch_idle(Some(&chunk));
                             for event in segmenter.push_audio(&chunk) {
-                                if event_tx.try_send(event).is_err() {
+                                let is_partial = matches!(event, StreamingEvent::Partial(_));
+                                let start = match event {
+                                    StreamingEvent::Final(_) => segmenter.take_final_start(),
+                                    StreamingEvent::Partial(_) => segmenter.take_partial_start(),
+                                    StreamingEvent::Reset => None,
+                                };
+                                if event_tx.try_send((event, start)).is_err() {
                                     tracing::warn!("segment queue full; dropping event");
+                                    if is_partial {
+                                        partial_gate_for_processing.release();
+                                    }
                                 }
                             }
                         }
-                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => watch_idle(None),
                         Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
                     }
                 }
             } else {
-                let mut segmenter = Segmenter::new(segmenter_cfg);
+                let mut segmenter =
+                    Segmenter::new(segmenter_cfg).with_vad_frames(vad_frame_ms, vad_hop_ms);
+                if let Some(gate) = loudness_gate {
+                    segmenter = segmenter.with_loudness_gate(gate);
+                }
                 while !stop_processing.load(Ordering::Relaxed) {
                     match audio_rx.recv_timeout(Duration::from_millis(50)) {
                         Ok(chunk) => {
+                            let mut chunk = to_asr.convert(chunk);
+                            preprocess.process(&mut chunk);
+                            report_levels(&chunk, running_on_battery(&power_for_processing));
+                            watch_idle(Some(&chunk));
                             for segment in segmenter.push_audio(&chunk) {
+                                let start = segmenter.take_segment_start();
                                 if event_tx
-                                    .try_send(StreamingEvent::Final(segment))
+                                    .try_send((StreamingEvent::Final(segment.into()), start))
                                     .is_err()
                                 {
                                     tracing::warn!("segment queue full; dropping segment");
                                 }
                             }
                         }
-                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => watch_idle(None),
                         Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
                     }
                 }
@@ -214,47 +1455,324 @@ pub fn start_engine(cli: Cli, caption_tx: Sender<CaptionEvent>) -> anyhow::Resul
         } else {
             Some(cli.input_language.trim().to_string())
         };
+        // Settings shared by every decode; the transcription thread fills in the rest per call.
+        let decode_cfg = TranscriberConfig {
+            input_language,
+            output_language: cli.output_language,
+            is_partial: false,
+            segment_timestamps: false,
+            prompt: cli.prompt.clone().filter(|prompt| !prompt.trim().is_empty()),
+            glossary,
+            diarize: cli.diarize && engine_caps.speaker_labels,
+        };
 
-        let mut transcriber: Box<dyn Transcriber> = match cli.engine.clone() {
-            Engine::Local => Box::new(
-                WhisperLocalTranscriber::new(
-                    cli.whisper_model.clone(),
-                    cli.whisper_model_preset.clone(),
-                    cli.whisper_threads,
-                )
-                .context("failed to initialize local whisper")?,
-            ),
-            Engine::OpenAI => Box::new(
-                OpenAiTranscriber::new(
-                    cli.openai_api_key.clone(),
-                    cli.openai_model.clone(),
-                    cli.openai_endpoint.clone(),
-                    cli.openai_translation_endpoint.clone(),
-                )
-                .context("failed to initialize OpenAI transcriber")?,
-            ),
+        let openai = || {
+            OpenAiTranscriber::new(
+                cli.openai_api_key.clone(),
+                cli.openai_model.clone(),
+                cli.openai_endpoint.clone(),
+                cli.openai_translation_endpoint.clone(),
+            )
+            .context("failed to initialize OpenAI transcriber")
+        };
+        // Fails fast on a missing key rather than on the first final.
+        let ensemble_cloud = cli.ensemble.then(openai).transpose()?;
+
+        // Whether battery saver swaps the model for `--battery-model-preset`: models picked by
+        // hand (a file, a custom or domain model) stay unless `--battery-replace-model`.
+        let battery_swaps_model = {
+            let replace = cli.battery_replace_model;
+            let hand_picked = cli.whisper_model.is_some() || custom_model.is_some();
+            move |domain: &Option<DomainModel>| replace || (!hand_picked && domain.is_none())
+        };
+        let local_loader = {
+            let model = cli.whisper_model.clone();
+            let preset = cli.whisper_model_preset.clone();
+            // Never a bigger model on battery than on AC.
+            let battery_preset = if preset_gpu_memory_mb(&cli.battery_model_preset)
+                < preset_gpu_memory_mb(&preset)
+            {
+                cli.battery_model_preset.clone()
+            } else {
+                preset.clone()
+            };
+            let language_pair = <[String; 2]>::try_from(cli.language_pair.clone()).ok();
+            let code_switching = cli.code_switching;
+            let threads = cli.whisper_threads;
+            let status_tx = caption_tx.clone();
+            // The battery preset on battery (see `battery_swaps_model`), else the domain model if
+            // one is selected, `--whisper-model`, the custom model or the preset.
+            move |domain: Option<DomainModel>, on_battery: bool| -> TranscriberLoader {
+                let (model, custom, preset) = if on_battery && battery_swaps_model(&domain) {
+                    (None, None, battery_preset.clone())
+                } else {
+                    (
+                        domain.map(|domain| domain.path).or_else(|| model.clone()),
+                        custom_model.clone(),
+                        preset.clone(),
+                    )
+                };
+                let cloud = ensemble_cloud.clone();
+                let language_pair = language_pair.clone();
+                let status_tx = status_tx.clone();
+                Box::new(move |on_progress: &mut dyn FnMut(DownloadProgress)| {
+                    let model = match (model, custom) {
+                        (None, Some(custom)) => {
+                            Some(resolve_custom_model_path(&custom, on_progress)?)
+                        }
+                        (model, _) => model,
+                    };
+                    let transcriber =
+                        load_local_whisper(model, preset, threads, on_progress, &status_tx)?;
+                    if let Some(languages) = language_pair {
+                        // Same model, second decoding state: no extra GPU memory for weights.
+                        let second = transcriber.fork()?;
+                        return Ok(Box::new(
+                            LanguagePairTranscriber::new(
+                                languages,
+                                Box::new(transcriber),
+                                Box::new(second),
+                            )
+                            .with_code_switching(code_switching),
+                        ));
+                    }
+                    let transcriber: Box<dyn Transcriber> = Box::new(transcriber);
+                    Ok(match cloud {
+                        Some(cloud) => {
+                            Box::new(EnsembleTranscriber::new(transcriber, Box::new(cloud)))
+                        }
+                        None => transcriber,
+                    })
+                })
+            }
+        };
+
+        let load_transcriber: TranscriberLoader = match cli.engine.clone() {
+            Engine::Local => local_loader(domain.selected(), starts_on_battery),
+            Engine::OpenAI => {
+                // Nothing to download; fail fast on a missing key.
+                let transcriber = openai()?;
+                Box::new(move |_: &mut dyn FnMut(DownloadProgress)| {
+                    Ok(Box::new(transcriber) as Box<dyn Transcriber>)
+                })
+            }
+            Engine::Custom(name) => custom_loader(&name)?,
+        };
+
+        let second_pass = if cli.second_pass_model.is_some() || cli.second_pass_preset.is_some() {
+            let preset = cli
+                .second_pass_preset
+                .clone()
+                .unwrap_or(WhisperModelPreset::LargeV3);
+            let model = cli.second_pass_model.clone();
+            let threads = cli.whisper_threads;
+            Some(SecondPass::spawn(
+                Box::new(move |on_progress: &mut dyn FnMut(DownloadProgress)| {
+                    let transcriber =
+                        WhisperLocalTranscriber::new_with_progress(model, preset, threads, on_progress)
+                            .context("failed to initialize second-pass whisper")?;
+                    Ok(Box::new(transcriber) as Box<dyn Transcriber>)
+                }),
+                decode_cfg.clone(),
+                filter_registry.build(&cli.text_filters)?,
+                voice_commands.clone(),
+                cli.second_pass_max_confidence,
+                caption_tx.clone(),
+                stop.clone(),
+            ))
+        } else {
+            None
+        };
+
+        let shadow = match cli.shadow_engine {
+            Some(engine) => {
+                let load: TranscriberLoader = match &engine {
+                    Engine::Local => local_loader(domain.selected(), false),
+                    Engine::OpenAI => {
+                        let transcriber = openai()?;
+                        Box::new(move |_: &mut dyn FnMut(DownloadProgress)| {
+                            Ok(Box::new(transcriber) as Box<dyn Transcriber>)
+                        })
+                    }
+                    Engine::Custom(name) => custom_loader(name)?,
+                };
+                Some(ShadowEngine::spawn(
+                    engine.to_string(),
+                    load,
+                    decode_cfg.clone(),
+                    filter_registry.build(&cli.text_filters)?,
+                    cli.shadow_log.as_deref(),
+                    stop.clone(),
+                )?)
+            }
+            None => None,
         };
 
-        let capture_handle = start_macos_system_audio_capture(audio_tx, stop.clone())
-            .context("failed to start ScreenCaptureKit audio capture")?;
+        if cli.idle_unload_model && cli.engine != Engine::Local {
+            tracing::warn!("--idle-unload-model only applies to the local engine");
+        }
+        if !cli.domain_model.is_empty() && cli.engine != Engine::Local {
+            tracing::warn!("--domain-model only applies to the local engine");
+        }
+        let idle_unload = cli.idle_unload_model && matches!(cli.engine, Engine::Local);
+        // The transcription thread reloads the model after idling and on domain switches.
+        let reload_transcriber = matches!(cli.engine, Engine::Local).then_some(local_loader);
+        let domain_for_worker = domain.clone();
+
+        let capture_handle = match (backend, cli.input_device.as_deref()) {
+            (CaptureBackend::Device, Some(device)) => {
+                start_device_capture(device, audio_tx, stop.clone())
+                    .context("failed to start audio device capture")
+                    .map_err(SubtitlesError::capture)?
+            }
+            (CaptureBackend::ProcessTap, _) => {
+                start_process_tap_capture(audio_tx, stop.clone(), capture.clone())
+                    .context("failed to start process tap capture")
+                    .map_err(SubtitlesError::capture)?
+            }
+            (CaptureBackend::Stream, _) => {
+                let url = cli.stream_url.as_deref().unwrap_or_default();
+                start_stream_capture(url, &cli.ffmpeg, audio_tx, stop.clone())
+                    .context("failed to start stream capture")
+                    .map_err(SubtitlesError::capture)?
+            }
+            (CaptureBackend::Stdin, _) => match cli.stdin_pcm {
+                Some(format) => start_stdin_capture(format, audio_tx, stop.clone()),
+                None => anyhow::bail!("--capture-backend stdin needs --stdin-pcm <format>"),
+            },
+            _ => start_macos_system_audio_capture(audio_tx, stop.clone(), capture.clone())
+                .context("failed to start ScreenCaptureKit audio capture")
+                .map_err(SubtitlesError::capture)?,
+        };
 
         let output_language_for_worker = output_language.clone();
         let stop_transcribe = stop.clone();
         let partial_stable_iters = cli.partial_stable_iters;
+        let stabilizer_tuning = stabilizer.clone();
+        let timestamp_endpointing = cli.timestamp_endpointing && engine_caps.word_timestamps;
+        let max_committed_tokens = cli.max_committed_tokens;
+        let warm_partials = cli.warm_context_s > 0.0;
+        let keep_committed_words = cli.keep_committed_words;
+        let mark_crosstalk = cli.mark_crosstalk;
+
+        let mut transcriber_rx = spawn_transcriber_loader(load_transcriber, caption_tx.clone());
 
         let transcription_handle = std::thread::spawn(move || {
-            let mut stabilizer_primary = Stabilizer::new(partial_stable_iters);
-            let mut stabilizer_secondary = Stabilizer::new(partial_stable_iters);
-            let mut last_caption = String::new();
-            let mut last_final = true;
+            let mut transcriber: Option<Box<dyn Transcriber>> = None;
+            let mut stabilizer_primary = Stabilizer::new(partial_stable_iters)
+                .with_max_committed(max_committed_tokens)
+                .with_carried_context(warm_partials)
+                .with_kept_committed_words(keep_committed_words);
+            let mut stabilizer_secondary = Stabilizer::new(partial_stable_iters)
+                .with_max_committed(max_committed_tokens)
+                .with_carried_context(warm_partials)
+                .with_kept_committed_words(keep_committed_words);
+            let mut last_caption = LastCaption::new();
+            let mut speakers = decode_cfg.diarize.then(SpeakerTurns::default);
             let mut last_mode = output_language_for_worker.get();
+            let mut segment_id: u64 = 0;
+            let mut was_idle = false;
+            let mut unloaded = false;
+            let mut active_domain = domain_for_worker.selected();
+            let mut active_on_battery = starts_on_battery;
+            let mut paused = false;
 
             while !stop_transcribe.load(Ordering::Relaxed) {
+                let wanted = domain_for_worker.selected();
+                let on_battery = running_on_battery(&power_for_worker);
+                let swaps_model = battery_swaps_model(&wanted);
+                if wanted != active_domain || (on_battery != active_on_battery && swaps_model) {
+                    active_domain = wanted;
+                    active_on_battery = on_battery;
+                    if let Some(reload) = reload_transcriber.as_ref() {
+                        let name = if on_battery && swaps_model {
+                            "battery saver"
+                        } else {
+                            active_domain.as_ref().map_or(DEFAULT_DOMAIN, |d| &d.name)
+                        };
+                        tracing::info!("switching to the {name} model");
+                        // Free the old model first; audio is dropped until the new one is ready.
+                        transcriber = None;
+                        stabilizer_primary.reset();
+                        stabilizer_secondary.reset();
+                        if !unloaded {
+                            transcriber_rx = spawn_transcriber_loader(
+                                reload(active_domain.clone(), active_on_battery),
+                                caption_tx.clone(),
+                            );
+                        }
+                    }
+                }
+
+                let is_idle = idle.load(Ordering::Relaxed);
+                if is_idle != was_idle {
+                    was_idle = is_idle;
+                    if is_idle {
+                        send_status(&caption_tx, EngineStatus::Idle);
+                        if idle_unload && transcriber.take().is_some() {
+                            tracing::info!("idle: unloaded transcription model");
+                            unloaded = true;
+                        }
+                    } else if unloaded {
+                        // Ready is sent once the reload finishes.
+                        let reload = reload_transcriber.as_ref().expect("unloaded without reload");
+                        transcriber_rx = spawn_transcriber_loader(
+                            reload(active_domain.clone(), active_on_battery),
+                            caption_tx.clone(),
+                        );
+                        unloaded = false;
+                    } else if paused {
+                        send_status(&caption_tx, EngineStatus::Paused);
+                    } else if transcriber.is_some() {
+                        send_status(&caption_tx, EngineStatus::Ready);
+                    }
+                }
+
+                if transcriber.is_none() && !unloaded {
+                    match transcriber_rx.try_recv() {
+                        Ok(Ok(loaded)) => {
+                            transcriber = Some(loaded);
+                            let status = if was_idle {
+                                EngineStatus::Idle
+                            } else {
+                                EngineStatus::Ready
+                            };
+                            send_status(&caption_tx, status);
+                        }
+                        Ok(Err(err)) => {
+                            tracing::error!("{err:#}");
+                            crate::crash::report_error("model load", &format!("{err:#}"));
+                            let err = SubtitlesError::model_load(err);
+                            send_status(
+                                &caption_tx,
+                                EngineStatus::Failed {
+                                    kind: err.kind(),
+                                    message: format!("{err:#}"),
+                                },
+                            );
+                            stop_transcribe.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                        Err(_) => {}
+                    }
+                }
+
                 match event_rx.recv_timeout(Duration::from_millis(50)) {
-                    Ok(mut event) => {
-                        // Coalesce queued partials to the newest audio to avoid redundant decode work.
-                        if matches!(event, StreamingEvent::Partial(_)) {
-                            while let Ok(next) = event_rx.try_recv() {
+                    Ok((mut event, mut audio_start)) => {
+                        let Some(transcriber) = transcriber.as_mut() else {
+                            // Still downloading/loading the model: drop audio rather than queue it.
+                            if matches!(event, StreamingEvent::Partial(_)) {
+                                partial_gate.release();
+                            }
+                            continue;
+                        };
+
+                        // The segmenter only has one partial in flight at a time; this is just a
+                        // safety net so stale audio never gets decoded.
+                        let holds_partial_gate = matches!(event, StreamingEvent::Partial(_));
+                        if holds_partial_gate {
+                            while let Ok((next, start)) = event_rx.try_recv() {
+                                audio_start = start;
                                 match next {
                                     StreamingEvent::Partial(audio) => {
                                         event = StreamingEvent::Partial(audio);
@@ -276,147 +1794,720 @@ pub fn start_engine(cli: Cli, caption_tx: Sender<CaptionEvent>) -> anyhow::Resul
                             stabilizer_primary.reset();
                             stabilizer_secondary.reset();
                             last_mode = mode;
-                            if !last_caption.is_empty() {
+                            if !last_caption.text.is_empty() {
                                 last_caption.clear();
-                                last_final = true;
                                 let _ = caption_tx.try_send(CaptionEvent::Clear);
                             }
                         }
 
+                        for stabilizer in [&mut stabilizer_primary, &mut stabilizer_secondary] {
+                            stabilizer.set_stable_required(stabilizer_tuning.stable_iters());
+                            stabilizer.set_policy(stabilizer_tuning.policy());
+                        }
+
+                        let joiner = |mode| {
+                            TextJoiner::for_output(mode, decode_cfg.input_language.as_deref())
+                        };
+                        // The input samples the event's audio covers, to line partials up with
+                        // the final decoded from the same audio.
+                        let audio_range = |audio: &[f32]| {
+                            audio_start.map(|start| start..start + audio.len() as u64)
+                        };
+
                         match event {
+                            // Still decoded as finals, to hear "resume".
+                            StreamingEvent::Partial(_) if paused => {}
                             StreamingEvent::Partial(audio) => {
+                                let window_s = audio.len() as f32 / 16_000.0;
+                                let range = audio_range(&audio);
+                                let decode_started = Instant::now();
+                                let mut adapt_step = |hypothesis: &str| {
+                                    if let Some(controller) = asr_step_controller.as_mut() {
+                                        let step = controller.update(
+                                            hypothesis,
+                                            window_s,
+                                            decode_started.elapsed(),
+                                        );
+                                        asr_step_for_worker.store(step, Ordering::Relaxed);
+                                    }
+                                };
+                                let request_endpoint = |transcript: &Transcript| {
+                                    if let Some(end_s) =
+                                        sentence_endpoint(&transcript.segments, window_s)
+                                    {
+                                        partial_gate.end_at((end_s * 16_000.0).round() as usize);
+                                    }
+                                };
+
                                 if mode == OutputLanguage::Bilingual {
-                                    let original = transcribe_text(
-                                        transcriber.as_mut(),
-                                        &input_language,
-                                        OutputLanguage::Chinese,
-                                        true,
-                                        &audio,
-                                    )
-                                    .unwrap_or_default();
+                                    let original = if timestamp_endpointing {
+                                        let transcript = transcribe_partial_segments(
+                                            transcriber.as_mut(),
+                                            &decode_cfg,
+                                            OutputLanguage::Chinese,
+                                            &audio,
+                                        )
+                                        .unwrap_or_default();
+                                        request_endpoint(&transcript);
+                                        transcript.text
+                                    } else {
+                                        transcribe_text(
+                                            transcriber.as_mut(),
+                                            &decode_cfg,
+                                            OutputLanguage::Chinese,
+                                            true,
+                                            &audio,
+                                        )
+                                        .unwrap_or_default()
+                                    };
                                     let english = transcribe_text(
                                         transcriber.as_mut(),
-                                        &input_language,
+                                        &decode_cfg,
                                         OutputLanguage::English,
                                         true,
                                         &audio,
                                     )
                                     .unwrap_or_default();
+                                    adapt_step(&original);
 
                                     let (committed_primary, partial_primary) =
-                                        stabilizer_primary.update(&original);
+                                        stabilizer_primary.update_at(&original, range.clone());
                                     let (committed_secondary, partial_secondary) =
-                                        stabilizer_secondary.update(&english);
+                                        stabilizer_secondary.update_at(&english, range.clone());
 
-                                    let line_primary =
-                                        combine_committed_partial(&committed_primary, &partial_primary);
-                                    let line_secondary =
-                                        combine_committed_partial(&committed_secondary, &partial_secondary);
+                                    let line_primary = combine_committed_partial(
+                                        joiner(OutputLanguage::Chinese),
+                                        &committed_primary,
+                                        &partial_primary,
+                                    );
+                                    let line_secondary = combine_committed_partial(
+                                        joiner(OutputLanguage::English),
+                                        &committed_secondary,
+                                        &partial_secondary,
+                                    );
 
                                     let display = merge_bilingual(&line_primary, &line_secondary);
-                                    maybe_send_update(
-                                        &caption_tx,
-                                        &mut last_caption,
-                                        &mut last_final,
-                                        display,
-                                        false,
-                                    );
-                                } else if let Some(text) = transcribe_text(
-                                    transcriber.as_mut(),
-                                    &input_language,
-                                    mode,
-                                    true,
-                                    &audio,
-                                ) {
-                                    let (committed, partial) = stabilizer_primary.update(&text);
-                                    let display = combine_committed_partial(&committed, &partial);
-                                    maybe_send_update(
+                                    // One committed text per line shown.
+                                    let mut committed = Vec::new();
+                                    if !line_primary.trim().is_empty() {
+                                        committed.push(
+                                            joiner(OutputLanguage::Chinese)
+                                                .format(&committed_primary),
+                                        );
+                                    }
+                                    if !line_secondary.trim().is_empty() {
+                                        committed.push(
+                                            joiner(OutputLanguage::English)
+                                                .format(&committed_secondary),
+                                        );
+                                    }
+                                    let spans = stability_spans(&display, &committed);
+                                    maybe_send_partial(
                                         &caption_tx,
                                         &mut last_caption,
-                                        &mut last_final,
+                                        segment_id,
                                         display,
-                                        false,
+                                        spans,
                                     );
+                                } else {
+                                    let text = if timestamp_endpointing {
+                                        transcribe_partial_segments(
+                                            transcriber.as_mut(),
+                                            &decode_cfg,
+                                            mode,
+                                            &audio,
+                                        )
+                                        .map(|transcript| {
+                                            request_endpoint(&transcript);
+                                            transcript.text
+                                        })
+                                    } else {
+                                        transcribe_text(
+                                            transcriber.as_mut(),
+                                            &decode_cfg,
+                                            mode,
+                                            true,
+                                            &audio,
+                                        )
+                                    };
+                                    if let Some(text) = text {
+                                        adapt_step(&text);
+                                        let (committed, partial) =
+                                            stabilizer_primary.update_at(&text, range);
+                                        let display = combine_committed_partial(
+                                            joiner(mode),
+                                            &committed,
+                                            &partial,
+                                        );
+                                        let spans = stability_spans(
+                                            &display,
+                                            &[joiner(mode).format(&committed)],
+                                        );
+                                        maybe_send_partial(
+                                            &caption_tx,
+                                            &mut last_caption,
+                                            segment_id,
+                                            display,
+                                            spans,
+                                        );
+                                    }
                                 }
                             }
                             StreamingEvent::Final(audio) => {
-                                if mode == OutputLanguage::Bilingual {
-                                    let original = transcribe_text(
+                                let mut speaker_turns = 0;
+                                let mut words = Vec::new();
+                                let mut untranslated = false;
+                                let (final_text, confidence) = if mode == OutputLanguage::Bilingual {
+                                    let original = transcribe_detailed(
                                         transcriber.as_mut(),
-                                        &input_language,
+                                        &decode_cfg,
                                         OutputLanguage::Chinese,
                                         false,
                                         &audio,
                                     )
                                     .unwrap_or_default();
-                                    let english = transcribe_text(
+                                    let remembered = translation_memory
+                                        .as_ref()
+                                        .and_then(|memory| memory.lookup(&original.text));
+                                    let english = match remembered {
+                                        Some(text) => Transcript {
+                                            text: text.to_string(),
+                                            confidence: original.confidence,
+                                            ..Default::default()
+                                        },
+                                        None => {
+                                            let english = transcribe_detailed(
+                                                transcriber.as_mut(),
+                                                &decode_cfg,
+                                                OutputLanguage::English,
+                                                false,
+                                                &audio,
+                                            )
+                                            .unwrap_or_default();
+                                            // The original again, not a second line.
+                                            if english.untranslated {
+                                                untranslated = true;
+                                                Transcript::default()
+                                            } else {
+                                                if let Some(memory) = translation_memory.as_mut() {
+                                                    memory.insert(&original.text, &english.text);
+                                                }
+                                                english
+                                            }
+                                        }
+                                    };
+
+                                    let final_primary = joiner(OutputLanguage::Chinese).format(
+                                        &stabilizer_primary
+                                            .finalize_at(&original.text, audio_range(&audio)),
+                                    );
+                                    let final_secondary = joiner(OutputLanguage::English).format(
+                                        &stabilizer_secondary
+                                            .finalize_at(&english.text, audio_range(&audio)),
+                                    );
+                                    let confidence = original
+                                        .confidence
+                                        .zip(english.confidence)
+                                        .map(|(a, b)| a.min(b));
+                                    speaker_turns = original.speaker_turns;
+                                    words = original.words;
+                                    (
+                                        Some(merge_bilingual(&final_primary, &final_secondary)),
+                                        confidence,
+                                    )
+                                } else {
+                                    match transcribe_detailed(
                                         transcriber.as_mut(),
-                                        &input_language,
-                                        OutputLanguage::English,
+                                        &decode_cfg,
+                                        mode,
                                         false,
                                         &audio,
-                                    )
-                                    .unwrap_or_default();
-
-                                    let final_primary = stabilizer_primary.finalize(&original);
-                                    let final_secondary = stabilizer_secondary.finalize(&english);
-                                    let final_text = merge_bilingual(&final_primary, &final_secondary);
+                                    ) {
+                                        Some(transcript) => {
+                                            speaker_turns = transcript.speaker_turns;
+                                            words = transcript.words;
+                                            untranslated = transcript.untranslated;
+                                            let joiner = if untranslated {
+                                                joiner(OutputLanguage::Chinese)
+                                            } else {
+                                                joiner(mode)
+                                            };
+                                            (
+                                                Some(joiner.format(
+                                                    &stabilizer_primary.finalize_at(
+                                                        &transcript.text,
+                                                        audio_range(&audio),
+                                                    ),
+                                                )),
+                                                transcript.confidence,
+                                            )
+                                        }
+                                        None => (None, None),
+                                    }
+                                };
 
-                                    if !final_text.trim().is_empty() {
-                                        maybe_send_update(
-                                            &caption_tx,
-                                            &mut last_caption,
-                                            &mut last_final,
-                                            final_text,
-                                            true,
+                                let mut final_text = final_text
+                                    .map(|text| final_filters.apply_text(segment_id, text, mode));
+                                let spotted = final_text.as_deref().and_then(|text| {
+                                    voice_commands.as_ref().and_then(|spotter| spotter.spot(text))
+                                });
+                                if let Some(spotted) = spotted {
+                                    run_voice_action(
+                                        spotted.action,
+                                        &mut paused,
+                                        &output_language_for_worker,
+                                        &domain_for_worker,
+                                        &caption_tx,
+                                        &mut last_caption,
+                                    );
+                                    final_text = Some(spotted.rest);
+                                }
+                                if paused {
+                                    final_text = None;
+                                }
+                                let has_text =
+                                    final_text.as_deref().is_some_and(|t| !t.trim().is_empty());
+                                let crosstalk = (mark_crosstalk && has_text)
+                                    .then(|| crosstalk_fraction(&audio))
+                                    .filter(|&fraction| fraction >= CROSSTALK_MARK);
+                                let confidence = match crosstalk {
+                                    Some(fraction) => {
+                                        tracing::debug!(
+                                            "crosstalk in {:.0}% of segment {segment_id}",
+                                            fraction * 100.0
                                         );
+                                        confidence.map(|value| value * (1.0 - fraction))
                                     }
-                                } else if let Some(text) = transcribe_text(
-                                    transcriber.as_mut(),
-                                    &input_language,
-                                    mode,
-                                    false,
-                                    &audio,
-                                ) {
-                                    let final_text = stabilizer_primary.finalize(&text);
-                                    if !final_text.trim().is_empty() {
-                                        maybe_send_update(
-                                            &caption_tx,
-                                            &mut last_caption,
-                                            &mut last_final,
-                                            final_text,
-                                            true,
-                                        );
+                                    None => confidence,
+                                };
+                                if let Some(final_text) =
+                                    final_text.filter(|text| !text.trim().is_empty())
+                                {
+                                    let shown = if crosstalk.is_some() {
+                                        format!("{CROSSTALK_TAG} {final_text}")
+                                    } else {
+                                        final_text.clone()
+                                    };
+                                    let diarized =
+                                        speakers.as_mut().map(|s| s.label(speaker_turns));
+                                    maybe_send_final(
+                                        &caption_tx,
+                                        &mut last_caption,
+                                        segment_id,
+                                        shown,
+                                        audio_start.map(|start| {
+                                            let words: Vec<_> = words
+                                                .into_iter()
+                                                .map(|word| CaptionWord::aligned(start, word))
+                                                .collect();
+                                            (CaptionTiming::from_samples(start, audio.len()), words)
+                                        }),
+                                        voices.as_ref().map_or(diarized, |voices| {
+                                            voices.label(&audio, diarized)
+                                        }),
+                                        untranslated,
+                                    );
+                                    segment_audio_for_worker.insert(segment_id, &audio);
+                                    if let Some(shadow) = shadow.as_ref() {
+                                        shadow.submit(ShadowJob {
+                                            segment_id,
+                                            audio: audio.clone(),
+                                            output_language: mode,
+                                            live: final_text.clone(),
+                                            crosstalk: crosstalk.is_some(),
+                                        });
                                     }
+                                    if let Some(second_pass) = second_pass.as_ref() {
+                                        second_pass.submit(SecondPassJob {
+                                            segment_id,
+                                            audio,
+                                            output_language: mode,
+                                            first_pass: final_text,
+                                            crosstalk: crosstalk.is_some(),
+                                            confidence,
+                                        });
+                                    }
+                                    segment_id += 1;
                                 }
                             }
                             StreamingEvent::Reset => {
                                 stabilizer_primary.reset();
                                 stabilizer_secondary.reset();
-                                if !last_caption.is_empty() {
+                                if !last_caption.text.is_empty() {
                                     last_caption.clear();
-                                    last_final = true;
                                     let _ = caption_tx.try_send(CaptionEvent::Clear);
                                 }
                             }
                         }
+                        stabilizer_tuning.add_retractions(
+                            stabilizer_primary.take_retractions()
+                                + stabilizer_secondary.take_retractions(),
+                        );
+
+                        if holds_partial_gate {
+                            partial_gate.release();
+                        }
                     }
                     Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
-                    Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+                    Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
+                        // Capture ended by itself (e.g. end of `--stdin-pcm` input) and
+                        // everything it delivered is transcribed.
+                        stop_transcribe.store(true, Ordering::Relaxed);
+                        break;
+                    }
                 }
             }
+
+            if let Some(second_pass) = second_pass {
+                second_pass.join();
+            }
+            if let Some(shadow) = shadow {
+                shadow.join();
+            }
         });
 
         Ok(EngineHandle {
             stop,
             output_language,
+            capture,
+            caption_delay,
+            stabilizer,
+            domain,
             capture_handle,
             processing_handle,
             transcription_handle,
+            delay_handle,
+            instance,
+            recent,
+            speaker_names,
+            segment_audio,
+            segment_edits,
+            replace_rules,
         })
     }
 }
 
+/// How many `--level-interval-ms` each meter update covers on battery (`--battery-saver`).
+const BATTERY_LEVEL_SLOWDOWN: usize = 4;
+
+/// `--battery-saver` is on and the Mac is running on battery.
+fn running_on_battery(power: &Option<PowerWatch>) -> bool {
+    power.as_ref().is_some_and(PowerWatch::on_battery)
+}
+
+/// Builds a registered `custom:NAME` transcriber on the transcription thread.
+fn custom_loader(name: &str) -> anyhow::Result<TranscriberLoader> {
+    let factory = engine_registry::custom_factory(name)?;
+    let name = name.to_string();
+    Ok(Box::new(move |_: &mut dyn FnMut(DownloadProgress)| {
+        factory().with_context(|| format!("failed to initialize custom:{name}"))
+    }))
+}
+
+/// Loads the local model. When a preset can't be downloaded (offline on first run), a smaller
+/// preset that is already downloaded stands in, with a warning status, rather than failing.
+fn load_local_whisper(
+    model: Option<PathBuf>,
+    preset: WhisperModelPreset,
+    threads: Option<usize>,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+    status_tx: &Sender<CaptionEvent>,
+) -> anyhow::Result<WhisperLocalTranscriber> {
+    let explicit = model.is_some();
+    let err = match WhisperLocalTranscriber::new_with_progress(
+        model,
+        preset.clone(),
+        threads,
+        on_progress,
+    ) {
+        Ok(transcriber) => return Ok(transcriber),
+        Err(err) => err.context("failed to initialize local whisper"),
+    };
+    // Only a preset that never arrived is substituted; a file that won't load stays an error.
+    if explicit || preset_model_path(&preset).exists() {
+        return Err(err);
+    }
+    let Some(fallback) = cached_smaller_preset(model_dir(), &preset) else {
+        return Err(err);
+    };
+    let message = format!(
+        "couldn't download the {} model, using the cached {} model instead",
+        preset_name(&preset),
+        preset_name(&fallback)
+    );
+    tracing::warn!("{message}: {err:#}");
+    let transcriber = WhisperLocalTranscriber::new(None, fallback, threads)
+        .context("failed to initialize local whisper")?;
+    send_status(status_tx, EngineStatus::Warning(message));
+    Ok(transcriber)
+}
+
+fn preset_name(preset: &WhisperModelPreset) -> String {
+    preset
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+/// The GPU memory the largest model the live engine may load is expected to take: the
+/// preset, `--whisper-model`, the custom model or a domain model. Files not yet downloaded
+/// count as nothing.
+fn live_gpu_memory_mb(cli: &Cli) -> u64 {
+    let file_mb = |path: &Path| {
+        std::fs::metadata(path).map_or(0, |meta| estimated_gpu_memory_mb(meta.len()))
+    };
+    let live_mb = match (&cli.whisper_model, cli.whisper_custom_model.as_deref()) {
+        (Some(path), _) => file_mb(path),
+        (None, Some(name)) => cli
+            .custom_model
+            .iter()
+            .find(|model| model.name.eq_ignore_ascii_case(name))
+            .map_or(0, |model| file_mb(&model.path())),
+        (None, None) => preset_gpu_memory_mb(&cli.whisper_model_preset),
+    };
+    cli.domain_model
+        .iter()
+        .map(|domain| file_mb(&domain.path))
+        .fold(live_mb, u64::max)
+}
+
+/// Steps `preset` down to the largest preset expected to fit in `left_mb` of GPU memory (what
+/// other models leave of `--gpu-memory-limit-mb`), with a warning. When none fits it is kept
+/// as is, and loads on the CPU instead.
+fn fit_gpu_memory_limit(
+    preset: &mut WhisperModelPreset,
+    left_mb: u64,
+    status_tx: &Sender<CaptionEvent>,
+) {
+    let Some(fitting) = largest_preset_within(preset, left_mb) else {
+        return;
+    };
+    if std::mem::discriminant(&fitting) == std::mem::discriminant(preset) {
+        return;
+    }
+    let message = format!(
+        "the {} model needs about {} MB of GPU memory, over the {left_mb} MB \
+         --gpu-memory-limit-mb leaves it: using the {} model instead",
+        preset_name(preset),
+        preset_gpu_memory_mb(preset),
+        preset_name(&fitting)
+    );
+    tracing::warn!("{message}");
+    send_status(status_tx, EngineStatus::Warning(message));
+    *preset = fitting;
+}
+
+/// The text filter stages `--text-filters` can name. Every chain shares `rules`, so rules
+/// added while running apply everywhere.
+fn text_filter_registry(cli: &Cli, rules: ReplaceRules) -> FilterRegistry {
+    let mut registry = FilterRegistry::default();
+    let itn = cli.itn;
+    registry.register("itn", move || Ok(Box::new(itn) as Box<dyn TextFilter>));
+    registry.register("replace", move || Ok(Box::new(rules.clone()) as Box<dyn TextFilter>));
+    let command = cli.filter_cmd.clone().map(|command| {
+        CommandFilter::new(command, Duration::from_millis(cli.filter_cmd_timeout_ms))
+    });
+    registry.register("cmd", move || Ok(Box::new(command.clone()) as Box<dyn TextFilter>));
+    registry
+}
+
+/// Carries out a spoken command (`--voice-commands`).
+fn run_voice_action(
+    action: VoiceAction,
+    paused: &mut bool,
+    output_language: &SharedOutputLanguage,
+    domain: &SharedDomainModel,
+    caption_tx: &Sender<CaptionEvent>,
+    last_caption: &mut LastCaption,
+) {
+    tracing::info!("voice command: {action:?}");
+    match action {
+        VoiceAction::Pause => {
+            *paused = true;
+            last_caption.clear();
+            let _ = caption_tx.try_send(CaptionEvent::Clear);
+            send_status(caption_tx, EngineStatus::Paused);
+        }
+        VoiceAction::Resume => {
+            if std::mem::take(paused) {
+                send_status(caption_tx, EngineStatus::Ready);
+            }
+        }
+        VoiceAction::Clear => {
+            last_caption.clear();
+            let _ = caption_tx.try_send(CaptionEvent::Clear);
+        }
+        VoiceAction::Translate(name) => match spoken_output_language(&name) {
+            Some(language) => output_language.set(language),
+            None => tracing::warn!(
+                "voice command: can't translate to `{name}`; whisper only translates into \
+                 English (try english, chinese or bilingual)"
+            ),
+        },
+        VoiceAction::Domain(name) => {
+            if let Err(err) = domain.select(&name) {
+                tracing::warn!("voice command: {err:#}");
+            }
+        }
+    }
+}
+
+/// Loads the live transcriber on a detached thread, forwarding download progress as status
+/// events, so capture can start (and the UI can show progress) while a model downloads.
+fn spawn_transcriber_loader(
+    load: TranscriberLoader,
+    caption_tx: Sender<CaptionEvent>,
+) -> crossbeam_channel::Receiver<anyhow::Result<Box<dyn Transcriber>>> {
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    std::thread::spawn(move || {
+        send_status(&caption_tx, EngineStatus::Loading);
+        let result = load(&mut |progress| {
+            send_status(&caption_tx, EngineStatus::DownloadingModel(progress))
+        });
+        let _ = tx.send(result);
+    });
+    rx
+}
+
+/// What the delay stage does with events besides delaying them.
+struct CaptionTaps {
+    /// `--script`, which sees events first and can rewrite or drop them.
+    script: Option<CaptionScript>,
+    srt: Option<SrtWriter>,
+    /// `--record-ass`, written alongside the SRT.
+    ass: Option<AssWriter>,
+    report: Option<SessionReport>,
+    /// Live stats for the HUD and how often to send them.
+    stats: Option<(StatsTracker, Duration)>,
+    /// Sees every event as it is forwarded.
+    recent: RecentEvents,
+    /// Retractions counted for `stats`.
+    stabilizer: SharedStabilizer,
+    /// Manual corrections, which `script` doesn't rewrite and revisions don't undo.
+    edits: SegmentEdits,
+    /// `--speak`, which reads finals aloud as they are shown.
+    speech: Option<SpeechSink>,
+    /// `--braille`, which shows finals on a braille display.
+    braille: Option<BrailleSink>,
+}
+
+/// Holds caption events for the current delay (and `--min-display-ms`/`--reading-cps` pacing)
+/// before forwarding them; status, level and stats events go straight through. The `--record`
+/// SRT and ASS are written before the delay, since the video isn't delayed either.
+fn spawn_caption_delay(
+    rx: Receiver<CaptionEvent>,
+    tx: Sender<CaptionEvent>,
+    delay: SharedCaptionDelay,
+    min_display: Duration,
+    reading: Option<ReadingSpeed>,
+    taps: CaptionTaps,
+    stop: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
+    let CaptionTaps {
+        script,
+        mut srt,
+        mut ass,
+        mut report,
+        mut stats,
+        recent,
+        stabilizer,
+        edits,
+        speech,
+        braille,
+    } = taps;
+    std::thread::spawn(move || {
+        let forward = |event: CaptionEvent| {
+            recent.record(&event);
+            if let Some(speech) = speech.as_ref() {
+                speech.handle(&event);
+            }
+            if let Some(braille) = braille.as_ref() {
+                braille.handle(&event);
+            }
+            let is_meter = matches!(event, CaptionEvent::Level(_) | CaptionEvent::Stats(_));
+            if tx.try_send(event).is_err() && !is_meter {
+                tracing::warn!("caption queue full; dropping event");
+            }
+        };
+
+        let mut queue =
+            CaptionDelayQueue::with_min_display(min_display).with_reading_speed(reading);
+        let mut stats_sent = Instant::now();
+        while !stop.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            while let Some(event) = queue.pop_due(delay.get(), now) {
+                forward(event);
+            }
+            if let Some((tracker, interval)) = stats.as_mut() {
+                if now.saturating_duration_since(stats_sent) >= *interval {
+                    stats_sent = now;
+                    tracker.set_retractions(stabilizer.retractions(), now);
+                    forward(CaptionEvent::Stats(tracker.stats(now)));
+                }
+            }
+
+            let wait = queue
+                .next_due(delay.get())
+                .map_or(Duration::from_millis(50), |due| {
+                    due.saturating_duration_since(now).min(Duration::from_millis(50))
+                });
+            let received = match (rx.recv_timeout(wait), script.as_ref()) {
+                (Ok(event), _) if edits.overrides(&event) => continue,
+                (Ok(event), _) if edits.is_edit(&event) => Ok(event),
+                // Dropped by the script: as if it was never sent.
+                (Ok(event), Some(script)) => match script.apply(event) {
+                    Some(event) => Ok(event),
+                    None => continue,
+                },
+                (received, _) => received,
+            };
+            if let (Ok(event), Some(writer)) = (&received, srt.as_mut()) {
+                if let Err(err) = writer.handle(event) {
+                    tracing::warn!("failed to write subtitles, stopping: {err:#}");
+                    srt = None;
+                }
+            }
+            if let (Ok(event), Some(writer)) = (&received, ass.as_mut()) {
+                if let Err(err) = writer.handle(event) {
+                    tracing::warn!("failed to write styled subtitles, stopping: {err:#}");
+                    ass = None;
+                }
+            }
+            if let (Ok(event), Some(report)) = (&received, report.as_mut()) {
+                report.handle(event);
+            }
+            if let (
+                Ok(CaptionEvent::Update {
+                    text,
+                    is_final: true,
+                    timing,
+                    ..
+                }),
+                Some((tracker, _)),
+            ) = (&received, stats.as_mut())
+            {
+                let duration_s = timing.map_or(0.0, |t| t.end_s - t.start_s);
+                tracker.push_final(text, duration_s, Instant::now());
+            }
+            match received {
+                Ok(event) if event.is_caption() => queue.push(event, Instant::now()),
+                Ok(event) => forward(event),
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        // A load failure is sent right before `stop` is set; make sure it still gets out.
+        for event in rx.try_iter().filter(|event| matches!(event, CaptionEvent::Status(_))) {
+            forward(event);
+        }
+        if let Some(report) = report {
+            if let Err(err) = report.write() {
+                tracing::warn!("failed to write session report: {err:#}");
+            }
+        }
+    })
+}
+
 pub fn run_headless(cli: Cli) -> anyhow::Result<()> {
     if !cli.no_ui {
         anyhow::bail!(
@@ -424,29 +2515,213 @@ pub fn run_headless(cli: Cli) -> anyhow::Result<()> {
         );
     }
 
-    let (caption_tx, caption_rx) = crossbeam_channel::bounded::<CaptionEvent>(64);
-    let engine = start_engine(cli, caption_tx)?;
-    let stop = engine.stop.clone();
-
-    let stop_for_handler = stop.clone();
+    let _pid_file = cli.pid_file.as_deref().map(PidFile::create).transpose()?;
+    let signals = Signals::install()?;
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let interrupted_for_handler = interrupted.clone();
     ctrlc::set_handler(move || {
-        stop_for_handler.store(true, Ordering::Relaxed);
+        interrupted_for_handler.store(true, Ordering::Relaxed);
     })
     .context("failed to set Ctrl-C handler")?;
 
+    let mut cli = cli;
+    loop {
+        if !run_headless_session(cli.clone(), &signals, &interrupted)? {
+            return Ok(());
+        }
+        // SIGHUP: re-read the settings file and restart the engine with them.
+        match parse_cli() {
+            Ok(reloaded) if reloaded.no_ui => {
+                tracing::info!("settings reloaded; restarting");
+                cli = reloaded;
+            }
+            Ok(_) => tracing::warn!("reloaded settings drop --no-ui; keeping the old ones"),
+            Err(err) => tracing::warn!("failed to reload settings, keeping the old ones: {err:#}"),
+        }
+    }
+}
+
+/// Runs one engine until it stops, fails or is interrupted. Returns true when SIGHUP asked for
+/// a restart with reloaded settings.
+fn run_headless_session(
+    cli: Cli,
+    signals: &Signals,
+    interrupted: &AtomicBool,
+) -> anyhow::Result<bool> {
+    let sound_tags = SoundTagFilter::new(cli.transcript_sound_tags, cli.sound_tag_glyph.clone());
+    let vertical_rows = (cli.caption_layout == CaptionLayout::Vertical)
+        .then_some(usize::from(cli.vertical_rows));
+    let json = cli.json;
+    let timestamps = cli.print_timestamps;
+    // A partial can't be redrawn in place over a block of vertical columns, or at all when
+    // stdout is a pipe or a file rather than a terminal.
+    let print = match cli.print {
+        PrintMode::Partials if vertical_rows.is_some() => PrintMode::Finals,
+        PrintMode::Partials if !std::io::stdout().is_terminal() => PrintMode::Finals,
+        print => print,
+    };
+    let (caption_tx, caption_rx) = crossbeam_channel::bounded::<CaptionEvent>(64);
+    let engine = start_engine(cli, caption_tx)?;
+    let stop = engine.stop.clone();
+    let mut out = std::io::stdout().lock();
+
+    let mut failure = None;
+    let mut reload = false;
+    let mut logged_decile = None;
+    // A partial is on screen without a newline after it (`--print partials`).
+    let mut live_line = false;
     while !stop.load(Ordering::Relaxed) {
+        if interrupted.load(Ordering::Relaxed) || signals.terminate_requested() {
+            break;
+        }
+        if signals.take_reload() {
+            reload = true;
+            break;
+        }
         match caption_rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(CaptionEvent::Update { text, is_final }) => {
-                if is_final && !text.trim().is_empty() {
-                    println!("{text}");
+            Ok(event) if json => {
+                if let CaptionEvent::Status(EngineStatus::Failed { message, .. }) = &event {
+                    failure = Some(message.clone());
+                }
+                if print_json(&mut out, &sound_tags, event).is_err() || failure.is_some() {
+                    break;
+                }
+            }
+            Ok(CaptionEvent::Update {
+                text,
+                is_final,
+                timing,
+                ..
+            }) => {
+                let timing = timing.filter(|_| timestamps);
+                let printed = match (print, is_final) {
+                    (PrintMode::None, _) | (PrintMode::Finals, false) => Ok(()),
+                    (_, false) => print_partial(&mut out, &sound_tags, &mut live_line, &text),
+                    (_, true) => clear_partial(&mut out, &mut live_line).and_then(|()| {
+                        print_final(&mut out, &sound_tags, vertical_rows, timing, &text)
+                    }),
+                };
+                if printed.is_err() {
+                    // Nobody is reading stdout any more (e.g. a closed pipe).
+                    break;
+                }
+            }
+            Ok(CaptionEvent::Status(EngineStatus::DownloadingModel(progress))) => {
+                if let Some(total) = progress.total_bytes.filter(|&total| total > 0) {
+                    let pct = progress.downloaded_bytes * 100 / total;
+                    if logged_decile != Some(pct / 10) {
+                        logged_decile = Some(pct / 10);
+                        tracing::info!("downloading {}: {pct}%", progress.file_name);
+                    }
                 }
             }
-            Ok(CaptionEvent::Clear) => {}
+            Ok(CaptionEvent::Status(EngineStatus::Failed { message, .. })) => {
+                failure = Some(message);
+                break;
+            }
+            Ok(CaptionEvent::Status(_) | CaptionEvent::Level(_) | CaptionEvent::Stats(_)) => {}
+            Ok(CaptionEvent::Revise { .. }) | Ok(CaptionEvent::Clear) => {}
+            Ok(CaptionEvent::SpeakerName { .. }) => {}
             Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
             Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
         }
     }
 
+    // A load failure also sets `stop`; join first so its status and any finals still held by
+    // the delay stage have made it through.
     engine.stop_and_join();
+    for event in caption_rx.try_iter() {
+        if let CaptionEvent::Status(EngineStatus::Failed { message, .. }) = &event {
+            failure.get_or_insert(message.clone());
+        }
+        if json {
+            let _ = print_json(&mut out, &sound_tags, event);
+        } else if let CaptionEvent::Update {
+            text,
+            is_final: true,
+            timing,
+            ..
+        } = event
+        {
+            if print != PrintMode::None {
+                let timing = timing.filter(|_| timestamps);
+                let _ = clear_partial(&mut out, &mut live_line);
+                let _ = print_final(&mut out, &sound_tags, vertical_rows, timing, &text);
+            }
+        }
+    }
+    if live_line {
+        // Leave the last partial readable above the shell prompt.
+        let _ = writeln!(out);
+    }
+    let _ = out.flush();
+    match failure {
+        Some(err) => anyhow::bail!(err),
+        None => Ok(reload),
+    }
+}
+
+/// Writes `event` as one line of protocol JSON.
+fn print_json(
+    out: &mut impl Write,
+    sound_tags: &SoundTagFilter,
+    event: CaptionEvent,
+) -> std::io::Result<()> {
+    let message = Message::new(ProtocolEvent::from_caption_event(event, sound_tags));
+    serde_json::to_writer(&mut *out, &message)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+/// Redraws the live line with a partial: back to the start of the line, clear it, write.
+fn print_partial(
+    out: &mut impl Write,
+    sound_tags: &SoundTagFilter,
+    live_line: &mut bool,
+    text: &str,
+) -> std::io::Result<()> {
+    let text = sound_tags.apply(text);
+    write!(out, "\r\x1b[K{}", text.trim())?;
+    *live_line = true;
+    out.flush()
+}
+
+/// Clears a partial off the live line so a final can be written in its place.
+fn clear_partial(out: &mut impl Write, live_line: &mut bool) -> std::io::Result<()> {
+    if std::mem::take(live_line) {
+        write!(out, "\r\x1b[K")?;
+    }
     Ok(())
 }
+
+/// Writes a final on its own line, or as a block of vertical columns with a blank line after
+/// it when `vertical_rows` is set. With `timing`, the line (or block) starts with its span.
+fn print_final(
+    out: &mut impl Write,
+    sound_tags: &SoundTagFilter,
+    vertical_rows: Option<usize>,
+    timing: Option<CaptionTiming>,
+    text: &str,
+) -> std::io::Result<()> {
+    let text = sound_tags.apply(text);
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+    if let Some(timing) = timing {
+        let span = format!(
+            "[{} --> {}]",
+            vtt::format_timestamp(timing.start_s),
+            vtt::format_timestamp(timing.end_s)
+        );
+        match vertical_rows {
+            Some(_) => writeln!(out, "{span}")?,
+            None => write!(out, "{span} ")?,
+        }
+    }
+    match vertical_rows {
+        Some(rows) => writeln!(out, "{}\n", render_vertical(&text, rows))?,
+        None => writeln!(out, "{text}")?,
+    }
+    // Flushed per line so a pipe or log file sees captions as they happen.
+    out.flush()
+}
@@ -0,0 +1,181 @@
+//! Advanced SubStation Alpha sidecar for `--record` (`--record-ass`): the same cues as the SRT,
+//! styled like the overlay (theme colors, outline or background box, letter spacing, size and
+//! width), with a style per `--diarize` speaker. Players and burn-in tools that render ASS then
+//! show the captions the way they looked live.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::CaptionEvent;
+use crate::srt::Cues;
+use crate::text::{ReadingSpeed, SoundTagFilter};
+use crate::theme::{ThemeStyle, SPEAKER_COLORS};
+
+/// The script's coordinate space; players scale it to the video.
+const PLAY_RES_X: u32 = 1920;
+const PLAY_RES_Y: u32 = 1080;
+/// Distance of the captions from the bottom edge, in script pixels.
+const MARGIN_V: u32 = 48;
+/// The overlay's caption font.
+const FONT: &str = "Optima";
+
+/// How the overlay draws captions, from the command line.
+#[derive(Debug, Clone)]
+pub struct AssStyle {
+    pub theme: ThemeStyle,
+    /// `--font-size`, before the theme's scale.
+    pub font_size: f32,
+    /// `--overlay-width-frac`, which sets the side margins.
+    pub width_frac: f32,
+}
+
+pub struct AssWriter {
+    path: PathBuf,
+    header: String,
+    cues: Cues,
+}
+
+impl AssWriter {
+    /// Creates (or truncates) the file up front so a bad path fails at startup.
+    pub fn create(
+        path: &Path,
+        sound_tags: SoundTagFilter,
+        style: &AssStyle,
+    ) -> anyhow::Result<Self> {
+        let header = render_header(style);
+        fs::write(path, &header).with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            header,
+            cues: Cues::new(sound_tags),
+        })
+    }
+
+    /// Splits finals too long to read at `speed` into several cues (`--reading-cps`).
+    pub fn with_reading_speed(mut self, speed: Option<ReadingSpeed>) -> Self {
+        self.cues.set_reading_speed(speed);
+        self
+    }
+
+    /// Adds timed finals and applies second-pass revisions, rewriting the file when a cue
+    /// changes so it is complete even if the app is killed.
+    pub fn handle(&mut self, event: &CaptionEvent) -> anyhow::Result<()> {
+        if !self.cues.handle(event) {
+            return Ok(());
+        }
+        fs::write(&self.path, self.render())
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = self.header.clone();
+        for (timing, text, speaker) in self.cues.rendered() {
+            let (style, name) = match speaker {
+                Some(speaker) => (
+                    format!("Speaker{}", speaker.id as usize % SPEAKER_COLORS.len() + 1),
+                    // The Name field is comma-separated from the rest of the event.
+                    match self.cues.speaker_name(speaker.id) {
+                        Some(name) => name.replace(',', " "),
+                        None => format!("Speaker {}", speaker.id + 1),
+                    },
+                ),
+                None => ("Default".to_string(), String::new()),
+            };
+            out.push_str(&format!(
+                "Dialogue: 0,{},{},{style},{name},0,0,0,,{}\n",
+                format_timestamp(timing.start_s),
+                format_timestamp(timing.end_s),
+                escape_text(text.trim())
+            ));
+        }
+        out
+    }
+}
+
+fn render_header(style: &AssStyle) -> String {
+    let mut out = format!(
+        "[Script Info]\n\
+         ScriptType: v4.00+\n\
+         PlayResX: {PLAY_RES_X}\n\
+         PlayResY: {PLAY_RES_Y}\n\
+         WrapStyle: 0\n\
+         ScaledBorderAndShadow: yes\n\
+         \n\
+         [V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, \
+         BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, \
+         BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n"
+    );
+    out.push_str(&style_line("Default", style.theme.text_color, style));
+    for (i, color) in SPEAKER_COLORS.iter().enumerate() {
+        out.push_str(&style_line(&format!("Speaker{}", i + 1), color, style));
+    }
+    out.push_str(
+        "\n[Events]\n\
+         Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+    );
+    out
+}
+
+fn style_line(name: &str, text_color: &str, style: &AssStyle) -> String {
+    let theme = &style.theme;
+    let font_size = style.font_size * theme.font_scale;
+    let boxed = theme.background_opacity > 0.0;
+    // Border style 3 draws an opaque box in the outline color, `Outline` wide around the text.
+    let (border_style, outline, outline_color, back_color, shadow) = if boxed {
+        let box_color = ass_color(&theme.background_color, theme.background_opacity);
+        let padding = theme.background_padding_em * font_size;
+        (3, padding, box_color.clone(), box_color, 0.0)
+    } else {
+        let outline_color = ass_color(theme.outline_color, 1.0);
+        // The overlay's soft drop shadow.
+        (
+            1,
+            theme.outline_px,
+            outline_color,
+            ass_color("#000000", 0.45),
+            2.0,
+        )
+    };
+    let side = ((1.0 - style.width_frac.clamp(0.1, 1.0)) / 2.0 * PLAY_RES_X as f32).round();
+    format!(
+        "Style: {name},{FONT},{font_size:.0},{primary},{primary},{outline_color},{back_color},\
+         0,0,0,0,100,100,{spacing:.1},0,{border_style},{outline:.1},{shadow:.1},2,\
+         {side},{side},{MARGIN_V},1\n",
+        primary = ass_color(text_color, 1.0),
+        spacing = theme.letter_spacing_em * font_size,
+    )
+}
+
+/// `&HAABBGGRR` from `#rrggbb`, where ASS alpha 00 is opaque.
+fn ass_color(hex: &str, opacity: f32) -> String {
+    let hex = hex.trim_start_matches('#');
+    let channel = |i: usize| hex.get(i..i + 2).unwrap_or("00").to_ascii_uppercase();
+    let alpha = ((1.0 - opacity.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    format!("&H{alpha:02X}{}{}{}", channel(4), channel(2), channel(0))
+}
+
+/// Line breaks become `\N`; braces would start override tags, so they are swapped for
+/// parentheses.
+fn escape_text(text: &str) -> String {
+    text.replace('{', "(")
+        .replace('}', ")")
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\\N")
+}
+
+/// `H:MM:SS.cc`, as ASS expects.
+pub fn format_timestamp(seconds: f64) -> String {
+    let cs = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        cs / 360_000,
+        cs / 6000 % 60,
+        cs / 100 % 60,
+        cs % 100
+    )
+}
@@ -0,0 +1,608 @@
+//! `subtitles batch`: transcribes every audio file in a directory to `.srt`, several files at a
+//! time on one loaded model (each worker only owns its decoding state), with progress per file
+//! and a summary at the end.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use anyhow::Context;
+use clap::{Args, Parser, ValueEnum};
+use serde::Serialize;
+
+use crate::app::{CaptionEvent, CaptionTiming};
+use crate::audio::{Resampler, Segmenter, SegmenterConfig};
+use crate::config::{OutputLanguage, SoundTagMode, WhisperModelPreset};
+use crate::srt::SrtWriter;
+use crate::text::SoundTagFilter;
+use crate::transcribe::{decode_final, Transcriber, TranscriberConfig, WhisperLocalTranscriber};
+use crate::vtt::VttWriter;
+
+/// Extensions picked up from `--dir`: audio, and video whose soundtrack `afconvert` can read.
+/// WAV is read directly; the rest go through macOS's `afconvert`.
+pub const AUDIO_EXTENSIONS: [&str; 12] = [
+    "wav", "wave", "mp3", "m4a", "aac", "aif", "aiff", "caf", "flac", "mp4", "m4v", "mov",
+];
+const SAMPLE_RATE: u32 = 16_000;
+/// Numbers `afconvert` output files, so files converted at the same time never share one.
+static CONVERTED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles batch",
+    about = "Transcribe every audio file in a directory to .srt subtitles"
+)]
+pub struct BatchCli {
+    /// Directory of recordings (not searched recursively).
+    #[arg(long)]
+    pub dir: PathBuf,
+
+    /// Where `<name>.srt` files are written (created if missing).
+    #[arg(long)]
+    pub out_dir: PathBuf,
+
+    /// Files transcribed at once. They share one loaded model, but each needs its own
+    /// decoding memory.
+    #[arg(long, default_value_t = 2)]
+    pub jobs: usize,
+
+    /// Transcribe files again even if their `.srt` already exists.
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// Also write the summary as JSON to this file.
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub transcribe: TranscribeArgs,
+}
+
+/// Model and decoding flags shared by `subtitles batch` and `subtitles watch`.
+#[derive(Debug, Args)]
+pub struct TranscribeArgs {
+    /// Local whisper model file path. If omitted, a model will be downloaded.
+    #[arg(long)]
+    pub whisper_model: Option<PathBuf>,
+
+    /// Local model preset to download when `--whisper-model` is not provided.
+    #[arg(long, value_enum, default_value_t = WhisperModelPreset::Medium)]
+    pub whisper_model_preset: WhisperModelPreset,
+
+    /// Threads per file (defaults to the CPU count, divided by `--jobs` for a batch).
+    #[arg(long)]
+    pub whisper_threads: Option<usize>,
+
+    /// Input language (e.g. `en`, `zh`, `ja`) or `auto`.
+    #[arg(long, alias = "language", default_value = "auto")]
+    pub input_language: String,
+
+    #[arg(long, value_enum, default_value_t = OutputLanguage::English)]
+    pub output_language: OutputLanguage,
+
+    /// Subtitle file format.
+    #[arg(long, value_enum, default_value_t = SubtitleFormat::Srt)]
+    pub format: SubtitleFormat,
+
+    /// Sound tags in the subtitles: `keep`, `strip` or `glyph`.
+    #[arg(long, value_enum, default_value_t = SoundTagMode::Strip)]
+    pub sound_tags: SoundTagMode,
+
+    /// VAD threshold (RMS) for speech detection.
+    #[arg(long, default_value_t = 0.012)]
+    pub vad_threshold: f32,
+
+    /// How long (seconds) of silence ends a cue.
+    #[arg(long, default_value_t = 0.6)]
+    pub vad_end_silence_s: f32,
+
+    /// Maximum cue length (seconds) before forcing a split.
+    #[arg(long, default_value_t = 20.0)]
+    pub max_segment_s: f32,
+}
+
+impl TranscribeArgs {
+    /// Loads the model, logging download progress, with `jobs` transcribers sharing it.
+    pub fn load_transcribers(&self, jobs: usize) -> anyhow::Result<Vec<Box<dyn Transcriber>>> {
+        let threads = self.whisper_threads.unwrap_or_else(|| {
+            let cpus = std::thread::available_parallelism().map_or(4, |n| n.get());
+            (cpus / jobs.max(1)).max(1)
+        });
+        let mut logged_decile = None;
+        let first = WhisperLocalTranscriber::new_with_progress(
+            self.whisper_model.clone(),
+            self.whisper_model_preset.clone(),
+            Some(threads),
+            &mut |progress| {
+                if let Some(total) = progress.total_bytes.filter(|&total| total > 0) {
+                    let pct = progress.downloaded_bytes * 100 / total;
+                    if logged_decile != Some(pct / 10) {
+                        logged_decile = Some(pct / 10);
+                        tracing::info!("downloading {}: {pct}%", progress.file_name);
+                    }
+                }
+            },
+        )
+        .context("failed to initialize local whisper")?;
+        let mut transcribers: Vec<Box<dyn Transcriber>> = Vec::with_capacity(jobs);
+        for _ in 1..jobs {
+            transcribers.push(Box::new(first.fork()?));
+        }
+        transcribers.push(Box::new(first));
+        Ok(transcribers)
+    }
+
+    pub fn options(&self, out_dir: Option<PathBuf>, overwrite: bool) -> BatchOptions {
+        let input_language = self.input_language.trim();
+        BatchOptions {
+            out_dir,
+            overwrite,
+            decode_cfg: TranscriberConfig {
+                input_language: (!input_language.eq_ignore_ascii_case("auto"))
+                    .then(|| input_language.to_string()),
+                output_language: self.output_language,
+                is_partial: false,
+                segment_timestamps: false,
+                prompt: None,
+                glossary: None,
+                diarize: false,
+            },
+            segmenter: SegmenterConfig {
+                sample_rate_hz: SAMPLE_RATE,
+                vad_threshold: self.vad_threshold,
+                vad_end_silence_s: self.vad_end_silence_s,
+                max_segment_s: self.max_segment_s,
+                pre_roll_s: 0.25,
+            },
+            format: self.format,
+            sound_tags: SoundTagFilter::new(self.sound_tags, "♪"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SubtitleFormat {
+    /// SubRip (`.srt`).
+    Srt,
+    /// WebVTT (`.vtt`).
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+        }
+    }
+
+    /// The format a file name's extension asks for, if it is one of these.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::from_str(&ext, true).ok()
+    }
+}
+
+/// Settings shared by every file in a batch.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Where subtitle files go; `None` writes each one next to its recording.
+    pub out_dir: Option<PathBuf>,
+    pub overwrite: bool,
+    pub decode_cfg: TranscriberConfig,
+    pub segmenter: SegmenterConfig,
+    pub format: SubtitleFormat,
+    pub sound_tags: SoundTagFilter,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "error")]
+pub enum FileStatus {
+    Done,
+    /// The `.srt` already existed.
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    #[serde(flatten)]
+    pub status: FileStatus,
+    pub cues: usize,
+    pub audio_s: f64,
+    pub elapsed_s: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub files: Vec<FileReport>,
+    pub elapsed_s: f64,
+}
+
+impl BatchReport {
+    pub fn count(&self, status: fn(&FileStatus) -> bool) -> usize {
+        self.files
+            .iter()
+            .filter(|file| status(&file.status))
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, FileStatus::Failed(_)))
+    }
+
+    /// One line per file, then the totals and speed relative to real time.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        for file in &self.files {
+            let name = file.input.file_name().unwrap_or_default().to_string_lossy();
+            let line = match &file.status {
+                FileStatus::Done => format!(
+                    "ok      {name}: {} cues, {} of audio in {:.1}s\n",
+                    file.cues,
+                    format_duration(file.audio_s),
+                    file.elapsed_s
+                ),
+                FileStatus::Skipped => format!("skipped {name}: subtitles already exist\n"),
+                FileStatus::Failed(err) => format!("failed  {name}: {err}\n"),
+            };
+            out.push_str(&line);
+        }
+        let audio_s: f64 = self.files.iter().map(|file| file.audio_s).sum();
+        out.push_str(&format!(
+            "{} done, {} skipped, {} failed; {} of audio in {:.1}s ({:.1}x real time)\n",
+            self.count(|status| *status == FileStatus::Done),
+            self.count(|status| *status == FileStatus::Skipped),
+            self.failed(),
+            format_duration(audio_s),
+            self.elapsed_s,
+            audio_s / self.elapsed_s.max(0.001)
+        ));
+        out
+    }
+}
+
+pub fn run(cli: BatchCli) -> anyhow::Result<()> {
+    let files = audio_files(&cli.dir)?;
+    anyhow::ensure!(!files.is_empty(), "no audio files in {}", cli.dir.display());
+    fs::create_dir_all(&cli.out_dir)
+        .with_context(|| format!("failed to create {}", cli.out_dir.display()))?;
+
+    let jobs = cli.jobs.clamp(1, files.len());
+    let transcribers = cli.transcribe.load_transcribers(jobs)?;
+    let options = cli.transcribe.options(Some(cli.out_dir), cli.overwrite);
+    options.check_outputs(&files)?;
+    let report = transcribe_all(&files, &options, transcribers);
+    print!("{}", report.summary());
+    if let Some(path) = cli.report {
+        let json = serde_json::to_string_pretty(&report)?;
+        fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    if report.failed() > 0 {
+        anyhow::bail!("{} of {} files failed", report.failed(), report.files.len());
+    }
+    Ok(())
+}
+
+impl BatchOptions {
+    /// `<name>.srt` (or `.vtt`) for a recording.
+    pub fn output_for(&self, input: &Path) -> PathBuf {
+        let name = format!(
+            "{}.{}",
+            input.file_stem().unwrap_or_default().to_string_lossy(),
+            self.format.extension()
+        );
+        match &self.out_dir {
+            Some(dir) => dir.join(name),
+            None => input.with_file_name(name),
+        }
+    }
+
+    /// Fails if two of `files` would be written to the same subtitles (talk.mp3 and talk.m4a
+    /// are both talk.srt), rather than have one silently replace or skip the other.
+    pub fn check_outputs(&self, files: &[PathBuf]) -> anyhow::Result<()> {
+        let mut outputs: HashMap<PathBuf, &Path> = HashMap::new();
+        for file in files {
+            let output = self.output_for(file);
+            if let Some(other) = outputs.get(&output) {
+                anyhow::bail!(
+                    "{} and {} would both be written to {}; rename one of them",
+                    other.display(),
+                    file.display(),
+                    output.display()
+                );
+            }
+            outputs.insert(output, file);
+        }
+        Ok(())
+    }
+}
+
+/// Audio files directly in `dir`, by name.
+pub fn audio_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if is_audio_file(&path) && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `path` has one of `AUDIO_EXTENSIONS`.
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Transcribes `files` with one worker per transcriber, each taking the next file when it
+/// finishes one. Failures are recorded in the report rather than stopping the batch.
+pub fn transcribe_all(
+    files: &[PathBuf],
+    options: &BatchOptions,
+    transcribers: Vec<Box<dyn Transcriber>>,
+) -> BatchReport {
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let mut reports: Vec<(usize, FileReport)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = transcribers
+            .into_iter()
+            .map(|mut transcriber| {
+                let next = &next;
+                scope.spawn(move || {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(input) = files.get(index) else {
+                            break;
+                        };
+                        let label = format!("[{}/{}]", index + 1, files.len());
+                        let report = transcribe_file(input, options, &mut *transcriber, &label);
+                        done.push((index, report));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+    reports.sort_by_key(|(index, _)| *index);
+    BatchReport {
+        files: reports.into_iter().map(|(_, report)| report).collect(),
+        elapsed_s: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// Transcribes one recording to `options.output_for(input)`; `label` prefixes its log lines.
+pub fn transcribe_file(
+    input: &Path,
+    options: &BatchOptions,
+    transcriber: &mut dyn Transcriber,
+    label: &str,
+) -> FileReport {
+    transcribe_file_to(
+        input,
+        &options.output_for(input),
+        options,
+        transcriber,
+        label,
+    )
+}
+
+/// Like `transcribe_file`, writing to `output` in `options.format`. The subtitles are written
+/// next to it and renamed into place when done, so a failed re-run keeps the old file.
+pub fn transcribe_file_to(
+    input: &Path,
+    output: &Path,
+    options: &BatchOptions,
+    transcriber: &mut dyn Transcriber,
+    label: &str,
+) -> FileReport {
+    let started = Instant::now();
+    let name = input.file_name().unwrap_or_default().to_string_lossy();
+    let output = output.to_path_buf();
+    let mut report = FileReport {
+        input: input.to_path_buf(),
+        output: output.clone(),
+        status: FileStatus::Done,
+        cues: 0,
+        audio_s: 0.0,
+        elapsed_s: 0.0,
+    };
+    if output.exists() && !options.overwrite {
+        tracing::info!("{label} {name}: {} exists, skipping", output.display());
+        report.status = FileStatus::Skipped;
+        return report;
+    }
+
+    tracing::info!("{label} {name}: transcribing");
+    let partial = partial_path(&output);
+    let result = read_audio_16k_mono(input).and_then(|audio| {
+        report.audio_s = audio.len() as f64 / SAMPLE_RATE as f64;
+        let mut last_tenth = 0;
+        let cues = transcribe_audio(&audio, options, transcriber, &mut |fraction| {
+            let tenth = (fraction * 10.0) as u32;
+            if tenth > last_tenth && tenth < 10 {
+                last_tenth = tenth;
+                tracing::info!("{label} {name}: {}%", tenth * 10);
+            }
+        })?;
+        report.cues = cues.len();
+        write_subtitles(&partial, options, cues)?;
+        fs::rename(&partial, &output)
+            .with_context(|| format!("failed to write {}", output.display()))
+    });
+    report.elapsed_s = started.elapsed().as_secs_f64();
+    match result {
+        Ok(()) => tracing::info!(
+            "{label} {name}: done, {} cues in {:.1}s",
+            report.cues,
+            report.elapsed_s
+        ),
+        Err(err) => {
+            tracing::error!("{label} {name}: {err:#}");
+            report.status = FileStatus::Failed(format!("{err:#}"));
+            let _ = fs::remove_file(&partial);
+        }
+    }
+    report
+}
+
+/// `.<name>.partial` next to `output`.
+fn partial_path(output: &Path) -> PathBuf {
+    let name = output.file_name().unwrap_or_default().to_string_lossy();
+    output.with_file_name(format!(".{name}.partial"))
+}
+
+fn write_subtitles(
+    output: &Path,
+    options: &BatchOptions,
+    cues: Vec<(CaptionTiming, String)>,
+) -> anyhow::Result<()> {
+    let events = cues
+        .into_iter()
+        .enumerate()
+        .map(|(segment_id, (timing, text))| CaptionEvent::Update {
+            segment_id: segment_id as u64,
+            text,
+            is_final: true,
+            timing: Some(timing),
+            speaker: None,
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        });
+    let sound_tags = options.sound_tags.clone();
+    match options.format {
+        SubtitleFormat::Srt => {
+            let mut srt = SrtWriter::create(output, sound_tags)?;
+            events.for_each(|event| {
+                srt.push(&event);
+            });
+            srt.save()
+        }
+        SubtitleFormat::Vtt => {
+            let mut vtt = VttWriter::create(output, sound_tags)?;
+            events.for_each(|event| {
+                vtt.push(&event);
+            });
+            vtt.save()
+        }
+    }
+}
+
+/// Splits `audio` into utterances like the live engine and decodes each one, reporting the
+/// fraction of the audio done. Empty results are left out.
+pub fn transcribe_audio(
+    audio: &[f32],
+    options: &BatchOptions,
+    transcriber: &mut dyn Transcriber,
+    on_progress: &mut dyn FnMut(f32),
+) -> anyhow::Result<Vec<(CaptionTiming, String)>> {
+    let cfg = &options.decode_cfg;
+    let mut segmenter = Segmenter::new(options.segmenter);
+    // Trailing silence ends an utterance still open at the end of the file.
+    let silence = (options.segmenter.vad_end_silence_s + 0.1) * SAMPLE_RATE as f32;
+    let mut segments = Vec::new();
+    for chunk in [audio, &vec![0.0; silence as usize]] {
+        for segment in segmenter.push_audio(chunk) {
+            let start = segmenter.take_segment_start().unwrap_or_default();
+            segments.push((start, segment));
+        }
+    }
+
+    let total_s = audio.len() as f64 / SAMPLE_RATE as f64;
+    let mut cues = Vec::new();
+    for (start, segment) in segments {
+        let text = decode_final(transcriber, &segment, cfg, cfg.output_language)?;
+        let end = start + segment.len() as u64;
+        on_progress((end as f32 / audio.len().max(1) as f32).min(1.0));
+        if !text.trim().is_empty() {
+            let timing = CaptionTiming {
+                start_s: start as f64 / SAMPLE_RATE as f64,
+                end_s: (end as f64 / SAMPLE_RATE as f64).min(total_s),
+            };
+            cues.push((timing, text));
+        }
+    }
+    Ok(cues)
+}
+
+/// Decodes an audio file to 16 kHz mono. Formats other than WAV are converted with
+/// `afconvert`, so they need macOS.
+pub fn read_audio_16k_mono(path: &Path) -> anyhow::Result<Vec<f32>> {
+    let is_wav = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "wav" | "wave"));
+    if is_wav {
+        return read_wav_16k_mono(path);
+    }
+
+    let converted = std::env::temp_dir().join(format!(
+        "subtitles-batch-{}-{}.wav",
+        std::process::id(),
+        CONVERTED.fetch_add(1, Ordering::Relaxed)
+    ));
+    let status = std::process::Command::new("afconvert")
+        .args(["-f", "WAVE", "-d", "LEI16@16000", "-c", "1"])
+        .arg(path)
+        .arg(&converted)
+        .status()
+        .context("failed to run afconvert (formats other than WAV need macOS)")?;
+    let audio = if status.success() {
+        read_wav_16k_mono(&converted)
+    } else {
+        Err(anyhow::anyhow!(
+            "afconvert could not decode the file ({status})"
+        ))
+    };
+    let _ = fs::remove_file(&converted);
+    audio
+}
+
+/// Reads a WAV file of any rate, channel count and PCM format, mixed down to 16 kHz mono.
+pub fn read_wav_16k_mono(path: &Path) -> anyhow::Result<Vec<f32>> {
+    let mut reader = hound::WavReader::open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    if spec.sample_rate == SAMPLE_RATE {
+        return Ok(mono);
+    }
+    Ok(Resampler::new(spec.sample_rate, SAMPLE_RATE).process(&mono))
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    if seconds >= 3600 {
+        format!("{}h{:02}m", seconds / 3600, seconds / 60 % 60)
+    } else {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    }
+}
@@ -0,0 +1,296 @@
+//! `--braille`: shows finals on a refreshable braille display through BRLTTY's BrlAPI, for
+//! deaf-blind users who can't use the overlay. Speaks the BrlAPI socket protocol directly, so
+//! there is no libbrlapi to link.
+//!
+//! The display shows the end of the latest final, like the overlay's last line; the sink
+//! reconnects on the next caption if BRLTTY restarts.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use crossbeam_channel::Sender;
+
+use crate::app::CaptionEvent;
+use crate::text::SoundTagFilter;
+
+const PROTOCOL_VERSION: u32 = 8;
+const PACKET_VERSION: u32 = b'v' as u32;
+const PACKET_AUTH: u32 = b'a' as u32;
+const PACKET_GET_DISPLAY_SIZE: u32 = b's' as u32;
+const PACKET_ENTER_TTY_MODE: u32 = b't' as u32;
+const PACKET_WRITE: u32 = b'w' as u32;
+const PACKET_ACK: u32 = b'A' as u32;
+const PACKET_ERROR: u32 = b'e' as u32;
+const PACKET_EXCEPTION: u32 = b'E' as u32;
+const AUTH_NONE: u32 = b'N' as u32;
+const AUTH_KEY: u32 = b'K' as u32;
+const WRITE_REGION: u32 = 0x02;
+const WRITE_TEXT: u32 = 0x04;
+const WRITE_CURSOR: u32 = 0x20;
+const WRITE_CHARSET: u32 = 0x40;
+/// BrlAPI's TCP port for display 0; display `n` listens on `PORT + n`.
+const PORT: u16 = 4101;
+const SOCKET_DIR: &str = "/var/lib/BrlAPI";
+const KEY_FILE: &str = "/etc/brlapi.key";
+const MAX_PACKET: usize = 64 * 1024;
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where BRLTTY listens, from a `BRLAPI_HOST`-style spec: `:0` for the local socket of
+/// display 0, `host` or `host:1` for TCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrlApiHost {
+    Local(PathBuf),
+    Tcp(String, u16),
+}
+
+impl BrlApiHost {
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let (host, display) = match spec.rsplit_once(':') {
+            Some((host, display)) => {
+                let display: u16 = display
+                    .parse()
+                    .with_context(|| format!("bad BrlAPI display number in {spec:?}"))?;
+                (host, display)
+            }
+            None => (spec, 0),
+        };
+        Ok(if host.is_empty() {
+            Self::Local(Path::new(SOCKET_DIR).join(display.to_string()))
+        } else {
+            Self::Tcp(host.to_string(), PORT + display)
+        })
+    }
+}
+
+trait Connection: Read + Write + Send {}
+impl<T: Read + Write + Send> Connection for T {}
+
+/// A session with BRLTTY that owns the display.
+pub struct BrlApi {
+    stream: Box<dyn Connection>,
+    cells: usize,
+}
+
+impl BrlApi {
+    /// Connects, authenticates (with `key_file`, default `/etc/brlapi.key`, if BRLTTY asks for
+    /// a key) and takes over the display.
+    pub fn connect(host: &BrlApiHost, key_file: Option<&Path>) -> anyhow::Result<Self> {
+        let stream: Box<dyn Connection> = match host {
+            BrlApiHost::Local(path) => {
+                let stream = UnixStream::connect(path)
+                    .with_context(|| format!("failed to connect to {}", path.display()))?;
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                Box::new(stream)
+            }
+            BrlApiHost::Tcp(host, port) => {
+                let stream = TcpStream::connect((host.as_str(), *port))
+                    .with_context(|| format!("failed to connect to {host}:{port}"))?;
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                Box::new(stream)
+            }
+        };
+        let mut api = Self { stream, cells: 0 };
+
+        let (kind, version) = api.read_packet()?;
+        anyhow::ensure!(
+            kind == PACKET_VERSION,
+            "BrlAPI server didn't greet with its version"
+        );
+        let version = read_u32(&version, 0).context("short version packet")?;
+        anyhow::ensure!(
+            version == PROTOCOL_VERSION,
+            "BrlAPI protocol {version} is not supported (need {PROTOCOL_VERSION})"
+        );
+        api.write_packet(PACKET_VERSION, &PROTOCOL_VERSION.to_be_bytes())?;
+        api.authenticate(key_file)?;
+
+        api.write_packet(PACKET_GET_DISPLAY_SIZE, &[])?;
+        let (kind, size) = api.read_packet()?;
+        anyhow::ensure!(
+            kind == PACKET_GET_DISPLAY_SIZE,
+            "unexpected reply to display size"
+        );
+        let columns = read_u32(&size, 0).context("short display size")?;
+        let rows = read_u32(&size, 4).context("short display size")?;
+        api.cells = (columns * rows.max(1)) as usize;
+        anyhow::ensure!(api.cells > 0, "no braille display is connected to BRLTTY");
+
+        // No ttys: the display is ours rather than following a console.
+        let mut enter = 0u32.to_be_bytes().to_vec();
+        enter.push(0);
+        api.write_packet(PACKET_ENTER_TTY_MODE, &enter)?;
+        api.wait_for_ack()?;
+        Ok(api)
+    }
+
+    /// Cells on the display (columns times rows).
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+
+    /// Replaces the whole display with `text`, see [`fit_to_display`].
+    pub fn show(&mut self, text: &str) -> anyhow::Result<()> {
+        let text = fit_to_display(text, self.cells);
+        let mut payload = Vec::new();
+        payload.extend((WRITE_REGION | WRITE_TEXT | WRITE_CURSOR | WRITE_CHARSET).to_be_bytes());
+        payload.extend(1u32.to_be_bytes());
+        payload.extend((self.cells as u32).to_be_bytes());
+        payload.extend((text.len() as u32).to_be_bytes());
+        payload.extend(text.as_bytes());
+        // No cursor.
+        payload.extend(0u32.to_be_bytes());
+        payload.push(5);
+        payload.extend(b"UTF-8");
+        self.write_packet(PACKET_WRITE, &payload)?;
+        self.wait_for_ack()
+    }
+
+    fn authenticate(&mut self, key_file: Option<&Path>) -> anyhow::Result<()> {
+        let (kind, methods) = self.read_packet()?;
+        anyhow::ensure!(
+            kind == PACKET_AUTH,
+            "BrlAPI server didn't offer authentication"
+        );
+        let methods: Vec<u32> = (0..methods.len() / 4)
+            .filter_map(|i| read_u32(&methods, i * 4))
+            .collect();
+        if methods.contains(&AUTH_NONE) {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            methods.contains(&AUTH_KEY),
+            "BrlAPI server wants an authentication method other than a key file"
+        );
+        let path = key_file.unwrap_or(Path::new(KEY_FILE));
+        let key = std::fs::read(path)
+            .with_context(|| format!("failed to read the BrlAPI key {}", path.display()))?;
+        let mut payload = AUTH_KEY.to_be_bytes().to_vec();
+        payload.extend(key);
+        self.write_packet(PACKET_AUTH, &payload)?;
+        self.wait_for_ack().context("BrlAPI rejected the key")
+    }
+
+    fn write_packet(&mut self, kind: u32, payload: &[u8]) -> anyhow::Result<()> {
+        let mut packet = Vec::with_capacity(8 + payload.len());
+        packet.extend((payload.len() as u32).to_be_bytes());
+        packet.extend(kind.to_be_bytes());
+        packet.extend(payload);
+        self.stream.write_all(&packet)?;
+        Ok(())
+    }
+
+    fn read_packet(&mut self) -> anyhow::Result<(u32, Vec<u8>)> {
+        let mut header = [0u8; 8];
+        self.stream
+            .read_exact(&mut header)
+            .context("BrlAPI connection closed")?;
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        anyhow::ensure!(size <= MAX_PACKET, "BrlAPI packet too large ({size} bytes)");
+        let mut payload = vec![0u8; size];
+        self.stream.read_exact(&mut payload)?;
+        Ok((kind, payload))
+    }
+
+    /// Waits for the server to acknowledge the last request, skipping key presses.
+    fn wait_for_ack(&mut self) -> anyhow::Result<()> {
+        loop {
+            match self.read_packet()? {
+                (PACKET_ACK, _) => return Ok(()),
+                (PACKET_ERROR | PACKET_EXCEPTION, payload) => {
+                    let code = read_u32(&payload, 0).unwrap_or_default();
+                    anyhow::bail!("BrlAPI error {code}");
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let bytes = bytes.get(at..at + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Exactly `cells` characters for the display: the end of `text` on one line (bilingual lines
+/// separated by ` / `), starting at a word when it has to be cut, padded with spaces.
+pub fn fit_to_display(text: &str, cells: usize) -> String {
+    let line = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" / ");
+    let chars: Vec<char> = line.chars().collect();
+    let mut shown = &chars[chars.len().saturating_sub(cells)..];
+    if shown.len() < chars.len() {
+        if let Some(space) = shown.iter().position(|c| c.is_whitespace()) {
+            shown = &shown[space + 1..];
+        }
+    }
+    let mut out: String = shown.iter().collect();
+    out.extend(std::iter::repeat_n(' ', cells - shown.len()));
+    out
+}
+
+/// Sends finals to the display from a worker thread, so a slow or missing BRLTTY never holds
+/// up captions.
+pub struct BrailleSink {
+    tx: Sender<String>,
+    sound_tags: SoundTagFilter,
+}
+
+impl BrailleSink {
+    pub fn start(host: BrlApiHost, key_file: Option<PathBuf>, sound_tags: SoundTagFilter) -> Self {
+        let (tx, rx) = crossbeam_channel::bounded::<String>(8);
+        std::thread::spawn(move || {
+            let mut api: Option<BrlApi> = None;
+            let mut warned = false;
+            while let Ok(text) = rx.recv() {
+                // Only the newest caption matters once the display is behind.
+                let text = rx.try_iter().last().unwrap_or(text);
+                if api.is_none() {
+                    match BrlApi::connect(&host, key_file.as_deref()) {
+                        Ok(connected) => {
+                            tracing::info!("braille display: {} cells", connected.cells());
+                            api = Some(connected);
+                            warned = false;
+                        }
+                        Err(err) => {
+                            if !warned {
+                                tracing::warn!("--braille: {err:#}");
+                                warned = true;
+                            }
+                            continue;
+                        }
+                    }
+                }
+                if let Some(Err(err)) = api.as_mut().map(|api| api.show(&text)) {
+                    tracing::warn!("--braille: {err:#}; reconnecting on the next caption");
+                    api = None;
+                }
+            }
+        });
+        Self { tx, sound_tags }
+    }
+
+    /// Shows finals; partials change too quickly to read by touch.
+    pub fn handle(&self, event: &CaptionEvent) {
+        if let CaptionEvent::Update {
+            text,
+            is_final: true,
+            ..
+        } = event
+        {
+            let text = self.sound_tags.apply(text);
+            if !text.trim().is_empty() && self.tx.try_send(text).is_err() {
+                tracing::debug!("braille display is behind; dropping a caption");
+            }
+        }
+    }
+}
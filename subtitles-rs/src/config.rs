@@ -1,59 +1,273 @@
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use clap::{ArgAction, Parser, ValueEnum};
+use clap::{ArgAction, Parser};
 
-#[derive(Debug, Clone, ValueEnum)]
+use crate::audio::PreprocessStage;
+use crate::domain::DomainModel;
+use crate::engine_registry;
+use crate::macos_capture::CaptureTarget;
+use crate::platform::CaptureBackend;
+use crate::stdin_capture::PcmFormat;
+use crate::theme::CaptionTheme;
+use crate::transcribe::CustomModel;
+
+pub use subtitles_core::config::{
+    CaptionLayout, CommitPolicy, ItnLocale, OutputLanguage, PrintMode, SoundTagMode,
+    WhisperModelPreset,
+};
+
+/// `local`, `openai` or `custom:NAME`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Engine {
     /// On-device transcription via whisper.cpp (Metal enabled).
-    #[value(name = "local")]
     Local,
     /// Cloud transcription via OpenAI-compatible `/v1/audio/transcriptions`.
-    #[value(name = "openai", alias = "open-ai", alias = "open_ai")]
     OpenAI,
+    /// A transcriber another crate registered with `engine_registry::register_transcriber`.
+    Custom(String),
 }
 
-#[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
-pub enum OutputLanguage {
-    /// Show subtitles in Chinese (assumes the input is Chinese).
-    #[value(
-        name = "chinese",
-        alias = "zh",
-        alias = "zh-cn",
-        alias = "zh-hans",
-        alias = "cn",
-        alias = "original"
-    )]
-    Chinese,
-    /// Show subtitles in English.
-    #[value(name = "english", alias = "en")]
-    English,
-    /// Show subtitles in both Chinese and English (original + English translation).
-    #[value(name = "bilingual", alias = "bi", alias = "zh-en", alias = "en-zh")]
-    Bilingual,
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "local" => Ok(Engine::Local),
+            "openai" | "open-ai" | "open_ai" => Ok(Engine::OpenAI),
+            _ => match s.strip_prefix("custom:").map(str::trim) {
+                Some(name) if !name.is_empty() => Ok(Engine::Custom(name.to_string())),
+                _ => Err(format!(
+                    "unknown engine `{s}`; use local, openai or custom:NAME"
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Engine::Local => f.write_str("local"),
+            Engine::OpenAI => f.write_str("openai"),
+            Engine::Custom(name) => write!(f, "custom:{name}"),
+        }
+    }
+}
+
+/// What an engine can do. `start_engine` sets up the pipeline from these rather than from
+/// which engine it is, turning off (with a warning) whatever was asked for but isn't there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineCapabilities {
+    /// Fast and cheap enough to re-decode the growing segment for live partials.
+    pub streaming_partials: bool,
+    /// Translates into English itself (`--output-language english`/`bilingual`).
+    pub translation: bool,
+    /// Reports when each part of a decode was said, for `--timestamp-endpointing`.
+    pub word_timestamps: bool,
+    /// Marks where the speaker changes, for `--diarize`.
+    pub speaker_labels: bool,
 }
 
-#[derive(Debug, Clone, ValueEnum)]
-pub enum WhisperModelPreset {
-    Tiny,
-    Base,
-    Small,
-    Medium,
-    #[value(name = "large-v3", alias = "largev3", alias = "large_v3")]
-    LargeV3,
+impl Engine {
+    pub fn capabilities(&self) -> EngineCapabilities {
+        match self {
+            Engine::Local => EngineCapabilities {
+                streaming_partials: true,
+                translation: true,
+                word_timestamps: true,
+                speaker_labels: true,
+            },
+            // One upload per decode, and the API returns plain text.
+            Engine::OpenAI => EngineCapabilities {
+                streaming_partials: false,
+                translation: true,
+                word_timestamps: false,
+                speaker_labels: false,
+            },
+            // Nothing, until it is registered.
+            Engine::Custom(name) => {
+                engine_registry::custom_capabilities(name).unwrap_or(EngineCapabilities {
+                    streaming_partials: false,
+                    translation: false,
+                    word_timestamps: false,
+                    speaker_labels: false,
+                })
+            }
+        }
+    }
 }
 
 #[derive(Debug, Parser, Clone)]
-#[command(name = "subtitles", version, about = "Live subtitles for macOS (Sequoia+)")]
+#[command(
+    name = "subtitles",
+    version,
+    about = "Live subtitles for macOS (Sequoia+)",
+    args_override_self = true
+)]
 pub struct Cli {
-    /// Transcription engine to use.
-    #[arg(long, value_enum, default_value_t = Engine::Local)]
+    /// Settings file (JSON, flag name to value) applied before command-line flags. Defaults to
+    /// the file written by the setup wizard, if there is one.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Transcription engine to use: `local`, `openai`, or `custom:NAME` for one registered by
+    /// an embedding app.
+    #[arg(long, default_value_t = Engine::Local)]
     pub engine: Engine,
 
+    /// Also send finals to the OpenAI engine and keep whichever result looks better (by
+    /// agreement and confidence). Needs `--engine local` and an API key; partials stay local.
+    #[arg(long)]
+    pub ensemble: bool,
+
+    /// Also decode every final with this engine without showing the result, and log both
+    /// outputs and how often they disagree, to compare engines on your own content.
+    #[arg(long)]
+    pub shadow_engine: Option<Engine>,
+
+    /// Append a JSON line for every final compared by `--shadow-engine` to this file.
+    #[arg(long)]
+    pub shadow_log: Option<PathBuf>,
+
+    /// Privacy mode: refuse to start any engine or sink that sends audio off this machine.
+    #[arg(long)]
+    pub local_only: bool,
+
+    /// Append a JSON line for every outbound network call (destination, bytes, purpose) to this file.
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    /// Write a report to `~/Library/Logs/subtitles/crashes` when the app panics or the engine
+    /// or capture fails. Reports contain error messages and backtraces, never audio or text.
+    #[arg(long)]
+    pub crash_reports: bool,
+
+    /// Also POST each report (JSON) to this URL; implies `--crash-reports`. Unsent reports are
+    /// retried on the next start.
+    #[arg(long)]
+    pub crash_report_endpoint: Option<String>,
+
+    /// Check the release feed at startup and report a newer version (never with
+    /// `--local-only`). Off by default so the engine makes no calls of its own; the Tauri app
+    /// turns it on from its settings. Install it with `subtitles self-update`.
+    #[arg(long, default_value_t = false, action = ArgAction::Set)]
+    pub check_updates: bool,
+
+    /// Release feed used by `--check-updates`.
+    #[arg(long, default_value = crate::update::DEFAULT_FEED)]
+    pub update_feed: String,
+
+    /// What to capture: `system`, `display:<id>`, `window:<id>` or `app:<bundle id>`
+    /// (can be changed live from the overlay).
+    #[arg(long, default_value = "system")]
+    pub capture_target: CaptureTarget,
+
+    /// Leave this app's audio out of the capture (bundle id or name, e.g. `com.spotify.client`
+    /// or `Spotify`). Repeatable; applies to `system` and `display:` targets.
+    #[arg(long)]
+    pub exclude_app: Vec<String>,
+
+    /// Sample rate to request from ScreenCaptureKit (default 48000). Audio is resampled to
+    /// 16 kHz from whatever rate is delivered; the negotiated format is logged at startup.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(8_000..=192_000))]
+    pub capture_sample_rate: Option<u32>,
+
+    /// Capture from this CoreAudio input device (e.g. `BlackHole 2ch`; any part of the name,
+    /// ignoring case) instead of ScreenCaptureKit. Takes the place of `--capture-target`.
+    #[arg(long)]
+    pub input_device: Option<String>,
+
+    /// Capture backend: `auto` (ScreenCaptureKit, or the `--input-device` when one is given),
+    /// `screencapturekit`, `device`, `process-tap` (macOS 14.4+, `system` and `app:` targets
+    /// only), `stream` (`--stream-url`) or `stdin` (`--stdin-pcm`). Checked against the macOS
+    /// version at startup.
+    #[arg(long, value_enum, default_value_t = CaptureBackend::Auto)]
+    pub capture_backend: CaptureBackend,
+
+    /// Caption a network stream instead of local audio: an RTSP camera, Icecast/HTTP radio or
+    /// HLS playlist. Pulled with ffmpeg and reconnected when it drops.
+    #[arg(long)]
+    pub stream_url: Option<String>,
+
+    /// ffmpeg executable used by `--stream-url`.
+    #[arg(long, default_value = "ffmpeg")]
+    pub ffmpeg: PathBuf,
+
+    /// Read raw PCM from stdin instead of capturing, as `ENCODING:RATE:CHANNELS` with
+    /// `s16le`, `s32le` or `f32le` (e.g. `s16le:48000:2`). The engine stops at end of input.
+    #[arg(long, value_name = "ENCODING:RATE:CHANNELS")]
+    pub stdin_pcm: Option<PcmFormat>,
+
+    /// Also record the captured display to this mp4, with a sidecar `.srt` of the finals next to
+    /// it. Both use the capture clock, so they line up without adjustment.
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+
+    /// With `--record`, also write a `.ass` sidecar styled like the overlay (theme, box, font
+    /// size, speaker colors), for players and burn-in tools that render ASS.
+    #[arg(long)]
+    pub record_ass: bool,
+
+    /// Time each word of a final by aligning whisper's tokens to the audio (DTW), and use them
+    /// in `--record`'s subtitles: cues start and end with the speech, and long finals are split
+    /// where their words were spoken. Local whisper models only.
+    #[arg(long)]
+    pub word_timings: bool,
+
+    /// Rhai script whose `on_caption(event)` can rewrite or drop each caption before it is
+    /// shown or recorded (see README).
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+
+    /// Write a session summary here when captioning stops: talk time, speaking rate, scripts
+    /// and frequent terms. Markdown for a `.md` path, JSON otherwise.
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
     /// Input language (e.g. `en`, `zh`, `ja`) or `auto`.
     #[arg(long, alias = "language", default_value = "auto")]
     pub input_language: String,
 
+    /// Two languages spoken in a bilingual meeting (e.g. `en,ja`): finals are decoded once
+    /// in each, side by side on the same local model, and the decode in the language detected
+    /// in the audio is kept, instead of auto-detect flip-flopping mid-stream.
+    #[arg(
+        long,
+        value_name = "A,B",
+        value_delimiter = ',',
+        num_args = 2,
+        conflicts_with = "ensemble"
+    )]
+    pub language_pair: Vec<String>,
+
+    /// With `--language-pair`: split finals where the language changes mid-sentence and decode
+    /// each part in its own language, instead of one language for the whole utterance.
+    #[arg(long)]
+    pub code_switching: bool,
+
+    /// Text that biases transcription style, e.g. "Transcribe with punctuation and numerals as
+    /// digits." Sent as whisper's initial prompt, or the `prompt` field for cloud engines.
+    #[arg(long)]
+    pub prompt: Option<String>,
+
+    /// JSON glossary of source terms and their preferred English translations, used to prompt
+    /// and post-edit translated output (see README).
+    #[arg(long)]
+    pub glossary: Option<PathBuf>,
+
+    /// Reuse the English translation of a sentence (or a nearly identical one) when it comes up
+    /// again in bilingual mode, so recurring phrases read the same and skip a decode.
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub translation_memory: bool,
+
+    /// JSON-lines file that keeps the translation memory across sessions (implies
+    /// `--translation-memory`).
+    #[arg(long)]
+    pub translation_memory_file: Option<PathBuf>,
+
     /// Output language (English, Chinese, or bilingual; can be changed live in the overlay UI).
     #[arg(long, value_enum, default_value_t = OutputLanguage::English)]
     pub output_language: OutputLanguage,
@@ -63,14 +277,57 @@ pub struct Cli {
     #[arg(long)]
     pub no_ui: bool,
 
+    /// With `--no-ui`, print every event (partials, revisions, status, levels) as a line of
+    /// versioned JSON instead of plain finals. The schema is in the README.
+    #[arg(long)]
+    pub json: bool,
+
+    /// With `--no-ui`, prefix each printed final with where it was said,
+    /// `[HH:MM:SS.mmm --> HH:MM:SS.mmm]` since capture started.
+    #[arg(long)]
+    pub print_timestamps: bool,
+
+    /// With `--no-ui`, what to print: `finals` (default), `partials` (the live line redrawn in
+    /// place, as the overlay shows it, then each final; finals only when stdout isn't a terminal)
+    /// or `none`. Ignored with `--json`.
+    #[arg(long, value_enum, default_value_t = PrintMode::Finals)]
+    pub print: PrintMode,
+
+    /// Write the process id here while running headless, for service managers and
+    /// `kill -HUP $(cat <file>)`. Refuses to start if it names another running instance.
+    #[arg(long)]
+    pub pid_file: Option<PathBuf>,
+
     /// Enable low-latency streaming partials (local engine only).
     #[arg(long, default_value_t = true, action = ArgAction::Set)]
     pub streaming: bool,
 
+    /// Audio preprocessing stages run on the capture before the VAD, in order (comma-separated):
+    /// `dc` removes a DC offset, `highpass[:hz]` cuts rumble below a cutoff (default 80),
+    /// `agc[:dBFS]` levels quiet or loud sources towards a target (default -20), and
+    /// `denoise[:strength]` subtracts steady background noise (default 1.5). Empty by default.
+    #[arg(long, value_delimiter = ',', value_name = "STAGE")]
+    pub preprocess: Vec<PreprocessStage>,
+
     /// VAD threshold (RMS) for speech detection.
     #[arg(long, default_value_t = 0.012)]
     pub vad_threshold: f32,
 
+    /// Audio the VAD judges at once, in milliseconds.
+    #[arg(long, default_value_t = 20)]
+    pub vad_frame_ms: u32,
+
+    /// How far the VAD moves between judgements, in milliseconds (default: `--vad-frame-ms`).
+    /// Smaller than the frame makes frames overlap, e.g. `--vad-frame-ms 32 --vad-hop-ms 10`.
+    #[arg(long)]
+    pub vad_hop_ms: Option<u32>,
+
+    /// Detect speech by loudness instead of `--vad-threshold`: frames louder than this many LUFS
+    /// (K-weighted, try -50) and no more than 15 LU below the last 3 s count as speech. Holds up
+    /// better across sources mastered at different levels, e.g. films and calls.
+    #[arg(long, value_name = "LUFS", allow_negative_numbers = true)]
+    pub vad_loudness_lufs: Option<f32>,
+
     /// How long (seconds) of silence ends a speech segment.
     #[arg(long, default_value_t = 0.6)]
     pub vad_end_silence_s: f32,
@@ -87,18 +344,214 @@ pub struct Cli {
     #[arg(long, default_value_t = 300)]
     pub min_speech_ms: u64,
 
+    /// Hold an utterance's first partial until this much audio (ms) has been heard, so the
+    /// one-word guesses at sentence starts don't flash up (0 = only `--min-speech-ms`).
+    #[arg(long, default_value_t = 700)]
+    pub partial_onset_ms: u64,
+
+    /// When speech resumes within this many seconds of the last final, decode its first
+    /// partials with that much audio from before it, so they show from the first step instead
+    /// of after `--partial-onset-ms` (0 = off).
+    #[arg(long, default_value_t = 0.0)]
+    pub warm_context_s: f32,
+
     /// How often (ms) to run ASR while speech is active.
     #[arg(long, default_value_t = 350)]
     pub asr_step_ms: u64,
 
+    /// Adjust `--asr-step-ms` to the speech rate: partials come more often while captions are
+    /// changing fast and less often during slow speech, never faster than decoding keeps up.
+    #[arg(long)]
+    pub adaptive_asr_step: bool,
+
+    /// Shortest step `--adaptive-asr-step` uses.
+    #[arg(long, default_value_t = 150)]
+    pub asr_step_min_ms: u64,
+
+    /// Longest step `--adaptive-asr-step` uses.
+    #[arg(long, default_value_t = 1000)]
+    pub asr_step_max_ms: u64,
+
     /// Maximum audio window (seconds) for partial decoding (0 = full segment).
-    #[arg(long, default_value_t = 12.0)]
+    #[arg(long, visible_alias = "partial-window-s", default_value_t = 12.0)]
     pub max_window_s: f32,
 
-    /// Partial stability: how many consecutive updates a token must survive to be committed.
+    /// Context (seconds) kept when a partial window fills up and restarts. Set it to the window
+    /// size to slide the window on every step instead.
+    #[arg(long, default_value_t = 3.0)]
+    pub partial_overlap_s: f32,
+
+    /// Force a final every N seconds of continuous speech (0 = off), for regular cues on long
+    /// monologues. Audio after the cut carries over to the next segment.
+    #[arg(long, default_value_t = 0.0)]
+    pub final_interval_s: f32,
+
+    /// End utterances at sentence boundaries found in partial decodes, not just on silence
+    /// (helps when music or noise keeps the level above `--vad-threshold`).
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub timestamp_endpointing: bool,
+
+    /// How often (ms) to report the input level for the overlay's meter (0 = off).
+    #[arg(long, default_value_t = 100)]
+    pub level_interval_ms: u64,
+
+    /// How often (ms) to send live words-per-minute and talk time for the overlay's HUD
+    /// (0 = off).
+    #[arg(long, default_value_t = 1000)]
+    pub stats_interval_ms: u64,
+
+    /// Rewrite spelled-out numbers, years, times, amounts and percentages in finals as digits,
+    /// using this locale's rules (`off` or `en`).
+    #[arg(long, value_enum, default_value_t = ItnLocale::Off)]
+    pub itn: ItnLocale,
+
+    /// JSON object of words or phrases to rewrite in finals, e.g. to censor
+    /// (`{"damn": "d***"}`) or fix recurring misrecognitions (see README).
+    #[arg(long)]
+    pub replace_rules: Option<PathBuf>,
+
+    /// Program run (via `sh -c`) on each final: it gets the segment as a line of JSON on stdin
+    /// and its stdout replaces the text (see README).
+    #[arg(long)]
+    pub filter_cmd: Option<String>,
+
+    /// How long (ms) `--filter-cmd` may take per segment before it is killed and the text is
+    /// kept as is.
+    #[arg(long, default_value_t = 1000)]
+    pub filter_cmd_timeout_ms: u64,
+
+    /// Text filter stages run on each final, in order (comma-separated). `itn` applies `--itn`,
+    /// `replace` applies `--replace-rules`, `cmd` runs `--filter-cmd`; a stage without its
+    /// setting passes text through.
+    #[arg(long, value_delimiter = ',', default_value = "itn,replace,cmd")]
+    pub text_filters: Vec<String>,
+
+    /// Sound tags (`[Music]`, `(laughs)`) in the overlay: `keep`, `strip` or `glyph`.
+    #[arg(long, value_enum, default_value_t = SoundTagMode::Keep)]
+    pub overlay_sound_tags: SoundTagMode,
+
+    /// Sound tags in the transcript printed by `--no-ui`: `keep`, `strip` or `glyph`.
+    #[arg(long, value_enum, default_value_t = SoundTagMode::Keep)]
+    pub transcript_sound_tags: SoundTagMode,
+
+    /// Replacement for sound tags in `glyph` mode.
+    #[arg(long, default_value = "♪")]
+    pub sound_tag_glyph: String,
+
+    /// Hold captions this long (ms) before showing them, e.g. to line up with Bluetooth audio
+    /// latency (can be changed live from the overlay).
+    #[arg(long, default_value_t = 0)]
+    pub caption_delay_ms: u64,
+
+    /// Keep each final on screen at least this long (ms) before the next caption replaces it;
+    /// later captions wait up to this long for it (0 = off).
+    #[arg(long, default_value_t = 0)]
+    pub min_display_ms: u64,
+
+    /// Reading speed (characters per second) to pace captions for, e.g. 17. Finals too long to
+    /// read in one go are shown (and written to `--record`'s SRT) as a series of cues, each up
+    /// for its reading time (0 = off).
+    #[arg(long, default_value_t = 0.0)]
+    pub reading_cps: f32,
+
+    /// Read the English of each final aloud with macOS `say` (the translation line in bilingual
+    /// mode; nothing in Chinese mode).
+    #[arg(long)]
+    pub speak: bool,
+
+    /// Carry out spoken commands: `--wake-word` followed by pause, resume, clear, translate
+    /// <english|chinese|bilingual> or domain <name>, e.g. "subtitle pause".
+    #[arg(long)]
+    pub voice_commands: bool,
+
+    /// Word (or words) that starts a voice command.
+    #[arg(long, default_value = "subtitle")]
+    pub wake_word: String,
+
+    /// JSON object of extra voice command phrases and their actions, e.g. `{"next question":
+    /// "clear"}` (implies `--voice-commands`).
+    #[arg(long)]
+    pub voice_command_file: Option<PathBuf>,
+
+    /// Voice for `--speak` (see `say -v '?'`).
+    #[arg(long)]
+    pub speak_voice: Option<String>,
+
+    /// Output device for `--speak`, by name or id (see `say -a '?'`). Pick one that isn't being
+    /// captured, or the speech is transcribed too.
+    #[arg(long)]
+    pub speak_device: Option<String>,
+
+    /// Show finals on a refreshable braille display through BRLTTY (BrlAPI).
+    #[arg(long)]
+    pub braille: bool,
+
+    /// BrlAPI server for `--braille`: `:0` for the local BRLTTY, `host` or `host:1` over TCP.
+    #[arg(long, env = "BRLAPI_HOST", default_value = ":0")]
+    pub braille_host: String,
+
+    /// BrlAPI key file, if BRLTTY asks for one (default `/etc/brlapi.key`).
+    #[arg(long)]
+    pub braille_key: Option<PathBuf>,
+
+    /// Captions the engine keeps so a reloaded overlay or reconnecting client can redraw what
+    /// was on screen (0 = only the engine status).
+    #[arg(long, default_value_t = 20)]
+    pub recent_events: usize,
+
+    /// Seconds of the latest finals' audio kept for listening back to a caption from the
+    /// overlay (0 = none; about 4 MB per minute).
+    #[arg(long, default_value_t = 300)]
+    pub segment_audio_s: u64,
+
+    /// Go idle after this many minutes without audible input, or as soon as a `window:`/`app:`
+    /// target closes (0 = never). Resumes when audio returns.
+    #[arg(long, default_value_t = 5.0)]
+    pub idle_after_min: f32,
+
+    /// Unload the local whisper model while idle; it is reloaded when audio returns.
+    #[arg(long)]
+    pub idle_unload_model: bool,
+
+    /// While the Mac runs on battery, use `--battery-model-preset`, `--battery-asr-step-ms`
+    /// and a slower level meter; the full configuration comes back on AC power.
+    #[arg(long)]
+    pub battery_saver: bool,
+
+    /// Local model preset used on battery with `--battery-saver`, when it is smaller than
+    /// `--whisper-model-preset`.
+    #[arg(long, value_enum, default_value_t = WhisperModelPreset::Base)]
+    pub battery_model_preset: WhisperModelPreset,
+
+    /// On battery, also replace `--whisper-model`, `--whisper-custom-model` and domain models
+    /// with `--battery-model-preset` (they are kept by default).
+    #[arg(long)]
+    pub battery_replace_model: bool,
+
+    /// `--asr-step-ms` on battery with `--battery-saver`.
+    #[arg(long, default_value_t = 1000)]
+    pub battery_asr_step_ms: u64,
+
+    /// Partial stability: how many consecutive updates a token must survive to be committed
+    /// (can be changed live). Lower shows words sooner but retracts more of them.
     #[arg(long, default_value_t = 2)]
     pub partial_stable_iters: usize,
 
+    /// When words of a partial are committed: `agreement` (after `--partial-stable-iters`
+    /// updates) or `final-only` (can be changed live).
+    #[arg(long, value_enum, default_value_t = CommitPolicy::Agreement)]
+    pub commit_policy: CommitPolicy,
+
+    /// Maximum committed words kept in the live caption before older ones scroll off (0 = unbounded).
+    #[arg(long, default_value_t = 32)]
+    pub max_committed_tokens: usize,
+
+    /// Keep committed partial words in the final where it heard them differently, so captions
+    /// don't change under the viewer. Off by default: the final decode is usually better, and
+    /// this changes what `--record` and the report keep too.
+    #[arg(long)]
+    pub keep_committed_words: bool,
+
     /// Local whisper model file path. If omitted, a model will be downloaded.
     #[arg(long)]
     pub whisper_model: Option<PathBuf>,
@@ -107,6 +560,70 @@ pub struct Cli {
     #[arg(long, value_enum, default_value_t = WhisperModelPreset::Medium)]
     pub whisper_model_preset: WhisperModelPreset,
 
+    /// GPU memory whisper may use, in MB: presets are stepped down to the largest that fits,
+    /// and a model that still doesn't fit runs on the CPU instead of Metal, leaving the GPU to
+    /// other apps (screen sharing, games).
+    #[arg(long, value_name = "MB")]
+    pub gpu_memory_limit_mb: Option<u64>,
+
+    /// A model of your own to download, as `NAME=URL`, optionally ending in `#sha256=HEX` to
+    /// check the download against. Repeatable; pick one with `--whisper-custom-model`.
+    #[arg(long, value_name = "NAME=URL")]
+    pub custom_model: Vec<CustomModel>,
+
+    /// `--custom-model` to use instead of the preset (downloaded to models/ if needed).
+    #[arg(long, value_name = "NAME", conflicts_with = "whisper_model")]
+    pub whisper_custom_model: Option<String>,
+
+    /// Host to download model presets from instead of huggingface.co (e.g.
+    /// `https://hf-mirror.com`). Repeatable, tried in order; defaults to `$HF_ENDPOINT`.
+    #[arg(long, value_name = "URL")]
+    pub model_mirror: Vec<String>,
+
+    /// Fine-tuned local model for a domain, as `NAME=PATH` (e.g. `medical=models/med.bin`).
+    /// Repeatable; switch between them with `--domain`, a second launch or the overlay.
+    #[arg(long, value_name = "NAME=PATH")]
+    pub domain_model: Vec<DomainModel>,
+
+    /// Domain model to start with (`default` is `--whisper-model`; can be changed live).
+    #[arg(long)]
+    pub domain: Option<String>,
+
+    /// Color finals by speaker, from whisper.cpp's tinydiarize speaker turns. Needs a
+    /// tinydiarize model (e.g. `--whisper-model ggml-small.en-tdrz.bin`) and the local engine.
+    #[arg(long)]
+    pub diarize: bool,
+
+    /// Enrolled voices (`subtitles enroll-voice`); finals that sound like one are labeled with
+    /// that person's name, with or without `--diarize`.
+    #[arg(long)]
+    pub voice_profiles: Option<PathBuf>,
+
+    /// How close (0..1; samples of one voice score about 0.9, different voices under 0.7) a
+    /// final's voice must be to an enrolled one to be labeled with it.
+    #[arg(long, default_value_t = 0.8)]
+    pub voice_match: f32,
+
+    /// Start finals in which two people talk at once with `[overlapping]` and lower their
+    /// confidence (so `--second-pass-max-confidence` re-runs them), instead of passing off
+    /// whisper's merge of both voices as confident text.
+    #[arg(long)]
+    pub mark_crosstalk: bool,
+
+    /// Larger local model file used to re-transcribe finalized segments in the background.
+    /// Revisions are emitted as `Revise` events for the history view and exports.
+    #[arg(long)]
+    pub second_pass_model: Option<PathBuf>,
+
+    /// Model preset for the second pass (downloaded if needed) when `--second-pass-model` is not provided.
+    #[arg(long, value_enum)]
+    pub second_pass_preset: Option<WhisperModelPreset>,
+
+    /// Only re-transcribe segments whose first-pass confidence (mean token probability, 0-1)
+    /// is below this value. Segments from engines without confidence scores are always re-run.
+    #[arg(long)]
+    pub second_pass_max_confidence: Option<f32>,
+
     /// Number of threads to use for local Whisper decoding (defaults to CPU count).
     #[arg(long)]
     pub whisper_threads: Option<usize>,
@@ -134,4 +651,31 @@ pub struct Cli {
     /// Overlay width as a fraction of screen width (0.1 - 1.0).
     #[arg(long, default_value_t = 0.85)]
     pub overlay_width_frac: f32,
+
+    /// Caption colors, outline, background box and spacing (UI mode only). Every theme except
+    /// `default` meets WCAG AAA contrast; switch themes from the overlay while it runs.
+    #[arg(long, value_enum, default_value_t = CaptionTheme::Default)]
+    pub caption_theme: CaptionTheme,
+
+    /// Solid box behind the caption text (`#rrggbb`), in place of the theme's; shadows alone
+    /// are unreadable over bright video (UI mode only).
+    #[arg(long)]
+    pub caption_box_color: Option<String>,
+
+    /// Caption box opacity, 0 (no box) to 1 (solid); defaults to the theme's.
+    #[arg(long)]
+    pub caption_box_opacity: Option<f32>,
+
+    /// Room between the caption text and the edge of its box, in em (0 - 2).
+    #[arg(long)]
+    pub caption_box_padding_em: Option<f32>,
+
+    /// `vertical` sets captions top to bottom in columns read right to left, as is usual for
+    /// Japanese subtitles, in the overlay and in `--no-ui` output.
+    #[arg(long, value_enum, default_value_t = CaptionLayout::Horizontal)]
+    pub caption_layout: CaptionLayout,
+
+    /// Characters per column with `--caption-layout vertical` in `--no-ui` output.
+    #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u16).range(2..))]
+    pub vertical_rows: u16,
 }
@@ -0,0 +1,232 @@
+//! Opt-in crash and error reports (`--crash-reports`): panics and engine failures are written
+//! as JSON files so intermittent capture problems leave something to debug. With
+//! `--crash-report-endpoint` they are also POSTed there; unsent reports are retried on the next
+//! start.
+//!
+//! Reports hold error messages, a backtrace and version info, never audio. A panic message
+//! can quote caption text (a bad string slice prints the whole string), so it stays in the
+//! local file and only the panic's location and backtrace are uploaded.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::network::{audit, AuditRecord};
+use crate::platform::Capabilities;
+
+/// Submitted reports are moved here, under the report directory.
+const SENT_DIR: &str = "sent";
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static CONFIG: Mutex<Option<ReporterConfig>> = Mutex::new(None);
+static PANIC_HOOK: Once = Once::new();
+/// Keeps two uploads from sending the same files.
+static SUBMITTING: AtomicBool = AtomicBool::new(false);
+/// The macOS version, detected once by `enable`: detecting runs `sw_vers`, too slow for the
+/// capture callbacks reports are made from.
+static MACOS: OnceLock<Option<String>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct ReporterConfig {
+    pub dir: PathBuf,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// `panic` or `error`.
+    pub kind: String,
+    /// Where it happened: the panic location, or the component that failed.
+    pub context: String,
+    pub message: String,
+    pub thread: Option<String>,
+    pub backtrace: Option<String>,
+    pub version: String,
+    pub macos: Option<String>,
+    pub ts_ms: u64,
+}
+
+impl CrashReport {
+    pub fn new(kind: &str, context: &str, message: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            context: context.to_string(),
+            message: message.to_string(),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: None,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            macos: MACOS.get().cloned().flatten(),
+            ts_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+        }
+    }
+
+    /// The report as `--crash-report-endpoint` gets it: without a panic's message.
+    pub fn for_upload(mut self) -> Self {
+        if self.kind == "panic" {
+            self.message = "panic".to_string();
+        }
+        self
+    }
+}
+
+/// `~/Library/Logs/subtitles/crashes`.
+pub fn default_report_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Logs/subtitles/crashes"))
+}
+
+/// Turns reporting on for the rest of the process (calling it again replaces the settings) and
+/// starts sending any reports left over from earlier runs.
+pub fn enable(config: ReporterConfig) -> anyhow::Result<()> {
+    fs::create_dir_all(&config.dir)
+        .with_context(|| format!("failed to create {}", config.dir.display()))?;
+    tracing::info!("writing crash reports to {}", config.dir.display());
+    MACOS.get_or_init(|| Capabilities::detect().version.map(|v| v.to_string()));
+    *CONFIG.lock().unwrap_or_else(|e| e.into_inner()) = Some(config);
+
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let location = info
+                .location()
+                .map_or_else(|| "unknown".to_string(), |l| l.to_string());
+            let message = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".to_string());
+            let mut report = CrashReport::new("panic", &location, &message);
+            report.backtrace = Some(std::backtrace::Backtrace::force_capture().to_string());
+            // Sending happens on the next start; the process may be going down.
+            if let Some(config) = current() {
+                let _ = write_report(&config.dir, &report);
+            }
+            previous(info);
+        }));
+    });
+
+    submit_in_background();
+    Ok(())
+}
+
+/// Records an engine or capture failure. No-op unless reporting is enabled. The report is
+/// written on another thread, so this is safe to call from capture callbacks.
+pub fn report_error(context: &str, message: &str) {
+    let Some(config) = current() else {
+        return;
+    };
+    let report = CrashReport::new("error", context, message);
+    std::thread::spawn(move || {
+        match write_report(&config.dir, &report) {
+            Ok(path) => tracing::info!("wrote error report {}", path.display()),
+            Err(err) => tracing::warn!("failed to write error report: {err:#}"),
+        }
+        submit_in_background();
+    });
+}
+
+fn current() -> Option<ReporterConfig> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Writes `report` as `<kind>-<time>.json` in `dir`.
+pub fn write_report(dir: &Path, report: &CrashReport) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut path = dir.join(format!("{}-{}.json", report.kind, report.ts_ms));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{}-{}-{n}.json", report.kind, report.ts_ms));
+        n += 1;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(report)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Reports in `dir` that haven't been submitted, oldest first.
+pub fn pending_reports(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    reports.sort_by_key(|path| report_order(path));
+    Ok(reports)
+}
+
+/// `(time, n)` from a `<kind>-<time>[-<n>].json` name.
+fn report_order(path: &Path) -> (u64, u64) {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut numbers = stem.split('-').skip(1).map(|part| part.parse().unwrap_or(0));
+    (numbers.next().unwrap_or(0), numbers.next().unwrap_or(0))
+}
+
+fn submit_in_background() {
+    let Some(ReporterConfig {
+        dir,
+        endpoint: Some(endpoint),
+    }) = current()
+    else {
+        return;
+    };
+    if SUBMITTING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    std::thread::spawn(move || {
+        if let Err(err) = submit_pending(&dir, &endpoint) {
+            tracing::warn!("failed to submit crash reports: {err:#}");
+        }
+        SUBMITTING.store(false, Ordering::Release);
+    });
+}
+
+fn submit_pending(dir: &Path, endpoint: &str) -> anyhow::Result<()> {
+    let reports = pending_reports(dir)?;
+    if reports.is_empty() {
+        return Ok(());
+    }
+    let client = reqwest::blocking::Client::builder()
+        .timeout(SUBMIT_TIMEOUT)
+        .build()
+        .context("failed to build HTTP client")?;
+    let sent = dir.join(SENT_DIR);
+    fs::create_dir_all(&sent)?;
+    for path in reports {
+        let report: CrashReport = serde_json::from_slice(&fs::read(&path)?)
+            .with_context(|| format!("invalid crash report {}", path.display()))?;
+        let body = serde_json::to_vec(&report.for_upload())?;
+        let bytes_sent = body.len() as u64;
+        let result = client
+            .post(endpoint)
+            .header("content-type", "application/json")
+            .body(body)
+            .send();
+        audit(AuditRecord {
+            purpose: "crash-report",
+            method: "POST",
+            destination: endpoint,
+            bytes_sent,
+            bytes_received: 0,
+            status: result.as_ref().ok().map(|r| r.status().as_u16()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        let response = result.context("crash report upload failed")?;
+        anyhow::ensure!(
+            response.status().is_success(),
+            "crash report endpoint returned {}",
+            response.status()
+        );
+        if let Some(name) = path.file_name() {
+            fs::rename(&path, sent.join(name))?;
+        }
+    }
+    Ok(())
+}
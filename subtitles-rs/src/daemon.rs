@@ -0,0 +1,117 @@
+//! Running headless as a background service (e.g. under launchd): a pid file, and signals
+//! for stopping (SIGTERM) and reloading settings (SIGHUP).
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Context;
+
+static TERMINATE: AtomicBool = AtomicBool::new(false);
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // Only async-signal-safe work here: the headless loop polls the flags.
+    match signal {
+        libc::SIGHUP => RELOAD.store(true, Ordering::Relaxed),
+        _ => TERMINATE.store(true, Ordering::Relaxed),
+    }
+}
+
+/// SIGTERM and SIGHUP, turned into flags the headless loop checks. SIGINT stays with the
+/// Ctrl-C handler.
+#[derive(Debug)]
+pub struct Signals(());
+
+impl Signals {
+    pub fn install() -> anyhow::Result<Self> {
+        for signal in [libc::SIGTERM, libc::SIGHUP] {
+            let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            // SAFETY: the handler only stores to atomics.
+            if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("failed to handle signal {signal}"));
+            }
+        }
+        Ok(Self(()))
+    }
+
+    /// True once SIGTERM has arrived.
+    pub fn terminate_requested(&self) -> bool {
+        TERMINATE.load(Ordering::Relaxed)
+    }
+
+    /// True once per SIGHUP (several in quick succession count once).
+    pub fn take_reload(&self) -> bool {
+        RELOAD.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Holds `--pid-file` for the life of the process and removes it on drop.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Writes this process's pid to `path`. Fails if the file names another process that is
+    /// still running; a stale file from a crashed run is replaced.
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(existing) => {
+                if let Some(pid) = existing
+                    .trim()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&p| is_running(p))
+                {
+                    anyhow::bail!(
+                        "another instance is running (pid {pid}, from {})",
+                        path.display()
+                    );
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave it alone if something else has taken it over since.
+        let ours = fs::read_to_string(&self.path)
+            .is_ok_and(|pid| pid.trim() == std::process::id().to_string());
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn is_running(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists; EPERM means it does but isn't ours.
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
@@ -0,0 +1,162 @@
+//! `--input-device`: capture from a named CoreAudio input (BlackHole, Loopback, a USB
+//! interface) instead of ScreenCaptureKit. Needs no Screen Recording permission and works on
+//! macOS versions without ScreenCaptureKit audio, but the user has to route audio there.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, SampleFormat, SizedSample, StreamConfig};
+use crossbeam_channel::Sender;
+
+use crate::audio::{AudioChunk, ChunkClock, Resampler};
+
+/// First input device whose name contains `name`, ignoring case.
+fn find_device(name: &str) -> anyhow::Result<Device> {
+    let wanted = name.to_lowercase();
+    let mut available = Vec::new();
+    for device in cpal::default_host()
+        .input_devices()
+        .context("failed to list audio input devices")?
+    {
+        let Ok(device_name) = device.name() else {
+            continue;
+        };
+        if device_name.to_lowercase().contains(&wanted) {
+            return Ok(device);
+        }
+        available.push(device_name);
+    }
+    anyhow::bail!(
+        "no audio input device matching `{name}` (available: {})",
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        }
+    )
+}
+
+/// One line per input device plus the default output, with default formats, for
+/// `subtitles diagnose`.
+pub fn describe_audio_devices() -> anyhow::Result<Vec<String>> {
+    let host = cpal::default_host();
+    let describe = |config: Result<cpal::SupportedStreamConfig, _>| match config {
+        Ok(config) => format!(
+            "{} Hz, {} ch, {}",
+            config.sample_rate().0,
+            config.channels(),
+            config.sample_format()
+        ),
+        Err(err) => format!("no default format ({err})"),
+    };
+    let mut lines = Vec::new();
+    if let Some(output) = host.default_output_device() {
+        let name = output.name().unwrap_or_else(|_| "?".to_string());
+        lines.push(format!(
+            "default output: {name}: {}",
+            describe(output.default_output_config())
+        ));
+    }
+    for device in host
+        .input_devices()
+        .context("failed to list audio input devices")?
+    {
+        let name = device.name().unwrap_or_else(|_| "?".to_string());
+        lines.push(format!("input: {name}: {}", describe(device.default_input_config())));
+    }
+    Ok(lines)
+}
+
+/// Starts capturing on a background thread and returns once the stream is running, so a
+/// missing device or unsupported format fails at startup.
+pub fn start_device_capture(
+    name: &str,
+    audio_tx: Sender<AudioChunk>,
+    stop: Arc<AtomicBool>,
+) -> anyhow::Result<std::thread::JoinHandle<()>> {
+    let name = name.to_string();
+    let (ready_tx, ready_rx) = crossbeam_channel::bounded::<anyhow::Result<()>>(1);
+    let handle = std::thread::spawn(move || {
+        // The stream isn't `Send` on macOS, so it lives and dies on this thread.
+        let stream = match open_stream(&name, audio_tx) {
+            Ok(stream) => stream,
+            Err(err) => {
+                let _ = ready_tx.send(Err(err));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        while !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        tracing::info!("stopping capture");
+        drop(stream);
+    });
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(handle),
+        Ok(Err(err)) => Err(err),
+        Err(_) => anyhow::bail!("capture thread exited during startup"),
+    }
+}
+
+fn open_stream(name: &str, audio_tx: Sender<AudioChunk>) -> anyhow::Result<cpal::Stream> {
+    let device = find_device(name)?;
+    let device_name = device.name().unwrap_or_else(|_| name.to_string());
+    let supported = device
+        .default_input_config()
+        .with_context(|| format!("`{device_name}` has no usable input format"))?;
+    let config = supported.config();
+    tracing::info!(
+        "capturing from `{device_name}`: {} Hz, {} ch, {}",
+        config.sample_rate.0,
+        config.channels,
+        supported.sample_format()
+    );
+
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, audio_tx, |s| s),
+        SampleFormat::I16 => {
+            build_stream::<i16>(&device, &config, audio_tx, |s| s as f32 / 32768.0)
+        }
+        SampleFormat::I32 => {
+            build_stream::<i32>(&device, &config, audio_tx, |s| s as f32 / 2_147_483_648.0)
+        }
+        other => anyhow::bail!("unsupported sample format {other} on `{device_name}`"),
+    }?;
+    stream
+        .play()
+        .with_context(|| format!("failed to start capture from `{device_name}`"))?;
+    Ok(stream)
+}
+
+fn build_stream<T: SizedSample>(
+    device: &Device,
+    config: &StreamConfig,
+    audio_tx: Sender<AudioChunk>,
+    to_f32: fn(T) -> f32,
+) -> anyhow::Result<cpal::Stream> {
+    let channels = usize::from(config.channels.max(1));
+    let mut resampler = Resampler::new(config.sample_rate.0, 16_000);
+    let mut clock = ChunkClock::asr();
+    let stream = device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut out = Vec::with_capacity(data.len() / channels / 2);
+                for frame in data.chunks_exact(channels) {
+                    let mono = frame.iter().map(|&s| to_f32(s)).sum::<f32>() / channels as f32;
+                    resampler.push(mono, &mut out);
+                }
+                if !out.is_empty() {
+                    let _ = audio_tx.try_send(clock.stamp(out));
+                }
+            },
+            |err| tracing::warn!("audio input error: {err}"),
+            None,
+        )
+        .context("failed to open audio input stream")?;
+    Ok(stream)
+}
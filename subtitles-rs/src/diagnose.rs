@@ -0,0 +1,269 @@
+//! `subtitles diagnose`: gathers what a bug report usually ends up asking for (macOS version,
+//! permissions, audio devices, models, settings, recent logs and a short capture sample) into
+//! one zip to attach.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+
+use crate::config::WhisperModelPreset;
+use crate::crash::{default_report_dir, pending_reports};
+use crate::device_capture::describe_audio_devices;
+use crate::macos_capture::{list_capture_sources, CaptureTarget};
+use crate::platform::Capabilities;
+use crate::service::ServicePaths;
+use crate::settings::{default_settings_path, load_settings, Settings};
+use crate::setup::{check_permissions, record_sample};
+use crate::transcribe::{encode_wav_16k_mono_i16, preset_model_path};
+
+/// Settings whose values are never copied into a bundle.
+const SECRET_SETTINGS: [&str; 3] = ["key", "token", "secret"];
+/// Newest unsent crash reports to include.
+const MAX_CRASH_REPORTS: usize = 10;
+/// How much of each log file to include, from the end.
+const LOG_TAIL_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles diagnose",
+    about = "Collect system and capture details into a zip for bug reports"
+)]
+pub struct DiagnoseCli {
+    /// Where to write the zip (default `subtitles-diagnostics-<time>.zip` here).
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Seconds of system audio to record into the bundle (0 = none). Play something that
+    /// shows the problem while it runs.
+    #[arg(long, default_value_t = 5)]
+    pub capture_s: u64,
+}
+
+pub fn run(cli: DiagnoseCli) -> anyhow::Result<()> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let name = format!("subtitles-diagnostics-{stamp}");
+    let output = cli
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("{name}.zip")));
+    let staging = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+    let bundle = staging.join(&name);
+    fs::create_dir_all(&bundle)
+        .with_context(|| format!("failed to create {}", bundle.display()))?;
+
+    let result = collect(&bundle, cli.capture_s).and_then(|()| zip(&bundle, &output));
+    let _ = fs::remove_dir_all(&staging);
+    result?;
+    println!("wrote {}", output.display());
+    println!("Check it over before sharing: it includes settings (secrets removed) and logs.");
+    Ok(())
+}
+
+fn collect(bundle: &Path, capture_s: u64) -> anyhow::Result<()> {
+    // Each section records its own failure, so one broken probe doesn't lose the rest.
+    let section = |name: &str, contents: anyhow::Result<String>| {
+        let contents = contents.unwrap_or_else(|err| format!("error: {err:#}\n"));
+        fs::write(bundle.join(name), contents).with_context(|| format!("failed to write {name}"))
+    };
+    section("system.txt", Ok(system_info()))?;
+    section("permissions.txt", Ok(permission_info()))?;
+    section("audio.txt", audio_info())?;
+    section("models.txt", Ok(model_info()))?;
+    section("settings.json", settings_info())?;
+
+    let logs = bundle.join("logs");
+    fs::create_dir_all(&logs)?;
+    if let Ok(home) = std::env::var("HOME") {
+        let service = ServicePaths::for_home(Path::new(&home));
+        for path in [service.log, service.transcript] {
+            if let Ok(bytes) = fs::read(&path) {
+                let name = path.file_name().unwrap_or_default();
+                fs::write(logs.join(name), tail(&bytes, LOG_TAIL_BYTES))?;
+            }
+        }
+    }
+
+    if let Some(dir) = default_report_dir() {
+        if let Ok(reports) = pending_reports(&dir) {
+            let crashes = bundle.join("crashes");
+            fs::create_dir_all(&crashes)?;
+            for path in reports.iter().rev().take(MAX_CRASH_REPORTS) {
+                if let Some(name) = path.file_name() {
+                    fs::copy(path, crashes.join(name))?;
+                }
+            }
+        }
+    }
+
+    if capture_s > 0 {
+        println!("recording {capture_s} s of system audio...");
+        match capture_sample(Duration::from_secs(capture_s)) {
+            Ok((summary, wav)) => {
+                section("capture.txt", Ok(summary))?;
+                fs::write(bundle.join("capture.wav"), wav)?;
+            }
+            Err(err) => section("capture.txt", Err(err))?,
+        }
+    }
+    Ok(())
+}
+
+fn system_info() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "subtitles {}", env!("CARGO_PKG_VERSION"));
+    let capabilities = Capabilities::detect();
+    let version = capabilities
+        .version
+        .map_or_else(|| "unknown".to_string(), |v| v.to_string());
+    let _ = writeln!(out, "macOS: {version}");
+    let _ = writeln!(
+        out,
+        "screencapturekit audio: {}, process taps: {}, recording: {}",
+        capabilities.screencapturekit_audio(),
+        capabilities.process_tap(),
+        capabilities.recording_output()
+    );
+    let _ = writeln!(out, "arch: {}", std::env::consts::ARCH);
+    for key in [
+        "hw.model",
+        "machdep.cpu.brand_string",
+        "hw.memsize",
+        "hw.ncpu",
+    ] {
+        if let Some(value) = command_output("sysctl", &["-n", key]) {
+            let _ = writeln!(out, "{key}: {value}");
+        }
+    }
+    out
+}
+
+fn permission_info() -> String {
+    let status = check_permissions();
+    let mut out = format!("screen recording: {}\n", status.screen_recording);
+    if let Some(detail) = status.detail {
+        let _ = writeln!(out, "detail: {detail}");
+    }
+    out
+}
+
+fn audio_info() -> anyhow::Result<String> {
+    let mut out = String::new();
+    match describe_audio_devices() {
+        Ok(devices) => {
+            for device in devices {
+                let _ = writeln!(out, "{device}");
+            }
+        }
+        Err(err) => {
+            let _ = writeln!(out, "devices: error: {err:#}");
+        }
+    }
+    // Window titles can be private, so only displays and apps are listed.
+    let sources = list_capture_sources()?;
+    let windows = sources
+        .iter()
+        .filter(|source| matches!(source.target, CaptureTarget::Window(_)))
+        .count();
+    for source in &sources {
+        if !matches!(source.target, CaptureTarget::Window(_)) {
+            let _ = writeln!(out, "capture source: {} ({})", source.target, source.name);
+        }
+    }
+    let _ = writeln!(out, "capturable windows: {windows}");
+    Ok(out)
+}
+
+fn model_info() -> String {
+    let mut out = String::new();
+    for preset in WhisperModelPreset::value_variants() {
+        let Some(name) = preset.to_possible_value() else {
+            continue;
+        };
+        let path = preset_model_path(preset);
+        let size = fs::metadata(&path).map(|meta| meta.len());
+        let _ = match size {
+            Ok(bytes) => writeln!(
+                out,
+                "{}: {} ({bytes} bytes)",
+                name.get_name(),
+                path.display()
+            ),
+            Err(_) => writeln!(out, "{}: not downloaded", name.get_name()),
+        };
+    }
+    out
+}
+
+fn settings_info() -> anyhow::Result<String> {
+    let Some(path) = default_settings_path().filter(|path| path.exists()) else {
+        return Ok("{}\n".to_string());
+    };
+    let settings = redact_settings(load_settings(&path)?);
+    Ok(serde_json::to_string_pretty(&settings)? + "\n")
+}
+
+/// Replaces the values of settings that look like credentials.
+pub fn redact_settings(mut settings: Settings) -> Settings {
+    for (key, value) in settings.iter_mut() {
+        let key = key.to_lowercase();
+        if SECRET_SETTINGS.iter().any(|secret| key.contains(secret)) && !value.is_null() {
+            *value = serde_json::Value::String("<redacted>".to_string());
+        }
+    }
+    settings
+}
+
+/// The last `max` bytes, starting at a line boundary when there is one.
+pub fn tail(bytes: &[u8], max: usize) -> &[u8] {
+    if bytes.len() <= max {
+        return bytes;
+    }
+    let rest = &bytes[bytes.len() - max..];
+    match rest.iter().position(|&b| b == b'\n') {
+        Some(newline) => &rest[newline + 1..],
+        None => rest,
+    }
+}
+
+fn capture_sample(duration: Duration) -> anyhow::Result<(String, Vec<u8>)> {
+    let (test, audio) = record_sample(CaptureTarget::System, duration)?;
+    let summary = format!(
+        "duration: {:.1} s\nsamples: {} (16 kHz mono)\npeak: {:.4}\nrms: {:.4}\nheard audio: {}\n",
+        test.duration_s,
+        audio.len(),
+        test.max_level.peak,
+        test.max_level.rms,
+        test.heard_audio
+    );
+    Ok((summary, encode_wav_16k_mono_i16(&audio)?))
+}
+
+fn zip(dir: &Path, output: &Path) -> anyhow::Result<()> {
+    // `ditto` ships with every macOS and writes zips Finder and Archive Utility open.
+    let status = Command::new("ditto")
+        .args(["-c", "-k", "--keepParent"])
+        .arg(dir)
+        .arg(output)
+        .status()
+        .context("failed to run ditto")?;
+    anyhow::ensure!(
+        status.success(),
+        "ditto failed to write {}",
+        output.display()
+    );
+    Ok(())
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
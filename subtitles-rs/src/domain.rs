@@ -0,0 +1,114 @@
+//! Domain models (`--domain-model medical=/models/medical.bin`): fine-tuned whisper models for
+//! a subject's vocabulary, declared up front and switched between while the engine runs
+//! (`--domain`, a second launch, or the overlay). `default` is the regular `--whisper-model`.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use crate::transcribe::{inspect_model, WhisperModelInfo};
+
+/// The name that selects the regular model.
+pub const DEFAULT_DOMAIN: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainModel {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl FromStr for DomainModel {
+    type Err = String;
+
+    /// `NAME=PATH`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=PATH, got `{s}`"))?;
+        let name = name.trim().to_ascii_lowercase();
+        if name.is_empty() || path.trim().is_empty() {
+            return Err(format!("expected NAME=PATH, got `{s}`"));
+        }
+        if name == DEFAULT_DOMAIN {
+            return Err(format!(
+                "`{DEFAULT_DOMAIN}` is the regular model; pick another name"
+            ));
+        }
+        Ok(Self {
+            name,
+            path: PathBuf::from(path.trim()),
+        })
+    }
+}
+
+impl DomainModel {
+    /// Checks that the file is a whisper model whisper.cpp can load, so a bad fine-tune fails
+    /// at startup rather than when it is switched to.
+    pub fn validate(&self) -> anyhow::Result<WhisperModelInfo> {
+        inspect_model(&self.path).with_context(|| format!("domain model `{}`", self.name))
+    }
+}
+
+/// The declared domain models and which one is selected, shared with the transcription
+/// thread (which reloads when it changes).
+#[derive(Debug, Clone, Default)]
+pub struct SharedDomainModel {
+    models: Arc<[DomainModel]>,
+    selected: Arc<parking_lot::Mutex<Option<usize>>>,
+}
+
+impl SharedDomainModel {
+    /// Fails on duplicate names or an unknown `initial` name.
+    pub fn new(models: Vec<DomainModel>, initial: Option<&str>) -> anyhow::Result<Self> {
+        for (i, model) in models.iter().enumerate() {
+            anyhow::ensure!(
+                !models[..i].iter().any(|other| other.name == model.name),
+                "domain model `{}` is declared twice",
+                model.name
+            );
+        }
+        let domain = Self {
+            models: models.into(),
+            selected: Arc::default(),
+        };
+        if let Some(name) = initial {
+            domain.select(name)?;
+        }
+        Ok(domain)
+    }
+
+    pub fn models(&self) -> &[DomainModel] {
+        &self.models
+    }
+
+    /// Switches to `name`, or back to the regular model for `default`.
+    pub fn select(&self, name: &str) -> anyhow::Result<()> {
+        let name = name.trim().to_ascii_lowercase();
+        let index = if name == DEFAULT_DOMAIN {
+            None
+        } else {
+            let index = self.models.iter().position(|model| model.name == name);
+            Some(index.with_context(|| {
+                let known: Vec<&str> = self.models.iter().map(|m| m.name.as_str()).collect();
+                format!(
+                    "unknown domain `{name}` (known: {DEFAULT_DOMAIN}, {})",
+                    known.join(", ")
+                )
+            })?)
+        };
+        *self.selected.lock() = index;
+        Ok(())
+    }
+
+    /// The selected domain model; `None` is the regular one.
+    pub fn selected(&self) -> Option<DomainModel> {
+        (*self.selected.lock()).map(|index| self.models[index].clone())
+    }
+
+    pub fn selected_name(&self) -> String {
+        self.selected()
+            .map_or_else(|| DEFAULT_DOMAIN.to_string(), |model| model.name)
+    }
+}
@@ -0,0 +1,84 @@
+//! Transcribers from other crates, selectable as `--engine custom:NAME` (and
+//! `--shadow-engine`) next to the built-in engines. Register them before `start_engine`:
+//!
+//! ```ignore
+//! subtitles::engine_registry::register_transcriber("vosk", capabilities, || {
+//!     Ok(Box::new(VoskTranscriber::new("models/vosk")?) as Box<dyn Transcriber>)
+//! });
+//! ```
+//!
+//! A factory runs on the transcription thread, so slow setup (loading a model) doesn't hold up
+//! capture. One that sends audio off the machine must call `network::ensure_egress_allowed`
+//! first, so `--local-only` is honored.
+
+use std::sync::{Arc, Mutex};
+
+use crate::config::EngineCapabilities;
+use crate::transcribe::Transcriber;
+
+pub type TranscriberFactory =
+    Arc<dyn Fn() -> anyhow::Result<Box<dyn Transcriber>> + Send + Sync + 'static>;
+
+#[derive(Clone)]
+struct Registered {
+    name: String,
+    capabilities: EngineCapabilities,
+    factory: TranscriberFactory,
+}
+
+static REGISTRY: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+fn registry() -> std::sync::MutexGuard<'static, Vec<Registered>> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Makes `factory` selectable as `--engine custom:<name>` (names ignore case); registering a
+/// name again replaces it. `capabilities` says what the pipeline can ask of it.
+pub fn register_transcriber(
+    name: &str,
+    capabilities: EngineCapabilities,
+    factory: impl Fn() -> anyhow::Result<Box<dyn Transcriber>> + Send + Sync + 'static,
+) {
+    let name = name.trim().to_ascii_lowercase();
+    let mut registry = registry();
+    registry.retain(|existing| existing.name != name);
+    registry.push(Registered {
+        name,
+        capabilities,
+        factory: Arc::new(factory),
+    });
+}
+
+/// The registered names, in registration order.
+pub fn registered_transcribers() -> Vec<String> {
+    registry().iter().map(|entry| entry.name.clone()).collect()
+}
+
+/// What the transcriber registered as `name` can do, if there is one.
+pub fn custom_capabilities(name: &str) -> Option<EngineCapabilities> {
+    registry()
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name))
+        .map(|entry| entry.capabilities)
+}
+
+/// The factory registered as `name`, or an error listing the ones that are.
+pub fn custom_factory(name: &str) -> anyhow::Result<TranscriberFactory> {
+    let registry = registry();
+    match registry
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name))
+    {
+        Some(entry) => Ok(entry.factory.clone()),
+        None if registry.is_empty() => {
+            anyhow::bail!("no transcriber is registered as `custom:{name}` (none are registered)")
+        }
+        None => {
+            let known: Vec<&str> = registry.iter().map(|entry| entry.name.as_str()).collect();
+            anyhow::bail!(
+                "no transcriber is registered as `custom:{name}` (available: {})",
+                known.join(", ")
+            )
+        }
+    }
+}
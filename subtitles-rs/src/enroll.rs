@@ -0,0 +1,93 @@
+//! `subtitles enroll-voice`: adds a person's voice to the `--voice-profiles` file from short
+//! recordings of them talking, so live captions can be labeled with their name.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::Parser;
+
+use crate::audio::{VoicePrint, VoiceProfiles};
+use crate::batch::read_wav_16k_mono;
+use crate::macos_capture::CaptureTarget;
+use crate::setup::record_sample;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles enroll-voice",
+    about = "Enroll a person's voice for labeling captions with their name"
+)]
+pub struct EnrollVoiceCli {
+    /// Who is talking in the samples; enrolling a name again adds to its profile.
+    #[arg(long)]
+    pub name: String,
+
+    /// Profiles file to add to (created if missing; default `voices.json` next to the
+    /// settings).
+    #[arg(long)]
+    pub voice_profiles: Option<PathBuf>,
+
+    /// Also record this many seconds from `--capture-target` while they talk.
+    #[arg(long)]
+    pub capture_s: Option<u64>,
+
+    #[arg(long, default_value = "system")]
+    pub capture_target: CaptureTarget,
+
+    /// WAV recordings of them alone, each with at least a second of speech (10-30 s is
+    /// better).
+    pub samples: Vec<PathBuf>,
+}
+
+/// `~/Library/Application Support/subtitles/voices.json`.
+pub fn default_voice_profiles_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(
+        PathBuf::from(home)
+            .join("Library/Application Support/subtitles")
+            .join("voices.json"),
+    )
+}
+
+pub fn run(cli: EnrollVoiceCli) -> anyhow::Result<()> {
+    let name = cli.name.trim();
+    if name.is_empty() {
+        anyhow::bail!("--name can't be empty");
+    }
+    if cli.samples.is_empty() && cli.capture_s.is_none() {
+        anyhow::bail!("give at least one WAV sample or --capture-s");
+    }
+    let path = cli
+        .voice_profiles
+        .or_else(default_voice_profiles_path)
+        .context("no --voice-profiles given and $HOME is not set")?;
+    let mut profiles = if path.exists() {
+        VoiceProfiles::load(&path)?
+    } else {
+        VoiceProfiles::default()
+    };
+
+    let mut recordings = Vec::new();
+    for sample in &cli.samples {
+        recordings.push((sample.display().to_string(), read_wav_16k_mono(sample)?));
+    }
+    if let Some(seconds) = cli.capture_s {
+        println!("recording {seconds} s from {}...", cli.capture_target);
+        let (_, audio) = record_sample(cli.capture_target, Duration::from_secs(seconds))?;
+        recordings.push(("the capture".to_string(), audio));
+    }
+    for (source, audio) in recordings {
+        let print = VoicePrint::from_samples(&audio)
+            .with_context(|| format!("{source} has less than a second of speech"))?;
+        if let Some((index, similarity)) = profiles.identify(&print, 0.0) {
+            let closest = &profiles.voices[index].name;
+            if !closest.eq_ignore_ascii_case(name) {
+                println!("{source}: closest enrolled voice is {closest} ({similarity:.2})");
+            }
+        }
+        profiles.enroll(name, print);
+    }
+    profiles.save(&path)?;
+    println!("enrolled {name} in {}", path.display());
+    Ok(())
+}
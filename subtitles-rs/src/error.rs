@@ -0,0 +1,128 @@
+//! Errors at the engine's API boundary (`start_engine`, `EngineStatus::Failed`), sorted into
+//! classes an embedding app can act on: point at System Settings, offer a smaller model,
+//! retry when back online. Inside the engine errors stay `anyhow`; they are classified where
+//! they cross the boundary.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// What kind of failure a `SubtitlesError` is, without the details; also sent with
+/// `EngineStatus::Failed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A macOS privacy permission (Screen Recording, System Audio Recording) isn't granted.
+    PermissionDenied,
+    /// The transcription model couldn't be found, downloaded or loaded.
+    ModelLoad,
+    /// Audio capture couldn't start: no such device or app, or an unsupported macOS.
+    Capture,
+    /// A server couldn't be reached or refused the request.
+    Network,
+    /// The settings are invalid or name a file that can't be used.
+    EngineConfig,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::PermissionDenied => "permission denied",
+            ErrorKind::ModelLoad => "model load",
+            ErrorKind::Capture => "capture",
+            ErrorKind::Network => "network",
+            ErrorKind::EngineConfig => "engine config",
+        })
+    }
+}
+
+/// Marks a missing permission inside an `anyhow` chain, so it is classified as
+/// `PermissionDenied` wherever it surfaces: `.context(PermissionDenied("Screen Recording"))`.
+#[derive(Debug, Clone, Copy)]
+pub struct PermissionDenied(pub &'static str);
+
+impl fmt::Display for PermissionDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} permission is not granted to this app/Terminal (System Settings > Privacy & \
+             Security)",
+            self.0
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum SubtitlesError {
+    PermissionDenied(anyhow::Error),
+    ModelLoad(anyhow::Error),
+    Capture(anyhow::Error),
+    Network(anyhow::Error),
+    EngineConfig(anyhow::Error),
+}
+
+impl SubtitlesError {
+    /// A capture failure, or `PermissionDenied` when a permission was missing.
+    pub fn capture(err: anyhow::Error) -> Self {
+        if err.downcast_ref::<PermissionDenied>().is_some() {
+            SubtitlesError::PermissionDenied(err)
+        } else {
+            SubtitlesError::Capture(err)
+        }
+    }
+
+    /// A model that couldn't be loaded, or `Network` when downloading it failed.
+    pub fn model_load(err: anyhow::Error) -> Self {
+        if err.chain().any(|cause| cause.is::<reqwest::Error>()) {
+            SubtitlesError::Network(err)
+        } else {
+            SubtitlesError::ModelLoad(err)
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SubtitlesError::PermissionDenied(_) => ErrorKind::PermissionDenied,
+            SubtitlesError::ModelLoad(_) => ErrorKind::ModelLoad,
+            SubtitlesError::Capture(_) => ErrorKind::Capture,
+            SubtitlesError::Network(_) => ErrorKind::Network,
+            SubtitlesError::EngineConfig(_) => ErrorKind::EngineConfig,
+        }
+    }
+
+    fn inner(&self) -> &anyhow::Error {
+        match self {
+            SubtitlesError::PermissionDenied(err)
+            | SubtitlesError::ModelLoad(err)
+            | SubtitlesError::Capture(err)
+            | SubtitlesError::Network(err)
+            | SubtitlesError::EngineConfig(err) => err,
+        }
+    }
+}
+
+/// Shows the wrapped error; `{:#}` includes its causes, as with `anyhow`.
+impl fmt::Display for SubtitlesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", self.inner())
+        } else {
+            write!(f, "{}", self.inner())
+        }
+    }
+}
+
+impl std::error::Error for SubtitlesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().source()
+    }
+}
+
+/// Errors that weren't classified where they happened: an already-classified one passes
+/// through, anything else is taken to be a configuration problem.
+impl From<anyhow::Error> for SubtitlesError {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast::<SubtitlesError>()
+            .unwrap_or_else(SubtitlesError::EngineConfig)
+    }
+}
@@ -0,0 +1,95 @@
+//! `--filter-cmd`: a text filter stage that hands each final to a user program. The segment is
+//! written to the program's stdin as one line of JSON (`segment_id`, `text`, `output_language`)
+//! and whatever it prints replaces the text.
+
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+use crate::text::{TextFilter, TextSegment};
+
+/// Runs `sh -c <command>` once per segment. On a timeout, a non-zero exit or any other failure
+/// the segment passes through unchanged, so a broken filter never loses captions.
+#[derive(Debug, Clone)]
+pub struct CommandFilter {
+    command: String,
+    timeout: Duration,
+}
+
+impl CommandFilter {
+    pub fn new(command: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            command: command.into(),
+            timeout,
+        }
+    }
+
+    /// The replacement text: the program's stdout with trailing newlines removed. An empty
+    /// output drops the caption.
+    pub fn run(&self, segment: &TextSegment) -> anyhow::Result<String> {
+        let output_language = segment
+            .output_language
+            .to_possible_value()
+            .map(|value| value.get_name().to_string());
+        let input = serde_json::json!({
+            "segment_id": segment.segment_id,
+            "text": segment.text,
+            "output_language": output_language,
+        });
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            // Its own process group, so a timeout also kills what the shell started; one of
+            // those holding stdout open would keep the reader thread waiting forever.
+            .process_group(0)
+            .spawn()
+            .with_context(|| format!("failed to start `{}`", self.command))?;
+        let result = self.communicate(&mut child, &format!("{input}\n"));
+        if result.is_err() {
+            // SAFETY: only sends a signal, to the group the child leads.
+            unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) };
+        }
+        let status = child.wait()?;
+        let stdout = result?;
+        anyhow::ensure!(status.success(), "`{}` exited with {status}", self.command);
+        Ok(stdout.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    fn communicate(&self, child: &mut Child, input: &str) -> anyhow::Result<String> {
+        let mut stdin = child.stdin.take().context("no stdin")?;
+        let mut stdout = child.stdout.take().context("no stdout")?;
+        // Read on a helper thread so a program that never exits (or never reads) can be timed
+        // out and killed.
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let input = input.to_string();
+        std::thread::spawn(move || {
+            // A program that ignores its input may close stdin early; that's fine.
+            let _ = stdin.write_all(input.as_bytes());
+            drop(stdin);
+            let mut output = String::new();
+            let _ = tx.send(stdout.read_to_string(&mut output).map(|_| output));
+        });
+        match rx.recv_timeout(self.timeout) {
+            Ok(output) => Ok(output.context("failed to read filter output")?),
+            Err(_) => anyhow::bail!("`{}` timed out after {:?}", self.command, self.timeout),
+        }
+    }
+}
+
+impl TextFilter for CommandFilter {
+    fn apply(&mut self, mut segment: TextSegment) -> TextSegment {
+        match self.run(&segment) {
+            Ok(text) => segment.text = text,
+            Err(err) => tracing::warn!("filter command failed; keeping text: {err:#}"),
+        }
+        segment
+    }
+}
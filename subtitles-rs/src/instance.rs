@@ -0,0 +1,319 @@
+//! One capturing engine per user. The running engine holds a lock file and listens on a local
+//! socket; a second launch forwards the live settings it was given (output language, capture
+//! target, caption delay, domain model, partial stability) to it and exits instead of starting
+//! a duplicate pipeline.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{SharedCaptionDelay, SharedOutputLanguage, SharedStabilizer};
+use crate::config::{Cli, CommitPolicy, OutputLanguage};
+use crate::domain::SharedDomainModel;
+use crate::macos_capture::{CaptureControl, CaptureTarget};
+
+const LOCK_FILE: &str = "engine.lock";
+const SOCKET_FILE: &str = "engine.sock";
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `~/Library/Application Support/subtitles`, next to the settings file.
+pub fn runtime_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Application Support/subtitles"))
+}
+
+/// Settings a running engine can change without restarting, taken from flags given
+/// explicitly on a second launch's command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Handoff {
+    pub output_language: Option<OutputLanguage>,
+    pub capture_target: Option<CaptureTarget>,
+    pub caption_delay_ms: Option<u64>,
+    pub domain: Option<String>,
+    pub partial_stable_iters: Option<usize>,
+    pub commit_policy: Option<CommitPolicy>,
+}
+
+/// One JSON line on the socket.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Wire {
+    output_language: Option<String>,
+    capture_target: Option<String>,
+    caption_delay_ms: Option<u64>,
+    domain: Option<String>,
+    partial_stable_iters: Option<usize>,
+    commit_policy: Option<String>,
+}
+
+impl Handoff {
+    /// The flags a second launch forwards to the running engine.
+    pub const FLAGS: [&'static str; 6] = [
+        "--output-language",
+        "--capture-target",
+        "--caption-delay-ms",
+        "--domain",
+        "--partial-stable-iters",
+        "--commit-policy",
+    ];
+
+    /// [`Self::FLAGS`] as a sentence, e.g. for "pass ... to change it".
+    pub fn flag_list() -> String {
+        let (last, rest) = Self::FLAGS.split_last().expect("FLAGS is not empty");
+        format!("{} or {last}", rest.join(", "))
+    }
+
+    /// Reads the live settings from `args` (a full command line, program name first). Flags
+    /// that were not typed out, e.g. defaults or settings-file values, are left unset.
+    pub fn from_args<I, T>(args: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Cli::command()
+            .try_get_matches_from(args)
+            .context("invalid command line")?;
+        let cli = Cli::from_arg_matches(&matches)?;
+        let [output_language, capture_target, caption_delay_ms, domain, stable_iters, policy] =
+            Self::FLAGS.map(|flag| {
+                let id = flag.trim_start_matches("--").replace('-', "_");
+                matches.value_source(&id) == Some(ValueSource::CommandLine)
+            });
+        Ok(Self {
+            output_language: output_language.then_some(cli.output_language),
+            capture_target: capture_target.then_some(cli.capture_target),
+            caption_delay_ms: caption_delay_ms.then_some(cli.caption_delay_ms),
+            domain: cli.domain.filter(|_| domain),
+            partial_stable_iters: stable_iters.then_some(cli.partial_stable_iters),
+            commit_policy: policy.then_some(cli.commit_policy),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn to_wire(&self) -> Wire {
+        Wire {
+            output_language: self
+                .output_language
+                .and_then(|lang| lang.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+            capture_target: self.capture_target.as_ref().map(ToString::to_string),
+            caption_delay_ms: self.caption_delay_ms,
+            domain: self.domain.clone(),
+            partial_stable_iters: self.partial_stable_iters,
+            commit_policy: self
+                .commit_policy
+                .and_then(|policy| policy.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+        }
+    }
+
+    fn from_wire(wire: Wire) -> anyhow::Result<Self> {
+        Ok(Self {
+            output_language: wire
+                .output_language
+                .map(|lang| OutputLanguage::from_str(&lang, true))
+                .transpose()
+                .map_err(|err| anyhow::anyhow!("bad output language: {err}"))?,
+            capture_target: wire
+                .capture_target
+                .map(|target| target.parse::<CaptureTarget>())
+                .transpose()
+                .map_err(|err| anyhow::anyhow!(err))?,
+            caption_delay_ms: wire.caption_delay_ms,
+            domain: wire.domain,
+            partial_stable_iters: wire.partial_stable_iters,
+            commit_policy: wire
+                .commit_policy
+                .map(|policy| CommitPolicy::from_str(&policy, true))
+                .transpose()
+                .map_err(|err| anyhow::anyhow!("bad commit policy: {err}"))?,
+        })
+    }
+}
+
+/// The running engine's live settings that a [`Handoff`] updates.
+#[derive(Debug, Clone)]
+pub struct LiveControls {
+    pub output_language: SharedOutputLanguage,
+    pub capture: CaptureControl,
+    pub caption_delay: SharedCaptionDelay,
+    pub stabilizer: SharedStabilizer,
+    pub domain: SharedDomainModel,
+}
+
+impl LiveControls {
+    fn apply(&self, handoff: &Handoff) {
+        if let Some(lang) = handoff.output_language {
+            tracing::info!("handoff: output language {lang:?}");
+            self.output_language.set(lang);
+        }
+        if let Some(target) = &handoff.capture_target {
+            tracing::info!("handoff: capture target {target}");
+            self.capture.set_target(target.clone());
+        }
+        if let Some(delay) = handoff.caption_delay_ms {
+            tracing::info!("handoff: caption delay {delay} ms");
+            self.caption_delay.set(Duration::from_millis(delay));
+        }
+        if let Some(iters) = handoff.partial_stable_iters {
+            tracing::info!("handoff: partial stable iters {iters}");
+            self.stabilizer.set_stable_iters(iters);
+        }
+        if let Some(policy) = handoff.commit_policy {
+            tracing::info!("handoff: commit policy {policy:?}");
+            self.stabilizer.set_policy(policy);
+        }
+        if let Some(domain) = &handoff.domain {
+            match self.domain.select(domain) {
+                Ok(()) => tracing::info!("handoff: domain model {domain}"),
+                Err(err) => tracing::warn!("handoff: {err:#}"),
+            }
+        }
+    }
+}
+
+/// Held by the engine that is capturing. Dropping it (or the process exiting) frees the slot.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+    socket: PathBuf,
+}
+
+impl InstanceLock {
+    /// Takes the lock in `dir`, or returns `None` if another engine holds it.
+    pub fn acquire(dir: &Path) -> anyhow::Result<Option<Self>> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = dir.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        // SAFETY: plain advisory lock on a descriptor we own; released when it is closed.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+                return Ok(None);
+            }
+            return Err(err).with_context(|| format!("failed to lock {}", path.display()));
+        }
+        Ok(Some(Self {
+            _file: file,
+            socket: dir.join(SOCKET_FILE),
+        }))
+    }
+
+    /// Applies handoffs from later launches until `stop` is set.
+    pub fn serve(
+        &self,
+        controls: LiveControls,
+        stop: Arc<AtomicBool>,
+    ) -> anyhow::Result<std::thread::JoinHandle<()>> {
+        // Whoever left this behind no longer holds the lock.
+        let _ = fs::remove_file(&self.socket);
+        let listener = UnixListener::bind(&self.socket)
+            .with_context(|| format!("failed to listen on {}", self.socket.display()))?;
+        listener.set_nonblocking(true)?;
+        Ok(std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(err) = handle_connection(stream, &controls) {
+                            tracing::warn!("bad handoff from another launch: {err:#}");
+                        }
+                    }
+                    Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                    Err(err) => {
+                        tracing::warn!("handoff socket failed: {err}");
+                        break;
+                    }
+                }
+            }
+        }))
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket);
+    }
+}
+
+fn handle_connection(stream: UnixStream, controls: &LiveControls) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let reply = serde_json::from_str::<Wire>(&line)
+        .map_err(anyhow::Error::from)
+        .and_then(Handoff::from_wire);
+    let mut stream = stream;
+    match reply {
+        Ok(handoff) => {
+            controls.apply(&handoff);
+            writeln!(stream, "ok")?;
+            Ok(())
+        }
+        Err(err) => {
+            writeln!(stream, "error: {err:#}")?;
+            Err(err)
+        }
+    }
+}
+
+/// Sends `handoff` to the engine listening in `dir`.
+pub fn send_handoff(dir: &Path, handoff: &Handoff) -> anyhow::Result<()> {
+    let path = dir.join(SOCKET_FILE);
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("failed to reach the running engine at {}", path.display()))?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    writeln!(stream, "{}", serde_json::to_string(&handoff.to_wire())?)?;
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    match reply.trim() {
+        "ok" => Ok(()),
+        other => anyhow::bail!("the running engine refused the handoff: {other}"),
+    }
+}
+
+/// For a second launch: if an engine is already capturing, forwards the live settings on
+/// `args` to it and returns true; the caller should then exit. Returns false when this launch
+/// should start its own engine.
+pub fn forward_to_running<I, T>(args: I) -> anyhow::Result<bool>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let Some(dir) = runtime_dir() else {
+        return Ok(false);
+    };
+    if InstanceLock::acquire(&dir)?.is_some() {
+        return Ok(false);
+    }
+    let handoff = Handoff::from_args(args)?;
+    if handoff.is_empty() {
+        anyhow::bail!(
+            "subtitles is already running; pass {} to change it, or stop it first",
+            Handoff::flag_list()
+        );
+    }
+    send_handoff(&dir, &handoff)?;
+    tracing::info!("handed off to the running engine");
+    Ok(true)
+}
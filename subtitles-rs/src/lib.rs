@@ -1,9 +1,47 @@
 pub mod app;
-pub mod audio;
+pub mod ass;
+pub mod batch;
+pub mod braille;
 pub mod config;
+pub mod crash;
+pub mod daemon;
+pub mod device_capture;
+pub mod diagnose;
+pub mod domain;
+pub mod engine_registry;
+pub mod enroll;
+pub mod error;
+pub mod filter_cmd;
+pub mod instance;
 pub mod macos_capture;
-pub mod streaming;
-pub mod transcribe;
+pub mod platform;
+pub mod playback;
+pub mod power;
+pub mod process_tap;
+pub mod protocol;
+pub mod remote;
+pub mod report;
+pub mod script;
+pub mod second_pass;
+pub mod service;
+pub mod settings;
+pub mod setup;
+pub mod shadow;
+pub mod speech;
+pub mod srt;
+pub mod stdin_capture;
+pub mod stream_capture;
+pub mod theme;
+pub mod update;
+pub mod voice_commands;
+pub mod vtt;
+pub mod watch;
 
-pub use app::{run_headless, start_engine, CaptionEvent, EngineHandle, SharedOutputLanguage};
+pub use subtitles_core::{audio, network, streaming, testing, text, transcribe};
+
+pub use app::{
+    run_headless, start_engine, CaptionEvent, EngineHandle, EngineStatus, SharedCaptionDelay,
+    SharedOutputLanguage,
+};
 pub use config::{Cli, Engine, OutputLanguage};
+pub use error::{ErrorKind, SubtitlesError};
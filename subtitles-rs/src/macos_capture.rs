@@ -1,55 +1,361 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use crossbeam_channel::Sender;
 use parking_lot::Mutex;
 use screencapturekit::dispatch_queue::{DispatchQueue, DispatchQoS};
 use screencapturekit::prelude::*;
+use screencapturekit::recording_output::{
+    SCRecordingOutput, SCRecordingOutputCodec, SCRecordingOutputConfiguration,
+    SCRecordingOutputFileType,
+};
+
+use crate::audio::{AudioChunk, ChunkClock, Resampler};
+use crate::error::PermissionDenied;
+
+/// What to capture audio from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "lowercase")]
+pub enum CaptureTarget {
+    /// Everything the system plays (first display).
+    #[default]
+    System,
+    Display(u32),
+    Window(u32),
+    /// Everything one app plays, by bundle identifier.
+    #[serde(rename = "app")]
+    Application(String),
+}
+
+impl fmt::Display for CaptureTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureTarget::System => write!(f, "system"),
+            CaptureTarget::Display(id) => write!(f, "display:{id}"),
+            CaptureTarget::Window(id) => write!(f, "window:{id}"),
+            CaptureTarget::Application(bundle_id) => write!(f, "app:{bundle_id}"),
+        }
+    }
+}
+
+impl FromStr for CaptureTarget {
+    type Err = String;
+
+    /// `system`, `display:<id>`, `window:<id>` or `app:<bundle id>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("system") {
+            return Ok(CaptureTarget::System);
+        }
+        let (kind, value) = s.split_once(':').ok_or_else(|| {
+            format!("expected system, display:<id>, window:<id> or app:<bundle id>, got `{s}`")
+        })?;
+        let parse_id = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid {kind} id `{value}`"))
+        };
+        match kind {
+            "display" => Ok(CaptureTarget::Display(parse_id(value)?)),
+            "window" => Ok(CaptureTarget::Window(parse_id(value)?)),
+            "app" if !value.is_empty() => Ok(CaptureTarget::Application(value.to_string())),
+            _ => Err(format!("unknown capture target `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CaptureSource {
+    pub target: CaptureTarget,
+    /// Human-readable label for pickers.
+    pub name: String,
+}
+
+/// Displays, on-screen windows and running apps that can be captured.
+pub fn list_capture_sources() -> anyhow::Result<Vec<CaptureSource>> {
+    let content = shareable_content()?;
+    let mut sources = vec![CaptureSource {
+        target: CaptureTarget::System,
+        name: "All system audio".to_string(),
+    }];
+
+    for display in content.displays() {
+        sources.push(CaptureSource {
+            target: CaptureTarget::Display(display.display_id()),
+            name: format!(
+                "Display {} ({}x{})",
+                display.display_id(),
+                display.width(),
+                display.height()
+            ),
+        });
+    }
+
+    let mut apps: Vec<_> = content
+        .applications()
+        .into_iter()
+        .filter(|app| !app.bundle_identifier().is_empty())
+        .collect();
+    apps.sort_by_key(|app| app.application_name().to_lowercase());
+    for app in apps {
+        sources.push(CaptureSource {
+            target: CaptureTarget::Application(app.bundle_identifier()),
+            name: app.application_name(),
+        });
+    }
+
+    for window in content.windows() {
+        let Some(title) = window.title().filter(|t| !t.trim().is_empty()) else {
+            continue;
+        };
+        if !window.is_on_screen() {
+            continue;
+        }
+        let owner = window
+            .owning_application()
+            .map(|app| app.application_name())
+            .unwrap_or_default();
+        sources.push(CaptureSource {
+            target: CaptureTarget::Window(window.window_id()),
+            name: if owner.is_empty() {
+                title
+            } else {
+                format!("{owner} - {title}")
+            },
+        });
+    }
+
+    Ok(sources)
+}
+
+/// Lets the engine's owner switch capture targets while the stream is running.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureControl {
+    current: Arc<Mutex<CaptureTarget>>,
+    pending: Arc<Mutex<Option<CaptureTarget>>>,
+    excluded_apps: Arc<Vec<String>>,
+    recording: Option<Arc<PathBuf>>,
+    sample_rate: Option<u32>,
+    source_gone: Arc<AtomicBool>,
+}
+
+impl CaptureControl {
+    pub fn new(initial: CaptureTarget) -> Self {
+        Self {
+            current: Arc::new(Mutex::new(initial)),
+            pending: Arc::default(),
+            excluded_apps: Arc::default(),
+            recording: None,
+            sample_rate: None,
+            source_gone: Arc::default(),
+        }
+    }
+
+    /// Apps (bundle id or name) whose audio is left out of `system`/`display` captures.
+    pub fn with_excluded_apps(mut self, apps: Vec<String>) -> Self {
+        self.excluded_apps = Arc::new(apps);
+        self
+    }
+
+    /// Also write the captured video to this mp4, starting with the audio.
+    pub fn with_recording(mut self, path: Option<PathBuf>) -> Self {
+        self.recording = path.map(Arc::new);
+        self
+    }
+
+    /// Sample rate to ask ScreenCaptureKit for (48 kHz when unset). The decoder follows
+    /// whatever rate is actually delivered.
+    pub fn with_sample_rate(mut self, sample_rate: Option<u32>) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn target(&self) -> CaptureTarget {
+        self.current.lock().clone()
+    }
+
+    /// Applied by the capture thread within ~100 ms; the stream keeps running.
+    pub fn set_target(&self, target: CaptureTarget) {
+        *self.pending.lock() = Some(target);
+    }
+
+    /// True while a `window:`/`app:` target has closed or quit. The filter is re-applied once
+    /// it shows up again.
+    pub fn source_gone(&self) -> bool {
+        self.source_gone.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn excluded_apps(&self) -> &[String] {
+        &self.excluded_apps
+    }
+
+    pub(crate) fn take_pending(&self) -> Option<CaptureTarget> {
+        self.pending.lock().take()
+    }
+
+    /// Records a switch made by the capture thread.
+    pub(crate) fn set_current(&self, target: CaptureTarget) {
+        *self.current.lock() = target;
+        self.source_gone.store(false, Ordering::Relaxed);
+    }
+}
 
 pub fn start_macos_system_audio_capture(
-    audio_tx: Sender<Vec<f32>>,
+    audio_tx: Sender<AudioChunk>,
     stop: Arc<AtomicBool>,
+    control: CaptureControl,
 ) -> anyhow::Result<std::thread::JoinHandle<()>> {
+    // A missing permission fails here, where the caller can report it, not on the thread.
+    shareable_content()?;
     let handle = std::thread::spawn(move || {
-        if let Err(err) = capture_thread_main(audio_tx, stop.clone()) {
+        if let Err(err) = capture_thread_main(audio_tx, stop.clone(), control) {
             tracing::error!("{err:#}");
+            crate::crash::report_error("capture", &format!("{err:#}"));
             stop.store(true, Ordering::Relaxed);
         }
     });
     Ok(handle)
 }
 
-fn capture_thread_main(audio_tx: Sender<Vec<f32>>, stop: Arc<AtomicBool>) -> anyhow::Result<()> {
-    tracing::info!("starting ScreenCaptureKit system audio capture (requires Screen Recording permission)");
+fn shareable_content() -> anyhow::Result<SCShareableContent> {
+    SCShareableContent::get()
+        .map_err(|e| anyhow::anyhow!("{e}"))
+        .context(PermissionDenied("Screen Recording"))
+        .context("failed to query shareable content")
+}
+
+/// Running apps matching `--exclude-app` entries. Apps launched later are picked up the next
+/// time the filter is rebuilt (e.g. on a target change).
+fn excluded_running_apps(
+    content: &SCShareableContent,
+    excluded: &[String],
+) -> Vec<SCRunningApplication> {
+    let apps = content.applications();
+    for name in excluded {
+        let running = apps.iter().any(|app| app_matches(app, name));
+        if !running {
+            tracing::warn!("--exclude-app {name}: no running app matches; not excluded");
+        }
+    }
+    apps.into_iter()
+        .filter(|app| excluded.iter().any(|name| app_matches(app, name)))
+        .collect()
+}
+
+fn app_matches(app: &SCRunningApplication, name: &str) -> bool {
+    app.bundle_identifier().eq_ignore_ascii_case(name)
+        || app.application_name().eq_ignore_ascii_case(name)
+}
 
-    let content = SCShareableContent::get().map_err(|e| anyhow::anyhow!("{e}")).context(
-        "failed to query shareable content (grant Screen Recording permission to this app/Terminal)",
-    )?;
+/// Whether the window or app a target points at still exists. System and display targets
+/// always do.
+fn target_present(content: &SCShareableContent, target: &CaptureTarget) -> bool {
+    match target {
+        CaptureTarget::System | CaptureTarget::Display(_) => true,
+        CaptureTarget::Window(id) => content.windows().iter().any(|w| w.window_id() == *id),
+        CaptureTarget::Application(bundle_id) => content
+            .applications()
+            .iter()
+            .any(|a| a.bundle_identifier() == *bundle_id),
+    }
+}
 
+fn build_filter(
+    content: &SCShareableContent,
+    target: &CaptureTarget,
+    excluded_apps: &[String],
+) -> anyhow::Result<SCContentFilter> {
     let displays = content.displays();
-    let display = displays
-        .first()
-        .context("no displays found via ScreenCaptureKit")?;
+    let first_display = || {
+        displays
+            .first()
+            .context("no displays found via ScreenCaptureKit")
+    };
+    let display_filter = |display: &SCDisplay| {
+        let excluded = excluded_running_apps(content, excluded_apps);
+        if excluded.is_empty() {
+            SCContentFilter::create()
+                .with_display(display)
+                .with_excluding_windows(&[])
+                .build()
+        } else {
+            let excluded: Vec<&SCRunningApplication> = excluded.iter().collect();
+            SCContentFilter::create()
+                .with_display(display)
+                .with_excluding_applications(&excluded, &[])
+                .build()
+        }
+    };
+
+    let filter = match target {
+        CaptureTarget::System => display_filter(first_display()?),
+        CaptureTarget::Display(id) => {
+            let display = displays
+                .iter()
+                .find(|d| d.display_id() == *id)
+                .with_context(|| format!("display {id} not found"))?;
+            display_filter(display)
+        }
+        CaptureTarget::Window(id) => {
+            let window = content
+                .windows()
+                .into_iter()
+                .find(|w| w.window_id() == *id)
+                .with_context(|| format!("window {id} not found"))?;
+            SCContentFilter::create().with_window(&window).build()
+        }
+        CaptureTarget::Application(bundle_id) => {
+            let app = content
+                .applications()
+                .into_iter()
+                .find(|a| a.bundle_identifier() == *bundle_id)
+                .with_context(|| format!("application {bundle_id} is not running"))?;
+            SCContentFilter::create()
+                .with_display(first_display()?)
+                .with_including_applications(&[&app], &[])
+                .build()
+        }
+    };
+    Ok(filter)
+}
 
-    let filter = SCContentFilter::create()
-        .with_display(display)
-        .with_excluding_windows(&[])
-        .build();
+fn capture_thread_main(
+    audio_tx: Sender<AudioChunk>,
+    stop: Arc<AtomicBool>,
+    control: CaptureControl,
+) -> anyhow::Result<()> {
+    tracing::info!("starting ScreenCaptureKit system audio capture (requires Screen Recording permission)");
 
+    let content = shareable_content()?;
+    let target = control.target();
+    let filter = build_filter(&content, &target, &control.excluded_apps)?;
+
+    // Audio-only capture still needs a video track; keep it tiny unless it is being recorded.
+    let (width, height, fps) = match control.recording {
+        Some(_) => {
+            let (width, height) = video_size(&content, &target)?;
+            (width, height, RECORDING_FPS)
+        }
+        None => (2, 2, 1),
+    };
+    let sample_rate = control.sample_rate.unwrap_or(CAPTURE_SAMPLE_RATE);
     let config = SCStreamConfiguration::new()
-        .with_width(2)
-        .with_height(2)
-        .with_fps(1)
+        .with_width(width)
+        .with_height(height)
+        .with_fps(fps)
         .with_captures_audio(true)
-        .with_sample_rate(48_000)
+        .with_sample_rate(sample_rate as i32)
         .with_channel_count(2)
         .with_excludes_current_process_audio(true);
 
-    let handler = AudioHandler::new(audio_tx);
+    let handler = AudioHandler::new(audio_tx, sample_rate);
     let queue = DispatchQueue::new("subtitles.capture.audio", DispatchQoS::UserInitiated);
 
     let mut stream = SCStream::new(&filter, &config);
@@ -60,34 +366,141 @@ fn capture_thread_main(audio_tx: Sender<Vec<f32>>, stop: Arc<AtomicBool>) -> any
         anyhow::bail!("failed to add audio output handler");
     }
 
+    // Attached before the stream starts so the file and the audio clock share time zero.
+    let recording = match control.recording.as_deref() {
+        Some(path) => Some(start_recording(&stream, path)?),
+        None => None,
+    };
+
     stream
         .start_capture()
         .map_err(|e| anyhow::anyhow!("{e}"))
         .context("failed to start capture")?;
 
-    tracing::info!("capture started");
+    tracing::info!("capture started ({target})");
 
+    let mut last_source_check = Instant::now();
     while !stop.load(Ordering::Relaxed) {
         std::thread::sleep(Duration::from_millis(100));
+
+        if last_source_check.elapsed() >= SOURCE_CHECK_INTERVAL {
+            last_source_check = Instant::now();
+            check_source(&stream, &control);
+        }
+
+        if let Some(target) = control.take_pending() {
+            let updated = shareable_content()
+                .and_then(|content| build_filter(&content, &target, &control.excluded_apps))
+                .and_then(|filter| {
+                    stream
+                        .update_content_filter(&filter)
+                        .map_err(|e| anyhow::anyhow!("{e}"))
+                });
+            match updated {
+                Ok(()) => {
+                    tracing::info!("capture target changed to {target}");
+                    control.set_current(target);
+                }
+                Err(err) => tracing::warn!("failed to switch capture to {target}: {err:#}"),
+            }
+        }
     }
 
     tracing::info!("stopping capture");
+    if let Some(recording) = recording {
+        // Finalizes the mp4; a file that is cut off by stopping the stream first won't play.
+        if let Err(err) = stream.remove_recording_output(&recording) {
+            tracing::warn!("failed to finish recording: {err}");
+        }
+    }
     let _ = stream.stop_capture();
     Ok(())
 }
 
+const RECORDING_FPS: u32 = 30;
+
+/// Size of the display a target is shown on, for recording at native resolution.
+fn video_size(content: &SCShareableContent, target: &CaptureTarget) -> anyhow::Result<(u32, u32)> {
+    let displays = content.displays();
+    let display = match target {
+        CaptureTarget::Display(id) => displays.iter().find(|d| d.display_id() == *id),
+        _ => displays.first(),
+    }
+    .context("no display to record")?;
+    Ok((display.width(), display.height()))
+}
+
+fn start_recording(stream: &SCStream, path: &Path) -> anyhow::Result<SCRecordingOutput> {
+    let config = SCRecordingOutputConfiguration::new()
+        .with_output_url(path)
+        .with_video_codec(SCRecordingOutputCodec::H264)
+        .with_output_file_type(SCRecordingOutputFileType::MP4);
+    let recording = SCRecordingOutput::new(&config).context("failed to create recording output")?;
+    stream
+        .add_recording_output(&recording)
+        .map_err(|e| anyhow::anyhow!("{e}"))
+        .with_context(|| format!("failed to start recording to {}", path.display()))?;
+    tracing::info!("recording to {}", path.display());
+    Ok(recording)
+}
+
+/// How often to check that a window/app target still exists.
+const SOURCE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Notices when the targeted window closes or app quits, and re-applies the filter when it
+/// comes back (a relaunched app is a new process the old filter doesn't match).
+fn check_source(stream: &SCStream, control: &CaptureControl) {
+    let target = control.target();
+    if matches!(target, CaptureTarget::System | CaptureTarget::Display(_)) {
+        return;
+    }
+    let Ok(content) = shareable_content() else {
+        return;
+    };
+    let present = target_present(&content, &target);
+    let was_gone = control.source_gone();
+    if !present && !was_gone {
+        tracing::info!("capture source {target} went away");
+        control.source_gone.store(true, Ordering::Relaxed);
+    } else if present && was_gone {
+        let reapplied = build_filter(&content, &target, &control.excluded_apps).and_then(|filter| {
+            stream
+                .update_content_filter(&filter)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        });
+        match reapplied {
+            Ok(()) => {
+                tracing::info!("capture source {target} is back");
+                control.source_gone.store(false, Ordering::Relaxed);
+            }
+            Err(err) => tracing::warn!("failed to re-attach capture to {target}: {err:#}"),
+        }
+    }
+}
+
+/// Rate requested unless `--capture-sample-rate` says otherwise. The device can still deliver
+/// something else (and change mid-session, e.g. when Bluetooth headphones connect), so the
+/// decoder follows each buffer.
+const CAPTURE_SAMPLE_RATE: u32 = 48_000;
+
 struct AudioHandler {
-    tx: Sender<Vec<f32>>,
-    decimator: Mutex<Decimator3>,
-    warned_decode_error: AtomicBool,
+    tx: Sender<AudioChunk>,
+    requested_rate: u32,
+    /// Configured from the first buffer's format, and again whenever the rate changes.
+    resampler: Mutex<Option<Resampler>>,
+    clock: Mutex<ChunkClock>,
+    /// Last decode error, logged once until decoding recovers or the error changes.
+    last_error: Mutex<Option<String>>,
 }
 
 impl AudioHandler {
-    fn new(tx: Sender<Vec<f32>>) -> Self {
+    fn new(tx: Sender<AudioChunk>, requested_rate: u32) -> Self {
         Self {
             tx,
-            decimator: Mutex::new(Decimator3::new()),
-            warned_decode_error: AtomicBool::new(false),
+            requested_rate,
+            resampler: Mutex::new(None),
+            clock: Mutex::new(ChunkClock::asr()),
+            last_error: Mutex::new(None),
         }
     }
 }
@@ -98,11 +511,22 @@ impl SCStreamOutputTrait for AudioHandler {
             return;
         }
 
-        let out_16k = match decode_and_resample_16k_mono(&sample_buffer, &self.decimator) {
-            Ok(v) => v,
+        let decoded =
+            decode_and_resample_16k_mono(&sample_buffer, &self.resampler, self.requested_rate);
+        let out_16k = match decoded {
+            Ok(v) => {
+                if self.last_error.lock().take().is_some() {
+                    tracing::info!("audio decoding recovered");
+                }
+                v
+            }
             Err(err) => {
-                if !self.warned_decode_error.swap(true, Ordering::Relaxed) {
-                    tracing::warn!("audio decode/resample error (suppressing further): {err:#}");
+                let message = format!("{err:#}");
+                let mut last_error = self.last_error.lock();
+                if last_error.as_deref() != Some(message.as_str()) {
+                    tracing::warn!("audio decode/resample error (suppressing repeats): {message}");
+                    crate::crash::report_error("capture decode", &message);
+                    *last_error = Some(message);
                 }
                 return;
             }
@@ -112,13 +536,14 @@ impl SCStreamOutputTrait for AudioHandler {
             return;
         }
 
-        let _ = self.tx.try_send(out_16k);
+        let _ = self.tx.try_send(self.clock.lock().stamp(out_16k));
     }
 }
 
 fn decode_and_resample_16k_mono(
     sample: &CMSampleBuffer,
-    decimator: &Mutex<Decimator3>,
+    resampler: &Mutex<Option<Resampler>>,
+    requested_rate: u32,
 ) -> anyhow::Result<Vec<f32>> {
     let fmt = sample
         .format_description()
@@ -131,8 +556,8 @@ fn decode_and_resample_16k_mono(
         .audio_channel_count()
         .context("missing audio channel count")? as usize;
 
-    if sample_rate != 48_000 {
-        anyhow::bail!("unexpected sample rate {sample_rate} (expected 48000)");
+    if sample_rate == 0 {
+        anyhow::bail!("invalid sample rate 0");
     }
     if fmt.audio_is_big_endian() {
         anyhow::bail!("big-endian audio not supported");
@@ -146,26 +571,46 @@ fn decode_and_resample_16k_mono(
     };
 
     let mut out = Vec::new();
-    let mut dec = decimator.lock();
+    let mut resampler = resampler.lock();
+    let dec = match resampler.take() {
+        Some(dec) if dec.input_rate() == sample_rate => resampler.insert(dec),
+        Some(dec) => {
+            tracing::info!(
+                "capture format changed: {} Hz -> {sample_rate} Hz ({channels} ch); \
+                 reconfiguring resampler",
+                dec.input_rate()
+            );
+            resampler.insert(Resampler::new(sample_rate, 16_000))
+        }
+        None => {
+            let layout = if abl.num_buffers() > 1 { "planar" } else { "interleaved" };
+            let kind = if is_float { "float" } else { "int" };
+            tracing::info!(
+                "capture format: {sample_rate} Hz, {channels} ch, {bits}-bit {kind}, {layout} \
+                 (requested {requested_rate} Hz)"
+            );
+            resampler.insert(Resampler::new(sample_rate, 16_000))
+        }
+    };
 
     match (abl.num_buffers(), is_float, bits) {
         (1, true, 32) => {
             let buf = abl.get(0).unwrap();
             match bytemuck::try_cast_slice::<u8, f32>(buf.data()) {
-                Ok(floats) => push_interleaved(&mut dec, floats, channels, &mut out),
+                Ok(floats) => push_interleaved(dec, floats, channels, &mut out),
                 Err(_) => {
                     let floats = decode_f32_le(buf.data())?;
-                    push_interleaved(&mut dec, &floats, channels, &mut out);
+                    push_interleaved(dec, &floats, channels, &mut out);
                 }
             }
         }
         (1, false, 16) => {
             let buf = abl.get(0).unwrap();
             match bytemuck::try_cast_slice::<u8, i16>(buf.data()) {
-                Ok(ints) => push_interleaved_i16(&mut dec, ints, channels, &mut out),
+                Ok(ints) => push_interleaved_i16(dec, ints, channels, &mut out),
                 Err(_) => {
                     let ints = decode_i16_le(buf.data())?;
-                    push_interleaved_i16(&mut dec, &ints, channels, &mut out);
+                    push_interleaved_i16(dec, &ints, channels, &mut out);
                 }
             }
         }
@@ -181,7 +626,7 @@ fn decode_and_resample_16k_mono(
                 chans_owned.push(channel);
             }
             let chans: Vec<&[f32]> = chans_owned.iter().map(|v| v.as_slice()).collect();
-            push_planar(&mut dec, &chans, &mut out);
+            push_planar(dec, &chans, &mut out);
         }
         (n, false, 16) if n == channels && channels > 1 => {
             let mut chans_owned: Vec<Vec<i16>> = Vec::with_capacity(channels);
@@ -194,7 +639,7 @@ fn decode_and_resample_16k_mono(
                 chans_owned.push(channel);
             }
             let chans: Vec<&[i16]> = chans_owned.iter().map(|v| v.as_slice()).collect();
-            push_planar_i16(&mut dec, &chans, &mut out);
+            push_planar_i16(dec, &chans, &mut out);
         }
         _ => {
             anyhow::bail!(
@@ -210,7 +655,7 @@ fn decode_and_resample_16k_mono(
     Ok(out)
 }
 
-fn push_interleaved(dec: &mut Decimator3, interleaved: &[f32], channels: usize, out: &mut Vec<f32>) {
+fn push_interleaved(dec: &mut Resampler, interleaved: &[f32], channels: usize, out: &mut Vec<f32>) {
     if channels == 0 {
         return;
     }
@@ -224,14 +669,12 @@ fn push_interleaved(dec: &mut Decimator3, interleaved: &[f32], channels: usize,
             }
             sum / (channels as f32)
         };
-        if let Some(s) = dec.push(mono) {
-            out.push(s);
-        }
+        dec.push(mono, out);
     }
 }
 
 fn push_interleaved_i16(
-    dec: &mut Decimator3,
+    dec: &mut Resampler,
     interleaved: &[i16],
     channels: usize,
     out: &mut Vec<f32>,
@@ -249,13 +692,11 @@ fn push_interleaved_i16(
             }
             sum / (channels as f32)
         };
-        if let Some(s) = dec.push(mono) {
-            out.push(s);
-        }
+        dec.push(mono, out);
     }
 }
 
-fn push_planar(dec: &mut Decimator3, channels: &[&[f32]], out: &mut Vec<f32>) {
+fn push_planar(dec: &mut Resampler, channels: &[&[f32]], out: &mut Vec<f32>) {
     if channels.is_empty() {
         return;
     }
@@ -266,13 +707,11 @@ fn push_planar(dec: &mut Decimator3, channels: &[&[f32]], out: &mut Vec<f32>) {
             sum += ch[i];
         }
         let mono = sum / (channels.len() as f32);
-        if let Some(s) = dec.push(mono) {
-            out.push(s);
-        }
+        dec.push(mono, out);
     }
 }
 
-fn push_planar_i16(dec: &mut Decimator3, channels: &[&[i16]], out: &mut Vec<f32>) {
+fn push_planar_i16(dec: &mut Resampler, channels: &[&[i16]], out: &mut Vec<f32>) {
     if channels.is_empty() {
         return;
     }
@@ -283,9 +722,7 @@ fn push_planar_i16(dec: &mut Decimator3, channels: &[&[i16]], out: &mut Vec<f32>
             sum += ch[i] as f32 / 32768.0;
         }
         let mono = sum / (channels.len() as f32);
-        if let Some(s) = dec.push(mono) {
-            out.push(s);
-        }
+        dec.push(mono, out);
     }
 }
 
@@ -310,27 +747,3 @@ fn decode_i16_le(bytes: &[u8]) -> anyhow::Result<Vec<i16>> {
     }
     Ok(out)
 }
-
-struct Decimator3 {
-    phase: u8,
-    acc: f32,
-}
-
-impl Decimator3 {
-    fn new() -> Self {
-        Self { phase: 0, acc: 0.0 }
-    }
-
-    fn push(&mut self, s: f32) -> Option<f32> {
-        self.acc += s;
-        self.phase += 1;
-        if self.phase == 3 {
-            let out = self.acc / 3.0;
-            self.phase = 0;
-            self.acc = 0.0;
-            Some(out)
-        } else {
-            None
-        }
-    }
-}
@@ -1,5 +1,14 @@
-use subtitles::config::Cli;
+use clap::Parser;
+use subtitles::batch::{self, BatchCli};
+use subtitles::diagnose::{self, DiagnoseCli};
+use subtitles::enroll::{self, EnrollVoiceCli};
+use subtitles::instance;
+use subtitles::remote::{self, TranscribeCli};
 use subtitles::run_headless;
+use subtitles::service::{self, ServiceCli};
+use subtitles::settings::parse_cli;
+use subtitles::update::{self, SelfUpdateCli};
+use subtitles::watch::{self, WatchCli};
 
 fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
@@ -7,8 +16,38 @@ fn main() -> anyhow::Result<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "info,subtitles=info".into()),
         )
+        // Keep stdout for the transcript.
+        .with_writer(std::io::stderr)
         .init();
 
-    let cli = <Cli as clap::Parser>::parse();
+    match std::env::args_os().nth(1) {
+        Some(arg) if arg == "service" => {
+            return service::run(ServiceCli::parse_from(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "batch" => {
+            return batch::run(BatchCli::parse_from(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "diagnose" => {
+            return diagnose::run(DiagnoseCli::parse_from(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "enroll-voice" => {
+            return enroll::run(EnrollVoiceCli::parse_from(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "transcribe" => {
+            return remote::run(TranscribeCli::parse_from(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "watch" => {
+            return watch::run(WatchCli::parse_from(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "self-update" => {
+            return update::run(SelfUpdateCli::parse_from(std::env::args_os().skip(1)));
+        }
+        _ => {}
+    }
+
+    let cli = parse_cli()?;
+    if instance::forward_to_running(std::env::args_os())? {
+        return Ok(());
+    }
     run_headless(cli)
 }
@@ -0,0 +1,164 @@
+//! What this Mac can capture with, checked at startup so an unsupported setup fails with a
+//! clear message instead of an opaque ScreenCaptureKit error.
+
+use std::fmt;
+use std::process::Command;
+
+use clap::ValueEnum;
+
+/// How audio gets into the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CaptureBackend {
+    /// The best backend this Mac supports.
+    Auto,
+    /// ScreenCaptureKit system, display, window or app audio (macOS 13+).
+    #[value(name = "screencapturekit", alias = "sck")]
+    ScreenCaptureKit,
+    /// A CoreAudio input device (`--input-device`), e.g. BlackHole.
+    Device,
+    /// A CoreAudio process tap of system or app audio (macOS 14.4+). Needs the System Audio
+    /// Recording permission rather than Screen Recording.
+    #[value(name = "process-tap", alias = "tap")]
+    ProcessTap,
+    /// A network stream (`--stream-url`: RTSP, Icecast/HTTP, HLS), decoded by ffmpeg.
+    Stream,
+    /// Raw PCM piped to stdin (`--stdin-pcm`), for platforms without a native backend.
+    Stdin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MacosVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl MacosVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses `sw_vers -productVersion` output such as `14.4.1` or `15.0`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().split('.').map(str::parse::<u32>);
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for MacosVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch > 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
+
+/// ScreenCaptureKit can capture audio.
+const SCREENCAPTUREKIT_AUDIO: MacosVersion = MacosVersion::new(13, 0, 0);
+/// `AudioHardwareCreateProcessTap`.
+const PROCESS_TAP: MacosVersion = MacosVersion::new(14, 4, 0);
+/// `SCRecordingOutput`, used by `--record`.
+const RECORDING_OUTPUT: MacosVersion = MacosVersion::new(15, 0, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `None` when it couldn't be determined; everything is then assumed to be available and
+    /// left to fail on its own.
+    pub version: Option<MacosVersion>,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        let version = Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| MacosVersion::parse(&String::from_utf8_lossy(&output.stdout)));
+        Self { version }
+    }
+
+    fn at_least(&self, required: MacosVersion) -> bool {
+        self.version.is_none_or(|version| version >= required)
+    }
+
+    pub fn screencapturekit_audio(&self) -> bool {
+        self.at_least(SCREENCAPTUREKIT_AUDIO)
+    }
+
+    pub fn process_tap(&self) -> bool {
+        self.at_least(PROCESS_TAP)
+    }
+
+    pub fn recording_output(&self) -> bool {
+        self.at_least(RECORDING_OUTPUT)
+    }
+
+    /// Resolves `requested` to a backend this Mac supports, or explains what to do instead.
+    pub fn choose(
+        &self,
+        requested: CaptureBackend,
+        input_device: Option<&str>,
+        record: bool,
+    ) -> anyhow::Result<CaptureBackend> {
+        let version = self.version.map_or_else(
+            || "an unknown macOS version".to_string(),
+            |v| format!("macOS {v}"),
+        );
+        let backend = match (requested, input_device) {
+            (CaptureBackend::Stream, _) => {
+                anyhow::bail!("--capture-backend stream needs --stream-url <url>")
+            }
+            (CaptureBackend::Stdin, _) => {
+                anyhow::bail!("--capture-backend stdin needs --stdin-pcm <format>")
+            }
+            (CaptureBackend::Auto | CaptureBackend::Device, Some(_)) => CaptureBackend::Device,
+            (CaptureBackend::Device, None) => anyhow::bail!(
+                "--capture-backend device needs --input-device <name>, e.g. \"BlackHole 2ch\""
+            ),
+            (CaptureBackend::ScreenCaptureKit | CaptureBackend::ProcessTap, Some(_)) => {
+                anyhow::bail!("--input-device only applies to --capture-backend device (or auto)")
+            }
+            (CaptureBackend::ProcessTap, None) => {
+                if !self.process_tap() {
+                    anyhow::bail!(
+                        "process taps need macOS 14.4 or later (this is {version}); use \
+                         --capture-backend screencapturekit"
+                    );
+                }
+                CaptureBackend::ProcessTap
+            }
+            (CaptureBackend::Auto | CaptureBackend::ScreenCaptureKit, None) => {
+                if !self.screencapturekit_audio() {
+                    anyhow::bail!(
+                        "capturing system audio needs macOS 13 or later (this is {version}). \
+                         Route audio through a virtual device such as BlackHole and pass \
+                         --input-device \"BlackHole 2ch\", or update macOS"
+                    );
+                }
+                CaptureBackend::ScreenCaptureKit
+            }
+        };
+        if record {
+            if backend != CaptureBackend::ScreenCaptureKit {
+                anyhow::bail!(
+                    "--record needs ScreenCaptureKit capture; drop --input-device and use \
+                     --capture-backend auto or screencapturekit"
+                );
+            }
+            if !self.recording_output() {
+                anyhow::bail!("--record needs macOS 15 or later (this is {version})");
+            }
+        }
+        Ok(backend)
+    }
+}
@@ -0,0 +1,94 @@
+//! Plays a caption's audio (`SegmentAudio`) on the default output device. ScreenCaptureKit
+//! capture and the `system` process tap exclude this process's audio, so listening back isn't
+//! captioned again there; device capture (a microphone or loopback device) does hear it.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::Context;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, SizedSample, StreamConfig};
+
+use crate::audio::Resampler;
+
+/// Bumped by every `play`, so an earlier clip stops when a new one starts.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Starts playing 16 kHz mono `samples` and returns once the stream is running; the clip
+/// plays out on a background thread.
+pub fn play(samples: Vec<f32>) -> anyhow::Result<()> {
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    let (ready_tx, ready_rx) = crossbeam_channel::bounded::<anyhow::Result<()>>(1);
+    std::thread::spawn(move || {
+        // The stream isn't `Send` on macOS, so it lives and dies on this thread.
+        let duration = Duration::from_secs_f64(samples.len() as f64 / 16_000.0);
+        let stream = match open_stream(samples) {
+            Ok(stream) => stream,
+            Err(err) => {
+                let _ = ready_tx.send(Err(err));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        // Some slack for the device's buffer to drain.
+        let mut left = duration + Duration::from_millis(200);
+        while !left.is_zero() && GENERATION.load(Ordering::Relaxed) == generation {
+            let step = left.min(Duration::from_millis(50));
+            std::thread::sleep(step);
+            left -= step;
+        }
+        drop(stream);
+    });
+    match ready_rx.recv() {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!("playback thread exited during startup"),
+    }
+}
+
+fn open_stream(samples: Vec<f32>) -> anyhow::Result<cpal::Stream> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .context("no audio output device")?;
+    let supported = device
+        .default_output_config()
+        .context("the audio output has no usable format")?;
+    let config = supported.config();
+    let resampled = Resampler::new(16_000, config.sample_rate.0).process(&samples);
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, resampled, |s| s),
+        SampleFormat::I16 => {
+            build_stream::<i16>(&device, &config, resampled, |s| (s * 32767.0) as i16)
+        }
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, resampled, |s| {
+            (s as f64 * 2_147_483_647.0) as i32
+        }),
+        other => anyhow::bail!("unsupported audio output format {other}"),
+    }?;
+    stream.play().context("failed to start audio output")?;
+    Ok(stream)
+}
+
+fn build_stream<T: SizedSample>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    samples: Vec<f32>,
+    from_f32: fn(f32) -> T,
+) -> anyhow::Result<cpal::Stream> {
+    let channels = usize::from(config.channels.max(1));
+    let mut position = 0;
+    let stream = device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_exact_mut(channels) {
+                    let sample = samples.get(position).copied().unwrap_or(0.0);
+                    position += 1;
+                    frame.fill(from_f32(sample.clamp(-1.0, 1.0)));
+                }
+            },
+            |err| tracing::warn!("audio output error: {err}"),
+            None,
+        )
+        .context("failed to open audio output stream")?;
+    Ok(stream)
+}
@@ -0,0 +1,101 @@
+//! `--battery-saver`: whether the Mac is running on battery, read from IOKit's power sources.
+//! While it is, the engine trades accuracy and smoothness for power: a smaller model, a longer
+//! `--asr-step-ms` and a slower level meter, back to the full configuration on AC power.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+type CfTypeRef = *const c_void;
+
+const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+/// What `IOPSGetProvidingPowerSourceType` reports while running on battery.
+const BATTERY_POWER: &str = "Battery Power";
+/// How often the power source is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[cfg_attr(target_os = "macos", link(name = "IOKit", kind = "framework"))]
+extern "C" {
+    fn IOPSCopyPowerSourcesInfo() -> CfTypeRef;
+    fn IOPSGetProvidingPowerSourceType(snapshot: CfTypeRef) -> CfTypeRef;
+}
+
+#[cfg_attr(target_os = "macos", link(name = "CoreFoundation", kind = "framework"))]
+extern "C" {
+    fn CFRelease(object: CfTypeRef);
+    fn CFStringGetCString(
+        string: CfTypeRef,
+        buffer: *mut c_char,
+        size: isize,
+        encoding: u32,
+    ) -> bool;
+}
+
+/// Whether the Mac is drawing from its battery; `None` when IOKit doesn't say (e.g. no
+/// power source information at all).
+pub fn on_battery() -> Option<bool> {
+    let snapshot = unsafe { IOPSCopyPowerSourcesInfo() };
+    if snapshot.is_null() {
+        return None;
+    }
+    // The type string belongs to the snapshot.
+    let source = unsafe { IOPSGetProvidingPowerSourceType(snapshot) };
+    let mut buffer = [0 as c_char; 64];
+    let read = !source.is_null()
+        && unsafe {
+            CFStringGetCString(
+                source,
+                buffer.as_mut_ptr(),
+                buffer.len() as isize,
+                CF_STRING_ENCODING_UTF8,
+            )
+        };
+    let kind = read.then(|| unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_string_lossy());
+    let on_battery = kind.map(|kind| kind == BATTERY_POWER);
+    unsafe { CFRelease(snapshot) };
+    on_battery
+}
+
+/// Follows the power source on a background thread for the engine's threads to check.
+#[derive(Debug, Clone)]
+pub struct PowerWatch {
+    on_battery: Arc<AtomicBool>,
+}
+
+impl PowerWatch {
+    /// Checks once right away, then every few seconds until `stop`.
+    pub fn spawn(stop: Arc<AtomicBool>) -> Self {
+        let watch = Self {
+            on_battery: Arc::new(AtomicBool::new(on_battery().unwrap_or(false))),
+        };
+        let on_battery_now = watch.on_battery.clone();
+        std::thread::spawn(move || {
+            let mut waited = Duration::ZERO;
+            while !stop.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(200));
+                waited += Duration::from_millis(200);
+                if waited < POLL_INTERVAL {
+                    continue;
+                }
+                waited = Duration::ZERO;
+                let now = on_battery().unwrap_or(false);
+                if on_battery_now.swap(now, Ordering::Relaxed) != now {
+                    tracing::info!(
+                        "{}",
+                        if now {
+                            "on battery: switching to the battery saver configuration"
+                        } else {
+                            "on AC power: restoring the full configuration"
+                        }
+                    );
+                }
+            }
+        });
+        watch
+    }
+
+    pub fn on_battery(&self) -> bool {
+        self.on_battery.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,626 @@
+//! CoreAudio process taps (macOS 14.4+): what other processes play, read straight from the HAL.
+//! Unlike ScreenCaptureKit this needs no Screen Recording permission (macOS asks for System
+//! Audio Recording instead) and can follow a single app's processes, including helpers.
+//!
+//! A tap is wrapped in a private aggregate device, and an IO proc on that device receives the
+//! tapped audio. There is no maintained binding for these APIs yet, so the few calls needed are
+//! declared here.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use crossbeam_channel::Sender;
+
+use crate::audio::{AudioChunk, ChunkClock, Resampler};
+use crate::macos_capture::{CaptureControl, CaptureTarget};
+
+type AudioObjectId = u32;
+type OsStatus = i32;
+type CfTypeRef = *const c_void;
+type Id = *mut c_void;
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+const SYSTEM_OBJECT: AudioObjectId = 1;
+const SCOPE_GLOBAL: u32 = fourcc(b"glob");
+const ELEMENT_MAIN: u32 = 0;
+const PROCESS_OBJECT_LIST: u32 = fourcc(b"prs#");
+const TRANSLATE_PID_TO_PROCESS: u32 = fourcc(b"id2p");
+const PROCESS_BUNDLE_ID: u32 = fourcc(b"pbid");
+const DEFAULT_SYSTEM_OUTPUT: u32 = fourcc(b"sOut");
+const DEVICE_UID: u32 = fourcc(b"uid ");
+const TAP_FORMAT: u32 = fourcc(b"tfmt");
+const FORMAT_FLAG_NON_INTERLEAVED: u32 = 1 << 5;
+const CF_NUMBER_SINT32: isize = 3;
+const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+#[repr(C)]
+struct PropertyAddress {
+    selector: u32,
+    scope: u32,
+    element: u32,
+}
+
+impl PropertyAddress {
+    const fn global(selector: u32) -> Self {
+        Self {
+            selector,
+            scope: SCOPE_GLOBAL,
+            element: ELEMENT_MAIN,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct StreamDescription {
+    sample_rate: f64,
+    format_id: u32,
+    format_flags: u32,
+    bytes_per_packet: u32,
+    frames_per_packet: u32,
+    bytes_per_frame: u32,
+    channels_per_frame: u32,
+    bits_per_channel: u32,
+    reserved: u32,
+}
+
+#[repr(C)]
+struct AudioBuffer {
+    channels: u32,
+    byte_size: u32,
+    data: *mut c_void,
+}
+
+#[repr(C)]
+struct AudioBufferList {
+    count: u32,
+    buffers: [AudioBuffer; 1],
+}
+
+type IoProc = extern "C" fn(
+    device: AudioObjectId,
+    now: *const c_void,
+    input: *const AudioBufferList,
+    input_time: *const c_void,
+    output: *mut AudioBufferList,
+    output_time: *const c_void,
+    client: *mut c_void,
+) -> OsStatus;
+type IoProcId = *mut c_void;
+
+#[cfg_attr(target_os = "macos", link(name = "CoreAudio", kind = "framework"))]
+extern "C" {
+    fn AudioObjectGetPropertyDataSize(
+        object: AudioObjectId,
+        address: *const PropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        size: *mut u32,
+    ) -> OsStatus;
+    fn AudioObjectGetPropertyData(
+        object: AudioObjectId,
+        address: *const PropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        size: *mut u32,
+        data: *mut c_void,
+    ) -> OsStatus;
+    fn AudioHardwareCreateProcessTap(description: Id, tap: *mut AudioObjectId) -> OsStatus;
+    fn AudioHardwareDestroyProcessTap(tap: AudioObjectId) -> OsStatus;
+    fn AudioHardwareCreateAggregateDevice(
+        description: CfTypeRef,
+        device: *mut AudioObjectId,
+    ) -> OsStatus;
+    fn AudioHardwareDestroyAggregateDevice(device: AudioObjectId) -> OsStatus;
+    fn AudioDeviceCreateIOProcID(
+        device: AudioObjectId,
+        io_proc: IoProc,
+        client: *mut c_void,
+        proc_id: *mut IoProcId,
+    ) -> OsStatus;
+    fn AudioDeviceDestroyIOProcID(device: AudioObjectId, proc_id: IoProcId) -> OsStatus;
+    fn AudioDeviceStart(device: AudioObjectId, proc_id: IoProcId) -> OsStatus;
+    fn AudioDeviceStop(device: AudioObjectId, proc_id: IoProcId) -> OsStatus;
+}
+
+#[cfg_attr(target_os = "macos", link(name = "CoreFoundation", kind = "framework"))]
+extern "C" {
+    static kCFTypeDictionaryKeyCallBacks: c_void;
+    static kCFTypeDictionaryValueCallBacks: c_void;
+    static kCFTypeArrayCallBacks: c_void;
+    static kCFBooleanTrue: CfTypeRef;
+    fn CFRelease(object: CfTypeRef);
+    fn CFStringCreateWithCString(
+        allocator: CfTypeRef,
+        text: *const c_char,
+        encoding: u32,
+    ) -> CfTypeRef;
+    fn CFStringGetCString(
+        string: CfTypeRef,
+        buffer: *mut c_char,
+        size: isize,
+        encoding: u32,
+    ) -> bool;
+    fn CFNumberCreate(allocator: CfTypeRef, kind: isize, value: *const c_void) -> CfTypeRef;
+    fn CFArrayCreateMutable(
+        allocator: CfTypeRef,
+        capacity: isize,
+        callbacks: *const c_void,
+    ) -> CfTypeRef;
+    fn CFArrayAppendValue(array: CfTypeRef, value: CfTypeRef);
+    fn CFDictionaryCreateMutable(
+        allocator: CfTypeRef,
+        capacity: isize,
+        key_callbacks: *const c_void,
+        value_callbacks: *const c_void,
+    ) -> CfTypeRef;
+    fn CFDictionarySetValue(dictionary: CfTypeRef, key: CfTypeRef, value: CfTypeRef);
+}
+
+#[cfg_attr(target_os = "macos", link(name = "objc"))]
+extern "C" {
+    fn objc_getClass(name: *const c_char) -> Id;
+    fn sel_registerName(name: *const c_char) -> *mut c_void;
+    fn objc_msgSend();
+}
+
+/// A CoreFoundation object released on drop.
+struct Cf(CfTypeRef);
+
+impl Cf {
+    fn string(text: &str) -> Self {
+        let text = std::ffi::CString::new(text).unwrap_or_default();
+        Self(unsafe {
+            CFStringCreateWithCString(ptr::null(), text.as_ptr(), CF_STRING_ENCODING_UTF8)
+        })
+    }
+
+    fn number(value: i32) -> Self {
+        Self(unsafe {
+            CFNumberCreate(ptr::null(), CF_NUMBER_SINT32, (&value as *const i32).cast())
+        })
+    }
+
+    fn array(items: &[&Cf]) -> Self {
+        let array = unsafe { CFArrayCreateMutable(ptr::null(), 0, &kCFTypeArrayCallBacks) };
+        for item in items {
+            unsafe { CFArrayAppendValue(array, item.0) };
+        }
+        Self(array)
+    }
+
+    fn dictionary(entries: &[(&str, CfTypeRef)]) -> Self {
+        let dictionary = unsafe {
+            CFDictionaryCreateMutable(
+                ptr::null(),
+                0,
+                &kCFTypeDictionaryKeyCallBacks,
+                &kCFTypeDictionaryValueCallBacks,
+            )
+        };
+        for (key, value) in entries {
+            let key = Cf::string(key);
+            unsafe { CFDictionarySetValue(dictionary, key.0, *value) };
+        }
+        Self(dictionary)
+    }
+}
+
+impl Drop for Cf {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { CFRelease(self.0) };
+        }
+    }
+}
+
+/// Reads a `CFString` (or toll-free bridged `NSString`) without taking ownership.
+fn cf_string_value(string: CfTypeRef) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+    let mut buffer = [0 as c_char; 512];
+    let ok = unsafe {
+        CFStringGetCString(
+            string,
+            buffer.as_mut_ptr(),
+            buffer.len() as isize,
+            CF_STRING_ENCODING_UTF8,
+        )
+    };
+    ok.then(|| {
+        unsafe { CStr::from_ptr(buffer.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+fn check(status: OsStatus, what: &str) -> anyhow::Result<()> {
+    if status == 0 {
+        Ok(())
+    } else {
+        let code = status.to_be_bytes();
+        let printable = code.iter().all(|b| b.is_ascii_graphic());
+        match printable {
+            true => anyhow::bail!("{what} failed ('{}')", String::from_utf8_lossy(&code)),
+            false => anyhow::bail!("{what} failed ({status})"),
+        }
+    }
+}
+
+fn property<T: Default>(
+    object: AudioObjectId,
+    selector: u32,
+    qualifier: Option<&i32>,
+) -> anyhow::Result<T> {
+    let address = PropertyAddress::global(selector);
+    let mut value = T::default();
+    let mut size = std::mem::size_of::<T>() as u32;
+    let (qualifier_size, qualifier) = match qualifier {
+        Some(q) => (std::mem::size_of::<i32>() as u32, (q as *const i32).cast()),
+        None => (0, ptr::null()),
+    };
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            object,
+            &address,
+            qualifier_size,
+            qualifier,
+            &mut size,
+            (&mut value as *mut T).cast(),
+        )
+    };
+    check(status, "AudioObjectGetPropertyData")?;
+    Ok(value)
+}
+
+/// A `CFString` property, copied out and released.
+fn string_property(object: AudioObjectId, selector: u32) -> anyhow::Result<Option<String>> {
+    let string: usize = property(object, selector, None)?;
+    let string = Cf(string as CfTypeRef);
+    Ok(cf_string_value(string.0))
+}
+
+fn process_objects() -> anyhow::Result<Vec<AudioObjectId>> {
+    let address = PropertyAddress::global(PROCESS_OBJECT_LIST);
+    let mut size = 0;
+    check(
+        unsafe {
+            AudioObjectGetPropertyDataSize(SYSTEM_OBJECT, &address, 0, ptr::null(), &mut size)
+        },
+        "listing audio processes",
+    )?;
+    let mut objects =
+        vec![0 as AudioObjectId; size as usize / std::mem::size_of::<AudioObjectId>()];
+    check(
+        unsafe {
+            AudioObjectGetPropertyData(
+                SYSTEM_OBJECT,
+                &address,
+                0,
+                ptr::null(),
+                &mut size,
+                objects.as_mut_ptr().cast(),
+            )
+        },
+        "listing audio processes",
+    )?;
+    objects.truncate(size as usize / std::mem::size_of::<AudioObjectId>());
+    Ok(objects)
+}
+
+/// Audio process objects of an app, matched by bundle id or a helper's bundle id under it
+/// (`com.google.Chrome` also matches `com.google.Chrome.helper`).
+fn processes_of(bundle_id: &str) -> anyhow::Result<Vec<AudioObjectId>> {
+    let wanted = bundle_id.to_lowercase();
+    let mut matches = Vec::new();
+    for object in process_objects()? {
+        let Ok(Some(bundle)) = string_property(object, PROCESS_BUNDLE_ID) else {
+            continue;
+        };
+        let bundle = bundle.to_lowercase();
+        if bundle == wanted || bundle.starts_with(&format!("{wanted}.")) {
+            matches.push(object);
+        }
+    }
+    Ok(matches)
+}
+
+fn own_process() -> Option<AudioObjectId> {
+    let pid = std::process::id() as i32;
+    property::<AudioObjectId>(SYSTEM_OBJECT, TRANSLATE_PID_TO_PROCESS, Some(&pid))
+        .ok()
+        .filter(|&object| object != 0)
+}
+
+/// Sends an Objective-C message with one pointer-sized argument (or none).
+unsafe fn send(receiver: Id, selector: &CStr, argument: Option<*const c_void>) -> Id {
+    let selector = sel_registerName(selector.as_ptr());
+    match argument {
+        Some(argument) => {
+            let send: unsafe extern "C" fn(Id, *mut c_void, *const c_void) -> Id =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            send(receiver, selector, argument)
+        }
+        None => {
+            let send: unsafe extern "C" fn(Id, *mut c_void) -> Id =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            send(receiver, selector)
+        }
+    }
+}
+
+/// Sends an Objective-C message with one `BOOL` argument.
+unsafe fn send_bool(receiver: Id, selector: &CStr, argument: bool) {
+    let selector = sel_registerName(selector.as_ptr());
+    let send: unsafe extern "C" fn(Id, *mut c_void, bool) =
+        std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    send(receiver, selector, argument)
+}
+
+/// Per-tap state handed to the IO proc.
+struct TapState {
+    tx: Sender<AudioChunk>,
+    resampler: Resampler,
+    clock: ChunkClock,
+    channels: usize,
+    interleaved: bool,
+}
+
+extern "C" fn io_proc(
+    _device: AudioObjectId,
+    _now: *const c_void,
+    input: *const AudioBufferList,
+    _input_time: *const c_void,
+    _output: *mut AudioBufferList,
+    _output_time: *const c_void,
+    client: *mut c_void,
+) -> OsStatus {
+    if input.is_null() || client.is_null() {
+        return 0;
+    }
+    // Only this device's IO thread calls the proc, and the state outlives the device.
+    let state = unsafe { &mut *(client as *mut TapState) };
+    let list = unsafe { &*input };
+    let buffers = unsafe { std::slice::from_raw_parts(list.buffers.as_ptr(), list.count as usize) };
+    let channel = |buffer: &AudioBuffer| -> &[f32] {
+        if buffer.data.is_null() {
+            return &[];
+        }
+        let len = buffer.byte_size as usize / std::mem::size_of::<f32>();
+        unsafe { std::slice::from_raw_parts(buffer.data as *const f32, len) }
+    };
+
+    let mut out = Vec::new();
+    if state.interleaved || buffers.len() == 1 {
+        let Some(buffer) = buffers.first() else {
+            return 0;
+        };
+        let channels = (buffer.channels as usize).max(1);
+        for frame in channel(buffer).chunks_exact(channels) {
+            state
+                .resampler
+                .push(frame.iter().sum::<f32>() / channels as f32, &mut out);
+        }
+    } else {
+        let planes: Vec<&[f32]> = buffers.iter().map(channel).collect();
+        let frames = planes.iter().map(|plane| plane.len()).min().unwrap_or(0);
+        let channels = planes.len().max(state.channels).max(1) as f32;
+        for i in 0..frames {
+            state.resampler.push(
+                planes.iter().map(|plane| plane[i]).sum::<f32>() / channels,
+                &mut out,
+            );
+        }
+    }
+    if !out.is_empty() {
+        let _ = state.tx.try_send(state.clock.stamp(out));
+    }
+    0
+}
+
+/// A running tap and the aggregate device it feeds. Dropping it tears both down.
+struct ProcessTap {
+    description: Id,
+    tap: AudioObjectId,
+    device: AudioObjectId,
+    proc_id: IoProcId,
+    state: *mut TapState,
+}
+
+impl ProcessTap {
+    fn start(
+        target: &CaptureTarget,
+        excluded_apps: &[String],
+        tx: Sender<AudioChunk>,
+    ) -> anyhow::Result<Self> {
+        let (selector, processes): (&CStr, Vec<AudioObjectId>) = match target {
+            CaptureTarget::System => {
+                let mut excluded: Vec<AudioObjectId> = own_process().into_iter().collect();
+                for app in excluded_apps {
+                    let processes = processes_of(app)?;
+                    if processes.is_empty() {
+                        tracing::warn!("--exclude-app {app}: no running app matches; not excluded");
+                    }
+                    excluded.extend(processes);
+                }
+                (c"initStereoGlobalTapButExcludeProcesses:", excluded)
+            }
+            CaptureTarget::Application(bundle_id) => {
+                let processes = processes_of(bundle_id)?;
+                anyhow::ensure!(
+                    !processes.is_empty(),
+                    "{bundle_id} isn't playing or recording audio (no audio process found); \
+                     start playback first"
+                );
+                (c"initStereoMixdownOfProcesses:", processes)
+            }
+            CaptureTarget::Display(_) | CaptureTarget::Window(_) => anyhow::bail!(
+                "process taps capture `system` or `app:` targets; use --capture-backend \
+                 screencapturekit for {target}"
+            ),
+        };
+
+        let numbers: Vec<Cf> = processes.iter().map(|&p| Cf::number(p as i32)).collect();
+        let processes = Cf::array(&numbers.iter().collect::<Vec<_>>());
+        let description = unsafe {
+            let class = objc_getClass(c"CATapDescription".as_ptr());
+            anyhow::ensure!(!class.is_null(), "process taps need macOS 14.4 or later");
+            let allocated = send(class, c"alloc", None);
+            send(allocated, selector, Some(processes.0))
+        };
+        anyhow::ensure!(!description.is_null(), "failed to describe the process tap");
+        unsafe {
+            send_bool(description, c"setPrivate:", true);
+        }
+        let uuid = unsafe { send(send(description, c"UUID", None), c"UUIDString", None) };
+        let tap_uid = cf_string_value(uuid as CfTypeRef).context("process tap has no UUID")?;
+
+        let mut tap = 0;
+        let created = check(
+            unsafe { AudioHardwareCreateProcessTap(description, &mut tap) },
+            "creating the process tap (allow System Audio Recording for this app)",
+        );
+        let mut this = Self {
+            description,
+            tap,
+            device: 0,
+            proc_id: ptr::null_mut(),
+            state: ptr::null_mut(),
+        };
+        created?;
+
+        let format: StreamDescription = property(tap, TAP_FORMAT, None)?;
+        anyhow::ensure!(
+            format.sample_rate > 0.0,
+            "process tap reported no sample rate"
+        );
+        let output: AudioObjectId = property(SYSTEM_OBJECT, DEFAULT_SYSTEM_OUTPUT, None)?;
+        let output_uid =
+            string_property(output, DEVICE_UID)?.context("output device has no UID")?;
+
+        let aggregate_uid = Cf::string(&format!("subtitles-tap-{tap_uid}"));
+        let name = Cf::string("subtitles process tap");
+        let output_uid = Cf::string(&output_uid);
+        let tap_uid = Cf::string(&tap_uid);
+        let sub_device = Cf::dictionary(&[("uid", output_uid.0)]);
+        let sub_tap = Cf::dictionary(&[("uid", tap_uid.0), ("drift", unsafe { kCFBooleanTrue })]);
+        let sub_devices = Cf::array(&[&sub_device]);
+        let taps = Cf::array(&[&sub_tap]);
+        let private = Cf::number(1);
+        let aggregate = Cf::dictionary(&[
+            ("uid", aggregate_uid.0),
+            ("name", name.0),
+            ("private", private.0),
+            ("master", output_uid.0),
+            ("subdevices", sub_devices.0),
+            ("taps", taps.0),
+            ("tapautostart", unsafe { kCFBooleanTrue }),
+        ]);
+        check(
+            unsafe { AudioHardwareCreateAggregateDevice(aggregate.0, &mut this.device) },
+            "creating the tap's aggregate device",
+        )?;
+
+        let sample_rate = format.sample_rate.round() as u32;
+        let channels = format.channels_per_frame as usize;
+        tracing::info!("process tap format: {sample_rate} Hz, {channels} ch");
+        this.state = Box::into_raw(Box::new(TapState {
+            tx,
+            resampler: Resampler::new(sample_rate, 16_000),
+            clock: ChunkClock::asr(),
+            channels,
+            interleaved: format.format_flags & FORMAT_FLAG_NON_INTERLEAVED == 0,
+        }));
+        check(
+            unsafe {
+                AudioDeviceCreateIOProcID(
+                    this.device,
+                    io_proc,
+                    this.state.cast(),
+                    &mut this.proc_id,
+                )
+            },
+            "installing the tap's IO proc",
+        )?;
+        check(
+            unsafe { AudioDeviceStart(this.device, this.proc_id) },
+            "starting the process tap",
+        )?;
+        Ok(this)
+    }
+}
+
+impl Drop for ProcessTap {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.proc_id.is_null() {
+                AudioDeviceStop(self.device, self.proc_id);
+                AudioDeviceDestroyIOProcID(self.device, self.proc_id);
+            }
+            if self.device != 0 {
+                AudioHardwareDestroyAggregateDevice(self.device);
+            }
+            if self.tap != 0 {
+                AudioHardwareDestroyProcessTap(self.tap);
+            }
+            if !self.state.is_null() {
+                drop(Box::from_raw(self.state));
+            }
+            send(self.description, c"release", None);
+        }
+    }
+}
+
+/// Starts tapping on a background thread and returns once audio is flowing, so a missing app
+/// or a denied permission fails at startup. Target changes rebuild the tap.
+pub fn start_process_tap_capture(
+    audio_tx: Sender<AudioChunk>,
+    stop: Arc<AtomicBool>,
+    control: CaptureControl,
+) -> anyhow::Result<std::thread::JoinHandle<()>> {
+    let (ready_tx, ready_rx) = crossbeam_channel::bounded::<anyhow::Result<()>>(1);
+    let handle = std::thread::spawn(move || {
+        let excluded_apps = control.excluded_apps();
+        let start =
+            |target: &CaptureTarget| ProcessTap::start(target, excluded_apps, audio_tx.clone());
+        let mut tap = match start(&control.target()) {
+            Ok(tap) => tap,
+            Err(err) => {
+                let _ = ready_tx.send(Err(err));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        tracing::info!("process tap started ({})", control.target());
+
+        while !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(100));
+            let Some(target) = control.take_pending() else {
+                continue;
+            };
+            match start(&target) {
+                Ok(new_tap) => {
+                    tap = new_tap;
+                    tracing::info!("capture target changed to {target}");
+                    control.set_current(target);
+                }
+                Err(err) => tracing::warn!("failed to switch capture to {target}: {err:#}"),
+            }
+        }
+        tracing::info!("stopping capture");
+        drop(tap);
+    });
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(handle),
+        Ok(Err(err)) => Err(err),
+        Err(_) => anyhow::bail!("capture thread exited during startup"),
+    }
+}
@@ -0,0 +1,302 @@
+//! The event schema external consumers see: headless `--json` lines and the overlay's Tauri
+//! events. These types are the contract, kept apart from `CaptionEvent` so the engine's
+//! internals can change without breaking readers. Within a `PROTOCOL_VERSION` fields are only
+//! ever added (readers should ignore unknown ones); renaming or removing one bumps it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::{CaptionEvent, CaptionSpan, EngineStatus};
+use crate::error::ErrorKind;
+use crate::text::SoundTagFilter;
+use crate::transcribe;
+use crate::update::UpdateInfo;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// One event with the schema version, as written by `--json`:
+/// `{"v":1,"event":"caption","payload":{...}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub v: u32,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl Message {
+    pub fn new(event: Event) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            event,
+        }
+    }
+}
+
+/// Named like the overlay's Tauri events, with the same payloads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "payload", rename_all = "kebab-case")]
+pub enum Event {
+    Caption(Caption),
+    CaptionRevise(Revise),
+    Status(Status),
+    ModelDownloadProgress(DownloadProgress),
+    UpdateAvailable(Update),
+    Warning(Warning),
+    Level(Level),
+    Stats(Stats),
+    SpeakerName(SpeakerName),
+}
+
+/// A partial or final caption, or (`clear`) the signal to take the current one down.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Caption {
+    /// Partials and the final of one utterance share an id.
+    pub segment_id: u64,
+    pub text: String,
+    pub is_final: bool,
+    pub clear: bool,
+    /// `--diarize` speaker of a final, and the color the overlay draws it in.
+    pub speaker: Option<u32>,
+    pub color: Option<String>,
+    /// Where a final's audio sits, in seconds since capture started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_s: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_s: Option<f64>,
+    /// A final left in the original language because translating it failed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub untranslated: bool,
+    /// A partial's `text` cut into what the stabilizer has committed (`stable`) and the tail
+    /// that may still change; they join back into `text`. Not sent for finals.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<Span>,
+}
+
+/// Part of a partial caption's text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub text: String,
+    pub stable: bool,
+}
+
+/// Corrected text for a final that was already sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revise {
+    pub segment_id: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineState {
+    Loading,
+    Ready,
+    Idle,
+    Paused,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub state: EngineState,
+    /// Why, for `failed`.
+    pub message: Option<String>,
+    /// What class of error, for `failed`, so a reader can react without parsing `message`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ErrorKind>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    pub file_name: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+impl From<transcribe::DownloadProgress> for DownloadProgress {
+    fn from(progress: transcribe::DownloadProgress) -> Self {
+        Self {
+            file_name: progress.file_name,
+            downloaded_bytes: progress.downloaded_bytes,
+            total_bytes: progress.total_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Update {
+    pub version: String,
+    pub current: String,
+    pub notes: Option<String>,
+}
+
+impl From<UpdateInfo> for Update {
+    fn from(update: UpdateInfo) -> Self {
+        Self {
+            version: update.version,
+            current: update.current,
+            notes: update.notes,
+        }
+    }
+}
+
+/// The engine runs, but worse than asked (e.g. on a smaller model than configured).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Warning {
+    pub message: String,
+}
+
+/// A name for `--diarize` speaker `id`, for its earlier captions too; empty goes back to the
+/// default label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerName {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub words_per_minute: f64,
+    pub talk_s: f64,
+    pub session_s: f64,
+    /// Committed partial text retracted in the last minute: how much the captions flicker.
+    #[serde(default)]
+    pub retractions_per_minute: u64,
+}
+
+impl Event {
+    /// The wire form of `event`, with `sound_tags` applied to caption text.
+    pub fn from_caption_event(event: CaptionEvent, sound_tags: &SoundTagFilter) -> Self {
+        match event {
+            CaptionEvent::Update {
+                segment_id,
+                text,
+                is_final,
+                timing,
+                speaker,
+                untranslated,
+                spans,
+                ..
+            } => {
+                let text = sound_tags.apply(&text);
+                let spans = filtered_spans(&spans, &text, sound_tags);
+                Event::Caption(Caption {
+                    segment_id,
+                    text,
+                    is_final,
+                    clear: false,
+                    speaker: speaker.map(|speaker| speaker.id),
+                    color: speaker.map(|speaker| speaker.color.to_string()),
+                    start_s: timing.map(|timing| timing.start_s),
+                    end_s: timing.map(|timing| timing.end_s),
+                    untranslated,
+                    spans,
+                })
+            }
+            CaptionEvent::Revise { segment_id, text } => Event::CaptionRevise(Revise {
+                segment_id,
+                text: sound_tags.apply(&text),
+            }),
+            CaptionEvent::Clear => Event::Caption(Caption {
+                segment_id: 0,
+                text: String::new(),
+                is_final: true,
+                clear: true,
+                speaker: None,
+                color: None,
+                start_s: None,
+                end_s: None,
+                untranslated: false,
+                spans: Vec::new(),
+            }),
+            CaptionEvent::Status(status) => {
+                let (state, message, kind) = match status {
+                    EngineStatus::DownloadingModel(progress) => {
+                        return Event::ModelDownloadProgress(progress.into())
+                    }
+                    EngineStatus::UpdateAvailable(update) => {
+                        return Event::UpdateAvailable(update.into())
+                    }
+                    EngineStatus::Warning(message) => return Event::Warning(Warning { message }),
+                    EngineStatus::Loading => (EngineState::Loading, None, None),
+                    EngineStatus::Ready => (EngineState::Ready, None, None),
+                    EngineStatus::Idle => (EngineState::Idle, None, None),
+                    EngineStatus::Paused => (EngineState::Paused, None, None),
+                    EngineStatus::Failed { kind, message } => {
+                        (EngineState::Failed, Some(message), Some(kind))
+                    }
+                };
+                Event::Status(Status {
+                    state,
+                    message,
+                    kind,
+                })
+            }
+            CaptionEvent::Level(level) => Event::Level(Level {
+                rms: level.rms,
+                peak: level.peak,
+            }),
+            CaptionEvent::Stats(stats) => Event::Stats(Stats {
+                words_per_minute: stats.words_per_minute,
+                talk_s: stats.talk_s,
+                session_s: stats.session_s,
+                retractions_per_minute: stats.retractions_per_minute,
+            }),
+            CaptionEvent::SpeakerName { id, name } => Event::SpeakerName(SpeakerName { id, name }),
+        }
+    }
+
+    /// The Tauri event name, also `event` in the JSON.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Caption(_) => "caption",
+            Event::CaptionRevise(_) => "caption-revise",
+            Event::Status(_) => "status",
+            Event::ModelDownloadProgress(_) => "model-download-progress",
+            Event::UpdateAvailable(_) => "update-available",
+            Event::Warning(_) => "warning",
+            Event::Level(_) => "level",
+            Event::Stats(_) => "stats",
+            Event::SpeakerName(_) => "speaker-name",
+        }
+    }
+
+    /// Just the payload, for emitting under `name()`.
+    pub fn payload(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        value["payload"].take()
+    }
+}
+
+/// `spans` cut again to line up with `text`, which had sound tags applied as a whole. Each cut
+/// falls where the filtered text up to it ends; if a tag straddles a cut the spans are dropped.
+fn filtered_spans(spans: &[CaptionSpan], text: &str, sound_tags: &SoundTagFilter) -> Vec<Span> {
+    let mut out = Vec::new();
+    let mut raw = String::new();
+    let mut cut = 0;
+    for (index, span) in spans.iter().enumerate() {
+        raw.push_str(&span.text);
+        let end = if index + 1 == spans.len() {
+            text.len()
+        } else {
+            let filtered = sound_tags.apply(&raw);
+            if !text.starts_with(&filtered) || filtered.len() < cut {
+                return Vec::new();
+            }
+            filtered.len()
+        };
+        if end > cut {
+            out.push(Span {
+                text: text[cut..end].to_string(),
+                stable: span.stable,
+            });
+        }
+        cut = end;
+    }
+    out
+}
@@ -0,0 +1,174 @@
+//! `subtitles transcribe --url`: downloads remote media and transcribes it to SRT or VTT, for
+//! content that can't be played through this machine. Direct links to audio files are fetched
+//! over HTTP; anything else (YouTube and other video pages) goes through `yt-dlp`.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::Parser;
+
+use crate::batch::{is_audio_file, transcribe_file_to, FileStatus, SubtitleFormat, TranscribeArgs};
+use crate::network::{audit, AuditRecord};
+
+/// Media formats asked of yt-dlp, best first: ones `afconvert` reads, so ffmpeg isn't needed.
+const YT_DLP_FORMAT: &str = "bestaudio[ext=m4a]/bestaudio[ext=mp3]/best[ext=mp4]";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles transcribe",
+    about = "Download remote media (a YouTube page or an audio URL) and transcribe it"
+)]
+pub struct TranscribeCli {
+    /// Page or file to transcribe.
+    #[arg(long)]
+    pub url: String,
+
+    /// Subtitle file to write; `.vtt` writes WebVTT. Defaults to the media's name, in the
+    /// current directory.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// yt-dlp executable, for pages that aren't a direct link to a file.
+    #[arg(long, default_value = "yt-dlp")]
+    pub yt_dlp: PathBuf,
+
+    /// Append a JSON line for the download (destination, bytes) to this file.
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub transcribe: TranscribeArgs,
+}
+
+pub fn run(cli: TranscribeCli) -> anyhow::Result<()> {
+    if let Some(path) = cli.audit_log.as_deref() {
+        crate::network::enable_audit_log(path)?;
+    }
+    let staging = std::env::temp_dir().join(format!("subtitles-url-{}", std::process::id()));
+    fs::create_dir_all(&staging)
+        .with_context(|| format!("failed to create {}", staging.display()))?;
+    let result = fetch_and_transcribe(&cli, &staging);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn fetch_and_transcribe(cli: &TranscribeCli, staging: &Path) -> anyhow::Result<()> {
+    let media = if is_direct_media(&cli.url) {
+        download(&cli.url, staging)?
+    } else {
+        yt_dlp(&cli.yt_dlp, &cli.url, staging)?
+    };
+
+    let mut options = cli.transcribe.options(None, true);
+    let output = match &cli.output {
+        Some(output) => {
+            options.format = SubtitleFormat::from_path(output).unwrap_or(options.format);
+            output.clone()
+        }
+        None => PathBuf::from(options.output_for(&media).file_name().unwrap_or_default()),
+    };
+    let mut transcribers = cli.transcribe.load_transcribers(1)?;
+    let mut transcriber = transcribers.pop().context("no transcriber")?;
+    let report = transcribe_file_to(&media, &output, &options, &mut *transcriber, "[1/1]");
+    match report.status {
+        FileStatus::Failed(err) => anyhow::bail!("{err}"),
+        _ => {
+            println!("{} ({} cues)", output.display(), report.cues);
+            Ok(())
+        }
+    }
+}
+
+/// Whether `url` links straight to an audio or video file, going by its path.
+pub fn is_direct_media(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    path.contains('/') && is_audio_file(Path::new(path))
+}
+
+/// Fetches `url` into `dir`, keeping the file name from its path.
+pub fn download(url: &str, dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name = path
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("media");
+    let dest = dir.join(name);
+
+    tracing::info!("downloading {url}");
+    // Long recordings take a while; only connecting is time-limited.
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(None)
+        .build()
+        .context("failed to build HTTP client")?;
+    let response = client.get(url).send();
+    let status = response.as_ref().ok().map(|r| r.status().as_u16());
+    let copied = response
+        .and_then(|r| r.error_for_status())
+        .map_err(anyhow::Error::from)
+        .and_then(|mut response| {
+            let mut file = File::create(&dest)
+                .with_context(|| format!("failed to create {}", dest.display()))?;
+            Ok(response.copy_to(&mut file)?)
+        });
+    audit(AuditRecord {
+        purpose: "media-download",
+        method: "GET",
+        destination: url,
+        bytes_sent: 0,
+        bytes_received: *copied.as_ref().unwrap_or(&0),
+        status,
+        error: copied.as_ref().err().map(|e| format!("{e:#}")),
+    });
+    copied.with_context(|| format!("failed to download {url}"))?;
+    Ok(dest)
+}
+
+/// Downloads the audio of a page with yt-dlp, preferring formats that don't need ffmpeg.
+fn yt_dlp(exe: &Path, url: &str, dir: &Path) -> anyhow::Result<PathBuf> {
+    tracing::info!("fetching {url} with yt-dlp");
+    let output = Command::new(exe)
+        .args(["--no-playlist", "--no-simulate", "-f", YT_DLP_FORMAT])
+        .args(["--print", "after_move:filepath", "-o"])
+        .arg(dir.join("%(title).100B [%(id)s].%(ext)s"))
+        .arg(url)
+        .output()
+        .with_context(|| {
+            format!(
+                "failed to run {} (install yt-dlp, e.g. `brew install yt-dlp`)",
+                exe.display()
+            )
+        })?;
+    let media = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .last()
+        .map(|line| PathBuf::from(line.trim()));
+    let error = (!output.status.success()).then(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        stderr.lines().last().unwrap_or_default().to_string()
+    });
+    audit(AuditRecord {
+        purpose: "media-download",
+        method: "GET",
+        destination: url,
+        bytes_sent: 0,
+        bytes_received: media
+            .as_ref()
+            .and_then(|path| fs::metadata(path).ok())
+            .map_or(0, |meta| meta.len()),
+        status: None,
+        error: error.clone(),
+    });
+    if let Some(error) = error {
+        anyhow::bail!("yt-dlp failed ({}): {error}", output.status);
+    }
+    media
+        .filter(|path| path.is_file())
+        .context("yt-dlp didn't report the downloaded file")
+}
@@ -0,0 +1,330 @@
+//! Session analytics. `--report` is a summary written when the session ends, for meeting
+//! facilitators: talk time against silence, speaking rate, the writing systems spoken and the
+//! most frequent terms. `StatsTracker` is the live counterpart behind the overlay's HUD.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::app::CaptionEvent;
+
+const TOP_TERMS: usize = 10;
+/// How far back the live speaking rate looks.
+const WPM_WINDOW: Duration = Duration::from_secs(60);
+
+/// Too common to say anything about a meeting.
+const STOPWORDS: &[&str] = &[
+    "about", "and", "are", "but", "can", "for", "from", "have", "just", "not", "that", "the",
+    "there", "they", "this", "was", "were", "what", "will", "with", "you", "your",
+];
+
+/// Collects finals (and their second-pass revisions) as the session runs.
+pub struct SessionReport {
+    path: PathBuf,
+    started: Instant,
+    /// Text and speech duration per segment.
+    segments: BTreeMap<u64, (String, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub session_s: f64,
+    /// Seconds covered by finals.
+    pub talk_s: f64,
+    pub silence_s: f64,
+    pub segments: usize,
+    pub words: usize,
+    /// Over talk time, not the whole session.
+    pub words_per_minute: f64,
+    /// Words per writing system (`Latin`, `Han`, ...), standing in for languages.
+    pub scripts: BTreeMap<String, usize>,
+    /// Most frequent words of three or more letters, excluding common function words.
+    pub top_terms: Vec<(String, usize)>,
+}
+
+impl SessionReport {
+    /// Checks the path is writable up front so a bad path fails at startup, not at exit.
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        fs::write(path, "").with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            segments: BTreeMap::new(),
+        })
+    }
+
+    pub fn handle(&mut self, event: &CaptionEvent) {
+        match event {
+            CaptionEvent::Update {
+                segment_id,
+                text,
+                is_final: true,
+                timing,
+                ..
+            } => {
+                let duration = timing.map_or(0.0, |t| (t.end_s - t.start_s).max(0.0));
+                self.segments.insert(*segment_id, (text.clone(), duration));
+            }
+            CaptionEvent::Revise { segment_id, text } => {
+                if let Some((final_text, _)) = self.segments.get_mut(segment_id) {
+                    *final_text = text.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        summarize(
+            self.started.elapsed().as_secs_f64(),
+            self.segments
+                .values()
+                .map(|(text, duration)| (text.as_str(), *duration)),
+        )
+    }
+
+    /// Markdown for a `.md` path, JSON otherwise.
+    pub fn write(&self) -> anyhow::Result<()> {
+        let summary = self.summary();
+        let is_markdown = self
+            .path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+        let out = if is_markdown {
+            summary.to_markdown()
+        } else {
+            serde_json::to_string_pretty(&summary)? + "\n"
+        };
+        fs::write(&self.path, out)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+/// Summarizes finals given as `(text, speech duration in seconds)`.
+pub fn summarize<'a>(
+    session_s: f64,
+    finals: impl IntoIterator<Item = (&'a str, f64)>,
+) -> SessionSummary {
+    let mut segments = 0;
+    let mut talk_s = 0.0;
+    let mut words = 0;
+    let mut scripts = BTreeMap::<String, usize>::new();
+    let mut terms = HashMap::<String, usize>::new();
+    for (text, duration) in finals {
+        segments += 1;
+        talk_s += duration;
+        for word in words_of(text) {
+            words += 1;
+            if let Some(script) = word.chars().find_map(script_of) {
+                *scripts.entry(script.to_string()).or_default() += 1;
+            }
+            let term = word.to_lowercase();
+            if term.chars().count() >= 3
+                && term.chars().all(char::is_alphabetic)
+                && !STOPWORDS.contains(&term.as_str())
+            {
+                *terms.entry(term).or_default() += 1;
+            }
+        }
+    }
+
+    let mut top_terms: Vec<(String, usize)> = terms.into_iter().collect();
+    top_terms.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    top_terms.truncate(TOP_TERMS);
+    // Finals can overlap the session edges slightly; never report negative silence.
+    let session_s = session_s.max(talk_s);
+    SessionSummary {
+        session_s,
+        talk_s,
+        silence_s: session_s - talk_s,
+        segments,
+        words,
+        words_per_minute: if talk_s > 0.0 {
+            words as f64 * 60.0 / talk_s
+        } else {
+            0.0
+        },
+        scripts,
+        top_terms,
+    }
+}
+
+impl SessionSummary {
+    pub fn to_markdown(&self) -> String {
+        let percent = |part: f64| {
+            if self.session_s > 0.0 {
+                part * 100.0 / self.session_s
+            } else {
+                0.0
+            }
+        };
+        let mut out = String::from("# Session report\n\n");
+        out.push_str(&format!(
+            "- Duration: {}\n- Talk time: {} ({:.0}%)\n- Silence: {} ({:.0}%)\n",
+            format_duration(self.session_s),
+            format_duration(self.talk_s),
+            percent(self.talk_s),
+            format_duration(self.silence_s),
+            percent(self.silence_s),
+        ));
+        out.push_str(&format!(
+            "- Segments: {}\n- Words: {} ({:.0} per minute of talk)\n",
+            self.segments, self.words, self.words_per_minute
+        ));
+        if !self.scripts.is_empty() {
+            out.push_str("\n## Writing systems\n\n| Script | Words | Share |\n|---|---:|---:|\n");
+            let total: usize = self.scripts.values().sum();
+            for (script, count) in &self.scripts {
+                let share = *count as f64 * 100.0 / total as f64;
+                out.push_str(&format!("| {script} | {count} | {share:.0}% |\n"));
+            }
+        }
+        if !self.top_terms.is_empty() {
+            out.push_str("\n## Frequent terms\n\n| Term | Count |\n|---|---:|\n");
+            for (term, count) in &self.top_terms {
+                out.push_str(&format!("| {term} | {count} |\n"));
+            }
+        }
+        out
+    }
+}
+
+/// Whitespace-separated words, trimmed of punctuation. Han and kana carry no spaces, so each
+/// of those characters counts as a word.
+fn words_of(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for token in text.split_whitespace() {
+        let mut word = String::new();
+        for c in token.chars() {
+            if matches!(script_of(c), Some("Han" | "Kana")) {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                words.push(c.to_string());
+            } else if c.is_alphanumeric() || (c == '\'' && !word.is_empty()) {
+                word.push(c);
+            } else if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    for word in &mut words {
+        while word.ends_with('\'') {
+            word.pop();
+        }
+    }
+    words
+}
+
+fn script_of(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{3040}'..='\u{30FF}' => "Kana",
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' => "Han",
+        '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => "Hangul",
+        '\u{0400}'..='\u{04FF}' => "Cyrillic",
+        '\u{0600}'..='\u{06FF}' => "Arabic",
+        '\u{0900}'..='\u{097F}' => "Devanagari",
+        c if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => "Latin",
+        c if c.is_alphabetic() => "Other",
+        _ => return None,
+    })
+}
+
+/// `1h 02m 05s`, `2m 05s` or `5s`.
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (h, m, s) = (total / 3600, total / 60 % 60, total % 60);
+    if h > 0 {
+        format!("{h}h {m:02}m {s:02}s")
+    } else if m > 0 {
+        format!("{m}m {s:02}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
+/// Live analytics for the HUD, sent every `--stats-interval-ms`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LiveStats {
+    /// Over the talk time of the last minute's finals; 0 when nobody has spoken lately.
+    pub words_per_minute: f64,
+    /// Seconds covered by finals since the session started.
+    pub talk_s: f64,
+    pub session_s: f64,
+    /// Committed partial text retracted in the last minute (see `--partial-stable-iters`).
+    pub retractions_per_minute: u64,
+}
+
+/// Running talk time and recent speaking rate, fed with finals as they arrive.
+pub struct StatsTracker {
+    started: Instant,
+    talk_s: f64,
+    /// Arrival, word count and duration of finals within `WPM_WINDOW`.
+    recent: VecDeque<(Instant, usize, f64)>,
+    /// When retractions were seen within `WPM_WINDOW`, and how many.
+    retracted: VecDeque<(Instant, u64)>,
+    retractions_seen: u64,
+}
+
+impl StatsTracker {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            talk_s: 0.0,
+            recent: VecDeque::new(),
+            retracted: VecDeque::new(),
+            retractions_seen: 0,
+        }
+    }
+
+    /// Takes the engine's running retraction count (`SharedStabilizer::retractions`).
+    pub fn set_retractions(&mut self, total: u64, now: Instant) {
+        if total > self.retractions_seen {
+            self.retracted.push_back((now, total - self.retractions_seen));
+            self.retractions_seen = total;
+        }
+    }
+
+    pub fn push_final(&mut self, text: &str, duration_s: f64, now: Instant) {
+        let duration_s = duration_s.max(0.0);
+        self.talk_s += duration_s;
+        self.recent
+            .push_back((now, words_of(text).len(), duration_s));
+    }
+
+    pub fn stats(&mut self, now: Instant) -> LiveStats {
+        while let Some(&(at, _, _)) = self.recent.front() {
+            if now.saturating_duration_since(at) <= WPM_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        while let Some(&(at, _)) = self.retracted.front() {
+            if now.saturating_duration_since(at) <= WPM_WINDOW {
+                break;
+            }
+            self.retracted.pop_front();
+        }
+        let (words, talk_s) = self
+            .recent
+            .iter()
+            .fold((0, 0.0), |(words, talk), &(_, w, d)| (words + w, talk + d));
+        LiveStats {
+            words_per_minute: if talk_s > 0.0 {
+                words as f64 * 60.0 / talk_s
+            } else {
+                0.0
+            },
+            talk_s: self.talk_s,
+            session_s: now.saturating_duration_since(self.started).as_secs_f64(),
+            retractions_per_minute: self.retracted.iter().map(|&(_, count)| count).sum(),
+        }
+    }
+}
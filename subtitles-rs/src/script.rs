@@ -0,0 +1,142 @@
+//! `--script`: a rhai script that sees each caption before it is shown or written out, and can
+//! rewrite or drop it.
+//!
+//! The script defines `fn on_caption(event)`, where `event` is a map with `kind` (`"partial"`,
+//! `"final"` or `"revise"`), `segment_id`, `text`, and for timed finals `start_s`/`end_s`. It
+//! returns a string to replace the text, `false` to drop the event, or nothing to leave it be.
+
+use std::path::Path;
+
+use anyhow::Context;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::app::CaptionEvent;
+
+const HOOK: &str = "on_caption";
+/// Bounds a runaway script; captions pass through it on the way to the overlay.
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub struct CaptionScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl CaptionScript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let engine = Self::engine();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|err| anyhow::anyhow!("{err}"))
+            .with_context(|| format!("failed to compile script {}", path.display()))?;
+        Self::new(engine, ast).with_context(|| format!("invalid script {}", path.display()))
+    }
+
+    pub fn from_source(source: &str) -> anyhow::Result<Self> {
+        let engine = Self::engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow::anyhow!("failed to compile script: {err}"))?;
+        Self::new(engine, ast)
+    }
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| tracing::info!("script: {text}"));
+        engine.on_debug(|text, _, _| tracing::debug!("script: {text}"));
+        engine
+    }
+
+    fn new(engine: Engine, ast: AST) -> anyhow::Result<Self> {
+        let hook = ast
+            .iter_functions()
+            .any(|f| f.name == HOOK && f.params.len() == 1);
+        anyhow::ensure!(hook, "script must define `fn {HOOK}(event)`");
+        Ok(Self { engine, ast })
+    }
+
+    /// Runs the hook on caption text events; others pass straight through. `None` means the
+    /// script dropped the event. A failing script leaves the event unchanged.
+    pub fn apply(&self, event: CaptionEvent) -> Option<CaptionEvent> {
+        let mut input = Map::new();
+        let (kind, segment_id, text) = match &event {
+            CaptionEvent::Update {
+                segment_id,
+                text,
+                is_final,
+                timing,
+                speaker,
+                ..
+            } => {
+                if let Some(timing) = timing {
+                    input.insert("start_s".into(), timing.start_s.into());
+                    input.insert("end_s".into(), timing.end_s.into());
+                }
+                if let Some(speaker) = speaker {
+                    input.insert("speaker".into(), (speaker.id as rhai::INT).into());
+                }
+                let kind = if *is_final { "final" } else { "partial" };
+                (kind, *segment_id, text)
+            }
+            CaptionEvent::Revise { segment_id, text } => ("revise", *segment_id, text),
+            _ => return Some(event),
+        };
+        input.insert("kind".into(), kind.into());
+        input.insert("segment_id".into(), (segment_id as rhai::INT).into());
+        input.insert("text".into(), text.clone().into());
+
+        let result = self.engine.call_fn::<Dynamic>(
+            &mut Scope::new(),
+            &self.ast,
+            HOOK,
+            (Dynamic::from(input),),
+        );
+        let output = match result {
+            Ok(output) => output,
+            Err(err) => {
+                tracing::warn!("script failed on segment {segment_id}: {err}");
+                return Some(event);
+            }
+        };
+        if output.is_unit() {
+            return Some(event);
+        }
+        if output.as_bool() == Ok(false) {
+            return None;
+        }
+        let type_name = output.type_name();
+        let Ok(new_text) = output.into_string() else {
+            tracing::warn!("script returned a {type_name} for segment {segment_id}; ignoring it");
+            return Some(event);
+        };
+        // Unchanged text keeps its spans and word timings.
+        if new_text == *text {
+            return Some(event);
+        }
+        Some(match event {
+            CaptionEvent::Update {
+                segment_id,
+                is_final,
+                timing,
+                speaker,
+                untranslated,
+                ..
+            } => CaptionEvent::Update {
+                segment_id,
+                text: new_text,
+                is_final,
+                timing,
+                speaker,
+                untranslated,
+                // The script's text no longer lines up with the stabilizer's or the words'.
+                spans: Vec::new(),
+                words: Vec::new(),
+            },
+            CaptionEvent::Revise { segment_id, .. } => CaptionEvent::Revise {
+                segment_id,
+                text: new_text,
+            },
+            other => other,
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+
+use crate::app::{send_status, CaptionEvent, EngineStatus};
+use crate::config::OutputLanguage;
+use crate::streaming::SharedAudio;
+use crate::text::{FilterChain, CROSSTALK_TAG};
+use crate::transcribe::{decode_final, TranscriberConfig, TranscriberLoader};
+use crate::voice_commands::CommandSpotter;
+
+/// A finalized segment queued for re-transcription.
+pub struct SecondPassJob {
+    pub segment_id: u64,
+    pub audio: SharedAudio,
+    pub output_language: OutputLanguage,
+    /// What the live (first-pass) decoder showed for this segment, without the crosstalk tag.
+    pub first_pass: String,
+    /// The live final was shown with `CROSSTALK_TAG`; a revision keeps it.
+    pub crosstalk: bool,
+    /// First-pass confidence, if the live engine reports one.
+    pub confidence: Option<f32>,
+}
+
+/// Background worker that re-runs finalized segments through a larger model and emits
+/// `CaptionEvent::Revise` when the result differs from what was shown live.
+pub struct SecondPass {
+    tx: Sender<SecondPassJob>,
+    handle: std::thread::JoinHandle<()>,
+    max_confidence: Option<f32>,
+}
+
+impl SecondPass {
+    /// Starts the worker; the model is loaded (and downloaded if needed) on the worker thread.
+    /// `commands` are taken out of revisions the way they are out of live finals.
+    pub fn spawn(
+        load: TranscriberLoader,
+        decode_cfg: TranscriberConfig,
+        filters: FilterChain,
+        commands: Option<CommandSpotter>,
+        max_confidence: Option<f32>,
+        caption_tx: Sender<CaptionEvent>,
+        stop: Arc<AtomicBool>,
+    ) -> Self {
+        let (tx, rx) = crossbeam_channel::bounded::<SecondPassJob>(16);
+        let handle = std::thread::spawn(move || {
+            worker_main(load, decode_cfg, filters, commands, rx, caption_tx, stop);
+        });
+        Self {
+            tx,
+            handle,
+            max_confidence,
+        }
+    }
+
+    /// Queues a segment, unless `max_confidence` is set and the first pass was already
+    /// confident enough. Segments without a confidence score are always re-run.
+    pub fn submit(&self, job: SecondPassJob) {
+        if let (Some(max), Some(confidence)) = (self.max_confidence, job.confidence) {
+            if confidence >= max {
+                tracing::debug!(
+                    "skipping second pass for segment {} (confidence {confidence:.2})",
+                    job.segment_id
+                );
+                return;
+            }
+        }
+        match self.tx.try_send(job) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("second-pass queue full; skipping segment");
+            }
+            // The worker failed to load its model and has already logged why.
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    pub fn join(self) {
+        drop(self.tx);
+        let _ = self.handle.join();
+    }
+}
+
+fn worker_main(
+    load: TranscriberLoader,
+    decode_cfg: TranscriberConfig,
+    mut filters: FilterChain,
+    commands: Option<CommandSpotter>,
+    rx: Receiver<SecondPassJob>,
+    caption_tx: Sender<CaptionEvent>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut transcriber = match load(&mut |progress| {
+        send_status(&caption_tx, EngineStatus::DownloadingModel(progress))
+    }) {
+        Ok(transcriber) => transcriber,
+        Err(err) => {
+            tracing::error!("second pass disabled: failed to initialize whisper: {err:#}");
+            return;
+        }
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let job = match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(job) => job,
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        };
+
+        let revised = decode_final(
+            transcriber.as_mut(),
+            &job.audio,
+            &decode_cfg,
+            job.output_language,
+        );
+        let text = match revised {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::warn!("second-pass transcription failed: {err:#}");
+                continue;
+            }
+        };
+        // Filtered the same way as the live final, so it only differs if the words do.
+        let text = filters.apply_text(job.segment_id, text, job.output_language);
+        let text = match commands.as_ref().and_then(|spotter| spotter.spot(&text)) {
+            Some(spotted) => spotted.rest,
+            None => text,
+        };
+        if text.is_empty() || text == job.first_pass.trim() {
+            continue;
+        }
+        let text = if job.crosstalk {
+            format!("{CROSSTALK_TAG} {text}")
+        } else {
+            text
+        };
+
+        tracing::debug!("second pass revised segment {}: {text}", job.segment_id);
+        if caption_tx
+            .try_send(CaptionEvent::Revise {
+                segment_id: job.segment_id,
+                text,
+            })
+            .is_err()
+        {
+            tracing::warn!("caption queue full; dropping revision");
+        }
+    }
+}
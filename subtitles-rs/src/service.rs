@@ -0,0 +1,266 @@
+//! `subtitles service install|uninstall|status`: runs the headless engine as a per-user launchd
+//! agent, so captions are transcribed in the background from login without starting the app.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+
+use crate::settings::default_settings_path;
+
+/// launchd label, also the plist's file name.
+pub const LABEL: &str = "com.subtitles.headless";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles service",
+    about = "Run headless captioning as a launchd agent"
+)]
+pub struct ServiceCli {
+    #[command(subcommand)]
+    pub action: ServiceAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ServiceAction {
+    /// Write the agent's plist and start it now and at every login. Reinstalling replaces it.
+    Install {
+        /// Where finals are appended (default `~/Library/Logs/subtitles/transcript.txt`).
+        #[arg(long)]
+        transcript: Option<PathBuf>,
+        /// Extra engine flags, after `--` (settings from the setup wizard apply as well).
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Stop the agent and remove its plist.
+    Uninstall,
+    /// Whether the agent is installed and running.
+    Status,
+}
+
+/// Where the agent keeps its files.
+#[derive(Debug, Clone)]
+pub struct ServicePaths {
+    pub plist: PathBuf,
+    pub transcript: PathBuf,
+    pub log: PathBuf,
+    pub pid_file: PathBuf,
+}
+
+impl ServicePaths {
+    pub fn for_home(home: &Path) -> Self {
+        let logs = home.join("Library/Logs/subtitles");
+        Self {
+            plist: home.join(format!("Library/LaunchAgents/{LABEL}.plist")),
+            transcript: logs.join("transcript.txt"),
+            log: logs.join("subtitles.log"),
+            pid_file: home.join("Library/Application Support/subtitles/subtitles.pid"),
+        }
+    }
+
+    fn current() -> anyhow::Result<Self> {
+        let home = std::env::var_os("HOME").context("HOME is not set")?;
+        Ok(Self::for_home(Path::new(&home)))
+    }
+}
+
+/// The agent's property list. The engine runs `--no-ui`: stdout (the transcript) and stderr
+/// (the log) go to files, and launchd restarts it if it exits with an error.
+pub fn render_plist(program: &Path, args: &[String], paths: &ServicePaths) -> String {
+    let mut arguments = vec![
+        program.display().to_string(),
+        "--no-ui".to_string(),
+        "--pid-file".to_string(),
+        paths.pid_file.display().to_string(),
+    ];
+    arguments.extend(args.iter().cloned());
+    let arguments: String = arguments
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ProcessType</key>
+    <string>Interactive</string>
+    <key>StandardOutPath</key>
+    <string>{transcript}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = LABEL,
+        transcript = xml_escape(&paths.transcript.display().to_string()),
+        log = xml_escape(&paths.log.display().to_string()),
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// What `launchctl print` says about a loaded agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentState {
+    pub state: String,
+    pub pid: Option<u32>,
+    pub last_exit: Option<String>,
+}
+
+/// Reads the top-level `state`, `pid` and `last exit code` lines of `launchctl print` output.
+pub fn parse_launchctl_print(output: &str) -> AgentState {
+    let mut state = AgentState {
+        state: "unknown".to_string(),
+        pid: None,
+        last_exit: None,
+    };
+    for line in output.lines() {
+        // Nested sections are indented further; only the service's own fields count.
+        if !line.starts_with('\t') || line.starts_with("\t\t") {
+            continue;
+        }
+        let Some((key, value)) = line.trim().split_once(" = ") else {
+            continue;
+        };
+        match key {
+            "state" => state.state = value.to_string(),
+            "pid" => state.pid = value.parse().ok(),
+            "last exit code" => state.last_exit = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    state
+}
+
+pub fn run(cli: ServiceCli) -> anyhow::Result<()> {
+    let paths = ServicePaths::current()?;
+    match cli.action {
+        ServiceAction::Install { transcript, args } => {
+            let paths = ServicePaths {
+                transcript: transcript.unwrap_or(paths.transcript),
+                ..paths
+            };
+            install(&paths, &args)
+        }
+        ServiceAction::Uninstall => uninstall(&paths),
+        ServiceAction::Status => status(&paths),
+    }
+}
+
+fn install(paths: &ServicePaths, args: &[String]) -> anyhow::Result<()> {
+    let program = std::env::current_exe().context("failed to locate this executable")?;
+    for dir in [&paths.plist, &paths.transcript, &paths.log, &paths.pid_file]
+        .iter()
+        .filter_map(|path| path.parent())
+    {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    fs::write(&paths.plist, render_plist(&program, args, paths))
+        .with_context(|| format!("failed to write {}", paths.plist.display()))?;
+
+    // Replace a running copy so a reinstall picks up new arguments.
+    let _ = launchctl(&["bootout", &service_target()?]);
+    launchctl(&["bootstrap", &domain()?, &paths.plist.display().to_string()])?;
+    println!("installed {}", paths.plist.display());
+    println!("transcript: {}", paths.transcript.display());
+    println!("log: {}", paths.log.display());
+    if let Some(settings) = default_settings_path().filter(|path| path.exists()) {
+        println!(
+            "settings: {} (send SIGHUP or reinstall after editing)",
+            settings.display()
+        );
+    }
+    Ok(())
+}
+
+fn uninstall(paths: &ServicePaths) -> anyhow::Result<()> {
+    let stopped = launchctl(&["bootout", &service_target()?]).is_ok();
+    match fs::remove_file(&paths.plist) {
+        Ok(()) => println!("removed {}", paths.plist.display()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            if !stopped {
+                println!("not installed");
+            }
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to remove {}", paths.plist.display()))
+        }
+    }
+    Ok(())
+}
+
+fn status(paths: &ServicePaths) -> anyhow::Result<()> {
+    if !paths.plist.exists() {
+        println!("not installed");
+        return Ok(());
+    }
+    println!("installed: {}", paths.plist.display());
+    match launchctl(&["print", &service_target()?]) {
+        Ok(output) => {
+            let agent = parse_launchctl_print(&output);
+            match agent.pid {
+                Some(pid) => println!("state: {} (pid {pid})", agent.state),
+                None => println!("state: {}", agent.state),
+            }
+            if let Some(code) = agent.last_exit {
+                println!("last exit: {code}");
+            }
+        }
+        Err(_) => println!("state: not loaded (log in again or reinstall)"),
+    }
+    println!("transcript: {}", paths.transcript.display());
+    println!("log: {}", paths.log.display());
+    Ok(())
+}
+
+fn domain() -> anyhow::Result<String> {
+    let output = Command::new("id")
+        .arg("-u")
+        .output()
+        .context("failed to run id")?;
+    let uid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    anyhow::ensure!(
+        output.status.success() && !uid.is_empty(),
+        "failed to look up the user id"
+    );
+    Ok(format!("gui/{uid}"))
+}
+
+fn service_target() -> anyhow::Result<String> {
+    Ok(format!("{}/{LABEL}", domain()?))
+}
+
+fn launchctl(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("launchctl")
+        .args(args)
+        .output()
+        .context("failed to run launchctl")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "launchctl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
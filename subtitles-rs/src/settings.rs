@@ -0,0 +1,139 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::{CommandFactory, Parser};
+
+use crate::config::Cli;
+
+/// Persisted settings: long flag name (e.g. `"whisper-model-preset"`) to value. They are
+/// applied as if passed before the real command line, so explicit flags always win.
+pub type Settings = serde_json::Map<String, serde_json::Value>;
+
+/// The settings file written by the setup wizard.
+pub fn default_settings_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(
+        PathBuf::from(home)
+            .join("Library/Application Support/subtitles")
+            .join("settings.json"),
+    )
+}
+
+pub fn load_settings(path: &Path) -> anyhow::Result<Settings> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read settings from {}", path.display()))?;
+    serde_json::from_str(&raw)
+        .with_context(|| format!("invalid settings file {}", path.display()))
+}
+
+pub fn save_settings(path: &Path, settings: &Settings) -> anyhow::Result<()> {
+    // Catch typos before they make the next launch fail.
+    settings_to_args(settings)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(settings)?;
+    fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Parses the process command line on top of the `--config` file, or the default settings
+/// file when it exists.
+pub fn parse_cli() -> anyhow::Result<Cli> {
+    parse_cli_from(std::env::args_os(), default_settings_path())
+}
+
+pub fn parse_cli_from<I, T>(args: I, default_path: Option<PathBuf>) -> anyhow::Result<Cli>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    if args.is_empty() {
+        args.push("subtitles".into());
+    }
+
+    let settings = match config_arg(&args[1..]) {
+        Some(path) => Some(load_settings(&path)?),
+        None => match default_path.filter(|path| path.exists()) {
+            Some(path) => match load_settings(&path) {
+                Ok(settings) => Some(settings),
+                Err(err) => {
+                    tracing::warn!("ignoring settings file: {err:#}");
+                    None
+                }
+            },
+            None => None,
+        },
+    };
+
+    let mut full = vec![args[0].clone()];
+    if let Some(settings) = settings {
+        full.extend(settings_to_args(&settings)?.into_iter().map(OsString::from));
+    }
+    full.extend(args.drain(1..));
+    Ok(Cli::parse_from(full))
+}
+
+fn config_arg(args: &[OsString]) -> Option<PathBuf> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--config" {
+            return iter.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+fn settings_to_args(settings: &Settings) -> anyhow::Result<Vec<String>> {
+    let cmd = Cli::command();
+    let mut out = Vec::new();
+    for (key, value) in settings {
+        if key == "config" {
+            continue;
+        }
+        let arg = cmd
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+            .with_context(|| format!("unknown setting `{key}`"))?;
+        let takes_value = arg.get_action().takes_values();
+
+        let values = match value {
+            serde_json::Value::Array(items) => items.clone(),
+            // A block of named values, e.g. `"domain-model": { "medical": "med.bin" }`, is
+            // passed as `--domain-model=medical=med.bin` for each entry.
+            serde_json::Value::Object(entries) => entries
+                .iter()
+                .map(|(name, value)| match value {
+                    serde_json::Value::String(s) => Ok(format!("{name}={s}").into()),
+                    _ => anyhow::bail!("setting `{key}.{name}` must be a string"),
+                })
+                .collect::<anyhow::Result<_>>()?,
+            other => vec![other.clone()],
+        };
+        for value in values {
+            match value {
+                serde_json::Value::Null => {}
+                serde_json::Value::Bool(flag) if !takes_value => {
+                    if flag {
+                        out.push(format!("--{key}"));
+                    }
+                }
+                serde_json::Value::String(s) => out.push(format!("--{key}={s}")),
+                serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+                    out.push(format!("--{key}={value}"))
+                }
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    anyhow::bail!("setting `{key}` must be a string, number or boolean")
+                }
+            }
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,165 @@
+//! Engine-side checks behind the first-run setup flow.
+
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+use crate::audio::{AudioLevel, LevelMeter};
+use crate::config::WhisperModelPreset;
+use crate::macos_capture::{start_macos_system_audio_capture, CaptureControl, CaptureTarget};
+use crate::transcribe::{preset_model_path, resolve_whisper_model_path, DownloadProgress};
+
+/// Level readings are reported per block of this many 16 kHz samples (100 ms).
+const LEVEL_BLOCK_SAMPLES: usize = 1_600;
+/// RMS above this (about -50 dBFS) counts as "something is playing".
+const AUDIBLE_RMS: f32 = 0.003;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PermissionStatus {
+    pub screen_recording: bool,
+    /// Why the check failed, when it did.
+    pub detail: Option<String>,
+}
+
+/// ScreenCaptureKit refuses to list shareable content without Screen Recording permission,
+/// which makes that query the permission check.
+pub fn check_permissions() -> PermissionStatus {
+    #[cfg(not(target_os = "macos"))]
+    {
+        PermissionStatus {
+            screen_recording: false,
+            detail: Some("system audio capture is only supported on macOS".to_string()),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        match screencapturekit::prelude::SCShareableContent::get() {
+            Ok(_) => PermissionStatus {
+                screen_recording: true,
+                detail: None,
+            },
+            Err(err) => PermissionStatus {
+                screen_recording: false,
+                detail: Some(err.to_string()),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CaptureTest {
+    pub duration_s: f32,
+    /// Loudest block seen during the test.
+    pub max_level: AudioLevel,
+    pub heard_audio: bool,
+}
+
+/// Captures system audio for `duration`, reporting a level every 100 ms, so the user can
+/// confirm the right audio is being picked up before starting the engine.
+pub fn test_capture(
+    target: CaptureTarget,
+    duration: Duration,
+    on_level: &mut dyn FnMut(AudioLevel),
+) -> anyhow::Result<CaptureTest> {
+    run_capture(target, duration, on_level, &mut |_| {})
+}
+
+/// Like [`test_capture`], but also returns the captured 16 kHz mono audio.
+pub fn record_sample(
+    target: CaptureTarget,
+    duration: Duration,
+) -> anyhow::Result<(CaptureTest, Vec<f32>)> {
+    let mut audio = Vec::new();
+    let test = run_capture(target, duration, &mut |_| {}, &mut |chunk| {
+        audio.extend_from_slice(chunk)
+    })?;
+    Ok((test, audio))
+}
+
+fn run_capture(
+    target: CaptureTarget,
+    duration: Duration,
+    on_level: &mut dyn FnMut(AudioLevel),
+    on_audio: &mut dyn FnMut(&[f32]),
+) -> anyhow::Result<CaptureTest> {
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (target, duration, on_level, on_audio);
+        anyhow::bail!("system audio capture is only supported on macOS");
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let control = CaptureControl::new(target);
+        let (audio_tx, audio_rx) = crossbeam_channel::bounded::<crate::audio::AudioChunk>(256);
+        let capture = start_macos_system_audio_capture(audio_tx, stop.clone(), control)
+            .context("failed to start ScreenCaptureKit audio capture")?;
+
+        let started = Instant::now();
+        let mut meter = LevelMeter::new(LEVEL_BLOCK_SAMPLES);
+        let mut max_level = AudioLevel::default();
+        while started.elapsed() < duration && !stop.load(Ordering::Relaxed) {
+            let chunk = match audio_rx.recv_timeout(Duration::from_millis(50)) {
+                Ok(chunk) => chunk.samples,
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+            };
+            on_audio(&chunk);
+            for level in meter.push(&chunk) {
+                max_level = max_level.max(level);
+                on_level(level);
+            }
+        }
+
+        // The capture thread sets `stop` itself when it fails to start.
+        let failed = stop.swap(true, Ordering::Relaxed);
+        let _ = capture.join();
+        if failed {
+            anyhow::bail!(
+                "audio capture stopped unexpectedly (is Screen Recording permission granted?)"
+            );
+        }
+
+        Ok(CaptureTest {
+            duration_s: started.elapsed().as_secs_f32(),
+            max_level,
+            heard_audio: max_level.rms >= AUDIBLE_RMS,
+        })
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ModelInfo {
+    /// Value for `--whisper-model-preset`.
+    pub preset: String,
+    pub downloaded: bool,
+}
+
+pub fn model_presets() -> Vec<ModelInfo> {
+    use clap::ValueEnum;
+
+    WhisperModelPreset::value_variants()
+        .iter()
+        .filter_map(|preset| {
+            Some(ModelInfo {
+                preset: preset.to_possible_value()?.get_name().to_string(),
+                downloaded: preset_model_path(preset).exists(),
+            })
+        })
+        .collect()
+}
+
+/// Downloads a preset model ahead of time (no-op if it is already present).
+pub fn download_model(
+    preset: WhisperModelPreset,
+    on_progress: &mut dyn FnMut(DownloadProgress),
+) -> anyhow::Result<PathBuf> {
+    resolve_whisper_model_path(None, preset, on_progress)
+}
@@ -0,0 +1,207 @@
+//! Shadow engine (`--shadow-engine`): a second engine decodes every final in the background
+//! and never shows anything. Both outputs and how often they disagree are logged (and written
+//! to `--shadow-log`), to judge on real content whether the other engine is worth switching to.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use anyhow::Context;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use serde::Serialize;
+
+use crate::config::OutputLanguage;
+use crate::streaming::SharedAudio;
+use crate::text::FilterChain;
+use crate::transcribe::{agreement, decode_final, TranscriberConfig, TranscriberLoader, AGREEMENT};
+
+/// A final as the live engine showed it.
+pub struct ShadowJob {
+    pub segment_id: u64,
+    pub audio: SharedAudio,
+    pub output_language: OutputLanguage,
+    /// The live text, without the crosstalk tag so only the engines' words are compared.
+    pub live: String,
+    /// The live final was shown with `CROSSTALK_TAG`.
+    pub crosstalk: bool,
+}
+
+/// Agreement between the live and shadow engines so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ShadowStats {
+    pub compared: usize,
+    /// Finals less than `AGREEMENT` alike.
+    pub disagreed: usize,
+    agreement_sum: f64,
+}
+
+impl ShadowStats {
+    pub fn record(&mut self, agreement: f32) {
+        self.compared += 1;
+        if agreement < AGREEMENT {
+            self.disagreed += 1;
+        }
+        self.agreement_sum += agreement as f64;
+    }
+
+    /// Share of finals the engines disagreed on, `0.0..=1.0`.
+    pub fn disagreement_rate(&self) -> f64 {
+        self.disagreed as f64 / self.compared.max(1) as f64
+    }
+
+    pub fn mean_agreement(&self) -> f64 {
+        if self.compared == 0 {
+            return 1.0;
+        }
+        self.agreement_sum / self.compared as f64
+    }
+}
+
+/// One JSON line of `--shadow-log`.
+#[derive(Serialize)]
+struct Record<'a> {
+    segment_id: u64,
+    live: &'a str,
+    shadow: &'a str,
+    crosstalk: bool,
+    agreement: f32,
+    disagreement_rate: f64,
+}
+
+pub struct ShadowEngine {
+    tx: Sender<ShadowJob>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl ShadowEngine {
+    /// Starts the worker; `name` labels the shadow engine in the log. The log file is created
+    /// up front so a bad path fails at startup.
+    pub fn spawn(
+        name: impl Into<String>,
+        load: TranscriberLoader,
+        decode_cfg: TranscriberConfig,
+        filters: FilterChain,
+        log: Option<&Path>,
+        stop: Arc<AtomicBool>,
+    ) -> anyhow::Result<Self> {
+        let log = match log {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("failed to open {}", path.display()))?,
+            ),
+            None => None,
+        };
+        let name = name.into();
+        let (tx, rx) = crossbeam_channel::bounded::<ShadowJob>(16);
+        let handle = std::thread::spawn(move || {
+            worker_main(name, load, decode_cfg, filters, log, rx, stop);
+        });
+        Ok(Self { tx, handle })
+    }
+
+    pub fn submit(&self, job: ShadowJob) {
+        match self.tx.try_send(job) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("shadow engine queue full; skipping segment");
+            }
+            // The worker failed to load its engine and has already logged why.
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    /// Waits for the final being compared, if any, and logs the session's totals. Finals still
+    /// queued once the session has stopped are skipped.
+    pub fn join(self) {
+        drop(self.tx);
+        let _ = self.handle.join();
+    }
+}
+
+fn worker_main(
+    name: String,
+    load: TranscriberLoader,
+    decode_cfg: TranscriberConfig,
+    mut filters: FilterChain,
+    mut log: Option<File>,
+    rx: Receiver<ShadowJob>,
+    stop: Arc<AtomicBool>,
+) {
+    // Download progress isn't shown: the shadow engine must not disturb the overlay.
+    let mut transcriber = match load(&mut |_| {}) {
+        Ok(transcriber) => transcriber,
+        Err(err) => {
+            tracing::error!("shadow engine disabled: {err:#}");
+            return;
+        }
+    };
+
+    let mut stats = ShadowStats::default();
+    while !stop.load(Ordering::Relaxed) {
+        let job = match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(job) => job,
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        };
+
+        let shadow = match decode_final(
+            transcriber.as_mut(),
+            &job.audio,
+            &decode_cfg,
+            job.output_language,
+        ) {
+            Ok(shadow) => shadow,
+            Err(err) => {
+                tracing::warn!("shadow {name} transcription failed: {err:#}");
+                continue;
+            }
+        };
+        // Filtered like the live final, so only the engines' words are compared.
+        let shadow = filters.apply_text(job.segment_id, shadow, job.output_language);
+
+        let agreement = agreement(&job.live, &shadow);
+        stats.record(agreement);
+        tracing::info!(
+            "shadow {name}: segment {} agreement {agreement:.2} ({:.0}% disagreed so far)\n  \
+             live:   {}\n  {name}: {}",
+            job.segment_id,
+            stats.disagreement_rate() * 100.0,
+            job.live.trim(),
+            shadow.trim()
+        );
+        if let Some(file) = log.as_mut() {
+            let record = Record {
+                segment_id: job.segment_id,
+                live: job.live.trim(),
+                shadow: shadow.trim(),
+                crosstalk: job.crosstalk,
+                agreement,
+                disagreement_rate: stats.disagreement_rate(),
+            };
+            if let Ok(json) = serde_json::to_string(&record) {
+                if let Err(err) = writeln!(file, "{json}") {
+                    tracing::warn!("failed to write shadow log: {err}");
+                    log = None;
+                }
+            }
+        }
+    }
+
+    if stats.compared > 0 {
+        tracing::info!(
+            "shadow {name}: {} finals compared, {} disagreed ({:.0}%), mean agreement {:.2}",
+            stats.compared,
+            stats.disagreed,
+            stats.disagreement_rate() * 100.0,
+            stats.mean_agreement()
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! `--speak`: reads the English of each final aloud with macOS's `say`, optionally into a
+//! chosen output device (`--speak-device`), for following a talk by ear.
+//!
+//! Speech runs one utterance at a time on its own thread. When it falls behind, the oldest
+//! waiting lines are skipped so it stays close to the captions.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::Context;
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::app::{CaptionEvent, SharedOutputLanguage};
+use crate::config::{OutputLanguage, SoundTagMode};
+use crate::text::SoundTagFilter;
+
+/// Lines waiting to be spoken before older ones are dropped.
+const MAX_QUEUED: usize = 3;
+
+pub struct SpeechSink {
+    tx: Sender<String>,
+    /// Kept to drop the oldest line when the queue is full.
+    rx: Receiver<String>,
+    output_language: SharedOutputLanguage,
+    sound_tags: SoundTagFilter,
+}
+
+impl SpeechSink {
+    /// Speaks with `command` (program first), which gets each line on stdin; see [`say_command`].
+    pub fn start(command: Vec<String>, output_language: SharedOutputLanguage) -> Self {
+        let (tx, rx) = crossbeam_channel::bounded::<String>(MAX_QUEUED);
+        let worker_rx = rx.clone();
+        std::thread::spawn(move || {
+            let mut failed = false;
+            for line in worker_rx {
+                match speak(&command, &line) {
+                    Ok(()) => failed = false,
+                    // One warning per run of failures, not per caption.
+                    Err(err) if !failed => {
+                        tracing::warn!("--speak failed: {err:#}");
+                        failed = true;
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+        Self {
+            tx,
+            rx,
+            output_language,
+            sound_tags: SoundTagFilter::new(SoundTagMode::Strip, ""),
+        }
+    }
+
+    /// Queues the English of a final for speaking; anything else is ignored.
+    pub fn handle(&self, event: &CaptionEvent) {
+        let CaptionEvent::Update {
+            text,
+            is_final: true,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let Some(line) = english_line(text, self.output_language.get()) else {
+            return;
+        };
+        let line = self.sound_tags.apply(line);
+        if line.trim().is_empty() {
+            return;
+        }
+        if self.tx.is_full() {
+            tracing::debug!("speech is behind; skipping a line");
+            let _ = self.rx.try_recv();
+        }
+        let _ = self.tx.try_send(line.trim().to_string());
+    }
+}
+
+/// `say`, with `voice` (`say -v '?'` lists them) and an output `device` (name or id, see
+/// `say -a '?'`). Text comes on stdin.
+pub fn say_command(voice: Option<&str>, device: Option<&str>) -> Vec<String> {
+    let mut command = vec!["say".to_string()];
+    if let Some(voice) = voice {
+        command.extend(["-v".to_string(), voice.to_string()]);
+    }
+    if let Some(device) = device {
+        command.extend(["-a".to_string(), device.to_string()]);
+    }
+    command
+}
+
+/// The English in a caption: all of it in English mode, the translation line in bilingual mode
+/// (or the only line, unless it is Chinese), and nothing in Chinese mode.
+pub fn english_line(text: &str, output_language: OutputLanguage) -> Option<&str> {
+    match output_language {
+        OutputLanguage::English => Some(text),
+        OutputLanguage::Chinese => None,
+        OutputLanguage::Bilingual => match text.trim().split_once('\n') {
+            Some((_, translation)) => Some(translation),
+            None => (!text.chars().any(is_cjk)).then_some(text),
+        },
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{9FFF}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+fn speak(command: &[String], line: &str) -> anyhow::Result<()> {
+    let (program, args) = command.split_first().context("empty speech command")?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to start `{program}`"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(line.as_bytes())?;
+    }
+    let status = child.wait()?;
+    anyhow::ensure!(status.success(), "`{program}` exited with {status}");
+    Ok(())
+}
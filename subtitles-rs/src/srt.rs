@@ -0,0 +1,205 @@
+//! SubRip sidecar for `--record`: one cue per final, timed on the capture clock so it lines up
+//! with the recorded video.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::{CaptionEvent, CaptionTiming, Speaker};
+use crate::text::{ReadingSpeed, SoundTagFilter};
+
+/// Timed finals for a subtitle file, with second-pass revisions applied. Shared by the SRT and
+/// ASS writers.
+pub(crate) struct Cues {
+    sound_tags: SoundTagFilter,
+    reading: Option<ReadingSpeed>,
+    /// Each final with its words' `(start_s, end_s)`, when aligned.
+    cues: BTreeMap<u64, (CaptionTiming, String, Option<Speaker>, Vec<(f64, f64)>)>,
+    /// Names given to diarized speakers, applied to cues already taken too.
+    names: BTreeMap<u32, String>,
+}
+
+impl Cues {
+    pub(crate) fn new(sound_tags: SoundTagFilter) -> Self {
+        Self {
+            sound_tags,
+            reading: None,
+            cues: BTreeMap::new(),
+            names: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn set_reading_speed(&mut self, speed: Option<ReadingSpeed>) {
+        self.reading = speed;
+    }
+
+    /// Takes timed finals, revisions and speaker names; true when a cue changed.
+    pub(crate) fn handle(&mut self, event: &CaptionEvent) -> bool {
+        match event {
+            CaptionEvent::Update {
+                segment_id,
+                text,
+                is_final: true,
+                timing: Some(timing),
+                speaker,
+                words,
+                ..
+            } => {
+                let words = words
+                    .iter()
+                    .map(|word| (word.timing.start_s, word.timing.end_s))
+                    .collect();
+                self.cues
+                    .insert(*segment_id, (*timing, text.clone(), *speaker, words));
+                true
+            }
+            CaptionEvent::Revise { segment_id, text } => match self.cues.get_mut(segment_id) {
+                Some((_, cue, _, _)) => {
+                    *cue = text.clone();
+                    true
+                }
+                None => false,
+            },
+            CaptionEvent::SpeakerName { id, name } => {
+                let changed = if name.is_empty() {
+                    self.names.remove(id).is_some()
+                } else {
+                    self.names.insert(*id, name.clone()).as_ref() != Some(name)
+                };
+                changed
+                    && self
+                        .cues
+                        .values()
+                        .any(|(_, _, speaker, _)| speaker.is_some_and(|s| s.id == *id))
+            }
+            _ => false,
+        }
+    }
+
+    /// The name given to speaker `id`, if any.
+    pub(crate) fn speaker_name(&self, id: u32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    /// The cues to write, in order: sound tags applied, empty ones dropped and long ones split
+    /// at the reading speed. Aligned finals run from their first word to their last, and are
+    /// split where the words were spoken.
+    pub(crate) fn rendered(&self) -> Vec<(CaptionTiming, String, Option<Speaker>)> {
+        self.cues
+            .values()
+            .filter_map(|(timing, text, speaker, words)| {
+                let text = self.sound_tags.apply(text);
+                let timing = match (words.first(), words.last()) {
+                    (Some(first), Some(last)) => CaptionTiming {
+                        start_s: first.0,
+                        end_s: last.1,
+                    },
+                    _ => *timing,
+                };
+                (!text.trim().is_empty()).then_some((timing, text, *speaker, words))
+            })
+            .flat_map(|(timing, text, speaker, words)| {
+                let chunks = self.reading.map_or_else(Vec::new, |speed| {
+                    speed.word_timed_chunks(&text, words).unwrap_or_else(|| {
+                        speed.timed_chunks(&text, timing.start_s, timing.end_s)
+                    })
+                });
+                if chunks.len() < 2 {
+                    return vec![(timing, text, speaker)];
+                }
+                chunks
+                    .into_iter()
+                    .map(|chunk| {
+                        let timing = CaptionTiming {
+                            start_s: chunk.start_s,
+                            end_s: chunk.end_s,
+                        };
+                        (timing, chunk.text, speaker)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+pub struct SrtWriter {
+    path: PathBuf,
+    cues: Cues,
+}
+
+impl SrtWriter {
+    /// Creates (or truncates) the file up front so a bad path fails at startup.
+    pub fn create(path: &Path, sound_tags: SoundTagFilter) -> anyhow::Result<Self> {
+        fs::write(path, "").with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            cues: Cues::new(sound_tags),
+        })
+    }
+
+    /// Splits finals too long to read at `speed` into several cues (`--reading-cps`).
+    pub fn with_reading_speed(mut self, speed: Option<ReadingSpeed>) -> Self {
+        self.cues.set_reading_speed(speed);
+        self
+    }
+
+    /// Adds timed finals and applies second-pass revisions, rewriting the file when a cue
+    /// changes so it is complete even if the app is killed.
+    pub fn handle(&mut self, event: &CaptionEvent) -> anyhow::Result<()> {
+        if !self.push(event) {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// Like `handle`, without rewriting the file; true when a cue changed. Call `save` after
+    /// the last event.
+    pub fn push(&mut self, event: &CaptionEvent) -> bool {
+        self.cues.handle(event)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        fs::write(&self.path, self.render())
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (index, (timing, text, speaker)) in self.cues.rendered().into_iter().enumerate() {
+            // Players that understand `<font>` show each speaker in their overlay color; named
+            // speakers also get the usual `Name: ` prefix.
+            let text = match speaker {
+                Some(speaker) => {
+                    let text = match self.cues.speaker_name(speaker.id) {
+                        Some(name) => format!("{name}: {}", text.trim()),
+                        None => text.trim().to_string(),
+                    };
+                    format!("<font color=\"{}\">{text}</font>", speaker.color)
+                }
+                None => text.trim().to_string(),
+            };
+            out.push_str(&format!(
+                "{}\n{} --> {}\n{}\n\n",
+                index + 1,
+                format_timestamp(timing.start_s),
+                format_timestamp(timing.end_s),
+                text
+            ));
+        }
+        out
+    }
+}
+
+/// `HH:MM:SS,mmm`, as SubRip expects.
+pub fn format_timestamp(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
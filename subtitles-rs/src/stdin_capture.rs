@@ -0,0 +1,201 @@
+//! `--stdin-pcm s16le:48000:2`: raw PCM piped in by another tool (`ffmpeg ... -f s16le -`,
+//! `pw-cat --record -`, `arecord`), for platforms without a native capture backend. Samples
+//! are downmixed and resampled to 16 kHz mono like the other sources.
+
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam_channel::Sender;
+
+use crate::audio::{AudioChunk, ChunkClock, Resampler};
+
+/// Bytes read from stdin at a time.
+const READ_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmEncoding {
+    S16Le,
+    S32Le,
+    F32Le,
+}
+
+impl PcmEncoding {
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            PcmEncoding::S16Le => 2,
+            PcmEncoding::S32Le | PcmEncoding::F32Le => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            PcmEncoding::S16Le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            PcmEncoding::S32Le => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                    / 2_147_483_648.0
+            }
+            PcmEncoding::F32Le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// The layout of the piped audio, as `ENCODING:RATE:CHANNELS` (ffmpeg's names for the
+/// encoding: `s16le`, `s32le` or `f32le`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    pub encoding: PcmEncoding,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl FromStr for PcmFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("expected ENCODING:RATE:CHANNELS (e.g. s16le:48000:2), got `{s}`");
+        let mut parts = s.split(':');
+        let (Some(encoding), Some(rate), Some(channels), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(usage());
+        };
+        let encoding = match encoding.trim().to_ascii_lowercase().as_str() {
+            "s16le" => PcmEncoding::S16Le,
+            "s32le" => PcmEncoding::S32Le,
+            "f32le" => PcmEncoding::F32Le,
+            other => {
+                return Err(format!(
+                    "unknown encoding `{other}`; use s16le, s32le or f32le"
+                ))
+            }
+        };
+        let sample_rate = rate
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|rate| (8_000..=192_000).contains(rate))
+            .ok_or_else(|| format!("sample rate `{rate}` must be 8000..=192000"))?;
+        let channels = channels
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|&channels| (1..=32).contains(&channels))
+            .ok_or_else(usage)?;
+        Ok(Self {
+            encoding,
+            sample_rate,
+            channels,
+        })
+    }
+}
+
+impl fmt::Display for PcmFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoding = match self.encoding {
+            PcmEncoding::S16Le => "s16le",
+            PcmEncoding::S32Le => "s32le",
+            PcmEncoding::F32Le => "f32le",
+        };
+        write!(f, "{encoding}:{}:{}", self.sample_rate, self.channels)
+    }
+}
+
+/// Turns byte reads of any size into 16 kHz mono, keeping a frame split across reads for the
+/// next one.
+pub struct PcmDecoder {
+    format: PcmFormat,
+    resampler: Resampler,
+    pending: Vec<u8>,
+}
+
+impl PcmDecoder {
+    pub fn new(format: PcmFormat) -> Self {
+        Self {
+            format,
+            resampler: Resampler::new(format.sample_rate, 16_000),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<f32> {
+        self.pending.extend_from_slice(bytes);
+        let sample_bytes = self.format.encoding.bytes_per_sample();
+        let frame_bytes = sample_bytes * usize::from(self.format.channels);
+        let whole = self.pending.len() / frame_bytes * frame_bytes;
+        let mut out = Vec::with_capacity(whole / frame_bytes);
+        for frame in self.pending[..whole].chunks_exact(frame_bytes) {
+            let sum: f32 = frame
+                .chunks_exact(sample_bytes)
+                .map(|sample| self.format.encoding.decode(sample))
+                .sum();
+            self.resampler
+                .push(sum / f32::from(self.format.channels), &mut out);
+        }
+        self.pending.drain(..whole);
+        out
+    }
+}
+
+/// Reads `format` audio from stdin until it closes or `stop` is set. The returned thread ends
+/// at end of input, which ends the engine once what was captured is transcribed.
+pub fn start_stdin_capture(
+    format: PcmFormat,
+    audio_tx: Sender<AudioChunk>,
+    stop: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
+    tracing::info!("capturing {format} PCM from stdin");
+    let (done_tx, done_rx) = crossbeam_channel::bounded::<()>(1);
+    // A read blocks until the writer sends more, so it gets a thread of its own that may
+    // outlive `stop`; this one is what gets joined.
+    std::thread::spawn(move || {
+        forward_pcm(std::io::stdin().lock(), format, &audio_tx);
+        drop(audio_tx);
+        let _ = done_tx.send(());
+    });
+    std::thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            match done_rx.recv_timeout(Duration::from_millis(100)) {
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+                _ => {
+                    tracing::info!("end of input on stdin");
+                    return;
+                }
+            }
+        }
+        tracing::info!("stopping capture");
+    })
+}
+
+/// Decodes `input` until it closes; returns the 16 kHz samples sent.
+pub fn forward_pcm(
+    mut input: impl Read,
+    format: PcmFormat,
+    audio_tx: &Sender<AudioChunk>,
+) -> usize {
+    let mut decoder = PcmDecoder::new(format);
+    let mut clock = ChunkClock::asr();
+    let mut buf = vec![0u8; READ_BYTES];
+    let mut sent = 0;
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                tracing::warn!("failed to read stdin: {err}");
+                break;
+            }
+        };
+        let samples = decoder.push(&buf[..n]);
+        if !samples.is_empty() {
+            sent += samples.len();
+            // Dropped if the pipeline is behind, like the other capture sources.
+            let _ = audio_tx.try_send(clock.stamp(samples));
+        }
+    }
+    sent
+}
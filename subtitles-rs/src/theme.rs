@@ -0,0 +1,268 @@
+//! Caption themes (`--caption-theme`): text and outline colors, a background box and letter
+//! spacing for the overlay, sent to the UI with its config and switchable while running.
+//!
+//! Every theme but `default` puts the text on a near-opaque box with at least WCAG AAA
+//! contrast (7:1), whatever the overlay sits on.
+
+use std::sync::Arc;
+
+use clap::ValueEnum;
+
+use crate::config::Cli;
+
+/// Colors for `--diarize` speakers, in order of first appearance: the overlay's speaker bar
+/// and the text color in subtitle files. Each keeps AA contrast on the boxed themes' black box
+/// and is easy to tell apart from its neighbours.
+pub const SPEAKER_COLORS: [&str; 6] = [
+    "#ffff00", "#00ffff", "#7cff4f", "#ff9ee8", "#ffb347", "#ffffff",
+];
+
+/// WCAG 2 AAA contrast for body text.
+pub const AAA_CONTRAST: f64 = 7.0;
+/// WCAG 2 AA contrast for body text.
+pub const AA_CONTRAST: f64 = 4.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CaptionTheme {
+    /// Light text with a thin outline straight on the overlay.
+    #[default]
+    Default,
+    /// White on a black box.
+    HighContrast,
+    /// Yellow on a black box, the broadcast caption look.
+    YellowOnBlack,
+    /// Black on a white box.
+    BlackOnWhite,
+    /// High contrast with 1.5x text and wide letter spacing.
+    LargePrint,
+}
+
+/// How a theme draws captions; colors are `#rrggbb`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ThemeStyle {
+    pub name: &'static str,
+    pub text_color: &'static str,
+    pub outline_color: &'static str,
+    pub outline_px: f32,
+    pub background_color: String,
+    /// 0 hides the box.
+    pub background_opacity: f32,
+    /// Room between the text and the edge of the box.
+    pub background_padding_em: f32,
+    pub letter_spacing_em: f32,
+    /// Multiplies the overlay font size.
+    pub font_scale: f32,
+}
+
+impl CaptionTheme {
+    pub const ALL: [CaptionTheme; 5] = [
+        Self::Default,
+        Self::HighContrast,
+        Self::YellowOnBlack,
+        Self::BlackOnWhite,
+        Self::LargePrint,
+    ];
+
+    /// The theme called `name` (as on the command line).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::from_str(name.trim(), true).ok()
+    }
+
+    pub fn style(self) -> ThemeStyle {
+        let boxed = |name, text_color, outline_color, background_color: &str| ThemeStyle {
+            name,
+            text_color,
+            outline_color,
+            outline_px: 2.0,
+            background_color: background_color.to_string(),
+            background_opacity: 0.9,
+            background_padding_em: 0.3,
+            letter_spacing_em: 0.04,
+            font_scale: 1.0,
+        };
+        match self {
+            Self::Default => ThemeStyle {
+                name: "default",
+                text_color: "#f8fafc",
+                outline_color: "#000000",
+                outline_px: 1.0,
+                background_color: "#000000".to_string(),
+                background_opacity: 0.0,
+                background_padding_em: 0.0,
+                letter_spacing_em: 0.02,
+                font_scale: 1.0,
+            },
+            Self::HighContrast => boxed("high-contrast", "#ffffff", "#000000", "#000000"),
+            Self::YellowOnBlack => boxed("yellow-on-black", "#ffff00", "#000000", "#000000"),
+            Self::BlackOnWhite => ThemeStyle {
+                outline_px: 0.0,
+                ..boxed("black-on-white", "#000000", "#ffffff", "#ffffff")
+            },
+            Self::LargePrint => ThemeStyle {
+                outline_px: 3.0,
+                background_opacity: 0.95,
+                // WCAG 1.4.12's letter spacing.
+                letter_spacing_em: 0.12,
+                font_scale: 1.5,
+                ..boxed("large-print", "#ffffff", "#000000", "#000000")
+            },
+        }
+    }
+}
+
+impl ThemeStyle {
+    /// This style with `caption_box`'s settings in place of the theme's own box.
+    pub fn with_box(mut self, caption_box: &CaptionBox) -> Self {
+        if let Some(color) = &caption_box.color {
+            self.background_color = color.clone();
+        }
+        if let Some(opacity) = caption_box.opacity {
+            self.background_opacity = opacity;
+        }
+        if let Some(padding_em) = caption_box.padding_em {
+            self.background_padding_em = padding_em;
+        }
+        self
+    }
+
+    /// Contrast of the text against its box over the worst case behind it (black or white);
+    /// `None` if a color doesn't parse.
+    pub fn box_contrast(&self) -> Option<f64> {
+        let text = parse_hex(self.text_color)?;
+        let background = parse_hex(&self.background_color)?;
+        let alpha = f64::from(self.background_opacity.clamp(0.0, 1.0));
+        [[0.0; 3], [1.0; 3]]
+            .into_iter()
+            .map(|behind| {
+                let shown =
+                    std::array::from_fn(|i| background[i] * alpha + behind[i] * (1.0 - alpha));
+                ratio(luminance(text), luminance(shown))
+            })
+            .reduce(f64::min)
+    }
+}
+
+/// WCAG 2 contrast ratio between two `#rrggbb` colors, from 1 to 21.
+pub fn contrast_ratio(a: &str, b: &str) -> Option<f64> {
+    Some(ratio(luminance(parse_hex(a)?), luminance(parse_hex(b)?)))
+}
+
+fn ratio(a: f64, b: f64) -> f64 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Relative luminance of sRGB channels in 0..=1.
+fn luminance(rgb: [f64; 3]) -> f64 {
+    let linear = |c: f64| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(rgb[0]) + 0.7152 * linear(rgb[1]) + 0.0722 * linear(rgb[2])
+}
+
+fn parse_hex(color: &str) -> Option<[f64; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|c| f64::from(c) / 255.0)
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// A solid box behind the caption text (`--caption-box-*`), for reading over bright video where
+/// an outline isn't enough. Unset fields keep the theme's box.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+pub struct CaptionBox {
+    /// `#rrggbb`.
+    pub color: Option<String>,
+    /// 0 (no box) to 1 (solid).
+    pub opacity: Option<f32>,
+    pub padding_em: Option<f32>,
+}
+
+impl CaptionBox {
+    /// The box from `--caption-box-color`, `--caption-box-opacity` and
+    /// `--caption-box-padding-em`.
+    pub fn from_cli(cli: &Cli) -> anyhow::Result<Self> {
+        let caption_box = Self {
+            color: cli.caption_box_color.clone(),
+            opacity: cli.caption_box_opacity,
+            padding_em: cli.caption_box_padding_em,
+        };
+        caption_box.validate()?;
+        Ok(caption_box)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(color) = &self.color {
+            anyhow::ensure!(
+                parse_hex(color).is_some(),
+                "caption box color must look like #rrggbb, got {color:?}"
+            );
+        }
+        if let Some(opacity) = self.opacity {
+            anyhow::ensure!(
+                (0.0..=1.0).contains(&opacity),
+                "caption box opacity must be between 0 and 1"
+            );
+        }
+        if let Some(padding_em) = self.padding_em {
+            anyhow::ensure!(
+                (0.0..=MAX_BOX_PADDING_EM).contains(&padding_em),
+                "caption box padding must be between 0 and {MAX_BOX_PADDING_EM} em"
+            );
+        }
+        Ok(())
+    }
+}
+
+const MAX_BOX_PADDING_EM: f32 = 2.0;
+
+/// The overlay's current theme and box, changed from the UI while running.
+#[derive(Debug, Clone, Default)]
+pub struct SharedCaptionTheme {
+    inner: Arc<parking_lot::Mutex<(CaptionTheme, CaptionBox)>>,
+}
+
+impl SharedCaptionTheme {
+    pub fn new(theme: CaptionTheme, caption_box: CaptionBox) -> Self {
+        Self {
+            inner: Arc::new(parking_lot::Mutex::new((theme, caption_box))),
+        }
+    }
+
+    pub fn get(&self) -> CaptionTheme {
+        self.inner.lock().0
+    }
+
+    pub fn set(&self, theme: CaptionTheme) {
+        self.inner.lock().0 = theme;
+    }
+
+    pub fn caption_box(&self) -> CaptionBox {
+        self.inner.lock().1.clone()
+    }
+
+    /// Changes the box settings given in `update`, keeping the rest.
+    pub fn update_box(&self, update: CaptionBox) {
+        let current = &mut self.inner.lock().1;
+        *current = CaptionBox {
+            color: update.color.or(current.color.take()),
+            opacity: update.opacity.or(current.opacity),
+            padding_em: update.padding_em.or(current.padding_em),
+        };
+    }
+
+    /// The theme's style with the box settings applied.
+    pub fn style(&self) -> ThemeStyle {
+        let inner = self.inner.lock();
+        inner.0.style().with_box(&inner.1)
+    }
+}
@@ -0,0 +1,221 @@
+//! Update checks against a JSON release feed, and `subtitles self-update` for the CLI.
+//!
+//! The feed looks like:
+//!
+//! ```json
+//! {
+//!   "version": "0.2.0",
+//!   "notes": "What changed",
+//!   "platforms": {
+//!     "macos-aarch64": { "url": "https://…/subtitles", "signature": "<base64 ed25519>" }
+//!   }
+//! }
+//! ```
+//!
+//! Downloaded binaries must carry an Ed25519 signature from the key baked in at build time
+//! (`SUBTITLES_UPDATE_PUBLIC_KEY`, base64); builds without one can check but not install.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use base64::Engine as _;
+use clap::Parser;
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{send_status, CaptionEvent, EngineStatus};
+use crate::network::{audit, AuditRecord};
+
+pub const DEFAULT_FEED: &str =
+    "https://github.com/geohotstan/live-subtitles/releases/latest/download/latest.json";
+/// Release signing key (base64 Ed25519 public key), set when building releases.
+pub const PUBLIC_KEY: Option<&str> = option_env!("SUBTITLES_UPDATE_PUBLIC_KEY");
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const FEED_TIMEOUT: Duration = Duration::from_secs(15);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseFeed {
+    pub version: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub platforms: BTreeMap<String, ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub url: String,
+    pub signature: String,
+}
+
+/// A newer release than this build, as shown to the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles self-update",
+    about = "Download and install the latest release of this binary"
+)]
+pub struct SelfUpdateCli {
+    /// Release feed to read.
+    #[arg(long, default_value = DEFAULT_FEED)]
+    pub feed: String,
+
+    /// Only report whether an update is available.
+    #[arg(long)]
+    pub check: bool,
+}
+
+/// `os-arch` key into the feed's `platforms`, e.g. `macos-aarch64`.
+pub fn platform_key() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Whether `candidate` is a later `major.minor.patch` than `current` (a leading `v` and any
+/// pre-release suffix are ignored). Unparseable versions never count as newer.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => false,
+    }
+}
+
+fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+    let text = text.trim().trim_start_matches('v');
+    let core = text.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(str::parse::<u64>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+/// Checks `data` against a base64 Ed25519 `signature` made with the base64 `public_key`.
+pub fn verify_signature(data: &[u8], signature: &str, public_key: &str) -> anyhow::Result<()> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let signature = b64
+        .decode(signature.trim())
+        .context("signature is not valid base64")?;
+    let public_key = b64
+        .decode(public_key.trim())
+        .context("public key is not valid base64")?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(data, &signature)
+        .map_err(|_| anyhow::anyhow!("signature does not match the release signing key"))
+}
+
+pub fn parse_feed(json: &str) -> anyhow::Result<ReleaseFeed> {
+    serde_json::from_str(json).context("invalid release feed")
+}
+
+/// The feed's release, if it is newer than this build.
+pub fn newer_release(feed: &ReleaseFeed) -> Option<UpdateInfo> {
+    is_newer(&feed.version, CURRENT_VERSION).then(|| UpdateInfo {
+        version: feed.version.clone(),
+        current: CURRENT_VERSION.to_string(),
+        notes: feed.notes.clone(),
+    })
+}
+
+fn get(url: &str, purpose: &str, timeout: Duration) -> anyhow::Result<Vec<u8>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .context("failed to build HTTP client")?;
+    let response = client.get(url).send();
+    let status = response.as_ref().ok().map(|r| r.status().as_u16());
+    let body = response
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.bytes());
+    audit(AuditRecord {
+        purpose,
+        method: "GET",
+        destination: url,
+        bytes_sent: 0,
+        bytes_received: body.as_ref().map_or(0, |b| b.len() as u64),
+        status,
+        error: body.as_ref().err().map(|e| e.to_string()),
+    });
+    Ok(body
+        .with_context(|| format!("failed to download {url}"))?
+        .to_vec())
+}
+
+pub fn fetch_feed(url: &str) -> anyhow::Result<ReleaseFeed> {
+    let body = get(url, "update-check", FEED_TIMEOUT)?;
+    parse_feed(&String::from_utf8_lossy(&body))
+}
+
+/// Checks once in the background and reports a newer release on the status channel.
+pub fn spawn_update_check(feed_url: String, caption_tx: Sender<CaptionEvent>) {
+    std::thread::spawn(move || match fetch_feed(&feed_url) {
+        Ok(feed) => {
+            if let Some(update) = newer_release(&feed) {
+                tracing::info!(
+                    "subtitles {} is available (this is {})",
+                    update.version,
+                    update.current
+                );
+                send_status(&caption_tx, EngineStatus::UpdateAvailable(update));
+            }
+        }
+        Err(err) => tracing::debug!("update check failed: {err:#}"),
+    });
+}
+
+pub fn run(cli: SelfUpdateCli) -> anyhow::Result<()> {
+    let feed = fetch_feed(&cli.feed)?;
+    let Some(update) = newer_release(&feed) else {
+        println!("subtitles {CURRENT_VERSION} is up to date");
+        return Ok(());
+    };
+    println!(
+        "subtitles {} is available (this is {})",
+        update.version, update.current
+    );
+    if let Some(notes) = &update.notes {
+        println!("\n{notes}\n");
+    }
+    if cli.check {
+        return Ok(());
+    }
+
+    let public_key = PUBLIC_KEY.context(
+        "this build has no release signing key, so updates can't be verified; download the \
+         release manually",
+    )?;
+    let platform = platform_key();
+    let asset = feed
+        .platforms
+        .get(&platform)
+        .with_context(|| format!("release {} has no build for {platform}", update.version))?;
+
+    let binary = get(&asset.url, "update-download", DOWNLOAD_TIMEOUT)?;
+    verify_signature(&binary, &asset.signature, public_key)?;
+    let exe = std::env::current_exe().context("failed to locate this executable")?;
+    install(&exe, &binary)?;
+    println!("updated {} to {}", exe.display(), update.version);
+    Ok(())
+}
+
+/// Replaces `exe` with `binary` via a rename, so a failure leaves the old one in place.
+fn install(exe: &Path, binary: &[u8]) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let staged = exe.with_extension("update");
+    fs::write(&staged, binary).with_context(|| format!("failed to write {}", staged.display()))?;
+    fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    fs::rename(&staged, exe).with_context(|| {
+        let _ = fs::remove_file(&staged);
+        format!("failed to replace {}", exe.display())
+    })
+}
@@ -0,0 +1,188 @@
+//! Spoken commands (`--voice-commands`): the wake word followed by a command, e.g. "subtitle
+//! pause" or "subtitle translate bilingual", found in finals and carried out by the engine, so
+//! a presenter can control captions without touching the keyboard. The command's words are
+//! taken out of the caption.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+use crate::config::OutputLanguage;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VoiceAction {
+    /// Stop showing captions (transcription keeps running to hear "resume").
+    Pause,
+    Resume,
+    /// Clear the caption on screen.
+    Clear,
+    /// Switch the output language; the name is as spoken.
+    Translate(String),
+    /// Switch to a `--domain-model` (or `default`).
+    Domain(String),
+}
+
+impl FromStr for VoiceAction {
+    type Err = String;
+
+    /// `pause`, `resume`, `clear`, `translate <language>` or `domain <name>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<String> = words(s).into_iter().map(|(word, _)| word).collect();
+        match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["pause"] => Ok(Self::Pause),
+            ["resume"] => Ok(Self::Resume),
+            ["clear"] => Ok(Self::Clear),
+            ["translate", language] => Ok(Self::Translate(language.to_string())),
+            ["domain", name] => Ok(Self::Domain(name.to_string())),
+            _ => Err(format!(
+                "unknown action `{s}` (expected pause, resume, clear, translate <language> or \
+                 domain <name>)"
+            )),
+        }
+    }
+}
+
+/// The output language a spoken name stands for. Whisper only translates into English, so
+/// e.g. "japanese" has none.
+pub fn spoken_output_language(name: &str) -> Option<OutputLanguage> {
+    match name {
+        "mandarin" | "original" => Some(OutputLanguage::Chinese),
+        "both" => Some(OutputLanguage::Bilingual),
+        name => OutputLanguage::from_str(name, true).ok(),
+    }
+}
+
+/// A command found in a caption, and the caption without it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpottedCommand {
+    pub action: VoiceAction,
+    pub rest: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandSpotter {
+    wake: Vec<String>,
+    /// Phrases from `--voice-command-file`, matched as written.
+    phrases: Vec<(Vec<String>, VoiceAction)>,
+}
+
+impl CommandSpotter {
+    /// The built-in commands after `wake_word`: pause (or stop), resume (or continue), clear,
+    /// translate <language>, and domain (or model) <name>.
+    pub fn new(wake_word: &str) -> anyhow::Result<Self> {
+        let wake: Vec<String> = words(wake_word).into_iter().map(|(word, _)| word).collect();
+        anyhow::ensure!(!wake.is_empty(), "--wake-word must contain a word");
+        Ok(Self {
+            wake,
+            phrases: Vec::new(),
+        })
+    }
+
+    /// Adds phrases from a JSON object of phrase to action, e.g. `{"next speaker please":
+    /// "clear"}`.
+    pub fn with_phrases_file(mut self, path: &Path) -> anyhow::Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let phrases: BTreeMap<String, String> = serde_json::from_str(&raw)
+            .with_context(|| format!("invalid voice commands file {}", path.display()))?;
+        for (phrase, action) in phrases {
+            let action = action
+                .parse()
+                .map_err(|err| anyhow::anyhow!("{}: `{phrase}`: {err}", path.display()))?;
+            self.add_phrase(&phrase, action)?;
+        }
+        Ok(self)
+    }
+
+    pub fn add_phrase(&mut self, phrase: &str, action: VoiceAction) -> anyhow::Result<()> {
+        let phrase: Vec<String> = words(phrase).into_iter().map(|(word, _)| word).collect();
+        anyhow::ensure!(!phrase.is_empty(), "empty voice command phrase");
+        self.phrases.push((phrase, action));
+        Ok(())
+    }
+
+    /// The first command in `text`, if any. Matching ignores case and punctuation, and
+    /// accepts the wake word with a trailing "s" ("subtitles pause").
+    pub fn spot(&self, text: &str) -> Option<SpottedCommand> {
+        let words = words(text);
+        let found = (0..words.len()).find_map(|start| {
+            self.custom_at(&words, start)
+                .or_else(|| self.builtin_at(&words, start))
+                .map(|(len, action)| (start, len, action))
+        })?;
+        let (start, len, action) = found;
+        let from = words[start].1.start;
+        let to = words[start + len - 1].1.end;
+        let before = text[..from].trim_end();
+        let after = text[to..].trim_start_matches(|c: char| !c.is_alphanumeric() && c != '\n');
+        let rest = match (before.is_empty(), after.trim().is_empty()) {
+            (true, _) => after.trim().to_string(),
+            (false, true) => before.to_string(),
+            (false, false) => format!("{before} {}", after.trim()),
+        };
+        Some(SpottedCommand { action, rest })
+    }
+
+    fn custom_at(&self, words: &[Word], start: usize) -> Option<(usize, VoiceAction)> {
+        self.phrases.iter().find_map(|(phrase, action)| {
+            let window = words.get(start..start + phrase.len())?;
+            window
+                .iter()
+                .zip(phrase)
+                .all(|((word, _), want)| word == want)
+                .then(|| (phrase.len(), action.clone()))
+        })
+    }
+
+    fn builtin_at(&self, words: &[Word], start: usize) -> Option<(usize, VoiceAction)> {
+        let wake = self.wake.len();
+        let window = words.get(start..start + wake)?;
+        let matches = window
+            .iter()
+            .zip(&self.wake)
+            .enumerate()
+            .all(|(i, ((word, _), want))| {
+                word == want || (i == wake - 1 && word.strip_suffix('s') == Some(want.as_str()))
+            });
+        if !matches {
+            return None;
+        }
+        let verb = words.get(start + wake).map(|(word, _)| word.as_str())?;
+        let argument = words.get(start + wake + 1).map(|(word, _)| word.clone());
+        let (action, len) = match (verb, argument) {
+            ("pause" | "stop", _) => (VoiceAction::Pause, 1),
+            ("resume" | "continue", _) => (VoiceAction::Resume, 1),
+            ("clear", _) => (VoiceAction::Clear, 1),
+            ("translate" | "language", Some(language)) => (VoiceAction::Translate(language), 2),
+            ("domain" | "model", Some(name)) => (VoiceAction::Domain(name), 2),
+            _ => return None,
+        };
+        Some((wake + len, action))
+    }
+}
+
+type Word = (String, std::ops::Range<usize>);
+
+/// Lowercased words and their byte ranges in `text`.
+fn words(text: &str) -> Vec<Word> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric() || c == '\'', start) {
+            (true, None) => start = Some(i),
+            (false, Some(from)) => {
+                let word = text[from..i].replace('\'', "").to_lowercase();
+                if !word.is_empty() {
+                    out.push((word, from..i));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out
+}
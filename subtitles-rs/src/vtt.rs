@@ -0,0 +1,77 @@
+//! WebVTT output for file transcription (`--format vtt`): the same cues as the SRT, for
+//! browsers' `<track>` and players that prefer it. Speakers become voice spans (`<v>`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::CaptionEvent;
+use crate::srt::Cues;
+use crate::text::SoundTagFilter;
+
+pub struct VttWriter {
+    path: PathBuf,
+    cues: Cues,
+}
+
+impl VttWriter {
+    /// Creates (or truncates) the file up front so a bad path fails at startup.
+    pub fn create(path: &Path, sound_tags: SoundTagFilter) -> anyhow::Result<Self> {
+        fs::write(path, "").with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            cues: Cues::new(sound_tags),
+        })
+    }
+
+    /// Adds timed finals and applies revisions; true when a cue changed. Call `save` after the
+    /// last event.
+    pub fn push(&mut self, event: &CaptionEvent) -> bool {
+        self.cues.handle(event)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        fs::write(&self.path, self.render())
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::from("WEBVTT\n\n");
+        for (timing, text, speaker) in self.cues.rendered() {
+            let text = escape_text(text.trim());
+            let text = match speaker {
+                Some(speaker) => match self.cues.speaker_name(speaker.id) {
+                    Some(name) => format!("<v {}>{text}", escape_text(name)),
+                    None => format!("<v Speaker {}>{text}", speaker.id + 1),
+                },
+                None => text,
+            };
+            out.push_str(&format!(
+                "{} --> {}\n{text}\n\n",
+                format_timestamp(timing.start_s),
+                format_timestamp(timing.end_s),
+            ));
+        }
+        out
+    }
+}
+
+/// `HH:MM:SS.mmm`, as WebVTT expects.
+pub fn format_timestamp(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// Cue text can't contain `-->`, and `&` and `<` start entities and tags.
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,220 @@
+//! `subtitles watch`: transcribes recordings as they appear in a folder, writing `<name>.srt`
+//! next to each one, so lectures recorded to that folder get subtitles without a batch run.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use clap::{ArgAction, Parser};
+use notify::event::ModifyKind;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::batch::{audio_files, is_audio_file, transcribe_file, BatchOptions, TranscribeArgs};
+use crate::transcribe::Transcriber;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "subtitles watch",
+    about = "Transcribe recordings to .srt as they are added to a folder"
+)]
+pub struct WatchCli {
+    /// Folder to watch.
+    #[arg(long)]
+    pub dir: PathBuf,
+
+    /// Also watch subfolders.
+    #[arg(long)]
+    pub recursive: bool,
+
+    /// Seconds a new file's size must stay the same before it is transcribed, so recordings
+    /// still being written or copied aren't picked up half done.
+    #[arg(long, default_value_t = 5.0)]
+    pub settle_s: f64,
+
+    /// At startup, transcribe recordings already in the folder that have no `.srt` yet.
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub existing: bool,
+
+    #[command(flatten)]
+    pub transcribe: TranscribeArgs,
+}
+
+pub fn run(cli: WatchCli) -> anyhow::Result<()> {
+    anyhow::ensure!(cli.dir.is_dir(), "{} is not a directory", cli.dir.display());
+    let mut transcribers = cli.transcribe.load_transcribers(1)?;
+    let transcriber = transcribers.pop().context("no transcriber")?;
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+        ctrlc::set_handler(move || stop.store(true, Ordering::Relaxed))
+            .context("failed to set Ctrl-C handler")?;
+    }
+    watch(
+        &cli.dir,
+        cli.recursive,
+        cli.existing,
+        Duration::from_secs_f64(cli.settle_s.max(0.0)),
+        &cli.transcribe.options(None, false),
+        transcriber,
+        &stop,
+    )
+}
+
+/// Transcribes recordings that appear under `dir` until `stop` is set. A file is only picked
+/// up once its size has held still for `settle`; one that changes after being transcribed
+/// (e.g. replaced by a new take) is transcribed again.
+pub fn watch(
+    dir: &Path,
+    recursive: bool,
+    existing: bool,
+    settle: Duration,
+    options: &BatchOptions,
+    mut transcriber: Box<dyn Transcriber>,
+    stop: &AtomicBool,
+) -> anyhow::Result<()> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .context("failed to start watching files")?;
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(dir, mode)
+        .with_context(|| format!("failed to watch {}", dir.display()))?;
+    tracing::info!("watching {} for recordings", dir.display());
+
+    let mut pending = PendingFiles::new(settle);
+    if existing {
+        let now = Instant::now();
+        for file in existing_files(dir, recursive)? {
+            if !options.output_for(&file).exists() {
+                pending.note(&file, now);
+            }
+        }
+    }
+
+    let mut done = 0;
+    while !stop.load(Ordering::Relaxed) {
+        match rx.recv_timeout(Duration::from_millis(250)) {
+            Ok(Ok(event)) => {
+                let changed = match event.kind {
+                    EventKind::Create(_) => true,
+                    // A touch, chmod or xattr change leaves the recording as it was.
+                    EventKind::Modify(kind) => !matches!(kind, ModifyKind::Metadata(_)),
+                    _ => false,
+                };
+                if changed {
+                    let now = Instant::now();
+                    for path in event.paths.iter().filter(|path| is_audio_file(path)) {
+                        pending.note(path, now);
+                    }
+                }
+            }
+            Ok(Err(err)) => tracing::warn!("file watcher: {err}"),
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        }
+
+        for file in pending.ready(Instant::now()) {
+            done += 1;
+            // Picked up because it is new or changed, so an older `.srt` is out of date.
+            let options = BatchOptions {
+                overwrite: true,
+                ..options.clone()
+            };
+            transcribe_file(&file, &options, &mut *transcriber, &format!("[{done}]"));
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Recordings already under `dir`, including its subfolders when `recursive` is set.
+fn existing_files(dir: &Path, recursive: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = audio_files(dir)?;
+    if recursive {
+        let entries =
+            std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            // Not through symlinks, which could loop back up the tree.
+            if entry.file_type()?.is_dir() {
+                files.extend(existing_files(&entry.path(), true)?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Files waiting for their size to settle.
+#[derive(Debug)]
+pub struct PendingFiles {
+    settle: Duration,
+    /// Size when last checked, and when it last changed.
+    files: HashMap<PathBuf, (Option<u64>, Instant)>,
+}
+
+impl PendingFiles {
+    pub fn new(settle: Duration) -> Self {
+        Self {
+            settle,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Records that `path` was created or written to.
+    pub fn note(&mut self, path: &Path, now: Instant) {
+        self.files
+            .insert(path.to_path_buf(), (file_size(path), now));
+    }
+
+    /// Files whose size hasn't changed for `settle`, oldest first; they are no longer pending.
+    /// Files that have disappeared are dropped.
+    pub fn ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let mut ready = Vec::new();
+        self.files.retain(|path, (size, changed)| {
+            let current = file_size(path);
+            if current.is_none() {
+                return false;
+            }
+            if current != *size {
+                *size = current;
+                *changed = now;
+                return true;
+            }
+            if now.duration_since(*changed) < self.settle || current == Some(0) {
+                return true;
+            }
+            ready.push((*changed, path.clone()));
+            false
+        });
+        ready.sort();
+        ready.into_iter().map(|(_, path)| path).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+}
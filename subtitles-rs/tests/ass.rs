@@ -0,0 +1,69 @@
+use subtitles::app::{CaptionEvent, CaptionTiming, Speaker};
+use subtitles::ass::{format_timestamp, AssStyle, AssWriter};
+use subtitles::config::SoundTagMode;
+use subtitles::text::SoundTagFilter;
+use subtitles::theme::CaptionTheme;
+
+fn final_update(segment_id: u64, text: &str, start_s: f64, speaker: Option<u32>) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text: text.to_string(),
+        is_final: true,
+        timing: Some(CaptionTiming {
+            start_s,
+            end_s: start_s + 1.5,
+        }),
+        speaker: speaker.map(Speaker::new),
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    }
+}
+
+fn writer(name: &str, theme: CaptionTheme) -> AssWriter {
+    let dir = std::env::temp_dir().join(format!("subtitles-ass-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let style = AssStyle {
+        theme: theme.style(),
+        font_size: 40.0,
+        width_frac: 0.8,
+    };
+    let filter = SoundTagFilter::new(SoundTagMode::Strip, "");
+    AssWriter::create(&dir.join(name), filter, &style).unwrap()
+}
+
+#[test]
+fn timestamps_use_ass_centiseconds() {
+    assert_eq!(format_timestamp(0.0), "0:00:00.00");
+    assert_eq!(format_timestamp(3725.456), "1:02:05.46");
+}
+
+#[test]
+fn styles_follow_the_theme_and_dialogue_uses_speaker_styles() {
+    let mut boxed = writer("boxed.ass", CaptionTheme::LargePrint);
+    boxed
+        .handle(&final_update(0, "Hello {there}.\n你好", 1.0, None))
+        .unwrap();
+    boxed
+        .handle(&final_update(1, "[Music]", 3.0, None))
+        .unwrap();
+    boxed.handle(&final_update(2, "Hi.", 4.0, Some(1))).unwrap();
+    let ass = boxed.render();
+
+    // Large print: 1.5x text, white on a 95% black box (border style 3) padded by 0.3 em.
+    assert!(ass.contains(
+        "Style: Default,Optima,60,&H00FFFFFF,&H00FFFFFF,&H0D000000,&H0D000000,\
+         0,0,0,0,100,100,7.2,0,3,18.0,0.0,2,192,192,48,1\n"
+    ));
+    assert!(ass.contains("Style: Speaker2,Optima,60,&H00FFFF00,"));
+    assert!(
+        ass.contains("Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,Hello (there).\\N你好\n")
+    );
+    assert!(ass.contains("Dialogue: 0,0:00:04.00,0:00:05.50,Speaker2,Speaker 2,0,0,0,,Hi.\n"));
+    assert!(!ass.contains("Music"));
+
+    // Without a box, the outline and drop shadow are used instead.
+    let outlined = writer("outlined.ass", CaptionTheme::Default).render();
+    assert!(outlined.contains(",&H00FCFAF8,&H00FCFAF8,&H00000000,&H8C000000,"));
+    assert!(outlined.contains(",0,1,1.0,2.0,2,"));
+}
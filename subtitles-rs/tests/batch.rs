@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use subtitles::audio::SegmenterConfig;
+use subtitles::batch::{
+    audio_files, read_wav_16k_mono, transcribe_all, BatchOptions, FileStatus, SubtitleFormat,
+};
+use subtitles::config::{OutputLanguage, SoundTagMode};
+use subtitles::text::SoundTagFilter;
+use subtitles::transcribe::{Transcriber, TranscriberConfig};
+
+/// Describes each utterance by its length, rounded down to a tenth of a second.
+struct Lengths;
+
+impl Transcriber for Lengths {
+    fn transcribe(&mut self, audio: &[f32], _: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(format!("{} samples", audio.len() / 1600 * 1600))
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("subtitles-batch-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes 16-bit stereo at `rate`: each `(seconds, speech)` span is a tone or silence.
+fn write_wav(path: &Path, rate: u32, spans: &[(f32, bool)]) {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    for &(seconds, speech) in spans {
+        for i in 0..(seconds * rate as f32) as usize {
+            let t = i as f32 / rate as f32;
+            let sample = if speech {
+                (t * 220.0 * std::f32::consts::TAU).sin() * 0.3
+            } else {
+                0.0
+            };
+            let sample = (sample * i16::MAX as f32) as i16;
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(sample).unwrap();
+        }
+    }
+    writer.finalize().unwrap();
+}
+
+fn options(out_dir: &Path) -> BatchOptions {
+    BatchOptions {
+        out_dir: Some(out_dir.to_path_buf()),
+        overwrite: false,
+        decode_cfg: TranscriberConfig {
+            input_language: None,
+            output_language: OutputLanguage::English,
+            is_partial: false,
+            segment_timestamps: false,
+            prompt: None,
+            glossary: None,
+            diarize: false,
+        },
+        segmenter: SegmenterConfig {
+            sample_rate_hz: 16_000,
+            vad_threshold: 0.012,
+            vad_end_silence_s: 0.6,
+            max_segment_s: 20.0,
+            pre_roll_s: 0.0,
+        },
+        format: SubtitleFormat::Srt,
+        sound_tags: SoundTagFilter::new(SoundTagMode::Strip, ""),
+    }
+}
+
+#[test]
+fn wav_files_are_mixed_down_and_resampled() {
+    let dir = temp_dir("wav");
+    let path = dir.join("tone.wav");
+    write_wav(&path, 48_000, &[(0.5, true), (0.5, false)]);
+
+    let audio = read_wav_16k_mono(&path).unwrap();
+    assert_eq!(audio.len(), 16_000);
+    let peak = audio.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    assert!((0.25..=0.31).contains(&peak), "peak {peak}");
+    assert!(audio[12_000..].iter().all(|&s| s == 0.0));
+
+    std::fs::write(dir.join("broken.wav"), b"not a wav").unwrap();
+    assert!(read_wav_16k_mono(&dir.join("broken.wav")).is_err());
+}
+
+#[test]
+fn a_directory_is_transcribed_in_parallel_with_a_report() {
+    let dir = temp_dir("dir");
+    let out = dir.join("srt");
+    write_wav(
+        &dir.join("a.wav"),
+        16_000,
+        &[(1.0, true), (1.0, false), (0.5, true)],
+    );
+    write_wav(&dir.join("b.WAV"), 44_100, &[(0.2, false), (1.0, true)]);
+    std::fs::write(dir.join("c.wav"), b"truncated").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not audio").unwrap();
+
+    let files = audio_files(&dir).unwrap();
+    let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap()).collect();
+    assert_eq!(names, ["a.wav", "b.WAV", "c.wav"]);
+
+    std::fs::create_dir_all(&out).unwrap();
+    let transcribers: Vec<Box<dyn Transcriber>> = vec![Box::new(Lengths), Box::new(Lengths)];
+    let report = transcribe_all(&files, &options(&out), transcribers);
+    let statuses: Vec<_> = report.files.iter().map(|f| &f.status).collect();
+    assert_eq!(statuses[..2], [&FileStatus::Done, &FileStatus::Done]);
+    assert!(matches!(statuses[2], FileStatus::Failed(_)));
+    assert!(!out.join("c.srt").exists());
+    assert_eq!(report.files[0].cues, 2);
+    assert_eq!(report.failed(), 1);
+    assert!(report.summary().contains("2 done, 0 skipped, 1 failed"));
+
+    let srt = std::fs::read_to_string(out.join("a.srt")).unwrap();
+    assert!(srt.starts_with("1\n00:00:00,0"), "{srt}");
+    assert!(srt.contains("\n2\n00:00:02,"), "{srt}");
+
+    // Finished files are skipped on a second run.
+    let report = transcribe_all(&files[..1], &options(&out), vec![Box::new(Lengths)]);
+    assert_eq!(report.files[0].status, FileStatus::Skipped);
+
+    // A failed re-run leaves the existing file alone.
+    std::fs::write(out.join("c.srt"), "kept").unwrap();
+    let overwrite = BatchOptions {
+        overwrite: true,
+        ..options(&out)
+    };
+    let report = transcribe_all(&files[2..], &overwrite, vec![Box::new(Lengths)]);
+    assert!(matches!(report.files[0].status, FileStatus::Failed(_)));
+    assert_eq!(std::fs::read_to_string(out.join("c.srt")).unwrap(), "kept");
+    assert_eq!(std::fs::read_dir(&out).unwrap().count(), 3);
+}
+
+#[test]
+fn recordings_with_the_same_name_are_rejected() {
+    let dir = temp_dir("stems");
+    let options = options(&dir.join("srt"));
+    let files = [dir.join("intro.wav"), dir.join("talk.m4a"), dir.join("talk.mp3")];
+    let err = options.check_outputs(&files).unwrap_err().to_string();
+    assert!(err.contains("talk.m4a") && err.contains("talk.mp3"), "{err}");
+    assert!(options.check_outputs(&files[..2]).is_ok());
+}
@@ -0,0 +1,83 @@
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+use subtitles::braille::{fit_to_display, BrlApi, BrlApiHost};
+
+#[test]
+fn captions_fit_the_display_from_the_end() {
+    assert_eq!(fit_to_display("Hello", 8), "Hello   ");
+    assert_eq!(
+        fit_to_display("The quick brown fox jumps", 12),
+        "fox jumps   "
+    );
+    assert_eq!(
+        fit_to_display("早上好\nGood  morning", 20),
+        "早上好 / Good morning  "
+    );
+    assert_eq!(fit_to_display("Unbreakable", 4), "able");
+
+    assert_eq!(
+        BrlApiHost::parse(":0").unwrap(),
+        BrlApiHost::Local(PathBuf::from("/var/lib/BrlAPI/0"))
+    );
+    assert_eq!(
+        BrlApiHost::parse("pi.local:1").unwrap(),
+        BrlApiHost::Tcp("pi.local".to_string(), 4102)
+    );
+    assert!(BrlApiHost::parse("pi.local:x").is_err());
+}
+
+fn send(stream: &mut UnixStream, kind: u8, payload: &[u8]) {
+    stream
+        .write_all(&(payload.len() as u32).to_be_bytes())
+        .unwrap();
+    stream.write_all(&(kind as u32).to_be_bytes()).unwrap();
+    stream.write_all(payload).unwrap();
+}
+
+fn receive(stream: &mut UnixStream) -> (u8, Vec<u8>) {
+    let mut header = [0u8; 8];
+    stream.read_exact(&mut header).unwrap();
+    let size = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    let mut payload = vec![0u8; size];
+    stream.read_exact(&mut payload).unwrap();
+    (header[7], payload)
+}
+
+#[test]
+fn brlapi_session_takes_the_display_and_writes_text() {
+    let dir = std::env::temp_dir().join(format!("subtitles-brlapi-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let socket = dir.join("0");
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket).unwrap();
+
+    // A minimal BRLTTY: no authentication and a 12-cell display.
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        send(&mut stream, b'v', &8u32.to_be_bytes());
+        assert_eq!(receive(&mut stream), (b'v', 8u32.to_be_bytes().to_vec()));
+        send(&mut stream, b'a', &(b'N' as u32).to_be_bytes());
+        assert_eq!(receive(&mut stream).0, b's');
+        send(&mut stream, b's', &[0, 0, 0, 12, 0, 0, 0, 1]);
+        assert_eq!(receive(&mut stream), (b't', vec![0, 0, 0, 0, 0]));
+        send(&mut stream, b'A', &[]);
+        let (kind, write) = receive(&mut stream);
+        send(&mut stream, b'A', &[]);
+        (kind, write)
+    });
+
+    let mut api = BrlApi::connect(&BrlApiHost::Local(socket), None).unwrap();
+    assert_eq!(api.cells(), 12);
+    api.show("Good morning everyone").unwrap();
+
+    let (kind, write) = server.join().unwrap();
+    assert_eq!(kind, b'w');
+    // Flags, region 1..=12, then the text.
+    assert_eq!(&write[..4], &0x66u32.to_be_bytes());
+    assert_eq!(&write[4..12], &[0, 0, 0, 1, 0, 0, 0, 12]);
+    assert_eq!(&write[12..16], &12u32.to_be_bytes());
+    assert_eq!(&write[16..28], b"everyone    ");
+    assert!(write.ends_with(b"\x05UTF-8"));
+}
@@ -0,0 +1,195 @@
+use std::time::{Duration, Instant};
+
+use subtitles::app::{CaptionDelayQueue, CaptionTiming};
+use subtitles::text::ReadingSpeed;
+use subtitles::CaptionEvent;
+
+fn update(segment_id: u64) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text: format!("segment {segment_id}"),
+        is_final: true,
+        timing: None,
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    }
+}
+
+fn segment_id(event: CaptionEvent) -> u64 {
+    match event {
+        CaptionEvent::Update { segment_id, .. } => segment_id,
+        other => panic!("unexpected event {other:?}"),
+    }
+}
+
+#[test]
+fn delay_queue_releases_events_in_order_once_due() {
+    let start = Instant::now();
+    let ms = |n: u64| start + Duration::from_millis(n);
+    let delay = Duration::from_millis(500);
+
+    let mut queue = CaptionDelayQueue::default();
+    queue.push(update(0), ms(0));
+    queue.push(update(1), ms(100));
+    assert_eq!(queue.next_due(delay), Some(ms(500)));
+
+    assert!(queue.pop_due(delay, ms(499)).is_none());
+    assert_eq!(queue.pop_due(delay, ms(550)).map(segment_id), Some(0));
+    assert!(queue.pop_due(delay, ms(550)).is_none());
+    assert_eq!(queue.pop_due(delay, ms(600)).map(segment_id), Some(1));
+    assert_eq!(queue.next_due(delay), None);
+}
+
+#[test]
+fn changing_the_delay_applies_to_queued_captions() {
+    let start = Instant::now();
+    let mut queue = CaptionDelayQueue::default();
+    queue.push(update(0), start);
+
+    let now = start + Duration::from_millis(200);
+    assert!(queue.pop_due(Duration::from_secs(1), now).is_none());
+    assert_eq!(queue.pop_due(Duration::ZERO, now).map(segment_id), Some(0));
+    assert!(!CaptionEvent::Status(subtitles::EngineStatus::Ready).is_caption());
+}
+
+fn partial(segment_id: u64) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text: format!("segment {segment_id}"),
+        is_final: false,
+        timing: None,
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    }
+}
+
+#[test]
+fn min_display_holds_the_next_caption_until_the_final_has_been_up_long_enough() {
+    let start = Instant::now();
+    let ms = |n: u64| start + Duration::from_millis(n);
+    let mut queue = CaptionDelayQueue::with_min_display(Duration::from_millis(1000));
+
+    queue.push(update(0), ms(0));
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(0)).map(segment_id),
+        Some(0)
+    );
+    queue.push(
+        CaptionEvent::Revise {
+            segment_id: 0,
+            text: "fixed".to_string(),
+        },
+        ms(100),
+    );
+    queue.push(partial(1), ms(300));
+    queue.push(update(1), ms(600));
+
+    // A revision edits the caption in place, so it isn't held.
+    assert!(matches!(
+        queue.pop_due(Duration::ZERO, ms(100)),
+        Some(CaptionEvent::Revise { .. })
+    ));
+    assert_eq!(queue.next_due(Duration::ZERO), Some(ms(1000)));
+    assert!(queue.pop_due(Duration::ZERO, ms(999)).is_none());
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(1000)).map(segment_id),
+        Some(1)
+    );
+    // Segment 1's final replaces its own partial, not a final.
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(1000)).map(segment_id),
+        Some(1)
+    );
+}
+
+#[test]
+fn min_display_never_holds_a_caption_longer_than_the_minimum() {
+    let start = Instant::now();
+    let ms = |n: u64| start + Duration::from_millis(n);
+    let mut queue = CaptionDelayQueue::with_min_display(Duration::from_millis(1000));
+
+    // Finals arriving every 200 ms: each waits for the previous one, up to 1 s past its own
+    // arrival, instead of queuing further and further behind.
+    for id in 0..4 {
+        queue.push(update(id), ms(id * 200));
+    }
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(0)).map(segment_id),
+        Some(0)
+    );
+    assert_eq!(queue.next_due(Duration::ZERO), Some(ms(1000)));
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(1000)).map(segment_id),
+        Some(1)
+    );
+    assert_eq!(queue.next_due(Duration::ZERO), Some(ms(1400)));
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(1400)).map(segment_id),
+        Some(2)
+    );
+    assert_eq!(queue.next_due(Duration::ZERO), Some(ms(1600)));
+
+    queue.push(CaptionEvent::Clear, ms(1500));
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(1600)).map(segment_id),
+        Some(3)
+    );
+    assert!(queue.pop_due(Duration::ZERO, ms(2000)).is_none());
+    assert!(matches!(
+        queue.pop_due(Duration::ZERO, ms(2500)),
+        Some(CaptionEvent::Clear)
+    ));
+}
+
+#[test]
+fn reading_speed_queues_long_finals_as_cues_read_one_after_another() {
+    let start = Instant::now();
+    let ms = |n: u64| start + Duration::from_millis(n);
+    let mut queue = CaptionDelayQueue::default().with_reading_speed(ReadingSpeed::new(10.0));
+
+    let text = "One two three four five six seven eight nine ten. Eleven twelve.";
+    queue.push(
+        CaptionEvent::Update {
+            segment_id: 0,
+            text: text.to_string(),
+            is_final: true,
+            timing: Some(CaptionTiming {
+                start_s: 4.0,
+                end_s: 5.0,
+            }),
+            speaker: None,
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        },
+        ms(0),
+    );
+    queue.push(partial(1), ms(100));
+
+    let text_of = |event: Option<CaptionEvent>| match event {
+        Some(CaptionEvent::Update { text, timing, .. }) => (text, timing.map(|t| t.start_s)),
+        other => panic!("unexpected event {other:?}"),
+    };
+    assert_eq!(
+        text_of(queue.pop_due(Duration::ZERO, ms(0))),
+        (
+            "One two three four five six seven eight nine ten.".to_string(),
+            Some(4.0)
+        )
+    );
+    // The second cue waits out the first one's 4.9 s, the next caption the second's 1.4 s.
+    assert_eq!(queue.next_due(Duration::ZERO), Some(ms(4900)));
+    assert!(queue.pop_due(Duration::ZERO, ms(4899)).is_none());
+    let (second, second_start) = text_of(queue.pop_due(Duration::ZERO, ms(4900)));
+    assert_eq!(second, "Eleven twelve.");
+    assert!((second_start.unwrap() - 8.9).abs() < 1e-9);
+    assert_eq!(queue.next_due(Duration::ZERO), Some(ms(6300)));
+    assert_eq!(
+        queue.pop_due(Duration::ZERO, ms(6300)).map(segment_id),
+        Some(1)
+    );
+}
@@ -0,0 +1,30 @@
+use subtitles::macos_capture::CaptureTarget;
+
+#[test]
+fn capture_targets_round_trip_through_strings() {
+    for target in [
+        CaptureTarget::System,
+        CaptureTarget::Display(69_733_378),
+        CaptureTarget::Window(4_211),
+        CaptureTarget::Application("com.google.Chrome".to_string()),
+    ] {
+        assert_eq!(target.to_string().parse::<CaptureTarget>(), Ok(target.clone()));
+    }
+    assert_eq!(" SYSTEM ".parse::<CaptureTarget>(), Ok(CaptureTarget::System));
+}
+
+#[test]
+fn malformed_capture_targets_are_rejected() {
+    for bad in ["", "display", "display:abc", "window:-1", "app:", "tab:3"] {
+        assert!(bad.parse::<CaptureTarget>().is_err(), "{bad:?}");
+    }
+}
+
+#[test]
+fn capture_targets_serialize_for_the_ui() {
+    let json = serde_json::to_value(CaptureTarget::Application("com.apple.Music".into())).unwrap();
+    assert_eq!(json, serde_json::json!({ "kind": "app", "id": "com.apple.Music" }));
+    let parsed: CaptureTarget =
+        serde_json::from_value(serde_json::json!({ "kind": "system" })).unwrap();
+    assert_eq!(parsed, CaptureTarget::System);
+}
@@ -0,0 +1,44 @@
+use subtitles::crash::{pending_reports, write_report, CrashReport};
+
+fn report_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("subtitles-crash-{}", std::process::id()))
+        .join(name)
+}
+
+#[test]
+fn reports_round_trip_as_json() {
+    let dir = report_dir("roundtrip");
+    let report = CrashReport::new("error", "capture", "stream stopped: -3805");
+    let path = write_report(&dir, &report).unwrap();
+    let read: CrashReport = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(read, report);
+    assert_eq!(read.version, env!("CARGO_PKG_VERSION"));
+    assert!(path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("error-"));
+}
+
+#[test]
+fn uploads_leave_out_panic_messages() {
+    let panic = CrashReport::new("panic", "src/srt.rs:1:1", "not a char boundary of `hi`");
+    let uploaded = panic.clone().for_upload();
+    assert_eq!(uploaded.message, "panic");
+    assert_eq!(uploaded.context, panic.context);
+
+    let error = CrashReport::new("error", "capture", "stream stopped: -3805");
+    assert_eq!(error.clone().for_upload(), error);
+}
+
+#[test]
+fn same_moment_reports_do_not_overwrite_each_other() {
+    let dir = report_dir("pending");
+    let report = CrashReport::new("panic", "src/app.rs:1:1", "boom");
+    let first = write_report(&dir, &report).unwrap();
+    let second = write_report(&dir, &report).unwrap();
+    assert_ne!(first, second);
+    std::fs::create_dir_all(dir.join("sent")).unwrap();
+    assert_eq!(pending_reports(&dir).unwrap(), vec![first, second]);
+}
@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::PathBuf;
+
+use subtitles::daemon::PidFile;
+
+fn pid_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("subtitles-daemon-{}", std::process::id()))
+        .join(name)
+}
+
+#[test]
+fn pid_file_is_written_and_removed() {
+    let path = pid_path("run.pid");
+    let pid_file = PidFile::create(&path).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap().trim(),
+        std::process::id().to_string()
+    );
+    drop(pid_file);
+    assert!(!path.exists());
+}
+
+#[test]
+fn pid_file_refuses_a_running_instance_but_replaces_a_stale_one() {
+    let path = pid_path("busy.pid");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    // pid 1 always exists.
+    fs::write(&path, "1\n").unwrap();
+    let err = PidFile::create(&path).unwrap_err().to_string();
+    assert!(err.contains("pid 1"), "{err}");
+
+    fs::write(&path, format!("{}\n", i32::MAX)).unwrap();
+    let pid_file = PidFile::create(&path).unwrap();
+    assert_eq!(pid_file.path(), path);
+}
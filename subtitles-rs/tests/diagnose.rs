@@ -0,0 +1,26 @@
+use subtitles::diagnose::{redact_settings, tail};
+use subtitles::settings::Settings;
+
+#[test]
+fn secrets_are_redacted_from_settings() {
+    let settings: Settings = serde_json::from_value(serde_json::json!({
+        "openai-api-key": "sk-live-123",
+        "engine": "openai",
+        "glossary": "/Users/ada/glossary.json",
+        "auth-token": null,
+    }))
+    .unwrap();
+    let redacted = redact_settings(settings);
+    assert_eq!(redacted["openai-api-key"], "<redacted>");
+    assert_eq!(redacted["engine"], "openai");
+    assert_eq!(redacted["glossary"], "/Users/ada/glossary.json");
+    assert!(redacted["auth-token"].is_null());
+}
+
+#[test]
+fn log_tail_starts_at_a_line() {
+    let log = b"first line\nsecond line\nthird line\n";
+    assert_eq!(tail(log, 100), log);
+    assert_eq!(tail(log, 16), b"third line\n");
+    assert_eq!(tail(b"no newlines at all", 5), b"t all");
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use subtitles::domain::{DomainModel, SharedDomainModel};
+use subtitles::settings::{parse_cli_from, save_settings, Settings};
+
+#[test]
+fn settings_block_declares_domain_models() {
+    let dir = std::env::temp_dir().join(format!("subtitles-domain-{}", std::process::id()));
+    let path = dir.join("settings.json");
+    let settings: Settings = serde_json::from_value(serde_json::json!({
+        "domain-model": { "medical": "models/medical.bin", "legal": "models/legal.bin" },
+        "domain": "legal",
+    }))
+    .unwrap();
+    save_settings(&path, &settings).unwrap();
+
+    let cli = parse_cli_from(["subtitles"], Some(path)).unwrap();
+    let names: Vec<&str> = cli.domain_model.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&"medical") && names.contains(&"legal"));
+    assert_eq!(cli.domain.as_deref(), Some("legal"));
+
+    let bad: Settings =
+        serde_json::from_value(serde_json::json!({ "domain-model": { "medical": 3 } })).unwrap();
+    assert!(save_settings(&dir.join("bad.json"), &bad).is_err());
+}
+
+#[test]
+fn domains_switch_by_name_and_back_to_default() {
+    let medical: DomainModel = "Medical=models/medical.bin".parse().unwrap();
+    assert_eq!(medical.name, "medical");
+    assert_eq!(medical.path, PathBuf::from("models/medical.bin"));
+    assert!("medical".parse::<DomainModel>().is_err());
+    assert!("default=models/x.bin".parse::<DomainModel>().is_err());
+
+    let legal: DomainModel = "legal=models/legal.bin".parse().unwrap();
+    assert!(SharedDomainModel::new(vec![legal.clone(), legal.clone()], None).is_err());
+    assert!(SharedDomainModel::new(vec![legal.clone()], Some("finance")).is_err());
+
+    let domain = SharedDomainModel::new(vec![medical.clone(), legal], None).unwrap();
+    assert_eq!(domain.selected(), None);
+    domain.select("medical").unwrap();
+    assert_eq!(domain.selected(), Some(medical));
+    assert!(domain.select("finance").is_err());
+    assert_eq!(domain.selected_name(), "medical");
+    domain.select("default").unwrap();
+    assert_eq!(domain.selected_name(), "default");
+
+    // Files are only checked when asked, so a missing one fails validation, not parsing.
+    let missing: DomainModel = "x=/nonexistent/model.bin".parse().unwrap();
+    assert!(missing.validate().is_err());
+}
@@ -0,0 +1,107 @@
+use clap::Parser;
+use subtitles::config::{Cli, Engine, EngineCapabilities, PrintMode};
+use subtitles::engine_registry::{custom_factory, register_transcriber, registered_transcribers};
+use subtitles::transcribe::{Transcriber, TranscriberConfig};
+
+const NOTHING: EngineCapabilities = EngineCapabilities {
+    streaming_partials: false,
+    translation: false,
+    word_timestamps: false,
+    speaker_labels: false,
+};
+
+struct Echo(&'static str);
+
+impl Transcriber for Echo {
+    fn transcribe(&mut self, _: &[f32], _: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+}
+
+#[test]
+fn only_the_local_engine_streams_and_labels_speakers() {
+    assert_eq!(
+        Engine::Local.capabilities(),
+        EngineCapabilities {
+            streaming_partials: true,
+            translation: true,
+            word_timestamps: true,
+            speaker_labels: true,
+        }
+    );
+    let openai = Engine::OpenAI.capabilities();
+    assert!(!openai.streaming_partials && !openai.word_timestamps && !openai.speaker_labels);
+    assert!(openai.translation);
+}
+
+#[test]
+fn engines_parse_and_print_as_their_flag_values() {
+    for value in ["local", "openai", "custom:vosk"] {
+        assert_eq!(value.parse::<Engine>().unwrap().to_string(), value);
+    }
+    assert_eq!("Open-AI".parse::<Engine>(), Ok(Engine::OpenAI));
+    assert!("custom:".parse::<Engine>().is_err());
+    assert!("whisperx".parse::<Engine>().is_err());
+
+    let cli = Cli::parse_from(["subtitles", "--engine", "custom:Vosk"]);
+    assert_eq!(cli.engine, Engine::Custom("vosk".into()));
+}
+
+#[test]
+fn registered_transcribers_are_selectable_by_name() {
+    let streaming = EngineCapabilities {
+        streaming_partials: true,
+        ..NOTHING
+    };
+    register_transcriber("Echo", NOTHING, || Ok(Box::new(Echo("first"))));
+    register_transcriber("echo", streaming, || Ok(Box::new(Echo("second"))));
+    assert_eq!(
+        registered_transcribers()
+            .iter()
+            .filter(|name| *name == "echo")
+            .count(),
+        1
+    );
+    assert_eq!(Engine::Custom("echo".into()).capabilities(), streaming);
+    assert_eq!(Engine::Custom("missing".into()).capabilities(), NOTHING);
+
+    let mut transcriber = custom_factory("ECHO").unwrap()().unwrap();
+    let cfg = TranscriberConfig {
+        input_language: None,
+        output_language: subtitles::OutputLanguage::Chinese,
+        is_partial: false,
+        segment_timestamps: false,
+        prompt: None,
+        glossary: None,
+        diarize: false,
+    };
+    assert_eq!(transcriber.transcribe(&[0.0; 160], &cfg).unwrap(), "second");
+
+    let err = custom_factory("missing").err().unwrap().to_string();
+    assert!(
+        err.contains("custom:missing") && err.contains("echo"),
+        "{err}"
+    );
+}
+
+#[test]
+fn a_language_pair_takes_exactly_two_languages() {
+    let cli = Cli::parse_from(["subtitles", "--language-pair", "en,ja"]);
+    assert_eq!(cli.language_pair, ["en", "ja"]);
+    assert!(Cli::try_parse_from(["subtitles", "--language-pair", "en"]).is_err());
+    assert!(Cli::try_parse_from(["subtitles", "--language-pair", "en,ja", "--ensemble"]).is_err());
+}
+
+#[test]
+fn headless_timestamps_are_off_unless_asked_for() {
+    assert!(!Cli::parse_from(["subtitles", "--no-ui"]).print_timestamps);
+    assert!(Cli::parse_from(["subtitles", "--no-ui", "--print-timestamps"]).print_timestamps);
+}
+
+#[test]
+fn headless_output_prints_finals_unless_told_otherwise() {
+    assert_eq!(Cli::parse_from(["subtitles", "--no-ui"]).print, PrintMode::Finals);
+    let cli = Cli::parse_from(["subtitles", "--no-ui", "--print", "partials"]);
+    assert_eq!(cli.print, PrintMode::Partials);
+    assert!(Cli::try_parse_from(["subtitles", "--print", "everything"]).is_err());
+}
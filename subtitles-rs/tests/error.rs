@@ -0,0 +1,55 @@
+use anyhow::Context;
+use subtitles::error::{ErrorKind, PermissionDenied, SubtitlesError};
+
+#[test]
+fn capture_errors_from_missing_permissions_are_told_apart() {
+    let denied = anyhow::anyhow!("The user declined TCCs for application, window, display capture")
+        .context(PermissionDenied("Screen Recording"))
+        .context("failed to query shareable content")
+        .context("failed to start ScreenCaptureKit audio capture");
+    let err = SubtitlesError::capture(denied);
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(
+        err.to_string(),
+        "failed to start ScreenCaptureKit audio capture"
+    );
+    let full = format!("{err:#}");
+    assert!(
+        full.contains("Screen Recording permission is not granted"),
+        "{full}"
+    );
+    assert!(full.ends_with("The user declined TCCs for application, window, display capture"));
+
+    let missing = anyhow::anyhow!("no input device matches `BlackHole`");
+    assert_eq!(SubtitlesError::capture(missing).kind(), ErrorKind::Capture);
+}
+
+#[test]
+fn download_failures_are_network_errors_and_the_rest_is_config() {
+    let refused = reqwest::blocking::get("http://127.0.0.1:1/ggml-medium.bin")
+        .map(drop)
+        .context("GET http://127.0.0.1:1/ggml-medium.bin")
+        .unwrap_err();
+    assert_eq!(
+        SubtitlesError::model_load(refused).kind(),
+        ErrorKind::Network
+    );
+    let corrupt = anyhow::anyhow!("failed to initialize whisper context");
+    assert_eq!(
+        SubtitlesError::model_load(corrupt).kind(),
+        ErrorKind::ModelLoad
+    );
+
+    // Classified errors keep their class through `?` in anyhow code.
+    let classified: anyhow::Error = SubtitlesError::Capture(anyhow::anyhow!("no tap")).into();
+    assert_eq!(SubtitlesError::from(classified).kind(), ErrorKind::Capture);
+    let unclassified = anyhow::anyhow!("--shadow-engine must differ from --engine");
+    assert_eq!(
+        SubtitlesError::from(unclassified).kind(),
+        ErrorKind::EngineConfig
+    );
+    assert_eq!(
+        serde_json::to_string(&ErrorKind::PermissionDenied).unwrap(),
+        "\"permission_denied\""
+    );
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use subtitles::config::OutputLanguage;
+use subtitles::filter_cmd::CommandFilter;
+use subtitles::text::{TextFilter, TextSegment};
+
+fn segment(text: &str) -> TextSegment {
+    TextSegment {
+        segment_id: 7,
+        text: text.to_string(),
+        output_language: OutputLanguage::English,
+    }
+}
+
+fn filter(command: &str) -> CommandFilter {
+    CommandFilter::new(command, Duration::from_millis(500))
+}
+
+#[test]
+fn program_output_replaces_the_text() {
+    let input = filter("cat").run(&segment("hello")).unwrap();
+    let input: serde_json::Value = serde_json::from_str(&input).unwrap();
+    assert_eq!(
+        input,
+        serde_json::json!({ "segment_id": 7, "text": "hello", "output_language": "english" })
+    );
+
+    let mut upper = filter(r#"sed 's/.*"text":"\([^"]*\)".*/\1/' | tr a-z A-Z"#);
+    assert_eq!(upper.apply(segment("hello there")).text, "HELLO THERE");
+}
+
+#[test]
+fn failures_and_timeouts_keep_the_text() {
+    assert!(filter("exit 3").run(&segment("x")).is_err());
+    assert_eq!(filter("exit 3").apply(segment("kept")).text, "kept");
+
+    let mut slow = CommandFilter::new("sleep 5", Duration::from_millis(50));
+    assert!(slow
+        .run(&segment("x"))
+        .unwrap_err()
+        .to_string()
+        .contains("timed out"));
+    assert_eq!(slow.apply(segment("kept")).text, "kept");
+}
+
+#[test]
+fn timeouts_kill_what_the_program_started() {
+    let pid_file = std::env::temp_dir().join(format!("subtitles-filter-{}", std::process::id()));
+    let command = format!("sleep 5 & echo $! > {}; wait", pid_file.display());
+    let slow = CommandFilter::new(command, Duration::from_millis(200));
+    assert!(slow.run(&segment("x")).is_err());
+
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+    // Gone, or a zombie waiting for whoever inherited it.
+    let ps = std::process::Command::new("ps")
+        .args(["-o", "stat=", "-p", pid.trim()])
+        .output()
+        .unwrap();
+    let state = String::from_utf8_lossy(&ps.stdout);
+    assert!(
+        state.trim().is_empty() || state.starts_with('Z'),
+        "sleep {} is still running ({state})",
+        pid.trim()
+    );
+}
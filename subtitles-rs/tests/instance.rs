@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::CommandFactory;
+
+use subtitles::app::{SharedCaptionDelay, SharedOutputLanguage, SharedStabilizer};
+use subtitles::config::{Cli, CommitPolicy, OutputLanguage};
+use subtitles::domain::SharedDomainModel;
+use subtitles::instance::{send_handoff, Handoff, InstanceLock, LiveControls};
+use subtitles::macos_capture::{CaptureControl, CaptureTarget};
+
+fn instance_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("subtitles-instance-{}", std::process::id()))
+        .join(name)
+}
+
+#[test]
+fn handoff_only_carries_flags_given_on_the_command_line() {
+    let handoff = Handoff::from_args([
+        "subtitles",
+        "--output-language",
+        "bilingual",
+        "--capture-target",
+        "app:us.zoom.xos",
+        "--vad-threshold",
+        "0.02",
+        "--commit-policy",
+        "final-only",
+    ])
+    .unwrap();
+    assert_eq!(handoff.output_language, Some(OutputLanguage::Bilingual));
+    assert_eq!(
+        handoff.capture_target,
+        Some(CaptureTarget::Application("us.zoom.xos".into()))
+    );
+    assert_eq!(handoff.caption_delay_ms, None);
+    assert_eq!(handoff.commit_policy, Some(CommitPolicy::FinalOnly));
+    assert_eq!(handoff.partial_stable_iters, None);
+    assert!(Handoff::from_args(["subtitles", "--no-ui"])
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn the_handoff_flags_are_real_flags() {
+    let command = Cli::command();
+    for flag in Handoff::FLAGS {
+        let long = flag.trim_start_matches("--");
+        assert!(
+            command
+                .get_arguments()
+                .any(|arg| arg.get_long() == Some(long)),
+            "{flag}"
+        );
+    }
+    assert!(Handoff::flag_list().ends_with(", --partial-stable-iters or --commit-policy"));
+}
+
+#[test]
+fn second_launch_hands_settings_to_the_lock_holder() {
+    let dir = instance_dir("handoff");
+    let lock = InstanceLock::acquire(&dir).unwrap().unwrap();
+    assert!(InstanceLock::acquire(&dir).unwrap().is_none());
+
+    let controls = LiveControls {
+        output_language: SharedOutputLanguage::new(OutputLanguage::English),
+        capture: CaptureControl::new(CaptureTarget::System),
+        caption_delay: SharedCaptionDelay::new(Duration::ZERO),
+        stabilizer: SharedStabilizer::new(2, CommitPolicy::Agreement),
+        domain: SharedDomainModel::new(vec!["legal=models/legal.bin".parse().unwrap()], None)
+            .unwrap(),
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let server = lock.serve(controls.clone(), stop.clone()).unwrap();
+    send_handoff(
+        &dir,
+        &Handoff {
+            output_language: Some(OutputLanguage::Chinese),
+            capture_target: None,
+            caption_delay_ms: Some(250),
+            domain: Some("legal".into()),
+            partial_stable_iters: Some(4),
+            commit_policy: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(controls.output_language.get(), OutputLanguage::Chinese);
+    assert_eq!(controls.caption_delay.get(), Duration::from_millis(250));
+    assert_eq!(controls.capture.target(), CaptureTarget::System);
+    assert_eq!(controls.domain.selected_name(), "legal");
+    assert_eq!(controls.stabilizer.stable_iters(), 4);
+    assert_eq!(controls.stabilizer.policy(), CommitPolicy::Agreement);
+
+    stop.store(true, Ordering::Relaxed);
+    server.join().unwrap();
+    drop(lock);
+    assert!(InstanceLock::acquire(&dir).unwrap().is_some());
+}
@@ -0,0 +1,71 @@
+use subtitles::platform::{Capabilities, CaptureBackend, MacosVersion};
+
+fn on(version: &str) -> Capabilities {
+    Capabilities {
+        version: MacosVersion::parse(version),
+    }
+}
+
+#[test]
+fn parses_sw_vers_output() {
+    assert_eq!(
+        MacosVersion::parse("14.4.1\n"),
+        Some(MacosVersion::new(14, 4, 1))
+    );
+    assert_eq!(
+        MacosVersion::parse("15.0"),
+        Some(MacosVersion::new(15, 0, 0))
+    );
+    assert_eq!(MacosVersion::parse("26"), Some(MacosVersion::new(26, 0, 0)));
+    assert_eq!(MacosVersion::parse("beta"), None);
+    assert!(MacosVersion::new(14, 4, 0) > MacosVersion::new(14, 3, 9));
+    assert_eq!(MacosVersion::new(14, 4, 1).to_string(), "14.4.1");
+}
+
+#[test]
+fn picks_a_backend_the_mac_supports() {
+    let auto = CaptureBackend::Auto;
+    assert_eq!(
+        on("15.1").choose(auto, None, true).unwrap(),
+        CaptureBackend::ScreenCaptureKit
+    );
+    assert_eq!(
+        on("12.6").choose(auto, Some("BlackHole"), false).unwrap(),
+        CaptureBackend::Device
+    );
+    // Unknown versions are given the benefit of the doubt.
+    assert_eq!(
+        Capabilities { version: None }
+            .choose(auto, None, true)
+            .unwrap(),
+        CaptureBackend::ScreenCaptureKit
+    );
+
+    let err = on("12.6")
+        .choose(auto, None, false)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("macOS 12.6") && err.contains("--input-device"),
+        "{err}"
+    );
+    let err = on("14.5").choose(auto, None, true).unwrap_err().to_string();
+    assert!(err.contains("macOS 15"), "{err}");
+    assert!(on("15.0")
+        .choose(CaptureBackend::Device, None, false)
+        .is_err());
+    assert!(on("15.0").choose(auto, Some("BlackHole"), true).is_err());
+
+    // Process taps are opt-in and need 14.4.
+    let tap = CaptureBackend::ProcessTap;
+    assert_eq!(
+        on("14.4").choose(tap, None, false).unwrap(),
+        CaptureBackend::ProcessTap
+    );
+    let err = on("14.3.1")
+        .choose(tap, None, false)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("14.4"), "{err}");
+    assert!(on("15.0").choose(tap, None, true).is_err());
+}
@@ -0,0 +1,182 @@
+use serde_json::json;
+use subtitles::app::{
+    stability_spans, CaptionEvent, CaptionSpan, CaptionTiming, EngineStatus, Speaker,
+};
+use subtitles::config::SoundTagMode;
+use subtitles::error::ErrorKind;
+use subtitles::protocol::{EngineState, Event, Message, Status, PROTOCOL_VERSION};
+use subtitles::text::SoundTagFilter;
+
+fn wire(event: CaptionEvent) -> serde_json::Value {
+    let filter = SoundTagFilter::new(SoundTagMode::Strip, "");
+    serde_json::to_value(Message::new(Event::from_caption_event(event, &filter))).unwrap()
+}
+
+#[test]
+fn events_serialize_to_the_documented_schema() {
+    assert_eq!(PROTOCOL_VERSION, 1);
+    assert_eq!(
+        wire(CaptionEvent::Update {
+            segment_id: 7,
+            text: "[Music] Welcome back.".to_string(),
+            is_final: true,
+            timing: Some(CaptionTiming {
+                start_s: 1.5,
+                end_s: 3.0,
+            }),
+            speaker: Some(Speaker::new(0)),
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        }),
+        json!({"v": 1, "event": "caption", "payload": {
+            "segment_id": 7, "text": "Welcome back.", "is_final": true, "clear": false,
+            "speaker": 0, "color": Speaker::new(0).color, "start_s": 1.5, "end_s": 3.0,
+        }})
+    );
+    assert_eq!(
+        wire(CaptionEvent::Update {
+            segment_id: 8,
+            text: "and".to_string(),
+            is_final: false,
+            timing: None,
+            speaker: None,
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        }),
+        json!({"v": 1, "event": "caption", "payload": {
+            "segment_id": 8, "text": "and", "is_final": false, "clear": false,
+            "speaker": null, "color": null,
+        }})
+    );
+    assert_eq!(
+        wire(CaptionEvent::Update {
+            segment_id: 9,
+            text: "我们周五发布".to_string(),
+            is_final: true,
+            timing: None,
+            speaker: None,
+            untranslated: true,
+            spans: Vec::new(),
+            words: Vec::new(),
+        }),
+        json!({"v": 1, "event": "caption", "payload": {
+            "segment_id": 9, "text": "我们周五发布", "is_final": true, "clear": false,
+            "speaker": null, "color": null, "untranslated": true,
+        }})
+    );
+    assert_eq!(
+        wire(CaptionEvent::Clear),
+        json!({"v": 1, "event": "caption", "payload": {
+            "segment_id": 0, "text": "", "is_final": true, "clear": true,
+            "speaker": null, "color": null,
+        }})
+    );
+    assert_eq!(
+        wire(CaptionEvent::Status(EngineStatus::Failed {
+            kind: ErrorKind::ModelLoad,
+            message: "no model".to_string(),
+        })),
+        json!({"v": 1, "event": "status", "payload": {
+            "state": "failed", "message": "no model", "kind": "model_load",
+        }})
+    );
+    assert_eq!(
+        wire(CaptionEvent::Revise {
+            segment_id: 7,
+            text: "Welcome back!".to_string(),
+        }),
+        json!({"v": 1, "event": "caption-revise", "payload": {
+            "segment_id": 7, "text": "Welcome back!",
+        }})
+    );
+    assert_eq!(
+        wire(CaptionEvent::SpeakerName {
+            id: 1,
+            name: "Alice".to_string(),
+        }),
+        json!({"v": 1, "event": "speaker-name", "payload": {"id": 1, "name": "Alice"}})
+    );
+}
+
+#[test]
+fn readers_round_trip_and_ignore_fields_added_later() {
+    let message: Message = serde_json::from_str(
+        r#"{"v":1,"event":"status","payload":{"state":"ready","message":null,"since_s":4},"x":1}"#,
+    )
+    .unwrap();
+    assert_eq!(message.v, 1);
+    let status = Event::Status(Status {
+        state: EngineState::Ready,
+        message: None,
+        kind: None,
+    });
+    assert_eq!(message.event, status);
+    assert_eq!(status.name(), "status");
+    assert_eq!(status.payload(), json!({"state": "ready", "message": null}));
+
+    let filter = SoundTagFilter::new(SoundTagMode::Keep, "");
+    let event = Event::from_caption_event(
+        CaptionEvent::Update {
+            segment_id: 1,
+            text: "hello".to_string(),
+            is_final: true,
+            timing: None,
+            speaker: None,
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        },
+        &filter,
+    );
+    let line = serde_json::to_string(&Message::new(event.clone())).unwrap();
+    assert_eq!(serde_json::from_str::<Message>(&line).unwrap().event, event);
+}
+
+fn span(text: &str, stable: bool) -> CaptionSpan {
+    CaptionSpan {
+        text: text.to_string(),
+        stable,
+    }
+}
+
+#[test]
+fn partials_carry_stable_and_pending_spans() {
+    // Each line is stable as far as it matches what that line has committed.
+    assert_eq!(
+        stability_spans(
+            "我们周五发布\nWe ship on Fri",
+            &["我们周五".to_string(), "We ship".to_string()]
+        ),
+        [
+            span("我们周五", true),
+            span("发布\n", false),
+            span("We ship", true),
+            span(" on Fri", false),
+        ]
+    );
+    assert_eq!(stability_spans("hello", &[]), [span("hello", false)]);
+
+    let spans = vec![span("[Music] We ship", true), span(" on Friday", false)];
+    assert_eq!(
+        wire(CaptionEvent::Update {
+            segment_id: 3,
+            text: "[Music] We ship on Friday".to_string(),
+            is_final: false,
+            timing: None,
+            speaker: None,
+            untranslated: false,
+            spans,
+            words: Vec::new(),
+        }),
+        json!({"v": 1, "event": "caption", "payload": {
+            "segment_id": 3, "text": "We ship on Friday", "is_final": false, "clear": false,
+            "speaker": null, "color": null,
+            "spans": [
+                {"text": "We ship", "stable": true},
+                {"text": " on Friday", "stable": false},
+            ],
+        }})
+    );
+}
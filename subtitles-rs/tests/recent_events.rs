@@ -0,0 +1,134 @@
+use subtitles::app::{EngineStatus, RecentEvents, SegmentEdits};
+use subtitles::CaptionEvent;
+
+fn update(segment_id: u64, text: &str, is_final: bool) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text: text.to_string(),
+        is_final,
+        timing: None,
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    }
+}
+
+fn describe(events: Vec<CaptionEvent>) -> Vec<String> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            CaptionEvent::Update {
+                segment_id,
+                text,
+                is_final,
+                ..
+            } => format!("{segment_id}{}: {text}", if is_final { "" } else { "~" }),
+            CaptionEvent::Status(status) => format!("status {status:?}"),
+            CaptionEvent::SpeakerName { id, name } => format!("speaker {id} {name}"),
+            other => panic!("unexpected event {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn recent_events_keep_latest_text_per_segment_and_status() {
+    let recent = RecentEvents::new(2);
+    recent.record(&CaptionEvent::Status(EngineStatus::Loading));
+    recent.record(&CaptionEvent::Status(EngineStatus::Ready));
+    recent.record(&update(0, "hello", false));
+    recent.record(&update(0, "hello there", true));
+    recent.record(&update(1, "second", true));
+    recent.record(&CaptionEvent::Revise {
+        segment_id: 0,
+        text: "Hello there.".to_string(),
+    });
+    recent.record(&update(2, "thi", false));
+
+    assert_eq!(
+        describe(recent.snapshot()),
+        ["status Ready", "1: second", "2~: thi"]
+    );
+    recent.record(&CaptionEvent::Revise {
+        segment_id: 1,
+        text: "Second.".to_string(),
+    });
+    assert_eq!(
+        describe(recent.snapshot()),
+        ["status Ready", "1: Second.", "2~: thi"]
+    );
+}
+
+#[test]
+fn recent_events_clear_drops_captions_but_not_status() {
+    let recent = RecentEvents::new(20);
+    recent.record(&CaptionEvent::Status(EngineStatus::Ready));
+    recent.record(&update(0, "gone soon", true));
+    recent.record(&CaptionEvent::Clear);
+    assert_eq!(describe(recent.snapshot()), ["status Ready"]);
+
+    let status_only = RecentEvents::new(0);
+    status_only.record(&update(0, "not kept", true));
+    assert!(status_only.snapshot().is_empty());
+}
+
+#[test]
+fn recent_events_keep_a_warning_past_later_statuses() {
+    let recent = RecentEvents::new(2);
+    recent.record(&CaptionEvent::Status(EngineStatus::Loading));
+    recent.record(&CaptionEvent::Status(EngineStatus::Warning(
+        "using the cached small model".to_string(),
+    )));
+    recent.record(&CaptionEvent::Status(EngineStatus::Ready));
+    assert_eq!(
+        describe(recent.snapshot()),
+        [
+            "status Ready",
+            "status Warning(\"using the cached small model\")"
+        ]
+    );
+}
+
+#[test]
+fn recent_events_replay_speaker_names_before_captions() {
+    let recent = RecentEvents::new(5);
+    recent.record(&CaptionEvent::Status(EngineStatus::Ready));
+    recent.record(&update(0, "hello", true));
+    for (id, name) in [(1, "Bob"), (0, "Alice"), (1, "")] {
+        recent.record(&CaptionEvent::SpeakerName {
+            id,
+            name: name.to_string(),
+        });
+    }
+    assert_eq!(
+        describe(recent.snapshot()),
+        ["status Ready", "speaker 0 Alice", "0: hello"]
+    );
+}
+
+#[test]
+fn manual_edits_win_over_later_revisions() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let edits = SegmentEdits::new(tx);
+    assert!(edits.edit(3, "   ").is_err());
+    edits.edit(3, " Fixed text. ").unwrap();
+    let sent = rx.try_recv().unwrap();
+    assert!(matches!(
+        &sent,
+        CaptionEvent::Revise { segment_id: 3, text } if text == "Fixed text."
+    ));
+    assert!(edits.is_edit(&sent));
+    assert!(!edits.overrides(&sent));
+
+    let revise = |segment_id, text: &str| CaptionEvent::Revise {
+        segment_id,
+        text: text.to_string(),
+    };
+    // The second pass finishing afterwards would put the mistake back.
+    assert!(edits.overrides(&revise(3, "Fixed test.")));
+    assert!(!edits.is_edit(&revise(3, "Fixed test.")));
+    assert!(!edits.overrides(&revise(4, "Other.")));
+
+    drop(rx);
+    assert!(edits.edit(3, "Again.").is_err());
+}
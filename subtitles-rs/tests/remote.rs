@@ -0,0 +1,96 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+use subtitles::app::{CaptionEvent, CaptionTiming, Speaker};
+use subtitles::config::SoundTagMode;
+use subtitles::remote::{download, is_direct_media};
+use subtitles::text::SoundTagFilter;
+use subtitles::vtt::{format_timestamp, VttWriter};
+
+/// Serves one response per connection: `body` for `/talk.mp3`, 404 for anything else.
+fn serve(body: &'static [u8], requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            let response = if request.starts_with("GET /talk.mp3") {
+                let mut response =
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+                        .into_bytes();
+                response.extend_from_slice(body);
+                response
+            } else {
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec()
+            };
+            stream.write_all(&response).unwrap();
+        }
+    });
+    format!("http://{addr}")
+}
+
+#[test]
+fn direct_links_are_downloaded_and_pages_are_not() {
+    assert!(is_direct_media(
+        "https://example.com/podcast/ep12.mp3?token=abc"
+    ));
+    assert!(is_direct_media("https://cdn.example.com/talk.M4A"));
+    assert!(!is_direct_media(
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+    ));
+    assert!(!is_direct_media("https://example.com/"));
+    assert!(!is_direct_media("https://talk.mp3"));
+
+    let dir = std::env::temp_dir().join(format!("subtitles-remote-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let base = serve(b"ID3 fake audio", 2);
+
+    let path = download(&format!("{base}/talk.mp3?session=1"), &dir).unwrap();
+    assert_eq!(path, dir.join("talk.mp3"));
+    assert_eq!(std::fs::read(&path).unwrap(), b"ID3 fake audio");
+
+    let err = download(&format!("{base}/missing.mp3"), &dir).unwrap_err();
+    assert!(format!("{err:#}").contains("404"), "{err:#}");
+}
+
+#[test]
+fn vtt_cues_use_dots_voices_and_escapes() {
+    assert_eq!(format_timestamp(3725.4567), "01:02:05.457");
+
+    let dir = std::env::temp_dir().join(format!("subtitles-vtt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut writer = VttWriter::create(
+        &dir.join("talk.vtt"),
+        SoundTagFilter::new(SoundTagMode::Strip, ""),
+    )
+    .unwrap();
+    for (segment_id, text, speaker) in [
+        (0, "Q&A starts <now>.", None),
+        (1, "[Music]", None),
+        (2, "Thanks.", Some(Speaker::new(1))),
+    ] {
+        writer.push(&CaptionEvent::Update {
+            segment_id,
+            text: text.to_string(),
+            is_final: true,
+            timing: Some(CaptionTiming {
+                start_s: segment_id as f64,
+                end_s: segment_id as f64 + 0.5,
+            }),
+            speaker,
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        });
+    }
+    writer.save().unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(dir.join("talk.vtt")).unwrap(),
+        "WEBVTT\n\n\
+         00:00:00.000 --> 00:00:00.500\nQ&amp;A starts &lt;now&gt;.\n\n\
+         00:00:02.000 --> 00:00:02.500\n<v Speaker 2>Thanks.\n\n"
+    );
+}
@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use subtitles::app::{CaptionEvent, CaptionTiming};
+use subtitles::report::{summarize, SessionReport, StatsTracker};
+
+#[test]
+fn summary_counts_talk_time_words_scripts_and_terms() {
+    let summary = summarize(
+        120.0,
+        [
+            ("The budget review: budget cuts, again.", 20.0),
+            ("Budget is fine, we're shipping Friday.", 10.0),
+            ("预算没问题", 6.0),
+        ],
+    );
+    assert_eq!(summary.segments, 3);
+    assert_eq!(summary.talk_s, 36.0);
+    assert_eq!(summary.silence_s, 84.0);
+    assert_eq!(summary.words, 17);
+    assert!((summary.words_per_minute - 17.0 * 60.0 / 36.0).abs() < 1e-9);
+    assert_eq!(summary.scripts["Latin"], 12);
+    assert_eq!(summary.scripts["Han"], 5);
+    assert_eq!(summary.top_terms[0], ("budget".to_string(), 3));
+    assert!(!summary.top_terms.iter().any(|(term, _)| term == "the"));
+
+    let markdown = summary.to_markdown();
+    assert!(markdown.contains("- Talk time: 36s (30%)\n- Silence: 1m 24s (70%)\n"));
+    assert!(markdown.contains("| budget | 3 |\n"));
+}
+
+#[test]
+fn report_uses_revisions_and_writes_json() {
+    let path = std::env::temp_dir().join(format!("subtitles-report-{}.json", std::process::id()));
+    let mut report = SessionReport::create(&path).unwrap();
+    report.handle(&CaptionEvent::Update {
+        segment_id: 0,
+        text: "helo world".into(),
+        is_final: true,
+        timing: Some(CaptionTiming {
+            start_s: 0.0,
+            end_s: 2.0,
+        }),
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    });
+    report.handle(&CaptionEvent::Update {
+        segment_id: 1,
+        text: "partial text".into(),
+        is_final: false,
+        timing: None,
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    });
+    report.handle(&CaptionEvent::Revise {
+        segment_id: 0,
+        text: "hello world".into(),
+    });
+    report.write().unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(json["segments"], 1);
+    assert_eq!(json["talk_s"], 2.0);
+    assert_eq!(json["words"], 2);
+    assert_eq!(json["top_terms"][0], serde_json::json!(["hello", 1]));
+}
+
+#[test]
+fn live_speaking_rate_covers_the_last_minute() {
+    let start = Instant::now();
+    let mut tracker = StatsTracker::new(start);
+    assert_eq!(tracker.stats(start).words_per_minute, 0.0);
+
+    tracker.push_final("one two three four five six", 3.0, start);
+    tracker.push_final("seven eight", 1.0, start + Duration::from_secs(40));
+    let stats = tracker.stats(start + Duration::from_secs(45));
+    assert_eq!(stats.words_per_minute, 120.0);
+    assert_eq!(stats.talk_s, 4.0);
+    assert_eq!(stats.session_s, 45.0);
+
+    // The first final has aged out of the rate, but still counts as talk time.
+    let stats = tracker.stats(start + Duration::from_secs(70));
+    assert_eq!(stats.words_per_minute, 120.0);
+    assert_eq!(stats.talk_s, 4.0);
+    let stats = tracker.stats(start + Duration::from_secs(120));
+    assert_eq!(stats.words_per_minute, 0.0);
+}
+
+#[test]
+fn retractions_per_minute_counts_the_last_minute() {
+    let start = Instant::now();
+    let mut tracker = StatsTracker::new(start);
+    tracker.set_retractions(2, start);
+    tracker.set_retractions(2, start + Duration::from_secs(10));
+    tracker.set_retractions(5, start + Duration::from_secs(30));
+    assert_eq!(
+        tracker
+            .stats(start + Duration::from_secs(40))
+            .retractions_per_minute,
+        5
+    );
+    assert_eq!(
+        tracker
+            .stats(start + Duration::from_secs(70))
+            .retractions_per_minute,
+        3
+    );
+    assert_eq!(
+        tracker
+            .stats(start + Duration::from_secs(100))
+            .retractions_per_minute,
+        0
+    );
+}
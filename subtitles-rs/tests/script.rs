@@ -0,0 +1,99 @@
+use subtitles::app::{CaptionEvent, CaptionTiming, CaptionWord};
+use subtitles::script::CaptionScript;
+
+fn final_event(segment_id: u64, text: &str) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text: text.to_string(),
+        is_final: true,
+        timing: Some(CaptionTiming {
+            start_s: 1.0,
+            end_s: 2.5,
+        }),
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    }
+}
+
+fn text(event: Option<CaptionEvent>) -> Option<String> {
+    match event? {
+        CaptionEvent::Update { text, .. } | CaptionEvent::Revise { text, .. } => Some(text),
+        other => panic!("unexpected event {other:?}"),
+    }
+}
+
+#[test]
+fn script_rewrites_drops_or_keeps_captions() {
+    let script = CaptionScript::from_source(
+        r#"
+        fn on_caption(event) {
+            if event.text.contains("secret") { return false; }
+            if event.kind == "final" && event.end_s - event.start_s > 1.0 {
+                return `${event.segment_id}: ${event.text}`;
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        text(script.apply(final_event(3, "hello"))).unwrap(),
+        "3: hello"
+    );
+    assert_eq!(text(script.apply(final_event(4, "the secret plan"))), None);
+    let revise = CaptionEvent::Revise {
+        segment_id: 3,
+        text: "hello there".into(),
+    };
+    assert_eq!(text(script.apply(revise)).unwrap(), "hello there");
+    assert!(matches!(
+        script.apply(CaptionEvent::Clear),
+        Some(CaptionEvent::Clear)
+    ));
+}
+
+#[test]
+fn unchanged_text_keeps_its_word_timings() {
+    let script = CaptionScript::from_source(
+        r#"fn on_caption(event) { if event.text == "hello" { event.text } else { "bye" } }"#,
+    )
+    .unwrap();
+    let timing = CaptionTiming {
+        start_s: 1.0,
+        end_s: 1.5,
+    };
+    let timed = |text: &str| CaptionEvent::Update {
+        segment_id: 1,
+        text: text.to_string(),
+        is_final: true,
+        timing: Some(timing),
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: vec![CaptionWord {
+            text: "hello".into(),
+            timing,
+        }],
+    };
+    let word_count = |event: Option<CaptionEvent>| match event {
+        Some(CaptionEvent::Update { words, .. }) => words.len(),
+        other => panic!("unexpected event {other:?}"),
+    };
+
+    assert_eq!(word_count(script.apply(timed("hello"))), 1);
+    assert_eq!(word_count(script.apply(timed("hi"))), 0);
+}
+
+#[test]
+fn broken_scripts_are_rejected_or_ignored() {
+    assert!(CaptionScript::from_source("fn on_caption( {").is_err());
+    assert!(CaptionScript::from_source("fn something_else(event) {}").is_err());
+
+    let script =
+        CaptionScript::from_source("fn on_caption(event) { event.text.len() / 0 }").unwrap();
+    assert_eq!(text(script.apply(final_event(1, "kept"))).unwrap(), "kept");
+    let looping = CaptionScript::from_source("fn on_caption(event) { loop {} }").unwrap();
+    assert_eq!(text(looping.apply(final_event(2, "kept"))).unwrap(), "kept");
+}
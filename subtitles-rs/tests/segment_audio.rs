@@ -0,0 +1,27 @@
+use subtitles::app::SegmentAudio;
+
+#[test]
+fn keeps_the_latest_segments_within_the_budget() {
+    let store = SegmentAudio::new(3);
+    let second = |value: f32| vec![value; 16_000];
+    store.insert(0, &second(0.1));
+    store.insert(1, &second(0.2));
+    store.insert(2, &second(0.3));
+    assert_eq!(store.get(0), Some(second(0.1)));
+
+    // The oldest segment makes room for the newest.
+    store.insert(3, &second(0.4));
+    assert_eq!(store.get(0), None);
+    assert_eq!(store.get(1), Some(second(0.2)));
+    assert_eq!(store.get(3), Some(second(0.4)));
+    assert_eq!(store.get(4), None);
+
+    // One longer than the whole budget isn't kept, and doesn't push the others out.
+    store.insert(4, &vec![0.5; 4 * 16_000]);
+    assert_eq!(store.get(4), None);
+    assert!(store.get(1).is_some());
+
+    let disabled = SegmentAudio::new(0);
+    disabled.insert(0, &second(0.1));
+    assert_eq!(disabled.get(0), None);
+}
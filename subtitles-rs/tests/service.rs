@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use subtitles::service::{parse_launchctl_print, render_plist, AgentState, ServicePaths, LABEL};
+
+#[test]
+fn plist_runs_the_headless_engine_with_its_files() {
+    let paths = ServicePaths::for_home(Path::new("/Users/ada"));
+    let plist = render_plist(
+        Path::new("/Applications/Subtitles.app/Contents/MacOS/subtitles"),
+        &["--prompt".into(), "Q&A <live>".into()],
+        &paths,
+    );
+    assert!(plist.contains(&format!("<string>{LABEL}</string>")));
+    assert!(plist.contains("<string>--no-ui</string>"));
+    assert!(plist.contains(
+        "<string>/Users/ada/Library/Application Support/subtitles/subtitles.pid</string>"
+    ));
+    assert!(plist.contains("<string>Q&amp;A &lt;live&gt;</string>"));
+    assert!(plist.contains("<string>/Users/ada/Library/Logs/subtitles/transcript.txt</string>"));
+    assert_eq!(
+        paths.plist,
+        Path::new("/Users/ada/Library/LaunchAgents").join(format!("{LABEL}.plist"))
+    );
+}
+
+#[test]
+fn reads_service_state_from_launchctl_print() {
+    let output = "gui/501/com.subtitles.headless = {\n\
+                  \tactive count = 1\n\
+                  \tstate = running\n\
+                  \tpid = 4242\n\
+                  \tlast exit code = (never exited)\n\
+                  \tendpoints = {\n\
+                  \t\tstate = idle\n\
+                  \t}\n\
+                  }\n";
+    assert_eq!(
+        parse_launchctl_print(output),
+        AgentState {
+            state: "running".into(),
+            pid: Some(4242),
+            last_exit: Some("(never exited)".into()),
+        }
+    );
+    assert_eq!(parse_launchctl_print("").state, "unknown");
+}
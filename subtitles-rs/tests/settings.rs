@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use subtitles::config::{Engine, OutputLanguage};
+use subtitles::settings::{load_settings, parse_cli_from, save_settings, Settings};
+
+fn temp_settings(name: &str, json: serde_json::Value) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("subtitles-settings-{}", std::process::id()));
+    let path = dir.join(name);
+    let settings: Settings = serde_json::from_value(json).unwrap();
+    save_settings(&path, &settings).unwrap();
+    path
+}
+
+#[test]
+fn settings_apply_before_command_line_flags() {
+    let path = temp_settings(
+        "override.json",
+        serde_json::json!({
+            "engine": "openai",
+            "output-language": "bilingual",
+            "max-window-s": 6.5,
+            "streaming": false,
+            "local-only": true,
+        }),
+    );
+
+    let cli = parse_cli_from(["subtitles", "--output-language", "chinese"], Some(path)).unwrap();
+    assert!(matches!(cli.engine, Engine::OpenAI));
+    assert_eq!(cli.output_language, OutputLanguage::Chinese);
+    assert_eq!(cli.max_window_s, 6.5);
+    assert!(!cli.streaming);
+    assert!(cli.local_only);
+}
+
+#[test]
+fn explicit_config_replaces_default_settings_file() {
+    let default = temp_settings("default.json", serde_json::json!({ "engine": "openai" }));
+    let explicit = temp_settings("explicit.json", serde_json::json!({ "vad-threshold": 0.05 }));
+
+    let cli = parse_cli_from(
+        ["subtitles".into(), format!("--config={}", explicit.display())],
+        Some(default),
+    )
+    .unwrap();
+    assert!(matches!(cli.engine, Engine::Local));
+    assert_eq!(cli.vad_threshold, 0.05);
+}
+
+#[test]
+fn unknown_settings_are_rejected_on_save() {
+    let settings: Settings =
+        serde_json::from_value(serde_json::json!({ "no-such-flag": 1 })).unwrap();
+    let path = std::env::temp_dir().join("subtitles-settings-unknown.json");
+    assert!(save_settings(&path, &settings).is_err());
+    assert!(load_settings(&path).is_err());
+}
+
+#[test]
+fn prompt_setting_keeps_spaces_and_punctuation() {
+    let prompt = "Transcribe with punctuation, and numerals as digits: 1, 2, 3.";
+    let path = temp_settings("prompt.json", serde_json::json!({ "prompt": prompt }));
+
+    let cli = parse_cli_from(["subtitles"], Some(path)).unwrap();
+    assert_eq!(cli.prompt.as_deref(), Some(prompt));
+}
+
+#[test]
+fn model_mirrors_and_custom_models_come_from_settings_blocks() {
+    let path = temp_settings(
+        "models.json",
+        serde_json::json!({
+            "model-mirror": ["https://hf-mirror.com", "https://models.acme.internal/hf"],
+            "custom-model": {
+                "acme-small": "https://models.acme.internal/ggml-acme-small.bin",
+            },
+            "whisper-custom-model": "acme-small",
+        }),
+    );
+
+    let cli = parse_cli_from(["subtitles"], Some(path)).unwrap();
+    assert_eq!(
+        cli.model_mirror,
+        ["https://hf-mirror.com", "https://models.acme.internal/hf"]
+    );
+    assert_eq!(cli.custom_model.len(), 1);
+    assert_eq!(cli.custom_model[0].name, "acme-small");
+    assert_eq!(
+        cli.custom_model[0].url,
+        "https://models.acme.internal/ggml-acme-small.bin"
+    );
+    assert_eq!(cli.whisper_custom_model.as_deref(), Some("acme-small"));
+}
@@ -0,0 +1,97 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use subtitles::config::OutputLanguage;
+use subtitles::shadow::{ShadowEngine, ShadowJob, ShadowStats};
+use subtitles::text::FilterChain;
+use subtitles::transcribe::{DownloadProgress, Transcriber, TranscriberConfig};
+
+/// Decodes every segment to the same text.
+struct Fixed(&'static str);
+
+impl Transcriber for Fixed {
+    fn transcribe(&mut self, _: &[f32], _: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+}
+
+fn config() -> TranscriberConfig {
+    TranscriberConfig {
+        input_language: None,
+        output_language: OutputLanguage::English,
+        is_partial: false,
+        segment_timestamps: false,
+        prompt: None,
+        glossary: None,
+        diarize: false,
+    }
+}
+
+#[test]
+fn stats_count_finals_below_the_agreement_threshold() {
+    let mut stats = ShadowStats::default();
+    assert_eq!(stats.disagreement_rate(), 0.0);
+    assert_eq!(stats.mean_agreement(), 1.0);
+
+    stats.record(1.0);
+    stats.record(0.9);
+    stats.record(0.4);
+    stats.record(0.5);
+    assert_eq!(stats.compared, 4);
+    assert_eq!(stats.disagreed, 2);
+    assert_eq!(stats.disagreement_rate(), 0.5);
+    assert!((stats.mean_agreement() - 0.7).abs() < 1e-6);
+}
+
+#[test]
+fn compared_finals_are_written_to_the_log() {
+    let dir = std::env::temp_dir().join(format!("subtitles-shadow-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("shadow.jsonl");
+    let _ = std::fs::remove_file(&path);
+
+    let shadow = ShadowEngine::spawn(
+        "openai",
+        Box::new(|_: &mut dyn FnMut(DownloadProgress)| {
+            Ok(Box::new(Fixed("We ship on Friday.")) as Box<dyn Transcriber>)
+        }),
+        config(),
+        FilterChain::default(),
+        Some(&path),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .unwrap();
+    for (segment_id, live) in [(0, "we ship on friday"), (1, "We sip on fried eggs.")] {
+        shadow.submit(ShadowJob {
+            segment_id,
+            audio: vec![0.0; 160].into(),
+            output_language: OutputLanguage::English,
+            live: live.to_string(),
+            crosstalk: false,
+        });
+    }
+    shadow.join();
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let records: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["shadow"], "We ship on Friday.");
+    assert_eq!(records[0]["agreement"], 1.0);
+    assert_eq!(records[1]["live"], "We sip on fried eggs.");
+    assert!(records[1]["agreement"].as_f64().unwrap() < 0.8);
+    assert_eq!(records[1]["disagreement_rate"], 0.5);
+
+    assert!(ShadowEngine::spawn(
+        "openai",
+        Box::new(|_: &mut dyn FnMut(DownloadProgress)| {
+            Ok(Box::new(Fixed("")) as Box<dyn Transcriber>)
+        }),
+        config(),
+        FilterChain::default(),
+        Some(&dir.join("missing").join("shadow.jsonl")),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .is_err());
+}
@@ -0,0 +1,153 @@
+use subtitles::app::{CaptionEvent, CaptionTiming, Speaker, SpeakerTurns, VoiceSpeakers};
+use subtitles::audio::{VoicePrint, VoiceProfiles};
+use subtitles::config::SoundTagMode;
+use subtitles::srt::SrtWriter;
+use subtitles::testing::SignalBuilder;
+use subtitles::text::SoundTagFilter;
+use subtitles::theme::{contrast_ratio, AA_CONTRAST, SPEAKER_COLORS};
+use subtitles::vtt::VttWriter;
+
+#[test]
+fn speaker_turns_alternate_and_keep_stable_colors() {
+    let mut turns = SpeakerTurns::default();
+    // A turn at the end of a final means the next one is the other speaker.
+    assert_eq!(turns.label(1), Speaker::new(0));
+    assert_eq!(turns.label(0), Speaker::new(1));
+    // Two turns inside one final end back on the same speaker.
+    assert_eq!(turns.label(2), Speaker::new(1));
+    assert_eq!(turns.label(1).id, 1);
+    assert_eq!(turns.label(0).id, 0);
+
+    assert_eq!(Speaker::new(1).color, Speaker::new(1).color);
+    assert_ne!(Speaker::new(0).color, Speaker::new(1).color);
+    for color in SPEAKER_COLORS {
+        assert!(
+            contrast_ratio(color, "#000000").unwrap() >= AA_CONTRAST,
+            "{color}"
+        );
+    }
+}
+
+#[test]
+fn srt_colors_cues_by_speaker() {
+    let dir = std::env::temp_dir().join(format!("subtitles-speakers-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filter = SoundTagFilter::new(SoundTagMode::Keep, "");
+    let mut writer = SrtWriter::create(&dir.join("speakers.srt"), filter).unwrap();
+    for (segment_id, text, speaker) in [(0, "Hi.", Some(0)), (1, "Hello.", None)] {
+        writer
+            .handle(&CaptionEvent::Update {
+                segment_id,
+                text: text.to_string(),
+                is_final: true,
+                timing: Some(CaptionTiming {
+                    start_s: segment_id as f64,
+                    end_s: segment_id as f64 + 0.5,
+                }),
+                speaker: speaker.map(Speaker::new),
+                untranslated: false,
+                spans: Vec::new(),
+                words: Vec::new(),
+            })
+            .unwrap();
+    }
+    assert_eq!(
+        writer.render(),
+        format!(
+            "1\n00:00:00,000 --> 00:00:00,500\n<font color=\"{}\">Hi.</font>\n\n\
+             2\n00:00:01,000 --> 00:00:01,500\nHello.\n\n",
+            SPEAKER_COLORS[0]
+        )
+    );
+}
+
+#[test]
+fn speaker_names_apply_to_cues_already_written() {
+    let dir =
+        std::env::temp_dir().join(format!("subtitles-speaker-names-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filter = || SoundTagFilter::new(SoundTagMode::Keep, "");
+    let mut srt = SrtWriter::create(&dir.join("names.srt"), filter()).unwrap();
+    let mut vtt = VttWriter::create(&dir.join("names.vtt"), filter()).unwrap();
+    let events = [
+        CaptionEvent::Update {
+            segment_id: 0,
+            text: "Hi.".to_string(),
+            is_final: true,
+            timing: Some(CaptionTiming {
+                start_s: 0.0,
+                end_s: 0.5,
+            }),
+            speaker: Some(Speaker::new(1)),
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        },
+        CaptionEvent::SpeakerName {
+            id: 1,
+            name: "Alice <host>".to_string(),
+        },
+    ];
+    for event in &events {
+        srt.handle(event).unwrap();
+        vtt.push(event);
+    }
+    assert!(srt.render().contains(">Alice <host>: Hi.</font>"));
+    assert!(vtt.render().contains("<v Alice &lt;host&gt;>Hi."));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("names.srt")).unwrap(),
+        srt.render()
+    );
+
+    // An empty name goes back to the default label.
+    let cleared = CaptionEvent::SpeakerName {
+        id: 1,
+        name: String::new(),
+    };
+    assert!(srt.push(&cleared));
+    vtt.push(&cleared);
+    assert!(srt.render().contains("\">Hi.</font>"));
+    assert!(vtt.render().contains("<v Speaker 2>Hi."));
+    // Naming a speaker with no cues changes nothing.
+    assert!(!srt.push(&CaptionEvent::SpeakerName {
+        id: 5,
+        name: "Nobody".to_string(),
+    }));
+}
+
+#[test]
+fn enrolled_voices_label_finals_by_name() {
+    let voice = |seed, pitch_hz, tract| {
+        SignalBuilder::new(16_000, seed)
+            .voice(2.0, pitch_hz, tract)
+            .build()
+    };
+    let mut profiles = VoiceProfiles::default();
+    for (name, pitch_hz, tract) in [("Alice", 210.0, 1.18), ("Bob", 110.0, 1.0)] {
+        let print = VoicePrint::from_samples(&voice(1, pitch_hz, tract)).unwrap();
+        profiles.enroll(name, print);
+    }
+    let voices = VoiceSpeakers::new(profiles, 0.8);
+    let names: Vec<(u32, String)> = voices
+        .names()
+        .into_iter()
+        .map(|event| match event {
+            CaptionEvent::SpeakerName { id, name } => (id, name),
+            other => panic!("unexpected event {other:?}"),
+        })
+        .collect();
+    assert_eq!(names, [(0, "Alice".to_string()), (1, "Bob".to_string())]);
+
+    let bob = voice(7, 105.0, 1.0);
+    assert_eq!(voices.label(&bob, None), Some(Speaker::new(1)));
+    assert_eq!(
+        voices.label(&bob, Some(Speaker::new(0))),
+        Some(Speaker::new(1))
+    );
+    // Too short to tell: `--diarize`'s label, after the enrolled ids.
+    assert_eq!(
+        voices.label(&bob[..8_000], Some(Speaker::new(0))),
+        Some(Speaker::new(2))
+    );
+    assert_eq!(voices.label(&bob[..8_000], None), None);
+}
@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+use subtitles::app::SharedOutputLanguage;
+use subtitles::config::OutputLanguage;
+use subtitles::speech::{english_line, say_command, SpeechSink};
+use subtitles::CaptionEvent;
+
+#[test]
+fn english_line_picks_the_translation() {
+    assert_eq!(
+        english_line("Good morning", OutputLanguage::English),
+        Some("Good morning")
+    );
+    assert_eq!(english_line("早上好", OutputLanguage::Chinese), None);
+    assert_eq!(
+        english_line("早上好\nGood morning", OutputLanguage::Bilingual),
+        Some("Good morning")
+    );
+    // English input in bilingual mode is a single line; untranslated Chinese isn't spoken.
+    assert_eq!(
+        english_line("Good morning", OutputLanguage::Bilingual),
+        Some("Good morning")
+    );
+    assert_eq!(english_line("早上好", OutputLanguage::Bilingual), None);
+
+    assert_eq!(
+        say_command(Some("Samantha"), Some("BlackHole 2ch")),
+        ["say", "-v", "Samantha", "-a", "BlackHole 2ch"]
+    );
+    assert_eq!(say_command(None, None), ["say"]);
+}
+
+#[test]
+fn sink_speaks_finals_without_sound_tags() {
+    let dir = std::env::temp_dir().join(format!("subtitles-speech-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spoken = dir.join("spoken.txt");
+    let command = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!(
+            "cat >> '{}'; echo >> '{}'",
+            spoken.display(),
+            spoken.display()
+        ),
+    ];
+    let language = SharedOutputLanguage::new(OutputLanguage::Bilingual);
+    let sink = SpeechSink::start(command, language);
+
+    let update = |text: &str, is_final: bool| CaptionEvent::Update {
+        segment_id: 0,
+        text: text.to_string(),
+        is_final,
+        timing: None,
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    };
+    sink.handle(&update("早上\nMorning", false));
+    sink.handle(&update("早上好\nGood morning [Music]", true));
+    sink.handle(&CaptionEvent::Clear);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while std::fs::read_to_string(&spoken)
+        .unwrap_or_default()
+        .is_empty()
+    {
+        assert!(Instant::now() < deadline, "nothing was spoken");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(std::fs::read_to_string(&spoken).unwrap(), "Good morning\n");
+}
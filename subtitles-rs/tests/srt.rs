@@ -0,0 +1,146 @@
+use subtitles::app::{CaptionEvent, CaptionTiming, CaptionWord};
+use subtitles::config::SoundTagMode;
+use subtitles::srt::{format_timestamp, SrtWriter};
+use subtitles::text::{ReadingSpeed, SoundTagFilter};
+
+fn final_update(segment_id: u64, text: &str, start_s: f64, end_s: f64) -> CaptionEvent {
+    CaptionEvent::Update {
+        segment_id,
+        text: text.to_string(),
+        is_final: true,
+        timing: Some(CaptionTiming { start_s, end_s }),
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words: Vec::new(),
+    }
+}
+
+#[test]
+fn timestamps_use_subrip_format() {
+    assert_eq!(format_timestamp(0.0), "00:00:00,000");
+    assert_eq!(format_timestamp(3725.4567), "01:02:05,457");
+}
+
+#[test]
+fn writer_numbers_timed_finals_and_applies_revisions() {
+    let dir = std::env::temp_dir().join(format!("subtitles-srt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("recording.srt");
+    let filter = SoundTagFilter::new(SoundTagMode::Strip, "♪".to_string());
+    let mut writer = SrtWriter::create(&path, filter).unwrap();
+
+    writer
+        .handle(&final_update(0, "Hello there.", 1.25, 2.5))
+        .unwrap();
+    writer
+        .handle(&final_update(1, "[Music]", 3.0, 5.0))
+        .unwrap();
+    writer
+        .handle(&final_update(2, "How are you?", 6.0, 7.75))
+        .unwrap();
+    // Partials and untimed finals don't make cues.
+    writer
+        .handle(&CaptionEvent::Update {
+            segment_id: 3,
+            text: "How".to_string(),
+            is_final: false,
+            timing: None,
+            speaker: None,
+            untranslated: false,
+            spans: Vec::new(),
+            words: Vec::new(),
+        })
+        .unwrap();
+    writer
+        .handle(&CaptionEvent::Revise {
+            segment_id: 0,
+            text: "Hello, there.".to_string(),
+        })
+        .unwrap();
+
+    let expected = "1\n00:00:01,250 --> 00:00:02,500\nHello, there.\n\n\
+                    2\n00:00:06,000 --> 00:00:07,750\nHow are you?\n\n";
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+    assert_eq!(writer.render(), expected);
+}
+
+#[test]
+fn reading_speed_splits_long_finals_into_cues() {
+    let dir = std::env::temp_dir().join(format!("subtitles-srt-reading-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filter = SoundTagFilter::new(SoundTagMode::Keep, "♪".to_string());
+    let mut writer = SrtWriter::create(&dir.join("recording.srt"), filter)
+        .unwrap()
+        .with_reading_speed(ReadingSpeed::new(10.0));
+
+    writer
+        .handle(&final_update(
+            0,
+            "One two three four five six seven eight nine ten. Eleven twelve.",
+            10.0,
+            22.6,
+        ))
+        .unwrap();
+    writer
+        .handle(&final_update(1, "Short.", 30.0, 30.5))
+        .unwrap();
+
+    // Stretched over the speech in proportion to their reading times; short finals keep theirs.
+    let expected = "1\n00:00:10,000 --> 00:00:19,800\n\
+                    One two three four five six seven eight nine ten.\n\n\
+                    2\n00:00:19,800 --> 00:00:22,600\nEleven twelve.\n\n\
+                    3\n00:00:30,000 --> 00:00:30,500\nShort.\n\n";
+    assert_eq!(writer.render(), expected);
+}
+
+#[test]
+fn aligned_words_time_cues_by_the_speech() {
+    let dir = std::env::temp_dir().join(format!("subtitles-srt-words-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filter = SoundTagFilter::new(SoundTagMode::Keep, "♪".to_string());
+    let mut writer = SrtWriter::create(&dir.join("recording.srt"), filter)
+        .unwrap()
+        .with_reading_speed(ReadingSpeed::new(10.0));
+
+    let text = "One two three four five six seven eight nine ten. Eleven twelve.";
+    // Speech from 10.5 s, a word every 0.5 s, with a pause before "Eleven".
+    let mut spans: Vec<(f64, f64)> = (0..10)
+        .map(|i| (10.5 + i as f64 * 0.5, 10.9 + i as f64 * 0.5))
+        .collect();
+    spans.extend([(17.0, 17.4), (17.5, 18.5)]);
+    let words = text
+        .split_whitespace()
+        .zip(spans)
+        .map(|(word, (start_s, end_s))| CaptionWord {
+            text: word.to_string(),
+            timing: CaptionTiming { start_s, end_s },
+        })
+        .collect();
+    let update = |segment_id, text: &str, start_s, end_s, words| CaptionEvent::Update {
+        segment_id,
+        text: text.to_string(),
+        is_final: true,
+        timing: Some(CaptionTiming { start_s, end_s }),
+        speaker: None,
+        untranslated: false,
+        spans: Vec::new(),
+        words,
+    };
+    writer.push(&update(0, text, 10.0, 19.0, words));
+    // A short final only trims to its speech.
+    let hello = vec![CaptionWord {
+        text: "Hello.".to_string(),
+        timing: CaptionTiming {
+            start_s: 30.25,
+            end_s: 30.75,
+        },
+    }];
+    writer.push(&update(1, "Hello.", 30.0, 31.5, hello));
+
+    let expected = "1\n00:00:10,500 --> 00:00:17,000\n\
+                    One two three four five six seven eight nine ten.\n\n\
+                    2\n00:00:17,000 --> 00:00:18,500\nEleven twelve.\n\n\
+                    3\n00:00:30,250 --> 00:00:30,750\nHello.\n\n";
+    assert_eq!(writer.render(), expected);
+}
@@ -0,0 +1,60 @@
+use std::io::Cursor;
+
+use subtitles::stdin_capture::{forward_pcm, PcmDecoder, PcmEncoding, PcmFormat};
+
+#[test]
+fn formats_parse_from_the_command_line() {
+    let format: PcmFormat = "s16le:48000:2".parse().unwrap();
+    assert_eq!(
+        format,
+        PcmFormat {
+            encoding: PcmEncoding::S16Le,
+            sample_rate: 48_000,
+            channels: 2,
+        }
+    );
+    assert_eq!(format.to_string(), "s16le:48000:2");
+    assert_eq!(
+        "F32LE:16000:1".parse::<PcmFormat>().unwrap().encoding,
+        PcmEncoding::F32Le
+    );
+
+    for bad in [
+        "s16le",
+        "s16le:48000",
+        "s16le:48000:2:1",
+        "s16le:48000:0",
+        "s16le:44k:2",
+    ] {
+        assert!(bad.parse::<PcmFormat>().is_err(), "{bad}");
+    }
+    let err = "u8:8000:1".parse::<PcmFormat>().unwrap_err();
+    assert!(err.contains("s16le, s32le or f32le"), "{err}");
+}
+
+#[test]
+fn frames_split_across_reads_are_downmixed_and_resampled() {
+    let mut decoder = PcmDecoder::new("s16le:16000:2".parse().unwrap());
+    // Left 0.5 and right -0.25 average to 0.125; the second frame arrives in pieces.
+    let frame = [0x00, 0x40, 0x00, 0xe0];
+    assert_eq!(decoder.push(&frame), [0.125]);
+    assert!(decoder.push(&frame[..3]).is_empty());
+    assert_eq!(decoder.push(&frame[3..]), [0.125]);
+
+    let mut decoder = PcmDecoder::new("f32le:16000:1".parse().unwrap());
+    assert_eq!(decoder.push(&(-0.75f32).to_le_bytes()), [-0.75]);
+
+    // One second of 48 kHz stereo becomes one second at 16 kHz.
+    let input: Vec<u8> = (0..48_000 * 2)
+        .flat_map(|_| 8192i16.to_le_bytes())
+        .collect();
+    let (audio_tx, audio_rx) = crossbeam_channel::unbounded();
+    let format = "s16le:48000:2".parse().unwrap();
+    assert_eq!(forward_pcm(Cursor::new(input), format, &audio_tx), 16_000);
+    let chunks: Vec<_> = audio_rx.try_iter().collect();
+    assert!(chunks.iter().all(|chunk| chunk.is_asr_format()));
+    assert_eq!(chunks[1].pts, chunks[0].duration());
+    let samples: Vec<f32> = chunks.into_iter().flat_map(|chunk| chunk.samples).collect();
+    assert_eq!(samples.len(), 16_000);
+    assert!(samples.iter().all(|&s| (s - 0.25).abs() < 1e-6));
+}
@@ -0,0 +1,76 @@
+use subtitles::theme::{
+    contrast_ratio, CaptionBox, CaptionTheme, SharedCaptionTheme, AAA_CONTRAST, AA_CONTRAST,
+};
+
+#[test]
+fn contrast_ratio_matches_wcag_reference_values() {
+    let black_white = contrast_ratio("#000000", "#ffffff").unwrap();
+    assert!((black_white - 21.0).abs() < 1e-9);
+    assert!((contrast_ratio("#777777", "#ffffff").unwrap() - 4.48).abs() < 0.01);
+    assert_eq!(contrast_ratio("#abc", "#ffffff"), None);
+}
+
+#[test]
+fn themes_meet_wcag_contrast_and_resolve_by_name() {
+    for theme in CaptionTheme::ALL {
+        let style = theme.style();
+        assert_eq!(CaptionTheme::from_name(style.name), Some(theme));
+        if theme == CaptionTheme::Default {
+            let outlined = contrast_ratio(style.text_color, style.outline_color).unwrap();
+            assert!(outlined >= AA_CONTRAST, "{}: {outlined}", style.name);
+        } else {
+            let boxed = style.box_contrast().unwrap();
+            assert!(boxed >= AAA_CONTRAST, "{}: {boxed}", style.name);
+        }
+    }
+    assert_eq!(
+        CaptionTheme::from_name("Large-Print"),
+        Some(CaptionTheme::LargePrint)
+    );
+    assert_eq!(CaptionTheme::from_name("neon"), None);
+}
+
+#[test]
+fn caption_box_overrides_the_theme_box_and_keeps_unset_fields() {
+    let shared = SharedCaptionTheme::new(
+        CaptionTheme::Default,
+        CaptionBox {
+            color: Some("#101010".into()),
+            ..CaptionBox::default()
+        },
+    );
+    assert_eq!(shared.style().background_opacity, 0.0);
+
+    shared.update_box(CaptionBox {
+        opacity: Some(0.85),
+        padding_em: Some(0.5),
+        ..CaptionBox::default()
+    });
+    let style = shared.style();
+    assert_eq!(style.background_color, "#101010");
+    assert_eq!(style.background_opacity, 0.85);
+    assert_eq!(style.background_padding_em, 0.5);
+    assert!(style.box_contrast().unwrap() >= AA_CONTRAST);
+
+    // The box outlives a theme switch.
+    shared.set(CaptionTheme::YellowOnBlack);
+    assert_eq!(shared.style().background_color, "#101010");
+}
+
+#[test]
+fn caption_box_rejects_bad_values() {
+    let bad = |caption_box: CaptionBox| caption_box.validate().is_err();
+    assert!(bad(CaptionBox {
+        color: Some("black".into()),
+        ..CaptionBox::default()
+    }));
+    assert!(bad(CaptionBox {
+        opacity: Some(1.5),
+        ..CaptionBox::default()
+    }));
+    assert!(bad(CaptionBox {
+        padding_em: Some(-0.1),
+        ..CaptionBox::default()
+    }));
+    assert!(CaptionBox::default().validate().is_ok());
+}
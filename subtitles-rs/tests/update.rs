@@ -0,0 +1,40 @@
+use base64::Engine as _;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use subtitles::update::{is_newer, newer_release, parse_feed, verify_signature};
+
+#[test]
+fn compares_release_versions() {
+    assert!(is_newer("0.2.0", "0.1.9"));
+    assert!(is_newer("v1.0", "0.9.3"));
+    assert!(is_newer("0.1.10", "0.1.9"));
+    assert!(!is_newer("0.1.0", "0.1.0"));
+    assert!(!is_newer("0.2.0-beta.1", "0.2.0"));
+    assert!(!is_newer("latest", "0.1.0"));
+
+    let feed = parse_feed(
+        r#"{ "version": "99.0.0", "notes": "Faster captions",
+             "platforms": { "macos-aarch64": { "url": "https://example.com/subtitles",
+                                               "signature": "AAAA" } } }"#,
+    )
+    .unwrap();
+    let update = newer_release(&feed).unwrap();
+    assert_eq!(update.version, "99.0.0");
+    assert_eq!(update.current, env!("CARGO_PKG_VERSION"));
+    assert_eq!(update.notes.as_deref(), Some("Faster captions"));
+    assert!(newer_release(&parse_feed(r#"{ "version": "0.0.1" }"#).unwrap()).is_none());
+}
+
+#[test]
+fn only_binaries_signed_by_the_release_key_verify() {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let rng = ring::rand::SystemRandom::new();
+    let key =
+        Ed25519KeyPair::from_pkcs8(Ed25519KeyPair::generate_pkcs8(&rng).unwrap().as_ref()).unwrap();
+    let public_key = b64.encode(key.public_key().as_ref());
+    let binary = b"\xcf\xfa\xed\xfe new subtitles build";
+    let signature = b64.encode(key.sign(binary).as_ref());
+
+    verify_signature(binary, &signature, &public_key).unwrap();
+    assert!(verify_signature(b"tampered build", &signature, &public_key).is_err());
+    assert!(verify_signature(binary, "not base64!", &public_key).is_err());
+}
@@ -0,0 +1,68 @@
+use subtitles::config::OutputLanguage;
+use subtitles::voice_commands::{spoken_output_language, CommandSpotter, VoiceAction};
+
+#[test]
+fn wake_word_commands_are_spotted_and_removed_from_the_caption() {
+    let spotter = CommandSpotter::new("subtitle").unwrap();
+
+    let spotted = spotter.spot("Subtitle, pause.").unwrap();
+    assert_eq!(spotted.action, VoiceAction::Pause);
+    assert_eq!(spotted.rest, "");
+
+    let spotted = spotter
+        .spot("Thanks for coming. Subtitles translate bilingual. Let's start.")
+        .unwrap();
+    assert_eq!(spotted.action, VoiceAction::Translate("bilingual".into()));
+    assert_eq!(spotted.rest, "Thanks for coming. Let's start.");
+
+    assert_eq!(
+        spotter.spot("subtitle model medical").unwrap().action,
+        VoiceAction::Domain("medical".into())
+    );
+    // The wake word alone, or an unknown command, is just speech.
+    assert_eq!(spotter.spot("The subtitle is too small."), None);
+    assert_eq!(spotter.spot("Pause the video."), None);
+
+    assert_eq!(
+        spoken_output_language("mandarin"),
+        Some(OutputLanguage::Chinese)
+    );
+    assert_eq!(spoken_output_language("japanese"), None);
+}
+
+#[test]
+fn configured_phrases_map_to_actions() {
+    let dir = std::env::temp_dir().join(format!("subtitles-voice-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("commands.json");
+    std::fs::write(
+        &path,
+        r#"{ "captions off please": "pause", "okay captions english": "translate english" }"#,
+    )
+    .unwrap();
+
+    let spotter = CommandSpotter::new("hey captions")
+        .unwrap()
+        .with_phrases_file(&path)
+        .unwrap();
+    assert_eq!(
+        spotter.spot("Captions off, please!").unwrap().action,
+        VoiceAction::Pause
+    );
+    assert_eq!(
+        spotter.spot("okay captions English").unwrap().action,
+        VoiceAction::Translate("english".into())
+    );
+    assert_eq!(
+        spotter.spot("hey captions resume").unwrap().action,
+        VoiceAction::Resume
+    );
+    assert_eq!(spotter.spot("subtitle pause"), None);
+
+    std::fs::write(&path, r#"{ "captions off": "explode" }"#).unwrap();
+    assert!(CommandSpotter::new("subtitle")
+        .unwrap()
+        .with_phrases_file(&path)
+        .is_err());
+    assert!(CommandSpotter::new("...").is_err());
+}
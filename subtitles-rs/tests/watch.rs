@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use subtitles::audio::SegmenterConfig;
+use subtitles::batch::{BatchOptions, SubtitleFormat};
+use subtitles::config::{OutputLanguage, SoundTagMode};
+use subtitles::text::SoundTagFilter;
+use subtitles::transcribe::{Transcriber, TranscriberConfig};
+use subtitles::watch::{watch, PendingFiles};
+
+struct Fixed(&'static str);
+
+impl Transcriber for Fixed {
+    fn transcribe(&mut self, _: &[f32], _: &TranscriberConfig) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("subtitles-watch-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// One second of a tone at 16 kHz.
+fn write_wav(path: &Path) {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16_000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    for i in 0..16_000 {
+        let sample = (i as f32 / 16_000.0 * 220.0 * std::f32::consts::TAU).sin() * 0.3;
+        writer
+            .write_sample((sample * i16::MAX as f32) as i16)
+            .unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn options() -> BatchOptions {
+    BatchOptions {
+        out_dir: None,
+        overwrite: false,
+        decode_cfg: TranscriberConfig {
+            input_language: None,
+            output_language: OutputLanguage::English,
+            is_partial: false,
+            segment_timestamps: false,
+            prompt: None,
+            glossary: None,
+            diarize: false,
+        },
+        segmenter: SegmenterConfig {
+            sample_rate_hz: 16_000,
+            vad_threshold: 0.012,
+            vad_end_silence_s: 0.6,
+            max_segment_s: 20.0,
+            pre_roll_s: 0.25,
+        },
+        format: SubtitleFormat::Srt,
+        sound_tags: SoundTagFilter::new(SoundTagMode::Strip, ""),
+    }
+}
+
+#[test]
+fn files_are_ready_once_their_size_settles() {
+    let dir = temp_dir("pending");
+    let growing = dir.join("lecture.m4a");
+    std::fs::write(&growing, b"abc").unwrap();
+    let start = Instant::now();
+    let mut pending = PendingFiles::new(Duration::from_secs(5));
+    pending.note(&growing, start);
+
+    assert!(pending.ready(start + Duration::from_secs(2)).is_empty());
+    // Still being written: the wait starts over.
+    std::fs::write(&growing, b"abcdef").unwrap();
+    assert!(pending.ready(start + Duration::from_secs(6)).is_empty());
+    assert!(pending.ready(start + Duration::from_secs(10)).is_empty());
+    assert_eq!(pending.ready(start + Duration::from_secs(11)), [growing]);
+    assert!(pending.is_empty());
+
+    // Deleted before it settled.
+    let gone = dir.join("gone.wav");
+    std::fs::write(&gone, b"abc").unwrap();
+    pending.note(&gone, start);
+    std::fs::remove_file(&gone).unwrap();
+    assert!(pending.ready(start + Duration::from_secs(60)).is_empty());
+    assert_eq!(pending.len(), 0);
+}
+
+#[test]
+fn new_recordings_get_an_srt_next_to_them() {
+    let dir = temp_dir("folder");
+    write_wav(&dir.join("old.wav"));
+    std::fs::write(dir.join("done.wav"), b"already transcribed").unwrap();
+    std::fs::write(dir.join("done.srt"), "1\n").unwrap();
+
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let watcher = scope.spawn(|| {
+            watch(
+                &dir,
+                false,
+                true,
+                Duration::from_millis(200),
+                &options(),
+                Box::new(Fixed("Welcome back.")),
+                &stop,
+            )
+        });
+        let wait_for = |path: &Path| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                let srt = std::fs::read_to_string(path).unwrap_or_default();
+                if !srt.is_empty() || Instant::now() > deadline {
+                    return srt;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        };
+        assert!(wait_for(&dir.join("old.srt")).contains("Welcome back."));
+
+        std::fs::create_dir_all(dir.join("drafts")).unwrap();
+        write_wav(&dir.join("drafts").join("skipped.wav"));
+        std::fs::write(dir.join("notes.txt"), "not audio").unwrap();
+        write_wav(&dir.join("new.wav"));
+        assert!(wait_for(&dir.join("new.srt")).contains("Welcome back."));
+
+        stop.store(true, Ordering::Relaxed);
+        watcher.join().unwrap().unwrap();
+    });
+    assert_eq!(
+        std::fs::read_to_string(dir.join("done.srt")).unwrap(),
+        "1\n"
+    );
+    assert!(!dir.join("notes.srt").exists());
+    assert!(!dir.join("drafts").join("skipped.srt").exists());
+}
+
+#[test]
+fn recursive_watches_start_with_recordings_in_subfolders() {
+    let dir = temp_dir("recursive");
+    std::fs::create_dir_all(dir.join("week1").join("monday")).unwrap();
+    write_wav(&dir.join("week1").join("monday").join("intro.wav"));
+
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let watcher = scope.spawn(|| {
+            watch(
+                &dir,
+                true,
+                true,
+                Duration::from_millis(200),
+                &options(),
+                Box::new(Fixed("Welcome back.")),
+                &stop,
+            )
+        });
+        let srt = dir.join("week1").join("monday").join("intro.srt");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !srt.exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        stop.store(true, Ordering::Relaxed);
+        watcher.join().unwrap().unwrap();
+    });
+    let srt = std::fs::read_to_string(dir.join("week1").join("monday").join("intro.srt"));
+    assert!(srt.unwrap().contains("Welcome back."));
+}
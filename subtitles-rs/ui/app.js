@@ -9,7 +9,22 @@
   const stageEl = document.getElementById("stage");
   const sizeRange = document.getElementById("sizeRange");
   const widthRange = document.getElementById("widthRange");
+  const delayRange = document.getElementById("delayRange");
+  const stabilityRange = document.getElementById("stabilityRange");
+  const themeSelect = document.getElementById("themeSelect");
+  const boxRange = document.getElementById("boxRange");
+  const domainControl = document.getElementById("domainControl");
+  const domainSelect = document.getElementById("domainSelect");
+  const updatesToggle = document.getElementById("updatesToggle");
   const langButtons = Array.from(document.querySelectorAll(".seg-btn"));
+  const downloadEl = document.getElementById("download");
+  const downloadLabel = downloadEl.querySelector(".download-label");
+  const downloadFill = downloadEl.querySelector(".download-fill");
+  const meterFill = document.querySelector("#meter .meter-fill");
+  const hudEl = document.getElementById("hud");
+  const updateEl = document.getElementById("update");
+  const warningEl = document.getElementById("warning");
+  const historyEl = document.getElementById("history");
 
   const STORAGE_KEY = "subtitles-ui";
   const defaults = {
@@ -24,6 +39,7 @@
     state.outputLanguage = "chinese";
   }
   let clearTimer = null;
+  let fontScale = 1;
 
   function loadPrefs() {
     try {
@@ -73,9 +89,15 @@
       return;
     }
 
-    const max = state.fontSize;
+    const max = state.fontSize * fontScale;
     const min = 18;
     const maxHeight = Math.max(80, stageEl.clientHeight - 8);
+    // Vertical captions grow sideways as columns are added.
+    const vertical = document.body.classList.contains("layout-vertical");
+    const fits = () =>
+      vertical
+        ? captionEl.scrollWidth <= captionWrap.clientWidth
+        : captionEl.scrollHeight <= maxHeight;
     let lo = min;
     let hi = max;
     let best = min;
@@ -83,7 +105,7 @@
     for (let i = 0; i < 9; i += 1) {
       const mid = (lo + hi) / 2;
       captionEl.style.fontSize = `${mid}px`;
-      if (captionEl.scrollHeight <= maxHeight) {
+      if (fits()) {
         best = mid;
         lo = mid;
       } else {
@@ -94,22 +116,88 @@
     captionEl.style.fontSize = `${best}px`;
   }
 
+  function hexToRgba(hex, alpha) {
+    const value = parseInt(String(hex || "#000000").slice(1), 16) || 0;
+    return `rgba(${(value >> 16) & 255}, ${(value >> 8) & 255}, ${value & 255}, ${alpha})`;
+  }
+
+  // The theme lives in the engine (`--caption-theme`), so it isn't kept in local prefs.
+  function applyTheme(theme) {
+    if (!theme || !theme.name) return;
+    const root = document.documentElement.style;
+    const boxed = theme.background_opacity > 0;
+    root.setProperty("--caption-text", theme.text_color);
+    root.setProperty("--caption-outline", theme.outline_color);
+    root.setProperty("--caption-outline-px", `${theme.outline_px}px`);
+    root.setProperty(
+      "--caption-box",
+      boxed ? hexToRgba(theme.background_color, theme.background_opacity) : "transparent",
+    );
+    const pad = theme.background_padding_em || 0;
+    root.setProperty("--caption-box-padding", boxed ? `${pad / 2}em ${pad * 1.5}em` : "0");
+    root.setProperty("--caption-letter-spacing", `${theme.letter_spacing_em}em`);
+    fontScale = theme.font_scale || 1;
+    themeSelect.value = theme.name;
+    boxRange.value = Math.round(theme.background_opacity * 100);
+    fitText();
+  }
+
   function showIdle() {
     captionEl.textContent = "Listening...";
     captionEl.classList.add("idle");
-    captionEl.classList.remove("partial");
+    captionEl.classList.remove("partial", "untranslated", "speaker");
     captionEl.style.fontSize = "22px";
+    captionEl.style.removeProperty("--speaker-color");
   }
 
-  function showCaption(text, isFinal, clear) {
+  // Names given to `--diarize` speakers (`set_speaker_name`), by speaker id.
+  const speakerNames = new Map();
+
+  function captionText(payload) {
+    const name = payload.speaker == null ? null : speakerNames.get(payload.speaker);
+    return name && payload.text ? `${name}: ${payload.text.trim()}` : payload.text || "";
+  }
+
+  function showCaption(text, isFinal, clear, color, untranslated, spans) {
     if (clear || !text || !text.trim()) {
       showIdle();
       return;
     }
 
-    captionEl.textContent = text.trim();
+    // A partial's tail that the stabilizer may still change is drawn dimmer.
+    if (!isFinal && Array.isArray(spans) && spans.length && spans.some((s) => !s.stable)) {
+      captionEl.replaceChildren(
+        ...spans.map((span, i) => {
+          let piece = span.text;
+          if (i === 0) {
+            piece = piece.trimStart();
+          }
+          if (i === spans.length - 1) {
+            piece = piece.trimEnd();
+          }
+          if (span.stable) {
+            return document.createTextNode(piece);
+          }
+          const el = document.createElement("span");
+          el.className = "pending";
+          el.textContent = piece;
+          return el;
+        }),
+      );
+    } else {
+      captionEl.textContent = text.trim();
+    }
+    // `--diarize` marks finals with a bar in the speaker's color; the text keeps the theme's
+    // color, which is chosen to read on its background.
+    if (color) {
+      captionEl.style.setProperty("--speaker-color", color);
+    } else {
+      captionEl.style.removeProperty("--speaker-color");
+    }
+    captionEl.classList.toggle("speaker", Boolean(color));
     captionEl.classList.remove("idle");
     captionEl.classList.toggle("partial", !isFinal);
+    captionEl.classList.toggle("untranslated", untranslated === true);
 
     if (clearTimer) {
       window.clearTimeout(clearTimer);
@@ -122,6 +210,188 @@
     });
   }
 
+  // Finals shown lately, oldest first; each can be listened back to and corrected.
+  const HISTORY_LIMIT = 50;
+
+  function pushHistory(payload) {
+    if (payload.is_final === false || payload.clear || !payload.text || !payload.text.trim()) {
+      return;
+    }
+    const item = document.createElement("li");
+    item.dataset.segmentId = payload.segment_id;
+    if (payload.speaker != null) {
+      item.dataset.speaker = payload.speaker;
+    }
+    const play = document.createElement("button");
+    play.type = "button";
+    play.className = "history-play";
+    play.textContent = "\u25b6";
+    play.title = "Play this caption's audio";
+    play.addEventListener("click", () => {
+      if (!invoke) {
+        return;
+      }
+      invoke("play_segment_audio", { segmentId: payload.segment_id }).catch((err) => {
+        // Only the last `--segment-audio-s` seconds are kept.
+        play.disabled = true;
+        play.title = String(err);
+      });
+    });
+    const text = document.createElement("span");
+    text.className = "history-text";
+    text.textContent = captionText(payload).trim();
+    text.dataset.raw = payload.text.trim();
+    text.title = "Double-click to correct";
+    // Corrections go to the engine (`edit_segment`), which revises the exports; the
+    // `caption-revise` event that follows updates this line.
+    text.addEventListener("dblclick", () => {
+      text.contentEditable = "true";
+      text.dataset.original = text.textContent;
+      text.textContent = text.dataset.raw;
+      text.focus();
+    });
+    text.addEventListener("keydown", (event) => {
+      event.stopPropagation();
+      if (event.key === "Escape") {
+        text.contentEditable = "false";
+        text.textContent = text.dataset.original;
+      } else if (event.key === "Enter") {
+        event.preventDefault();
+        text.contentEditable = "false";
+        const corrected = text.textContent.trim();
+        text.textContent = text.dataset.original;
+        if (invoke && corrected && corrected !== text.dataset.raw) {
+          invoke("edit_segment", {
+            segmentId: payload.segment_id,
+            correctedText: corrected,
+            originalText: text.dataset.raw,
+          })
+            .then((pairs) => offerReplacements(item, pairs || []))
+            .catch(() => {});
+        }
+      }
+    });
+    item.append(play, text);
+
+    historyEl.querySelector(`li[data-segment-id="${payload.segment_id}"]`)?.remove();
+    historyEl.append(item);
+    while (historyEl.children.length > HISTORY_LIMIT) {
+      historyEl.firstElementChild.remove();
+    }
+    historyEl.scrollTop = historyEl.scrollHeight;
+  }
+
+  // After a correction, offers to fix the same phrases in later finals (`--replace-rules`,
+  // for the rest of the session).
+  function offerReplacements(item, pairs) {
+    item.querySelectorAll(".history-offer").forEach((offer) => offer.remove());
+    for (const { from, to } of pairs) {
+      const offer = document.createElement("button");
+      offer.type = "button";
+      offer.className = "history-offer";
+      offer.textContent = `Always "${from}" \u2192 "${to}"`;
+      offer.title = "Fix this in later captions too";
+      offer.addEventListener("click", () => {
+        invoke("add_replacement_rule", { from, to })
+          .then(() => offer.remove())
+          .catch((err) => {
+            offer.disabled = true;
+            offer.title = String(err);
+          });
+      });
+      item.append(offer);
+    }
+  }
+
+  function reviseHistory({ segment_id: segmentId, text }) {
+    const item = historyEl.querySelector(`li[data-segment-id="${segmentId}"]`);
+    const textEl = item?.querySelector(".history-text");
+    if (textEl && text && textEl.contentEditable !== "true") {
+      const speaker = item.dataset.speaker === undefined ? null : Number(item.dataset.speaker);
+      textEl.dataset.raw = text.trim();
+      textEl.textContent = captionText({ speaker, text }).trim();
+    }
+  }
+
+  function formatMegabytes(bytes) {
+    return `${Math.round(bytes / (1024 * 1024))} MB`;
+  }
+
+  function showDownload(progress) {
+    const done = progress.downloaded_bytes || 0;
+    const total = progress.total_bytes || 0;
+    downloadEl.hidden = false;
+    if (total > 0) {
+      const pct = Math.min(100, Math.round((done / total) * 100));
+      downloadLabel.textContent =
+        `Downloading ${progress.file_name} - ${formatMegabytes(done)} / ${formatMegabytes(total)}`;
+      downloadFill.style.width = `${pct}%`;
+    } else {
+      downloadLabel.textContent = `Downloading ${progress.file_name} - ${formatMegabytes(done)}`;
+    }
+  }
+
+  // What to do about a failure, by its `kind`; the message has the details.
+  const FAILURE_HINTS = {
+    permission_denied: "Grant the permission in System Settings > Privacy & Security and relaunch.",
+    network: "The model couldn't be downloaded; check the connection and relaunch.",
+    model_load: "The model couldn't be loaded; try a smaller model preset.",
+  };
+
+  function showStatus(status) {
+    if (status.state === "failed") {
+      downloadEl.hidden = true;
+      const hint = FAILURE_HINTS[status.kind];
+      const message = status.message || "Failed to start";
+      captionEl.textContent = hint ? `${hint} (${message})` : message;
+      captionEl.classList.add("idle");
+      return;
+    }
+    if (status.state === "ready") {
+      downloadEl.hidden = true;
+    }
+    document.body.classList.toggle("engine-idle", status.state === "idle");
+    document.body.classList.toggle("engine-paused", status.state === "paused");
+  }
+
+  function showLevel(level) {
+    // Map -60..0 dBFS onto the meter width.
+    const rms = Math.max(level.rms || 0, 1e-6);
+    const db = 20 * Math.log10(rms);
+    const pct = Math.max(0, Math.min(100, ((db + 60) / 60) * 100));
+    meterFill.style.width = `${pct}%`;
+  }
+
+  function formatMinutes(seconds) {
+    const total = Math.round(seconds || 0);
+    const m = Math.floor(total / 60);
+    const s = String(total % 60).padStart(2, "0");
+    return `${m}:${s}`;
+  }
+
+  function showStats(stats) {
+    const wpm = Math.round(stats.words_per_minute || 0);
+    const retracted = stats.retractions_per_minute || 0;
+    hudEl.textContent =
+      `${wpm} wpm - ${formatMinutes(stats.talk_s)} talk - ${retracted} retracted/min`;
+    hudEl.hidden = false;
+  }
+
+  function showUpdate(update) {
+    if (!update.version) return;
+    updateEl.textContent = `v${update.version} available`;
+    updateEl.title = update.notes || `You have v${update.current}`;
+    updateEl.hidden = false;
+  }
+
+  // E.g. a smaller model standing in for one that couldn't be downloaded.
+  function showWarning(warning) {
+    if (!warning.message) return;
+    warningEl.textContent = "Degraded";
+    warningEl.title = warning.message;
+    warningEl.hidden = false;
+  }
+
   function applyInitialState() {
     sizeRange.value = state.fontSize;
     widthRange.value = state.widthPct;
@@ -144,6 +414,69 @@
     fitText();
   });
 
+  // The delay lives in the engine (`--caption-delay-ms`), so it isn't kept in local prefs.
+  delayRange.addEventListener("input", (event) => {
+    const delayMs = Number(event.target.value);
+    delayRange.title = `${delayMs} ms`;
+    if (invoke) {
+      invoke("set_caption_delay", { delayMs }).catch(() => {});
+    }
+  });
+
+  // 0 holds every partial word until the final; otherwise how many updates a word must
+  // survive before it stops changing.
+  function stabilityTitle(value) {
+    return value === 0 ? "final only" : `${value} updates`;
+  }
+
+  stabilityRange.addEventListener("input", (event) => {
+    const value = Number(event.target.value);
+    stabilityRange.title = stabilityTitle(value);
+    if (invoke) {
+      const args =
+        value === 0
+          ? { commitPolicy: "final-only" }
+          : { stableIters: value, commitPolicy: "agreement" };
+      invoke("set_partial_stability", args).catch(() => {});
+    }
+  });
+
+  boxRange.addEventListener("input", (event) => {
+    const captionBox = { opacity: Number(event.target.value) / 100 };
+    if (invoke) {
+      invoke("set_caption_box", { captionBox }).then(applyTheme).catch(() => {});
+    }
+  });
+
+  themeSelect.addEventListener("change", (event) => {
+    if (invoke) {
+      invoke("set_caption_theme", { name: event.target.value }).then(applyTheme).catch(() => {});
+    }
+  });
+
+  domainSelect.addEventListener("change", (event) => {
+    if (invoke) {
+      invoke("set_domain_model", { name: event.target.value }).catch(() => {});
+    }
+  });
+
+  // Saved with the other settings; the engine checks the feed from the next start.
+  updatesToggle.addEventListener("change", (event) => {
+    if (invoke) {
+      const checked = event.target.checked;
+      invoke("load_settings")
+        .then((values) =>
+          invoke("save_settings", {
+            values: { ...values, "check-updates": checked },
+            restart: false,
+          }),
+        )
+        .catch(() => {
+          updatesToggle.checked = !checked;
+        });
+    }
+  });
+
   langButtons.forEach((btn) => {
     btn.addEventListener("click", () => {
       setLanguage(btn.dataset.lang, true);
@@ -165,6 +498,11 @@
       applyBodyState();
       persistPrefs();
     }
+
+    if (event.key.toLowerCase() === "h") {
+      historyEl.hidden = !historyEl.hidden;
+      historyEl.scrollTop = historyEl.scrollHeight;
+    }
   });
 
   window.addEventListener("resize", () => {
@@ -186,13 +524,130 @@
         state.outputLanguage =
           cfg.output_language === "original" ? "chinese" : cfg.output_language;
       }
+      if (typeof cfg.caption_delay_ms === "number") {
+        delayRange.value = cfg.caption_delay_ms;
+        delayRange.title = `${cfg.caption_delay_ms} ms`;
+      }
+      if (typeof cfg.partial_stable_iters === "number") {
+        const value = cfg.commit_policy === "final-only" ? 0 : cfg.partial_stable_iters;
+        stabilityRange.value = value;
+        stabilityRange.title = stabilityTitle(value);
+      }
+      applyTheme(cfg.caption_theme);
+      document.body.classList.toggle("layout-vertical", cfg.caption_layout === "vertical");
       applyInitialState();
     });
 
     listen("caption", (event) => {
       const payload = event.payload || {};
-      showCaption(payload.text || "", payload.is_final !== false, payload.clear === true);
+      pushHistory(payload);
+      showCaption(
+        captionText(payload),
+        payload.is_final !== false,
+        payload.clear === true,
+        payload.color,
+        payload.untranslated,
+        payload.spans,
+      );
+    });
+
+    listen("caption-revise", (event) => {
+      reviseHistory(event.payload || {});
+    });
+
+    listen("speaker-name", (event) => {
+      const { id, name } = event.payload || {};
+      if (name) {
+        speakerNames.set(id, name);
+      } else {
+        speakerNames.delete(id);
+      }
+    });
+
+    listen("model-download-progress", (event) => {
+      showDownload(event.payload || {});
+    });
+
+    listen("level", (event) => {
+      showLevel(event.payload || {});
+    });
+
+    listen("stats", (event) => {
+      showStats(event.payload || {});
+    });
+
+    listen("update-available", (event) => {
+      showUpdate(event.payload || {});
     });
+
+    listen("warning", (event) => {
+      showWarning(event.payload || {});
+    });
+
+    listen("status", (event) => {
+      showStatus(event.payload || {});
+    });
+
+    listen("caption-theme", (event) => {
+      applyTheme(event.payload);
+    });
+  }
+
+  if (invoke) {
+    invoke("list_caption_themes")
+      .then((themes) => {
+        themeSelect.replaceChildren(
+          ...(themes || []).map((theme) => new Option(theme.name.replace(/-/g, " "), theme.name)),
+        );
+        return invoke("get_caption_theme");
+      })
+      .then(applyTheme)
+      .catch(() => {});
+
+    // Only shown when `--domain-model` declared something to switch to.
+    invoke("list_domain_models")
+      .then(({ names, selected }) => {
+        domainSelect.replaceChildren(...(names || []).map((name) => new Option(name, name)));
+        domainSelect.value = selected;
+        domainControl.hidden = (names || []).length < 2;
+      })
+      .catch(() => {});
+
+    invoke("load_settings")
+      .then((values) => {
+        updatesToggle.checked = values?.["check-updates"] === true;
+      })
+      .catch(() => {});
+  }
+
+  // After a reload the engine is mid-session; draw what it last showed instead of waiting
+  // for the next utterance.
+  if (invoke) {
+    invoke("get_recent_events")
+      .then((events) => {
+        for (const { event, payload } of events || []) {
+          if (event === "caption") {
+            pushHistory(payload);
+            showCaption(
+              captionText(payload),
+              payload.is_final !== false,
+              false,
+              payload.color,
+              payload.untranslated,
+              payload.spans,
+            );
+          } else if (event === "speaker-name") {
+            speakerNames.set(payload.id, payload.name);
+          } else if (event === "status") {
+            showStatus(payload || {});
+          } else if (event === "update-available") {
+            showUpdate(payload || {});
+          } else if (event === "warning") {
+            showWarning(payload || {});
+          }
+        }
+      })
+      .catch(() => {});
   }
 
   applyInitialState();
@@ -0,0 +1,120 @@
+//! WebAssembly exports of the caption smoothing and text shaping in `subtitles-core`, so web
+//! front ends (the Tauri overlay, a browser page) stabilize partials exactly like the engine.
+//!
+//! Build with `wasm-pack build wasm --target web`.
+
+use subtitles_core::{config, streaming, text};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputLanguage {
+    Chinese,
+    English,
+    Bilingual,
+}
+
+impl From<OutputLanguage> for config::OutputLanguage {
+    fn from(lang: OutputLanguage) -> Self {
+        match lang {
+            OutputLanguage::Chinese => config::OutputLanguage::Chinese,
+            OutputLanguage::English => config::OutputLanguage::English,
+            OutputLanguage::Bilingual => config::OutputLanguage::Bilingual,
+        }
+    }
+}
+
+/// Result of one stabilizer step: text that won't change any more, and the tail that may.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StableText {
+    pub committed: String,
+    pub partial: String,
+}
+
+/// Commits partial-hypothesis tokens once they survive `stable_required` updates.
+#[wasm_bindgen]
+pub struct Stabilizer {
+    inner: streaming::Stabilizer,
+}
+
+#[wasm_bindgen]
+impl Stabilizer {
+    /// `max_committed` is how many committed tokens are kept before older ones scroll off
+    /// (0 = unbounded).
+    #[wasm_bindgen(constructor)]
+    pub fn new(stable_required: usize, max_committed: usize) -> Self {
+        Self {
+            inner: streaming::Stabilizer::new(stable_required).with_max_committed(max_committed),
+        }
+    }
+
+    pub fn update(&mut self, hypothesis: &str) -> StableText {
+        let (committed, partial) = self.inner.update(hypothesis);
+        StableText { committed, partial }
+    }
+
+    /// Final text for the utterance; the stabilizer is reset for the next one.
+    pub fn finalize(&mut self, hypothesis: &str) -> String {
+        self.inner.finalize(hypothesis)
+    }
+
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    pub fn committed(&self) -> Vec<String> {
+        self.inner.committed().to_vec()
+    }
+}
+
+/// Splits text into the tokens the stabilizer compares.
+#[wasm_bindgen]
+pub fn tokenize(text: &str) -> Vec<String> {
+    streaming::tokenize(text)
+}
+
+/// Language-aware joining and spacing (CJK without spaces, French punctuation spacing).
+#[wasm_bindgen]
+pub struct TextJoiner {
+    inner: text::TextJoiner,
+}
+
+#[wasm_bindgen]
+impl TextJoiner {
+    /// Rules for a language code such as `en`, `zh` or `fr`; omit it for auto-detected input.
+    #[wasm_bindgen(js_name = forLanguage)]
+    pub fn for_language(language: Option<String>) -> Self {
+        Self {
+            inner: text::TextJoiner::for_language(language.as_deref()),
+        }
+    }
+
+    /// Rules for one caption line of `output`, given the input language.
+    #[wasm_bindgen(js_name = forOutput)]
+    pub fn for_output(output: OutputLanguage, input_language: Option<String>) -> Self {
+        Self {
+            inner: text::TextJoiner::for_output(output.into(), input_language.as_deref()),
+        }
+    }
+
+    pub fn join(&self, left: &str, right: &str) -> String {
+        self.inner.join(left, right)
+    }
+
+    pub fn format(&self, text: &str) -> String {
+        self.inner.format(text)
+    }
+
+    /// The live caption line for a stabilizer step, as the engine shows it.
+    pub fn caption(&self, stable: &StableText) -> String {
+        self.inner
+            .format(&self.inner.join(&stable.committed, &stable.partial))
+    }
+}
+
+/// Original line over its translation, or just one when they match or one is empty.
+#[wasm_bindgen(js_name = mergeBilingual)]
+pub fn merge_bilingual(primary: &str, secondary: &str) -> String {
+    text::merge_bilingual(primary, secondary)
+}
@@ -0,0 +1,32 @@
+use subtitles_wasm::{tokenize, OutputLanguage, Stabilizer, TextJoiner};
+
+#[test]
+fn wasm_stabilizer_matches_the_engine() {
+    let hypotheses = [
+        "we are going",
+        "we are going to the",
+        "we are going to the market",
+        "we are going to the market today",
+    ];
+    let mut engine = subtitles_core::streaming::Stabilizer::new(2).with_max_committed(4);
+    let mut wasm = Stabilizer::new(2, 4);
+    for hypothesis in hypotheses {
+        let (committed, partial) = engine.update(hypothesis);
+        let stable = wasm.update(hypothesis);
+        assert_eq!((stable.committed, stable.partial), (committed, partial));
+    }
+    assert_eq!(wasm.committed(), engine.committed());
+    assert_eq!(
+        wasm.finalize("we are going to the market today."),
+        engine.finalize("we are going to the market today.")
+    );
+    assert_eq!(tokenize(" two  words "), ["two", "words"]);
+}
+
+#[test]
+fn caption_lines_are_shaped_per_language() {
+    let mut stabilizer = Stabilizer::new(1, 0);
+    let stable = stabilizer.update("我们 今天 讨论");
+    let joiner = TextJoiner::for_output(OutputLanguage::Chinese, Some("zh".to_string()));
+    assert_eq!(joiner.caption(&stable), "我们今天讨论");
+}